@@ -0,0 +1,148 @@
+//! Assembly for the data of a translation unit, in AT&T syntax for the GNU
+//! assembler on x86-64 Linux.
+//!
+//! Every object this translation unit defines gets a label, made global if it
+//! has external linkage, in `.bss` if it starts out all zeros and in `.data`
+//! otherwise. Objects that are only declared are left to the linker.
+
+use std::fmt::Write;
+
+use wacc_parser::types::TagTable;
+use wacc_semantics::{
+    resolve::Linkage,
+    symbols::{Definition, StaticInit, SymbolTable},
+};
+
+pub fn emit_data(symbols: &SymbolTable, tags: &TagTable) -> String {
+    let mut output = String::new();
+    for symbol in symbols.iter() {
+        if symbol.is_function || symbol.definition != Definition::Defined {
+            continue;
+        }
+        let layout = tags
+            .layout(&symbol.ty.ty)
+            .expect("defined objects are complete");
+        if symbol.linkage == Linkage::External {
+            writeln!(output, "\t.globl\t{}", symbol.name).unwrap();
+        }
+        let is_zero = symbol
+            .init
+            .iter()
+            .all(|init| matches!(init, StaticInit::Zero(_)));
+        let section = match is_zero {
+            true => ".bss",
+            false => ".data",
+        };
+        writeln!(output, "\t{section}").unwrap();
+        writeln!(output, "\t.balign\t{}", layout.align).unwrap();
+        writeln!(output, "{}:", symbol.name).unwrap();
+        for init in &symbol.init {
+            match init {
+                StaticInit::Zero(size) => writeln!(output, "\t.zero\t{size}"),
+                StaticInit::Integer(size, value) => {
+                    let directive = match size {
+                        1 => ".byte",
+                        2 => ".short",
+                        4 => ".long",
+                        8 => ".quad",
+                        _ => unreachable!("scalars are 1, 2, 4 or 8 bytes"),
+                    };
+                    writeln!(output, "\t{directive}\t{value}")
+                }
+                StaticInit::Bytes(bytes) => writeln!(output, "\t.ascii\t\"{}\"", escape(bytes)),
+                StaticInit::Address(name, 0) => writeln!(output, "\t.quad\t{name}"),
+                StaticInit::Address(name, offset) => writeln!(output, "\t.quad\t{name}{offset:+}"),
+            }
+            .unwrap();
+        }
+    }
+    output.push_str("\t.section\t.note.GNU-stack,\"\",@progbits\n");
+    output
+}
+
+/// Escape bytes for a string in assembly, with octal escapes for anything that
+/// isn't printable ASCII.
+fn escape(bytes: &[u8]) -> String {
+    let mut escaped = String::new();
+    for &byte in bytes {
+        match byte {
+            b'"' | b'\\' => write!(escaped, "\\{}", byte as char),
+            b' '..=b'~' => write!(escaped, "{}", byte as char),
+            _ => write!(escaped, "\\{byte:03o}"),
+        }
+        .unwrap();
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod emit_tests {
+    use expect_test::{expect, Expect};
+    use wacc_lexer::Lexer;
+    use wacc_parser::Parser;
+    use wacc_semantics::{resolve::Resolver, symbols::SymbolCollector, typecheck::TypeChecker};
+
+    use super::emit_data;
+
+    fn check_emit(src: &str, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let mut program = parser.parse_program();
+        let resolution = Resolver::new().resolve_program(&mut program);
+        TypeChecker::new(&resolution).check_program(&mut program);
+        let symbols = SymbolCollector::new(&resolution).collect_program(&program);
+        expect.assert_eq(&emit_data(&symbols, &program.tags));
+    }
+
+    #[test]
+    fn test_emit_data() {
+        check_emit(
+            r#"
+            int zeroed;
+            static long counter = 5;
+            extern int elsewhere;
+            char greeting[8] = "hi\"\n";
+            struct { short s; int bits : 4; } mixed = { -2, 3 };
+            char *last = greeting + 7;
+            int main(void) {
+                static int calls;
+                return calls + elsewhere;
+            }
+            "#,
+            expect![[r#"
+                	.globl	zeroed
+                	.bss
+                	.balign	4
+                zeroed:
+                	.zero	4
+                	.data
+                	.balign	8
+                counter:
+                	.quad	5
+                	.globl	greeting
+                	.data
+                	.balign	1
+                greeting:
+                	.ascii	"hi\"\012\000"
+                	.zero	3
+                	.globl	mixed
+                	.data
+                	.balign	4
+                mixed:
+                	.short	65534
+                	.ascii	"\003"
+                	.zero	1
+                	.globl	last
+                	.data
+                	.balign	8
+                last:
+                	.quad	greeting+7
+                	.bss
+                	.balign	4
+                calls.0:
+                	.zero	4
+                	.section	.note.GNU-stack,"",@progbits
+            "#]],
+        );
+    }
+}
@@ -1,15 +1,36 @@
-use std::{fs, path, process};
+mod emit;
+
+use std::{fmt, fs, io, path, process};
 
 use anyhow::Result;
-use clap::{ArgGroup, Parser};
-use wacc_lexer::{c_token::CToken, Lexer};
+use clap::{ArgGroup, Parser, ValueEnum};
+use wacc_ir::{
+    cfg::Cfg,
+    interpret::Interpreter,
+    lower::Lowerer,
+    optimize::{self, OptLevel},
+    pass::PassManager,
+};
+use wacc_lexer::Lexer;
+use wacc_parser::{
+    dump::{dump_program, DumpFormat},
+    Parser as CParser,
+};
+use wacc_semantics::{
+    flow::FlowChecker,
+    resolve::Resolver,
+    symbols::SymbolCollector,
+    typecheck::TypeChecker,
+    warning::{Severity, Warning, WarningOptions},
+};
 
 #[derive(Parser)]
 #[command(
     about = "The compiler driver for Writing a C Compiler.",
     group = ArgGroup::new("mode")
-        .args(&["lex", "parse", "codegen"])
-        .required(true)
+        .args(&["lex", "parse", "dump_ast", "validate", "tacky", "dump_cfg", "interpret", "codegen"])
+        .required(true),
+    group = ArgGroup::new("pipeline").args(&["opt_level", "passes", "optimize"])
 )]
 pub struct Command {
     /// The path to a C source file
@@ -21,32 +42,271 @@ pub struct Command {
     #[arg(help = "lex, parse, then stop", long)]
     parse: bool,
 
+    #[arg(
+        help = "lex, parse, print the AST to stdout, then stop",
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "tree"
+    )]
+    dump_ast: Option<DumpAst>,
+
+    #[arg(help = "lex, parse, check the program is valid C, then stop", long)]
+    validate: bool,
+
+    #[arg(
+        help = "lex, parse, check the program, generate TACKY, then stop",
+        long
+    )]
+    tacky: bool,
+
+    #[arg(
+        help = "lex, parse, check the program, generate TACKY, print the control-flow graph of each function in Graphviz dot, then stop",
+        long
+    )]
+    dump_cfg: bool,
+
+    #[arg(
+        help = "lex, parse, check the program, generate TACKY, then run it and exit with its status",
+        long
+    )]
+    interpret: bool,
+
     #[arg(help = "lex, parse, generate assembly, then stop", long)]
     codegen: bool,
+
+    #[arg(
+        help = "optimize TACKY at a level: 0 for not at all, 1, 2, or s for size",
+        short = 'O',
+        value_enum,
+        value_name = "LEVEL"
+    )]
+    opt_level: Option<Level>,
+
+    #[arg(
+        help = "run a pipeline of passes over TACKY, e.g. --passes=fold,dce or --passes='fixpoint(fold,copyprop)'",
+        long,
+        value_name = "PIPELINE"
+    )]
+    passes: Option<String>,
+
+    #[arg(
+        help = "print each function, or the program for a pass over all of it, to stderr before and after every run of a pass",
+        long,
+        value_name = "PASS"
+    )]
+    print_after: Vec<String>,
+
+    #[arg(
+        help = "check that TACKY is well formed after every pass, which debug builds always do",
+        long
+    )]
+    verify_each: bool,
+
+    #[arg(
+        help = "fold constant expressions in TACKY",
+        long,
+        conflicts_with = "pipeline"
+    )]
+    fold_constants: bool,
+
+    #[arg(
+        help = "remove code in TACKY that can't be reached",
+        long,
+        conflicts_with = "pipeline"
+    )]
+    eliminate_unreachable_code: bool,
+
+    #[arg(
+        help = "replace variables in TACKY with the values copied to them",
+        long,
+        conflicts_with = "pipeline"
+    )]
+    propagate_copies: bool,
+
+    #[arg(
+        help = "remove assignments in TACKY to variables that are never read",
+        long,
+        conflicts_with = "pipeline"
+    )]
+    eliminate_dead_stores: bool,
+
+    #[arg(help = "run every TACKY optimization, like -O2", long)]
+    optimize: bool,
+
+    #[arg(
+        help = "turn on a warning or group of warnings, e.g. -Wall, -Wextra, -Wno-shadow, -Werror=conversion",
+        short = 'W',
+        value_name = "WARNING"
+    )]
+    warnings: Vec<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DumpAst {
+    /// An indented tree with the span of every node
+    Tree,
+    /// S-expressions
+    Sexpr,
+    /// JSON
+    Json,
+    /// Normalized C source
+    C,
+}
+impl From<DumpAst> for DumpFormat {
+    fn from(format: DumpAst) -> Self {
+        match format {
+            DumpAst::Tree => Self::Tree,
+            DumpAst::Sexpr => Self::Sexpr,
+            DumpAst::Json => Self::Json,
+            DumpAst::C => Self::C,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Level {
+    #[value(name = "0")]
+    O0,
+    #[value(name = "1")]
+    O1,
+    #[value(name = "2")]
+    O2,
+    #[value(name = "s")]
+    Os,
+}
+impl From<Level> for OptLevel {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::O0 => Self::O0,
+            Level::O1 => Self::O1,
+            Level::O2 => Self::O2,
+            Level::Os => Self::Os,
+        }
+    }
+}
+
+/// The stage the compiler stops after, as chosen by the mode flags.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Lex,
+    Parse,
+    Validate,
+    Tacky,
+    Interpret,
+    Codegen,
+}
+
+/// Print the warnings that are on, returning whether any of them are errors.
+fn report_warnings(file: &str, warnings: &[Warning], options: &WarningOptions) -> bool {
+    let mut has_errors = false;
+    for warning in warnings {
+        match options.severity(warning.kind()) {
+            Some(Severity::Warning) => eprintln!("{file}:{warning}\n"),
+            Some(Severity::Error) => {
+                eprintln!("{file}:{}\n", warning.to_error());
+                has_errors = true;
+            }
+            None => {}
+        }
+    }
+    has_errors
+}
+
+/// Print the errors a stage found, if any, and exit.
+fn exit_on_errors(file: &str, errors: &[impl fmt::Display]) {
+    if !errors.is_empty() {
+        errors.iter().for_each(|err| eprintln!("{file}:{err}\n"));
+        process::exit(1);
+    }
 }
 
 struct WaccCommand;
 impl WaccCommand {
     /// Compile the preprocessed source file and output an assembly file with a .s extension.
-    fn compile(preprocessed_file: &str, lex: bool, _parse: bool, _codegen: bool) -> Result<String> {
-        if lex {
-            let source_str = fs::read_to_string(preprocessed_file)?;
-            Lexer::lex::<CToken>(&source_str, String::lex_c);
+    fn compile(
+        preprocessed_file: &str,
+        stage: Stage,
+        dump_ast: Option<DumpAst>,
+        dump_cfg: bool,
+        passes: &PassManager,
+        warnings: &WarningOptions,
+    ) -> Result<String> {
+        let source_str = fs::read_to_string(preprocessed_file)?;
+        // Diagnostics name the preprocessed file, which is what their lines and
+        // columns count in: `-P` drops the line markers and runs of blank lines.
+        let (tokens, errors) = String::lex_c_with_errors(&source_str);
+        exit_on_errors(preprocessed_file, &errors);
+        if stage == Stage::Lex {
+            process::exit(0);
+        }
+
+        let mut parser = CParser::new(&source_str, &tokens);
+        let mut program = parser.parse_program();
+        if let Some(format) = dump_ast {
+            print!("{}", dump_program(&program, format.into()));
+        }
+        exit_on_errors(preprocessed_file, parser.errors());
+        if stage == Stage::Parse {
+            process::exit(0);
+        }
+
+        let mut resolver = Resolver::new();
+        let resolution = resolver.resolve_program(&mut program);
+        let mut warned_as_error = report_warnings(preprocessed_file, resolver.warnings(), warnings);
+        exit_on_errors(preprocessed_file, resolver.errors());
+        let mut checker = TypeChecker::new(&resolution);
+        checker.check_program(&mut program);
+        warned_as_error |= report_warnings(preprocessed_file, checker.warnings(), warnings);
+        exit_on_errors(preprocessed_file, checker.errors());
+        let mut flow = FlowChecker::new(&resolution);
+        flow.check_program(&program);
+        warned_as_error |= report_warnings(preprocessed_file, flow.warnings(), warnings);
+        let mut collector = SymbolCollector::new(&resolution);
+        let symbols = collector.collect_program(&program);
+        exit_on_errors(preprocessed_file, collector.errors());
+        if warned_as_error {
+            process::exit(1);
+        }
+        if stage == Stage::Validate {
             process::exit(0);
         }
 
+        // TODO: generate assembly from the TACKY once there is a backend
+        let mut tacky = Lowerer::new(&resolution, &symbols).lower_program(&program);
+        if let Err(err) = passes.run(&mut tacky, &mut io::stderr()) {
+            eprintln!("{err}");
+            process::exit(1);
+        }
+        if dump_cfg {
+            for function in &tacky.functions {
+                let cfg = Cfg::new(function.body.clone());
+                print!("{}", cfg.to_dot(&function.name));
+            }
+        }
+        if stage == Stage::Tacky {
+            process::exit(0);
+        }
+        if stage == Stage::Interpret {
+            match Interpreter::new(&tacky).run(&mut io::stdout()) {
+                Ok(status) => process::exit(status),
+                Err(err) => {
+                    eprintln!("{err}");
+                    process::exit(1);
+                }
+            }
+        }
+
         let (assembly_file, _ext) = preprocessed_file
             .rsplit_once('.')
             .expect("expected a valid filename");
         let mut assembly_file = String::from(assembly_file);
         assembly_file.push_str(".s");
 
-        // TODO: replace this once compiler is written
-        fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&assembly_file)?;
+        // TODO: emit code for functions once there is a backend
+        fs::write(&assembly_file, emit::emit_data(&symbols, &program.tags))?;
 
         fs::remove_file(preprocessed_file)
             .map_err(|e| anyhow::anyhow!("couldn't remove preprocessed file: {e:?}"))?;
@@ -113,8 +373,81 @@ fn main() -> Result<()> {
         c_source_file,
         lex,
         parse,
-        codegen,
+        dump_ast,
+        validate,
+        tacky,
+        dump_cfg,
+        interpret,
+        codegen: _,
+        opt_level,
+        passes,
+        print_after,
+        verify_each,
+        fold_constants,
+        eliminate_unreachable_code,
+        propagate_copies,
+        eliminate_dead_stores,
+        optimize,
+        warnings,
     } = Command::parse();
+    let stage = if lex {
+        Stage::Lex
+    } else if parse || dump_ast.is_some() {
+        Stage::Parse
+    } else if validate {
+        Stage::Validate
+    } else if tacky || dump_cfg {
+        Stage::Tacky
+    } else if interpret {
+        Stage::Interpret
+    } else {
+        Stage::Codegen
+    };
+    // The single optimization flags run the optimizations they choose the way
+    // -O2 runs all of them
+    let pipeline = match (passes, opt_level) {
+        (Some(passes), _) => passes,
+        (None, Some(level)) => OptLevel::from(level).pipeline().to_string(),
+        (None, None) if optimize => OptLevel::O2.pipeline().to_string(),
+        (None, None) => {
+            let chosen: Vec<&str> = [
+                (fold_constants, "fold"),
+                (eliminate_unreachable_code, "unreachable"),
+                (propagate_copies, "copyprop"),
+                (eliminate_dead_stores, "dce"),
+            ]
+            .into_iter()
+            .filter_map(|(on, pass)| on.then_some(pass))
+            .collect();
+            match chosen.is_empty() {
+                true => String::new(),
+                false => format!("fixpoint({}),prune", chosen.join(",")),
+            }
+        }
+    };
+    let registry = optimize::registry();
+    let mut passes = match registry.parse_pipeline(&pipeline) {
+        Ok(pipeline) => PassManager::new(pipeline),
+        Err(err) => {
+            eprintln!("{err}");
+            process::exit(1);
+        }
+    };
+    for pass in &print_after {
+        if !registry.is_transform(pass) {
+            eprintln!("unknown pass `{pass}`");
+            process::exit(1);
+        }
+        passes.print_after(pass);
+    }
+    passes.verify_each(verify_each || cfg!(debug_assertions));
+    let warnings = match WarningOptions::from_flags(warnings.iter().map(String::as_str)) {
+        Ok(warnings) => warnings,
+        Err(err) => {
+            eprintln!("{err}");
+            process::exit(1);
+        }
+    };
 
     if !path::Path::new(&c_source_file).exists() {
         eprintln!("file not found: {c_source_file}");
@@ -123,9 +456,11 @@ fn main() -> Result<()> {
 
     GccCommand::assemble(&WaccCommand::compile(
         &GccCommand::preprocess(&c_source_file)?,
-        lex,
-        parse,
-        codegen,
+        stage,
+        dump_ast,
+        dump_cfg,
+        &passes,
+        &warnings,
     )?)
 }
 
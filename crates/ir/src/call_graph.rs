@@ -0,0 +1,295 @@
+//! The call graph of a program.
+//!
+//! Each function the program defines is a node, with an edge to each of them
+//! it calls by name. A call through a pointer could go to any function whose
+//! address is taken, so the graph doesn't try to say where it goes, and only
+//! records which functions have their address taken, by an instruction or by
+//! the initializer of a static object.
+//!
+//! The strongly connected components of the graph are found with Tarjan's
+//! algorithm, which hands them out callees first. A function is recursive if
+//! its component has more than one function in it, or it calls itself.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::tacky::{Callee, Instruction, Program, StaticInit};
+
+/// A function of the graph, by its index in the program's functions.
+pub type FunctionId = usize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallGraph {
+    /// The name of each function, in the program's order.
+    pub names: Vec<String>,
+    ids: HashMap<String, FunctionId>,
+    /// How many times each function calls each function it calls by name.
+    calls: Vec<BTreeMap<FunctionId, usize>>,
+    callers: Vec<BTreeMap<FunctionId, usize>>,
+    /// The functions each function takes the address of.
+    addresses: Vec<Vec<FunctionId>>,
+    /// The functions whose address a static object's initializer holds.
+    address_in_statics: HashSet<FunctionId>,
+    /// The strongly connected components, each after every one it calls into.
+    components: Vec<Vec<FunctionId>>,
+    component: Vec<usize>,
+}
+impl CallGraph {
+    pub fn new(program: &Program) -> Self {
+        let names: Vec<String> = program.functions.iter().map(|f| f.name.clone()).collect();
+        let ids: HashMap<String, FunctionId> = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (name.clone(), id))
+            .collect();
+        let mut calls = vec![BTreeMap::new(); names.len()];
+        let mut callers = vec![BTreeMap::new(); names.len()];
+        let mut addresses = vec![Vec::new(); names.len()];
+        for (caller, function) in program.functions.iter().enumerate() {
+            for instruction in &function.body {
+                match instruction {
+                    Instruction::FunCall {
+                        callee: Callee::Direct(name),
+                        ..
+                    } => {
+                        if let Some(&callee) = ids.get(name) {
+                            *calls[caller].entry(callee).or_default() += 1;
+                            *callers[callee].entry(caller).or_default() += 1;
+                        }
+                    }
+                    // A local can't have the name of a function, which is at file scope
+                    Instruction::GetAddress { src, .. }
+                        if !function.variables.contains_key(src) =>
+                    {
+                        addresses[caller].extend(ids.get(src));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let address_in_statics = program
+            .statics
+            .iter()
+            .flat_map(|variable| variable.init.iter().flatten())
+            .filter_map(|init| match init {
+                StaticInit::Address(name, _) => ids.get(name).copied(),
+                _ => None,
+            })
+            .collect();
+
+        let mut graph = Self {
+            names,
+            ids,
+            calls,
+            callers,
+            addresses,
+            address_in_statics,
+            components: Vec::new(),
+            component: Vec::new(),
+        };
+        graph.find_components();
+        graph
+    }
+
+    pub fn id(&self, name: &str) -> Option<FunctionId> {
+        self.ids.get(name).copied()
+    }
+
+    /// The functions `function` calls by name.
+    pub fn callees(&self, function: FunctionId) -> impl Iterator<Item = FunctionId> + '_ {
+        self.calls[function].keys().copied()
+    }
+
+    /// The functions that call `function` by name.
+    pub fn callers(&self, function: FunctionId) -> impl Iterator<Item = FunctionId> + '_ {
+        self.callers[function].keys().copied()
+    }
+
+    /// How many calls there are to `function` by name, in the whole program.
+    pub fn call_sites(&self, function: FunctionId) -> usize {
+        self.callers[function].values().sum()
+    }
+
+    /// The functions `function` calls by name or takes the address of.
+    pub fn references(&self, function: FunctionId) -> impl Iterator<Item = FunctionId> + '_ {
+        self.callees(function)
+            .chain(self.addresses[function].iter().copied())
+    }
+
+    /// Whether a function's address is taken anywhere, so it could be called
+    /// through a pointer.
+    pub fn is_address_taken(&self, function: FunctionId) -> bool {
+        self.address_in_statics.contains(&function)
+            || self.addresses.iter().any(|taken| taken.contains(&function))
+    }
+
+    /// Whether the address of a function is in a static object's initializer.
+    pub fn is_address_in_statics(&self, function: FunctionId) -> bool {
+        self.address_in_statics.contains(&function)
+    }
+
+    /// Whether a function can call itself, directly or through other functions.
+    pub fn is_recursive(&self, function: FunctionId) -> bool {
+        self.components[self.component[function]].len() > 1
+            || self.calls[function].contains_key(&function)
+    }
+
+    /// The strongly connected components, each after every one it calls into,
+    /// so callees come before their callers unless they are in a cycle.
+    pub fn components(&self) -> &[Vec<FunctionId>] {
+        &self.components
+    }
+
+    /// Tarjan's algorithm, with an explicit stack so deep call chains can't
+    /// overflow the real one.
+    fn find_components(&mut self) {
+        const UNVISITED: usize = usize::MAX;
+        let count = self.names.len();
+        let mut index = vec![UNVISITED; count];
+        let mut low_link = vec![0; count];
+        let mut on_stack = vec![false; count];
+        let mut stack = Vec::new();
+        let mut next_index = 0;
+        self.component = vec![0; count];
+        for root in 0..count {
+            if index[root] != UNVISITED {
+                continue;
+            }
+            // Each frame is a function and the callees it has yet to visit
+            let mut frames = vec![(root, self.callees(root).collect::<Vec<_>>())];
+            index[root] = next_index;
+            low_link[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            while let Some((function, callees)) = frames.last_mut() {
+                let function = *function;
+                if let Some(callee) = callees.pop() {
+                    if index[callee] == UNVISITED {
+                        index[callee] = next_index;
+                        low_link[callee] = next_index;
+                        next_index += 1;
+                        stack.push(callee);
+                        on_stack[callee] = true;
+                        frames.push((callee, self.callees(callee).collect()));
+                    } else if on_stack[callee] {
+                        low_link[function] = low_link[function].min(index[callee]);
+                    }
+                    continue;
+                }
+                frames.pop();
+                if let Some(&(caller, _)) = frames.last() {
+                    low_link[caller] = low_link[caller].min(low_link[function]);
+                }
+                if low_link[function] == index[function] {
+                    let mut component = Vec::new();
+                    loop {
+                        let member = stack.pop().expect("the root is still on the stack");
+                        on_stack[member] = false;
+                        self.component[member] = self.components.len();
+                        component.push(member);
+                        if member == function {
+                            break;
+                        }
+                    }
+                    component.reverse();
+                    self.components.push(component);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod call_graph_tests {
+    use expect_test::{expect, Expect};
+
+    use super::CallGraph;
+    use crate::text::parse_program;
+
+    fn check_graph(src: &str, expect: Expect) {
+        let graph = CallGraph::new(&parse_program(src).unwrap());
+        let mut output = Vec::new();
+        for (id, name) in graph.names.iter().enumerate() {
+            let callees: Vec<&str> = graph.callees(id).map(|c| graph.names[c].as_str()).collect();
+            output.push(format!(
+                "{name}: calls {callees:?}, {} call sites{}{}",
+                graph.call_sites(id),
+                if graph.is_recursive(id) {
+                    ", recursive"
+                } else {
+                    ""
+                },
+                if graph.is_address_taken(id) {
+                    ", address taken"
+                } else {
+                    ""
+                },
+            ));
+        }
+        for component in graph.components() {
+            let names: Vec<&str> = component.iter().map(|&f| graph.names[f].as_str()).collect();
+            output.push(format!("{names:?}"));
+        }
+        expect.assert_eq(&output.join("\n"));
+    }
+
+    #[test]
+    fn test_call_graph() {
+        check_graph(
+            r#"
+static %handler: ptr = { address %leaf +0 }
+
+global function main() -> i32 {
+    local %.t0: i32
+    local %p: ptr
+    %.t0 = call even(i32 4)
+    %.t0 = call leaf(%.t0)
+    %p = addr %twice
+    call *%p()
+    call puts()
+    return %.t0
+}
+
+function even(%n: i32) -> i32 {
+    local %.t0: i32
+    jz %n, zero.0
+    %n = sub %n, i32 1
+    %.t0 = call odd(%n)
+    return %.t0
+  zero.0:
+    return i32 1
+}
+
+function odd(%n: i32) -> i32 {
+    local %.t0: i32
+    jz %n, zero.0
+    %n = sub %n, i32 1
+    %.t0 = call even(%n)
+    %.t0 = call leaf(%.t0)
+    return %.t0
+  zero.0:
+    return i32 0
+}
+
+function leaf(%n: i32) -> i32 {
+    return %n
+}
+
+function twice() -> void {
+    call twice()
+    return
+}
+"#,
+            expect![[r#"
+                main: calls ["even", "leaf"], 0 call sites
+                even: calls ["odd"], 2 call sites, recursive
+                odd: calls ["even", "leaf"], 1 call sites, recursive
+                leaf: calls [], 2 call sites, address taken
+                twice: calls ["twice"], 1 call sites, recursive, address taken
+                ["leaf"]
+                ["even", "odd"]
+                ["main"]
+                ["twice"]"#]],
+        );
+    }
+}
@@ -0,0 +1,394 @@
+//! Control-flow graphs of function bodies.
+//!
+//! A body is split into basic blocks: straight-line runs of instructions that
+//! start at a label or after a jump, and end at a jump or `return` or just
+//! before the next label. Two more blocks, with no instructions, stand for
+//! where the function starts and where it returns to its caller, so every
+//! function has one entry and one exit.
+//!
+//! Blocks keep the order they had in the body, which is what lets a block fall
+//! through to the next one, and what [`Cfg::into_instructions`] puts back.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+};
+
+use crate::{
+    dominance::Dominators,
+    loops::Loops,
+    ssa::Phi,
+    tacky::{Function, Instruction, Type},
+};
+
+/// A block by its index in [`Cfg::blocks`].
+pub type BlockId = usize;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BasicBlock {
+    /// The phis at the start of the block, which only a function in SSA form has.
+    pub phis: Vec<Phi>,
+    pub instructions: Vec<Instruction>,
+    pub predecessors: Vec<BlockId>,
+    pub successors: Vec<BlockId>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cfg {
+    /// The entry, the exit, then the blocks of the body in order.
+    pub blocks: Vec<BasicBlock>,
+}
+impl Cfg {
+    pub const ENTRY: BlockId = 0;
+    pub const EXIT: BlockId = 1;
+
+    pub fn new(instructions: Vec<Instruction>) -> Self {
+        let mut blocks = vec![BasicBlock::default(), BasicBlock::default()];
+        let mut current = Vec::new();
+        for instruction in instructions {
+            if matches!(instruction, Instruction::Label(_)) && !current.is_empty() {
+                blocks.push(BasicBlock {
+                    instructions: std::mem::take(&mut current),
+                    ..BasicBlock::default()
+                });
+            }
+            let ends_block = matches!(
+                instruction,
+                Instruction::Return(_)
+                    | Instruction::Jump(_)
+                    | Instruction::JumpIfZero(..)
+                    | Instruction::JumpIfNotZero(..)
+            );
+            current.push(instruction);
+            if ends_block {
+                blocks.push(BasicBlock {
+                    instructions: std::mem::take(&mut current),
+                    ..BasicBlock::default()
+                });
+            }
+        }
+        if !current.is_empty() {
+            blocks.push(BasicBlock {
+                instructions: current,
+                ..BasicBlock::default()
+            });
+        }
+        let mut cfg = Self { blocks };
+        cfg.add_edges();
+        cfg
+    }
+
+    /// The body of a function, which is left empty.
+    pub fn of_function(function: &mut Function) -> Self {
+        Self::new(std::mem::take(&mut function.body))
+    }
+
+    /// The instructions of every block, in order. The graph must not be in SSA
+    /// form, since phis aren't instructions.
+    pub fn into_instructions(self) -> Vec<Instruction> {
+        debug_assert!(
+            self.blocks.iter().all(|block| block.phis.is_empty()),
+            "the graph is still in SSA form"
+        );
+        self.blocks
+            .into_iter()
+            .flat_map(|block| block.instructions)
+            .collect()
+    }
+
+    /// The blocks of the body, leaving out the entry and the exit.
+    pub fn body(&self) -> impl Iterator<Item = BlockId> {
+        2..self.blocks.len()
+    }
+
+    /// The label a block starts with, if any.
+    pub fn label(&self, block: BlockId) -> Option<&str> {
+        match self.blocks[block].instructions.first() {
+            Some(Instruction::Label(label)) => Some(label),
+            _ => None,
+        }
+    }
+
+    /// The variables only their own assignments can change: the scalars among
+    /// `variables` that no instruction names as an object, so that no pointer
+    /// can point to them.
+    pub fn unaliased_scalars(&self, variables: &BTreeMap<String, Type>) -> HashSet<String> {
+        let named: HashSet<&str> = self
+            .blocks
+            .iter()
+            .flat_map(|block| &block.instructions)
+            .filter_map(Instruction::named_object)
+            .collect();
+        variables
+            .iter()
+            .filter(|(name, ty)| !ty.is_bytes() && !named.contains(name.as_str()))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Work out the edges between blocks from the instructions they end with.
+    fn add_edges(&mut self) {
+        let labels: HashMap<String, BlockId> = self
+            .body()
+            .filter_map(|block| Some((self.label(block)?.to_string(), block)))
+            .collect();
+        let first = match self.blocks.len() > 2 {
+            true => 2,
+            false => Self::EXIT,
+        };
+        let mut edges = vec![(Self::ENTRY, first)];
+        for block in self.body() {
+            let next = match block + 1 < self.blocks.len() {
+                true => block + 1,
+                false => Self::EXIT,
+            };
+            let target = |label: &String| {
+                *labels
+                    .get(label)
+                    .unwrap_or_else(|| panic!("there is no label `{label}`"))
+            };
+            match self.blocks[block].instructions.last() {
+                Some(Instruction::Return(_)) => edges.push((block, Self::EXIT)),
+                Some(Instruction::Jump(label)) => edges.push((block, target(label))),
+                Some(Instruction::JumpIfZero(_, label) | Instruction::JumpIfNotZero(_, label)) => {
+                    edges.push((block, target(label)));
+                    edges.push((block, next));
+                }
+                _ => edges.push((block, next)),
+            }
+        }
+        for (from, to) in edges {
+            if !self.blocks[from].successors.contains(&to) {
+                self.blocks[from].successors.push(to);
+                self.blocks[to].predecessors.push(from);
+            }
+        }
+    }
+
+    /// The blocks reachable from the entry, each before its successors except
+    /// along back edges.
+    pub fn reverse_postorder(&self) -> Vec<BlockId> {
+        let mut visited = vec![false; self.blocks.len()];
+        let mut postorder = Vec::new();
+        // Each block on the stack is paired with how many of its successors
+        // have been visited
+        let mut stack = vec![(Self::ENTRY, 0)];
+        visited[Self::ENTRY] = true;
+        while let Some((block, next)) = stack.last_mut() {
+            match self.blocks[*block].successors.get(*next) {
+                Some(&successor) => {
+                    *next += 1;
+                    if !visited[successor] {
+                        visited[successor] = true;
+                        stack.push((successor, 0));
+                    }
+                }
+                None => {
+                    postorder.push(*block);
+                    stack.pop();
+                }
+            }
+        }
+        postorder.reverse();
+        postorder
+    }
+
+    /// The graph in Graphviz `dot`, with the instructions of each block, its
+    /// immediate dominator, dominance frontier and loop depth. Dominator tree
+    /// edges are dashed, and back edges of loops red.
+    pub fn to_dot(&self, name: &str) -> String {
+        let dominators = Dominators::new(self);
+        let loops = Loops::new(self, &dominators);
+        let mut dot = String::new();
+        writeln!(dot, "digraph \"{}\" {{", escape(name)).unwrap();
+        writeln!(dot, "    node [shape=box, fontname=monospace];").unwrap();
+        for (block, contents) in self.blocks.iter().enumerate() {
+            let mut label = match block {
+                Self::ENTRY => "entry\\l".to_string(),
+                Self::EXIT => "exit\\l".to_string(),
+                _ => format!("block {block}\\l"),
+            };
+            if let Some(idom) = dominators.idom(block) {
+                write!(label, "idom: {idom}").unwrap();
+                let frontier = dominators.frontier(block);
+                if !frontier.is_empty() {
+                    let frontier: Vec<_> = frontier.iter().map(BlockId::to_string).collect();
+                    write!(label, ", frontier: {}", frontier.join(" ")).unwrap();
+                }
+                label.push_str("\\l");
+            }
+            if loops.depth(block) > 0 {
+                write!(label, "loop depth: {}\\l", loops.depth(block)).unwrap();
+            }
+            for phi in &contents.phis {
+                write!(label, "    {}\\l", escape(&phi.to_string())).unwrap();
+            }
+            for instruction in &contents.instructions {
+                let indent = match instruction {
+                    Instruction::Label(_) => "  ",
+                    _ => "    ",
+                };
+                write!(label, "{indent}{}\\l", escape(&instruction.to_string())).unwrap();
+            }
+            writeln!(dot, "    b{block} [label=\"{label}\"];").unwrap();
+        }
+        for (block, contents) in self.blocks.iter().enumerate() {
+            for &successor in &contents.successors {
+                let back_edge = dominators.dominates(successor, block);
+                let attributes = if back_edge { " [color=red]" } else { "" };
+                writeln!(dot, "    b{block} -> b{successor}{attributes};").unwrap();
+            }
+        }
+        for block in 0..self.blocks.len() {
+            for &child in dominators.children(block) {
+                writeln!(
+                    dot,
+                    "    b{block} -> b{child} [style=dashed, color=gray, constraint=false];"
+                )
+                .unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// A string with the characters that end or escape a `dot` string escaped.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod cfg_tests {
+    use std::fmt::Write;
+
+    use expect_test::{expect, Expect};
+
+    use super::Cfg;
+    use crate::text::parse_program;
+
+    /// The blocks of the first function of a program, with their edges.
+    fn check_blocks(src: &str, expect: Expect) {
+        let mut program = parse_program(src).unwrap();
+        let cfg = Cfg::of_function(&mut program.functions[0]);
+        let mut actual = String::new();
+        for (block, contents) in cfg.blocks.iter().enumerate() {
+            writeln!(
+                actual,
+                "{block}: {:?} -> {:?}",
+                contents.predecessors, contents.successors
+            )
+            .unwrap();
+            for instruction in &contents.instructions {
+                writeln!(actual, "    {instruction}").unwrap();
+            }
+        }
+        expect.assert_eq(&actual);
+        let instructions = cfg.into_instructions();
+        assert_eq!(instructions, parse_program(src).unwrap().functions[0].body);
+    }
+
+    #[test]
+    fn test_blocks() {
+        check_blocks(
+            r#"
+global function f(%n: i32) -> i32 {
+    local %x: i32
+    %x = copy i32 0
+  start.0:
+  continue.0:
+    jz %n, break.0
+    %x = add %x, %n
+    %n = sub %n, i32 1
+    jump start.0
+  break.0:
+    return %x
+}
+"#,
+            expect![[r#"
+                0: [] -> [2]
+                1: [6] -> []
+                2: [0] -> [3]
+                    %x = copy i32 0
+                3: [2, 5] -> [4]
+                    start.0:
+                4: [3] -> [6, 5]
+                    continue.0:
+                    jz %n, break.0
+                5: [4] -> [3]
+                    %x = add %x, %n
+                    %n = sub %n, i32 1
+                    jump start.0
+                6: [4] -> [1]
+                    break.0:
+                    return %x
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_fall_through_and_dead_code() {
+        check_blocks(
+            r#"
+global function f(%n: i32) -> i32 {
+    jnz %n, one.0
+    return i32 0
+    jump one.0
+  one.0:
+    %n = copy i32 1
+}
+"#,
+            expect![[r#"
+                0: [] -> [2]
+                1: [3, 5] -> []
+                2: [0] -> [5, 3]
+                    jnz %n, one.0
+                3: [2] -> [1]
+                    return i32 0
+                4: [] -> [5]
+                    jump one.0
+                5: [2, 4] -> [1]
+                    one.0:
+                    %n = copy i32 1
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_dot() {
+        let mut program = parse_program(
+            r#"
+global function f(%n: i32) -> void {
+  start.0:
+    jz %n, end.0
+    %n = sub %n, i32 1
+    jump start.0
+  end.0:
+    return
+}
+"#,
+        )
+        .unwrap();
+        let cfg = Cfg::of_function(&mut program.functions[0]);
+        expect![[r#"
+            digraph "f" {
+                node [shape=box, fontname=monospace];
+                b0 [label="entry\l"];
+                b1 [label="exit\lidom: 4\l"];
+                b2 [label="block 2\lidom: 0, frontier: 2\lloop depth: 1\l  start.0:\l    jz %n, end.0\l"];
+                b3 [label="block 3\lidom: 2, frontier: 2\lloop depth: 1\l    %n = sub %n, i32 1\l    jump start.0\l"];
+                b4 [label="block 4\lidom: 2\l  end.0:\l    return\l"];
+                b0 -> b2;
+                b2 -> b4;
+                b2 -> b3;
+                b3 -> b2 [color=red];
+                b4 -> b1;
+                b0 -> b2 [style=dashed, color=gray, constraint=false];
+                b2 -> b3 [style=dashed, color=gray, constraint=false];
+                b2 -> b4 [style=dashed, color=gray, constraint=false];
+                b4 -> b1 [style=dashed, color=gray, constraint=false];
+            }
+        "#]]
+        .assert_eq(&cfg.to_dot("f"));
+    }
+}
@@ -0,0 +1,260 @@
+//! Dominators and dominance frontiers of a [`Cfg`].
+//!
+//! A block dominates another if every path from the entry to the other goes
+//! through it. The immediate dominators are found with the iterative algorithm
+//! of Cooper, Harvey and Kennedy ("A Simple, Fast Dominance Algorithm"), which
+//! walks the blocks in reverse postorder until nothing changes, and the
+//! dominance frontiers with their algorithm from the same paper. Blocks that
+//! can't be reached from the entry are in none of it.
+
+use crate::cfg::{BlockId, Cfg};
+
+#[derive(Debug, Clone)]
+pub struct Dominators {
+    /// The immediate dominator of every reachable block but the entry.
+    idoms: Vec<Option<BlockId>>,
+    reachable: Vec<bool>,
+    /// The children of every block in the dominator tree.
+    children: Vec<Vec<BlockId>>,
+    frontiers: Vec<Vec<BlockId>>,
+    /// When a depth-first walk of the dominator tree enters and leaves each
+    /// block, which makes asking whether one block dominates another cheap.
+    enter: Vec<usize>,
+    leave: Vec<usize>,
+}
+impl Dominators {
+    pub fn new(cfg: &Cfg) -> Self {
+        let count = cfg.blocks.len();
+        let order = cfg.reverse_postorder();
+        let mut reachable = vec![false; count];
+        // The position of every reachable block in postorder
+        let mut postorder = vec![0; count];
+        for (i, &block) in order.iter().enumerate() {
+            reachable[block] = true;
+            postorder[block] = order.len() - i;
+        }
+
+        let mut idoms = vec![None; count];
+        idoms[Cfg::ENTRY] = Some(Cfg::ENTRY);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in &order[1..] {
+                let mut processed = cfg.blocks[block]
+                    .predecessors
+                    .iter()
+                    .copied()
+                    .filter(|&predecessor| idoms[predecessor].is_some());
+                let first = processed
+                    .next()
+                    .expect("a reachable block has a processed predecessor");
+                let idom = processed.fold(first, |mut finger1, mut finger2| {
+                    while finger1 != finger2 {
+                        while postorder[finger1] < postorder[finger2] {
+                            finger1 = idoms[finger1].expect("it is processed");
+                        }
+                        while postorder[finger2] < postorder[finger1] {
+                            finger2 = idoms[finger2].expect("it is processed");
+                        }
+                    }
+                    finger1
+                });
+                if idoms[block] != Some(idom) {
+                    idoms[block] = Some(idom);
+                    changed = true;
+                }
+            }
+        }
+        idoms[Cfg::ENTRY] = None;
+
+        let mut children = vec![Vec::new(); count];
+        for (block, idom) in idoms.iter().enumerate() {
+            if let Some(idom) = idom {
+                children[*idom].push(block);
+            }
+        }
+
+        let mut frontiers: Vec<Vec<BlockId>> = vec![Vec::new(); count];
+        for &block in &order {
+            let predecessors = &cfg.blocks[block].predecessors;
+            if predecessors.len() < 2 {
+                continue;
+            }
+            for &predecessor in predecessors {
+                let mut runner = predecessor;
+                while reachable[runner] && Some(runner) != idoms[block] {
+                    if !frontiers[runner].contains(&block) {
+                        frontiers[runner].push(block);
+                    }
+                    let Some(idom) = idoms[runner] else {
+                        break;
+                    };
+                    runner = idom;
+                }
+            }
+        }
+        for frontier in &mut frontiers {
+            frontier.sort_unstable();
+        }
+
+        let (mut enter, mut leave) = (vec![0; count], vec![0; count]);
+        let mut clock = 0;
+        let mut stack = vec![(Cfg::ENTRY, false)];
+        while let Some((block, left)) = stack.pop() {
+            clock += 1;
+            if left {
+                leave[block] = clock;
+                continue;
+            }
+            enter[block] = clock;
+            stack.push((block, true));
+            stack.extend(children[block].iter().rev().map(|&child| (child, false)));
+        }
+
+        Self {
+            idoms,
+            reachable,
+            children,
+            frontiers,
+            enter,
+            leave,
+        }
+    }
+
+    pub fn is_reachable(&self, block: BlockId) -> bool {
+        self.reachable[block]
+    }
+
+    /// The immediate dominator of a block, or `None` for the entry and blocks
+    /// that can't be reached.
+    pub fn idom(&self, block: BlockId) -> Option<BlockId> {
+        self.idoms[block]
+    }
+
+    /// The blocks a block immediately dominates, in order.
+    pub fn children(&self, block: BlockId) -> &[BlockId] {
+        &self.children[block]
+    }
+
+    /// The blocks just past where a block dominates, in order.
+    pub fn frontier(&self, block: BlockId) -> &[BlockId] {
+        &self.frontiers[block]
+    }
+
+    /// Whether `a` dominates `b`, which every reachable block does itself.
+    pub fn dominates(&self, a: BlockId, b: BlockId) -> bool {
+        self.reachable[a]
+            && self.reachable[b]
+            && self.enter[a] <= self.enter[b]
+            && self.leave[b] <= self.leave[a]
+    }
+
+    /// The reachable blocks, each before the blocks it dominates.
+    pub fn preorder(&self) -> Vec<BlockId> {
+        let mut order = Vec::new();
+        let mut stack = vec![Cfg::ENTRY];
+        while let Some(block) = stack.pop() {
+            order.push(block);
+            stack.extend(self.children[block].iter().rev());
+        }
+        order
+    }
+}
+
+#[cfg(test)]
+mod dominance_tests {
+    use std::fmt::Write;
+
+    use expect_test::{expect, Expect};
+
+    use super::Dominators;
+    use crate::{cfg::Cfg, text::parse_program};
+
+    /// The immediate dominator and dominance frontier of every block of the
+    /// first function of a program.
+    fn check_dominators(src: &str, expect: Expect) {
+        let mut program = parse_program(src).unwrap();
+        let cfg = Cfg::of_function(&mut program.functions[0]);
+        let dominators = Dominators::new(&cfg);
+        let mut actual = String::new();
+        for block in 0..cfg.blocks.len() {
+            if !dominators.is_reachable(block) {
+                writeln!(actual, "{block}: unreachable").unwrap();
+                continue;
+            }
+            writeln!(
+                actual,
+                "{block}: idom {:?}, frontier {:?}",
+                dominators.idom(block),
+                dominators.frontier(block)
+            )
+            .unwrap();
+        }
+        expect.assert_eq(&actual);
+        // Check `dominates` against the definition, by walking up the tree
+        for a in 0..cfg.blocks.len() {
+            for b in 0..cfg.blocks.len() {
+                let mut runner = dominators.is_reachable(b).then_some(b);
+                let mut dominates = false;
+                while let Some(block) = runner {
+                    dominates |= block == a;
+                    runner = dominators.idom(block);
+                }
+                assert_eq!(dominators.dominates(a, b), dominates, "{a} dominates {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_diamond() {
+        check_dominators(
+            r#"
+global function f(%n: i32) -> i32 {
+    jz %n, else.0
+    %n = copy i32 1
+    jump end.0
+  else.0:
+    %n = copy i32 2
+  end.0:
+    return %n
+}
+"#,
+            expect![[r#"
+                0: idom None, frontier []
+                1: idom Some(5), frontier []
+                2: idom Some(0), frontier []
+                3: idom Some(2), frontier [5]
+                4: idom Some(2), frontier [5]
+                5: idom Some(2), frontier []
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_loops_and_unreachable_blocks() {
+        check_dominators(
+            r#"
+global function f(%n: i32) -> i32 {
+  outer.0:
+    jz %n, end.0
+  inner.1:
+    %n = sub %n, i32 1
+    jnz %n, inner.1
+    jump outer.0
+    return i32 1
+  end.0:
+    return %n
+}
+"#,
+            expect![[r#"
+                0: idom None, frontier []
+                1: idom Some(6), frontier []
+                2: idom Some(0), frontier [2]
+                3: idom Some(2), frontier [2, 3]
+                4: idom Some(3), frontier [2]
+                5: unreachable
+                6: idom Some(2), frontier []
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! What TACKY's operators and conversions do to constants.
+//!
+//! The interpreter and constant folding both evaluate instructions with these
+//! functions, so a program means the same thing before and after it is
+//! optimized. Integer arithmetic wraps to the width of its type, as it does on
+//! the target, but an operation C leaves undefined and the target can trap on,
+//! like division by zero, is an [`EvalError`] rather than some value.
+
+use crate::tacky::{BinaryOperator, Constant, Instruction, Type, UnaryOperator, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum EvalError {
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("the quotient of signed division overflows")]
+    DivisionOverflow,
+    #[error("shift count {0} is negative or not less than the width of the operand")]
+    ShiftCount(i128),
+    #[error("`{0}` doesn't apply to floating values")]
+    NotFloating(BinaryOperator),
+}
+
+/// The conversion instructions, each of which converts `src` to the type of `dst`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conversion {
+    SignExtend,
+    ZeroExtend,
+    Truncate,
+    IntToFloat,
+    FloatToInt,
+    FloatExtend,
+    FloatTruncate,
+}
+impl Conversion {
+    /// The conversion an instruction does, with its source and destination.
+    pub fn of(instruction: &Instruction) -> Option<(Self, &Value, &Value)> {
+        let (conversion, src, dst) = match instruction {
+            Instruction::SignExtend { src, dst } => (Self::SignExtend, src, dst),
+            Instruction::ZeroExtend { src, dst } => (Self::ZeroExtend, src, dst),
+            Instruction::Truncate { src, dst } => (Self::Truncate, src, dst),
+            Instruction::IntToFloat { src, dst } => (Self::IntToFloat, src, dst),
+            Instruction::FloatToInt { src, dst } => (Self::FloatToInt, src, dst),
+            Instruction::FloatExtend { src, dst } => (Self::FloatExtend, src, dst),
+            Instruction::FloatTruncate { src, dst } => (Self::FloatTruncate, src, dst),
+            _ => return None,
+        };
+        Some((conversion, src, dst))
+    }
+}
+
+/// Convert `value` to `ty`. Converting a floating value that is out of the range
+/// of an integer type saturates.
+pub fn convert(conversion: Conversion, value: Constant, ty: Type) -> Constant {
+    match conversion {
+        Conversion::SignExtend => Constant::integer(ty, i128::from(signed(value))),
+        Conversion::ZeroExtend | Conversion::Truncate => {
+            Constant::integer(ty, i128::from(value.bits))
+        }
+        Conversion::IntToFloat => {
+            let value = value.as_i128();
+            match ty {
+                // Round once, straight to the narrower type
+                Type::F32 => Constant::floating(ty, f64::from(value as f32)),
+                _ => Constant::floating(ty, value as f64),
+            }
+        }
+        Conversion::FloatToInt => {
+            let value = value.as_f64();
+            let value = match ty {
+                Type::I8 => i128::from(value as i8),
+                Type::U8 => i128::from(value as u8),
+                Type::I16 => i128::from(value as i16),
+                Type::U16 => i128::from(value as u16),
+                Type::I32 => i128::from(value as i32),
+                Type::U32 => i128::from(value as u32),
+                Type::I64 => i128::from(value as i64),
+                _ => i128::from(value as u64),
+            };
+            Constant::integer(ty, value)
+        }
+        Conversion::FloatExtend | Conversion::FloatTruncate => {
+            Constant::floating(ty, value.as_f64())
+        }
+    }
+}
+
+/// Whether a scalar is zero, which is false for a floating NaN.
+pub fn is_zero(value: Constant) -> bool {
+    match value.ty.is_floating() {
+        true => value.as_f64() == 0.0,
+        false => value.bits == 0,
+    }
+}
+
+pub fn unary(op: UnaryOperator, value: Constant) -> Constant {
+    let ty = value.ty;
+    match op {
+        UnaryOperator::Not => Constant::integer(Type::I32, i128::from(is_zero(value))),
+        UnaryOperator::Negate if ty.is_floating() => Constant::floating(ty, -value.as_f64()),
+        UnaryOperator::Negate => Constant::integer(ty, value.as_i128().wrapping_neg()),
+        UnaryOperator::Complement => Constant::integer(ty, !value.as_i128()),
+    }
+}
+
+/// Apply a binary operator to two constants of the same type.
+pub fn binary(op: BinaryOperator, lhs: Constant, rhs: Constant) -> Result<Constant, EvalError> {
+    debug_assert_eq!(lhs.ty, rhs.ty);
+    let ty = lhs.ty;
+    if op.is_comparison() {
+        let ordering = match ty.is_floating() {
+            true => lhs.as_f64().partial_cmp(&rhs.as_f64()),
+            false => Some(lhs.as_i128().cmp(&rhs.as_i128())),
+        };
+        let result = ordering.is_some_and(|ordering| match op {
+            BinaryOperator::Equal => ordering.is_eq(),
+            BinaryOperator::NotEqual => ordering.is_ne(),
+            BinaryOperator::LessThan => ordering.is_lt(),
+            BinaryOperator::LessOrEqual => ordering.is_le(),
+            BinaryOperator::GreaterThan => ordering.is_gt(),
+            _ => ordering.is_ge(),
+        });
+        // Every comparison with a NaN is false, except `!=`
+        let result = result || (ordering.is_none() && op == BinaryOperator::NotEqual);
+        return Ok(Constant::integer(Type::I32, i128::from(result)));
+    }
+    if ty.is_floating() {
+        return floating_binary(op, lhs, rhs);
+    }
+    let (a, b) = (lhs.as_i128(), rhs.as_i128());
+    let result = match op {
+        BinaryOperator::Add => a.wrapping_add(b),
+        BinaryOperator::Subtract => a.wrapping_sub(b),
+        BinaryOperator::Multiply => a.wrapping_mul(b),
+        BinaryOperator::Divide | BinaryOperator::Remainder => {
+            if b == 0 {
+                return Err(EvalError::DivisionByZero);
+            }
+            // Only `MIN / -1` can overflow, and the target traps on its remainder too
+            let quotient = a / b;
+            if Constant::integer(ty, quotient).as_i128() != quotient {
+                return Err(EvalError::DivisionOverflow);
+            }
+            match op {
+                BinaryOperator::Divide => quotient,
+                _ => a % b,
+            }
+        }
+        BinaryOperator::BitwiseAnd => a & b,
+        BinaryOperator::BitwiseOr => a | b,
+        BinaryOperator::BitwiseXor => a ^ b,
+        BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => {
+            if !(0..i128::from(ty.size() * 8)).contains(&b) {
+                return Err(EvalError::ShiftCount(b));
+            }
+            match op {
+                BinaryOperator::ShiftLeft => a << b,
+                // `a` is sign-extended only if its type is signed
+                _ => a >> b,
+            }
+        }
+        _ => unreachable!("comparisons are handled above"),
+    };
+    Ok(Constant::integer(ty, result))
+}
+
+fn floating_binary(
+    op: BinaryOperator,
+    lhs: Constant,
+    rhs: Constant,
+) -> Result<Constant, EvalError> {
+    let ty = lhs.ty;
+    let result = match ty {
+        Type::F32 => {
+            let (a, b) = (lhs.as_f64() as f32, rhs.as_f64() as f32);
+            f64::from(match op {
+                BinaryOperator::Add => a + b,
+                BinaryOperator::Subtract => a - b,
+                BinaryOperator::Multiply => a * b,
+                BinaryOperator::Divide => a / b,
+                _ => return Err(EvalError::NotFloating(op)),
+            })
+        }
+        _ => {
+            let (a, b) = (lhs.as_f64(), rhs.as_f64());
+            match op {
+                BinaryOperator::Add => a + b,
+                BinaryOperator::Subtract => a - b,
+                BinaryOperator::Multiply => a * b,
+                BinaryOperator::Divide => a / b,
+                _ => return Err(EvalError::NotFloating(op)),
+            }
+        }
+    };
+    Ok(Constant::floating(ty, result))
+}
+
+/// The bits of an integer constant as a signed value of its width.
+fn signed(value: Constant) -> i64 {
+    let bits = value.ty.size() * 8;
+    (value.bits << (64 - bits)) as i64 >> (64 - bits)
+}
+
+#[cfg(test)]
+mod eval_tests {
+    use super::{binary, convert, unary, Conversion, EvalError};
+    use crate::tacky::{BinaryOperator, Constant, Type, UnaryOperator};
+
+    fn int(ty: Type, value: i128) -> Constant {
+        Constant::integer(ty, value)
+    }
+
+    #[test]
+    fn test_integer_arithmetic() {
+        let add = binary(
+            BinaryOperator::Add,
+            int(Type::I32, i32::MAX.into()),
+            int(Type::I32, 1),
+        );
+        assert_eq!(add, Ok(int(Type::I32, i32::MIN.into())));
+        let div = binary(
+            BinaryOperator::Divide,
+            int(Type::I32, -7),
+            int(Type::I32, 2),
+        );
+        assert_eq!(div, Ok(int(Type::I32, -3)));
+        let rem = binary(
+            BinaryOperator::Remainder,
+            int(Type::I32, -7),
+            int(Type::I32, 2),
+        );
+        assert_eq!(rem, Ok(int(Type::I32, -1)));
+        let div = binary(
+            BinaryOperator::Divide,
+            int(Type::U32, -7),
+            int(Type::U32, 2),
+        );
+        assert_eq!(div, Ok(int(Type::U32, 0x7fff_fffc)));
+        let shr = binary(
+            BinaryOperator::ShiftRight,
+            int(Type::I64, -8),
+            int(Type::I64, 1),
+        );
+        assert_eq!(shr, Ok(int(Type::I64, -4)));
+        let shr = binary(
+            BinaryOperator::ShiftRight,
+            int(Type::U8, 0x80),
+            int(Type::U8, 7),
+        );
+        assert_eq!(shr, Ok(int(Type::U8, 1)));
+        let less = binary(
+            BinaryOperator::LessThan,
+            int(Type::U32, -1),
+            int(Type::U32, 1),
+        );
+        assert_eq!(less, Ok(int(Type::I32, 0)));
+        assert_eq!(
+            unary(UnaryOperator::Complement, int(Type::U16, 0)),
+            int(Type::U16, 0xffff)
+        );
+        assert_eq!(
+            unary(UnaryOperator::Negate, int(Type::I64, i64::MIN.into())),
+            int(Type::I64, i64::MIN.into())
+        );
+    }
+
+    #[test]
+    fn test_undefined_arithmetic() {
+        let zero = binary(
+            BinaryOperator::Remainder,
+            int(Type::U64, 1),
+            int(Type::U64, 0),
+        );
+        assert_eq!(zero, Err(EvalError::DivisionByZero));
+        let overflow = binary(
+            BinaryOperator::Divide,
+            int(Type::I32, i32::MIN.into()),
+            int(Type::I32, -1),
+        );
+        assert_eq!(overflow, Err(EvalError::DivisionOverflow));
+        let overflow = binary(
+            BinaryOperator::Remainder,
+            int(Type::I64, i64::MIN.into()),
+            int(Type::I64, -1),
+        );
+        assert_eq!(overflow, Err(EvalError::DivisionOverflow));
+        let shift = binary(
+            BinaryOperator::ShiftLeft,
+            int(Type::I32, 1),
+            int(Type::I32, 32),
+        );
+        assert_eq!(shift, Err(EvalError::ShiftCount(32)));
+    }
+
+    #[test]
+    fn test_floating_arithmetic() {
+        let float = |value| Constant::floating(Type::F32, value);
+        let sum = binary(BinaryOperator::Add, float(16777216.0), float(1.0));
+        assert_eq!(sum, Ok(float(16777216.0)));
+        let nan = Constant::floating(Type::F64, f64::NAN);
+        let equal = binary(BinaryOperator::Equal, nan, nan);
+        assert_eq!(equal, Ok(int(Type::I32, 0)));
+        let not_equal = binary(BinaryOperator::NotEqual, nan, nan);
+        assert_eq!(not_equal, Ok(int(Type::I32, 1)));
+        assert_eq!(unary(UnaryOperator::Not, nan), int(Type::I32, 0));
+        let rem = binary(BinaryOperator::Remainder, nan, nan);
+        assert_eq!(rem, Err(EvalError::NotFloating(BinaryOperator::Remainder)));
+    }
+
+    #[test]
+    fn test_conversions() {
+        let convert_int = |conversion, value, ty| convert(conversion, value, ty);
+        assert_eq!(
+            convert_int(Conversion::SignExtend, int(Type::I8, -1), Type::U64),
+            int(Type::U64, -1)
+        );
+        assert_eq!(
+            convert_int(Conversion::ZeroExtend, int(Type::I8, -1), Type::I32),
+            int(Type::I32, 255)
+        );
+        assert_eq!(
+            convert_int(Conversion::Truncate, int(Type::I64, 0x1_2345), Type::I16),
+            int(Type::I16, 0x2345)
+        );
+        // Just over halfway between two floats, but exactly halfway once rounded to a double
+        let big = int(Type::U64, (1 << 60) + (1 << 36) + 1);
+        let float = convert(Conversion::IntToFloat, big, Type::F32);
+        assert_eq!(float.as_f64(), ((1u64 << 60) + (1 << 36) + 1) as f32 as f64);
+        let large = Constant::floating(Type::F64, 1e20);
+        assert_eq!(
+            convert(Conversion::FloatToInt, large, Type::I32),
+            int(Type::I32, i32::MAX.into())
+        );
+        let negative = Constant::floating(Type::F64, -1.5);
+        assert_eq!(
+            convert(Conversion::FloatToInt, negative, Type::I8),
+            int(Type::I8, -1)
+        );
+    }
+}
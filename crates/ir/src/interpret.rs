@@ -0,0 +1,906 @@
+//! An interpreter for TACKY programs.
+//!
+//! It runs a program's `main` and gives back what `main` returns, so a program
+//! can be tested without a backend, and its behavior compared before and after
+//! an optimization. Arithmetic is done by [`eval`](crate::eval), the same as
+//! constant folding does it.
+//!
+//! Memory is a set of separate allocations, one for each static object, each
+//! variable of a running function and each block from `malloc`. Pointers are
+//! plain addresses, but every load and store has to land inside a live
+//! allocation, so reading past the end of an array, or through a pointer to a
+//! local of a function that has returned, is a [`RuntimeError`] and not garbage.
+//! Functions have addresses too, for calls through pointers. The only functions
+//! from outside the program are the builtins `putchar`, `malloc` and `free`.
+//!
+//! A program only gets so much memory and so many nested calls: `malloc` gives
+//! back a null pointer when it would go over, and anything else that would is
+//! a [`RuntimeError`], so a runaway program stops instead of taking the host
+//! down with it.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Write,
+};
+
+use crate::{
+    eval::{self, Conversion},
+    tacky::{Callee, Constant, Function, Instruction, Program, StaticInit, Type, Value},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RuntimeError {
+    /// The program can't start, like when it has no `main`.
+    #[error("{0}")]
+    Program(String),
+    /// Something went wrong running a function.
+    #[error("in `{function}`: {message}")]
+    Function { function: String, message: String },
+}
+
+/// The most calls that can be running at once.
+const MAX_CALL_DEPTH: usize = 100_000;
+
+/// Runs programs, each from the beginning with fresh memory.
+pub struct Interpreter<'a> {
+    program: &'a Program,
+    functions: HashMap<&'a str, Code<'a>>,
+}
+impl<'a> Interpreter<'a> {
+    pub fn new(program: &'a Program) -> Self {
+        let functions = program
+            .functions
+            .iter()
+            .map(|function| (function.name.as_str(), Code::new(function)))
+            .collect();
+        Self { program, functions }
+    }
+
+    /// Run `main` with zero for any parameters it has, writing what the program
+    /// prints to `output`, and return the exit status.
+    pub fn run(&self, output: &mut impl Write) -> Result<i32, RuntimeError> {
+        let main = self
+            .functions
+            .get("main")
+            .ok_or_else(|| RuntimeError::Program("there is no function `main`".into()))?;
+        let mut machine = Machine::new(self).map_err(RuntimeError::Program)?;
+        let args = main
+            .function
+            .params
+            .iter()
+            .map(|param| vec![0; main.function.variables[param].size() as usize])
+            .collect();
+        let in_function = |frame: &Frame| {
+            let function = frame.code.function.name.clone();
+            move |message| RuntimeError::Function { function, message }
+        };
+        let mut stack =
+            vec![machine
+                .enter(main, args, None)
+                .map_err(|message| RuntimeError::Function {
+                    function: "main".into(),
+                    message,
+                })?];
+        loop {
+            let depth = stack.len();
+            let frame = stack.last_mut().expect("the stack is never empty here");
+            match machine.step(frame, output).map_err(in_function(frame))? {
+                Step::Next => {}
+                Step::Call(code, args, dst) => {
+                    if depth == MAX_CALL_DEPTH {
+                        return Err(in_function(frame)(format!(
+                            "more than {MAX_CALL_DEPTH} calls are running at once"
+                        )));
+                    }
+                    let callee = machine.enter(code, args, dst).map_err(in_function(frame))?;
+                    stack.push(callee);
+                }
+                Step::Return(bytes) => {
+                    let callee = stack.pop().expect("the stack is never empty here");
+                    machine.leave(&callee);
+                    let Some(caller) = stack.last() else {
+                        // `main` returns an `int`, or nothing if it's `void`
+                        return Ok(match bytes.len() {
+                            0 => 0,
+                            _ => decode(Type::I32, &bytes[..4]).as_i128() as i32,
+                        });
+                    };
+                    if let Some(dst) = callee.dst {
+                        machine
+                            .write(caller, dst, &bytes)
+                            .map_err(in_function(caller))?;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A function, with where each of its labels is.
+struct Code<'a> {
+    function: &'a Function,
+    labels: HashMap<&'a str, usize>,
+}
+impl<'a> Code<'a> {
+    fn new(function: &'a Function) -> Self {
+        let labels = function
+            .body
+            .iter()
+            .enumerate()
+            .filter_map(|(i, instruction)| match instruction {
+                Instruction::Label(label) => Some((label.as_str(), i)),
+                _ => None,
+            })
+            .collect();
+        Self { function, labels }
+    }
+}
+
+/// A call of a function that is running.
+struct Frame<'a> {
+    code: &'a Code<'a>,
+    /// The next instruction.
+    pc: usize,
+    /// The address and type of every variable.
+    variables: HashMap<&'a str, (u64, Type)>,
+    /// Where in the caller the result goes.
+    dst: Option<&'a Value>,
+}
+
+/// What to do after an instruction.
+enum Step<'a> {
+    Next,
+    Call(&'a Code<'a>, Vec<Vec<u8>>, Option<&'a Value>),
+    Return(Vec<u8>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Storage {
+    Static,
+    Stack,
+    Heap,
+    Function,
+}
+
+struct Allocation {
+    bytes: Vec<u8>,
+    storage: Storage,
+}
+
+/// All the memory of a running program, by the address each allocation starts at.
+struct Memory {
+    allocations: BTreeMap<u64, Allocation>,
+    next: u64,
+    /// How many bytes the live allocations have between them.
+    used: u64,
+}
+impl Memory {
+    /// Bytes left between allocations, so that a pointer just past the end of
+    /// one is never the address of the next.
+    const GAP: u64 = 16;
+    /// The most bytes the live allocations can have between them.
+    const LIMIT: u64 = 1 << 28;
+
+    /// A new allocation of `size` zero bytes, or `None` if there isn't room for it.
+    fn allocate(&mut self, size: u64, align: u64, storage: Storage) -> Option<u64> {
+        let used = self
+            .used
+            .checked_add(size)
+            .filter(|&used| used <= Self::LIMIT)?;
+        let address = self.next.checked_next_multiple_of(align.max(1))?;
+        self.next = address.checked_add(size)?.checked_add(Self::GAP)?;
+        self.used = used;
+        let bytes = vec![0; size as usize];
+        self.allocations
+            .insert(address, Allocation { bytes, storage });
+        Some(address)
+    }
+
+    fn free(&mut self, address: u64) {
+        if let Some(allocation) = self.allocations.remove(&address) {
+            self.used -= allocation.bytes.len() as u64;
+        }
+    }
+
+    /// The allocation `size` bytes at `address` are in, and where they start in it.
+    fn find(&self, address: u64, size: u64) -> Option<(u64, usize)> {
+        let (&base, allocation) = self.allocations.range(..=address).next_back()?;
+        let offset = address - base;
+        (offset.checked_add(size)? <= allocation.bytes.len() as u64
+            && allocation.storage != Storage::Function)
+            .then_some((base, offset as usize))
+    }
+
+    fn read(&self, address: u64, size: u64) -> Result<&[u8], String> {
+        let (base, offset) = self
+            .find(address, size)
+            .ok_or_else(|| format!("invalid read of {size} bytes at {address:#x}"))?;
+        Ok(&self.allocations[&base].bytes[offset..offset + size as usize])
+    }
+
+    fn write(&mut self, address: u64, bytes: &[u8]) -> Result<(), String> {
+        let size = bytes.len() as u64;
+        let (base, offset) = self
+            .find(address, size)
+            .ok_or_else(|| format!("invalid write of {size} bytes at {address:#x}"))?;
+        let allocation = self.allocations.get_mut(&base).expect("it was just found");
+        allocation.bytes[offset..offset + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// The builtin functions.
+#[derive(Clone, Copy)]
+enum Builtin {
+    Putchar,
+    Malloc,
+    Free,
+}
+impl Builtin {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "putchar" => Some(Self::Putchar),
+            "malloc" => Some(Self::Malloc),
+            "free" => Some(Self::Free),
+            _ => None,
+        }
+    }
+}
+
+/// The state of a running program besides its call stack.
+struct Machine<'a> {
+    interpreter: &'a Interpreter<'a>,
+    memory: Memory,
+    /// The address and type of every static object defined in the program.
+    statics: HashMap<&'a str, (u64, Type)>,
+    /// The address of every function and builtin, and back.
+    function_addresses: HashMap<&'a str, u64>,
+    functions_at: HashMap<u64, &'a str>,
+}
+impl<'a> Machine<'a> {
+    fn new(interpreter: &'a Interpreter<'a>) -> Result<Self, String> {
+        let mut machine = Self {
+            interpreter,
+            memory: Memory {
+                allocations: BTreeMap::new(),
+                // Leave the first page out, so that no object is near a null pointer
+                next: 0x1000,
+                used: 0,
+            },
+            statics: HashMap::new(),
+            function_addresses: HashMap::new(),
+            functions_at: HashMap::new(),
+        };
+        let functions = interpreter.program.functions.iter();
+        let builtins = ["putchar", "malloc", "free"].into_iter();
+        for name in functions
+            .map(|function| function.name.as_str())
+            .chain(builtins)
+        {
+            let address = machine
+                .memory
+                .allocate(0, 16, Storage::Function)
+                .expect("an empty allocation always fits");
+            machine.function_addresses.insert(name, address);
+            machine.functions_at.insert(address, name);
+        }
+        // Every static has an address before any is initialized, since one may
+        // hold the address of another
+        for variable in &interpreter.program.statics {
+            if variable.init.is_some() {
+                let (size, align) = (variable.ty.size(), variable.ty.align());
+                let address = machine
+                    .memory
+                    .allocate(size, align, Storage::Static)
+                    .ok_or_else(|| format!("`%{}` is too big", variable.name))?;
+                machine
+                    .statics
+                    .insert(variable.name.as_str(), (address, variable.ty));
+            }
+        }
+        for variable in &interpreter.program.statics {
+            let Some(init) = &variable.init else {
+                continue;
+            };
+            let mut bytes = Vec::new();
+            for init in init {
+                match init {
+                    StaticInit::Zero(size) => bytes.resize(bytes.len() + *size as usize, 0),
+                    StaticInit::Integer(size, bits) => {
+                        bytes.extend_from_slice(&bits.to_le_bytes()[..*size as usize]);
+                    }
+                    StaticInit::Bytes(data) => bytes.extend_from_slice(data),
+                    StaticInit::Address(name, offset) => {
+                        let address = machine
+                            .global_address(name)
+                            .ok_or_else(|| format!("`%{name}` isn't defined in this program"))?;
+                        bytes
+                            .extend_from_slice(&address.wrapping_add_signed(*offset).to_le_bytes());
+                    }
+                }
+            }
+            let (address, ty) = machine.statics[variable.name.as_str()];
+            if bytes.len() as u64 != ty.size() {
+                return Err(format!(
+                    "the initializer of `%{}` has {} bytes, but its type `{ty}` has {}",
+                    variable.name,
+                    bytes.len(),
+                    ty.size()
+                ));
+            }
+            machine.memory.write(address, &bytes)?;
+        }
+        Ok(machine)
+    }
+
+    /// Start a call of a function, with the bytes of its arguments.
+    fn enter(
+        &mut self,
+        code: &'a Code<'a>,
+        args: Vec<Vec<u8>>,
+        dst: Option<&'a Value>,
+    ) -> Result<Frame<'a>, String> {
+        let function = code.function;
+        let params = function.params.len();
+        if args.len() < params || args.len() > params && !function.variadic {
+            return Err(format!(
+                "`{}` takes {}{params} arguments, but was called with {}",
+                function.name,
+                if function.variadic { "at least " } else { "" },
+                args.len()
+            ));
+        }
+        let mut frame = Frame {
+            code,
+            pc: 0,
+            variables: HashMap::new(),
+            dst,
+        };
+        for (name, &ty) in &function.variables {
+            let Some(address) = self.memory.allocate(ty.size(), ty.align(), Storage::Stack) else {
+                self.leave(&frame);
+                return Err(format!(
+                    "out of memory for the variables of `{}`",
+                    function.name
+                ));
+            };
+            frame.variables.insert(name.as_str(), (address, ty));
+        }
+        for (param, arg) in function.params.iter().zip(args) {
+            let param = Value::Variable(param.clone());
+            if let Err(message) = self.write(&frame, &param, &arg) {
+                self.leave(&frame);
+                return Err(message);
+            }
+        }
+        Ok(frame)
+    }
+
+    /// Free the variables of a call that has returned.
+    fn leave(&mut self, frame: &Frame) {
+        for &(address, _) in frame.variables.values() {
+            self.memory.free(address);
+        }
+    }
+
+    /// The address of a static object or function.
+    fn global_address(&self, name: &str) -> Option<u64> {
+        match self.statics.get(name) {
+            Some((address, _)) => Some(*address),
+            None => self.function_addresses.get(name).copied(),
+        }
+    }
+
+    /// The address and type of a variable or static object.
+    fn object(&self, frame: &Frame, name: &str) -> Result<(u64, Type), String> {
+        frame
+            .variables
+            .get(name)
+            .or_else(|| self.statics.get(name))
+            .copied()
+            .ok_or_else(|| format!("`%{name}` isn't an object defined in this program"))
+    }
+
+    fn type_of(&self, frame: &Frame, value: &Value) -> Result<Type, String> {
+        match value {
+            Value::Constant(constant) => Ok(constant.ty),
+            Value::Variable(name) => Ok(self.object(frame, name)?.1),
+        }
+    }
+
+    /// The type and bytes of a value.
+    fn read(&self, frame: &Frame, value: &Value) -> Result<(Type, Vec<u8>), String> {
+        match value {
+            Value::Constant(constant) => Ok((constant.ty, encode(*constant))),
+            Value::Variable(name) => {
+                let (address, ty) = self.object(frame, name)?;
+                Ok((ty, self.memory.read(address, ty.size())?.to_vec()))
+            }
+        }
+    }
+
+    fn scalar(&self, frame: &Frame, value: &Value) -> Result<Constant, String> {
+        match self.read(frame, value)? {
+            (ty @ Type::Bytes { .. }, _) => Err(format!("`{value}` has type `{ty}`, not a scalar")),
+            (ty, bytes) => Ok(decode(ty, &bytes)),
+        }
+    }
+
+    /// Write bytes to a variable or static object, which must be their size.
+    fn write(&mut self, frame: &Frame, dst: &Value, bytes: &[u8]) -> Result<(), String> {
+        let Value::Variable(name) = dst else {
+            return Err(format!("can't assign to the constant `{dst}`"));
+        };
+        let (address, ty) = self.object(frame, name)?;
+        if bytes.len() as u64 != ty.size() {
+            return Err(format!(
+                "can't store {} bytes in `%{name}`, which has type `{ty}`",
+                bytes.len()
+            ));
+        }
+        self.memory.write(address, bytes)
+    }
+
+    fn write_scalar(&mut self, frame: &Frame, dst: &Value, value: Constant) -> Result<(), String> {
+        self.write(frame, dst, &encode(value))
+    }
+
+    /// Run the next instruction of a call.
+    fn step(&mut self, frame: &mut Frame<'a>, output: &mut impl Write) -> Result<Step<'a>, String> {
+        let function = frame.code.function;
+        let Some(instruction) = function.body.get(frame.pc) else {
+            return Err("reached the end of the function without a `return`".into());
+        };
+        frame.pc += 1;
+        if let Some((conversion, src, dst)) = Conversion::of(instruction) {
+            let src = self.scalar(frame, src)?;
+            let ty = self.type_of(frame, dst)?;
+            let value = eval::convert(conversion, src, ty);
+            self.write_scalar(frame, dst, value)?;
+            return Ok(Step::Next);
+        }
+        match instruction {
+            Instruction::Return(value) => {
+                let bytes = match value {
+                    Some(value) => self.read(frame, value)?.1,
+                    None => Vec::new(),
+                };
+                return Ok(Step::Return(bytes));
+            }
+            Instruction::Unary { op, src, dst } => {
+                let value = eval::unary(*op, self.scalar(frame, src)?);
+                self.write_scalar(frame, dst, value)?;
+            }
+            Instruction::Binary { op, lhs, rhs, dst } => {
+                let (lhs, rhs) = (self.scalar(frame, lhs)?, self.scalar(frame, rhs)?);
+                if lhs.ty != rhs.ty {
+                    return Err(format!(
+                        "the operands of `{op}` have types `{}` and `{}`",
+                        lhs.ty, rhs.ty
+                    ));
+                }
+                let value = eval::binary(*op, lhs, rhs).map_err(|err| err.to_string())?;
+                self.write_scalar(frame, dst, value)?;
+            }
+            Instruction::Copy { src, dst } => {
+                let (_, bytes) = self.read(frame, src)?;
+                self.write(frame, dst, &bytes)?;
+            }
+            Instruction::GetAddress { src, dst } => {
+                let address = match frame.variables.get(src.as_str()) {
+                    Some((address, _)) => *address,
+                    None => self
+                        .global_address(src)
+                        .ok_or_else(|| format!("`%{src}` isn't defined in this program"))?,
+                };
+                self.write_scalar(frame, dst, pointer(address))?;
+            }
+            Instruction::Load { ptr, dst } => {
+                let address = self.scalar(frame, ptr)?.bits;
+                let ty = self.type_of(frame, dst)?;
+                let bytes = self.memory.read(address, ty.size())?.to_vec();
+                self.write(frame, dst, &bytes)?;
+            }
+            Instruction::Store { src, ptr } => {
+                let (_, bytes) = self.read(frame, src)?;
+                let address = self.scalar(frame, ptr)?.bits;
+                self.memory.write(address, &bytes)?;
+            }
+            Instruction::AddPtr {
+                ptr,
+                index,
+                scale,
+                dst,
+            } => {
+                let address = self.scalar(frame, ptr)?.bits;
+                let index = self.scalar(frame, index)?.as_i128();
+                let offset = index.wrapping_mul(i128::from(*scale)) as i64;
+                self.write_scalar(frame, dst, pointer(address.wrapping_add_signed(offset)))?;
+            }
+            Instruction::CopyToOffset { src, dst, offset } => {
+                let (_, bytes) = self.read(frame, src)?;
+                let (address, ty) = self.object(frame, dst)?;
+                if offset + bytes.len() as u64 > ty.size() {
+                    return Err(format!(
+                        "{} bytes at offset {offset} are outside `%{dst}`, which has type `{ty}`",
+                        bytes.len()
+                    ));
+                }
+                self.memory.write(address + offset, &bytes)?;
+            }
+            Instruction::CopyFromOffset { src, offset, dst } => {
+                let (address, ty) = self.object(frame, src)?;
+                let size = self.type_of(frame, dst)?.size();
+                if offset + size > ty.size() {
+                    return Err(format!(
+                        "{size} bytes at offset {offset} are outside `%{src}`, which has type `{ty}`"
+                    ));
+                }
+                let bytes = self.memory.read(address + offset, size)?.to_vec();
+                self.write(frame, dst, &bytes)?;
+            }
+            Instruction::Jump(label) => frame.pc = self.label(frame, label)?,
+            Instruction::JumpIfZero(value, label) => {
+                if eval::is_zero(self.scalar(frame, value)?) {
+                    frame.pc = self.label(frame, label)?;
+                }
+            }
+            Instruction::JumpIfNotZero(value, label) => {
+                if !eval::is_zero(self.scalar(frame, value)?) {
+                    frame.pc = self.label(frame, label)?;
+                }
+            }
+            Instruction::Label(_) => {}
+            Instruction::FunCall { callee, args, dst } => {
+                let name = match callee {
+                    Callee::Direct(name) => name.as_str(),
+                    Callee::Indirect(value) => {
+                        let address = self.scalar(frame, value)?.bits;
+                        self.functions_at.get(&address).copied().ok_or_else(|| {
+                            format!("called {address:#x}, which isn't the address of a function")
+                        })?
+                    }
+                };
+                if let Some(code) = self.interpreter.functions.get(name) {
+                    let args = args
+                        .iter()
+                        .map(|arg| Ok(self.read(frame, arg)?.1))
+                        .collect::<Result<_, String>>()?;
+                    return Ok(Step::Call(code, args, dst.as_ref()));
+                }
+                let builtin = Builtin::from_name(name)
+                    .ok_or_else(|| format!("`{name}` isn't defined in this program"))?;
+                let [arg] = args.as_slice() else {
+                    return Err(format!(
+                        "`{name}` takes 1 argument, but was called with {}",
+                        args.len()
+                    ));
+                };
+                let arg = self.scalar(frame, arg)?;
+                let result = self.call_builtin(builtin, arg, output)?;
+                match (dst, result) {
+                    (Some(dst), Some(result)) => self.write_scalar(frame, dst, result)?,
+                    (Some(_), None) => return Err(format!("`{name}` doesn't return a value")),
+                    (None, _) => {}
+                }
+            }
+            _ => unreachable!("conversions are handled above"),
+        }
+        Ok(Step::Next)
+    }
+
+    fn label(&self, frame: &Frame, label: &str) -> Result<usize, String> {
+        frame
+            .code
+            .labels
+            .get(label)
+            .copied()
+            .ok_or_else(|| format!("there is no label `{label}`"))
+    }
+
+    fn call_builtin(
+        &mut self,
+        builtin: Builtin,
+        arg: Constant,
+        output: &mut impl Write,
+    ) -> Result<Option<Constant>, String> {
+        match builtin {
+            Builtin::Putchar => {
+                let byte = arg.bits as u8;
+                output
+                    .write_all(&[byte])
+                    .map_err(|err| format!("couldn't write output: {err}"))?;
+                Ok(Some(Constant::integer(Type::I32, i128::from(byte))))
+            }
+            Builtin::Malloc => {
+                // Like C, a failed allocation gives back a null pointer
+                let address = self.memory.allocate(arg.bits, 16, Storage::Heap);
+                Ok(Some(pointer(address.unwrap_or(0))))
+            }
+            Builtin::Free => {
+                let address = arg.bits;
+                if address != 0 {
+                    match self.memory.allocations.get(&address) {
+                        Some(allocation) if allocation.storage == Storage::Heap => {
+                            self.memory.free(address);
+                        }
+                        _ => {
+                            return Err(format!(
+                                "freed {address:#x}, which isn't a live block from `malloc`"
+                            ))
+                        }
+                    }
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+fn pointer(address: u64) -> Constant {
+    Constant {
+        ty: Type::Pointer,
+        bits: address,
+    }
+}
+
+/// How a scalar is laid out in memory.
+fn encode(value: Constant) -> Vec<u8> {
+    match value.ty {
+        Type::F32 => (value.as_f64() as f32).to_le_bytes().to_vec(),
+        ty => value.bits.to_le_bytes()[..ty.size() as usize].to_vec(),
+    }
+}
+
+fn decode(ty: Type, bytes: &[u8]) -> Constant {
+    match ty {
+        Type::F32 => {
+            let value = f32::from_le_bytes(bytes.try_into().expect("an `f32` has 4 bytes"));
+            Constant::floating(ty, f64::from(value))
+        }
+        _ => {
+            let mut bits = [0; 8];
+            bits[..bytes.len()].copy_from_slice(bytes);
+            Constant {
+                ty,
+                bits: u64::from_le_bytes(bits),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod interpret_tests {
+    use std::{fs, path::Path};
+
+    use expect_test::{expect, Expect};
+
+    use super::Interpreter;
+    use crate::{tacky::Program, testing::lower, text::parse_program};
+
+    /// The exit status and output of a program, or the error it stopped with.
+    fn run(program: &Program) -> String {
+        let mut output = Vec::new();
+        match Interpreter::new(program).run(&mut output) {
+            Ok(status) => format!("{status}: {:?}", String::from_utf8_lossy(&output)),
+            Err(err) => format!("error {err}"),
+        }
+    }
+
+    fn check_run(src: &str, expect: Expect) {
+        expect.assert_eq(&run(&lower(src)));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        check_run(
+            r#"
+            int fib(int n) {
+                return n < 2 ? n : fib(n - 1) + fib(n - 2);
+            }
+            int main(void) {
+                unsigned int u = 0;
+                u = u - 1;
+                long big = 2147483647;
+                big = big + 1;
+                double d = 7 / 2 + (double)7 / 2;
+                int total = 0;
+                for (int i = 0; i < 10; i = i + 1) {
+                    switch (i % 3) {
+                        case 0: total = total + 1; break;
+                        case 1: continue;
+                        default: total = total + 10;
+                    }
+                }
+                return fib(15) == 610 && u == 4294967295 && big == 2147483648
+                    && d * 2 == 13 && (signed char)200 == -56 && total == 34 ? 42 : 1;
+            }
+            "#,
+            expect![[r#"42: """#]],
+        );
+    }
+
+    #[test]
+    fn test_memory() {
+        check_run(
+            r#"
+            int putchar(int c);
+            struct point { int x; int y; };
+            static char greeting[] = "hi!";
+            int *counter_at(void) {
+                static int counter = 3;
+                counter = counter + 1;
+                return &counter;
+            }
+            int sum(int *values, int count) {
+                int total = 0;
+                for (int i = 0; i < count; i = i + 1)
+                    total = total + values[i];
+                return total;
+            }
+            int main(void) {
+                for (char *c = greeting; *c; c = c + 1)
+                    putchar(*c);
+                int values[4] = {1, 2, 3};
+                values[3] = values[0] + values[2];
+                struct point a = {5, 6};
+                struct point b = a;
+                b.y = 7;
+                counter_at();
+                return sum(values, 4) * 100 + a.y * 10 + b.y + *counter_at();
+            }
+            "#,
+            expect![[r#"1072: "hi!""#]],
+        );
+        check_run(
+            r#"
+            struct node { long key; int values[3]; };
+            int main(void) {
+                struct node n = {1, {20, 30, 42}};
+                int *value = &n.values[2];
+                char *start = (char *)value - __builtin_offsetof(struct node, values[2]);
+                return ((struct node *)start)->key * 100 + *value;
+            }
+            "#,
+            expect![[r#"142: """#]],
+        );
+    }
+
+    #[test]
+    fn test_heap_and_function_pointers() {
+        check_run(
+            r#"
+            void *malloc(unsigned long size);
+            void free(void *ptr);
+            struct node { int value; struct node *next; };
+            int twice(int x) { return 2 * x; }
+            int apply(int (*f)(int), int x) { return f(x); }
+            int main(void) {
+                struct node *list = 0;
+                for (int i = 1; i <= 5; i = i + 1) {
+                    struct node *node = malloc(sizeof(struct node));
+                    node->value = apply(twice, i);
+                    node->next = list;
+                    list = node;
+                }
+                int total = 0;
+                while (list) {
+                    struct node *next = list->next;
+                    total = total * 10 + list->value % 10;
+                    free(list);
+                    list = next;
+                }
+                return total % 256;
+            }
+            "#,
+            expect![[r#"194: """#]],
+        );
+    }
+
+    #[test]
+    fn test_runtime_errors() {
+        check_run(
+            "int main(void) { int zero = 0; return 1 / zero; }",
+            expect![[r#"error in `main`: division by zero"#]],
+        );
+        check_run(
+            r#"
+            int main(void) {
+                int values[2] = {1, 2};
+                int *p = values;
+                return p[2];
+            }
+            "#,
+            expect!["error in `main`: invalid read of 4 bytes at 0x10c0"],
+        );
+        check_run(
+            r#"
+            int *dangling(void) { int local = 1; return &local; }
+            int main(void) { return *dangling(); }
+            "#,
+            expect!["error in `main`: invalid read of 4 bytes at 0x1098"],
+        );
+        check_run(
+            r#"
+            void *malloc(unsigned long size);
+            void free(void *ptr);
+            int main(void) {
+                int *p = malloc(4);
+                free(p);
+                free(p);
+                return 0;
+            }
+            "#,
+            expect!["error in `main`: freed 0x1090, which isn't a live block from `malloc`"],
+        );
+        check_run(
+            "int forever(int n) { return forever(n + 1); } int main(void) { return forever(0); }",
+            expect!["error in `forever`: more than 100000 calls are running at once"],
+        );
+    }
+
+    #[test]
+    fn test_failed_allocations() {
+        check_run(
+            r#"
+            void *malloc(unsigned long size);
+            void free(void *ptr);
+            int main(void) {
+                unsigned long huge = 1;
+                huge = huge << 62;
+                int failed = !malloc(huge) + !malloc(-1);
+                long half = 1 << 27;
+                void *a = malloc(half);
+                void *b = malloc(half);
+                failed = failed * 10 + !malloc(half);
+                free(a);
+                failed = failed * 10 + !malloc(half);
+                return failed;
+            }
+            "#,
+            expect![[r#"210: """#]],
+        );
+    }
+
+    /// Extra arguments to a variadic function are passed, though nothing can read them.
+    #[test]
+    fn test_variadic() {
+        let src = r#"function first(%n: i32, ...) -> i32 {
+    return %n
+}
+
+global function main() -> i32 {
+    local %.t0: i32
+    local %.t1: i32
+    %.t0 = call first(i32 7, i32 1, f64 2.0)
+    %.t1 = call first(i32 3)
+    %.t0 = add %.t0, %.t1
+    return %.t0
+}
+"#;
+        let program = parse_program(src).unwrap();
+        assert_eq!(program.to_string(), src);
+        expect![[r#"10: """#]].assert_eq(&run(&program));
+        let src = src.replace("call first(i32 3)", "call first()");
+        expect!["error in `main`: `first` takes at least 1 arguments, but was called with 0"]
+            .assert_eq(&run(&parse_program(&src).unwrap()));
+        check_run(
+            r#"
+            int va(int n, ...) { return n * 10; }
+            int main(void) { return va(2, 1, 2) + va(1, 3.0); }
+            "#,
+            expect![[r#"30: """#]],
+        );
+    }
+
+    #[test]
+    fn test_testdata() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let run_file = |name: &str| {
+            let src = fs::read_to_string(dir.join(name)).unwrap();
+            run(&parse_program(&src).unwrap())
+        };
+        expect![[r#"111: """#]].assert_eq(&run_file("control_flow.tacky"));
+        expect![[r#"1: "\n""#]].assert_eq(&run_file("memory.tacky"));
+    }
+}
@@ -0,0 +1,33 @@
+//! The TACKY intermediate representation, between the AST and assembly.
+//!
+//! - [`tacky`] defines the IR: programs of static objects and functions whose
+//!   bodies are lists of three-address instructions.
+//! - [`eval`] says what each operator and conversion does to constants.
+//! - [`lower`] turns a resolved, type checked AST into it.
+//! - [`text`] prints the IR as text and parses it back, for tests and debugging.
+//! - [`interpret`] runs a program, to test it without a backend.
+//! - [`cfg`] splits function bodies into control-flow graphs of basic blocks,
+//!   [`dominance`] finds their dominators and dominance frontiers, and [`loops`]
+//!   their natural loops.
+//! - [`call_graph`] finds which functions call which, and which are recursive.
+//! - [`ssa`] puts function bodies into SSA form and takes them back out.
+//! - [`pass`] runs pipelines of passes over programs, and [`optimize`] has the
+//!   optimizations.
+//! - [`verify`] checks that the IR is well formed, between passes.
+
+pub mod call_graph;
+pub mod cfg;
+pub mod dominance;
+pub mod eval;
+pub mod interpret;
+pub mod loops;
+pub mod lower;
+pub mod optimize;
+pub mod pass;
+pub mod ssa;
+pub mod tacky;
+pub mod text;
+pub mod verify;
+
+#[cfg(test)]
+mod testing;
@@ -0,0 +1,178 @@
+//! Natural loops of a [`Cfg`].
+//!
+//! An edge is a back edge if its target dominates its source. The natural loop
+//! of a back edge is its target, the loop's header, and every block that can
+//! reach the source without going through the header. Loops with the same
+//! header are one loop, so any two loops are either nested or disjoint, and
+//! the depth of a block is how many loops it is in.
+
+use std::collections::BTreeSet;
+
+use crate::{
+    cfg::{BlockId, Cfg},
+    dominance::Dominators,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    pub header: BlockId,
+    /// Every block of the loop, including the header.
+    pub blocks: BTreeSet<BlockId>,
+    /// The innermost loop this one is nested in, by its index in [`Loops::loops`].
+    pub parent: Option<usize>,
+    /// 1 for an outermost loop, 2 for a loop inside it, and so on.
+    pub depth: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Loops {
+    /// Every loop, each after the loops it is nested in.
+    pub loops: Vec<Loop>,
+    depths: Vec<u32>,
+}
+impl Loops {
+    pub fn new(cfg: &Cfg, dominators: &Dominators) -> Self {
+        let mut loops: Vec<Loop> = Vec::new();
+        for header in dominators.preorder() {
+            let latches: Vec<BlockId> = cfg.blocks[header]
+                .predecessors
+                .iter()
+                .copied()
+                .filter(|&predecessor| dominators.dominates(header, predecessor))
+                .collect();
+            if latches.is_empty() {
+                continue;
+            }
+            let mut blocks = BTreeSet::from([header]);
+            let mut stack = latches;
+            while let Some(block) = stack.pop() {
+                if blocks.insert(block) {
+                    stack.extend(
+                        cfg.blocks[block]
+                            .predecessors
+                            .iter()
+                            .filter(|&&predecessor| dominators.is_reachable(predecessor)),
+                    );
+                }
+            }
+            // Headers come in dominator tree preorder, so the loops this one is
+            // nested in are already here, and the last one holding its header
+            // is the innermost
+            let parent = loops
+                .iter()
+                .rposition(|outer| outer.blocks.contains(&header));
+            let depth = parent.map_or(1, |parent| loops[parent].depth + 1);
+            loops.push(Loop {
+                header,
+                blocks,
+                parent,
+                depth,
+            });
+        }
+        let mut depths = vec![0; cfg.blocks.len()];
+        for natural_loop in &loops {
+            for &block in &natural_loop.blocks {
+                depths[block] = depths[block].max(natural_loop.depth);
+            }
+        }
+        Self { loops, depths }
+    }
+
+    /// How many loops a block is in.
+    pub fn depth(&self, block: BlockId) -> u32 {
+        self.depths[block]
+    }
+
+    /// The innermost loop a block is in.
+    pub fn innermost(&self, block: BlockId) -> Option<&Loop> {
+        self.loops
+            .iter()
+            .rev()
+            .find(|natural_loop| natural_loop.blocks.contains(&block))
+    }
+}
+
+#[cfg(test)]
+mod loops_tests {
+    use std::fmt::Write;
+
+    use expect_test::{expect, Expect};
+
+    use super::Loops;
+    use crate::{cfg::Cfg, dominance::Dominators, text::parse_program};
+
+    /// The loops of the first function of a program.
+    fn check_loops(src: &str, expect: Expect) {
+        let mut program = parse_program(src).unwrap();
+        let cfg = Cfg::of_function(&mut program.functions[0]);
+        let dominators = Dominators::new(&cfg);
+        let loops = Loops::new(&cfg, &dominators);
+        let mut actual = String::new();
+        for (i, natural_loop) in loops.loops.iter().enumerate() {
+            writeln!(
+                actual,
+                "loop {i} at {}: {:?}, parent {:?}, depth {}",
+                natural_loop.header, natural_loop.blocks, natural_loop.parent, natural_loop.depth
+            )
+            .unwrap();
+        }
+        let depths: Vec<_> = (0..cfg.blocks.len())
+            .map(|block| loops.depth(block))
+            .collect();
+        writeln!(actual, "depths: {depths:?}").unwrap();
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_nested_loops() {
+        check_loops(
+            r#"
+global function f(%n: i32) -> i32 {
+    local %i: i32
+  outer.0:
+    jz %n, end.0
+    %i = copy %n
+  inner.1:
+    %i = sub %i, i32 1
+    jnz %i, inner.1
+  inner.2:
+    %i = add %i, i32 1
+    jz %i, inner.2
+    %n = sub %n, i32 1
+    jump outer.0
+  end.0:
+    return %n
+}
+"#,
+            expect![[r#"
+                loop 0 at 2: {2, 3, 4, 5, 6}, parent None, depth 1
+                loop 1 at 4: {4}, parent Some(0), depth 2
+                loop 2 at 5: {5}, parent Some(0), depth 2
+                depths: [0, 0, 1, 1, 2, 2, 1, 0]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_shared_header() {
+        // A `continue` makes a second back edge to the same header
+        check_loops(
+            r#"
+global function f(%n: i32) -> i32 {
+  start.0:
+    %n = sub %n, i32 1
+    jz %n, end.0
+    jnz %n, start.0
+    %n = sub %n, i32 1
+    jump start.0
+  end.0:
+    return %n
+}
+"#,
+            expect![[r#"
+                loop 0 at 2: {2, 3, 4}, parent None, depth 1
+                depths: [0, 0, 1, 1, 1, 0]
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,1664 @@
+//! Lowering of the resolved, type checked AST to TACKY.
+//!
+//! Every expression is flattened into instructions that leave its value in a
+//! temporary, and every statement into labels and jumps. The type checker has
+//! already made every conversion explicit, so lowering only has to pick the
+//! instruction that does each of them. Objects with static storage duration come
+//! from the [`SymbolTable`]; the locals of a function are defined where they are
+//! declared, and their initializers run each time the declaration is reached.
+//!
+//! Temporaries are named `.t0`, `.t1`, ... and generated labels after what they
+//! mark, as in `break.3`. Neither can clash with a renamed local like `x.0` or a
+//! label from the source, since C identifiers can't contain a `.`.
+
+use std::collections::{BTreeMap, HashMap};
+
+use tokengen::span::Span;
+use wacc_parser::{
+    ast::{
+        self, AssignmentOperator, Block, BlockItem, Conversion, Declaration, Expression,
+        ExpressionKind, ForInit, FunctionDefinition, Identifier, InitializerEntry,
+        InitializerEntryKind, PostfixOperator, Statement, StatementKind,
+    },
+    types::{self, FunctionType, Tag, TagTable},
+};
+use wacc_semantics::{
+    resolve::{Linkage, Resolution, SymbolKind},
+    symbols::{self, Definition, SymbolTable},
+};
+
+use crate::tacky::{
+    BinaryOperator, Callee, Constant, Function, Instruction, Program, StaticInit, StaticVariable,
+    Type, UnaryOperator, Value,
+};
+
+/// What an expression designates: a value, or an object to read, write or take
+/// the address of.
+#[derive(Debug, Clone)]
+enum Operand {
+    /// A value, or a named object, which stands for its own value.
+    Plain(Value),
+    /// The object a pointer points to.
+    Dereferenced(Value),
+    /// The part of a named object at a byte offset, like a member of a local struct.
+    SubObject(String, u64),
+    /// A bit-field in the storage unit `unit`, which has type `ty`.
+    BitField {
+        unit: Box<Operand>,
+        ty: Type,
+        bit_offset: u32,
+        width: u32,
+    },
+    /// The result of an expression of type `void`.
+    Void,
+}
+
+/// Lowers a program after it has been checked and its symbols collected.
+pub struct Lowerer<'a> {
+    resolution: &'a Resolution,
+    symbols: &'a SymbolTable,
+    tags: TagTable,
+    /// The array made for the contents of each string literal, with its terminating null.
+    strings: HashMap<Vec<u8>, String>,
+    statics: Vec<StaticVariable>,
+    /// The type of every variable of the current function.
+    variables: BTreeMap<String, Type>,
+    instructions: Vec<Instruction>,
+    temporaries: usize,
+    labels: usize,
+    /// Where `break` and `continue` jump to, innermost last.
+    breaks: Vec<String>,
+    continues: Vec<String>,
+    /// The label of every `case` and `default` in the current function, by the
+    /// start of its span.
+    cases: HashMap<usize, String>,
+}
+impl<'a> Lowerer<'a> {
+    pub fn new(resolution: &'a Resolution, symbols: &'a SymbolTable) -> Self {
+        Self {
+            resolution,
+            symbols,
+            tags: TagTable::default(),
+            strings: HashMap::new(),
+            statics: Vec::new(),
+            variables: BTreeMap::new(),
+            instructions: Vec::new(),
+            temporaries: 0,
+            labels: 0,
+            breaks: Vec::new(),
+            continues: Vec::new(),
+            cases: HashMap::new(),
+        }
+    }
+
+    pub fn lower_program(&mut self, program: &ast::Program) -> Program {
+        self.tags.clone_from(&program.tags);
+        for symbol in self.symbols.iter().filter(|symbol| !symbol.is_function) {
+            // The collector made arrays for the string literals in initializers.
+            if let [symbols::StaticInit::Bytes(bytes)] = symbol.init.as_slice() {
+                if symbol.name.starts_with(".Lstring.") {
+                    self.strings.insert(bytes.clone(), symbol.name.clone());
+                }
+            }
+            let init = match symbol.definition {
+                Definition::Declared => None,
+                _ => Some(symbol.init.iter().map(static_init).collect()),
+            };
+            self.statics.push(StaticVariable {
+                name: symbol.name.clone(),
+                global: symbol.linkage == Linkage::External,
+                ty: self.ir_type(&symbol.ty.ty),
+                init,
+            });
+        }
+        let mut functions = Vec::new();
+        for item in &program.items {
+            if let ast::ExternalDeclaration::Function(function) = item {
+                functions.push(self.function_definition(function));
+            }
+        }
+        Program {
+            statics: std::mem::take(&mut self.statics),
+            functions,
+        }
+    }
+
+    fn function_definition(&mut self, function: &FunctionDefinition) -> Function {
+        self.variables.clear();
+        self.temporaries = 0;
+        self.labels = 0;
+        self.cases.clear();
+        let types::Type::Function(FunctionType {
+            return_type,
+            is_variadic,
+            ..
+        }) = &function.ty.ty
+        else {
+            unreachable!("a function definition has a function type");
+        };
+        let return_type = match &return_type.ty {
+            types::Type::Void => None,
+            ty => Some(self.ir_type(ty)),
+        };
+        let mut params = Vec::new();
+        for parameter in &function.parameters {
+            let ty = self.ir_type(&parameter.ty.ty);
+            let name = match &parameter.name {
+                Some(name) => self.unique_name(name),
+                None => self.variable(ty),
+            };
+            self.variables.insert(name.clone(), ty);
+            params.push(name);
+        }
+
+        self.block(&function.body);
+        // Falling off the end of `main` returns 0 (C17 5.1.2.2.3). For any other
+        // function, the value is undefined and only matters if it's used.
+        let value = match return_type {
+            None => None,
+            Some(ty) if ty.is_bytes() => Some(self.temporary(ty)),
+            Some(ty) => Some(Value::Constant(Constant::zero(ty))),
+        };
+        self.emit(Instruction::Return(value));
+
+        let symbol = self.symbol(&function.name);
+        Function {
+            name: symbol.unique_name.clone(),
+            global: symbol.linkage == Linkage::External,
+            inline: function.specifiers.is_inline,
+            params,
+            variadic: *is_variadic,
+            return_type,
+            variables: std::mem::take(&mut self.variables),
+            body: std::mem::take(&mut self.instructions),
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        for item in &block.items {
+            match item {
+                BlockItem::Declaration(declaration) => self.declaration(declaration),
+                BlockItem::StaticAssert(_) => {}
+                BlockItem::Statement(statement) => self.statement(statement),
+            }
+        }
+    }
+
+    /// Define the locals a declaration declares and run their initializers.
+    /// Everything with static storage duration is already in the symbol table.
+    fn declaration(&mut self, declaration: &Declaration) {
+        if declaration.specifiers.storage_class.is_some() {
+            return;
+        }
+        for declarator in &declaration.declarators {
+            if declarator.ty.ty.is_function() {
+                continue;
+            }
+            let name = self.unique_name(&declarator.name);
+            let ty = self.ir_type(&declarator.ty.ty);
+            self.variables.insert(name.clone(), ty);
+            self.initialize(&name, ty, &declarator.entries);
+        }
+    }
+
+    /// Store the entries of a resolved initializer into the object `name` of type `ty`.
+    fn initialize(&mut self, name: &str, ty: Type, entries: &[InitializerEntry]) {
+        // A bit-field entry only covers its own bits, so the rest of its storage
+        // unit, which no other entry covers, has to start out zero.
+        let has_bit_fields = entries
+            .iter()
+            .any(|entry| matches!(entry.kind, InitializerEntryKind::BitField(..)));
+        if has_bit_fields {
+            self.zero(name, ty, 0, ty.size());
+        }
+        for entry in entries {
+            match &entry.kind {
+                InitializerEntryKind::Zero(size) if !has_bit_fields => {
+                    self.zero(name, ty, entry.offset, *size)
+                }
+                InitializerEntryKind::Zero(_) => {}
+                InitializerEntryKind::Value(_, expression) => {
+                    let value = self.value(expression);
+                    let object = sub_object(name, ty, entry.offset);
+                    self.assign(object, value);
+                }
+                InitializerEntryKind::BitField(member, bit_offset, width, expression) => {
+                    let value = self.value(expression);
+                    let bit_field = Operand::BitField {
+                        unit: Box::new(sub_object(name, ty, entry.offset)),
+                        ty: self.ir_type(&member.ty),
+                        bit_offset: *bit_offset,
+                        width: *width,
+                    };
+                    self.assign(bit_field, value);
+                }
+                InitializerEntryKind::Bytes(bytes) => {
+                    let mut offset = entry.offset;
+                    let mut bytes = bytes.as_slice();
+                    while !bytes.is_empty() {
+                        let chunk = chunk_type(offset, bytes.len() as u64);
+                        let (head, rest) = bytes.split_at(chunk.size() as usize);
+                        let bits = head
+                            .iter()
+                            .rev()
+                            .fold(0_i128, |bits, &byte| bits << 8 | i128::from(byte));
+                        let value = Value::Constant(Constant::integer(chunk, bits));
+                        self.assign(sub_object(name, ty, offset), value);
+                        offset += chunk.size();
+                        bytes = rest;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Zero `size` bytes of the object `name` of type `ty`, from `offset`.
+    fn zero(&mut self, name: &str, ty: Type, mut offset: u64, size: u64) {
+        if !ty.is_bytes() {
+            let zero = Value::Constant(Constant::zero(ty));
+            self.assign(Operand::Plain(Value::Variable(name.into())), zero);
+            return;
+        }
+        let end = offset + size;
+        while offset < end {
+            let chunk = chunk_type(offset, end - offset);
+            let zero = Value::Constant(Constant::zero(chunk));
+            self.assign(sub_object(name, ty, offset), zero);
+            offset += chunk.size();
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match &statement.kind {
+            StatementKind::Return(None) => self.emit(Instruction::Return(None)),
+            StatementKind::Return(Some(value)) => {
+                let value = match self.expression(value) {
+                    Operand::Void => None,
+                    operand => Some(self.load(operand, self.type_of(value))),
+                };
+                self.emit(Instruction::Return(value));
+            }
+            StatementKind::Expression(expression) => {
+                self.expression(expression);
+            }
+            StatementKind::Null | StatementKind::Error => {}
+            StatementKind::Compound(block) => self.block(block),
+            StatementKind::If(condition, then, els) => {
+                let id = self.label_id();
+                let end = format!("end.{id}");
+                let condition = self.value(condition);
+                match els {
+                    Some(els) => {
+                        let else_label = format!("else.{id}");
+                        self.emit(Instruction::JumpIfZero(condition, else_label.clone()));
+                        self.statement(then);
+                        self.emit(Instruction::Jump(end.clone()));
+                        self.emit(Instruction::Label(else_label));
+                        self.statement(els);
+                    }
+                    None => {
+                        self.emit(Instruction::JumpIfZero(condition, end.clone()));
+                        self.statement(then);
+                    }
+                }
+                self.emit(Instruction::Label(end));
+            }
+            StatementKind::While(condition, body) => {
+                let id = self.label_id();
+                let (continue_label, break_label) =
+                    (format!("continue.{id}"), format!("break.{id}"));
+                self.emit(Instruction::Label(continue_label.clone()));
+                let condition = self.value(condition);
+                self.emit(Instruction::JumpIfZero(condition, break_label.clone()));
+                self.loop_body(body, &break_label, &continue_label);
+                self.emit(Instruction::Jump(continue_label));
+                self.emit(Instruction::Label(break_label));
+            }
+            StatementKind::DoWhile(body, condition) => {
+                let id = self.label_id();
+                let start = format!("start.{id}");
+                let (continue_label, break_label) =
+                    (format!("continue.{id}"), format!("break.{id}"));
+                self.emit(Instruction::Label(start.clone()));
+                self.loop_body(body, &break_label, &continue_label);
+                self.emit(Instruction::Label(continue_label));
+                let condition = self.value(condition);
+                self.emit(Instruction::JumpIfNotZero(condition, start));
+                self.emit(Instruction::Label(break_label));
+            }
+            StatementKind::For(init, condition, post, body) => {
+                match init.as_ref() {
+                    ForInit::Declaration(declaration) => self.declaration(declaration),
+                    ForInit::Expression(expression) => {
+                        if let Some(expression) = expression {
+                            self.expression(expression);
+                        }
+                    }
+                }
+                let id = self.label_id();
+                let start = format!("start.{id}");
+                let (continue_label, break_label) =
+                    (format!("continue.{id}"), format!("break.{id}"));
+                self.emit(Instruction::Label(start.clone()));
+                if let Some(condition) = condition {
+                    let condition = self.value(condition);
+                    self.emit(Instruction::JumpIfZero(condition, break_label.clone()));
+                }
+                self.loop_body(body, &break_label, &continue_label);
+                self.emit(Instruction::Label(continue_label));
+                if let Some(post) = post {
+                    self.expression(post);
+                }
+                self.emit(Instruction::Jump(start));
+                self.emit(Instruction::Label(break_label));
+            }
+            StatementKind::Break => {
+                let label = self
+                    .breaks
+                    .last()
+                    .expect("`break` is inside a loop or switch");
+                self.emit(Instruction::Jump(label.clone()));
+            }
+            StatementKind::Continue => {
+                let label = self.continues.last().expect("`continue` is inside a loop");
+                self.emit(Instruction::Jump(label.clone()));
+            }
+            StatementKind::Switch(value, body) => self.switch(value, body),
+            StatementKind::Case(_, _, body) | StatementKind::Default(body) => {
+                let label = self.cases[&statement.span.start()].clone();
+                self.emit(Instruction::Label(label));
+                self.statement(body);
+            }
+            StatementKind::Goto(label) => self.emit(Instruction::Jump(label.name.clone())),
+            StatementKind::Labeled(label, body) => {
+                self.emit(Instruction::Label(label.name.clone()));
+                self.statement(body);
+            }
+        }
+    }
+
+    fn loop_body(&mut self, body: &Statement, break_label: &str, continue_label: &str) {
+        self.breaks.push(break_label.into());
+        self.continues.push(continue_label.into());
+        self.statement(body);
+        self.breaks.pop();
+        self.continues.pop();
+    }
+
+    /// A `switch` compares its value against each `case` in turn, and jumps to the
+    /// first that matches, or else to the `default` label or out of the switch.
+    fn switch(&mut self, value: &Expression, body: &Statement) {
+        let id = self.label_id();
+        let break_label = format!("break.{id}");
+        let ty = self.type_of(value);
+        let value = self.value(value);
+        let mut cases = Vec::new();
+        collect_cases(body, &mut cases);
+        let mut default = None;
+        for (i, (case, start)) in cases.into_iter().enumerate() {
+            let Some(case) = case else {
+                let label = format!("default.{id}");
+                default = Some(label.clone());
+                self.cases.insert(start, label);
+                continue;
+            };
+            let label = format!("case.{id}.{i}");
+            let constant = Value::Constant(Constant::integer(ty, case));
+            let is_match =
+                self.binary_op(BinaryOperator::Equal, value.clone(), constant, Type::I32);
+            self.emit(Instruction::JumpIfNotZero(is_match, label.clone()));
+            self.cases.insert(start, label);
+        }
+        self.emit(Instruction::Jump(default.unwrap_or(break_label.clone())));
+        self.breaks.push(break_label.clone());
+        self.statement(body);
+        self.breaks.pop();
+        self.emit(Instruction::Label(break_label));
+    }
+
+    /// The value of an expression that isn't `void`.
+    fn value(&mut self, expression: &Expression) -> Value {
+        let ty = self.type_of(expression);
+        let operand = self.expression(expression);
+        self.load(operand, ty)
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Operand {
+        match &expression.kind {
+            ExpressionKind::Constant(literal) => {
+                let ty = self.type_of(expression);
+                Operand::Plain(Value::Constant(match literal {
+                    ast::Literal::Integer(value, _) => Constant::integer(ty, *value),
+                    ast::Literal::Floating(value, _) => Constant::floating(ty, *value),
+                }))
+            }
+            ExpressionKind::Identifier(name) => {
+                let symbol = self.symbol(name);
+                match symbol.kind {
+                    SymbolKind::EnumConstant(value) => Operand::Plain(Value::Constant(
+                        Constant::integer(Type::I32, i128::from(value)),
+                    )),
+                    _ => Operand::Plain(Value::Variable(symbol.unique_name.clone())),
+                }
+            }
+            ExpressionKind::StringLiteral(bytes) => {
+                Operand::Plain(Value::Variable(self.string(bytes)))
+            }
+            ExpressionKind::Unary(operator, operand) => self.unary(*operator, operand, expression),
+            ExpressionKind::Postfix(operator, operand) => {
+                self.increment(operand, *operator == PostfixOperator::Increment, true)
+            }
+            ExpressionKind::Binary(operator, lhs, rhs) => {
+                self.binary(*operator, lhs, rhs, expression)
+            }
+            ExpressionKind::Assignment(operator, lhs, rhs) => self.assignment(*operator, lhs, rhs),
+            ExpressionKind::Conditional(condition, then, els) => {
+                self.conditional(condition, then, els, expression)
+            }
+            // The operand has already been converted to the type cast to, unless that's `void`.
+            ExpressionKind::Cast(type_name, operand) => match type_name.ty.ty {
+                types::Type::Void => {
+                    self.expression(operand);
+                    Operand::Void
+                }
+                _ => Operand::Plain(self.value(operand)),
+            },
+            ExpressionKind::CompoundLiteral(literal) => {
+                let ty = self.ir_type(&literal.ty.ty);
+                let name = self.variable(ty);
+                self.initialize(&name, ty, &literal.entries);
+                Operand::Plain(Value::Variable(name))
+            }
+            ExpressionKind::SizeofExpression(operand) => {
+                let size = self.size_of(self.c_type(operand));
+                Operand::Plain(Value::Constant(Constant::integer(Type::U64, size.into())))
+            }
+            ExpressionKind::SizeofType(type_name) => {
+                let size = self.size_of(&type_name.ty.ty);
+                Operand::Plain(Value::Constant(Constant::integer(Type::U64, size.into())))
+            }
+            ExpressionKind::Offsetof(_, _, offset) => Operand::Plain(Value::Constant(
+                Constant::integer(Type::U64, (*offset).into()),
+            )),
+            ExpressionKind::Call(callee, arguments) => self.call(callee, arguments, expression),
+            ExpressionKind::Subscript(base, index) => {
+                let is_pointer = matches!(self.c_type(base), types::Type::Pointer(_));
+                let (pointer, integer) = match is_pointer {
+                    true => (base, index),
+                    false => (index, base),
+                };
+                let scale = self.pointee_size(pointer);
+                let base = self.value(base);
+                let index = self.value(index);
+                let (pointer, index) = match is_pointer {
+                    true => (base, index),
+                    false => (index, base),
+                };
+                debug_assert!(self.c_type(integer).is_integer());
+                Operand::Dereferenced(self.add_pointer(pointer, index, scale))
+            }
+            ExpressionKind::Member(base, name) => {
+                let (types::Type::Struct(tag) | types::Type::Union(tag)) = self.c_type(base) else {
+                    unreachable!("members are type checked");
+                };
+                let tag = tag.clone();
+                let record = self.expression(base);
+                self.member(record, &tag, name)
+            }
+            ExpressionKind::PointerMember(base, name) => {
+                let types::Type::Pointer(record) = self.c_type(base) else {
+                    unreachable!("members are type checked");
+                };
+                let (types::Type::Struct(tag) | types::Type::Union(tag)) = &record.ty else {
+                    unreachable!("members are type checked");
+                };
+                let tag = tag.clone();
+                let pointer = self.value(base);
+                self.member(Operand::Dereferenced(pointer), &tag, name)
+            }
+            ExpressionKind::ImplicitConversion(conversion, operand) => match conversion {
+                Conversion::ArrayToPointer | Conversion::FunctionToPointer => {
+                    let object = self.expression(operand);
+                    Operand::Plain(self.address(object))
+                }
+                _ => {
+                    let (from, to) = (self.type_of(operand), self.type_of(expression));
+                    let value = self.value(operand);
+                    Operand::Plain(self.convert(value, from, to))
+                }
+            },
+            ExpressionKind::Error => unreachable!("only valid programs are lowered"),
+        }
+    }
+
+    fn unary(
+        &mut self,
+        operator: ast::UnaryOperator,
+        operand: &Expression,
+        expression: &Expression,
+    ) -> Operand {
+        let operator = match operator {
+            ast::UnaryOperator::Negate => UnaryOperator::Negate,
+            ast::UnaryOperator::Complement => UnaryOperator::Complement,
+            ast::UnaryOperator::Not => UnaryOperator::Not,
+            ast::UnaryOperator::Plus => return Operand::Plain(self.value(operand)),
+            ast::UnaryOperator::AddressOf => {
+                let object = self.expression(operand);
+                return Operand::Plain(self.address(object));
+            }
+            ast::UnaryOperator::Dereference => {
+                return Operand::Dereferenced(self.value(operand));
+            }
+            ast::UnaryOperator::PreIncrement => return self.increment(operand, true, false),
+            ast::UnaryOperator::PreDecrement => return self.increment(operand, false, false),
+        };
+        let src = self.value(operand);
+        let dst = self.temporary(self.type_of(expression));
+        self.emit(Instruction::Unary {
+            op: operator,
+            src,
+            dst: dst.clone(),
+        });
+        Operand::Plain(dst)
+    }
+
+    /// `++` or `--`, which give the value from before the change when they come
+    /// after the operand.
+    fn increment(&mut self, operand: &Expression, is_increment: bool, is_postfix: bool) -> Operand {
+        let c_type = self.c_type(operand).clone();
+        let ty = self.ir_type(&c_type);
+        let object = self.expression(operand);
+        let mut old = self.load(object.clone(), ty);
+        if is_postfix && matches!(object, Operand::Plain(_)) {
+            // A variable is its own value, which is about to change.
+            let copy = self.temporary(ty);
+            self.emit(Instruction::Copy {
+                src: old,
+                dst: copy.clone(),
+            });
+            old = copy;
+        }
+        let step = match is_increment {
+            true => 1,
+            false => -1,
+        };
+        let new = match &c_type {
+            types::Type::Pointer(pointee) => {
+                let scale = self.size_of(&pointee.ty);
+                let index = Value::Constant(Constant::integer(Type::I64, step));
+                self.add_pointer(old.clone(), index, scale)
+            }
+            c_type => {
+                // The arithmetic is done in the type of `x + 1`.
+                let computation = self.ir_type(&self.tags.common_type(c_type, &types::Type::Int));
+                let converted = self.convert(old.clone(), ty, computation);
+                let step = match computation.is_floating() {
+                    true => Constant::floating(computation, step as f64),
+                    false => Constant::integer(computation, step),
+                };
+                let sum = self.binary_op(
+                    BinaryOperator::Add,
+                    converted,
+                    Value::Constant(step),
+                    computation,
+                );
+                self.convert(sum, computation, ty)
+            }
+        };
+        let result = self.assign_result(object, new);
+        match is_postfix {
+            true => Operand::Plain(old),
+            false => result,
+        }
+    }
+
+    fn binary(
+        &mut self,
+        operator: ast::BinaryOperator,
+        lhs: &Expression,
+        rhs: &Expression,
+        expression: &Expression,
+    ) -> Operand {
+        let ty = self.type_of(expression);
+        let operator = match operator {
+            ast::BinaryOperator::Comma => {
+                self.expression(lhs);
+                return self.expression(rhs);
+            }
+            ast::BinaryOperator::LogicalAnd | ast::BinaryOperator::LogicalOr => {
+                return Operand::Plain(self.logical(operator, lhs, rhs));
+            }
+            ast::BinaryOperator::Add | ast::BinaryOperator::Subtract => {
+                if let Some(value) = self.pointer_arithmetic(operator, lhs, rhs) {
+                    return Operand::Plain(value);
+                }
+                binary_operator(operator)
+            }
+            operator => binary_operator(operator),
+        };
+        let lhs = self.value(lhs);
+        let rhs = self.value(rhs);
+        Operand::Plain(self.binary_op(operator, lhs, rhs, ty))
+    }
+
+    /// `+` and `-` with a pointer operand, or `None` for arithmetic on numbers.
+    fn pointer_arithmetic(
+        &mut self,
+        operator: ast::BinaryOperator,
+        lhs: &Expression,
+        rhs: &Expression,
+    ) -> Option<Value> {
+        let is_pointer =
+            |expression: &Expression| matches!(self.c_type(expression), types::Type::Pointer(_));
+        let value = match (is_pointer(lhs), is_pointer(rhs)) {
+            // The difference of two pointers counts elements, not bytes.
+            (true, true) => {
+                let scale = self.pointee_size(lhs);
+                let (lhs, rhs) = (self.value(lhs), self.value(rhs));
+                let bytes = self.binary_op(BinaryOperator::Subtract, lhs, rhs, Type::Pointer);
+                let bytes = self.convert(bytes, Type::Pointer, Type::I64);
+                let scale = Value::Constant(Constant::integer(Type::I64, scale.into()));
+                self.binary_op(BinaryOperator::Divide, bytes, scale, Type::I64)
+            }
+            (true, false) => {
+                let scale = self.pointee_size(lhs);
+                let pointer = self.value(lhs);
+                let mut index = self.value(rhs);
+                if operator == ast::BinaryOperator::Subtract {
+                    index = self.negate(index, Type::I64);
+                }
+                self.add_pointer(pointer, index, scale)
+            }
+            (false, true) => {
+                let scale = self.pointee_size(rhs);
+                let index = self.value(lhs);
+                let pointer = self.value(rhs);
+                self.add_pointer(pointer, index, scale)
+            }
+            (false, false) => return None,
+        };
+        Some(value)
+    }
+
+    /// `&&` and `||`, which only evaluate their right operand if the left one
+    /// doesn't decide the result.
+    fn logical(
+        &mut self,
+        operator: ast::BinaryOperator,
+        lhs: &Expression,
+        rhs: &Expression,
+    ) -> Value {
+        let id = self.label_id();
+        let (jump, decided, decided_value): (fn(Value, String) -> Instruction, _, _) =
+            match operator {
+                ast::BinaryOperator::LogicalAnd => {
+                    (Instruction::JumpIfZero, format!("false.{id}"), 0)
+                }
+                _ => (Instruction::JumpIfNotZero, format!("true.{id}"), 1),
+            };
+        let end = format!("end.{id}");
+        let result = self.temporary(Type::I32);
+        let lhs = self.value(lhs);
+        self.emit(jump(lhs, decided.clone()));
+        let rhs = self.value(rhs);
+        self.emit(jump(rhs, decided.clone()));
+        self.emit(Instruction::Copy {
+            src: Value::Constant(Constant::integer(Type::I32, 1 - decided_value)),
+            dst: result.clone(),
+        });
+        self.emit(Instruction::Jump(end.clone()));
+        self.emit(Instruction::Label(decided));
+        self.emit(Instruction::Copy {
+            src: Value::Constant(Constant::integer(Type::I32, decided_value)),
+            dst: result.clone(),
+        });
+        self.emit(Instruction::Label(end));
+        result
+    }
+
+    /// Simple and compound assignment. For a compound assignment, the checker has
+    /// already converted the right operand to the type the operation is done in.
+    fn assignment(
+        &mut self,
+        operator: AssignmentOperator,
+        lhs: &Expression,
+        rhs: &Expression,
+    ) -> Operand {
+        let c_type = self.c_type(lhs).clone();
+        let ty = self.ir_type(&c_type);
+        let object = self.expression(lhs);
+        let value = match operator {
+            AssignmentOperator::Assign => self.value(rhs),
+            AssignmentOperator::Compound(operator) => {
+                let old = self.load(object.clone(), ty);
+                match &c_type {
+                    types::Type::Pointer(pointee) => {
+                        let scale = self.size_of(&pointee.ty);
+                        let mut index = self.value(rhs);
+                        if operator == ast::BinaryOperator::Subtract {
+                            index = self.negate(index, Type::I64);
+                        }
+                        self.add_pointer(old, index, scale)
+                    }
+                    _ => {
+                        let computation = self.type_of(rhs);
+                        let old = self.convert(old, ty, computation);
+                        let rhs = self.value(rhs);
+                        let result =
+                            self.binary_op(binary_operator(operator), old, rhs, computation);
+                        self.convert(result, computation, ty)
+                    }
+                }
+            }
+        };
+        self.assign_result(object, value)
+    }
+
+    fn conditional(
+        &mut self,
+        condition: &Expression,
+        then: &Expression,
+        els: &Expression,
+        expression: &Expression,
+    ) -> Operand {
+        let id = self.label_id();
+        let (else_label, end) = (format!("else.{id}"), format!("end.{id}"));
+        let condition = self.value(condition);
+        self.emit(Instruction::JumpIfZero(condition, else_label.clone()));
+        if *self.c_type(expression) == types::Type::Void {
+            self.expression(then);
+            self.emit(Instruction::Jump(end.clone()));
+            self.emit(Instruction::Label(else_label));
+            self.expression(els);
+            self.emit(Instruction::Label(end));
+            return Operand::Void;
+        }
+        let result = self.temporary(self.type_of(expression));
+        let then = self.value(then);
+        self.emit(Instruction::Copy {
+            src: then,
+            dst: result.clone(),
+        });
+        self.emit(Instruction::Jump(end.clone()));
+        self.emit(Instruction::Label(else_label));
+        let els = self.value(els);
+        self.emit(Instruction::Copy {
+            src: els,
+            dst: result.clone(),
+        });
+        self.emit(Instruction::Label(end));
+        Operand::Plain(result)
+    }
+
+    fn call(
+        &mut self,
+        callee: &Expression,
+        arguments: &[Expression],
+        expression: &Expression,
+    ) -> Operand {
+        // Calling a function by name doesn't need its address.
+        let function = match &callee.kind {
+            ExpressionKind::ImplicitConversion(Conversion::FunctionToPointer, function) => {
+                match &function.kind {
+                    ExpressionKind::Identifier(name) => Some(self.symbol(name))
+                        .filter(|symbol| symbol.kind == SymbolKind::Function)
+                        .map(|symbol| symbol.unique_name.clone()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let callee = match function {
+            Some(name) => Callee::Direct(name),
+            None => Callee::Indirect(self.value(callee)),
+        };
+        let args = arguments
+            .iter()
+            .map(|argument| self.value(argument))
+            .collect();
+        let dst = match self.c_type(expression) {
+            types::Type::Void => None,
+            _ => Some(self.temporary(self.type_of(expression))),
+        };
+        self.emit(Instruction::FunCall {
+            callee,
+            args,
+            dst: dst.clone(),
+        });
+        dst.map_or(Operand::Void, Operand::Plain)
+    }
+
+    /// The member `name` of the struct or union with tag `tag` that `record` designates.
+    fn member(&mut self, record: Operand, tag: &Tag, name: &Identifier) -> Operand {
+        let (field, member) = self
+            .tags
+            .member(tag, &name.name)
+            .expect("members are type checked");
+        let bit_field = field.bit_offset.zip(member.bit_width);
+        let ty = self.ir_type(&member.ty.ty);
+        let unit = match record {
+            Operand::Plain(Value::Variable(name)) => Operand::SubObject(name, field.offset),
+            Operand::SubObject(name, offset) => Operand::SubObject(name, offset + field.offset),
+            Operand::Dereferenced(pointer) => {
+                Operand::Dereferenced(self.offset_pointer(pointer, field.offset))
+            }
+            _ => unreachable!("only objects have members"),
+        };
+        match bit_field {
+            Some((bit_offset, width)) => Operand::BitField {
+                unit: Box::new(unit),
+                ty,
+                bit_offset,
+                width,
+            },
+            None => unit,
+        }
+    }
+
+    /// Read the value of an operand of type `ty`.
+    fn load(&mut self, operand: Operand, ty: Type) -> Value {
+        match operand {
+            Operand::Plain(value) => value,
+            Operand::Dereferenced(ptr) => {
+                let dst = self.temporary(ty);
+                self.emit(Instruction::Load {
+                    ptr,
+                    dst: dst.clone(),
+                });
+                dst
+            }
+            Operand::SubObject(src, offset) => {
+                let dst = self.temporary(ty);
+                self.emit(Instruction::CopyFromOffset {
+                    src,
+                    offset,
+                    dst: dst.clone(),
+                });
+                dst
+            }
+            // Shift the field to the top of the unit, then back down to extend its sign.
+            Operand::BitField {
+                unit,
+                ty,
+                bit_offset,
+                width,
+            } => {
+                let unit = self.load(*unit, ty);
+                let bits = ty.size() as u32 * 8;
+                let left =
+                    Value::Constant(Constant::integer(ty, (bits - bit_offset - width).into()));
+                let unit = self.binary_op(BinaryOperator::ShiftLeft, unit, left, ty);
+                let right = Value::Constant(Constant::integer(ty, (bits - width).into()));
+                self.binary_op(BinaryOperator::ShiftRight, unit, right, ty)
+            }
+            Operand::Void => unreachable!("void expressions have no value"),
+        }
+    }
+
+    /// Write a value of the operand's type to it.
+    fn assign(&mut self, operand: Operand, value: Value) {
+        match operand {
+            Operand::Plain(dst @ Value::Variable(_)) => {
+                self.emit(Instruction::Copy { src: value, dst })
+            }
+            Operand::Dereferenced(ptr) => self.emit(Instruction::Store { src: value, ptr }),
+            Operand::SubObject(dst, offset) => self.emit(Instruction::CopyToOffset {
+                src: value,
+                dst,
+                offset,
+            }),
+            // Replace the field's bits in the unit, keeping the rest.
+            Operand::BitField {
+                unit,
+                ty,
+                bit_offset,
+                width,
+            } => {
+                let mask = match width {
+                    64 => u64::MAX,
+                    width => (1 << width) - 1,
+                };
+                let constant = |bits: u64| Value::Constant(Constant::integer(ty, bits.into()));
+                let field = self.binary_op(BinaryOperator::BitwiseAnd, value, constant(mask), ty);
+                let field = self.binary_op(
+                    BinaryOperator::ShiftLeft,
+                    field,
+                    constant(bit_offset.into()),
+                    ty,
+                );
+                let old = self.load((*unit).clone(), ty);
+                let rest = self.binary_op(
+                    BinaryOperator::BitwiseAnd,
+                    old,
+                    constant(!(mask << bit_offset)),
+                    ty,
+                );
+                let new = self.binary_op(BinaryOperator::BitwiseOr, rest, field, ty);
+                self.assign(*unit, new);
+            }
+            Operand::Plain(Value::Constant(_)) | Operand::Void => {
+                unreachable!("only objects can be assigned to")
+            }
+        }
+    }
+
+    /// Assign a value to an object, giving the value the object ends up holding.
+    /// A bit-field may have had to truncate it, so that is read back if it's used.
+    fn assign_result(&mut self, object: Operand, value: Value) -> Operand {
+        self.assign(object.clone(), value.clone());
+        match object {
+            Operand::BitField { .. } => object,
+            _ => Operand::Plain(value),
+        }
+    }
+
+    /// The address of an object.
+    fn address(&mut self, operand: Operand) -> Value {
+        match operand {
+            Operand::Plain(Value::Variable(src)) => {
+                let dst = self.temporary(Type::Pointer);
+                self.emit(Instruction::GetAddress {
+                    src,
+                    dst: dst.clone(),
+                });
+                dst
+            }
+            Operand::Dereferenced(pointer) => pointer,
+            Operand::SubObject(name, offset) => {
+                let base = self.address(Operand::Plain(Value::Variable(name)));
+                self.offset_pointer(base, offset)
+            }
+            Operand::Plain(Value::Constant(_)) | Operand::BitField { .. } | Operand::Void => {
+                unreachable!("only objects have addresses")
+            }
+        }
+    }
+
+    /// Convert a value from one type to another.
+    fn convert(&mut self, value: Value, from: Type, to: Type) -> Value {
+        if from == to {
+            return value;
+        }
+        let (src, dst) = (value, self.temporary(to));
+        let result = dst.clone();
+        let instruction = match (from.is_floating(), to.is_floating()) {
+            (false, false) if from.size() == to.size() => Instruction::Copy { src, dst },
+            (false, false) if from.size() > to.size() => Instruction::Truncate { src, dst },
+            (false, false) if from.is_signed() => Instruction::SignExtend { src, dst },
+            (false, false) => Instruction::ZeroExtend { src, dst },
+            (false, true) => Instruction::IntToFloat { src, dst },
+            (true, false) => Instruction::FloatToInt { src, dst },
+            (true, true) if from.size() < to.size() => Instruction::FloatExtend { src, dst },
+            (true, true) => Instruction::FloatTruncate { src, dst },
+        };
+        self.emit(instruction);
+        result
+    }
+
+    fn binary_op(&mut self, op: BinaryOperator, lhs: Value, rhs: Value, ty: Type) -> Value {
+        let dst = self.temporary(ty);
+        self.emit(Instruction::Binary {
+            op,
+            lhs,
+            rhs,
+            dst: dst.clone(),
+        });
+        dst
+    }
+
+    fn negate(&mut self, src: Value, ty: Type) -> Value {
+        let dst = self.temporary(ty);
+        self.emit(Instruction::Unary {
+            op: UnaryOperator::Negate,
+            src,
+            dst: dst.clone(),
+        });
+        dst
+    }
+
+    fn add_pointer(&mut self, ptr: Value, index: Value, scale: u64) -> Value {
+        let dst = self.temporary(Type::Pointer);
+        self.emit(Instruction::AddPtr {
+            ptr,
+            index,
+            scale,
+            dst: dst.clone(),
+        });
+        dst
+    }
+
+    /// A pointer `offset` bytes past `pointer`.
+    fn offset_pointer(&mut self, pointer: Value, offset: u64) -> Value {
+        match offset {
+            0 => pointer,
+            offset => {
+                let offset = Value::Constant(Constant::integer(Type::I64, offset.into()));
+                self.add_pointer(pointer, offset, 1)
+            }
+        }
+    }
+
+    /// The array holding the bytes of a string literal and a terminating null,
+    /// shared by every string literal with the same contents.
+    fn string(&mut self, bytes: &[u8]) -> String {
+        let mut bytes = bytes.to_vec();
+        bytes.push(0);
+        if let Some(name) = self.strings.get(&bytes) {
+            return name.clone();
+        }
+        let name = format!(".Lstring.{}", self.strings.len());
+        self.strings.insert(bytes.clone(), name.clone());
+        self.statics.push(StaticVariable {
+            name: name.clone(),
+            global: false,
+            ty: Type::Bytes {
+                size: bytes.len() as u64,
+                align: 1,
+            },
+            init: Some(vec![StaticInit::Bytes(bytes)]),
+        });
+        name
+    }
+
+    fn emit(&mut self, instruction: Instruction) {
+        self.instructions.push(instruction);
+    }
+
+    /// A new variable of type `ty`, by name.
+    fn variable(&mut self, ty: Type) -> String {
+        let name = format!(".t{}", self.temporaries);
+        self.temporaries += 1;
+        self.variables.insert(name.clone(), ty);
+        name
+    }
+
+    fn temporary(&mut self, ty: Type) -> Value {
+        Value::Variable(self.variable(ty))
+    }
+
+    /// A number for the labels of a new statement or expression.
+    fn label_id(&mut self) -> usize {
+        self.labels += 1;
+        self.labels - 1
+    }
+
+    fn symbol(&self, name: &Identifier) -> &'a wacc_semantics::resolve::Symbol {
+        let id = self
+            .resolution
+            .binding(name)
+            .expect("identifiers are resolved");
+        self.resolution.symbol(id)
+    }
+
+    fn unique_name(&self, name: &Identifier) -> String {
+        self.symbol(name).unique_name.clone()
+    }
+
+    fn c_type<'e>(&self, expression: &'e Expression) -> &'e types::Type {
+        &expression
+            .ty
+            .as_ref()
+            .expect("expressions are type checked")
+            .ty
+    }
+
+    fn type_of(&self, expression: &Expression) -> Type {
+        self.ir_type(self.c_type(expression))
+    }
+
+    fn size_of(&self, ty: &types::Type) -> u64 {
+        self.tags.layout(ty).expect("sizes are type checked").size
+    }
+
+    /// The size of what a pointer-typed expression points to.
+    fn pointee_size(&self, pointer: &Expression) -> u64 {
+        match self.c_type(pointer) {
+            types::Type::Pointer(pointee) => self.size_of(&pointee.ty),
+            _ => unreachable!("pointer arithmetic is type checked"),
+        }
+    }
+
+    /// The IR type of an object of a C type.
+    fn ir_type(&self, ty: &types::Type) -> Type {
+        match ty {
+            types::Type::Char | types::Type::SignedChar => Type::I8,
+            types::Type::UnsignedChar => Type::U8,
+            types::Type::Short => Type::I16,
+            types::Type::UnsignedShort => Type::U16,
+            types::Type::Int => Type::I32,
+            types::Type::UnsignedInt => Type::U32,
+            types::Type::Long | types::Type::LongLong => Type::I64,
+            types::Type::UnsignedLong | types::Type::UnsignedLongLong => Type::U64,
+            types::Type::Float => Type::F32,
+            types::Type::Double => Type::F64,
+            types::Type::Pointer(_) => Type::Pointer,
+            types::Type::Enum(_) => self.ir_type(&self.tags.underlying_type(ty)),
+            types::Type::Array(..) | types::Type::Struct(_) | types::Type::Union(_) => {
+                match self.tags.layout(ty) {
+                    Some(layout) => Type::Bytes {
+                        size: layout.size,
+                        align: layout.align,
+                    },
+                    // Only an object defined elsewhere can have an incomplete type.
+                    None => Type::Bytes {
+                        size: 0,
+                        align: match ty {
+                            types::Type::Array(element, _) => self.ir_type(&element.ty).align(),
+                            _ => 1,
+                        },
+                    },
+                }
+            }
+            types::Type::Void | types::Type::Function(_) => {
+                unreachable!("`{ty}` is not the type of an object")
+            }
+        }
+    }
+}
+
+fn binary_operator(operator: ast::BinaryOperator) -> BinaryOperator {
+    match operator {
+        ast::BinaryOperator::Multiply => BinaryOperator::Multiply,
+        ast::BinaryOperator::Divide => BinaryOperator::Divide,
+        ast::BinaryOperator::Remainder => BinaryOperator::Remainder,
+        ast::BinaryOperator::Add => BinaryOperator::Add,
+        ast::BinaryOperator::Subtract => BinaryOperator::Subtract,
+        ast::BinaryOperator::ShiftLeft => BinaryOperator::ShiftLeft,
+        ast::BinaryOperator::ShiftRight => BinaryOperator::ShiftRight,
+        ast::BinaryOperator::LessThan => BinaryOperator::LessThan,
+        ast::BinaryOperator::GreaterThan => BinaryOperator::GreaterThan,
+        ast::BinaryOperator::LessOrEqual => BinaryOperator::LessOrEqual,
+        ast::BinaryOperator::GreaterOrEqual => BinaryOperator::GreaterOrEqual,
+        ast::BinaryOperator::Equal => BinaryOperator::Equal,
+        ast::BinaryOperator::NotEqual => BinaryOperator::NotEqual,
+        ast::BinaryOperator::BitwiseAnd => BinaryOperator::BitwiseAnd,
+        ast::BinaryOperator::BitwiseXor => BinaryOperator::BitwiseXor,
+        ast::BinaryOperator::BitwiseOr => BinaryOperator::BitwiseOr,
+        ast::BinaryOperator::LogicalAnd
+        | ast::BinaryOperator::LogicalOr
+        | ast::BinaryOperator::Comma => {
+            unreachable!("`{operator}` is lowered to control flow")
+        }
+    }
+}
+
+fn static_init(init: &symbols::StaticInit) -> StaticInit {
+    match init {
+        symbols::StaticInit::Zero(size) => StaticInit::Zero(*size),
+        symbols::StaticInit::Integer(size, bits) => StaticInit::Integer(*size, *bits),
+        symbols::StaticInit::Bytes(bytes) => StaticInit::Bytes(bytes.clone()),
+        symbols::StaticInit::Address(name, offset) => StaticInit::Address(name.clone(), *offset),
+    }
+}
+
+/// The part at `offset` of the object `name` of type `ty`, which is the whole
+/// object if it's a scalar.
+fn sub_object(name: &str, ty: Type, offset: u64) -> Operand {
+    match ty.is_bytes() {
+        true => Operand::SubObject(name.into(), offset),
+        false => Operand::Plain(Value::Variable(name.into())),
+    }
+}
+
+/// The widest integer type that fits in `size` bytes and is aligned at `offset`,
+/// for filling in an object piece by piece.
+fn chunk_type(offset: u64, size: u64) -> Type {
+    [Type::I64, Type::I32, Type::I16, Type::I8]
+        .into_iter()
+        .find(|ty| ty.size() <= size && offset.is_multiple_of(ty.size()))
+        .expect("a single byte always fits")
+}
+
+/// Every `case` and `default` label of a `switch` body, with the start of its
+/// span, leaving out those of nested switches.
+fn collect_cases(statement: &Statement, cases: &mut Vec<(Option<i128>, usize)>) {
+    match &statement.kind {
+        StatementKind::Case(_, value, body) => {
+            cases.push((Some(*value), statement.span.start()));
+            collect_cases(body, cases);
+        }
+        StatementKind::Default(body) => {
+            cases.push((None, statement.span.start()));
+            collect_cases(body, cases);
+        }
+        StatementKind::Compound(block) => {
+            for item in &block.items {
+                if let BlockItem::Statement(statement) = item {
+                    collect_cases(statement, cases);
+                }
+            }
+        }
+        StatementKind::If(_, then, els) => {
+            collect_cases(then, cases);
+            if let Some(els) = els {
+                collect_cases(els, cases);
+            }
+        }
+        StatementKind::While(_, body)
+        | StatementKind::DoWhile(body, _)
+        | StatementKind::For(_, _, _, body)
+        | StatementKind::Labeled(_, body) => collect_cases(body, cases),
+        StatementKind::Return(_)
+        | StatementKind::Expression(_)
+        | StatementKind::Null
+        | StatementKind::Break
+        | StatementKind::Continue
+        | StatementKind::Switch(..)
+        | StatementKind::Goto(_)
+        | StatementKind::Error => {}
+    }
+}
+
+#[cfg(test)]
+mod lower_tests {
+    use expect_test::{expect, Expect};
+    use wacc_lexer::Lexer;
+    use wacc_parser::Parser;
+    use wacc_semantics::{resolve::Resolver, symbols::SymbolCollector, typecheck::TypeChecker};
+
+    use super::Lowerer;
+    use crate::{text::parse_program, verify::verify_program};
+
+    /// The TACKY for a valid program.
+    fn check_lowering(src: &str, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let mut program = parser.parse_program();
+        assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+        let mut resolver = Resolver::new();
+        let resolution = resolver.resolve_program(&mut program);
+        assert!(resolver.errors().is_empty(), "{:?}", resolver.errors());
+        let mut checker = TypeChecker::new(&resolution);
+        checker.check_program(&mut program);
+        assert!(checker.errors().is_empty(), "{:?}", checker.errors());
+        let mut collector = SymbolCollector::new(&resolution);
+        let symbols = collector.collect_program(&program);
+        assert!(collector.errors().is_empty(), "{:?}", collector.errors());
+        let output = Lowerer::new(&resolution, &symbols).lower_program(&program);
+        let text = output.to_string();
+        assert_eq!(parse_program(&text).unwrap(), output);
+        if let Err(err) = verify_program(&output) {
+            panic!("{err}\n{text}");
+        }
+        expect.assert_eq(&text);
+    }
+
+    #[test]
+    fn test_expressions() {
+        check_lowering(
+            r#"
+            int main(void) {
+                int a = 3;
+                long b = a * 2 + 1;
+                unsigned char c = b;
+                double d = -a;
+                return !a || b && c > d;
+            }
+            "#,
+            expect![[r#"
+                global function main() -> i32 {
+                    local %.t0: i32
+                    local %.t1: i32
+                    local %.t2: i64
+                    local %.t3: u8
+                    local %.t4: i32
+                    local %.t5: f64
+                    local %.t6: i32
+                    local %.t7: i32
+                    local %.t8: i32
+                    local %.t9: f64
+                    local %.t10: i32
+                    local %a.0: i32
+                    local %b.1: i64
+                    local %c.2: u8
+                    local %d.3: f64
+                    %a.0 = copy i32 3
+                    %.t0 = mul %a.0, i32 2
+                    %.t1 = add %.t0, i32 1
+                    %.t2 = sext %.t1
+                    %b.1 = copy %.t2
+                    %.t3 = trunc %b.1
+                    %c.2 = copy %.t3
+                    %.t4 = neg %a.0
+                    %.t5 = itof %.t4
+                    %d.3 = copy %.t5
+                    %.t7 = lnot %a.0
+                    jnz %.t7, true.0
+                    jz %b.1, false.1
+                    %.t9 = itof %c.2
+                    %.t10 = gt %.t9, %d.3
+                    jz %.t10, false.1
+                    %.t8 = copy i32 1
+                    jump end.1
+                  false.1:
+                    %.t8 = copy i32 0
+                  end.1:
+                    jnz %.t8, true.0
+                    %.t6 = copy i32 0
+                    jump end.0
+                  true.0:
+                    %.t6 = copy i32 1
+                  end.0:
+                    return %.t6
+                    return i32 0
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_control_flow() {
+        check_lowering(
+            r#"
+            int f(int n) {
+                int total = 0;
+                for (int i = 0; i < n; i++) {
+                    if (i % 2)
+                        continue;
+                    total += i;
+                }
+                while (total > 100)
+                    total /= 2;
+                do {
+                    total--;
+                } while (total > 50);
+                switch (n) {
+                case 1:
+                    return 1;
+                case 2:
+                    total = 0;
+                default:
+                    break;
+                }
+                goto done;
+            done:
+                return n ? total : -1;
+            }
+            "#,
+            expect![[r#"
+                global function f(%n.0: i32) -> i32 {
+                    local %.t0: i32
+                    local %.t1: i32
+                    local %.t2: i32
+                    local %.t3: i32
+                    local %.t4: i32
+                    local %.t5: i32
+                    local %.t6: i32
+                    local %.t7: i32
+                    local %.t8: i32
+                    local %.t9: i32
+                    local %.t10: i32
+                    local %.t11: i32
+                    local %.t12: i32
+                    local %.t13: i32
+                    local %i.2: i32
+                    local %total.1: i32
+                    %total.1 = copy i32 0
+                    %i.2 = copy i32 0
+                  start.0:
+                    %.t0 = lt %i.2, %n.0
+                    jz %.t0, break.0
+                    %.t1 = rem %i.2, i32 2
+                    jz %.t1, end.1
+                    jump continue.0
+                  end.1:
+                    %.t2 = add %total.1, %i.2
+                    %total.1 = copy %.t2
+                  continue.0:
+                    %.t3 = copy %i.2
+                    %.t4 = add %.t3, i32 1
+                    %i.2 = copy %.t4
+                    jump start.0
+                  break.0:
+                  continue.2:
+                    %.t5 = gt %total.1, i32 100
+                    jz %.t5, break.2
+                    %.t6 = div %total.1, i32 2
+                    %total.1 = copy %.t6
+                    jump continue.2
+                  break.2:
+                  start.3:
+                    %.t7 = copy %total.1
+                    %.t8 = add %.t7, i32 -1
+                    %total.1 = copy %.t8
+                  continue.3:
+                    %.t9 = gt %total.1, i32 50
+                    jnz %.t9, start.3
+                  break.3:
+                    %.t10 = eq %n.0, i32 1
+                    jnz %.t10, case.4.0
+                    %.t11 = eq %n.0, i32 2
+                    jnz %.t11, case.4.1
+                    jump default.4
+                  case.4.0:
+                    return i32 1
+                  case.4.1:
+                    %total.1 = copy i32 0
+                  default.4:
+                    jump break.4
+                  break.4:
+                    jump done
+                  done:
+                    jz %n.0, else.5
+                    %.t12 = copy %total.1
+                    jump end.5
+                  else.5:
+                    %.t13 = neg i32 1
+                    %.t12 = copy %.t13
+                  end.5:
+                    return %.t12
+                    return i32 0
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_pointers() {
+        check_lowering(
+            r#"
+            int putchar(int c);
+            struct point { int x; long y; unsigned flag : 1; };
+            int values[3] = { 1, 2, 3 };
+            static char *message = "hi";
+            void g(struct point *p) {
+                int *q = &values[1];
+                long distance = q - values;
+                p->y = distance;
+                p->flag = 1;
+                putchar(*message + p->x);
+            }
+            int main(void) {
+                struct point s = { 1, 2, 1 };
+                int (*fp)(int) = putchar;
+                g(&s);
+                s.x++;
+                fp(s.x);
+                return sizeof s + *"abc";
+            }
+            "#,
+            expect![[r#"
+                global static %values: bytes(12, 4) = { integer 4 0x1, integer 4 0x2, integer 4 0x3 }
+                static %message: ptr = { address %.Lstring.0 +0 }
+                static %.Lstring.0: bytes(3, 1) = { bytes "hi\x00" }
+                static %.Lstring.1: bytes(4, 1) = { bytes "abc\x00" }
+
+                global function g(%p.1: ptr) -> void {
+                    local %.t0: ptr
+                    local %.t1: i64
+                    local %.t2: ptr
+                    local %.t3: ptr
+                    local %.t4: ptr
+                    local %.t5: i64
+                    local %.t6: i64
+                    local %.t7: ptr
+                    local %.t8: ptr
+                    local %.t9: u32
+                    local %.t10: u32
+                    local %.t11: u32
+                    local %.t12: u32
+                    local %.t13: u32
+                    local %.t14: u32
+                    local %.t15: i8
+                    local %.t16: i32
+                    local %.t17: i32
+                    local %.t18: i32
+                    local %.t19: i32
+                    local %distance.3: i64
+                    local %q.2: ptr
+                    %.t0 = addr %values
+                    %.t1 = sext i32 1
+                    %.t2 = addptr %.t0, %.t1, 4
+                    %q.2 = copy %.t2
+                    %.t3 = addr %values
+                    %.t4 = sub %q.2, %.t3
+                    %.t5 = copy %.t4
+                    %.t6 = div %.t5, i64 4
+                    %distance.3 = copy %.t6
+                    %.t7 = addptr %p.1, i64 8, 1
+                    store %distance.3, %.t7
+                    %.t8 = addptr %p.1, i64 16, 1
+                    %.t9 = copy i32 1
+                    %.t10 = and %.t9, u32 1
+                    %.t11 = shl %.t10, u32 0
+                    %.t12 = load %.t8
+                    %.t13 = and %.t12, u32 4294967294
+                    %.t14 = or %.t13, %.t11
+                    store %.t14, %.t8
+                    %.t15 = load %message
+                    %.t16 = sext %.t15
+                    %.t17 = load %p.1
+                    %.t18 = add %.t16, %.t17
+                    %.t19 = call putchar(%.t18)
+                    return
+                }
+
+                global function main() -> i32 {
+                    local %.t0: i64
+                    local %.t1: u32
+                    local %.t2: u32
+                    local %.t3: u32
+                    local %.t4: u32
+                    local %.t5: u32
+                    local %.t6: u32
+                    local %.t7: ptr
+                    local %.t8: ptr
+                    local %.t9: i32
+                    local %.t10: i32
+                    local %.t11: i32
+                    local %.t12: i32
+                    local %.t13: ptr
+                    local %.t14: i8
+                    local %.t15: u64
+                    local %.t16: u64
+                    local %.t17: i32
+                    local %fp.5: ptr
+                    local %s.4: bytes(24, 8)
+                    copy_to_offset i64 0, %s.4, 0
+                    copy_to_offset i64 0, %s.4, 8
+                    copy_to_offset i64 0, %s.4, 16
+                    copy_to_offset i32 1, %s.4, 0
+                    %.t0 = sext i32 2
+                    copy_to_offset %.t0, %s.4, 8
+                    %.t1 = copy i32 1
+                    %.t2 = and %.t1, u32 1
+                    %.t3 = shl %.t2, u32 0
+                    %.t4 = copy_from_offset %s.4, 16
+                    %.t5 = and %.t4, u32 4294967294
+                    %.t6 = or %.t5, %.t3
+                    copy_to_offset %.t6, %s.4, 16
+                    %.t7 = addr %putchar
+                    %fp.5 = copy %.t7
+                    %.t8 = addr %s.4
+                    call g(%.t8)
+                    %.t9 = copy_from_offset %s.4, 0
+                    %.t10 = add %.t9, i32 1
+                    copy_to_offset %.t10, %s.4, 0
+                    %.t11 = copy_from_offset %s.4, 0
+                    %.t12 = call *%fp.5(%.t11)
+                    %.t13 = addr %.Lstring.1
+                    %.t14 = load %.t13
+                    %.t15 = sext %.t14
+                    %.t16 = add u64 24, %.t15
+                    %.t17 = trunc %.t16
+                    return %.t17
+                    return i32 0
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_initializers() {
+        check_lowering(
+            r#"
+            int main(void) {
+                char name[6] = "hello";
+                int grid[2][2] = { [1] = { 4 } };
+                int *p = (int[]){ 7, 8 };
+                static int calls = 0;
+                return name[1] + grid[1][0] + p[1] + ++calls;
+            }
+            "#,
+            expect![[r#"
+                static %calls.3: i32 = { integer 4 0x0 }
+
+                global function main() -> i32 {
+                    local %.t0: bytes(8, 4)
+                    local %.t1: ptr
+                    local %.t2: ptr
+                    local %.t3: i64
+                    local %.t4: ptr
+                    local %.t5: i8
+                    local %.t6: i32
+                    local %.t7: ptr
+                    local %.t8: i64
+                    local %.t9: ptr
+                    local %.t10: i64
+                    local %.t11: ptr
+                    local %.t12: i32
+                    local %.t13: i32
+                    local %.t14: i64
+                    local %.t15: ptr
+                    local %.t16: i32
+                    local %.t17: i32
+                    local %.t18: i32
+                    local %.t19: i32
+                    local %grid.1: bytes(16, 4)
+                    local %name.0: bytes(6, 1)
+                    local %p.2: ptr
+                    copy_to_offset i32 1819043176, %name.0, 0
+                    copy_to_offset i16 111, %name.0, 4
+                    copy_to_offset i64 0, %grid.1, 0
+                    copy_to_offset i32 4, %grid.1, 8
+                    copy_to_offset i32 0, %grid.1, 12
+                    copy_to_offset i32 7, %.t0, 0
+                    copy_to_offset i32 8, %.t0, 4
+                    %.t1 = addr %.t0
+                    %p.2 = copy %.t1
+                    %.t2 = addr %name.0
+                    %.t3 = sext i32 1
+                    %.t4 = addptr %.t2, %.t3, 1
+                    %.t5 = load %.t4
+                    %.t6 = sext %.t5
+                    %.t7 = addr %grid.1
+                    %.t8 = sext i32 1
+                    %.t9 = addptr %.t7, %.t8, 8
+                    %.t10 = sext i32 0
+                    %.t11 = addptr %.t9, %.t10, 4
+                    %.t12 = load %.t11
+                    %.t13 = add %.t6, %.t12
+                    %.t14 = sext i32 1
+                    %.t15 = addptr %p.2, %.t14, 4
+                    %.t16 = load %.t15
+                    %.t17 = add %.t13, %.t16
+                    %.t18 = add %calls.3, i32 1
+                    %calls.3 = copy %.t18
+                    %.t19 = add %.t17, %.t18
+                    return %.t19
+                    return i32 0
+                }
+            "#]],
+        );
+    }
+}
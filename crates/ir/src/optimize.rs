@@ -0,0 +1,318 @@
+//! Optimizations of TACKY functions, as passes for the [pass manager](crate::pass).
+//!
+//! These are the four optimizations of the book's optimization chapter, each
+//! in its own module. Each one can leave work for the others, like a folded
+//! condition that leaves a branch unreachable, or a propagated copy that leaves
+//! its assignment dead, so the pipelines of the higher [levels](OptLevel) run
+//! them in turn until none of them changes anything, and then forget the
+//! variables the function no longer mentions.
+//!
+//! The higher levels also [inline](inline) calls, between two rounds of the
+//! others, so that the callees are as small as they get before their size is
+//! judged and the copies are cleaned up after. Every level but `-O0` deletes
+//! the functions that can no longer be called.
+
+pub mod copy_propagation;
+pub mod dead_stores;
+pub mod fold;
+pub mod inline;
+pub mod unreachable;
+
+use std::collections::HashSet;
+
+use crate::{
+    cfg::Cfg,
+    dominance::Dominators,
+    loops::Loops,
+    pass::{Analyses, Pass, PassRegistry, Preserved},
+    ssa,
+    tacky::{Function, Value},
+};
+
+/// How hard to optimize, as chosen by `-O`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptLevel {
+    /// Don't.
+    O0,
+    /// Run each optimization once.
+    O1,
+    /// Run the optimizations until they stop finding anything.
+    O2,
+    /// Optimize for size, which is what [`O2`](Self::O2) does, except that
+    /// only the calls that are about as big as the function are inlined.
+    Os,
+}
+impl OptLevel {
+    /// The pipeline of the level, for [`PassRegistry::parse_pipeline`].
+    pub fn pipeline(self) -> &'static str {
+        match self {
+            Self::O0 => "",
+            Self::O1 => "fold,unreachable,copyprop,dce,prune,prune-functions",
+            Self::O2 => concat!(
+                "fixpoint(fold,unreachable,copyprop,dce),inline,",
+                "fixpoint(fold,unreachable,copyprop,dce),prune,prune-functions",
+            ),
+            Self::Os => concat!(
+                "fixpoint(fold,unreachable,copyprop,dce),inline-small,",
+                "fixpoint(fold,unreachable,copyprop,dce),prune,prune-functions",
+            ),
+        }
+    }
+}
+
+/// A registry of the optimizations and the analyses they use.
+pub fn registry() -> PassRegistry {
+    let mut registry = PassRegistry::new();
+    registry.register_analysis::<Cfg>();
+    registry.register_analysis::<Dominators>();
+    registry.register_analysis::<Loops>();
+    registry.register_pass(fold::FoldConstants);
+    registry.register_pass(unreachable::EliminateUnreachableCode);
+    registry.register_pass(copy_propagation::PropagateCopies);
+    registry.register_pass(dead_stores::EliminateDeadStores);
+    registry.register_pass(PruneVariables);
+    registry.register_check("verify-ssa", |function| {
+        ssa::verify_into_ssa(function).map_err(|err| err.to_string())
+    });
+    registry.register_program_pass(inline::InlineFunctions::FOR_SPEED);
+    registry.register_program_pass(inline::InlineFunctions::FOR_SIZE);
+    registry.register_program_pass(inline::PruneFunctions);
+    registry
+}
+
+/// Forgets the variables a function no longer mentions.
+pub struct PruneVariables;
+impl Pass for PruneVariables {
+    fn name(&self) -> &'static str {
+        "prune"
+    }
+
+    fn preserves(&self) -> Preserved {
+        Preserved::ControlFlow
+    }
+
+    fn run(&self, function: &mut Function, _: &mut Analyses) {
+        let mut mentioned: HashSet<&str> = function.params.iter().map(String::as_str).collect();
+        for instruction in &function.body {
+            let values = instruction.sources().into_iter().chain(instruction.dst());
+            mentioned.extend(values.filter_map(|value| match value {
+                Value::Variable(name) => Some(name.as_str()),
+                Value::Constant(_) => None,
+            }));
+            mentioned.extend(instruction.named_object());
+        }
+        let unused: Vec<String> = function
+            .variables
+            .keys()
+            .filter(|name| !mentioned.contains(name.as_str()))
+            .cloned()
+            .collect();
+        for name in unused {
+            function.variables.remove(&name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod optimize_tests {
+    use expect_test::{expect, Expect};
+
+    use super::{registry, OptLevel};
+    use crate::{interpret::Interpreter, pass::PassManager, tacky::Program, testing::lower};
+
+    fn run(program: &Program) -> String {
+        let mut output = Vec::new();
+        let status = Interpreter::new(program).run(&mut output);
+        format!("{status:?} {:?}", String::from_utf8_lossy(&output))
+    }
+
+    /// The optimized program, which must behave as it did before.
+    fn check_optimize(src: &str, expect: Expect) {
+        let mut program = lower(src);
+        let before = run(&program);
+        let pipeline = registry().parse_pipeline(OptLevel::O2.pipeline()).unwrap();
+        let mut manager = PassManager::new(pipeline);
+        manager.verify_each(true);
+        if let Err(err) = manager.run(&mut program, &mut std::io::sink()) {
+            panic!("{err}");
+        }
+        assert_eq!(run(&program), before, "{program}");
+        expect.assert_eq(&program.to_string());
+    }
+
+    #[test]
+    fn test_straight_line() {
+        check_optimize(
+            r#"
+            int main(void) {
+                int x = 3;
+                int y = x * 4 + 1;
+                unsigned int z = -y;
+                return z / 1000000 == 4294 ? y : 0;
+            }
+            "#,
+            expect![[r#"
+                global function main() -> i32 {
+                    return i32 13
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_loops_and_branches() {
+        check_optimize(
+            r#"
+            int putchar(int c);
+            int main(void) {
+                int total = 0;
+                int debug = 0;
+                for (int i = 0; i < 5; i = i + 1) {
+                    int step = 2;
+                    if (debug)
+                        putchar(33);
+                    total = total + i * step;
+                }
+                putchar(48 + total);
+                return total;
+            }
+            "#,
+            expect![[r#"
+                global function main() -> i32 {
+                    local %.t0: i32
+                    local %.t2: i32
+                    local %.t3: i32
+                    local %.t4: i32
+                    local %.t5: i32
+                    local %.t6: i32
+                    local %i.3: i32
+                    local %total.1: i32
+                    %total.1 = copy i32 0
+                    %i.3 = copy i32 0
+                  start.0:
+                    %.t0 = lt %i.3, i32 5
+                    jz %.t0, break.0
+                    %.t2 = mul %i.3, i32 2
+                    %.t3 = add %total.1, %.t2
+                    %total.1 = copy %.t3
+                    %.t4 = add %i.3, i32 1
+                    %i.3 = copy %.t4
+                    jump start.0
+                  break.0:
+                    %.t5 = add i32 48, %total.1
+                    %.t6 = call putchar(%.t5)
+                    return %total.1
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_memory_is_left_alone() {
+        check_optimize(
+            r#"
+            int counter = 0;
+            void bump(int *p) { *p = *p + 1; counter = counter + 1; }
+            int main(void) {
+                int x = 1;
+                int *p = &x;
+                bump(p);
+                int seen = counter;
+                bump(&x);
+                return x * 10 + seen + counter;
+            }
+            "#,
+            expect![[r#"
+                global static %counter: i32 = { integer 4 0x0 }
+
+                global function bump(%p.0: ptr) -> void {
+                    local %.t0: i32
+                    local %.t1: i32
+                    local %.t2: i32
+                    %.t0 = load %p.0
+                    %.t1 = add %.t0, i32 1
+                    store %.t1, %p.0
+                    %.t2 = add %counter, i32 1
+                    %counter = copy %.t2
+                    return
+                }
+
+                global function main() -> i32 {
+                    local %.t0: ptr
+                    local %.t1: ptr
+                    local %.t2: i32
+                    local %.t3: i32
+                    local %.t4: i32
+                    local %.t0.bump.0: i32
+                    local %.t0.bump.1: i32
+                    local %.t1.bump.0: i32
+                    local %.t1.bump.1: i32
+                    local %.t2.bump.0: i32
+                    local %.t2.bump.1: i32
+                    local %seen.3: i32
+                    local %x.1: i32
+                    %x.1 = copy i32 1
+                    %.t0 = addr %x.1
+                    %.t0.bump.0 = load %.t0
+                    %.t1.bump.0 = add %.t0.bump.0, i32 1
+                    store %.t1.bump.0, %.t0
+                    %.t2.bump.0 = add %counter, i32 1
+                    %counter = copy %.t2.bump.0
+                    %seen.3 = copy %counter
+                    %.t1 = addr %x.1
+                    %.t0.bump.1 = load %.t1
+                    %.t1.bump.1 = add %.t0.bump.1, i32 1
+                    store %.t1.bump.1, %.t1
+                    %.t2.bump.1 = add %counter, i32 1
+                    %counter = copy %.t2.bump.1
+                    %.t2 = mul %x.1, i32 10
+                    %.t3 = add %.t2, %seen.3
+                    %.t4 = add %.t3, %counter
+                    return %.t4
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_inlining() {
+        // `fact` calls itself, so it stays a call, and the static functions
+        // nothing calls any more are gone
+        check_optimize(
+            r#"
+            static inline int square(int x) { return x * x; }
+            static int fact(int n) { return n <= 1 ? 1 : n * fact(n - 1); }
+            static int unused(void) { return 7; }
+            int main(void) { return square(3) + fact(4); }
+            "#,
+            expect![[r#"
+                function fact(%n.1: i32) -> i32 {
+                    local %.t0: i32
+                    local %.t1: i32
+                    local %.t2: i32
+                    local %.t3: i32
+                    local %.t4: i32
+                    %.t0 = le %n.1, i32 1
+                    jz %.t0, else.0
+                    %.t1 = copy i32 1
+                    jump end.0
+                  else.0:
+                    %.t2 = sub %n.1, i32 1
+                    %.t3 = call fact(%.t2)
+                    %.t4 = mul %n.1, %.t3
+                    %.t1 = copy %.t4
+                  end.0:
+                    return %.t1
+                }
+
+                global function main() -> i32 {
+                    local %.t1: i32
+                    local %.t2: i32
+                    %.t1 = call fact(i32 4)
+                    %.t2 = add i32 9, %.t1
+                    return %.t2
+                }
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,309 @@
+//! Copy propagation.
+//!
+//! Where a copy `x = y` reaches a use of `x` along every path, with neither
+//! `x` nor `y` assigned since, the use can read `y` instead; and a copy that
+//! reaches a copy the other way round makes it do nothing. Which copies reach
+//! each block is found by a forward dataflow analysis over the [`Cfg`], whose
+//! meet is intersection.
+//!
+//! Only copies between the function's own unaliased scalars, or of a constant
+//! to one, are propagated, and only when both sides have the same type. Nothing
+//! but an assignment can change such a variable, so a call or a store through
+//! a pointer never kills a copy.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    cfg::{BlockId, Cfg},
+    pass::{Analyses, Pass, Preserved},
+    tacky::{Function, Instruction, Type, Value},
+};
+
+/// The copies a function makes, by index.
+struct Copies {
+    copies: Vec<(String, Value)>,
+    indices: HashMap<(String, Value), usize>,
+    /// The copies each variable is the destination or source of.
+    involving: HashMap<String, Vec<usize>>,
+    tracked: HashSet<String>,
+}
+impl Copies {
+    fn new(function: &Function, cfg: &Cfg) -> Self {
+        let tracked = cfg.unaliased_scalars(&function.variables);
+        let mut copies = Self {
+            copies: Vec::new(),
+            indices: HashMap::new(),
+            involving: HashMap::new(),
+            tracked,
+        };
+        for instruction in cfg.blocks.iter().flat_map(|block| &block.instructions) {
+            if let Instruction::Copy {
+                src,
+                dst: Value::Variable(dst),
+            } = instruction
+            {
+                copies.add(function, dst, src);
+            }
+        }
+        copies
+    }
+
+    fn add(&mut self, function: &Function, dst: &str, src: &Value) {
+        if !self.tracked.contains(dst) || src == &Value::Variable(dst.into()) {
+            return;
+        }
+        let ty = function.variables[dst];
+        let src_type: Option<Type> = match src {
+            Value::Constant(constant) => Some(constant.ty),
+            Value::Variable(name) if self.tracked.contains(name) => {
+                function.variables.get(name).copied()
+            }
+            Value::Variable(_) => None,
+        };
+        if src_type != Some(ty) {
+            return;
+        }
+        let key = (dst.to_string(), src.clone());
+        if self.indices.contains_key(&key) {
+            return;
+        }
+        let index = self.copies.len();
+        self.indices.insert(key, index);
+        self.involving.entry(dst.into()).or_default().push(index);
+        if let Value::Variable(name) = src {
+            self.involving.entry(name.clone()).or_default().push(index);
+        }
+        self.copies.push((dst.to_string(), src.clone()));
+    }
+
+    fn index(&self, dst: &Value, src: &Value) -> Option<usize> {
+        let Value::Variable(dst) = dst else {
+            return None;
+        };
+        self.indices.get(&(dst.clone(), src.clone())).copied()
+    }
+
+    /// The copy of some value to `name` in `reaching`.
+    fn reaching_source(&self, reaching: &[bool], name: &str) -> Option<&Value> {
+        self.involving
+            .get(name)?
+            .iter()
+            .find(|&&index| reaching[index] && self.copies[index].0 == name)
+            .map(|&index| &self.copies[index].1)
+    }
+
+    /// Update the copies that reach an instruction to those that reach past it.
+    fn transfer(&self, instruction: &Instruction, reaching: &mut [bool]) {
+        if self.is_redundant(instruction, reaching) {
+            return;
+        }
+        if let Instruction::Copy { src, dst } = instruction {
+            if let Some(index) = self.index(dst, src) {
+                self.kill(reaching, dst);
+                reaching[index] = true;
+                return;
+            }
+        }
+        if let Some(dst) = instruction.dst() {
+            self.kill(reaching, dst);
+        }
+    }
+
+    /// Whether an instruction is a copy between two values the copies that
+    /// reach it already make the same.
+    fn is_redundant(&self, instruction: &Instruction, reaching: &[bool]) -> bool {
+        let Instruction::Copy { src, dst } = instruction else {
+            return false;
+        };
+        let reaches = |index: Option<usize>| index.is_some_and(|index| reaching[index]);
+        src == dst || reaches(self.index(dst, src)) || reaches(self.index(src, dst))
+    }
+
+    /// Rewrite the variables an instruction reads to the values copied to them.
+    fn rewrite(&self, instruction: &mut Instruction, reaching: &[bool]) {
+        for src in instruction.sources_mut() {
+            let Value::Variable(name) = src else {
+                continue;
+            };
+            if let Some(value) = self.reaching_source(reaching, name) {
+                *src = value.clone();
+            }
+        }
+    }
+
+    /// Forget every copy to or from a value that is assigned.
+    fn kill(&self, reaching: &mut [bool], assigned: &Value) {
+        let Value::Variable(name) = assigned else {
+            return;
+        };
+        for &index in self.involving.get(name).into_iter().flatten() {
+            reaching[index] = false;
+        }
+    }
+}
+
+/// [`propagate_copies`] as a pass.
+pub struct PropagateCopies;
+impl Pass for PropagateCopies {
+    fn name(&self) -> &'static str {
+        "copyprop"
+    }
+
+    fn preserves(&self) -> Preserved {
+        Preserved::ControlFlow
+    }
+
+    fn run(&self, function: &mut Function, analyses: &mut Analyses) {
+        propagate_copies(function, &analyses.get::<Cfg>(function));
+    }
+}
+
+/// Propagate the copies of a function whose graph is `cfg`.
+pub fn propagate_copies(function: &mut Function, cfg: &Cfg) {
+    let copies = Copies::new(function, cfg);
+    let order = cfg.reverse_postorder();
+
+    // The copies that reach the end of each block, starting from all of them,
+    // since the meet is intersection, except at the entry
+    let mut outs: Vec<Vec<bool>> = vec![vec![true; copies.copies.len()]; cfg.blocks.len()];
+    outs[Cfg::ENTRY].fill(false);
+    let reaching_in = |outs: &[Vec<bool>], block: BlockId| {
+        let mut reaching = vec![!cfg.blocks[block].predecessors.is_empty(); copies.copies.len()];
+        for &predecessor in &cfg.blocks[block].predecessors {
+            for (reaches, out) in reaching.iter_mut().zip(&outs[predecessor]) {
+                *reaches &= out;
+            }
+        }
+        reaching
+    };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &block in &order {
+            if block == Cfg::ENTRY {
+                continue;
+            }
+            let mut reaching = reaching_in(&outs, block);
+            for instruction in &cfg.blocks[block].instructions {
+                copies.transfer(instruction, &mut reaching);
+            }
+            if reaching != outs[block] {
+                outs[block] = reaching;
+                changed = true;
+            }
+        }
+    }
+
+    let mut body = Vec::new();
+    for block in cfg.body() {
+        let mut reaching = reaching_in(&outs, block);
+        for original in &cfg.blocks[block].instructions {
+            if copies.is_redundant(original, &reaching) {
+                continue;
+            }
+            let mut instruction = original.clone();
+            copies.rewrite(&mut instruction, &reaching);
+            copies.transfer(original, &mut reaching);
+            body.push(instruction);
+        }
+    }
+    function.body = body;
+}
+
+#[cfg(test)]
+mod copy_propagation_tests {
+    use expect_test::{expect, Expect};
+
+    use super::propagate_copies;
+    use crate::{cfg::Cfg, text::parse_program};
+
+    fn check_propagation(src: &str, expect: Expect) {
+        let mut program = parse_program(src).unwrap();
+        let function = &mut program.functions[0];
+        let cfg = Cfg::new(function.body.clone());
+        propagate_copies(function, &cfg);
+        expect.assert_eq(&program.to_string());
+    }
+
+    #[test]
+    fn test_propagation() {
+        check_propagation(
+            r#"
+global function f(%n: i32) -> i32 {
+    local %x: i32
+    local %y: i32
+    local %u: u32
+    local %p: ptr
+    %x = copy i32 3
+    %y = copy %x
+    %u = copy %x
+    %n = add %n, %y
+    %x = copy %y
+    jz %n, else.0
+    %y = copy i32 4
+    %x = add %y, %x
+  else.0:
+    %p = addr %n
+    call g(%p)
+    return %y
+}
+"#,
+            expect![[r#"
+                global function f(%n: i32) -> i32 {
+                    local %p: ptr
+                    local %u: u32
+                    local %x: i32
+                    local %y: i32
+                    %x = copy i32 3
+                    %y = copy i32 3
+                    %u = copy i32 3
+                    %n = add %n, %x
+                    jz %n, else.0
+                    %y = copy i32 4
+                    %x = add i32 4, i32 3
+                  else.0:
+                    %p = addr %n
+                    call g(%p)
+                    return %y
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_loop() {
+        // The copy of `i` doesn't reach the loop's condition along the back edge
+        check_propagation(
+            r#"
+global function f() -> i32 {
+    local %i: i32
+    local %j: i32
+    %i = copy i32 0
+    %j = copy i32 7
+  start.0:
+    jz %i, end.0
+    %i = sub %i, i32 1
+    jump start.0
+  end.0:
+    %i = add %i, %j
+    return %i
+}
+"#,
+            expect![[r#"
+                global function f() -> i32 {
+                    local %i: i32
+                    local %j: i32
+                    %i = copy i32 0
+                    %j = copy i32 7
+                  start.0:
+                    jz %i, end.0
+                    %i = sub %i, i32 1
+                    jump start.0
+                  end.0:
+                    %i = add %i, i32 7
+                    return %i
+                }
+            "#]],
+        );
+    }
+}
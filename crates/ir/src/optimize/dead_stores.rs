@@ -0,0 +1,160 @@
+//! Dead store elimination.
+//!
+//! An instruction that only assigns a variable that nothing reads before it is
+//! assigned again does nothing worth keeping. Which variables are live at the
+//! end of each block is found by a backward dataflow analysis over the
+//! [`Cfg`], whose meet is union.
+//!
+//! Only the function's own unaliased scalars are considered, since anything
+//! else could be read through a pointer or by another function, and calls
+//! are kept even when their result is dead.
+
+use std::collections::HashSet;
+
+use crate::{
+    cfg::{BlockId, Cfg},
+    pass::{Analyses, Pass, Preserved},
+    tacky::{Function, Instruction, Value},
+};
+
+/// [`eliminate_dead_stores`] as a pass.
+pub struct EliminateDeadStores;
+impl Pass for EliminateDeadStores {
+    fn name(&self) -> &'static str {
+        "dce"
+    }
+
+    fn preserves(&self) -> Preserved {
+        Preserved::ControlFlow
+    }
+
+    fn run(&self, function: &mut Function, analyses: &mut Analyses) {
+        eliminate_dead_stores(function, &analyses.get::<Cfg>(function));
+    }
+}
+
+/// Remove the dead stores of a function whose graph is `cfg`.
+pub fn eliminate_dead_stores(function: &mut Function, cfg: &Cfg) {
+    let tracked = cfg.unaliased_scalars(&function.variables);
+    let mut order = cfg.reverse_postorder();
+    order.reverse();
+
+    // Update what is live before an instruction from what is live after it
+    let transfer = |instruction: &Instruction, live: &mut HashSet<String>| {
+        if let Some(Value::Variable(dst)) = instruction.dst() {
+            live.remove(dst);
+        }
+        for src in instruction.sources() {
+            if let Value::Variable(name) = src {
+                if tracked.contains(name) {
+                    live.insert(name.clone());
+                }
+            }
+        }
+    };
+    let live_out = |ins: &[HashSet<String>], block: BlockId| {
+        let mut live = HashSet::new();
+        for &successor in &cfg.blocks[block].successors {
+            live.extend(ins[successor].iter().cloned());
+        }
+        live
+    };
+    let mut ins: Vec<HashSet<String>> = vec![HashSet::new(); cfg.blocks.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &block in &order {
+            let mut live = live_out(&ins, block);
+            for instruction in cfg.blocks[block].instructions.iter().rev() {
+                transfer(instruction, &mut live);
+            }
+            if live != ins[block] {
+                ins[block] = live;
+                changed = true;
+            }
+        }
+    }
+
+    let is_dead = |instruction: &Instruction, live: &HashSet<String>| match instruction.dst() {
+        Some(Value::Variable(dst)) => {
+            tracked.contains(dst)
+                && !live.contains(dst)
+                && !matches!(instruction, Instruction::FunCall { .. })
+        }
+        _ => false,
+    };
+    let mut body = Vec::new();
+    for block in cfg.body() {
+        let mut live = live_out(&ins, block);
+        let start = body.len();
+        for instruction in cfg.blocks[block].instructions.iter().rev() {
+            if !is_dead(instruction, &live) {
+                transfer(instruction, &mut live);
+                body.push(instruction.clone());
+            }
+        }
+        body[start..].reverse();
+    }
+    function.body = body;
+}
+
+#[cfg(test)]
+mod dead_stores_tests {
+    use expect_test::{expect, Expect};
+
+    use super::eliminate_dead_stores;
+    use crate::{cfg::Cfg, text::parse_program};
+
+    fn check_dead_stores(src: &str, expect: Expect) {
+        let mut program = parse_program(src).unwrap();
+        let function = &mut program.functions[0];
+        let cfg = Cfg::new(function.body.clone());
+        eliminate_dead_stores(function, &cfg);
+        expect.assert_eq(&program.to_string());
+    }
+
+    #[test]
+    fn test_dead_stores() {
+        check_dead_stores(
+            r#"
+global function f(%n: i32) -> i32 {
+    local %x: i32
+    local %y: i32
+    local %z: i32
+    local %p: ptr
+    %x = copy i32 1
+    %y = mul %n, i32 2
+    %z = call g()
+    %x = copy %n
+    %p = addr %z
+    store i32 5, %p
+  start.0:
+    jz %x, end.0
+    %y = copy %x
+    %x = sub %x, i32 1
+    jump start.0
+  end.0:
+    return %x
+}
+"#,
+            expect![[r#"
+                global function f(%n: i32) -> i32 {
+                    local %p: ptr
+                    local %x: i32
+                    local %y: i32
+                    local %z: i32
+                    %z = call g()
+                    %x = copy %n
+                    %p = addr %z
+                    store i32 5, %p
+                  start.0:
+                    jz %x, end.0
+                    %x = sub %x, i32 1
+                    jump start.0
+                  end.0:
+                    return %x
+                }
+            "#]],
+        );
+    }
+}
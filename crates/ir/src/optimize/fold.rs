@@ -0,0 +1,154 @@
+//! Constant folding.
+//!
+//! An instruction whose operands are all constants becomes a copy of its
+//! result, worked out by [`eval`](crate::eval) just as the program would work
+//! it out, and a conditional jump on a constant becomes a jump or goes away.
+//! An operation that is undefined for its operands, like a division by zero,
+//! is left for the program to do.
+
+use crate::{
+    eval::{self, Conversion},
+    pass::{Analyses, Pass},
+    tacky::{Constant, Function, Instruction, Value},
+};
+
+/// [`fold_constants`] as a pass.
+pub struct FoldConstants;
+impl Pass for FoldConstants {
+    fn name(&self) -> &'static str {
+        "fold"
+    }
+
+    fn run(&self, function: &mut Function, _: &mut Analyses) {
+        fold_constants(function);
+    }
+}
+
+pub fn fold_constants(function: &mut Function) {
+    let body = std::mem::take(&mut function.body);
+    function.body = body
+        .into_iter()
+        .filter_map(|instruction| fold(instruction, function))
+        .collect();
+}
+
+/// The instruction, folded if it can be, or `None` if it does nothing.
+fn fold(instruction: Instruction, function: &Function) -> Option<Instruction> {
+    let constant = |value: &Value| match value {
+        Value::Constant(constant) => Some(*constant),
+        Value::Variable(_) => None,
+    };
+    // The type of a variable of the function, which a static object isn't
+    let variable_type = |value: &Value| match value {
+        Value::Variable(name) => function.variables.get(name).copied(),
+        Value::Constant(_) => None,
+    };
+    let copy = |value: Constant, dst: &Value| {
+        Some(Instruction::Copy {
+            src: Value::Constant(value),
+            dst: dst.clone(),
+        })
+    };
+
+    if let Some((conversion, src, dst)) = Conversion::of(&instruction) {
+        if let (Some(src), Some(ty)) = (constant(src), variable_type(dst)) {
+            return copy(eval::convert(conversion, src, ty), dst);
+        }
+        return Some(instruction);
+    }
+    match &instruction {
+        Instruction::Unary { op, src, dst } => match constant(src) {
+            Some(src) => copy(eval::unary(*op, src), dst),
+            None => Some(instruction),
+        },
+        Instruction::Binary { op, lhs, rhs, dst } => match (constant(lhs), constant(rhs)) {
+            (Some(lhs), Some(rhs)) => match eval::binary(*op, lhs, rhs) {
+                Ok(value) => copy(value, dst),
+                Err(_) => Some(instruction),
+            },
+            _ => Some(instruction),
+        },
+        // A copy that reinterprets a constant, as from signed to unsigned, gives
+        // the constant the type of its destination
+        Instruction::Copy { src, dst } => match (constant(src), variable_type(dst)) {
+            (Some(src), Some(ty)) if src.ty != ty && src.ty.is_integer() && ty.is_integer() => {
+                copy(Constant { ty, bits: src.bits }, dst)
+            }
+            _ => Some(instruction),
+        },
+        Instruction::JumpIfZero(condition, label) => match constant(condition) {
+            Some(condition) if eval::is_zero(condition) => Some(Instruction::Jump(label.clone())),
+            Some(_) => None,
+            None => Some(instruction),
+        },
+        Instruction::JumpIfNotZero(condition, label) => match constant(condition) {
+            Some(condition) if eval::is_zero(condition) => None,
+            Some(_) => Some(Instruction::Jump(label.clone())),
+            None => Some(instruction),
+        },
+        _ => Some(instruction),
+    }
+}
+
+#[cfg(test)]
+mod fold_tests {
+    use expect_test::{expect, Expect};
+
+    use super::fold_constants;
+    use crate::text::parse_program;
+
+    fn check_fold(src: &str, expect: Expect) {
+        let mut program = parse_program(src).unwrap();
+        fold_constants(&mut program.functions[0]);
+        expect.assert_eq(&program.to_string());
+    }
+
+    #[test]
+    fn test_fold() {
+        check_fold(
+            r#"
+global function f() -> i32 {
+    local %a: i32
+    local %b: u32
+    local %c: f32
+    local %d: i8
+    %a = add i32 2147483647, i32 1
+    %b = div u32 4294967295, u32 2
+    %b = copy i32 -1
+    %c = itof u64 18446744073709551615
+    %d = trunc i32 300
+    %a = lnot f64 0.0
+    %a = div i32 1, i32 0
+    %a = shl i32 1, i32 32
+    jz i32 0, end.0
+    jnz i32 0, end.0
+    jz f64 -0.0, end.0
+    jnz i32 7, end.0
+  end.0:
+    return %a
+}
+"#,
+            expect![[r#"
+                global function f() -> i32 {
+                    local %a: i32
+                    local %b: u32
+                    local %c: f32
+                    local %d: i8
+                    %a = copy i32 -2147483648
+                    %b = copy u32 2147483647
+                    %b = copy u32 4294967295
+                    %c = copy f32 1.8446744073709552e19
+                    %d = copy i8 44
+                    %a = copy i32 1
+                    %a = div i32 1, i32 0
+                    %a = shl i32 1, i32 32
+                    jump end.0
+                    jump end.0
+                    jump end.0
+                  end.0:
+                    return %a
+                }
+            "#]],
+        );
+    }
+}
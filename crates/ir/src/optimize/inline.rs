@@ -0,0 +1,664 @@
+//! Inlining, and deleting the functions nothing refers to any more.
+//!
+//! [`InlineFunctions`] replaces a call to a function the program defines with
+//! a copy of its body: the arguments are copied to its parameters, and each
+//! return copies the value to where the call put its result and jumps past the
+//! rest of the copy. The callee's variables and labels get new names in the
+//! caller, so that any number of copies can go in one function.
+//!
+//! A call is inlined if the callee is small enough, with more room for one
+//! defined `inline`, or if it is the only call to a function that can't be
+//! called any other way, which makes the program smaller once
+//! [`PruneFunctions`] deletes the function. A recursive function is never
+//! inlined, since there would be no end to it. Functions are visited callees
+//! first, by the components of the [`CallGraph`], so a callee's own calls have
+//! been inlined before it is, and a small function that calls another small
+//! function can become one big one that isn't inlined any further.
+//!
+//! Inlining leaves a lot for the other optimizations to clean up: copies of
+//! the arguments to propagate, and jumps to the labels that come next.
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::{
+    call_graph::{CallGraph, FunctionId},
+    pass::ProgramPass,
+    tacky::{Callee, Function, Instruction, Program, Value},
+};
+
+/// Inlines the calls to small functions, and to functions called only once.
+pub struct InlineFunctions {
+    name: &'static str,
+    /// The most instructions a function can have and still be inlined.
+    max_size: usize,
+    /// The same for functions defined `inline`.
+    max_inline_size: usize,
+}
+impl InlineFunctions {
+    /// For speed, where a call is worth saving even if the code gets a bit bigger.
+    pub const FOR_SPEED: Self = Self {
+        name: "inline",
+        max_size: 16,
+        max_inline_size: 64,
+    };
+    /// For size, where only functions about as small as the calls to them are
+    /// inlined.
+    pub const FOR_SIZE: Self = Self {
+        name: "inline-small",
+        max_size: 4,
+        max_inline_size: 8,
+    };
+
+    /// Whether to inline a call in `caller` to `callee` that passes `args`
+    /// and puts the result in `dst`.
+    fn should_inline(
+        &self,
+        graph: &CallGraph,
+        (id, callee): (FunctionId, &Function),
+        args: &[Value],
+        dst: Option<&Value>,
+    ) -> bool {
+        // A call through a declaration without a prototype can pass the wrong
+        // number of arguments, which only matters if it is ever made
+        let fits =
+            args.len() == callee.params.len() && (dst.is_none() || callee.return_type.is_some());
+        if !fits || graph.is_recursive(id) {
+            return false;
+        }
+        let only_way_in =
+            !callee.global && !graph.is_address_taken(id) && graph.call_sites(id) == 1;
+        let max_size = match callee.inline {
+            true => self.max_inline_size,
+            false => self.max_size,
+        };
+        only_way_in || size(callee) <= max_size
+    }
+}
+impl ProgramPass for InlineFunctions {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn run(&self, program: &mut Program) {
+        let graph = CallGraph::new(program);
+        let mut globals: HashSet<String> = program.statics.iter().map(|s| s.name.clone()).collect();
+        globals.extend(graph.names.iter().cloned());
+        for component in graph.components() {
+            for &caller in component {
+                let mut function = program.functions[caller].clone();
+                let mut inliner = Inliner::new(&function, &globals);
+                let mut body = Vec::new();
+                for instruction in std::mem::take(&mut function.body) {
+                    if let Instruction::FunCall {
+                        callee: Callee::Direct(name),
+                        args,
+                        dst,
+                    } = &instruction
+                    {
+                        let inlined = graph.id(name).and_then(|id| {
+                            let callee = &program.functions[id];
+                            let inline =
+                                self.should_inline(&graph, (id, callee), args, dst.as_ref());
+                            inline.then_some(callee)
+                        });
+                        if let Some(callee) = inlined {
+                            inliner.inline(&mut function, &mut body, callee, args, dst.as_ref());
+                            continue;
+                        }
+                    }
+                    body.push(instruction);
+                }
+                if inliner.count > 0 {
+                    function.body = body;
+                    program.functions[caller] = function;
+                }
+            }
+        }
+    }
+}
+
+/// How big a function is, as the number of instructions that aren't labels.
+fn size(function: &Function) -> usize {
+    function
+        .body
+        .iter()
+        .filter(|instruction| !matches!(instruction, Instruction::Label(_)))
+        .count()
+}
+
+/// Puts copies of functions into one function.
+struct Inliner<'a> {
+    /// The names of the static objects and functions, which the callee's
+    /// variables mustn't be renamed to.
+    globals: &'a HashSet<String>,
+    labels: HashSet<String>,
+    /// The number for the names of the next copy, which is more than zero once
+    /// any call has been inlined.
+    count: usize,
+}
+impl<'a> Inliner<'a> {
+    fn new(function: &Function, globals: &'a HashSet<String>) -> Self {
+        let labels = function
+            .body
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::Label(label) => Some(label.clone()),
+                _ => None,
+            })
+            .collect();
+        Self {
+            globals,
+            labels,
+            count: 0,
+        }
+    }
+
+    /// Push a copy of `callee` onto `body`, for a call that passes `args` and
+    /// puts the result in `dst`, declaring its variables in `function`.
+    fn inline(
+        &mut self,
+        function: &mut Function,
+        body: &mut Vec<Instruction>,
+        callee: &Function,
+        args: &[Value],
+        dst: Option<&Value>,
+    ) {
+        let suffix = self.fresh_suffix(function, callee);
+        let rename = |name: &mut String| {
+            if callee.variables.contains_key(name.as_str()) {
+                name.push_str(&suffix);
+            }
+        };
+        for (name, &ty) in &callee.variables {
+            function.variables.insert(format!("{name}{suffix}"), ty);
+        }
+        for (param, arg) in callee.params.iter().zip(args) {
+            body.push(Instruction::Copy {
+                src: arg.clone(),
+                dst: Value::Variable(format!("{param}{suffix}")),
+            });
+        }
+        let end = format!("return{suffix}");
+        for instruction in &callee.body {
+            let mut instruction = instruction.clone();
+            for value in instruction.sources_mut() {
+                if let Value::Variable(name) = value {
+                    rename(name);
+                }
+            }
+            if let Some(Value::Variable(name)) = instruction.dst_mut() {
+                rename(name);
+            }
+            match &mut instruction {
+                Instruction::GetAddress { src: name, .. }
+                | Instruction::CopyToOffset { dst: name, .. }
+                | Instruction::CopyFromOffset { src: name, .. } => rename(name),
+                Instruction::Jump(label)
+                | Instruction::JumpIfZero(_, label)
+                | Instruction::JumpIfNotZero(_, label)
+                | Instruction::Label(label) => label.push_str(&suffix),
+                Instruction::Return(value) => {
+                    if let (Some(src), Some(dst)) = (value.take(), dst) {
+                        body.push(Instruction::Copy {
+                            src,
+                            dst: dst.clone(),
+                        });
+                    }
+                    body.push(Instruction::Jump(end.clone()));
+                    continue;
+                }
+                _ => {}
+            }
+            body.push(instruction);
+        }
+        body.push(Instruction::Label(end.clone()));
+        self.labels.extend(
+            callee
+                .body
+                .iter()
+                .filter_map(|instruction| match instruction {
+                    Instruction::Label(label) => Some(format!("{label}{suffix}")),
+                    _ => None,
+                }),
+        );
+        self.labels.insert(end);
+        self.count += 1;
+    }
+
+    /// A suffix for the names of a copy of `callee`, which makes none of them
+    /// clash with a name `function` already has.
+    fn fresh_suffix(&mut self, function: &Function, callee: &Function) -> String {
+        let labels = callee
+            .body
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::Label(label) => Some(label),
+                _ => None,
+            });
+        let names: Vec<&String> = callee.variables.keys().chain(labels).collect();
+        loop {
+            let suffix = format!(".{}.{}", callee.name, self.count);
+            let clashes = |name: &String| {
+                let name = format!("{name}{suffix}");
+                function.variables.contains_key(&name)
+                    || self.globals.contains(&name)
+                    || self.labels.contains(&name)
+            };
+            if !names.iter().copied().any(clashes)
+                && !self.labels.contains(&format!("return{suffix}"))
+            {
+                return suffix;
+            }
+            self.count += 1;
+        }
+    }
+}
+
+/// Deletes the functions that aren't visible to other translation units, and
+/// that nothing they are could call them from refers to.
+pub struct PruneFunctions;
+impl ProgramPass for PruneFunctions {
+    fn name(&self) -> &'static str {
+        "prune-functions"
+    }
+
+    fn run(&self, program: &mut Program) {
+        let graph = CallGraph::new(program);
+        let mut live = vec![false; graph.names.len()];
+        let mut queue: VecDeque<FunctionId> = program
+            .functions
+            .iter()
+            .enumerate()
+            .filter(|&(id, function)| function.global || graph.is_address_in_statics(id))
+            .map(|(id, _)| id)
+            .collect();
+        while let Some(function) = queue.pop_front() {
+            if !std::mem::replace(&mut live[function], true) {
+                queue.extend(graph.references(function));
+            }
+        }
+        let mut live = live.into_iter();
+        program.functions.retain(|_| live.next().unwrap());
+    }
+}
+
+#[cfg(test)]
+mod inline_tests {
+    use expect_test::{expect, Expect};
+
+    use super::{InlineFunctions, PruneFunctions};
+    use crate::{pass::ProgramPass, text::parse_program, verify::verify_program};
+
+    fn check_pass(pass: &dyn ProgramPass, src: &str, expect: Expect) {
+        let mut program = parse_program(src).unwrap();
+        pass.run(&mut program);
+        if let Err(err) = verify_program(&program) {
+            panic!("{err}\n{program}");
+        }
+        expect.assert_eq(&program.to_string());
+    }
+
+    #[test]
+    fn test_inline() {
+        // `square` goes into `cube` first, and then `cube` is too big for `main`
+        check_pass(
+            &InlineFunctions::FOR_SPEED,
+            r#"
+global function main() -> i32 {
+    local %.t0: i32
+    %.t0 = call cube(i32 3)
+    %.t0 = call clamp(%.t0)
+    return %.t0
+}
+
+function square(%x: i32) -> i32 {
+    local %.t0: i32
+    %.t0 = mul %x, %x
+    return %.t0
+}
+
+global function cube(%x: i32) -> i32 {
+    local %.t0: i32
+    local %.t1: i32
+    %.t0 = call square(%x)
+    %.t1 = mul %.t0, %x
+    %.t1 = add %.t1, i32 1
+    %.t1 = sub %.t1, i32 1
+    %.t1 = add %.t1, i32 1
+    %.t1 = sub %.t1, i32 1
+    %.t1 = add %.t1, i32 1
+    %.t1 = sub %.t1, i32 1
+    %.t1 = add %.t1, i32 1
+    %.t1 = sub %.t1, i32 1
+    %.t1 = add %.t1, i32 1
+    %.t1 = sub %.t1, i32 1
+    %.t1 = add %.t1, i32 1
+    %.t1 = sub %.t1, i32 1
+    return %.t1
+}
+
+function clamp(%x: i32) -> i32 {
+    local %.t0: i32
+    %.t0 = gt %x, i32 10
+    jz %.t0, small.0
+    return i32 10
+  small.0:
+    return %x
+}
+"#,
+            expect![[r#"
+                global function main() -> i32 {
+                    local %.t0: i32
+                    local %.t0.clamp.0: i32
+                    local %x.clamp.0: i32
+                    %.t0 = call cube(i32 3)
+                    %x.clamp.0 = copy %.t0
+                    %.t0.clamp.0 = gt %x.clamp.0, i32 10
+                    jz %.t0.clamp.0, small.0.clamp.0
+                    %.t0 = copy i32 10
+                    jump return.clamp.0
+                  small.0.clamp.0:
+                    %.t0 = copy %x.clamp.0
+                    jump return.clamp.0
+                  return.clamp.0:
+                    return %.t0
+                }
+
+                function square(%x: i32) -> i32 {
+                    local %.t0: i32
+                    %.t0 = mul %x, %x
+                    return %.t0
+                }
+
+                global function cube(%x: i32) -> i32 {
+                    local %.t0: i32
+                    local %.t1: i32
+                    local %.t0.square.0: i32
+                    local %x.square.0: i32
+                    %x.square.0 = copy %x
+                    %.t0.square.0 = mul %x.square.0, %x.square.0
+                    %.t0 = copy %.t0.square.0
+                    jump return.square.0
+                  return.square.0:
+                    %.t1 = mul %.t0, %x
+                    %.t1 = add %.t1, i32 1
+                    %.t1 = sub %.t1, i32 1
+                    %.t1 = add %.t1, i32 1
+                    %.t1 = sub %.t1, i32 1
+                    %.t1 = add %.t1, i32 1
+                    %.t1 = sub %.t1, i32 1
+                    %.t1 = add %.t1, i32 1
+                    %.t1 = sub %.t1, i32 1
+                    %.t1 = add %.t1, i32 1
+                    %.t1 = sub %.t1, i32 1
+                    %.t1 = add %.t1, i32 1
+                    %.t1 = sub %.t1, i32 1
+                    return %.t1
+                }
+
+                function clamp(%x: i32) -> i32 {
+                    local %.t0: i32
+                    %.t0 = gt %x, i32 10
+                    jz %.t0, small.0
+                    return i32 10
+                  small.0:
+                    return %x
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_inline_keyword_and_recursion() {
+        // Only `big` is small enough once it is `inline`, and `count` calls itself
+        let mut big = String::new();
+        for _ in 0..20 {
+            big.push_str("    %x = add %x, i32 1\n");
+        }
+        check_pass(
+            &InlineFunctions::FOR_SPEED,
+            &format!(
+                r#"
+global function main() -> i32 {{
+    local %.t0: i32
+    %.t0 = call big(i32 0)
+    %.t0 = call plain(%.t0)
+    %.t0 = call count(%.t0)
+    call count(i32 1)
+    return %.t0
+}}
+
+global inline function big(%x: i32) -> i32 {{
+{big}    return %x
+}}
+
+global function plain(%x: i32) -> i32 {{
+{big}    return %x
+}}
+
+global function count(%n: i32) -> i32 {{
+    local %.t0: i32
+    jz %n, done.0
+    %n = sub %n, i32 1
+    %.t0 = call count(%n)
+    return %.t0
+  done.0:
+    return i32 0
+}}
+"#
+            ),
+            expect![[r#"
+                global function main() -> i32 {
+                    local %.t0: i32
+                    local %x.big.0: i32
+                    %x.big.0 = copy i32 0
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %x.big.0 = add %x.big.0, i32 1
+                    %.t0 = copy %x.big.0
+                    jump return.big.0
+                  return.big.0:
+                    %.t0 = call plain(%.t0)
+                    %.t0 = call count(%.t0)
+                    call count(i32 1)
+                    return %.t0
+                }
+
+                global inline function big(%x: i32) -> i32 {
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    return %x
+                }
+
+                global function plain(%x: i32) -> i32 {
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    %x = add %x, i32 1
+                    return %x
+                }
+
+                global function count(%n: i32) -> i32 {
+                    local %.t0: i32
+                    jz %n, done.0
+                    %n = sub %n, i32 1
+                    %.t0 = call count(%n)
+                    return %.t0
+                  done.0:
+                    return i32 0
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_names() {
+        // Each copy gets names of its own, which don't clash with the caller's
+        check_pass(
+            &InlineFunctions::FOR_SIZE,
+            r#"
+global function main() -> i32 {
+    local %x.square.0: i32
+    local %.t0: i32
+    %x.square.0 = copy i32 2
+    %.t0 = call square(%x.square.0)
+    %.t0 = call square(%.t0)
+    call nothing()
+    return %.t0
+}
+
+function square(%x: i32) -> i32 {
+    %x = mul %x, %x
+    return %x
+}
+
+function nothing() -> void {
+    return
+}
+"#,
+            expect![[r#"
+                global function main() -> i32 {
+                    local %.t0: i32
+                    local %x.square.0: i32
+                    local %x.square.1: i32
+                    local %x.square.2: i32
+                    %x.square.0 = copy i32 2
+                    %x.square.1 = copy %x.square.0
+                    %x.square.1 = mul %x.square.1, %x.square.1
+                    %.t0 = copy %x.square.1
+                    jump return.square.1
+                  return.square.1:
+                    %x.square.2 = copy %.t0
+                    %x.square.2 = mul %x.square.2, %x.square.2
+                    %.t0 = copy %x.square.2
+                    jump return.square.2
+                  return.square.2:
+                    jump return.nothing.3
+                  return.nothing.3:
+                    return %.t0
+                }
+
+                function square(%x: i32) -> i32 {
+                    %x = mul %x, %x
+                    return %x
+                }
+
+                function nothing() -> void {
+                    return
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_prune_functions() {
+        check_pass(
+            &PruneFunctions,
+            r#"
+static %table: ptr = { address %in_table +0 }
+
+global function main() -> i32 {
+    local %p: ptr
+    call used()
+    %p = addr %taken
+    return i32 0
+}
+
+function used() -> void {
+    return
+}
+
+function taken() -> void {
+    return
+}
+
+function in_table() -> void {
+    return
+}
+
+function ping() -> void {
+    call pong()
+    return
+}
+
+function pong() -> void {
+    call ping()
+    return
+}
+"#,
+            expect![[r#"
+                static %table: ptr = { address %in_table +0 }
+
+                global function main() -> i32 {
+                    local %p: ptr
+                    call used()
+                    %p = addr %taken
+                    return i32 0
+                }
+
+                function used() -> void {
+                    return
+                }
+
+                function taken() -> void {
+                    return
+                }
+
+                function in_table() -> void {
+                    return
+                }
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Unreachable code elimination.
+//!
+//! Blocks that can't be reached from the start of the function go, and then so
+//! do jumps to the block right after them, and labels nothing jumps to.
+
+use std::collections::HashSet;
+
+use crate::{
+    cfg::Cfg,
+    pass::{Analyses, Pass},
+    tacky::{Function, Instruction},
+};
+
+/// [`eliminate_unreachable_code`] as a pass.
+pub struct EliminateUnreachableCode;
+impl Pass for EliminateUnreachableCode {
+    fn name(&self) -> &'static str {
+        "unreachable"
+    }
+
+    fn run(&self, function: &mut Function, analyses: &mut Analyses) {
+        eliminate_unreachable_code(function, &analyses.get::<Cfg>(function));
+    }
+}
+
+/// Remove the unreachable code of a function whose graph is `cfg`.
+pub fn eliminate_unreachable_code(function: &mut Function, cfg: &Cfg) {
+    let reachable: HashSet<_> = cfg.reverse_postorder().into_iter().collect();
+    let mut blocks: Vec<Vec<Instruction>> = cfg
+        .body()
+        .filter(|block| reachable.contains(block))
+        .map(|block| cfg.blocks[block].instructions.clone())
+        .collect();
+
+    for i in 0..blocks.len() {
+        let next_label = match blocks.get(i + 1).and_then(|block| block.first()) {
+            Some(Instruction::Label(label)) => Some(label.clone()),
+            _ => None,
+        };
+        let target = blocks[i].last().and_then(Instruction::target);
+        if target.is_some() && target == next_label.as_deref() {
+            blocks[i].pop();
+        }
+    }
+
+    let targets: HashSet<String> = blocks
+        .iter()
+        .flatten()
+        .filter_map(|instruction| Some(instruction.target()?.to_string()))
+        .collect();
+    function.body = blocks
+        .into_iter()
+        .flatten()
+        .filter(|instruction| match instruction {
+            Instruction::Label(label) => targets.contains(label),
+            _ => true,
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod unreachable_tests {
+    use expect_test::{expect, Expect};
+
+    use super::eliminate_unreachable_code;
+    use crate::{cfg::Cfg, text::parse_program};
+
+    fn check_unreachable(src: &str, expect: Expect) {
+        let mut program = parse_program(src).unwrap();
+        let function = &mut program.functions[0];
+        let cfg = Cfg::new(function.body.clone());
+        eliminate_unreachable_code(function, &cfg);
+        expect.assert_eq(&program.to_string());
+    }
+
+    #[test]
+    fn test_unreachable() {
+        check_unreachable(
+            r#"
+global function f(%n: i32) -> i32 {
+    jump else.0
+    %n = copy i32 1
+    jump end.0
+  else.0:
+    %n = add %n, i32 2
+  end.0:
+    jz %n, done.1
+  done.1:
+  loop.2:
+    %n = sub %n, i32 1
+    jnz %n, loop.2
+    return %n
+    jump loop.2
+}
+"#,
+            expect![[r#"
+                global function f(%n: i32) -> i32 {
+                    %n = add %n, i32 2
+                  loop.2:
+                    %n = sub %n, i32 1
+                    jnz %n, loop.2
+                    return %n
+                }
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,785 @@
+//! The pass manager, which runs pipelines of passes over a program.
+//!
+//! A transform pass changes a function, and an analysis works out facts about
+//! one, like its [`Cfg`] or [`Dominators`]. Transforms ask [`Analyses`] for the
+//! analyses they need, which computes each one once and keeps it until a pass
+//! changes the function. A pass that keeps the function's jumps and labels as
+//! they are can say so, and then analyses of the shape of its graph are kept
+//! too. A [`ProgramPass`] changes the whole program at once, for transforms
+//! that look across functions, like inlining, and the analyses of each
+//! function it changes start over.
+//!
+//! Both kinds of pass are registered by name in a [`PassRegistry`], which reads
+//! pipelines written as text, like `fold,dce` or
+//! `fixpoint(fold,unreachable,copyprop,dce),prune`. A name runs a transform or
+//! computes an analysis ahead of the passes that need it, and `fixpoint(...)`
+//! runs its passes in turn until none of them changes anything. A check, like
+//! `verify-ssa`, changes nothing but stops the pipeline with an error when a
+//! function fails it.
+//!
+//! With [`PassManager::verify_each`], the [verifier](crate::verify) checks the
+//! program before the pipeline and each function after every pass that changes
+//! it, so that a pass that breaks the IR is caught in the act. After a program
+//! pass, every function is checked again, since a function the pass left alone
+//! may call one it deleted or changed.
+
+use std::{
+    any::{Any, TypeId},
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    io::{self, Write},
+    rc::Rc,
+};
+
+use thiserror::Error;
+
+use crate::{
+    cfg::Cfg,
+    dominance::Dominators,
+    loops::Loops,
+    tacky::{Function, Program},
+    verify::{Verifier, VerifyError},
+};
+
+/// Facts about a function, worked out from its body.
+pub trait Analysis: 'static {
+    /// The name pipelines know the analysis by.
+    const NAME: &'static str;
+    /// Whether the analysis depends only on the shape of the control-flow graph,
+    /// and not on what the blocks do.
+    const CONTROL_FLOW_ONLY: bool;
+
+    fn compute(function: &Function, analyses: &mut Analyses) -> Self;
+}
+
+impl Analysis for Cfg {
+    const NAME: &'static str = "cfg";
+    const CONTROL_FLOW_ONLY: bool = false;
+
+    fn compute(function: &Function, _: &mut Analyses) -> Self {
+        Cfg::new(function.body.clone())
+    }
+}
+
+impl Analysis for Dominators {
+    const NAME: &'static str = "dominators";
+    const CONTROL_FLOW_ONLY: bool = true;
+
+    fn compute(function: &Function, analyses: &mut Analyses) -> Self {
+        Dominators::new(&analyses.get::<Cfg>(function))
+    }
+}
+
+impl Analysis for Loops {
+    const NAME: &'static str = "loops";
+    const CONTROL_FLOW_ONLY: bool = true;
+
+    fn compute(function: &Function, analyses: &mut Analyses) -> Self {
+        let cfg = analyses.get::<Cfg>(function);
+        let dominators = analyses.get::<Dominators>(function);
+        Loops::new(&cfg, &dominators)
+    }
+}
+
+/// The analyses of one function that are up to date.
+#[derive(Default)]
+pub struct Analyses {
+    cache: HashMap<TypeId, CachedAnalysis>,
+}
+struct CachedAnalysis {
+    analysis: Rc<dyn Any>,
+    control_flow_only: bool,
+}
+impl Analyses {
+    /// An analysis of `function`, which must be the function the cached
+    /// analyses are of.
+    pub fn get<A: Analysis>(&mut self, function: &Function) -> Rc<A> {
+        if let Some(cached) = self.cache.get(&TypeId::of::<A>()) {
+            return Rc::clone(&cached.analysis)
+                .downcast()
+                .expect("an analysis is cached by its own type");
+        }
+        let analysis = Rc::new(A::compute(function, self));
+        self.cache.insert(
+            TypeId::of::<A>(),
+            CachedAnalysis {
+                analysis: Rc::clone(&analysis) as Rc<dyn Any>,
+                control_flow_only: A::CONTROL_FLOW_ONLY,
+            },
+        );
+        analysis
+    }
+
+    pub fn is_cached<A: Analysis>(&self) -> bool {
+        self.cache.contains_key(&TypeId::of::<A>())
+    }
+
+    /// Forget the analyses a change to the function has made out of date.
+    pub fn invalidate(&mut self, preserved: Preserved) {
+        match preserved {
+            Preserved::Nothing => self.cache.clear(),
+            Preserved::ControlFlow => self.cache.retain(|_, cached| cached.control_flow_only),
+        }
+    }
+}
+
+/// What a transform leaves as it was when it changes a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preserved {
+    Nothing,
+    /// Every jump and label, so the control-flow graph has the same shape.
+    ControlFlow,
+}
+
+/// A transform of functions.
+pub trait Pass {
+    /// The name pipelines know the pass by.
+    fn name(&self) -> &'static str;
+
+    fn preserves(&self) -> Preserved {
+        Preserved::Nothing
+    }
+
+    fn run(&self, function: &mut Function, analyses: &mut Analyses);
+}
+
+/// A transform of a whole program, which can add and delete functions.
+pub trait ProgramPass {
+    /// The name pipelines know the pass by.
+    fn name(&self) -> &'static str;
+
+    fn run(&self, program: &mut Program);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PipelineError {
+    #[error("unknown pass `{name}`, expected one of: {}", .known.join(", "))]
+    UnknownPass { name: String, known: Vec<String> },
+    #[error("expected {expected} at `{rest}`")]
+    Expected {
+        expected: &'static str,
+        rest: String,
+    },
+    #[error("expected {expected} at the end of the pipeline")]
+    ExpectedAtEnd { expected: &'static str },
+}
+
+#[derive(Debug, Error)]
+pub enum PassError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("the IR is malformed before any pass: {error}\n{function}")]
+    MalformedInput {
+        error: VerifyError,
+        function: String,
+    },
+    #[error("the IR is malformed after `{pass}`: {error}\n{function}")]
+    Malformed {
+        pass: &'static str,
+        error: VerifyError,
+        function: String,
+    },
+    #[error("the check `{check}` failed on `{name}`: {error}\n{function}")]
+    CheckFailed {
+        check: &'static str,
+        name: String,
+        error: String,
+        function: String,
+    },
+}
+
+/// A check of a function, which says what is wrong with it if it fails.
+pub type Check = fn(&Function) -> Result<(), String>;
+
+/// The passes pipelines can name.
+#[derive(Default)]
+pub struct PassRegistry {
+    passes: BTreeMap<&'static str, Registered>,
+}
+#[derive(Clone)]
+enum Registered {
+    Transform(Rc<dyn Pass>),
+    Program(Rc<dyn ProgramPass>),
+    Analysis(fn(&Function, &mut Analyses)),
+    Check(&'static str, Check),
+}
+impl PassRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register_pass(&mut self, pass: impl Pass + 'static) {
+        self.passes
+            .insert(pass.name(), Registered::Transform(Rc::new(pass)));
+    }
+
+    pub fn register_program_pass(&mut self, pass: impl ProgramPass + 'static) {
+        self.passes
+            .insert(pass.name(), Registered::Program(Rc::new(pass)));
+    }
+
+    pub fn register_analysis<A: Analysis>(&mut self) {
+        fn require<A: Analysis>(function: &Function, analyses: &mut Analyses) {
+            analyses.get::<A>(function);
+        }
+        self.passes
+            .insert(A::NAME, Registered::Analysis(require::<A>));
+    }
+
+    pub fn register_check(&mut self, name: &'static str, check: Check) {
+        self.passes.insert(name, Registered::Check(name, check));
+    }
+
+    /// Whether `name` is a registered transform, of functions or of the program.
+    pub fn is_transform(&self, name: &str) -> bool {
+        matches!(
+            self.passes.get(name),
+            Some(Registered::Transform(_) | Registered::Program(_))
+        )
+    }
+
+    /// Read a pipeline: passes separated by commas, where `fixpoint(...)`
+    /// repeats the passes inside it.
+    pub fn parse_pipeline(&self, text: &str) -> Result<Pipeline, PipelineError> {
+        let mut rest = text.trim_start();
+        let steps = match rest.is_empty() {
+            true => Vec::new(),
+            false => self.parse_steps(&mut rest)?,
+        };
+        match rest.is_empty() {
+            true => Ok(Pipeline { steps }),
+            false => Err(PipelineError::Expected {
+                expected: "`,`",
+                rest: rest.to_string(),
+            }),
+        }
+    }
+
+    fn parse_steps(&self, rest: &mut &str) -> Result<Vec<Step>, PipelineError> {
+        let mut steps = vec![self.parse_step(rest)?];
+        while let Some(after) = rest.strip_prefix(',') {
+            *rest = after.trim_start();
+            steps.push(self.parse_step(rest)?);
+        }
+        Ok(steps)
+    }
+
+    fn parse_step(&self, rest: &mut &str) -> Result<Step, PipelineError> {
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(rest.len());
+        let name = &rest[..end];
+        if name.is_empty() {
+            return Err(expected("a pass", rest));
+        }
+        *rest = rest[end..].trim_start();
+        if name == "fixpoint" {
+            *rest = rest
+                .strip_prefix('(')
+                .ok_or_else(|| expected("`(`", rest))?
+                .trim_start();
+            let steps = self.parse_steps(rest)?;
+            *rest = rest
+                .strip_prefix(')')
+                .ok_or_else(|| expected("`)`", rest))?
+                .trim_start();
+            return Ok(Step::FixedPoint(steps));
+        }
+        match self.passes.get(name) {
+            Some(Registered::Transform(pass)) => Ok(Step::Transform(Rc::clone(pass))),
+            Some(Registered::Program(pass)) => Ok(Step::Program(Rc::clone(pass))),
+            Some(Registered::Analysis(compute)) => Ok(Step::Analysis(name.to_string(), *compute)),
+            Some(Registered::Check(name, check)) => Ok(Step::Check(name, *check)),
+            None => Err(PipelineError::UnknownPass {
+                name: name.to_string(),
+                known: self.passes.keys().map(|name| name.to_string()).collect(),
+            }),
+        }
+    }
+}
+
+fn expected(expected: &'static str, rest: &str) -> PipelineError {
+    match rest.is_empty() {
+        true => PipelineError::ExpectedAtEnd { expected },
+        false => PipelineError::Expected {
+            expected,
+            rest: rest.to_string(),
+        },
+    }
+}
+
+/// Passes to run in order, as read by [`PassRegistry::parse_pipeline`].
+pub struct Pipeline {
+    steps: Vec<Step>,
+}
+enum Step {
+    Transform(Rc<dyn Pass>),
+    Program(Rc<dyn ProgramPass>),
+    Analysis(String, fn(&Function, &mut Analyses)),
+    Check(&'static str, Check),
+    FixedPoint(Vec<Step>),
+}
+
+impl fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_steps(f: &mut fmt::Formatter<'_>, steps: &[Step]) -> fmt::Result {
+            for (i, step) in steps.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ",")?;
+                }
+                match step {
+                    Step::Transform(pass) => write!(f, "{}", pass.name())?,
+                    Step::Program(pass) => write!(f, "{}", pass.name())?,
+                    Step::Analysis(name, _) => write!(f, "{name}")?,
+                    Step::Check(name, _) => write!(f, "{name}")?,
+                    Step::FixedPoint(steps) => {
+                        write!(f, "fixpoint(")?;
+                        write_steps(f, steps)?;
+                        write!(f, ")")?;
+                    }
+                }
+            }
+            Ok(())
+        }
+        write_steps(f, &self.steps)
+    }
+}
+
+/// Runs a pipeline over every function of a program.
+pub struct PassManager {
+    pipeline: Pipeline,
+    print_after: HashSet<String>,
+    verify_each: bool,
+}
+impl PassManager {
+    pub fn new(pipeline: Pipeline) -> Self {
+        Self {
+            pipeline,
+            print_after: HashSet::new(),
+            verify_each: false,
+        }
+    }
+
+    /// Check that the program is well formed before the pipeline, and that each
+    /// function still is after every pass that changes it.
+    pub fn verify_each(&mut self, verify: bool) {
+        self.verify_each = verify;
+    }
+
+    /// Print each function before and after every run of the pass `name`, or
+    /// the whole program for a [`ProgramPass`].
+    pub fn print_after(&mut self, name: &str) {
+        self.print_after.insert(name.to_string());
+    }
+
+    /// Run the pipeline, printing to `dump` what [`print_after`](Self::print_after) asks for.
+    pub fn run(&self, program: &mut Program, dump: &mut impl Write) -> Result<(), PassError> {
+        let mut verifier = match self.verify_each {
+            true => {
+                let verifier = Verifier::new(program);
+                for function in &program.functions {
+                    verifier.verify_function(function).map_err(|error| {
+                        PassError::MalformedInput {
+                            error,
+                            function: function.to_string(),
+                        }
+                    })?;
+                }
+                Some(verifier)
+            }
+            false => None,
+        };
+        let mut analyses: Vec<Analyses> = program
+            .functions
+            .iter()
+            .map(|_| Analyses::default())
+            .collect();
+        self.run_steps(
+            &self.pipeline.steps,
+            program,
+            &mut analyses,
+            &mut verifier,
+            dump,
+        )?;
+        Ok(())
+    }
+
+    /// Run some steps, returning whether they changed any function.
+    fn run_steps(
+        &self,
+        steps: &[Step],
+        program: &mut Program,
+        analyses: &mut Vec<Analyses>,
+        verifier: &mut Option<Verifier>,
+        dump: &mut impl Write,
+    ) -> Result<bool, PassError> {
+        let mut changed = false;
+        for step in steps {
+            match step {
+                Step::Transform(pass) => {
+                    let printing = self.print_after.contains(pass.name());
+                    for (function, analyses) in program.functions.iter_mut().zip(&mut *analyses) {
+                        if printing {
+                            writeln!(dump, "*** before {} on {} ***", pass.name(), function.name)?;
+                            writeln!(dump, "{function}")?;
+                        }
+                        let before = function.body.clone();
+                        pass.run(function, analyses);
+                        if function.body != before {
+                            analyses.invalidate(pass.preserves());
+                            changed = true;
+                            if let Some(verifier) = &verifier {
+                                verifier.verify_function(function).map_err(|error| {
+                                    PassError::Malformed {
+                                        pass: pass.name(),
+                                        error,
+                                        function: function.to_string(),
+                                    }
+                                })?;
+                            }
+                        }
+                        if printing {
+                            writeln!(dump, "*** after {} on {} ***", pass.name(), function.name)?;
+                            writeln!(dump, "{function}")?;
+                        }
+                    }
+                }
+                Step::Program(pass) => {
+                    let printing = self.print_after.contains(pass.name());
+                    if printing {
+                        writeln!(dump, "*** before {} ***", pass.name())?;
+                        write!(dump, "{program}")?;
+                    }
+                    let before = program.functions.clone();
+                    pass.run(program);
+                    if program.functions != before {
+                        changed = true;
+                        // Keep the analyses of the functions the pass left alone
+                        let mut kept: HashMap<&str, (&Function, Analyses)> = before
+                            .iter()
+                            .zip(analyses.drain(..))
+                            .map(|(function, analyses)| {
+                                (function.name.as_str(), (function, analyses))
+                            })
+                            .collect();
+                        for function in &program.functions {
+                            match kept.remove(function.name.as_str()) {
+                                Some((old, cached)) if old == function => analyses.push(cached),
+                                _ => analyses.push(Analyses::default()),
+                            }
+                        }
+                        if let Some(old) = verifier {
+                            let new = old.after(program);
+                            for function in &program.functions {
+                                new.verify_function(function).map_err(|error| {
+                                    PassError::Malformed {
+                                        pass: pass.name(),
+                                        error,
+                                        function: function.to_string(),
+                                    }
+                                })?;
+                            }
+                            *verifier = Some(new);
+                        }
+                    }
+                    if printing {
+                        writeln!(dump, "*** after {} ***", pass.name())?;
+                        write!(dump, "{program}")?;
+                    }
+                }
+                Step::Analysis(_, compute) => {
+                    for (function, analyses) in program.functions.iter().zip(&mut *analyses) {
+                        compute(function, analyses);
+                    }
+                }
+                Step::Check(check, run) => {
+                    for function in &program.functions {
+                        run(function).map_err(|error| PassError::CheckFailed {
+                            check,
+                            name: function.name.clone(),
+                            error,
+                            function: function.to_string(),
+                        })?;
+                    }
+                }
+                Step::FixedPoint(steps) => {
+                    while self.run_steps(steps, program, analyses, verifier, dump)? {
+                        changed = true;
+                    }
+                }
+            }
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod pass_tests {
+    use std::cell::Cell;
+
+    use expect_test::{expect, Expect};
+
+    use super::{Analyses, Analysis, Pass, PassManager, PassRegistry, Preserved, ProgramPass};
+    use crate::{
+        cfg::Cfg,
+        dominance::Dominators,
+        tacky::{Function, Instruction, Program},
+        text::parse_program,
+    };
+
+    thread_local! {
+        static COMPUTED: Cell<u32> = const { Cell::new(0) };
+    }
+
+    /// An analysis that counts how many times it is computed.
+    struct Counted;
+    impl Analysis for Counted {
+        const NAME: &'static str = "counted";
+        const CONTROL_FLOW_ONLY: bool = true;
+
+        fn compute(_: &Function, _: &mut Analyses) -> Self {
+            COMPUTED.with(|computed| computed.set(computed.get() + 1));
+            Counted
+        }
+    }
+
+    /// Removes the first instruction of a function that isn't a label or a
+    /// return, using the analysis it is given.
+    struct RemoveFirst(&'static str, Preserved);
+    impl Pass for RemoveFirst {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn preserves(&self) -> Preserved {
+            self.1
+        }
+
+        fn run(&self, function: &mut Function, analyses: &mut Analyses) {
+            analyses.get::<Counted>(function);
+            let first = function.body.iter().position(|instruction| {
+                !matches!(instruction, Instruction::Label(_) | Instruction::Return(_))
+            });
+            if let Some(first) = first {
+                function.body.remove(first);
+            }
+        }
+    }
+
+    /// Removes the last instruction of a function, which leaves it running off
+    /// the end.
+    struct DropLast;
+    impl Pass for DropLast {
+        fn name(&self) -> &'static str {
+            "drop-last"
+        }
+
+        fn run(&self, function: &mut Function, _: &mut Analyses) {
+            function.body.pop();
+        }
+    }
+
+    /// Deletes the last function of the program, whether or not it is called.
+    struct DeleteLast;
+    impl ProgramPass for DeleteLast {
+        fn name(&self) -> &'static str {
+            "delete-last"
+        }
+
+        fn run(&self, program: &mut Program) {
+            program.functions.pop();
+        }
+    }
+
+    fn registry() -> PassRegistry {
+        let mut registry = PassRegistry::new();
+        registry.register_pass(DropLast);
+        registry.register_program_pass(DeleteLast);
+        registry.register_pass(RemoveFirst("keep", Preserved::ControlFlow));
+        registry.register_pass(RemoveFirst("clobber", Preserved::Nothing));
+        registry.register_analysis::<Counted>();
+        registry.register_analysis::<Cfg>();
+        registry.register_analysis::<Dominators>();
+        registry.register_check("no-adds", |function| {
+            match function
+                .body
+                .iter()
+                .any(|i| matches!(i, Instruction::Binary { .. }))
+            {
+                true => Err("it adds".into()),
+                false => Ok(()),
+            }
+        });
+        registry
+    }
+
+    const SRC: &str = r#"
+global function f(%n: i32) -> i32 {
+    %n = add %n, i32 1
+    %n = add %n, i32 2
+    %n = add %n, i32 3
+    return %n
+}
+"#;
+
+    /// How many times the pipeline computes the analysis, and what it prints
+    /// after `clobber`.
+    fn check_pipeline(pipeline: &str, expect: Expect) {
+        let registry = registry();
+        let pipeline = registry.parse_pipeline(pipeline).unwrap();
+        let mut manager = PassManager::new(pipeline);
+        manager.print_after("clobber");
+        let mut program = parse_program(SRC).unwrap();
+        let mut dump = Vec::new();
+        COMPUTED.with(|computed| computed.set(0));
+        manager.run(&mut program, &mut dump).unwrap();
+        let computed = COMPUTED.with(Cell::get);
+        let dump = String::from_utf8(dump).unwrap();
+        expect.assert_eq(&format!("computed {computed} times\n{dump}"));
+    }
+
+    #[test]
+    fn test_caching() {
+        check_pipeline(
+            "counted,keep,keep",
+            expect![[r#"
+                computed 1 times
+            "#]],
+        );
+        check_pipeline(
+            "keep,fixpoint(keep),counted",
+            expect![[r#"
+                computed 1 times
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_invalidation_and_printing() {
+        check_pipeline(
+            "keep,clobber,counted",
+            expect![[r#"
+                computed 2 times
+                *** before clobber on f ***
+                global function f(%n: i32) -> i32 {
+                    %n = add %n, i32 2
+                    %n = add %n, i32 3
+                    return %n
+                }
+                *** after clobber on f ***
+                global function f(%n: i32) -> i32 {
+                    %n = add %n, i32 3
+                    return %n
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_verify_each() {
+        let registry = registry();
+        let check = |src: &str, expect: Expect| {
+            let pipeline = registry.parse_pipeline("keep,drop-last").unwrap();
+            let mut manager = PassManager::new(pipeline);
+            manager.verify_each(true);
+            let mut program = parse_program(src).unwrap();
+            let err = manager.run(&mut program, &mut Vec::new()).unwrap_err();
+            expect.assert_eq(&err.to_string());
+        };
+        check(
+            SRC,
+            expect![[r#"
+                the IR is malformed after `drop-last`: in `f`: the body doesn't end in a return
+                global function f(%n: i32) -> i32 {
+                    %n = add %n, i32 2
+                    %n = add %n, i32 3
+                }"#]],
+        );
+        check(
+            "function f() -> i32 {\n    jump end.0\n}\n",
+            expect![[r#"
+                the IR is malformed before any pass: in `f`: `jump end.0` jumps to the undefined label `end.0`
+                function f() -> i32 {
+                    jump end.0
+                }"#]],
+        );
+    }
+
+    #[test]
+    fn test_verify_after_program_pass() {
+        let registry = registry();
+        let pipeline = registry.parse_pipeline("delete-last").unwrap();
+        let mut manager = PassManager::new(pipeline);
+        manager.verify_each(true);
+        let mut program = parse_program(
+            r#"
+global function main() -> i32 {
+    local %.t0: i32
+    %.t0 = call helper()
+    return %.t0
+}
+
+function helper() -> i32 {
+    return i32 1
+}
+"#,
+        )
+        .unwrap();
+        let err = manager.run(&mut program, &mut Vec::new()).unwrap_err();
+        expect![[r#"
+            the IR is malformed after `delete-last`: in `main`: `%.t0 = call helper()` calls `helper`, which was deleted
+            global function main() -> i32 {
+                local %.t0: i32
+                %.t0 = call helper()
+                return %.t0
+            }"#]]
+        .assert_eq(&err.to_string());
+    }
+
+    #[test]
+    fn test_check() {
+        let registry = registry();
+        let pipeline = registry.parse_pipeline("keep,no-adds").unwrap();
+        let err = PassManager::new(pipeline)
+            .run(&mut parse_program(SRC).unwrap(), &mut Vec::new())
+            .unwrap_err();
+        expect![[r#"
+            the check `no-adds` failed on `f`: it adds
+            global function f(%n: i32) -> i32 {
+                %n = add %n, i32 2
+                %n = add %n, i32 3
+                return %n
+            }"#]]
+        .assert_eq(&err.to_string());
+    }
+
+    #[test]
+    fn test_parse_pipeline() {
+        let registry = registry();
+        let check = |text: &str, expect: Expect| {
+            let actual = match registry.parse_pipeline(text) {
+                Ok(pipeline) => format!("`{pipeline}`"),
+                Err(err) => err.to_string(),
+            };
+            expect.assert_eq(&actual);
+        };
+        check("", expect!["``"]);
+        check(
+            " keep , fixpoint( clobber,dominators ) ,cfg",
+            expect!["`keep,fixpoint(clobber,dominators),cfg`"],
+        );
+        check(
+            "keep,fold",
+            expect![
+                "unknown pass `fold`, expected one of: cfg, clobber, counted, delete-last, dominators, drop-last, keep, no-adds"
+            ],
+        );
+        check("keep,,cfg", expect!["expected a pass at `,cfg`"]);
+        check("keep cfg", expect!["expected `,` at `cfg`"]);
+        check(
+            "fixpoint(keep",
+            expect!["expected `)` at the end of the pipeline"],
+        );
+        check("fixpoint keep", expect!["expected `(` at `keep`"]);
+    }
+}
@@ -0,0 +1,799 @@
+//! Static single assignment form.
+//!
+//! In SSA form every variable is assigned in one place, and that place
+//! dominates every use. [`into_ssa`] gets there by giving each assignment of a
+//! variable a new version of it, `x.0.1`, `x.0.2`, ..., and putting a [`Phi`]
+//! at each join where different versions meet. Phis go in the iterated
+//! dominance frontiers of the assignments, and versions are handed out in a
+//! walk of the dominator tree, as in Cytron et al. A variable that is only ever
+//! read in the block that assigned it, like most temporaries, needs no phis,
+//! and a phi whose value nothing reads is dropped, since its arguments can be
+//! versions that were never assigned. The variable itself stands for its value
+//! on entry, so a parameter, or a local read before it is assigned, keeps its
+//! name.
+//!
+//! Only scalars whose address is never taken are renamed: an aggregate is
+//! assigned piece by piece, and an object with its address taken can be
+//! assigned through a pointer anywhere.
+//!
+//! [`out_of_ssa`] turns each phi back into copies at the end of the
+//! predecessors. A copy can't go at the end of a predecessor with another
+//! successor, so an edge like that is split by a new block; and since the phis
+//! of a block all take their values at once, their copies are ordered so none
+//! overwrites a value another still has to read, with a temporary to break
+//! cycles like a swap.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
+};
+
+use crate::{
+    cfg::{BlockId, Cfg},
+    dominance::Dominators,
+    tacky::{Function, Instruction, Type, Value},
+};
+
+/// Assigns `dst` the argument for the predecessor control came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Phi {
+    pub dst: String,
+    /// One argument for each predecessor of the block.
+    pub args: Vec<(BlockId, Value)>,
+}
+impl fmt::Display for Phi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "%{} = phi ", self.dst)?;
+        for (i, (block, value)) in self.args.iter().enumerate() {
+            let separator = if i == 0 { "[" } else { ", " };
+            write!(f, "{separator}{block}: {value}")?;
+        }
+        write!(f, "]")
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("block {block}: {message}")]
+pub struct SsaError {
+    block: BlockId,
+    message: String,
+}
+impl SsaError {
+    pub fn block(&self) -> BlockId {
+        self.block
+    }
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// A name for a new variable of type `ty`, starting with `base`.
+fn fresh_variable(
+    variables: &mut BTreeMap<String, Type>,
+    counters: &mut HashMap<String, usize>,
+    base: &str,
+    ty: Type,
+) -> String {
+    let counter = counters.entry(base.to_string()).or_default();
+    loop {
+        *counter += 1;
+        let name = format!("{base}.{counter}");
+        if !variables.contains_key(&name) {
+            variables.insert(name.clone(), ty);
+            return name;
+        }
+    }
+}
+
+/// Put the body of a function into SSA form, as a graph that has phis. The
+/// function is left with an empty body, and the new versions of its variables.
+pub fn into_ssa(function: &mut Function) -> Cfg {
+    let mut cfg = Cfg::of_function(function);
+    let dominators = Dominators::new(&cfg);
+    let renamed = cfg.unaliased_scalars(&function.variables);
+
+    // Only a variable read in some block before that block assigns it can
+    // need a phi, which leaves out most temporaries
+    let mut assigned: BTreeMap<String, BTreeSet<BlockId>> = BTreeMap::new();
+    let mut live_across_blocks = HashSet::new();
+    for block in cfg.body() {
+        let mut assigned_here = HashSet::new();
+        for instruction in &cfg.blocks[block].instructions {
+            for src in instruction.sources() {
+                if let Value::Variable(name) = src {
+                    if !assigned_here.contains(name) {
+                        live_across_blocks.insert(name);
+                    }
+                }
+            }
+            if let Some(Value::Variable(name)) = instruction.dst() {
+                if renamed.contains(name) {
+                    assigned_here.insert(name);
+                    assigned.entry(name.clone()).or_default().insert(block);
+                }
+            }
+        }
+    }
+    assigned.retain(|variable, _| live_across_blocks.contains(variable));
+    // The variable each phi is for, since renaming changes its `dst`
+    let mut phi_variables: Vec<Vec<String>> = vec![Vec::new(); cfg.blocks.len()];
+    for (variable, blocks) in &assigned {
+        let mut has_phi = BTreeSet::new();
+        let mut worklist: Vec<BlockId> = blocks.iter().copied().collect();
+        while let Some(block) = worklist.pop() {
+            for &join in dominators.frontier(block) {
+                if join == Cfg::EXIT || !has_phi.insert(join) {
+                    continue;
+                }
+                let args = cfg.blocks[join]
+                    .predecessors
+                    .iter()
+                    .map(|&predecessor| (predecessor, Value::Variable(variable.to_string())))
+                    .collect();
+                cfg.blocks[join].phis.push(Phi {
+                    dst: variable.to_string(),
+                    args,
+                });
+                phi_variables[join].push(variable.to_string());
+                if !blocks.contains(&join) {
+                    worklist.push(join);
+                }
+            }
+        }
+    }
+
+    // The versions of each variable that reach the block being visited
+    let mut versions: HashMap<String, Vec<String>> = HashMap::new();
+    let mut counters = HashMap::new();
+    let current = |versions: &HashMap<String, Vec<String>>, name: &str| {
+        versions
+            .get(name)
+            .and_then(|stack| stack.last())
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    };
+    enum Visit {
+        Enter(BlockId),
+        /// Leave a block, forgetting the versions it assigned.
+        Leave(Vec<String>),
+    }
+    let mut visits = vec![Visit::Enter(Cfg::ENTRY)];
+    while let Some(visit) = visits.pop() {
+        let block = match visit {
+            Visit::Enter(block) => block,
+            Visit::Leave(assigned) => {
+                for variable in assigned {
+                    versions.get_mut(&variable).and_then(Vec::pop);
+                }
+                continue;
+            }
+        };
+        let mut assigned = Vec::new();
+        let contents = &mut cfg.blocks[block];
+        for (phi, variable) in contents.phis.iter_mut().zip(&phi_variables[block]) {
+            let ty = function.variables[variable];
+            phi.dst = fresh_variable(&mut function.variables, &mut counters, variable, ty);
+            versions
+                .entry(variable.clone())
+                .or_default()
+                .push(phi.dst.clone());
+            assigned.push(variable.clone());
+        }
+        for instruction in &mut contents.instructions {
+            for src in instruction.sources_mut() {
+                if let Value::Variable(name) = src {
+                    if renamed.contains(name) {
+                        *name = current(&versions, name);
+                    }
+                }
+            }
+            if let Some(Value::Variable(name)) = instruction.dst_mut() {
+                if renamed.contains(name) {
+                    let ty = function.variables[name.as_str()];
+                    let version = fresh_variable(&mut function.variables, &mut counters, name, ty);
+                    versions
+                        .entry(name.clone())
+                        .or_default()
+                        .push(version.clone());
+                    assigned.push(std::mem::replace(name, version));
+                }
+            }
+        }
+        for successor in cfg.blocks[block].successors.clone() {
+            let join = &mut cfg.blocks[successor];
+            for (phi, variable) in join.phis.iter_mut().zip(&phi_variables[successor]) {
+                for (predecessor, value) in &mut phi.args {
+                    if *predecessor == block {
+                        *value = Value::Variable(current(&versions, variable));
+                    }
+                }
+            }
+        }
+        visits.push(Visit::Leave(assigned));
+        visits.extend(
+            dominators
+                .children(block)
+                .iter()
+                .rev()
+                .map(|&child| Visit::Enter(child)),
+        );
+    }
+    remove_dead_phis(&mut cfg);
+    cfg
+}
+
+/// Remove the phis whose value no instruction needs, even through other phis.
+fn remove_dead_phis(cfg: &mut Cfg) {
+    let mut live: HashSet<String> = HashSet::new();
+    let mut worklist: Vec<String> = Vec::new();
+    for instruction in cfg.blocks.iter().flat_map(|block| &block.instructions) {
+        for src in instruction.sources() {
+            if let Value::Variable(name) = src {
+                if live.insert(name.clone()) {
+                    worklist.push(name.clone());
+                }
+            }
+        }
+    }
+    let phis: HashMap<String, &Phi> = cfg
+        .blocks
+        .iter()
+        .flat_map(|block| &block.phis)
+        .map(|phi| (phi.dst.clone(), phi))
+        .collect();
+    while let Some(name) = worklist.pop() {
+        let Some(phi) = phis.get(&name) else {
+            continue;
+        };
+        for (_, value) in &phi.args {
+            if let Value::Variable(arg) = value {
+                if live.insert(arg.clone()) {
+                    worklist.push(arg.clone());
+                }
+            }
+        }
+    }
+    for block in &mut cfg.blocks {
+        block.phis.retain(|phi| live.contains(&phi.dst));
+    }
+}
+
+/// Take the body of a function out of SSA form, turning its phis into copies.
+pub fn out_of_ssa(function: &mut Function, cfg: Cfg) {
+    let labels: HashSet<&str> = cfg.body().filter_map(|block| cfg.label(block)).collect();
+    let mut counters = HashMap::new();
+    // The copies that do what the phis of `successor` do for `block`
+    let mut edge_copies = |variables: &mut BTreeMap<String, Type>, block, successor: BlockId| {
+        let copies = cfg.blocks[successor]
+            .phis
+            .iter()
+            .map(|phi| {
+                let (_, value) = phi
+                    .args
+                    .iter()
+                    .find(|(predecessor, _)| *predecessor == block)
+                    .expect("a phi has an argument for every predecessor");
+                (phi.dst.clone(), value.clone())
+            })
+            .collect();
+        sequentialize(copies, variables, &mut counters)
+    };
+
+    let mut body = edge_copies(
+        &mut function.variables,
+        Cfg::ENTRY,
+        2.min(cfg.blocks.len() - 1),
+    );
+    // Blocks for split edges, which go after everything else
+    let mut split_blocks = Vec::new();
+    let mut label_count = 0;
+    for block in cfg.body() {
+        let contents = &cfg.blocks[block];
+        let mut instructions = contents.instructions.clone();
+        let next = match block + 1 < cfg.blocks.len() {
+            true => block + 1,
+            false => Cfg::EXIT,
+        };
+        if let [successor] = contents.successors[..] {
+            let copies = edge_copies(&mut function.variables, block, successor);
+            match instructions.last() {
+                Some(Instruction::Jump(_)) => {
+                    let jump = instructions.pop();
+                    instructions.extend(copies);
+                    instructions.extend(jump);
+                }
+                // A conditional jump to the next block does nothing, and
+                // copies after it mustn't be skipped
+                Some(Instruction::JumpIfZero(..) | Instruction::JumpIfNotZero(..))
+                    if !copies.is_empty() =>
+                {
+                    instructions.pop();
+                    instructions.extend(copies);
+                }
+                _ => instructions.extend(copies),
+            }
+            body.extend(instructions);
+            continue;
+        }
+        let mut fall_through = Vec::new();
+        for &successor in &contents.successors {
+            let copies = edge_copies(&mut function.variables, block, successor);
+            if copies.is_empty() {
+                continue;
+            }
+            if successor == next {
+                fall_through = copies;
+                continue;
+            }
+            let label = fresh_label(&labels, &mut label_count);
+            let target = match instructions.last_mut() {
+                Some(
+                    Instruction::JumpIfZero(_, target) | Instruction::JumpIfNotZero(_, target),
+                ) => std::mem::replace(target, label.clone()),
+                _ => unreachable!("only a conditional jump has two successors"),
+            };
+            split_blocks.push(Instruction::Label(label));
+            split_blocks.extend(copies);
+            split_blocks.push(Instruction::Jump(target));
+        }
+        body.extend(instructions);
+        body.extend(fall_through);
+    }
+
+    if !split_blocks.is_empty() {
+        // Don't let the end of the body fall into the split blocks
+        let falls_off_end = !matches!(
+            body.last(),
+            Some(Instruction::Return(_) | Instruction::Jump(_))
+        );
+        let end = falls_off_end.then(|| fresh_label(&labels, &mut label_count));
+        if let Some(end) = &end {
+            body.push(Instruction::Jump(end.clone()));
+        }
+        body.extend(split_blocks);
+        body.extend(end.map(Instruction::Label));
+    }
+    function.body = body;
+}
+
+/// A label for a block `out_of_ssa` adds, which none in `labels` clashes with.
+fn fresh_label(labels: &HashSet<&str>, count: &mut usize) -> String {
+    loop {
+        *count += 1;
+        let label = format!("split.{count}");
+        if !labels.contains(label.as_str()) {
+            return label;
+        }
+    }
+}
+
+/// Copies that do what a set of simultaneous copies does, each `(dst, src)`.
+fn sequentialize(
+    mut copies: Vec<(String, Value)>,
+    variables: &mut BTreeMap<String, Type>,
+    counters: &mut HashMap<String, usize>,
+) -> Vec<Instruction> {
+    copies.retain(|(dst, src)| *src != Value::Variable(dst.clone()));
+    let mut instructions = Vec::new();
+    while !copies.is_empty() {
+        let is_read = |copies: &[(String, Value)], name: &str| {
+            copies
+                .iter()
+                .any(|(_, src)| matches!(src, Value::Variable(src) if src == name))
+        };
+        match copies.iter().position(|(dst, _)| !is_read(&copies, dst)) {
+            Some(i) => {
+                let (dst, src) = copies.remove(i);
+                instructions.push(Instruction::Copy {
+                    src,
+                    dst: Value::Variable(dst),
+                });
+            }
+            None => {
+                // Every copy left is in a cycle, which saving one destination
+                // first breaks
+                let saved = copies[0].0.clone();
+                let ty = variables[&saved];
+                let temporary = fresh_variable(variables, counters, ".swap", ty);
+                instructions.push(Instruction::Copy {
+                    src: Value::Variable(saved.clone()),
+                    dst: Value::Variable(temporary.clone()),
+                });
+                for (_, src) in &mut copies {
+                    if *src == Value::Variable(saved.clone()) {
+                        *src = Value::Variable(temporary.clone());
+                    }
+                }
+            }
+        }
+    }
+    instructions
+}
+
+/// Put a copy of a function into SSA form and check it, for the `verify-ssa`
+/// step of a pipeline.
+pub fn verify_into_ssa(function: &Function) -> Result<(), SsaError> {
+    let mut function = function.clone();
+    let cfg = into_ssa(&mut function);
+    verify_ssa(&function, &cfg)
+}
+
+/// Check that a function's graph is in SSA form: that each phi has an argument
+/// for each predecessor, and that each renamed variable is assigned once, in a
+/// place that dominates all its uses. Blocks that can't be reached are ignored.
+pub fn verify_ssa(function: &Function, cfg: &Cfg) -> Result<(), SsaError> {
+    let dominators = Dominators::new(cfg);
+    let renamed = cfg.unaliased_scalars(&function.variables);
+    // Where each variable is assigned: the block, and the index of the
+    // instruction, or `None` for a phi
+    let mut assignments: HashMap<String, (BlockId, Option<usize>)> = HashMap::new();
+    let mut assign = |name: &str, block, index| {
+        if !renamed.contains(name) {
+            return Ok(());
+        }
+        match assignments.insert(name.to_string(), (block, index)) {
+            Some(_) => Err(SsaError {
+                block,
+                message: format!("`%{name}` is assigned more than once"),
+            }),
+            None => Ok(()),
+        }
+    };
+    for (block, contents) in cfg.blocks.iter().enumerate() {
+        if !dominators.is_reachable(block) {
+            continue;
+        }
+        for phi in &contents.phis {
+            let mut predecessors: Vec<_> = phi.args.iter().map(|(block, _)| *block).collect();
+            predecessors.sort_unstable();
+            let mut expected = contents.predecessors.clone();
+            expected.sort_unstable();
+            if predecessors != expected {
+                return Err(SsaError {
+                    block,
+                    message: format!(
+                        "the phi for `%{}` has arguments for {predecessors:?}, but the predecessors are {expected:?}",
+                        phi.dst
+                    ),
+                });
+            }
+            assign(&phi.dst, block, None)?;
+        }
+        for (i, instruction) in contents.instructions.iter().enumerate() {
+            if let Some(Value::Variable(name)) = instruction.dst() {
+                assign(name, block, Some(i))?;
+            }
+        }
+    }
+
+    // A variable that is never assigned holds its value on entry
+    let check_use = |name: &str, block: BlockId, index: Option<usize>| {
+        let Some(&(assigned, assigned_at)) = assignments.get(name) else {
+            return Ok(());
+        };
+        let dominated = match assigned == block {
+            true => assigned_at < index,
+            false => dominators.dominates(assigned, block),
+        };
+        match dominated {
+            true => Ok(()),
+            false => Err(SsaError {
+                block,
+                message: format!(
+                    "`%{name}` is used where its assignment in block {assigned} doesn't dominate"
+                ),
+            }),
+        }
+    };
+    for (block, contents) in cfg.blocks.iter().enumerate() {
+        if !dominators.is_reachable(block) {
+            continue;
+        }
+        for phi in &contents.phis {
+            for (predecessor, value) in &phi.args {
+                if let Value::Variable(name) = value {
+                    if dominators.is_reachable(*predecessor) {
+                        // As if it were read at the end of the predecessor
+                        check_use(name, *predecessor, Some(usize::MAX))?;
+                    }
+                }
+            }
+        }
+        for (i, instruction) in contents.instructions.iter().enumerate() {
+            for src in instruction.sources() {
+                if let Value::Variable(name) = src {
+                    check_use(name, block, Some(i))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod ssa_tests {
+    use std::{collections::HashMap, fmt::Write};
+
+    use expect_test::{expect, Expect};
+
+    use super::{into_ssa, out_of_ssa, sequentialize, verify_ssa};
+    use crate::{
+        cfg::Cfg,
+        interpret::Interpreter,
+        tacky::{Instruction, Program, Type, Value},
+        testing::lower,
+        text::parse_program,
+        verify::verify_program,
+    };
+
+    fn print_cfg(cfg: &Cfg) -> String {
+        let mut text = String::new();
+        for (block, contents) in cfg.blocks.iter().enumerate().skip(2) {
+            writeln!(text, "{block}: {:?}", contents.predecessors).unwrap();
+            for phi in &contents.phis {
+                writeln!(text, "    {phi}").unwrap();
+            }
+            for instruction in &contents.instructions {
+                match instruction {
+                    Instruction::Label(_) => writeln!(text, "  {instruction}").unwrap(),
+                    _ => writeln!(text, "    {instruction}").unwrap(),
+                }
+            }
+        }
+        text
+    }
+
+    /// The SSA form of the first function of a program, then the function
+    /// after it is taken out of SSA form.
+    fn check_ssa(src: &str, expect: Expect) {
+        let mut program = parse_program(src).unwrap();
+        let function = &mut program.functions[0];
+        let cfg = into_ssa(function);
+        verify_ssa(function, &cfg).unwrap();
+        let ssa = print_cfg(&cfg);
+        out_of_ssa(function, cfg);
+        expect.assert_eq(&format!("{ssa}\n{function}"));
+    }
+
+    fn run(program: &Program) -> String {
+        let mut output = Vec::new();
+        let status = Interpreter::new(program).run(&mut output);
+        format!("{status:?} {output:?}")
+    }
+
+    #[test]
+    fn test_loop() {
+        check_ssa(
+            r#"
+global function main() -> i32 {
+    local %i: i32
+    local %s: i32
+    local %.t0: i32
+    %s = copy i32 0
+    %i = copy i32 0
+  start.0:
+    %.t0 = lt %i, i32 10
+    jz %.t0, break.0
+    %s = add %s, %i
+    %i = add %i, i32 1
+    jump start.0
+  break.0:
+    return %s
+}
+"#,
+            expect![[r#"
+                2: [0]
+                    %s.1 = copy i32 0
+                    %i.1 = copy i32 0
+                3: [2, 4]
+                    %i.2 = phi [2: %i.1, 4: %i.3]
+                    %s.2 = phi [2: %s.1, 4: %s.3]
+                  start.0:
+                    %.t0.1 = lt %i.2, i32 10
+                    jz %.t0.1, break.0
+                4: [3]
+                    %s.3 = add %s.2, %i.2
+                    %i.3 = add %i.2, i32 1
+                    jump start.0
+                5: [3]
+                  break.0:
+                    return %s.2
+
+                global function main() -> i32 {
+                    local %.t0: i32
+                    local %.t0.1: i32
+                    local %i: i32
+                    local %i.1: i32
+                    local %i.2: i32
+                    local %i.3: i32
+                    local %s: i32
+                    local %s.1: i32
+                    local %s.2: i32
+                    local %s.3: i32
+                    %s.1 = copy i32 0
+                    %i.1 = copy i32 0
+                    %i.2 = copy %i.1
+                    %s.2 = copy %s.1
+                  start.0:
+                    %.t0.1 = lt %i.2, i32 10
+                    jz %.t0.1, break.0
+                    %s.3 = add %s.2, %i.2
+                    %i.3 = add %i.2, i32 1
+                    %i.2 = copy %i.3
+                    %s.2 = copy %s.3
+                    jump start.0
+                  break.0:
+                    return %s.2
+                }"#]],
+        );
+    }
+
+    #[test]
+    fn test_critical_edge() {
+        // The back edge leaves a block that also falls through to the return
+        let src = r#"
+global function main() -> i32 {
+    local %n: i32
+    local %x: i32
+    %n = copy i32 3
+    %x = copy i32 0
+  loop.0:
+    %x = add %x, i32 2
+    %n = sub %n, i32 1
+    jnz %n, loop.0
+    return %x
+}
+"#;
+        check_ssa(
+            src,
+            expect![[r#"
+                2: [0]
+                    %n.1 = copy i32 3
+                    %x.1 = copy i32 0
+                3: [2, 3]
+                    %n.2 = phi [2: %n.1, 3: %n.3]
+                    %x.2 = phi [2: %x.1, 3: %x.3]
+                  loop.0:
+                    %x.3 = add %x.2, i32 2
+                    %n.3 = sub %n.2, i32 1
+                    jnz %n.3, loop.0
+                4: [3]
+                    return %x.3
+
+                global function main() -> i32 {
+                    local %n: i32
+                    local %n.1: i32
+                    local %n.2: i32
+                    local %n.3: i32
+                    local %x: i32
+                    local %x.1: i32
+                    local %x.2: i32
+                    local %x.3: i32
+                    %n.1 = copy i32 3
+                    %x.1 = copy i32 0
+                    %n.2 = copy %n.1
+                    %x.2 = copy %x.1
+                  loop.0:
+                    %x.3 = add %x.2, i32 2
+                    %n.3 = sub %n.2, i32 1
+                    jnz %n.3, split.1
+                    return %x.3
+                  split.1:
+                    %n.2 = copy %n.3
+                    %x.2 = copy %x.3
+                    jump loop.0
+                }"#]],
+        );
+        let mut program = parse_program(src).unwrap();
+        let cfg = into_ssa(&mut program.functions[0]);
+        out_of_ssa(&mut program.functions[0], cfg);
+        assert_eq!(run(&program), "Ok(6) []");
+    }
+
+    #[test]
+    fn test_parallel_copies() {
+        let mut variables = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|name| (name.to_string(), Type::I32))
+            .collect();
+        let var = |name: &str| Value::Variable(name.into());
+        // A rotation of three, a copy out of it, and a copy to itself
+        let copies = vec![
+            ("a".into(), var("b")),
+            ("b".into(), var("c")),
+            ("c".into(), var("a")),
+            ("d".into(), var("a")),
+            ("d".into(), var("d")),
+        ];
+        let instructions = sequentialize(copies, &mut variables, &mut HashMap::new());
+        let text: Vec<_> = instructions.iter().map(ToString::to_string).collect();
+        expect![[r#"
+            [
+                "%d = copy %a",
+                "%.swap.1 = copy %a",
+                "%a = copy %b",
+                "%b = copy %c",
+                "%c = copy %.swap.1",
+            ]
+        "#]]
+        .assert_debug_eq(&text);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let sources = [
+            r#"
+            int collatz(long n) {
+                int steps = 0;
+                while (n != 1) {
+                    n = n % 2 ? 3 * n + 1 : n / 2;
+                    steps = steps + 1;
+                }
+                return steps;
+            }
+            int main(void) { return collatz(27); }
+            "#,
+            r#"
+            int putchar(int c);
+            int main(void) {
+                int a = 0, b = 1;
+                for (int i = 0; i < 10; i = i + 1) {
+                    if (i == 7)
+                        continue;
+                    int t = a + b;
+                    a = b;
+                    b = t;
+                    putchar(97 + a % 26);
+                }
+                int *p = &a;
+                *p = *p + 1;
+                return a && b || !a;
+            }
+            "#,
+            // The result of each `&&` is a temporary assigned on both arms,
+            // whose phis further down are dead
+            r#"
+            int main(void) {
+                int x = 1;
+                int *p = &x;
+                return p == &x && !(p != &x) && (p ? 1 : 0);
+            }
+            "#,
+        ];
+        for src in sources {
+            let mut program = lower(src);
+            let before = run(&program);
+            for function in &mut program.functions {
+                let cfg = into_ssa(function);
+                verify_ssa(function, &cfg).unwrap_or_else(|err| panic!("{err}\n{function}"));
+                out_of_ssa(function, cfg);
+            }
+            verify_program(&program).unwrap_or_else(|err| panic!("{err}\n{program}"));
+            assert_eq!(run(&program), before, "{program}");
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let mut program = parse_program(
+            r#"
+global function f(%n: i32) -> i32 {
+    local %x: i32
+    jz %n, else.0
+    %x = copy i32 1
+  else.0:
+    return %x
+}
+"#,
+        )
+        .unwrap();
+        let function = &mut program.functions[0];
+        let mut cfg = into_ssa(function);
+        verify_ssa(function, &cfg).unwrap();
+        // Read the version from one arm of the `if` after the join
+        let phi = cfg.blocks[4].phis.pop().unwrap();
+        cfg.blocks[4].instructions[1] = Instruction::Return(Some(phi.args[1].1.clone()));
+        expect![["block 4: `%x.1` is used where its assignment in block 3 doesn't dominate"]]
+            .assert_eq(&verify_ssa(function, &cfg).unwrap_err().to_string());
+    }
+}
@@ -0,0 +1,457 @@
+//! The TACKY intermediate representation.
+//!
+//! A program is a list of objects with static storage duration and a list of
+//! functions, whose bodies are flat lists of three-address [`Instruction`]s.
+//! Every operand is a [`Value`]: a typed constant, or a named object, which is
+//! either one of the function's variables (its parameters, locals and
+//! temporaries, each declared with its type) or a static object or function.
+//!
+//! Nothing is implicit: every conversion is its own instruction, the operands of
+//! a binary operator have the same type, and structured control flow has become
+//! labels and jumps.
+
+use std::collections::BTreeMap;
+
+/// The types of values and objects, down to what the target machine cares about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    I64,
+    U64,
+    F32,
+    F64,
+    /// A pointer, which behaves as a `U64` in arithmetic and comparisons.
+    Pointer,
+    /// An array, struct or union, as an opaque block of bytes.
+    Bytes {
+        size: u64,
+        align: u64,
+    },
+}
+impl Type {
+    pub fn size(self) -> u64 {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::I64 | Self::U64 | Self::F64 | Self::Pointer => 8,
+            Self::Bytes { size, .. } => size,
+        }
+    }
+    pub fn align(self) -> u64 {
+        match self {
+            Self::Bytes { align, .. } => align,
+            scalar => scalar.size(),
+        }
+    }
+    pub fn is_signed(self) -> bool {
+        matches!(self, Self::I8 | Self::I16 | Self::I32 | Self::I64)
+    }
+    /// Whether this is an integer type, counting pointers.
+    pub fn is_integer(self) -> bool {
+        !self.is_floating() && !self.is_bytes()
+    }
+    pub fn is_floating(self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+    pub fn is_bytes(self) -> bool {
+        matches!(self, Self::Bytes { .. })
+    }
+}
+
+/// A constant of a scalar type. Integers are stored as their bits, reduced to the
+/// width of the type, and floating values as the bits of an `f64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Constant {
+    pub ty: Type,
+    pub bits: u64,
+}
+impl Constant {
+    /// An integer or pointer constant, wrapping `value` to the width of `ty`.
+    pub fn integer(ty: Type, value: i128) -> Self {
+        debug_assert!(ty.is_integer(), "`{ty}` is not an integer type");
+        let bits = match ty.size() {
+            8 => value as u64,
+            size => value as u64 & ((1 << (size * 8)) - 1),
+        };
+        Self { ty, bits }
+    }
+    /// A floating constant, rounded to `f32` if that is its type.
+    pub fn floating(ty: Type, value: f64) -> Self {
+        let value = match ty {
+            Type::F32 => f64::from(value as f32),
+            _ => value,
+        };
+        Self {
+            ty,
+            bits: value.to_bits(),
+        }
+    }
+    /// The zero of a scalar type.
+    pub fn zero(ty: Type) -> Self {
+        match ty.is_floating() {
+            true => Self::floating(ty, 0.0),
+            false => Self::integer(ty, 0),
+        }
+    }
+    /// The value of an integer constant, sign-extended if its type is signed.
+    pub fn as_i128(self) -> i128 {
+        let bits = self.ty.size() * 8;
+        match self.ty.is_signed() {
+            true => i128::from((self.bits << (64 - bits)) as i64 >> (64 - bits)),
+            false => i128::from(self.bits),
+        }
+    }
+    pub fn as_f64(self) -> f64 {
+        f64::from_bits(self.bits)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Value {
+    Constant(Constant),
+    /// A variable of the function, or a static object or function, by name.
+    Variable(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Negate,
+    Complement,
+    /// Logical not, whose result is an `I32` 1 or 0 whatever the type of its operand.
+    Not,
+}
+
+/// Binary operators, whose operands have the same type. Comparisons give an `I32`
+/// 1 or 0, and every other operator a value of the type of its operands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    ShiftLeft,
+    /// Arithmetic for signed operands, logical for unsigned ones.
+    ShiftRight,
+    Equal,
+    NotEqual,
+    LessThan,
+    LessOrEqual,
+    GreaterThan,
+    GreaterOrEqual,
+}
+impl BinaryOperator {
+    pub fn is_comparison(self) -> bool {
+        matches!(
+            self,
+            Self::Equal
+                | Self::NotEqual
+                | Self::LessThan
+                | Self::LessOrEqual
+                | Self::GreaterThan
+                | Self::GreaterOrEqual
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Callee {
+    /// A function by name.
+    Direct(String),
+    /// A pointer to a function.
+    Indirect(Value),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    Return(Option<Value>),
+    /// A signed integer to a wider integer type.
+    SignExtend {
+        src: Value,
+        dst: Value,
+    },
+    /// An unsigned integer or pointer to a wider integer type.
+    ZeroExtend {
+        src: Value,
+        dst: Value,
+    },
+    /// An integer to a narrower integer type, keeping the low bits.
+    Truncate {
+        src: Value,
+        dst: Value,
+    },
+    /// An integer to a floating type, as signed or unsigned as the integer's type is.
+    IntToFloat {
+        src: Value,
+        dst: Value,
+    },
+    /// A floating value to an integer type, rounding toward zero.
+    FloatToInt {
+        src: Value,
+        dst: Value,
+    },
+    FloatExtend {
+        src: Value,
+        dst: Value,
+    },
+    FloatTruncate {
+        src: Value,
+        dst: Value,
+    },
+    Unary {
+        op: UnaryOperator,
+        src: Value,
+        dst: Value,
+    },
+    Binary {
+        op: BinaryOperator,
+        lhs: Value,
+        rhs: Value,
+        dst: Value,
+    },
+    /// Copy a value to a variable of the same size, which may reinterpret it, as
+    /// between a pointer and a `U64` or between signed and unsigned integers.
+    Copy {
+        src: Value,
+        dst: Value,
+    },
+    /// The address of a variable, static object or function.
+    GetAddress {
+        src: String,
+        dst: Value,
+    },
+    /// Read the object `ptr` points to into `dst`, which has the object's type.
+    Load {
+        ptr: Value,
+        dst: Value,
+    },
+    /// Write `src` to the object `ptr` points to.
+    Store {
+        src: Value,
+        ptr: Value,
+    },
+    /// `ptr + index * scale`, with the `I64` `index` counted in elements of `scale` bytes.
+    AddPtr {
+        ptr: Value,
+        index: Value,
+        scale: u64,
+        dst: Value,
+    },
+    /// Write `src` into the object `dst` at a byte offset.
+    CopyToOffset {
+        src: Value,
+        dst: String,
+        offset: u64,
+    },
+    /// Read `dst` out of the object `src` at a byte offset.
+    CopyFromOffset {
+        src: String,
+        offset: u64,
+        dst: Value,
+    },
+    Jump(String),
+    JumpIfZero(Value, String),
+    JumpIfNotZero(Value, String),
+    Label(String),
+    FunCall {
+        callee: Callee,
+        args: Vec<Value>,
+        /// Where the result goes, or `None` for a function returning `void`.
+        dst: Option<Value>,
+    },
+}
+
+impl Instruction {
+    /// The value an instruction assigns, which doesn't count a `Store` or a
+    /// `CopyToOffset`, since they write only part of some object.
+    pub fn dst(&self) -> Option<&Value> {
+        match self {
+            Self::SignExtend { dst, .. }
+            | Self::ZeroExtend { dst, .. }
+            | Self::Truncate { dst, .. }
+            | Self::IntToFloat { dst, .. }
+            | Self::FloatToInt { dst, .. }
+            | Self::FloatExtend { dst, .. }
+            | Self::FloatTruncate { dst, .. }
+            | Self::Unary { dst, .. }
+            | Self::Binary { dst, .. }
+            | Self::Copy { dst, .. }
+            | Self::GetAddress { dst, .. }
+            | Self::Load { dst, .. }
+            | Self::AddPtr { dst, .. }
+            | Self::CopyFromOffset { dst, .. } => Some(dst),
+            Self::FunCall { dst, .. } => dst.as_ref(),
+            Self::Return(_)
+            | Self::Store { .. }
+            | Self::CopyToOffset { .. }
+            | Self::Jump(_)
+            | Self::JumpIfZero(..)
+            | Self::JumpIfNotZero(..)
+            | Self::Label(_) => None,
+        }
+    }
+
+    pub fn dst_mut(&mut self) -> Option<&mut Value> {
+        match self {
+            Self::SignExtend { dst, .. }
+            | Self::ZeroExtend { dst, .. }
+            | Self::Truncate { dst, .. }
+            | Self::IntToFloat { dst, .. }
+            | Self::FloatToInt { dst, .. }
+            | Self::FloatExtend { dst, .. }
+            | Self::FloatTruncate { dst, .. }
+            | Self::Unary { dst, .. }
+            | Self::Binary { dst, .. }
+            | Self::Copy { dst, .. }
+            | Self::GetAddress { dst, .. }
+            | Self::Load { dst, .. }
+            | Self::AddPtr { dst, .. }
+            | Self::CopyFromOffset { dst, .. } => Some(dst),
+            Self::FunCall { dst, .. } => dst.as_mut(),
+            Self::Return(_)
+            | Self::Store { .. }
+            | Self::CopyToOffset { .. }
+            | Self::Jump(_)
+            | Self::JumpIfZero(..)
+            | Self::JumpIfNotZero(..)
+            | Self::Label(_) => None,
+        }
+    }
+
+    /// The values an instruction reads, leaving out objects named by a plain
+    /// string, like the source of a `GetAddress`.
+    pub fn sources(&self) -> Vec<&Value> {
+        match self {
+            Self::Return(value) => value.iter().collect(),
+            Self::SignExtend { src, .. }
+            | Self::ZeroExtend { src, .. }
+            | Self::Truncate { src, .. }
+            | Self::IntToFloat { src, .. }
+            | Self::FloatToInt { src, .. }
+            | Self::FloatExtend { src, .. }
+            | Self::FloatTruncate { src, .. }
+            | Self::Unary { src, .. }
+            | Self::Copy { src, .. }
+            | Self::CopyToOffset { src, .. }
+            | Self::Load { ptr: src, .. }
+            | Self::JumpIfZero(src, _)
+            | Self::JumpIfNotZero(src, _) => vec![src],
+            Self::Binary { lhs, rhs, .. } => vec![lhs, rhs],
+            Self::Store { src, ptr } => vec![src, ptr],
+            Self::AddPtr { ptr, index, .. } => vec![ptr, index],
+            Self::FunCall { callee, args, .. } => match callee {
+                Callee::Direct(_) => args.iter().collect(),
+                Callee::Indirect(ptr) => std::iter::once(ptr).chain(args).collect(),
+            },
+            Self::GetAddress { .. }
+            | Self::CopyFromOffset { .. }
+            | Self::Jump(_)
+            | Self::Label(_) => Vec::new(),
+        }
+    }
+
+    pub fn sources_mut(&mut self) -> Vec<&mut Value> {
+        match self {
+            Self::Return(value) => value.iter_mut().collect(),
+            Self::SignExtend { src, .. }
+            | Self::ZeroExtend { src, .. }
+            | Self::Truncate { src, .. }
+            | Self::IntToFloat { src, .. }
+            | Self::FloatToInt { src, .. }
+            | Self::FloatExtend { src, .. }
+            | Self::FloatTruncate { src, .. }
+            | Self::Unary { src, .. }
+            | Self::Copy { src, .. }
+            | Self::CopyToOffset { src, .. }
+            | Self::Load { ptr: src, .. }
+            | Self::JumpIfZero(src, _)
+            | Self::JumpIfNotZero(src, _) => vec![src],
+            Self::Binary { lhs, rhs, .. } => vec![lhs, rhs],
+            Self::Store { src, ptr } => vec![src, ptr],
+            Self::AddPtr { ptr, index, .. } => vec![ptr, index],
+            Self::FunCall { callee, args, .. } => match callee {
+                Callee::Direct(_) => args.iter_mut().collect(),
+                Callee::Indirect(ptr) => std::iter::once(ptr).chain(args).collect(),
+            },
+            Self::GetAddress { .. }
+            | Self::CopyFromOffset { .. }
+            | Self::Jump(_)
+            | Self::Label(_) => Vec::new(),
+        }
+    }
+
+    /// The object an instruction names by a plain string: the source of a
+    /// `GetAddress`, or the object of a `CopyToOffset` or `CopyFromOffset`.
+    pub fn named_object(&self) -> Option<&str> {
+        match self {
+            Self::GetAddress { src: name, .. }
+            | Self::CopyToOffset { dst: name, .. }
+            | Self::CopyFromOffset { src: name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The label a jump may go to.
+    pub fn target(&self) -> Option<&str> {
+        match self {
+            Self::Jump(label) | Self::JumpIfZero(_, label) | Self::JumpIfNotZero(_, label) => {
+                Some(label)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaticInit {
+    /// This many bytes of zeros.
+    Zero(u64),
+    /// An integer, or the bits of a floating value, stored in this many bytes.
+    Integer(u64, u64),
+    Bytes(Vec<u8>),
+    /// The address of a static object or function plus a byte offset, stored in 8 bytes.
+    Address(String, i64),
+}
+
+/// An object with static storage duration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticVariable {
+    pub name: String,
+    /// Whether it is visible to other translation units.
+    pub global: bool,
+    pub ty: Type,
+    /// The initial contents, which cover all of it, or `None` if it is defined elsewhere.
+    pub init: Option<Vec<StaticInit>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    /// Whether it is visible to other translation units.
+    pub global: bool,
+    /// Whether it was defined `inline`, which makes it more worth inlining.
+    pub inline: bool,
+    pub params: Vec<String>,
+    /// Whether it takes more arguments after its parameters, with `...`.
+    pub variadic: bool,
+    /// `None` for a function returning `void`.
+    pub return_type: Option<Type>,
+    /// The type of every parameter, local and temporary, by name.
+    pub variables: BTreeMap<String, Type>,
+    pub body: Vec<Instruction>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Program {
+    pub statics: Vec<StaticVariable>,
+    pub functions: Vec<Function>,
+}
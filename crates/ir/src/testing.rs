@@ -0,0 +1,29 @@
+//! Helpers for the tests of this crate.
+
+use wacc_lexer::Lexer;
+use wacc_parser::Parser;
+use wacc_semantics::{resolve::Resolver, symbols::SymbolCollector, typecheck::TypeChecker};
+
+use crate::{lower::Lowerer, tacky::Program, verify::verify_program};
+
+/// The TACKY for a valid C program, which must be well formed.
+pub fn lower(src: &str) -> Program {
+    let tokens = String::lex_c(src);
+    let mut parser = Parser::new(src, &tokens);
+    let mut program = parser.parse_program();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    let mut resolver = Resolver::new();
+    let resolution = resolver.resolve_program(&mut program);
+    assert!(resolver.errors().is_empty(), "{:?}", resolver.errors());
+    let mut checker = TypeChecker::new(&resolution);
+    checker.check_program(&mut program);
+    assert!(checker.errors().is_empty(), "{:?}", checker.errors());
+    let mut collector = SymbolCollector::new(&resolution);
+    let symbols = collector.collect_program(&program);
+    assert!(collector.errors().is_empty(), "{:?}", collector.errors());
+    let tacky = Lowerer::new(&resolution, &symbols).lower_program(&program);
+    if let Err(err) = verify_program(&tacky) {
+        panic!("{err}\n{tacky}");
+    }
+    tacky
+}
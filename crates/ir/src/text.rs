@@ -0,0 +1,961 @@
+//! The text form of TACKY, for reading the IR and writing it by hand in tests.
+//!
+//! [`Display`](fmt::Display) prints a program and [`parse_program`] reads it back,
+//! so that printing what was parsed gives the same text. A program is its static
+//! objects, one per line, followed by its functions:
+//!
+//! ```text
+//! global static %counter: i32 = { integer 4 0x0 }
+//! static %.Lstring.0: bytes(3, 1) = { bytes "hi\x00" }
+//! global static %declared: i64
+//!
+//! global function add(%a.0: i32, %b.1: i32) -> i32 {
+//!     local %.t0: i32
+//!     %.t0 = add %a.0, %b.1
+//!     jz %.t0, zero.0
+//!     return %.t0
+//!   zero.0:
+//!     return i32 1
+//! }
+//! ```
+//!
+//! Variables and static objects are written with a leading `%`, and constants
+//! with their type, as in `i32 -1` or `f64 0.5`. A static object without an
+//! initializer is defined in another translation unit, a function defined
+//! `inline` is written `inline function`, and a variadic function ends its
+//! parameters with `...`.
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use crate::tacky::{
+    BinaryOperator, Callee, Constant, Function, Instruction, Program, StaticInit, StaticVariable,
+    Type, UnaryOperator, Value,
+};
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::I8 => write!(f, "i8"),
+            Self::U8 => write!(f, "u8"),
+            Self::I16 => write!(f, "i16"),
+            Self::U16 => write!(f, "u16"),
+            Self::I32 => write!(f, "i32"),
+            Self::U32 => write!(f, "u32"),
+            Self::I64 => write!(f, "i64"),
+            Self::U64 => write!(f, "u64"),
+            Self::F32 => write!(f, "f32"),
+            Self::F64 => write!(f, "f64"),
+            Self::Pointer => write!(f, "ptr"),
+            Self::Bytes { size, align } => write!(f, "bytes({size}, {align})"),
+        }
+    }
+}
+
+impl fmt::Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ty.is_floating() {
+            true => write!(f, "{} {:?}", self.ty, self.as_f64()),
+            false => write!(f, "{} {}", self.ty, self.as_i128()),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Constant(constant) => write!(f, "{constant}"),
+            Self::Variable(name) => write!(f, "%{name}"),
+        }
+    }
+}
+
+impl fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Negate => "neg",
+            Self::Complement => "not",
+            Self::Not => "lnot",
+        })
+    }
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Add => "add",
+            Self::Subtract => "sub",
+            Self::Multiply => "mul",
+            Self::Divide => "div",
+            Self::Remainder => "rem",
+            Self::BitwiseAnd => "and",
+            Self::BitwiseOr => "or",
+            Self::BitwiseXor => "xor",
+            Self::ShiftLeft => "shl",
+            Self::ShiftRight => "shr",
+            Self::Equal => "eq",
+            Self::NotEqual => "ne",
+            Self::LessThan => "lt",
+            Self::LessOrEqual => "le",
+            Self::GreaterThan => "gt",
+            Self::GreaterOrEqual => "ge",
+        })
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Return(None) => write!(f, "return"),
+            Self::Return(Some(value)) => write!(f, "return {value}"),
+            Self::SignExtend { src, dst } => write!(f, "{dst} = sext {src}"),
+            Self::ZeroExtend { src, dst } => write!(f, "{dst} = zext {src}"),
+            Self::Truncate { src, dst } => write!(f, "{dst} = trunc {src}"),
+            Self::IntToFloat { src, dst } => write!(f, "{dst} = itof {src}"),
+            Self::FloatToInt { src, dst } => write!(f, "{dst} = ftoi {src}"),
+            Self::FloatExtend { src, dst } => write!(f, "{dst} = fext {src}"),
+            Self::FloatTruncate { src, dst } => write!(f, "{dst} = ftrunc {src}"),
+            Self::Unary { op, src, dst } => write!(f, "{dst} = {op} {src}"),
+            Self::Binary { op, lhs, rhs, dst } => write!(f, "{dst} = {op} {lhs}, {rhs}"),
+            Self::Copy { src, dst } => write!(f, "{dst} = copy {src}"),
+            Self::GetAddress { src, dst } => write!(f, "{dst} = addr %{src}"),
+            Self::Load { ptr, dst } => write!(f, "{dst} = load {ptr}"),
+            Self::Store { src, ptr } => write!(f, "store {src}, {ptr}"),
+            Self::AddPtr {
+                ptr,
+                index,
+                scale,
+                dst,
+            } => write!(f, "{dst} = addptr {ptr}, {index}, {scale}"),
+            Self::CopyToOffset { src, dst, offset } => {
+                write!(f, "copy_to_offset {src}, %{dst}, {offset}")
+            }
+            Self::CopyFromOffset { src, offset, dst } => {
+                write!(f, "{dst} = copy_from_offset %{src}, {offset}")
+            }
+            Self::Jump(label) => write!(f, "jump {label}"),
+            Self::JumpIfZero(condition, label) => write!(f, "jz {condition}, {label}"),
+            Self::JumpIfNotZero(condition, label) => write!(f, "jnz {condition}, {label}"),
+            Self::Label(label) => write!(f, "{label}:"),
+            Self::FunCall { callee, args, dst } => {
+                if let Some(dst) = dst {
+                    write!(f, "{dst} = ")?;
+                }
+                match callee {
+                    Callee::Direct(name) => write!(f, "call {name}(")?,
+                    Callee::Indirect(pointer) => write!(f, "call *{pointer}(")?,
+                }
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{arg}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl fmt::Display for StaticInit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Zero(size) => write!(f, "zero {size}"),
+            Self::Integer(size, bits) => write!(f, "integer {size} {bits:#x}"),
+            Self::Bytes(bytes) => write!(f, "bytes \"{}\"", bytes.escape_ascii()),
+            Self::Address(name, offset) => write!(f, "address %{name} {offset:+}"),
+        }
+    }
+}
+
+impl fmt::Display for StaticVariable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.global {
+            write!(f, "global ")?;
+        }
+        write!(f, "static %{}: {}", self.name, self.ty)?;
+        if let Some(init) = &self.init {
+            write!(f, " = {{ ")?;
+            for (i, item) in init.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{item}")?;
+            }
+            write!(f, " }}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A function as its signature, the declarations of its locals and temporaries,
+/// then its body with one instruction per line and labels outdented.
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.global {
+            write!(f, "global ")?;
+        }
+        if self.inline {
+            write!(f, "inline ")?;
+        }
+        write!(f, "function {}(", self.name)?;
+        for (i, param) in self.params.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "%{param}: {}", self.variables[param])?;
+        }
+        if self.variadic {
+            write!(f, ", ...")?;
+        }
+        match self.return_type {
+            Some(ty) => writeln!(f, ") -> {ty} {{")?,
+            None => writeln!(f, ") -> void {{")?,
+        }
+        // In order of the numbers they end in, so `.t2` comes before `.t10`.
+        let mut locals = self
+            .variables
+            .iter()
+            .filter(|(name, _)| !self.params.contains(name))
+            .collect::<Vec<_>>();
+        locals.sort_by_key(|(name, _)| {
+            let stem = name.trim_end_matches(|c: char| c.is_ascii_digit());
+            (stem, name[stem.len()..].parse::<u64>().ok())
+        });
+        for (name, ty) in locals {
+            writeln!(f, "    local %{name}: {ty}")?;
+        }
+        for instruction in &self.body {
+            match instruction {
+                Instruction::Label(_) => writeln!(f, "  {instruction}")?,
+                _ => writeln!(f, "    {instruction}")?,
+            }
+        }
+        write!(f, "}}")
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for variable in &self.statics {
+            writeln!(f, "{variable}")?;
+        }
+        for (i, function) in self.functions.iter().enumerate() {
+            if i > 0 || !self.statics.is_empty() {
+                writeln!(f)?;
+            }
+            writeln!(f, "{function}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("line {line}: {message}")]
+pub struct ParseError {
+    line: usize,
+    message: String,
+}
+impl ParseError {
+    pub fn line(&self) -> usize {
+        self.line
+    }
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Read a program in the form [`Program`]'s `Display` prints it.
+pub fn parse_program(src: &str) -> Result<Program, ParseError> {
+    let mut lines = Vec::new();
+    for (i, text) in src.lines().enumerate() {
+        let tokens = tokenize(text).map_err(|message| ParseError {
+            line: i + 1,
+            message,
+        })?;
+        if !tokens.is_empty() {
+            lines.push(Line {
+                number: i + 1,
+                tokens,
+                position: 0,
+            });
+        }
+    }
+    let mut lines = lines.into_iter();
+    let mut program = Program::default();
+    while let Some(mut line) = lines.next() {
+        let global = line.eat_word("global");
+        let inline = line.eat_word("inline");
+        match line.next()? {
+            Token::Word(word) if word == "static" && !inline => {
+                program.statics.push(static_variable(line, global)?)
+            }
+            Token::Word(word) if word == "function" => program
+                .functions
+                .push(function(line, global, inline, &mut lines)?),
+            token => {
+                return Err(line.error(format!("expected `static` or `function`, found {token}")))
+            }
+        }
+    }
+    Ok(program)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A keyword, a number, or the name of a function or label.
+    Word(String),
+    /// A name after a `%`.
+    Variable(String),
+    String(Vec<u8>),
+    Punct(char),
+    Arrow,
+}
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Word(word) => write!(f, "`{word}`"),
+            Self::Variable(name) => write!(f, "`%{name}`"),
+            Self::String(_) => write!(f, "a string"),
+            Self::Punct(c) => write!(f, "`{c}`"),
+            Self::Arrow => write!(f, "`->`"),
+        }
+    }
+}
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'.' | b'+' | b'-')
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let bytes = text.as_bytes();
+    let word = |start: usize| {
+        let end = bytes[start..]
+            .iter()
+            .position(|&byte| !is_word_byte(byte))
+            .map_or(bytes.len(), |length| start + length);
+        (text[start..end].to_string(), end)
+    };
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            byte if byte.is_ascii_whitespace() => i += 1,
+            b'-' if bytes.get(i + 1) == Some(&b'>') => {
+                tokens.push(Token::Arrow);
+                i += 2;
+            }
+            b'%' => {
+                let (name, end) = word(i + 1);
+                if name.is_empty() {
+                    return Err("expected a name after `%`".into());
+                }
+                tokens.push(Token::Variable(name));
+                i = end;
+            }
+            b'"' => {
+                let (string, end) = string(bytes, i + 1)?;
+                tokens.push(Token::String(string));
+                i = end;
+            }
+            byte @ (b'(' | b')' | b',' | b':' | b'=' | b'{' | b'}' | b'*') => {
+                tokens.push(Token::Punct(byte.into()));
+                i += 1;
+            }
+            byte if is_word_byte(byte) => {
+                let (word, end) = word(i);
+                tokens.push(Token::Word(word));
+                i = end;
+            }
+            _ => {
+                let c = text[i..].chars().next().unwrap_or_default();
+                return Err(format!("unexpected character `{c}`"));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// The contents of a string starting at `start`, just after its opening quote,
+/// with the escapes `escape_ascii` writes, and where it ends.
+fn string(bytes: &[u8], start: usize) -> Result<(Vec<u8>, usize), String> {
+    let mut string = Vec::new();
+    let mut i = start;
+    loop {
+        match bytes.get(i) {
+            None => return Err("unterminated string".into()),
+            Some(b'"') => return Ok((string, i + 1)),
+            Some(b'\\') => {
+                let (byte, length) = match bytes.get(i + 1) {
+                    Some(b'n') => (b'\n', 2),
+                    Some(b'r') => (b'\r', 2),
+                    Some(b't') => (b'\t', 2),
+                    Some(&byte @ (b'\\' | b'\'' | b'"')) => (byte, 2),
+                    Some(b'x') => {
+                        let digits = bytes.get(i + 2..i + 4).unwrap_or_default();
+                        let byte = std::str::from_utf8(digits)
+                            .ok()
+                            .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                            .ok_or("expected two hex digits after `\\x`")?;
+                        (byte, 4)
+                    }
+                    _ => return Err("unknown escape in string".into()),
+                };
+                string.push(byte);
+                i += length;
+            }
+            Some(&byte) => {
+                string.push(byte);
+                i += 1;
+            }
+        }
+    }
+}
+
+/// The tokens of one line, and how many of them have been read.
+struct Line {
+    number: usize,
+    tokens: Vec<Token>,
+    position: usize,
+}
+impl Line {
+    fn error(&self, message: String) -> ParseError {
+        ParseError {
+            line: self.number,
+            message,
+        }
+    }
+
+    fn expected(&self, what: &str) -> ParseError {
+        match self.tokens.get(self.position) {
+            Some(token) => self.error(format!("expected {what}, found {token}")),
+            None => self.error(format!("expected {what} at end of line")),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token, ParseError> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| self.error("unexpected end of line".into()))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn end(&self) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(token) => Err(self.error(format!("unexpected {token} at end of line"))),
+            None => Ok(()),
+        }
+    }
+
+    fn word(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Some(Token::Word(word)) => {
+                let word = word.clone();
+                self.position += 1;
+                Ok(word)
+            }
+            _ => Err(self.expected("a name")),
+        }
+    }
+
+    fn eat_word(&mut self, keyword: &str) -> bool {
+        let is_keyword = matches!(self.peek(), Some(Token::Word(word)) if word == keyword);
+        if is_keyword {
+            self.position += 1;
+        }
+        is_keyword
+    }
+
+    fn punct(&mut self, c: char) -> Result<(), ParseError> {
+        match self.eat_punct(c) {
+            true => Ok(()),
+            false => Err(self.expected(&format!("`{c}`"))),
+        }
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        let is_punct = self.peek() == Some(&Token::Punct(c));
+        if is_punct {
+            self.position += 1;
+        }
+        is_punct
+    }
+
+    fn variable(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Some(Token::Variable(name)) => {
+                let name = name.clone();
+                self.position += 1;
+                Ok(name)
+            }
+            _ => Err(self.expected("a `%` name")),
+        }
+    }
+
+    fn number<T: FromStr>(&mut self) -> Result<T, ParseError> {
+        let number = match self.peek() {
+            Some(Token::Word(word)) => word.parse().ok(),
+            _ => None,
+        };
+        let number = number.ok_or_else(|| self.expected("a number"))?;
+        self.position += 1;
+        Ok(number)
+    }
+
+    fn ty(&mut self) -> Result<Type, ParseError> {
+        let ty = match self.word().map_err(|_| self.expected("a type"))?.as_str() {
+            "i8" => Type::I8,
+            "u8" => Type::U8,
+            "i16" => Type::I16,
+            "u16" => Type::U16,
+            "i32" => Type::I32,
+            "u32" => Type::U32,
+            "i64" => Type::I64,
+            "u64" => Type::U64,
+            "f32" => Type::F32,
+            "f64" => Type::F64,
+            "ptr" => Type::Pointer,
+            "bytes" => {
+                self.punct('(')?;
+                let size = self.number()?;
+                self.punct(',')?;
+                let align = self.number()?;
+                self.punct(')')?;
+                Type::Bytes { size, align }
+            }
+            word => return Err(self.error(format!("unknown type `{word}`"))),
+        };
+        Ok(ty)
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        if let Some(Token::Variable(_)) = self.peek() {
+            return Ok(Value::Variable(self.variable()?));
+        }
+        let ty = self.ty().map_err(|_| self.expected("a value"))?;
+        let constant = match ty {
+            Type::F32 | Type::F64 => Constant::floating(ty, self.number()?),
+            Type::Bytes { .. } => return Err(self.error(format!("no constants have type `{ty}`"))),
+            _ => {
+                let value = self.number()?;
+                let constant = Constant::integer(ty, value);
+                if constant.as_i128() != value {
+                    return Err(self.error(format!("`{value}` is out of range for `{ty}`")));
+                }
+                constant
+            }
+        };
+        Ok(Value::Constant(constant))
+    }
+}
+
+fn static_variable(mut line: Line, global: bool) -> Result<StaticVariable, ParseError> {
+    let name = line.variable()?;
+    line.punct(':')?;
+    let ty = line.ty()?;
+    let mut init = None;
+    if line.eat_punct('=') {
+        line.punct('{')?;
+        let mut items = Vec::new();
+        loop {
+            items.push(static_init(&mut line)?);
+            if !line.eat_punct(',') {
+                break;
+            }
+        }
+        line.punct('}')?;
+        init = Some(items);
+    }
+    line.end()?;
+    Ok(StaticVariable {
+        name,
+        global,
+        ty,
+        init,
+    })
+}
+
+fn static_init(line: &mut Line) -> Result<StaticInit, ParseError> {
+    let init = match line.word()?.as_str() {
+        "zero" => StaticInit::Zero(line.number()?),
+        "integer" => {
+            let size = line.number()?;
+            let bits = match line.word()? {
+                word if word.starts_with("0x") => u64::from_str_radix(&word[2..], 16).ok(),
+                word => word.parse().ok(),
+            };
+            let bits = bits.ok_or_else(|| line.error("expected the bits of an integer".into()))?;
+            StaticInit::Integer(size, bits)
+        }
+        "bytes" => match line.next()? {
+            Token::String(bytes) => StaticInit::Bytes(bytes),
+            token => return Err(line.error(format!("expected a string, found {token}"))),
+        },
+        "address" => {
+            let name = line.variable()?;
+            StaticInit::Address(name, line.number()?)
+        }
+        word => return Err(line.error(format!("unknown initializer `{word}`"))),
+    };
+    Ok(init)
+}
+
+/// A function from its first line, which `global`, `inline` and `function`
+/// have been read from, to its closing brace.
+fn function(
+    mut line: Line,
+    global: bool,
+    inline: bool,
+    lines: &mut impl Iterator<Item = Line>,
+) -> Result<Function, ParseError> {
+    let name = line.word()?;
+    let mut params = Vec::new();
+    let mut variadic = false;
+    let mut variables = BTreeMap::new();
+    line.punct('(')?;
+    if !line.eat_punct(')') {
+        loop {
+            if !params.is_empty() && line.eat_word("...") {
+                variadic = true;
+                break;
+            }
+            let param = line.variable()?;
+            line.punct(':')?;
+            let ty = line.ty()?;
+            declare(&mut variables, &line, &param, ty)?;
+            params.push(param);
+            if !line.eat_punct(',') {
+                break;
+            }
+        }
+        line.punct(')')?;
+    }
+    if line.next()? != Token::Arrow {
+        return Err(line.error("expected `->` before the return type".into()));
+    }
+    let return_type = match line.eat_word("void") {
+        true => None,
+        false => Some(line.ty()?),
+    };
+    line.punct('{')?;
+    line.end()?;
+
+    let mut body = Vec::new();
+    loop {
+        let Some(mut line) = lines.next() else {
+            return Err(ParseError {
+                line: line.number,
+                message: format!("function `{name}` has no closing `}}`"),
+            });
+        };
+        if line.eat_punct('}') {
+            line.end()?;
+            break;
+        }
+        if line.eat_word("local") {
+            let local = line.variable()?;
+            line.punct(':')?;
+            let ty = line.ty()?;
+            declare(&mut variables, &line, &local, ty)?;
+        } else {
+            body.push(instruction(&mut line)?);
+        }
+        line.end()?;
+    }
+    Ok(Function {
+        name,
+        global,
+        inline,
+        params,
+        variadic,
+        return_type,
+        variables,
+        body,
+    })
+}
+
+fn declare(
+    variables: &mut BTreeMap<String, Type>,
+    line: &Line,
+    name: &str,
+    ty: Type,
+) -> Result<(), ParseError> {
+    match variables.insert(name.into(), ty) {
+        Some(_) => Err(line.error(format!("`%{name}` is declared twice"))),
+        None => Ok(()),
+    }
+}
+
+fn instruction(line: &mut Line) -> Result<Instruction, ParseError> {
+    // A label is a name and a colon on a line of their own.
+    if let [Token::Word(label), Token::Punct(':')] = line.tokens.as_slice() {
+        let label = label.clone();
+        line.position = 2;
+        return Ok(Instruction::Label(label));
+    }
+    let word = match line.next()? {
+        Token::Variable(dst) => {
+            line.punct('=')?;
+            return assignment(line, Value::Variable(dst));
+        }
+        Token::Word(word) => word,
+        token => return Err(line.error(format!("expected an instruction, found {token}"))),
+    };
+    let instruction = match word.as_str() {
+        "return" => match line.peek() {
+            Some(_) => Instruction::Return(Some(line.value()?)),
+            None => Instruction::Return(None),
+        },
+        "store" => {
+            let src = line.value()?;
+            line.punct(',')?;
+            Instruction::Store {
+                src,
+                ptr: line.value()?,
+            }
+        }
+        "copy_to_offset" => {
+            let src = line.value()?;
+            line.punct(',')?;
+            let dst = line.variable()?;
+            line.punct(',')?;
+            Instruction::CopyToOffset {
+                src,
+                dst,
+                offset: line.number()?,
+            }
+        }
+        "jump" => Instruction::Jump(line.word()?),
+        "jz" | "jnz" => {
+            let condition = line.value()?;
+            line.punct(',')?;
+            let label = line.word()?;
+            match word.as_str() {
+                "jz" => Instruction::JumpIfZero(condition, label),
+                _ => Instruction::JumpIfNotZero(condition, label),
+            }
+        }
+        "call" => call(line, None)?,
+        word => return Err(line.error(format!("unknown instruction `{word}`"))),
+    };
+    Ok(instruction)
+}
+
+/// An instruction after its `%dst =`.
+fn assignment(line: &mut Line, dst: Value) -> Result<Instruction, ParseError> {
+    let operation = line.word()?;
+    let unary = match operation.as_str() {
+        "neg" => Some(UnaryOperator::Negate),
+        "not" => Some(UnaryOperator::Complement),
+        "lnot" => Some(UnaryOperator::Not),
+        _ => None,
+    };
+    if let Some(op) = unary {
+        let src = line.value()?;
+        return Ok(Instruction::Unary { op, src, dst });
+    }
+    if let Some(op) = binary_operator(&operation) {
+        let lhs = line.value()?;
+        line.punct(',')?;
+        let rhs = line.value()?;
+        return Ok(Instruction::Binary { op, lhs, rhs, dst });
+    }
+    let instruction = match operation.as_str() {
+        "sext" => Instruction::SignExtend {
+            src: line.value()?,
+            dst,
+        },
+        "zext" => Instruction::ZeroExtend {
+            src: line.value()?,
+            dst,
+        },
+        "trunc" => Instruction::Truncate {
+            src: line.value()?,
+            dst,
+        },
+        "itof" => Instruction::IntToFloat {
+            src: line.value()?,
+            dst,
+        },
+        "ftoi" => Instruction::FloatToInt {
+            src: line.value()?,
+            dst,
+        },
+        "fext" => Instruction::FloatExtend {
+            src: line.value()?,
+            dst,
+        },
+        "ftrunc" => Instruction::FloatTruncate {
+            src: line.value()?,
+            dst,
+        },
+        "copy" => Instruction::Copy {
+            src: line.value()?,
+            dst,
+        },
+        "addr" => Instruction::GetAddress {
+            src: line.variable()?,
+            dst,
+        },
+        "load" => Instruction::Load {
+            ptr: line.value()?,
+            dst,
+        },
+        "addptr" => {
+            let ptr = line.value()?;
+            line.punct(',')?;
+            let index = line.value()?;
+            line.punct(',')?;
+            Instruction::AddPtr {
+                ptr,
+                index,
+                scale: line.number()?,
+                dst,
+            }
+        }
+        "copy_from_offset" => {
+            let src = line.variable()?;
+            line.punct(',')?;
+            Instruction::CopyFromOffset {
+                src,
+                offset: line.number()?,
+                dst,
+            }
+        }
+        "call" => call(line, Some(dst))?,
+        operation => return Err(line.error(format!("unknown operation `{operation}`"))),
+    };
+    Ok(instruction)
+}
+
+fn binary_operator(name: &str) -> Option<BinaryOperator> {
+    let op = match name {
+        "add" => BinaryOperator::Add,
+        "sub" => BinaryOperator::Subtract,
+        "mul" => BinaryOperator::Multiply,
+        "div" => BinaryOperator::Divide,
+        "rem" => BinaryOperator::Remainder,
+        "and" => BinaryOperator::BitwiseAnd,
+        "or" => BinaryOperator::BitwiseOr,
+        "xor" => BinaryOperator::BitwiseXor,
+        "shl" => BinaryOperator::ShiftLeft,
+        "shr" => BinaryOperator::ShiftRight,
+        "eq" => BinaryOperator::Equal,
+        "ne" => BinaryOperator::NotEqual,
+        "lt" => BinaryOperator::LessThan,
+        "le" => BinaryOperator::LessOrEqual,
+        "gt" => BinaryOperator::GreaterThan,
+        "ge" => BinaryOperator::GreaterOrEqual,
+        _ => return None,
+    };
+    Some(op)
+}
+
+/// A call after its `call`.
+fn call(line: &mut Line, dst: Option<Value>) -> Result<Instruction, ParseError> {
+    let callee = match line.eat_punct('*') {
+        true => Callee::Indirect(line.value()?),
+        false => Callee::Direct(line.word()?),
+    };
+    line.punct('(')?;
+    let mut args = Vec::new();
+    if !line.eat_punct(')') {
+        loop {
+            args.push(line.value()?);
+            if !line.eat_punct(',') {
+                break;
+            }
+        }
+        line.punct(')')?;
+    }
+    Ok(Instruction::FunCall { callee, args, dst })
+}
+
+#[cfg(test)]
+mod text_tests {
+    use std::{fs, path::Path};
+
+    use expect_test::{expect, Expect};
+
+    use super::parse_program;
+    use crate::verify::verify_program;
+
+    /// The error from parsing a program that isn't valid.
+    fn check_error(src: &str, expect: Expect) {
+        match parse_program(src) {
+            Ok(program) => panic!("expected an error, parsed:\n{program}"),
+            Err(err) => expect.assert_eq(&err.to_string()),
+        }
+    }
+
+    /// Every program in `testdata` must print back as it was read, and be well
+    /// formed, since the interpreter's tests run them too.
+    #[test]
+    fn test_round_trip() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let mut checked = 0;
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path
+                .extension()
+                .is_none_or(|extension| extension != "tacky")
+            {
+                continue;
+            }
+            let src = fs::read_to_string(&path).unwrap();
+            let program = parse_program(&src).unwrap_or_else(|err| panic!("{path:?}: {err}"));
+            assert_eq!(program.to_string(), src, "{path:?}");
+            verify_program(&program).unwrap_or_else(|err| panic!("{path:?}: {err}"));
+            checked += 1;
+        }
+        assert!(checked > 0, "no .tacky files in testdata");
+    }
+
+    #[test]
+    fn test_ill_formed_testdata() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/memory.tacky");
+        let src = fs::read_to_string(path)
+            .unwrap()
+            .replace("load %.t0", "load %counter");
+        let err = verify_program(&parse_program(&src).unwrap()).unwrap_err();
+        expect!["in `main`: `%.t2 = load %counter` goes through a `i32`, not a pointer"]
+            .assert_eq(&err.to_string());
+    }
+
+    #[test]
+    fn test_errors() {
+        check_error(
+            "function f() -> i32 {\n    return i8 300\n}\n",
+            expect!["line 2: `300` is out of range for `i8`"],
+        );
+        check_error(
+            "function f() -> void {\n    %x = frob %y\n}\n",
+            expect!["line 2: unknown operation `frob`"],
+        );
+        check_error(
+            "function f(%x: i32) -> void {\n    local %x: i64\n}\n",
+            expect!["line 2: `%x` is declared twice"],
+        );
+        check_error(
+            "function f() -> void {\n    return\n",
+            expect!["line 1: function `f` has no closing `}`"],
+        );
+        check_error(
+            "static %s: bytes(2, 1) = { bytes \"a\\q\" }\n",
+            expect!["line 1: unknown escape in string"],
+        );
+        check_error(
+            "global static %x: i32 = { zero 4 } extra\n",
+            expect!["line 1: unexpected `extra` at end of line"],
+        );
+        check_error(
+            "variable %x: i32\n",
+            expect!["line 1: expected `static` or `function`, found `variable`"],
+        );
+    }
+}
@@ -0,0 +1,644 @@
+//! Checking that TACKY is well formed.
+//!
+//! Lowering and every pass should leave functions that:
+//!
+//! - can't run off the end of their body, which ends in a return or a jump;
+//! - define each label once, and jump only to labels they define;
+//! - declare every variable they name, unless it is a static object;
+//! - give the operands of each instruction the types the instruction expects,
+//!   as described on [`Instruction`];
+//! - assign every temporary before reading it, on every path to the read.
+//!
+//! After a pass deletes a function, nothing may call it or take its address
+//! any more, since it was defined nowhere else.
+//!
+//! Temporaries are the variables lowering makes up, whose names start with a
+//! `.`. A local may be read before it is assigned, since C allows that, but
+//! lowering never reads a temporary before computing it, so a pass that makes
+//! that happen has lost an instruction.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    cfg::Cfg,
+    tacky::{BinaryOperator, Callee, Function, Instruction, Program, Type, UnaryOperator, Value},
+};
+
+#[derive(Debug, thiserror::Error)]
+#[error("in `{function}`: {message}")]
+pub struct VerifyError {
+    function: String,
+    message: String,
+}
+impl VerifyError {
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Check every function of a program.
+pub fn verify_program(program: &Program) -> Result<(), VerifyError> {
+    let verifier = Verifier::new(program);
+    program
+        .functions
+        .iter()
+        .try_for_each(|function| verifier.verify_function(function))
+}
+
+/// Checks functions against what the rest of their program declares.
+pub struct Verifier {
+    statics: HashMap<String, Type>,
+    functions: HashMap<String, Signature>,
+    /// The functions the program defined before passes deleted them.
+    deleted: HashSet<String>,
+}
+struct Signature {
+    params: Vec<Type>,
+    variadic: bool,
+    return_type: Option<Type>,
+}
+impl Verifier {
+    pub fn new(program: &Program) -> Self {
+        let statics = program
+            .statics
+            .iter()
+            .map(|object| (object.name.clone(), object.ty))
+            .collect();
+        let functions = program
+            .functions
+            .iter()
+            .map(|function| {
+                let signature = Signature {
+                    params: function
+                        .params
+                        .iter()
+                        .map(|param| function.variables[param])
+                        .collect(),
+                    variadic: function.variadic,
+                    return_type: function.return_type,
+                };
+                (function.name.clone(), signature)
+            })
+            .collect();
+        Self {
+            statics,
+            functions,
+            deleted: HashSet::new(),
+        }
+    }
+
+    /// A verifier for `program` after a pass changed the program this one checks,
+    /// which may have deleted some of its functions.
+    pub fn after(&self, program: &Program) -> Self {
+        let mut verifier = Self::new(program);
+        verifier.deleted = self
+            .functions
+            .keys()
+            .chain(&self.deleted)
+            .filter(|name| !verifier.functions.contains_key(*name))
+            .cloned()
+            .collect();
+        verifier
+    }
+
+    pub fn verify_function(&self, function: &Function) -> Result<(), VerifyError> {
+        let error = |message| VerifyError {
+            function: function.name.clone(),
+            message,
+        };
+        match function.body.last() {
+            Some(Instruction::Return(_) | Instruction::Jump(_)) => {}
+            _ => return Err(error("the body doesn't end in a return".into())),
+        }
+
+        let mut labels = HashSet::new();
+        for instruction in &function.body {
+            if let Instruction::Label(label) = instruction {
+                if !labels.insert(label.as_str()) {
+                    return Err(error(format!("the label `{label}` is defined twice")));
+                }
+            }
+        }
+        for instruction in &function.body {
+            if let Some(label) = instruction.target() {
+                if !labels.contains(label) {
+                    return Err(error(format!(
+                        "`{instruction}` jumps to the undefined label `{label}`"
+                    )));
+                }
+            }
+        }
+
+        for instruction in &function.body {
+            self.check_types(function, instruction)
+                .map_err(|problem| error(format!("`{instruction}` {problem}")))?;
+        }
+        check_temporaries(function).map_err(error)
+    }
+
+    /// The type of a value in a function.
+    fn type_of(&self, function: &Function, value: &Value) -> Result<Type, String> {
+        match value {
+            Value::Constant(constant) => Ok(constant.ty),
+            Value::Variable(name) => self.object_type(function, name),
+        }
+    }
+
+    fn object_type(&self, function: &Function, name: &str) -> Result<Type, String> {
+        function
+            .variables
+            .get(name)
+            .or_else(|| self.statics.get(name))
+            .copied()
+            .ok_or_else(|| format!("names `%{name}`, which isn't declared"))
+    }
+
+    fn check_types(&self, function: &Function, instruction: &Instruction) -> Result<(), String> {
+        let type_of = |value| self.type_of(function, value);
+        if let Some(Value::Constant(_)) = instruction.dst() {
+            return Err("assigns to a constant".into());
+        }
+        match instruction {
+            Instruction::Return(value) => {
+                let ty = value.as_ref().map(type_of).transpose()?;
+                ensure(ty == function.return_type, || match function.return_type {
+                    Some(expected) => format!("doesn't return the function's type `{expected}`"),
+                    None => "returns a value from a function returning void".into(),
+                })
+            }
+            Instruction::SignExtend { src, dst } | Instruction::ZeroExtend { src, dst } => {
+                let (from, to) = (type_of(src)?, type_of(dst)?);
+                ensure(
+                    from.is_integer() && to.is_integer() && from.size() < to.size(),
+                    || format!("doesn't widen an integer, from `{from}` to `{to}`"),
+                )
+            }
+            Instruction::Truncate { src, dst } => {
+                let (from, to) = (type_of(src)?, type_of(dst)?);
+                ensure(
+                    from.is_integer() && to.is_integer() && from.size() > to.size(),
+                    || format!("doesn't narrow an integer, from `{from}` to `{to}`"),
+                )
+            }
+            Instruction::IntToFloat { src, dst } => {
+                let (from, to) = (type_of(src)?, type_of(dst)?);
+                ensure(from.is_integer() && to.is_floating(), || {
+                    format!(
+                        "doesn't convert an integer to a floating type, from `{from}` to `{to}`"
+                    )
+                })
+            }
+            Instruction::FloatToInt { src, dst } => {
+                let (from, to) = (type_of(src)?, type_of(dst)?);
+                ensure(from.is_floating() && to.is_integer(), || {
+                    format!("doesn't convert a floating value to an integer type, from `{from}` to `{to}`")
+                })
+            }
+            Instruction::FloatExtend { src, dst } => {
+                let (from, to) = (type_of(src)?, type_of(dst)?);
+                ensure(from == Type::F32 && to == Type::F64, || {
+                    format!("doesn't convert `f32` to `f64`, but `{from}` to `{to}`")
+                })
+            }
+            Instruction::FloatTruncate { src, dst } => {
+                let (from, to) = (type_of(src)?, type_of(dst)?);
+                ensure(from == Type::F64 && to == Type::F32, || {
+                    format!("doesn't convert `f64` to `f32`, but `{from}` to `{to}`")
+                })
+            }
+            Instruction::Unary { op, src, dst } => {
+                let (operand, result) = (type_of(src)?, type_of(dst)?);
+                ensure(!operand.is_bytes(), || {
+                    format!("has an operand of type `{operand}`")
+                })?;
+                match op {
+                    UnaryOperator::Not => ensure(result == Type::I32, || {
+                        format!("gives an `i32`, not a `{result}`")
+                    }),
+                    UnaryOperator::Negate | UnaryOperator::Complement => {
+                        ensure(*op == UnaryOperator::Negate || operand.is_integer(), || {
+                            format!("has a floating operand of type `{operand}`")
+                        })?;
+                        ensure(operand == result, || {
+                            format!("gives a `{operand}`, not a `{result}`")
+                        })
+                    }
+                }
+            }
+            Instruction::Binary { op, lhs, rhs, dst } => {
+                let (left, right, result) = (type_of(lhs)?, type_of(rhs)?, type_of(dst)?);
+                ensure(left == right && !left.is_bytes(), || {
+                    format!("has operands of types `{left}` and `{right}`")
+                })?;
+                let integer_only = matches!(
+                    op,
+                    BinaryOperator::Remainder
+                        | BinaryOperator::BitwiseAnd
+                        | BinaryOperator::BitwiseOr
+                        | BinaryOperator::BitwiseXor
+                        | BinaryOperator::ShiftLeft
+                        | BinaryOperator::ShiftRight
+                );
+                ensure(!integer_only || left.is_integer(), || {
+                    format!("has floating operands of type `{left}`")
+                })?;
+                let expected = match op.is_comparison() {
+                    true => Type::I32,
+                    false => left,
+                };
+                ensure(result == expected, || {
+                    format!("gives a `{expected}`, not a `{result}`")
+                })
+            }
+            Instruction::Copy { src, dst } => {
+                let (from, to) = (type_of(src)?, type_of(dst)?);
+                ensure(
+                    from.size() == to.size() && from.is_bytes() == to.is_bytes(),
+                    || format!("copies a `{from}` to a `{to}`"),
+                )
+            }
+            Instruction::GetAddress { src, dst } => {
+                ensure(!self.deleted.contains(src), || {
+                    format!("takes the address of `{src}`, which was deleted")
+                })?;
+                // A function defined in another translation unit is declared
+                // nowhere in the program
+                if !self.functions.contains_key(src) {
+                    let known =
+                        function.variables.contains_key(src) || self.statics.contains_key(src);
+                    ensure(known || !src.starts_with('.'), || {
+                        format!("names `%{src}`, which isn't declared")
+                    })?;
+                }
+                let to = type_of(dst)?;
+                ensure(to == Type::Pointer, || {
+                    format!("puts an address in a `{to}`")
+                })
+            }
+            Instruction::Load { ptr, .. } | Instruction::Store { ptr, .. } => {
+                let pointer = type_of(ptr)?;
+                if let Instruction::Store { src, .. } = instruction {
+                    type_of(src)?;
+                } else if let Some(dst) = instruction.dst() {
+                    type_of(dst)?;
+                }
+                ensure(pointer == Type::Pointer, || {
+                    format!("goes through a `{pointer}`, not a pointer")
+                })
+            }
+            Instruction::AddPtr {
+                ptr, index, dst, ..
+            } => {
+                let (pointer, index, result) = (type_of(ptr)?, type_of(index)?, type_of(dst)?);
+                ensure(
+                    pointer == Type::Pointer && index == Type::I64 && result == Type::Pointer,
+                    || format!("adds a `{index}` to a `{pointer}` giving a `{result}`"),
+                )
+            }
+            Instruction::CopyToOffset { src, dst, offset } => {
+                let (value, object) = (type_of(src)?, self.object_type(function, dst)?);
+                ensure(offset + value.size() <= object.size(), || {
+                    format!("writes past the end of a `{object}`")
+                })
+            }
+            Instruction::CopyFromOffset { src, offset, dst } => {
+                let (object, value) = (self.object_type(function, src)?, type_of(dst)?);
+                ensure(offset + value.size() <= object.size(), || {
+                    format!("reads past the end of a `{object}`")
+                })
+            }
+            Instruction::JumpIfZero(condition, _) | Instruction::JumpIfNotZero(condition, _) => {
+                let ty = type_of(condition)?;
+                ensure(!ty.is_bytes(), || format!("tests a `{ty}`"))
+            }
+            Instruction::Jump(_) | Instruction::Label(_) => Ok(()),
+            Instruction::FunCall { callee, args, dst } => {
+                let args: Vec<Type> = args.iter().map(type_of).collect::<Result<_, _>>()?;
+                let dst = dst.as_ref().map(type_of).transpose()?;
+                let signature = match callee {
+                    Callee::Direct(name) => {
+                        ensure(!self.deleted.contains(name), || {
+                            format!("calls `{name}`, which was deleted")
+                        })?;
+                        self.functions.get(name)
+                    }
+                    Callee::Indirect(ptr) => {
+                        let pointer = type_of(ptr)?;
+                        ensure(pointer == Type::Pointer, || {
+                            format!("calls through a `{pointer}`, not a pointer")
+                        })?;
+                        None
+                    }
+                };
+                // Only a function defined in the program has a known signature
+                let Some(signature) = signature else {
+                    return Ok(());
+                };
+                // Anything can follow the parameters of a variadic function
+                let fixed = match signature.variadic {
+                    true => args.get(..signature.params.len()),
+                    false => Some(&args[..]),
+                };
+                ensure(fixed == Some(&signature.params[..]), || {
+                    format!(
+                        "passes arguments of types {args:?} for parameters of types {:?}{}",
+                        signature.params,
+                        if signature.variadic { " and `...`" } else { "" }
+                    )
+                })?;
+                match (dst, signature.return_type) {
+                    (None, _) => Ok(()),
+                    (Some(dst), Some(returned)) => ensure(dst == returned, || {
+                        format!("puts the `{returned}` it returns in a `{dst}`")
+                    }),
+                    (Some(_), None) => Err("uses the result of a function returning void".into()),
+                }
+            }
+        }
+    }
+}
+
+fn ensure(holds: bool, problem: impl FnOnce() -> String) -> Result<(), String> {
+    match holds {
+        true => Ok(()),
+        false => Err(problem()),
+    }
+}
+
+/// Check that every path to each read of a temporary assigns it first, with a
+/// forward dataflow analysis whose meet is intersection.
+fn check_temporaries(function: &Function) -> Result<(), String> {
+    let temporaries: HashMap<&str, usize> = function
+        .variables
+        .keys()
+        .filter(|name| name.starts_with('.'))
+        .enumerate()
+        .map(|(i, name)| (name.as_str(), i))
+        .collect();
+    let index = |name: &str| temporaries.get(name).copied();
+    fn uses(instruction: &Instruction) -> Vec<&str> {
+        let mut names: Vec<&str> = instruction
+            .sources()
+            .into_iter()
+            .filter_map(|value| match value {
+                Value::Variable(name) => Some(name.as_str()),
+                Value::Constant(_) => None,
+            })
+            .collect();
+        if let Instruction::CopyFromOffset { src, .. } = instruction {
+            names.push(src);
+        }
+        names
+    }
+    // Writing part of an object counts as assigning it, and so does taking its
+    // address, since it could be assigned through the pointer
+    fn definitions(instruction: &Instruction) -> impl Iterator<Item = &str> {
+        let dst = match instruction.dst() {
+            Some(Value::Variable(name)) => Some(name.as_str()),
+            _ => None,
+        };
+        let object = match instruction {
+            Instruction::CopyToOffset { dst: name, .. }
+            | Instruction::GetAddress { src: name, .. } => Some(name.as_str()),
+            _ => None,
+        };
+        dst.into_iter().chain(object)
+    }
+
+    let cfg = Cfg::new(function.body.clone());
+    let order = cfg.reverse_postorder();
+    let mut outs = vec![vec![true; temporaries.len()]; cfg.blocks.len()];
+    outs[Cfg::ENTRY].fill(false);
+    let defined_in = |outs: &[Vec<bool>], block: usize| {
+        let mut defined = vec![true; temporaries.len()];
+        for &predecessor in &cfg.blocks[block].predecessors {
+            for (defined, out) in defined.iter_mut().zip(&outs[predecessor]) {
+                *defined &= out;
+            }
+        }
+        defined
+    };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &block in &order[1..] {
+            let mut defined = defined_in(&outs, block);
+            for instruction in &cfg.blocks[block].instructions {
+                for i in definitions(instruction).filter_map(index) {
+                    defined[i] = true;
+                }
+            }
+            if defined != outs[block] {
+                outs[block] = defined;
+                changed = true;
+            }
+        }
+    }
+
+    for &block in &order[1..] {
+        let mut defined = defined_in(&outs, block);
+        for instruction in &cfg.blocks[block].instructions {
+            for name in uses(instruction) {
+                if index(name).is_some_and(|i| !defined[i]) {
+                    return Err(format!(
+                        "`{instruction}` reads `%{name}`, which may not be assigned yet"
+                    ));
+                }
+            }
+            for i in definitions(instruction).filter_map(index) {
+                defined[i] = true;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod verify_tests {
+    use expect_test::{expect, Expect};
+
+    use super::verify_program;
+    use crate::text::parse_program;
+
+    fn check_verify(src: &str, expect: Expect) {
+        let program = parse_program(src).unwrap();
+        let actual = match verify_program(&program) {
+            Ok(()) => "ok".to_string(),
+            Err(err) => err.to_string(),
+        };
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_well_formed() {
+        check_verify(
+            r#"
+global static %counter: i32 = { integer 4 0x0 }
+
+function add(%a: i32, %b: i64) -> i64 {
+    local %.t0: i64
+    local %.t1: i64
+    local %x: i32
+    %.t0 = sext %a
+    jz %x, skip.0
+    %.t1 = add %.t0, %b
+    return %.t1
+  skip.0:
+    %counter = add %counter, %x
+    return %.t0
+}
+
+global function main() -> i32 {
+    local %.t0: i64
+    local %.t1: ptr
+    local %s: bytes(12, 4)
+    %.t0 = call add(i32 1, i64 2)
+    %.t1 = addr %putchar
+    call *%.t1(i32 65)
+    copy_to_offset i32 7, %s, 8
+  loop.0:
+    jump loop.0
+}
+"#,
+            expect!["ok"],
+        );
+    }
+
+    #[test]
+    fn test_control_flow() {
+        check_verify(
+            r#"
+global function f(%n: i32) -> i32 {
+    jz %n, end.0
+    return %n
+  end.0:
+    %n = add %n, i32 1
+}
+"#,
+            expect!["in `f`: the body doesn't end in a return"],
+        );
+        check_verify(
+            r#"
+global function f(%n: i32) -> i32 {
+    jz %n, end.1
+  end.0:
+    return %n
+}
+"#,
+            expect!["in `f`: `jz %n, end.1` jumps to the undefined label `end.1`"],
+        );
+        check_verify(
+            r#"
+global function f(%n: i32) -> i32 {
+  end.0:
+  end.0:
+    return %n
+}
+"#,
+            expect!["in `f`: the label `end.0` is defined twice"],
+        );
+    }
+
+    #[test]
+    fn test_types() {
+        let check_function = |body: &str, expect: Expect| {
+            check_verify(
+                &format!(
+                    "global function f(%n: i32, %p: ptr) -> i32 {{\n    local %l: i64\n    local %f: f64\n    local %s: bytes(8, 4)\n{body}    return %n\n}}\n"
+                ),
+                expect,
+            );
+        };
+        check_function(
+            "    %n = add %n, %l\n",
+            expect!["in `f`: `%n = add %n, %l` has operands of types `i32` and `i64`"],
+        );
+        check_function(
+            "    %l = lt %n, i32 1\n",
+            expect!["in `f`: `%l = lt %n, i32 1` gives a `i32`, not a `i64`"],
+        );
+        check_function(
+            "    %f = rem %f, f64 1.0\n",
+            expect!["in `f`: `%f = rem %f, f64 1.0` has floating operands of type `f64`"],
+        );
+        check_function(
+            "    %n = sext %l\n",
+            expect!["in `f`: `%n = sext %l` doesn't widen an integer, from `i64` to `i32`"],
+        );
+        check_function(
+            "    %n = copy %l\n",
+            expect!["in `f`: `%n = copy %l` copies a `i64` to a `i32`"],
+        );
+        check_function(
+            "    %n = load %l\n",
+            expect!["in `f`: `%n = load %l` goes through a `i64`, not a pointer"],
+        );
+        check_function(
+            "    copy_to_offset i32 1, %s, 6\n",
+            expect!["in `f`: `copy_to_offset i32 1, %s, 6` writes past the end of a `bytes(8, 4)`"],
+        );
+        check_function(
+            "    %n = add %m, i32 1\n",
+            expect!["in `f`: `%n = add %m, i32 1` names `%m`, which isn't declared"],
+        );
+        check_function(
+            "    %n = call f(%n)\n",
+            expect![[
+                r#"in `f`: `%n = call f(%n)` passes arguments of types [I32] for parameters of types [I32, Pointer]"#
+            ]],
+        );
+        check_function(
+            "    return %l\n",
+            expect!["in `f`: `return %l` doesn't return the function's type `i32`"],
+        );
+    }
+
+    #[test]
+    fn test_variadic_calls() {
+        let check_call = |call: &str, expect: Expect| {
+            check_verify(
+                &format!(
+                    "function v(%n: i32, ...) -> void {{\n    return\n}}\n\nglobal function main() -> i32 {{\n    {call}\n    return i32 0\n}}\n"
+                ),
+                expect,
+            );
+        };
+        check_call("call v(i32 1)", expect!["ok"]);
+        check_call("call v(i32 1, f64 2.0, ptr 0)", expect!["ok"]);
+        check_call(
+            "call v()",
+            expect![[
+                r#"in `main`: `call v()` passes arguments of types [] for parameters of types [I32] and `...`"#
+            ]],
+        );
+        check_call(
+            "call v(i64 1, i32 2)",
+            expect![[
+                r#"in `main`: `call v(i64 1, i32 2)` passes arguments of types [I64, I32] for parameters of types [I32] and `...`"#
+            ]],
+        );
+    }
+
+    #[test]
+    fn test_temporaries() {
+        check_verify(
+            r#"
+global function f(%n: i32) -> i32 {
+    local %.t0: i32
+    local %x: i32
+    jz %n, else.0
+    %.t0 = add %n, i32 1
+    jump end.0
+  else.0:
+    %x = add %x, i32 1
+  end.0:
+    return %.t0
+}
+"#,
+            expect!["in `f`: `return %.t0` reads `%.t0`, which may not be assigned yet"],
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! Compares the full `TokenStream<CToken>` layout against the struct-of-arrays
+//! `CompactTokenStream<CTokenKind>` on a large translation unit.
+//!
+//! Set `WACC_BENCH_FILE` to a preprocessed `.i` file to benchmark it, otherwise a
+//! large translation unit is generated.
+
+use std::{hint::black_box, mem};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use tokengen::span::{SourceMap, Span};
+use wacc_lexer::{c_token::CToken, Lexer};
+
+/// Roughly the size of a preprocessed file that pulls in a handful of libc headers.
+const GENERATED_SIZE: usize = 4 * 1024 * 1024;
+
+fn translation_unit() -> String {
+    if let Ok(path) = std::env::var("WACC_BENCH_FILE") {
+        return std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("couldn't read {path}: {e}"));
+    }
+    let mut src = String::with_capacity(GENERATED_SIZE);
+    let mut i = 0;
+    while src.len() < GENERATED_SIZE {
+        src.push_str(&format!(
+            "int function_{i}(void) {{\n    return {i};\n}}\n\nint main_{i}(void) {{ return 0; }}\n"
+        ));
+        i += 1;
+    }
+    src
+}
+
+fn report_memory(src: &str) {
+    let tokens = String::lex_c(src);
+    let compact = String::lex_c_compact(src);
+    let full = tokens.len() * mem::size_of::<CToken>();
+    println!(
+        "{} bytes of source, {} tokens\n  TokenStream<CToken>:  {full} bytes used ({} per token), {} bytes reserved\n  CompactTokenStream:   {} bytes used (9 per token)",
+        src.len(),
+        tokens.len(),
+        mem::size_of::<CToken>(),
+        tokens.heap_size(),
+        compact.heap_size(),
+    );
+}
+
+fn token_layout(c: &mut Criterion) {
+    let src = translation_unit();
+    report_memory(&src);
+
+    let mut group = c.benchmark_group("token_layout");
+    group.sample_size(10);
+    group.bench_function("lex/full", |b| b.iter(|| String::lex_c(black_box(&src))));
+    group.bench_function("lex/compact", |b| {
+        b.iter(|| String::lex_c_compact(black_box(&src)))
+    });
+
+    let tokens = String::lex_c(&src);
+    let compact = String::lex_c_compact(&src);
+    let source_map = SourceMap::new(&src);
+    group.bench_function("spans/full", |b| {
+        b.iter(|| {
+            tokens
+                .iter()
+                .filter_map(|token| token.source_span())
+                .map(|span| span.span().len())
+                .sum::<usize>()
+        })
+    });
+    group.bench_function("spans/compact", |b| {
+        b.iter(|| {
+            (0..compact.len())
+                .map(|i| compact.span(i, &source_map).len())
+                .sum::<usize>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, token_layout);
+criterion_main!(benches);
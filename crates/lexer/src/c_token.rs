@@ -1,4 +1,9 @@
-use tokengen::{span::SourceSpan, token::Token, Token};
+use tokengen::{
+    compact::{CompactToken, TokenKind},
+    span::{SourceSpan, Span},
+    token::Token,
+    Token,
+};
 
 pub mod c_keyword;
 pub mod c_symbol;
@@ -10,17 +15,109 @@ pub enum CToken {
     Punctuator(c_symbol::Punctuator),
     Identifier(tokengen::token::Ident),
     Constant(Constant),
+    StringLiteral(StringLiteral),
     Whitespace,
 }
 impl CToken {
     pub fn is_whitespace(&self) -> bool {
         self == &Self::Whitespace
     }
+    /// The kind of the token without its span, `None` for whitespace.
+    pub fn kind(&self) -> Option<CTokenKind> {
+        Some(match self {
+            Self::Keyword(keyword) => CTokenKind::Keyword(keyword.kind()),
+            Self::Operator(operator) => CTokenKind::Operator(operator.kind()),
+            Self::Punctuator(punctuator) => CTokenKind::Punctuator(punctuator.kind()),
+            Self::Identifier(_) => CTokenKind::Identifier,
+            Self::Constant(_) => CTokenKind::Constant,
+            Self::StringLiteral(_) => CTokenKind::StringLiteral,
+            Self::Whitespace => return None,
+        })
+    }
+    /// The span of the token, `None` for whitespace.
+    pub fn source_span(&self) -> Option<SourceSpan> {
+        Some(match self {
+            Self::Keyword(keyword) => SourceSpan::from_span(keyword),
+            Self::Operator(operator) => SourceSpan::from_span(operator),
+            Self::Punctuator(punctuator) => SourceSpan::from_span(punctuator),
+            Self::Identifier(ident) => SourceSpan::from_span(ident),
+            Self::Constant(constant) => constant.span,
+            Self::StringLiteral(literal) => literal.span,
+            Self::Whitespace => return None,
+        })
+    }
+}
+impl CompactToken for CToken {
+    type Kind = CTokenKind;
+
+    fn compact(&self) -> Option<(CTokenKind, SourceSpan)> {
+        self.kind().zip(self.source_span())
+    }
+}
+
+/// The kind of a [`CToken`], stored as a single byte in a
+/// [`CompactTokenStream`](tokengen::compact::CompactTokenStream).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CTokenKind {
+    Keyword(c_keyword::KeywordKind),
+    Operator(c_symbol::CookedSymbolKind),
+    Punctuator(c_symbol::SymbolKind),
+    Identifier,
+    Constant,
+    StringLiteral,
+}
+impl std::fmt::Display for CTokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Keyword(keyword) => write!(f, "`{}`", keyword.as_ref()),
+            Self::Operator(operator) => write!(f, "`{}`", operator.as_ref()),
+            Self::Punctuator(punctuator) => write!(f, "`{}`", punctuator.as_ref()),
+            Self::Identifier => write!(f, "identifier"),
+            Self::Constant => write!(f, "constant"),
+            Self::StringLiteral => write!(f, "string literal"),
+        }
+    }
+}
+impl CTokenKind {
+    const KEYWORDS: u8 = c_keyword::KeywordKind::ALL.len() as u8;
+    const OPERATORS: u8 = c_symbol::CookedSymbolKind::ALL.len() as u8;
+    const PUNCTUATORS: u8 = c_symbol::SymbolKind::ALL.len() as u8;
+    const OPERATOR: u8 = Self::KEYWORDS;
+    const PUNCTUATOR: u8 = Self::OPERATOR + Self::OPERATORS;
+    const IDENTIFIER: u8 = Self::PUNCTUATOR + Self::PUNCTUATORS;
+    const CONSTANT: u8 = Self::IDENTIFIER + 1;
+    const STRING_LITERAL: u8 = Self::CONSTANT + 1;
+}
+impl TokenKind for CTokenKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::Keyword(keyword) => keyword as u8,
+            Self::Operator(operator) => Self::OPERATOR + operator as u8,
+            Self::Punctuator(punctuator) => Self::PUNCTUATOR + punctuator as u8,
+            Self::Identifier => Self::IDENTIFIER,
+            Self::Constant => Self::CONSTANT,
+            Self::StringLiteral => Self::STRING_LITERAL,
+        }
+    }
+    fn from_byte(byte: u8) -> Self {
+        match byte {
+            b if b < Self::OPERATOR => Self::Keyword(c_keyword::KeywordKind::ALL[b as usize]),
+            b if b < Self::PUNCTUATOR => {
+                Self::Operator(c_symbol::CookedSymbolKind::ALL[(b - Self::OPERATOR) as usize])
+            }
+            b if b < Self::IDENTIFIER => {
+                Self::Punctuator(c_symbol::SymbolKind::ALL[(b - Self::PUNCTUATOR) as usize])
+            }
+            Self::IDENTIFIER => Self::Identifier,
+            Self::CONSTANT => Self::Constant,
+            Self::STRING_LITERAL => Self::StringLiteral,
+            _ => panic!("invalid token kind byte: {byte}"),
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, Token, PartialEq, Eq)]
 pub struct Constant {
-    #[allow(dead_code)]
     span: SourceSpan,
 }
 impl Constant {
@@ -30,6 +127,53 @@ impl Constant {
         }
     }
 }
+impl Span for Constant {
+    fn src(&self) -> &str {
+        self.span.src()
+    }
+    fn start(&self) -> usize {
+        self.span.start()
+    }
+    fn end(&self) -> usize {
+        self.span.end()
+    }
+    fn span(&self) -> &str {
+        self.span.span()
+    }
+    fn len(&self) -> usize {
+        self.span.len()
+    }
+}
+
+/// A string literal, quotes and escape sequences included as written.
+#[derive(Debug, Copy, Clone, Token, PartialEq, Eq)]
+pub struct StringLiteral {
+    span: SourceSpan,
+}
+impl StringLiteral {
+    pub fn new(src: &str, start: usize, end: usize) -> Self {
+        Self {
+            span: SourceSpan::new(src, start, end),
+        }
+    }
+}
+impl Span for StringLiteral {
+    fn src(&self) -> &str {
+        self.span.src()
+    }
+    fn start(&self) -> usize {
+        self.span.start()
+    }
+    fn end(&self) -> usize {
+        self.span.end()
+    }
+    fn span(&self) -> &str {
+        self.span.span()
+    }
+    fn len(&self) -> usize {
+        self.span.len()
+    }
+}
 
 #[macro_export]
 macro_rules! match_regex {
@@ -39,26 +183,27 @@ macro_rules! match_regex {
         use once_cell::sync::Lazy;
         use regex::Regex;
 
+        // Patterns are anchored to the current position, otherwise a pattern that doesn't
+        // match here would scan the rest of the source looking for its next match.
         static REGEX_PATTERNS_AND_CLOSURES: Lazy<Vec<(Regex, fn(&str, usize, usize) -> Result<CToken, LexError>)>> = Lazy::new(|| vec![
             $(
                 (
-                    Regex::new($pattern).expect(&format!("failed to create regex from pattern: {}", $pattern)),
+                    Regex::new(&format!(r"\A(?:{})", $pattern)).expect(&format!("failed to create regex from pattern: {}", $pattern)),
                     $closure,
                 ),
             )*
         ]);
 
         for (regex, closure) in REGEX_PATTERNS_AND_CLOSURES.iter() {
-            if let Some(mat) = regex.find_at($src, $pos) {
-                if $pos == mat.start() {
-                    match closure($src, mat.start(), mat.end()) {
-                        Ok(ctoken) if !ctoken.is_whitespace() => $token_stream.push(ctoken),
-                        Err(err) => $errors.push(err),
-                        _ => {}
-                    }
-                    $pos = mat.end();
-                    break;
+            if let Some(mat) = regex.find(&$src[$pos..]) {
+                let (start, end) = ($pos + mat.start(), $pos + mat.end());
+                match closure($src, start, end) {
+                    Ok(ctoken) if !ctoken.is_whitespace() => $token_stream.push(ctoken),
+                    Err(err) => $errors.push(err),
+                    _ => {}
                 }
+                $pos = end;
+                break;
             }
         }
     }};
@@ -1,3 +1,37 @@
 use tokengen::{token::Token, Token};
 
-tokengen::keyword!([Int, "int"], [Void, "void"], [Return, "return"]);
+tokengen::keyword!(
+    [Int, "int"],
+    [Void, "void"],
+    [Return, "return"],
+    [Char, "char"],
+    [Short, "short"],
+    [Long, "long"],
+    [Signed, "signed"],
+    [Unsigned, "unsigned"],
+    [Float, "float"],
+    [Double, "double"],
+    [Sizeof, "sizeof"],
+    [Const, "const"],
+    [Volatile, "volatile"],
+    [Restrict, "restrict"],
+    [Typedef, "typedef"],
+    [StaticAssert, "_Static_assert"],
+    [Static, "static"],
+    [Extern, "extern"],
+    [Inline, "inline"],
+    [Struct, "struct"],
+    [Union, "union"],
+    [Enum, "enum"],
+    [If, "if"],
+    [Else, "else"],
+    [While, "while"],
+    [Do, "do"],
+    [For, "for"],
+    [Break, "break"],
+    [Continue, "continue"],
+    [Switch, "switch"],
+    [Case, "case"],
+    [Default, "default"],
+    [Goto, "goto"]
+);
@@ -1,6 +1,7 @@
 use tokengen::{token::Token, Token};
 
-pub type Operator = Symbol;
+/// Multi-character punctuators, all of which are operators apart from `...`.
+pub type Operator = CookedSymbol;
 pub type Punctuator = Symbol;
 
 tokengen::symbol!(
@@ -8,5 +9,49 @@ tokengen::symbol!(
     [CloseParenthesis, ')'],
     [OpenCurlyBrace, '{'],
     [CloseCurlyBrace, '}'],
-    [Semicolon, ';']
+    [OpenSquareBracket, '['],
+    [CloseSquareBracket, ']'],
+    [Semicolon, ';'],
+    [Comma, ','],
+    [Dot, '.'],
+    [QuestionMark, '?'],
+    [Colon, ':'],
+    [Tilde, '~'],
+    [ExclamationMark, '!', [Bang]],
+    [Plus, '+'],
+    [Minus, '-', [Hyphen]],
+    [Asterisk, '*', [Star]],
+    [ForwardSlash, '/', [Slash]],
+    [Percent, '%'],
+    [Ampersand, '&'],
+    [VerticalBar, '|', [Pipe]],
+    [Caret, '^'],
+    [LessThan, '<'],
+    [GreaterThan, '>'],
+    [Equals, '=']
+);
+
+tokengen::cooked_symbol!(
+    [ShiftLeftAssign, "<<="],
+    [ShiftRightAssign, ">>="],
+    [Ellipsis, "..."],
+    [Arrow, "->"],
+    [Increment, "++"],
+    [Decrement, "--"],
+    [ShiftLeft, "<<"],
+    [ShiftRight, ">>"],
+    [LessThanOrEqual, "<="],
+    [GreaterThanOrEqual, ">="],
+    [DoubleEquals, "=="],
+    [NotEquals, "!="],
+    [LogicalAnd, "&&"],
+    [LogicalOr, "||"],
+    [MultiplyAssign, "*="],
+    [DivideAssign, "/="],
+    [RemainderAssign, "%="],
+    [AddAssign, "+="],
+    [SubtractAssign, "-="],
+    [AndAssign, "&="],
+    [XorAssign, "^="],
+    [OrAssign, "|="]
 );
@@ -1,13 +1,14 @@
 use c_token::{
-    c_keyword::{Int, Keyword, Return, Void},
-    c_symbol::{CloseCurlyBrace, CloseParenthesis, OpenCurlyBrace, OpenParenthesis, Semicolon},
+    c_keyword::Keyword,
+    c_symbol::{CookedSymbol, Symbol},
 };
 use tokengen::{
+    compact::CompactTokenStream,
     span::{SourceSpan, Span},
-    token::{Ident, Token, TokenStream},
+    token::{Ident, Token, TokenSink, TokenStream},
 };
 
-use crate::c_token::CToken;
+use crate::c_token::{CToken, CTokenKind};
 
 pub mod c_token;
 
@@ -29,10 +30,13 @@ where
     }
     pub fn report_errors(self) {
         if !self.state.is_empty() {
-            self.state.iter().for_each(|e| eprintln!("Error: {e:?}\n"));
+            self.state.iter().for_each(|e| eprintln!("{e}\n"));
             std::process::exit(1);
         }
     }
+    pub fn into_errors(self) -> Vec<E> {
+        self.state
+    }
 }
 impl<E> Default for ErrorEmitter<E>
 where
@@ -43,8 +47,10 @@ where
     }
 }
 
+/// Shown as `line:col: error: context`, then the source it covers, like the
+/// parser's errors.
 #[derive(Debug, thiserror::Error)]
-#[error("Error: {context}:\n{source}", source = span.span())]
+#[error("{location}: error: {context}:\n{source}", location = span.location(), source = span.span())]
 pub struct LexError {
     span: SourceSpan,
     context: String,
@@ -56,6 +62,12 @@ impl LexError {
             context: context.into(),
         }
     }
+    pub fn span(&self) -> SourceSpan {
+        self.span
+    }
+    pub fn context(&self) -> &str {
+        &self.context
+    }
 }
 
 pub trait Lexable: AsRef<str> + Sized {}
@@ -69,76 +81,101 @@ pub trait Lexer: Lexable {
     {
         f(self.as_ref())
     }
+    /// Lex C source, printing any errors and exiting if there are some.
     fn lex_c(src: &str) -> TokenStream<CToken> {
-        let mut error_emitter = ErrorEmitter::default();
-        let mut pos = 0_usize;
         let mut token_stream = TokenStream::new(src.len());
-        while pos < src.len() {
-            match_regex!(src, pos, token_stream, error_emitter, {
-                r"\s" => {
-                    |_, _, _| -> Result<CToken, LexError> {
-                        Ok(CToken::Whitespace)
-                    }
-                },
-                r"[a-zA-Z_]\w*\b" => {
-                    |src: &str, start: usize, end: usize| -> Result<CToken, LexError> {
-                        let raw = SourceSpan::new(src, start, end);
-                        Ok(match raw.span() {
-                            "int" => CToken::Keyword(Keyword::Int(Int::new(src, start, end))),
-                            "void" => CToken::Keyword(Keyword::Void(Void::new(src, start, end))),
-                            "return" => CToken::Keyword(Keyword::Return(Return::new(src, start, end))),
-                            _ => CToken::Identifier(Ident::new(src, start, end)),
-                        })
-                    }
-                },
-                r"[0-9]+\b" => {
-                    |src: &str, start: usize, end: usize| -> Result<CToken, LexError> {
-                        Ok(CToken::Constant(c_token::Constant::new(src, start, end)))
-                    }
-                },
-                r"\(" => {
-                    |src: &str, start: usize, end: usize| -> Result<CToken, LexError> {
-                        Ok(CToken::Punctuator(c_token::c_symbol::Symbol::OpenParenthesis(OpenParenthesis::new(src, start, end))))
-                    }
-                },
-                r"\)" => {
-                    |src: &str, start: usize, end: usize| -> Result<CToken, LexError> {
-                        Ok(CToken::Punctuator(c_token::c_symbol::Symbol::CloseParenthesis(CloseParenthesis::new(src, start, end))))
-                    }
-                },
-                r"\{" => {
-                    |src: &str, start: usize, end: usize| -> Result<CToken, LexError> {
-                        Ok(CToken::Punctuator(c_token::c_symbol::Symbol::OpenCurlyBrace(OpenCurlyBrace::new(src, start, end))))
-                    }
-                },
-                r"\}" => {
-                    |src: &str, start: usize, end: usize| -> Result<CToken, LexError> {
-                        Ok(CToken::Punctuator(c_token::c_symbol::Symbol::CloseCurlyBrace(CloseCurlyBrace::new(src, start, end))))
-                    }
-                },
-                r"\;" => {
-                    |src: &str, start: usize, end: usize| -> Result<CToken, LexError> {
-                        Ok(CToken::Punctuator(c_token::c_symbol::Symbol::Semicolon(Semicolon::new(src, start, end))))
-                    }
-                },
-                r"." => {
-                    |src: &str, start: usize, end: usize| -> Result<CToken, LexError> {
-                        Err(LexError::new(src, start, end, "Unrecognized token"))
-                    }
-                },
-            });
-        }
-
-        if token_stream.is_empty() {
-            eprintln!("token stream is empty");
-            std::process::exit(1);
-        }
+        let error_emitter = lex_c_into(src, &mut token_stream);
+        exit_if_empty(&token_stream);
         error_emitter.report_errors();
-
+        token_stream
+    }
+    /// Lex C source, handing back the errors with whatever tokens were found
+    /// around them, for callers that report errors themselves.
+    fn lex_c_with_errors(src: &str) -> (TokenStream<CToken>, Vec<LexError>) {
+        let mut token_stream = TokenStream::new(src.len());
+        let error_emitter = lex_c_into(src, &mut token_stream);
+        (token_stream, error_emitter.into_errors())
+    }
+    /// Lex C source into a [`CompactTokenStream`], for translation units where
+    /// storing a full span with every token would dominate memory.
+    fn lex_c_compact(src: &str) -> CompactTokenStream<CTokenKind> {
+        // Roughly one token for every four bytes of source, rather than one per byte.
+        let mut token_stream = CompactTokenStream::new(src.len() / 4);
+        let error_emitter = lex_c_into(src, &mut token_stream);
+        exit_if_empty(&token_stream);
+        error_emitter.report_errors();
+        token_stream.shrink_to_fit();
         token_stream
     }
 }
 
+fn lex_c_into<S: TokenSink<CToken>>(src: &str, token_stream: &mut S) -> ErrorEmitter<LexError> {
+    let mut error_emitter = ErrorEmitter::default();
+    let mut pos = 0_usize;
+    while pos < src.len() {
+        match_regex!(src, pos, token_stream, error_emitter, {
+            r"\s" => {
+                |_, _, _| -> Result<CToken, LexError> {
+                    Ok(CToken::Whitespace)
+                }
+            },
+            r"[a-zA-Z_]\w*\b" => {
+                |src: &str, start: usize, end: usize| -> Result<CToken, LexError> {
+                    Ok(Keyword::from_src(src, start, end)
+                        .map(CToken::Keyword)
+                        .unwrap_or_else(|| CToken::Identifier(Ident::new(src, start, end))))
+                }
+            },
+            // A preprocessing number (C17 6.4.8), which takes in every integer and
+            // floating constant; the parser works out which, if either, it is.
+            r"\.?[0-9]([eEpP][+\-]|[0-9a-zA-Z_.])*" => {
+                |src: &str, start: usize, end: usize| -> Result<CToken, LexError> {
+                    Ok(CToken::Constant(c_token::Constant::new(src, start, end)))
+                }
+            },
+            r"'([^'\\\n]|\\.)*'" => {
+                |src: &str, start: usize, end: usize| -> Result<CToken, LexError> {
+                    Ok(CToken::Constant(c_token::Constant::new(src, start, end)))
+                }
+            },
+            r#""([^"\\\n]|\\.)*""# => {
+                |src: &str, start: usize, end: usize| -> Result<CToken, LexError> {
+                    Ok(CToken::StringLiteral(c_token::StringLiteral::new(src, start, end)))
+                }
+            },
+            // Alternatives are tried left to right, so longer operators must come first.
+            r"<<=|>>=|\.\.\.|->|\+\+|--|<<|>>|<=|>=|==|!=|&&|\|\||[*/%+\-&^|]=" => {
+                |src: &str, start: usize, end: usize| -> Result<CToken, LexError> {
+                    CookedSymbol::from_src(src, start, end)
+                        .map(CToken::Operator)
+                        .ok_or_else(|| LexError::new(src, start, end, "Unrecognized operator"))
+                }
+            },
+            r"[(){}\[\];,.?:~!+\-*/%&|^<>=]" => {
+                |src: &str, start: usize, end: usize| -> Result<CToken, LexError> {
+                    Symbol::from_src(src, start, end)
+                        .map(CToken::Punctuator)
+                        .ok_or_else(|| LexError::new(src, start, end, "Unrecognized punctuator"))
+                }
+            },
+            r"." => {
+                |src: &str, start: usize, end: usize| -> Result<CToken, LexError> {
+                    Err(LexError::new(src, start, end, "Unrecognized token"))
+                }
+            },
+        });
+    }
+
+    error_emitter
+}
+
+fn exit_if_empty<S: TokenSink<CToken>>(token_stream: &S) {
+    if token_stream.is_empty() {
+        eprintln!("token stream is empty");
+        std::process::exit(1);
+    }
+}
+
 pub struct Source<S: Lexable>(S);
 impl<S: Lexable> Source<S> {
     pub fn new(src: S) -> Self {
@@ -155,7 +192,10 @@ impl<T: Lexable> Lexer for T {}
 #[cfg(test)]
 mod lexer_tests {
     use expect_test::{expect, Expect};
-    use tokengen::token::{Token, TokenStream};
+    use tokengen::{
+        span::{SourceMap, Span},
+        token::{Token, TokenStream},
+    };
 
     use super::Lexer;
     use crate::c_token::CToken;
@@ -286,4 +326,48 @@ mod lexer_tests {
                 )"#]],
         );
     }
+
+    #[test]
+    fn test_lex_c_compact() {
+        let input = "int main(void) {\n  return 2;\n}\n";
+        let tokens = String::lex_c(input);
+        let compact = String::lex_c_compact(input);
+        let source_map = SourceMap::new(input);
+
+        assert_eq!(tokens.len(), compact.len());
+        for (i, token) in tokens.iter().enumerate() {
+            let span = token.source_span().unwrap();
+            assert_eq!(token.kind().unwrap(), compact.kind(i));
+            assert_eq!(span.span(), compact.span(i, &source_map));
+            assert_eq!(span, compact.source_span(i, &source_map));
+        }
+        assert_eq!(source_map.line_col(compact.start(7)), (2, 10));
+        assert!(compact.heap_size() < tokens.heap_size() / 4);
+    }
+
+    #[test]
+    fn test_lex_c_operators() {
+        let input = r#"a<<=b->c++ - --d...e!=~f>>g;x[1]=y?z:(unsigned)sizeof w"s\"t\\""#;
+        let kinds = String::lex_c(input)
+            .iter()
+            .map(|token| token.kind().unwrap().to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        expect!["identifier `<<=` identifier `->` identifier `++` `-` `--` identifier `...` identifier `!=` `~` identifier `>>` identifier `;` identifier `[` constant `]` `=` identifier `?` identifier `:` `(` `unsigned` `)` `sizeof` identifier string literal"]
+        .assert_eq(&kinds);
+    }
+
+    #[test]
+    fn test_lex_c_errors() {
+        let input = "int main(void) {\n  return 2 @ 3;\n}\n";
+        let (tokens, errors) = String::lex_c_with_errors(input);
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        expect![[r#"
+            [
+                "2:12: error: Unrecognized token:\n@",
+            ]
+        "#]]
+        .assert_debug_eq(&errors);
+        assert_eq!(tokens.len(), 11);
+    }
 }
@@ -0,0 +1,549 @@
+//! The typed abstract syntax tree produced by the [`Parser`](crate::Parser).
+//!
+//! Every node carries the [`SourceSpan`] of the tokens it was parsed from.
+
+use std::fmt;
+
+use tokengen::span::SourceSpan;
+
+use crate::types::{QualifiedType, Qualifiers, Tag, TagKind, TagTable, Type};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub items: Vec<ExternalDeclaration>,
+    /// Every struct, union and enum declared in the program.
+    pub tags: TagTable,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExternalDeclaration {
+    Function(Box<FunctionDefinition>),
+    Declaration(Declaration),
+    StaticAssert(StaticAssert),
+    /// A top-level declaration that failed to parse, covering the tokens skipped to recover.
+    Error(SourceSpan),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDefinition {
+    pub specifiers: DeclarationSpecifiers,
+    pub declarator: Declarator,
+    pub name: Identifier,
+    /// The function's derived type.
+    pub ty: QualifiedType,
+    pub parameters: Vec<ParameterDeclaration>,
+    pub body: Block,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub items: Vec<BlockItem>,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockItem {
+    Declaration(Declaration),
+    StaticAssert(StaticAssert),
+    Statement(Statement),
+}
+
+/// `_Static_assert ( <constant-exp> , <string-literal> ) ;`, which is checked as it is parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaticAssert {
+    pub condition: Expression,
+    /// The bytes of the message, without a terminating null.
+    pub message: Vec<u8>,
+    pub span: SourceSpan,
+}
+
+/// `<declaration-specifiers> [ <init-declarator> { "," <init-declarator> } ] ";"`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub specifiers: DeclarationSpecifiers,
+    pub declarators: Vec<InitDeclarator>,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeclarationSpecifiers {
+    pub storage_class: Option<StorageClass>,
+    /// Whether the function specifier `inline` was written.
+    pub is_inline: bool,
+    /// The type specifiers in the order they were written, e.g. `[Unsigned, Long, Int]`.
+    pub type_specifiers: Vec<TypeSpecifier>,
+    pub qualifiers: Qualifiers,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageClass {
+    Typedef,
+    Static,
+    Extern,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeSpecifier {
+    Void,
+    Char,
+    Short,
+    Int,
+    Long,
+    Float,
+    Double,
+    Signed,
+    Unsigned,
+    /// A name declared by a `typedef` in scope.
+    TypedefName(Identifier),
+    Record(RecordSpecifier),
+    Enum(EnumSpecifier),
+}
+
+/// `struct` or `union`, followed by a tag, a list of members, or both.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordSpecifier {
+    /// [`TagKind::Struct`] or [`TagKind::Union`].
+    pub kind: TagKind,
+    pub name: Option<Identifier>,
+    /// The member declarations if this specifier defines the record.
+    pub members: Option<Vec<MemberDeclaration>>,
+    pub tag: Tag,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemberDeclaration {
+    pub specifiers: DeclarationSpecifiers,
+    /// Empty for an anonymous struct or union member.
+    pub declarators: Vec<MemberDeclarator>,
+    pub span: SourceSpan,
+}
+
+/// `<declarator> [ ":" <width> ]`, or just `":" <width>` for an unnamed bit-field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemberDeclarator {
+    pub declarator: Declarator,
+    pub name: Option<Identifier>,
+    pub ty: QualifiedType,
+    pub bit_width: Option<Expression>,
+    pub span: SourceSpan,
+}
+
+/// `enum`, followed by a tag, a list of enumerators, or both.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumSpecifier {
+    pub name: Option<Identifier>,
+    /// The enumerators if this specifier defines the enum.
+    pub enumerators: Option<Vec<Enumerator>>,
+    pub tag: Tag,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enumerator {
+    pub name: Identifier,
+    pub value: Option<Expression>,
+    /// The value of the constant, whether given explicitly or implied.
+    pub constant: i64,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitDeclarator {
+    pub declarator: Declarator,
+    pub name: Identifier,
+    /// The type derived from the declaration specifiers and the declarator.
+    pub ty: QualifiedType,
+    pub initializer: Option<Initializer>,
+    /// The initializer resolved against `ty`, or empty without an initializer.
+    pub entries: Vec<InitializerEntry>,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Initializer {
+    pub kind: InitializerKind,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InitializerKind {
+    Expression(Expression),
+    /// `{ [ <designation> ] <initializer> , ... }`
+    List(Vec<InitializerListItem>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitializerListItem {
+    /// The designators before the `=`, outermost first, as in `.x[3] = 1`.
+    pub designators: Vec<Designator>,
+    pub initializer: Initializer,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Designator {
+    pub kind: DesignatorKind,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DesignatorKind {
+    /// `[ <constant-exp> ]`, with the value of the index.
+    Index(Expression, u64),
+    /// `. <identifier>`
+    Member(Identifier),
+}
+
+/// A piece of the initial contents of an object, at a byte offset from its start.
+///
+/// Resolving an initializer gives entries in order of offset that cover the whole
+/// object, whether it ends up as static data or as a sequence of stores.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitializerEntry {
+    pub offset: u64,
+    pub kind: InitializerEntryKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InitializerEntryKind {
+    /// This many bytes of zeros, for subobjects and padding with no initializer.
+    Zero(u64),
+    /// An object of the given type, usually a scalar, with the value of an expression.
+    Value(QualifiedType, Expression),
+    /// A bit-field of the given type, bit offset and width in the storage unit at the entry's offset.
+    BitField(QualifiedType, u32, u32, Expression),
+    /// The bytes of a string literal, with its terminating null if the array has room for it.
+    Bytes(Vec<u8>),
+}
+
+/// C's inside-out declarator syntax, where the declarator nearest the name is
+/// the outermost part of the type: `*x[3]` is `Pointer(Array(x, 3))`, an array
+/// of three pointers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declarator {
+    pub kind: DeclaratorKind,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeclaratorKind {
+    /// The declared name, or `None` in an abstract declarator.
+    Identifier(Option<Identifier>),
+    Pointer(Qualifiers, Box<Declarator>),
+    Array(Box<Declarator>, Option<Box<Expression>>),
+    Function(Box<Declarator>, ParameterList),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterList {
+    /// Empty for both `(void)` and `()`, which are told apart by `is_prototype`.
+    pub parameters: Vec<ParameterDeclaration>,
+    pub is_variadic: bool,
+    /// `false` for an old-style `()` list, whose parameters are unspecified.
+    pub is_prototype: bool,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterDeclaration {
+    pub specifiers: DeclarationSpecifiers,
+    pub declarator: Declarator,
+    pub name: Option<Identifier>,
+    /// The parameter's type after arrays and functions are adjusted to pointers.
+    pub ty: QualifiedType,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementKind {
+    /// `return [ <exp> ] ;`
+    Return(Option<Expression>),
+    Expression(Expression),
+    /// The empty statement `;`.
+    Null,
+    Compound(Block),
+    /// `if ( <exp> ) <statement> [ else <statement> ]`
+    If(Expression, Box<Statement>, Option<Box<Statement>>),
+    While(Expression, Box<Statement>),
+    /// `do <statement> while ( <exp> ) ;`
+    DoWhile(Box<Statement>, Expression),
+    /// `for ( <for-init> [ <exp> ] ; [ <exp> ] ) <statement>`
+    For(
+        Box<ForInit>,
+        Option<Box<Expression>>,
+        Option<Box<Expression>>,
+        Box<Statement>,
+    ),
+    Break,
+    Continue,
+    Switch(Expression, Box<Statement>),
+    /// `case <constant-exp> : <statement>`, with the value of the constant, which
+    /// type checking converts to the promoted type of the controlling expression.
+    Case(Expression, i128, Box<Statement>),
+    Default(Box<Statement>),
+    Goto(Identifier),
+    /// `<identifier> : <statement>`
+    Labeled(Identifier, Box<Statement>),
+    /// A statement that failed to parse, covering the tokens skipped to recover.
+    Error,
+}
+
+/// The first clause of a `for` loop, which may declare variables scoped to the loop.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ForInit {
+    Declaration(Declaration),
+    /// `[ <exp> ] ;`
+    Expression(Option<Expression>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: SourceSpan,
+    /// The type of the expression, which is `None` until it has been type checked.
+    pub ty: Option<QualifiedType>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionKind {
+    Constant(Literal),
+    Identifier(Identifier),
+    /// A prefix operator applied to its operand.
+    Unary(UnaryOperator, Box<Expression>),
+    Postfix(PostfixOperator, Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+    /// Simple (`=`) or compound (`+=`, `<<=`, ...) assignment.
+    Assignment(AssignmentOperator, Box<Expression>, Box<Expression>),
+    /// `condition ? then : else`
+    Conditional(Box<Expression>, Box<Expression>, Box<Expression>),
+    /// The bytes of adjacent string literals concatenated, without a terminating null.
+    StringLiteral(Vec<u8>),
+    Cast(Box<TypeName>, Box<Expression>),
+    /// `( <type-name> ) { <initializer-list> }`
+    CompoundLiteral(Box<CompoundLiteral>),
+    SizeofExpression(Box<Expression>),
+    SizeofType(Box<TypeName>),
+    /// `__builtin_offsetof ( <type-name> , <identifier> { <designator> } )`, which
+    /// `offsetof` expands to, with the member written as designators and its offset.
+    Offsetof(Box<TypeName>, Vec<Designator>, u64),
+    Call(Box<Expression>, Vec<Expression>),
+    Subscript(Box<Expression>, Box<Expression>),
+    /// `base.member`
+    Member(Box<Expression>, Identifier),
+    /// `base->member`
+    PointerMember(Box<Expression>, Identifier),
+    /// A conversion the type checker made explicit, to the type of this expression.
+    ImplicitConversion(Conversion, Box<Expression>),
+    /// A missing operand, with an empty span where it was expected.
+    Error,
+}
+
+/// The kinds of conversion C performs without a cast.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conversion {
+    /// An array decays to a pointer to its first element.
+    ArrayToPointer,
+    /// A function designator decays to a pointer to the function.
+    FunctionToPointer,
+    /// Between integer types, including promotions and enums.
+    Integer,
+    IntegerToFloating,
+    FloatingToInteger,
+    /// Between floating types.
+    Floating,
+    /// A null pointer constant to a pointer type.
+    NullPointer,
+    /// Between pointer types, or between a pointer and an integer.
+    Pointer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    /// `-`
+    Negate,
+    /// `+`
+    Plus,
+    /// `~`
+    Complement,
+    /// `!`
+    Not,
+    /// `&`
+    AddressOf,
+    /// `*`
+    Dereference,
+    /// `++`
+    PreIncrement,
+    /// `--`
+    PreDecrement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostfixOperator {
+    /// `++`
+    Increment,
+    /// `--`
+    Decrement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Multiply,
+    Divide,
+    Remainder,
+    Add,
+    Subtract,
+    ShiftLeft,
+    ShiftRight,
+    LessThan,
+    GreaterThan,
+    LessOrEqual,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+    BitwiseAnd,
+    BitwiseXor,
+    BitwiseOr,
+    LogicalAnd,
+    LogicalOr,
+    Comma,
+}
+
+/// An integer, floating or character constant, with the type its spelling
+/// gives it (C17 6.4.4).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    /// An integer or character constant. Only a character constant can be negative.
+    Integer(i128, Type),
+    Floating(f64, Type),
+}
+
+/// Assignment operators, where every compound assignment `a op= b` wraps the
+/// [`BinaryOperator`] it applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignmentOperator {
+    Assign,
+    Compound(BinaryOperator),
+}
+
+/// Spelled so that it parses back to the same value and type.
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Integer(value, _) if *value < 0 => write!(f, "'\\x{:02x}'", *value as u8),
+            Self::Integer(value, ty) => {
+                let suffix = match ty {
+                    Type::UnsignedInt => "u",
+                    Type::Long => "l",
+                    Type::UnsignedLong => "ul",
+                    Type::LongLong => "ll",
+                    Type::UnsignedLongLong => "ull",
+                    _ => "",
+                };
+                write!(f, "{value}{suffix}")
+            }
+            // `Debug` always writes a point or an exponent, unlike `Display`.
+            Self::Floating(value, Type::Float) => write!(f, "{:?}f", *value as f32),
+            Self::Floating(value, _) => write!(f, "{value:?}"),
+        }
+    }
+}
+
+impl fmt::Display for StorageClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Typedef => "typedef",
+            Self::Static => "static",
+            Self::Extern => "extern",
+        })
+    }
+}
+
+impl fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Negate => "-",
+            Self::Plus => "+",
+            Self::Complement => "~",
+            Self::Not => "!",
+            Self::AddressOf => "&",
+            Self::Dereference => "*",
+            Self::PreIncrement => "++",
+            Self::PreDecrement => "--",
+        })
+    }
+}
+impl fmt::Display for PostfixOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Increment => "++",
+            Self::Decrement => "--",
+        })
+    }
+}
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Multiply => "*",
+            Self::Divide => "/",
+            Self::Remainder => "%",
+            Self::Add => "+",
+            Self::Subtract => "-",
+            Self::ShiftLeft => "<<",
+            Self::ShiftRight => ">>",
+            Self::LessThan => "<",
+            Self::GreaterThan => ">",
+            Self::LessOrEqual => "<=",
+            Self::GreaterOrEqual => ">=",
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::BitwiseAnd => "&",
+            Self::BitwiseXor => "^",
+            Self::BitwiseOr => "|",
+            Self::LogicalAnd => "&&",
+            Self::LogicalOr => "||",
+            Self::Comma => ",",
+        })
+    }
+}
+impl fmt::Display for AssignmentOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Assign => write!(f, "="),
+            Self::Compound(operator) => write!(f, "{operator}="),
+        }
+    }
+}
+
+/// The type named in a cast or `sizeof`, e.g. `unsigned long (*)[3]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeName {
+    pub specifiers: DeclarationSpecifiers,
+    pub declarator: Declarator,
+    pub ty: QualifiedType,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompoundLiteral {
+    pub type_name: TypeName,
+    /// The type of the literal, with an array of unknown length completed by its initializer.
+    pub ty: QualifiedType,
+    pub initializer: Initializer,
+    pub entries: Vec<InitializerEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identifier {
+    pub name: String,
+    pub span: SourceSpan,
+}
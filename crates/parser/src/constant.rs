@@ -0,0 +1,898 @@
+//! Evaluation of constant expressions (C17 6.6) as the target computes them:
+//! integers wrap to the widths of their x86-64 types, and operations whose
+//! behavior is undefined, like division by zero, signed overflow or an address
+//! past the end of an object, are reported rather than folded.
+//!
+//! The same [`Evaluator`] runs on the parser's untyped expressions, for array
+//! lengths, bit-field widths, enumerators, `case` labels, designators and
+//! `_Static_assert`, and on type checked ones for static initializers. Without a
+//! type from the checker, the type of each subexpression is worked out here by
+//! the same rules.
+
+use tokengen::span::SourceSpan;
+
+use crate::{
+    ast::{
+        BinaryOperator, Conversion, Expression, ExpressionKind, Identifier, Literal, UnaryOperator,
+    },
+    types::{QualifiedType, TagTable, Type},
+    ParseError,
+};
+
+/// What an identifier in a constant expression refers to, if it can appear in one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Entity {
+    EnumConstant(i64),
+    /// An object with static storage duration, by its name in assembly, and its type.
+    Object(String, QualifiedType),
+    Function(String, Type),
+}
+
+/// Where an address constant points.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Base {
+    /// An object or function with static storage duration, by its name in assembly,
+    /// and the size of the object if its type is complete.
+    Symbol(String, Option<u64>),
+    /// A string literal's array, with its terminating null.
+    String(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// An integer, or a pointer made from one, already reduced to the range of its type.
+    Integer(i128),
+    Floating(f64),
+    /// An address constant: the address of `Base` plus a byte offset.
+    Address(Base, i128),
+}
+
+/// The value of a constant expression and its type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constant {
+    pub value: Value,
+    pub ty: Type,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    /// A subexpression that can't be evaluated at compile time.
+    NotConstant(SourceSpan),
+    /// A subexpression whose evaluation has undefined behavior, and why.
+    Undefined(SourceSpan, String),
+}
+impl EvalError {
+    /// Report the error, as `not_constant` if the expression isn't constant at all.
+    pub fn into_error(self, not_constant: &str) -> ParseError {
+        match self {
+            Self::NotConstant(span) => ParseError::new(span, not_constant),
+            Self::Undefined(span, context) => ParseError::new(span, &context),
+        }
+    }
+}
+
+/// How a subexpression is being evaluated.
+#[derive(Clone, Copy)]
+struct Context {
+    /// Whether this is an integer constant expression, which can't involve addresses.
+    integer: bool,
+    /// Whether the value is used, rather than in the arm of a conditional or
+    /// logical operator that isn't taken, where undefined behavior doesn't happen.
+    evaluated: bool,
+}
+
+pub struct Evaluator<'a> {
+    tags: &'a TagTable,
+    lookup: &'a dyn Fn(&Identifier) -> Option<Entity>,
+}
+impl<'a> Evaluator<'a> {
+    /// An evaluator that asks `lookup` what identifiers refer to.
+    pub fn new(tags: &'a TagTable, lookup: &'a dyn Fn(&Identifier) -> Option<Entity>) -> Self {
+        Self { tags, lookup }
+    }
+
+    /// Evaluate an integer constant expression.
+    pub fn integer(&self, expression: &Expression) -> Result<i128, EvalError> {
+        let context = Context {
+            integer: true,
+            evaluated: true,
+        };
+        match self.evaluate_in(expression, context)? {
+            Constant {
+                value: Value::Integer(value),
+                ty,
+            } if ty.is_integer() => Ok(value),
+            _ => Err(EvalError::NotConstant(expression.span)),
+        }
+    }
+
+    /// Evaluate an arithmetic constant expression or an address constant, as in
+    /// the initializer of an object with static storage duration.
+    pub fn evaluate(&self, expression: &Expression) -> Result<Constant, EvalError> {
+        let context = Context {
+            integer: false,
+            evaluated: true,
+        };
+        self.evaluate_in(expression, context).map(decay)
+    }
+
+    /// Evaluate an expression without the array-to-pointer and function-to-pointer
+    /// conversions, so that arrays and functions have their own types.
+    fn evaluate_in(&self, expression: &Expression, cx: Context) -> Result<Constant, EvalError> {
+        let span = expression.span;
+        let not_constant = Err(EvalError::NotConstant(span));
+        match &expression.kind {
+            ExpressionKind::Constant(Literal::Integer(value, ty)) => Ok(Constant {
+                value: Value::Integer(*value),
+                ty: ty.clone(),
+            }),
+            ExpressionKind::Constant(Literal::Floating(value, ty)) => Ok(Constant {
+                value: Value::Floating(*value),
+                ty: ty.clone(),
+            }),
+            ExpressionKind::Identifier(identifier) => match (self.lookup)(identifier) {
+                Some(Entity::EnumConstant(value)) => Ok(Constant {
+                    value: Value::Integer(i128::from(value)),
+                    ty: Type::Int,
+                }),
+                Some(Entity::Object(..) | Entity::Function(..)) if !cx.integer => {
+                    self.designator(expression, cx)
+                }
+                _ => not_constant,
+            },
+            ExpressionKind::StringLiteral(_)
+            | ExpressionKind::Subscript(..)
+            | ExpressionKind::Member(..)
+            | ExpressionKind::PointerMember(..)
+            | ExpressionKind::Unary(UnaryOperator::Dereference, _)
+                if !cx.integer =>
+            {
+                self.designator(expression, cx)
+            }
+            ExpressionKind::Unary(UnaryOperator::AddressOf, operand) if !cx.integer => {
+                let Constant { value, ty } = self.address(operand, cx)?;
+                Ok(Constant {
+                    value,
+                    ty: Type::pointer_to(ty),
+                })
+            }
+            ExpressionKind::Unary(op, operand) => self.unary(*op, operand, span, cx),
+            ExpressionKind::Binary(op, left, right) => self.binary(*op, left, right, span, cx),
+            ExpressionKind::Conditional(condition, then, els) => {
+                let condition = self.operand(condition, cx)?;
+                let is_true = self.is_true(&condition);
+                let (taken, skipped) = match is_true {
+                    true => (then, els),
+                    false => (els, then),
+                };
+                let taken = self.operand(taken, cx)?;
+                let skipped = self.operand(
+                    skipped,
+                    Context {
+                        evaluated: false,
+                        ..cx
+                    },
+                )?;
+                let ty = match (
+                    &expression.ty,
+                    taken.ty.is_arithmetic() && skipped.ty.is_arithmetic(),
+                ) {
+                    (Some(ty), _) => ty.ty.clone(),
+                    (None, true) => self.tags.common_type(&taken.ty, &skipped.ty),
+                    (None, false) => taken.ty.clone(),
+                };
+                self.convert(taken, &ty, span, cx)
+            }
+            ExpressionKind::Cast(type_name, operand) => {
+                let ty = &type_name.ty.ty;
+                if cx.integer && !ty.is_integer() {
+                    return not_constant;
+                }
+                let operand = self.operand(operand, cx)?;
+                self.convert(operand, ty, span, cx)
+            }
+            ExpressionKind::ImplicitConversion(conversion, operand) => {
+                let Some(ty) = &expression.ty else {
+                    return not_constant;
+                };
+                match conversion {
+                    Conversion::ArrayToPointer | Conversion::FunctionToPointer => {
+                        Ok(decay(self.evaluate_in(operand, cx)?))
+                    }
+                    _ => {
+                        let operand = self.operand(operand, cx)?;
+                        self.convert(operand, &ty.ty, span, cx)
+                    }
+                }
+            }
+            ExpressionKind::SizeofExpression(operand) => {
+                // The operand isn't evaluated, so only its type matters.
+                let ty = match &operand.ty {
+                    Some(ty) => ty.ty.clone(),
+                    None => {
+                        let cx = Context {
+                            integer: false,
+                            evaluated: false,
+                        };
+                        self.evaluate_in(operand, cx)?.ty
+                    }
+                };
+                self.size_of(&ty, span)
+            }
+            ExpressionKind::SizeofType(type_name) => self.size_of(&type_name.ty.ty, span),
+            ExpressionKind::Offsetof(_, _, offset) => Ok(Constant {
+                value: Value::Integer(*offset as i128),
+                ty: Type::UnsignedLong,
+            }),
+            _ => not_constant,
+        }
+    }
+
+    /// Evaluate an operand, after the conversions of arrays and functions to pointers.
+    fn operand(&self, expression: &Expression, cx: Context) -> Result<Constant, EvalError> {
+        self.evaluate_in(expression, cx).map(decay)
+    }
+
+    /// An array or function designator, which stands for its address with the
+    /// type of the array or function. Reading the value of any other object isn't constant.
+    fn designator(&self, expression: &Expression, cx: Context) -> Result<Constant, EvalError> {
+        let address = self.address(expression, cx)?;
+        match address.ty.is_array() || address.ty.is_function() {
+            true => Ok(address),
+            false => Err(EvalError::NotConstant(expression.span)),
+        }
+    }
+
+    /// The address of an lvalue, with the type of the object it designates.
+    fn address(&self, expression: &Expression, cx: Context) -> Result<Constant, EvalError> {
+        let not_constant = Err(EvalError::NotConstant(expression.span));
+        let address = |base, ty| Constant {
+            value: Value::Address(base, 0),
+            ty,
+        };
+        match &expression.kind {
+            ExpressionKind::Identifier(identifier) => match (self.lookup)(identifier) {
+                Some(Entity::Object(name, ty)) => {
+                    let size = self.tags.layout(&ty.ty).map(|layout| layout.size);
+                    Ok(address(Base::Symbol(name, size), ty.ty))
+                }
+                Some(Entity::Function(name, ty)) => Ok(address(Base::Symbol(name, None), ty)),
+                _ => not_constant,
+            },
+            ExpressionKind::StringLiteral(bytes) => {
+                let mut bytes = bytes.clone();
+                bytes.push(0);
+                let ty = Type::Array(Box::new(Type::Char.into()), Some(bytes.len() as u64));
+                Ok(address(Base::String(bytes), ty))
+            }
+            ExpressionKind::Unary(UnaryOperator::Dereference, pointer) => {
+                let pointer = self.operand(pointer, cx)?;
+                match (&pointer.value, pointer.ty) {
+                    (Value::Address(..), Type::Pointer(pointee)) => Ok(Constant {
+                        value: pointer.value,
+                        ty: pointee.ty,
+                    }),
+                    _ => not_constant,
+                }
+            }
+            ExpressionKind::Subscript(left, right) => {
+                let (left, right) = (self.operand(left, cx)?, self.operand(right, cx)?);
+                let (pointer, index) = match left.ty {
+                    Type::Pointer(_) => (left, right),
+                    _ => (right, left),
+                };
+                let pointer = self.offset(pointer, index, false, expression.span, cx)?;
+                let Type::Pointer(element) = pointer.ty else {
+                    return not_constant;
+                };
+                Ok(Constant {
+                    value: pointer.value,
+                    ty: element.ty,
+                })
+            }
+            ExpressionKind::Member(base, member) => {
+                let base = self.address(base, cx)?;
+                self.member(base, member, expression.span)
+            }
+            ExpressionKind::PointerMember(pointer, member) => {
+                let pointer = self.operand(pointer, cx)?;
+                let Type::Pointer(record) = pointer.ty else {
+                    return not_constant;
+                };
+                let base = Constant {
+                    value: pointer.value,
+                    ty: record.ty,
+                };
+                self.member(base, member, expression.span)
+            }
+            _ => not_constant,
+        }
+    }
+
+    /// The address of a member of the struct or union at `base`.
+    fn member(
+        &self,
+        base: Constant,
+        member: &Identifier,
+        span: SourceSpan,
+    ) -> Result<Constant, EvalError> {
+        let (Value::Address(address, offset), Type::Struct(tag) | Type::Union(tag)) =
+            (base.value, &base.ty)
+        else {
+            return Err(EvalError::NotConstant(span));
+        };
+        match self.tags.member(tag, &member.name) {
+            Some((field, member)) if field.bit_offset.is_none() => Ok(Constant {
+                value: Value::Address(address, offset + i128::from(field.offset)),
+                ty: member.ty.ty.clone(),
+            }),
+            _ => Err(EvalError::NotConstant(span)),
+        }
+    }
+
+    fn unary(
+        &self,
+        op: UnaryOperator,
+        operand: &Expression,
+        span: SourceSpan,
+        cx: Context,
+    ) -> Result<Constant, EvalError> {
+        let operand = self.operand(operand, cx)?;
+        if op == UnaryOperator::Not {
+            let value = i128::from(!self.is_true(&operand));
+            return self.integer_result(value, &Type::Int, span, cx);
+        }
+        if !operand.ty.is_arithmetic() {
+            return Err(EvalError::NotConstant(span));
+        }
+        let ty = self.tags.promote(&operand.ty);
+        let Constant { value, ty } = self.convert(operand, &ty, span, cx)?;
+        match (op, value) {
+            (UnaryOperator::Plus, value) => Ok(Constant { value, ty }),
+            (UnaryOperator::Negate, Value::Floating(value)) => self.floating(-value, ty),
+            (UnaryOperator::Negate, Value::Integer(value)) => {
+                self.integer_result(-value, &ty, span, cx)
+            }
+            (UnaryOperator::Complement, Value::Integer(value)) => {
+                self.integer_result(!value, &ty, span, cx)
+            }
+            _ => Err(EvalError::NotConstant(span)),
+        }
+    }
+
+    fn binary(
+        &self,
+        op: BinaryOperator,
+        left: &Expression,
+        right: &Expression,
+        span: SourceSpan,
+        cx: Context,
+    ) -> Result<Constant, EvalError> {
+        let not_constant = Err(EvalError::NotConstant(span));
+        match op {
+            BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr => {
+                let left = self.is_true(&self.operand(left, cx)?);
+                // The right operand only runs if the left doesn't decide the result.
+                let decided = left == (op == BinaryOperator::LogicalOr);
+                let right_cx = Context {
+                    evaluated: cx.evaluated && !decided,
+                    ..cx
+                };
+                let right = self.is_true(&self.operand(right, right_cx)?);
+                let value = match op {
+                    BinaryOperator::LogicalAnd => left && right,
+                    _ => left || right,
+                };
+                return self.integer_result(i128::from(value), &Type::Int, span, cx);
+            }
+            // The comma operator can't appear in a constant expression (C17 6.6p3).
+            BinaryOperator::Comma => return not_constant,
+            _ => {}
+        }
+        let (left, right) = (self.operand(left, cx)?, self.operand(right, cx)?);
+        match (op, &left.ty, &right.ty) {
+            (BinaryOperator::Add, Type::Pointer(_), _) => {
+                return self.offset(left, right, false, span, cx)
+            }
+            (BinaryOperator::Add, _, Type::Pointer(_)) => {
+                return self.offset(right, left, false, span, cx)
+            }
+            (BinaryOperator::Subtract, Type::Pointer(_), Type::Pointer(_)) => {
+                return self.difference(left, right, span, cx)
+            }
+            (BinaryOperator::Subtract, Type::Pointer(_), _) => {
+                return self.offset(left, right, true, span, cx)
+            }
+            _ if !left.ty.is_arithmetic() || !right.ty.is_arithmetic() => return not_constant,
+            _ => {}
+        }
+
+        let is_shift = matches!(op, BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight);
+        let ty = match is_shift {
+            true => self.tags.promote(&left.ty),
+            false => self.tags.common_type(&left.ty, &right.ty),
+        };
+        let left = self.convert(left, &ty, span, cx)?.value;
+        let right = match is_shift {
+            true => {
+                let right_ty = self.tags.promote(&right.ty);
+                self.convert(right, &right_ty, span, cx)?.value
+            }
+            false => self.convert(right, &ty, span, cx)?.value,
+        };
+        let boolean = |value: bool| self.integer_result(i128::from(value), &Type::Int, span, cx);
+        let (left, right) = match (left, right) {
+            (Value::Integer(left), Value::Integer(right)) => (left, right),
+            (Value::Floating(left), Value::Floating(right)) => {
+                return match op {
+                    BinaryOperator::Multiply => self.floating(left * right, ty),
+                    BinaryOperator::Divide => self.floating(left / right, ty),
+                    BinaryOperator::Add => self.floating(left + right, ty),
+                    BinaryOperator::Subtract => self.floating(left - right, ty),
+                    BinaryOperator::LessThan => boolean(left < right),
+                    BinaryOperator::GreaterThan => boolean(left > right),
+                    BinaryOperator::LessOrEqual => boolean(left <= right),
+                    BinaryOperator::GreaterOrEqual => boolean(left >= right),
+                    BinaryOperator::Equal => boolean(left == right),
+                    BinaryOperator::NotEqual => boolean(left != right),
+                    _ => not_constant,
+                };
+            }
+            _ => return not_constant,
+        };
+        let undefined = |context: String| match cx.evaluated {
+            true => Err(EvalError::Undefined(span, context)),
+            false => Ok(Constant {
+                value: Value::Integer(0),
+                ty: ty.clone(),
+            }),
+        };
+        let value = match op {
+            BinaryOperator::Multiply => left * right,
+            BinaryOperator::Divide | BinaryOperator::Remainder if right == 0 => {
+                return undefined("division by zero".to_string())
+            }
+            BinaryOperator::Divide => left / right,
+            BinaryOperator::Remainder => {
+                // `%` is undefined whenever `/` is, even though the remainder would fit.
+                self.integer_result(left / right, &ty, span, cx)?;
+                left % right
+            }
+            BinaryOperator::Add => left + right,
+            BinaryOperator::Subtract => left - right,
+            BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => {
+                let width = self.size(&ty) * 8;
+                if right < 0 {
+                    return undefined(format!("shift count {right} is negative"));
+                }
+                if right >= i128::from(width) {
+                    return undefined(format!(
+                        "shift count {right} is not less than the width of `{ty}`"
+                    ));
+                }
+                match op {
+                    BinaryOperator::ShiftLeft if left < 0 => {
+                        return undefined(format!("left shift of negative value {left}"))
+                    }
+                    BinaryOperator::ShiftLeft => left << right,
+                    _ => left >> right,
+                }
+            }
+            BinaryOperator::LessThan => return boolean(left < right),
+            BinaryOperator::GreaterThan => return boolean(left > right),
+            BinaryOperator::LessOrEqual => return boolean(left <= right),
+            BinaryOperator::GreaterOrEqual => return boolean(left >= right),
+            BinaryOperator::Equal => return boolean(left == right),
+            BinaryOperator::NotEqual => return boolean(left != right),
+            BinaryOperator::BitwiseAnd => left & right,
+            BinaryOperator::BitwiseXor => left ^ right,
+            BinaryOperator::BitwiseOr => left | right,
+            BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr | BinaryOperator::Comma => {
+                unreachable!("handled above")
+            }
+        };
+        self.integer_result(value, &ty, span, cx)
+    }
+
+    /// `pointer + index`, or `pointer - index`, in units of the pointed-to type.
+    fn offset(
+        &self,
+        pointer: Constant,
+        index: Constant,
+        negate: bool,
+        span: SourceSpan,
+        cx: Context,
+    ) -> Result<Constant, EvalError> {
+        let not_constant = Err(EvalError::NotConstant(span));
+        let (Type::Pointer(pointee), Value::Integer(index)) = (&pointer.ty, index.value) else {
+            return not_constant;
+        };
+        let Some(layout) = self.tags.layout(&pointee.ty) else {
+            return not_constant;
+        };
+        let delta = match negate {
+            true => -index,
+            false => index,
+        } * i128::from(layout.size);
+        match pointer.value {
+            Value::Address(base, offset) => {
+                // Only the addresses from the start of an object to just past its
+                // end can be computed.
+                let offset = offset + delta;
+                let size = match &base {
+                    Base::Symbol(_, size) => *size,
+                    Base::String(bytes) => Some(bytes.len() as u64),
+                };
+                match size {
+                    Some(size) if cx.evaluated && !(0..=i128::from(size)).contains(&offset) => {
+                        Err(EvalError::Undefined(
+                            span,
+                            format!("the address is {offset} bytes into an object of {size} bytes"),
+                        ))
+                    }
+                    _ => Ok(Constant {
+                        value: Value::Address(base, offset),
+                        ty: pointer.ty,
+                    }),
+                }
+            }
+            Value::Integer(value) => self.convert(
+                Constant {
+                    value: Value::Integer(value + delta),
+                    ty: Type::UnsignedLong,
+                },
+                &pointer.ty,
+                span,
+                cx,
+            ),
+            Value::Floating(_) => not_constant,
+        }
+    }
+
+    /// `left - right` for two pointers into the same object, in units of the pointed-to type.
+    fn difference(
+        &self,
+        left: Constant,
+        right: Constant,
+        span: SourceSpan,
+        cx: Context,
+    ) -> Result<Constant, EvalError> {
+        let not_constant = Err(EvalError::NotConstant(span));
+        let (
+            Value::Address(left_base, left_offset),
+            Value::Address(right_base, right_offset),
+            Type::Pointer(pointee),
+        ) = (left.value, right.value, left.ty)
+        else {
+            return not_constant;
+        };
+        match self.tags.layout(&pointee.ty) {
+            Some(layout) if left_base == right_base && layout.size > 0 => {
+                let value = (left_offset - right_offset) / i128::from(layout.size);
+                self.integer_result(value, &Type::Long, span, cx)
+            }
+            _ => not_constant,
+        }
+    }
+
+    /// Convert a constant to `ty`, as a cast or implicit conversion does.
+    fn convert(
+        &self,
+        constant: Constant,
+        ty: &Type,
+        span: SourceSpan,
+        cx: Context,
+    ) -> Result<Constant, EvalError> {
+        let not_constant = Err(EvalError::NotConstant(span));
+        let target = self.tags.underlying_type(ty);
+        let value = match (constant.value, &target) {
+            (Value::Integer(value), Type::Float | Type::Double) => {
+                return self.floating(value as f64, target)
+            }
+            (Value::Integer(value), Type::Pointer(_)) if !cx.integer => {
+                Value::Integer(self.wrap(value, &target))
+            }
+            (Value::Integer(value), _) if target.is_integer() => {
+                Value::Integer(self.wrap(value, &target))
+            }
+            (Value::Floating(value), Type::Float | Type::Double) => {
+                return self.floating(value, target)
+            }
+            (Value::Floating(value), _) if target.is_integer() => {
+                let truncated = value.trunc();
+                let (min, max) = self.range(&target);
+                if !(min as f64..=max as f64).contains(&truncated) {
+                    return match cx.evaluated {
+                        true => Err(EvalError::Undefined(
+                            span,
+                            format!(
+                                "value {value} is outside the range of representable values of type `{ty}`"
+                            ),
+                        )),
+                        false => Ok(Constant {
+                            value: Value::Integer(0),
+                            ty: ty.clone(),
+                        }),
+                    };
+                }
+                Value::Integer(truncated as i128)
+            }
+            (value @ Value::Address(..), Type::Pointer(_)) => value,
+            _ => return not_constant,
+        };
+        Ok(Constant {
+            value,
+            ty: ty.clone(),
+        })
+    }
+
+    /// The result of an integer operation, which must fit in a signed type.
+    fn integer_result(
+        &self,
+        value: i128,
+        ty: &Type,
+        span: SourceSpan,
+        cx: Context,
+    ) -> Result<Constant, EvalError> {
+        let (min, max) = self.range(ty);
+        let value = match ty.is_signed() && !(min..=max).contains(&value) {
+            true if cx.evaluated => {
+                return Err(EvalError::Undefined(
+                    span,
+                    format!(
+                        "value {value} is outside the range of representable values of type `{ty}`"
+                    ),
+                ))
+            }
+            _ => self.wrap(value, ty),
+        };
+        Ok(Constant {
+            value: Value::Integer(value),
+            ty: ty.clone(),
+        })
+    }
+
+    fn floating(&self, value: f64, ty: Type) -> Result<Constant, EvalError> {
+        let value = match ty {
+            Type::Float => f64::from(value as f32),
+            _ => value,
+        };
+        Ok(Constant {
+            value: Value::Floating(value),
+            ty,
+        })
+    }
+
+    fn size_of(&self, ty: &Type, span: SourceSpan) -> Result<Constant, EvalError> {
+        match self.tags.layout(ty) {
+            Some(layout) => Ok(Constant {
+                value: Value::Integer(i128::from(layout.size)),
+                ty: Type::UnsignedLong,
+            }),
+            None => Err(EvalError::NotConstant(span)),
+        }
+    }
+
+    fn size(&self, ty: &Type) -> u64 {
+        self.tags
+            .layout(ty)
+            .expect("scalar types are complete")
+            .size
+    }
+
+    /// The smallest and largest values of an integer or pointer type.
+    fn range(&self, ty: &Type) -> (i128, i128) {
+        let ty = self.tags.underlying_type(ty);
+        let bits = self.size(&ty) * 8;
+        match ty.is_signed() {
+            true => (-(1 << (bits - 1)), (1 << (bits - 1)) - 1),
+            false => (0, (1 << bits) - 1),
+        }
+    }
+
+    /// Reduce `value` modulo the width of `ty`, as a conversion to it does.
+    fn wrap(&self, value: i128, ty: &Type) -> i128 {
+        let (min, max) = self.range(ty);
+        let modulus = max - min + 1;
+        (value - min).rem_euclid(modulus) + min
+    }
+
+    fn is_true(&self, constant: &Constant) -> bool {
+        match &constant.value {
+            Value::Integer(value) => *value != 0,
+            Value::Floating(value) => *value != 0.0,
+            // The address of an object is never null.
+            Value::Address(..) => true,
+        }
+    }
+}
+
+/// The array-to-pointer and function-to-pointer conversions.
+fn decay(constant: Constant) -> Constant {
+    let ty = match constant.ty {
+        Type::Array(element, _) => Type::Pointer(element),
+        Type::Function(function) => Type::pointer_to(Type::Function(function)),
+        ty => ty,
+    };
+    Constant { ty, ..constant }
+}
+
+#[cfg(test)]
+mod constant_tests {
+    use std::collections::HashMap;
+
+    use expect_test::{expect, Expect};
+    use tokengen::span::Span;
+    use wacc_lexer::Lexer;
+
+    use super::{Base, Entity, EvalError, Evaluator, Value};
+    use crate::{
+        ast::{ExternalDeclaration, Identifier, InitializerKind},
+        Parser,
+    };
+
+    /// Evaluate the initializer of every file-scope declaration, as the parser
+    /// leaves them, with every file-scope name an object or function.
+    fn check_constants(src: &str, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let program = parser.parse_program();
+        let declarators = program
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ExternalDeclaration::Declaration(declaration) => Some(&declaration.declarators),
+                _ => None,
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+        let entities = declarators
+            .iter()
+            .map(|declarator| {
+                let name = declarator.name.name.clone();
+                let entity = match declarator.ty.ty.is_function() {
+                    true => Entity::Function(name.clone(), declarator.ty.ty.clone()),
+                    false => Entity::Object(name.clone(), declarator.ty.clone()),
+                };
+                (name, entity)
+            })
+            .collect::<HashMap<_, _>>();
+        let lookup = |identifier: &Identifier| entities.get(&identifier.name).cloned();
+        let evaluator = Evaluator::new(&program.tags, &lookup);
+        let mut output = Vec::new();
+        for declarator in declarators {
+            let Some(InitializerKind::Expression(expression)) = declarator
+                .initializer
+                .as_ref()
+                .map(|initializer| &initializer.kind)
+            else {
+                continue;
+            };
+            let result = match evaluator.evaluate(expression) {
+                Ok(constant) => {
+                    let value = match constant.value {
+                        Value::Integer(value) => value.to_string(),
+                        Value::Floating(value) => format!("{value:?}"),
+                        Value::Address(Base::Symbol(name, _), offset) => {
+                            format!("&{name} + {offset}")
+                        }
+                        Value::Address(Base::String(bytes), offset) => {
+                            format!("&\"{}\" + {offset}", bytes.escape_ascii())
+                        }
+                    };
+                    format!("{value} : {}", constant.ty)
+                }
+                Err(EvalError::NotConstant(span)) => format!("not constant at `{}`", span.span()),
+                Err(EvalError::Undefined(span, context)) => {
+                    format!("{context} at `{}`", span.span())
+                }
+            };
+            output.push(format!("{}: {result}", declarator.name.name));
+        }
+        expect.assert_eq(&output.join("\n"));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        check_constants(
+            r#"
+            long big = 4294967295;
+            unsigned all = (unsigned)-1;
+            unsigned char low = (unsigned char)300;
+            unsigned sum = (unsigned)4000000000 + 500000000;
+            int min = -2147483647 - 1;
+            int compare = -1 < (unsigned)0;
+            double half = (float)3 / 2;
+            int truncated = (int)((double)7 / 2);
+            unsigned long size = sizeof(int[3]) + sizeof(struct { char c; long l; });
+            "#,
+            expect![[r#"
+                big: 4294967295 : long
+                all: 4294967295 : unsigned int
+                low: 44 : unsigned char
+                sum: 205032704 : unsigned int
+                min: -2147483648 : int
+                compare: 0 : int
+                half: 1.5 : float
+                truncated: 3 : int
+                size: 28 : unsigned long"#]],
+        );
+    }
+
+    #[test]
+    fn test_undefined_behavior() {
+        check_constants(
+            r#"
+            int overflow = 2147483647 + 1;
+            long quotient = 1 / (2 - 2);
+            int wide = 1 << 32;
+            int negative = 1 >> -1;
+            int shifted = -1 << 1;
+            int converted = (char)(double)1000;
+            int skipped = 1 || 1 / 0;
+            int untaken = 0 ? 2147483647 + 1 : 2;
+            int short_circuit = 0 && (char)(double)1000;
+            int remainder = (-2147483647 - 1) % -1;
+            long long_remainder = (-9223372036854775807 - 1) % -1;
+            int untaken_remainder = 0 && (-2147483647 - 1) % -1;
+            "#,
+            expect![[r#"
+                overflow: value 2147483648 is outside the range of representable values of type `int` at `2147483647 + 1`
+                quotient: division by zero at `1 / (2 - 2)`
+                wide: shift count 32 is not less than the width of `int` at `1 << 32`
+                negative: shift count -1 is negative at `1 >> -1`
+                shifted: left shift of negative value -1 at `-1 << 1`
+                converted: value 1000 is outside the range of representable values of type `char` at `(char)(double)1000`
+                skipped: 1 : int
+                untaken: 2 : int
+                short_circuit: 0 : int
+                remainder: value 2147483648 is outside the range of representable values of type `int` at `(-2147483647 - 1) % -1`
+                long_remainder: value 9223372036854775808 is outside the range of representable values of type `long` at `(-9223372036854775807 - 1) % -1`
+                untaken_remainder: 0 : int"#]],
+        );
+    }
+
+    #[test]
+    fn test_address_constants() {
+        check_constants(
+            r#"
+            int g, arr[4];
+            struct s { int a; long b; } st;
+            int main(void);
+            int *element = &arr[2] + 1;
+            int *decayed = arr;
+            long *member = &st.b;
+            struct s *self = &st;
+            char *string = "hi" + 1;
+            long distance = &arr[3] - &arr[1];
+            int (*function)(void) = main;
+            int *null = (int *)0 + 2;
+            int read = g;
+            int *apart = &g - &st.a;
+            int *end = arr + 4;
+            int *past_end = arr + 5;
+            int *before = &arr[0] - 1;
+            int *past_g = &g + 2;
+            char *past_string = "hi" + 4;
+            int *untaken = 1 ? arr : arr + 5;
+            "#,
+            expect![[r#"
+                element: &arr + 12 : int *
+                decayed: &arr + 0 : int *
+                member: &st + 8 : long *
+                self: &st + 0 : struct s *
+                string: &"hi\x00" + 1 : char *
+                distance: 2 : long
+                function: &main + 0 : int (*)(void)
+                null: 8 : int *
+                read: not constant at `g`
+                apart: not constant at `&g - &st.a`
+                end: &arr + 16 : int *
+                past_end: the address is 20 bytes into an object of 16 bytes at `arr + 5`
+                before: the address is -4 bytes into an object of 16 bytes at `&arr[0] - 1`
+                past_g: the address is 8 bytes into an object of 4 bytes at `&g + 2`
+                past_string: the address is 4 bytes into an object of 3 bytes at `"hi" + 4`
+                untaken: &arr + 0 : int *"#]],
+        );
+    }
+}
@@ -0,0 +1,225 @@
+//! Checks on the structured control flow of a function body.
+//!
+//! These run once the whole body is parsed, since a `goto` may jump to a label
+//! defined further down. Each statement is checked against the loops and switches
+//! enclosing it: `break` needs a loop or switch, `continue` a loop, and `case`
+//! and `default` a switch. Whether two case values are the same depends on the
+//! type of the controlling expression, so the type checker sees to that.
+
+use std::collections::HashSet;
+
+use tokengen::span::{SourceSpan, Span};
+
+use crate::{
+    ast::{Block, BlockItem, Identifier, Statement, StatementKind},
+    ParseError, Parser,
+};
+
+/// A statement that `break` can leave.
+enum Breakable {
+    Loop,
+    Switch { has_default: bool },
+}
+
+#[derive(Default)]
+struct ControlFlow<'a> {
+    /// The loops and switches enclosing the current statement, innermost last.
+    enclosing: Vec<Breakable>,
+    labels: HashSet<&'a str>,
+    gotos: Vec<&'a Identifier>,
+    errors: Vec<ParseError>,
+}
+impl<'a> ControlFlow<'a> {
+    fn block(&mut self, block: &'a Block) {
+        for item in &block.items {
+            if let BlockItem::Statement(statement) = item {
+                self.statement(statement);
+            }
+        }
+    }
+
+    fn nested(&mut self, breakable: Breakable, body: &'a Statement) {
+        self.enclosing.push(breakable);
+        self.statement(body);
+        self.enclosing.pop();
+    }
+
+    /// Whether the innermost enclosing switch has a `default` label, if there is one.
+    fn innermost_switch(&mut self) -> Option<&mut bool> {
+        self.enclosing
+            .iter_mut()
+            .rev()
+            .find_map(|breakable| match breakable {
+                Breakable::Switch { has_default } => Some(has_default),
+                Breakable::Loop => None,
+            })
+    }
+
+    fn error(&mut self, span: SourceSpan, context: &str) {
+        self.errors.push(ParseError::new(span, context));
+    }
+
+    fn statement(&mut self, statement: &'a Statement) {
+        match &statement.kind {
+            StatementKind::Return(_)
+            | StatementKind::Expression(_)
+            | StatementKind::Null
+            | StatementKind::Error => {}
+            StatementKind::Compound(block) => self.block(block),
+            StatementKind::If(_, then, els) => {
+                self.statement(then);
+                if let Some(els) = els {
+                    self.statement(els);
+                }
+            }
+            StatementKind::While(_, body)
+            | StatementKind::DoWhile(body, _)
+            | StatementKind::For(_, _, _, body) => self.nested(Breakable::Loop, body),
+            StatementKind::Switch(_, body) => {
+                self.nested(Breakable::Switch { has_default: false }, body)
+            }
+            StatementKind::Break => {
+                if self.enclosing.is_empty() {
+                    self.error(statement.span, "`break` outside of a loop or switch");
+                }
+            }
+            StatementKind::Continue => {
+                let in_loop = self
+                    .enclosing
+                    .iter()
+                    .any(|breakable| matches!(breakable, Breakable::Loop));
+                if !in_loop {
+                    self.error(statement.span, "`continue` outside of a loop");
+                }
+            }
+            StatementKind::Case(_, _, body) => {
+                if self.innermost_switch().is_none() {
+                    self.error(statement.span, "`case` label outside of a switch");
+                }
+                self.statement(body);
+            }
+            StatementKind::Default(body) => {
+                match self.innermost_switch() {
+                    None => self.error(statement.span, "`default` label outside of a switch"),
+                    Some(has_default) => {
+                        if std::mem::replace(has_default, true) {
+                            self.error(statement.span, "multiple `default` labels in one switch");
+                        }
+                    }
+                }
+                self.statement(body);
+            }
+            StatementKind::Goto(label) => self.gotos.push(label),
+            StatementKind::Labeled(label, body) => {
+                if !self.labels.insert(&label.name) {
+                    self.error(label.span, &format!("duplicate label `{}`", label.name));
+                }
+                self.statement(body);
+            }
+        }
+    }
+}
+
+impl Parser<'_> {
+    /// Check the control flow of a function body, recording any errors among the syntax errors.
+    pub(crate) fn check_control_flow(&mut self, body: &Block) {
+        let mut control_flow = ControlFlow::default();
+        control_flow.block(body);
+        for label in std::mem::take(&mut control_flow.gotos) {
+            if !control_flow.labels.contains(label.name.as_str()) {
+                control_flow.error(label.span, &format!("undefined label `{}`", label.name));
+            }
+        }
+        if control_flow.errors.is_empty() {
+            return;
+        }
+        self.errors.extend(control_flow.errors);
+        // Keep every error in source order, interleaved with the syntax errors in the body.
+        self.errors.sort_by_key(|err| err.span().start());
+    }
+}
+
+#[cfg(test)]
+mod control_flow_tests {
+    use expect_test::{expect, Expect};
+    use tokengen::span::Span;
+    use wacc_lexer::Lexer;
+
+    use crate::Parser;
+
+    fn check_errors(src: &str, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        parser.parse_program();
+        let errors = parser
+            .errors()
+            .iter()
+            .map(|err| format!("{} at `{}`", err.context(), err.span().span()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        expect.assert_eq(&errors);
+    }
+
+    #[test]
+    fn test_valid_control_flow() {
+        check_errors(
+            r#"
+            enum { ONE = 1 };
+            int f(int x) {
+                goto done;
+                while (x) {
+                    switch (x) {
+                    case ONE: continue;
+                    case 1 + 1: { case 3: break; }
+                    default: for (;;) { if (x) break; else continue; }
+                    }
+                    switch (x) case 1: switch (x) case 1: default: ;
+                    switch ((unsigned long)x) case 0xFFFFFFFFFFFFFFFFul: case -1l - 0x7FFFFFFFFFFFFFFF: ;
+                }
+                do { again: x--; } while (x);
+            done:
+                if (x) goto again;
+                return x;
+            }
+            int g(void) { done: return 0; }
+            "#,
+            expect![""],
+        );
+    }
+
+    #[test]
+    fn test_control_flow_errors() {
+        check_errors(
+            r#"
+            int f(int x) {
+                break;
+                if (x) continue;
+                case 1: ;
+                default: ;
+                switch (x) {
+                case 1: case 2: while (x) case 1: ;
+                default: continue;
+                default: break;
+                }
+                goto missing;
+            twice: ;
+                { twice: ; }
+                x = (1;
+                goto twice;
+                goto nowhere;
+            }
+            "#,
+            expect![[r#"
+                `break` outside of a loop or switch at `break;`
+                `continue` outside of a loop at `continue;`
+                `case` label outside of a switch at `case 1: ;`
+                `default` label outside of a switch at `default: ;`
+                `continue` outside of a loop at `continue;`
+                multiple `default` labels in one switch at `default: break;`
+                undefined label `missing` at `missing`
+                duplicate label `twice` at `twice`
+                expected `)`, found `;` at `;`
+                undefined label `nowhere` at `nowhere`"#]],
+        );
+    }
+}
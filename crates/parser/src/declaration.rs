@@ -0,0 +1,1133 @@
+//! Declarations, and C's inside-out declarator syntax.
+//!
+//! A declarator is parsed into a [`Declarator`] tree mirroring how it was written,
+//! then the type of the declared name is derived by unwinding that tree around
+//! the base type given by the declaration specifiers.
+
+use tokengen::span::{SourceSpan, Span};
+use wacc_lexer::c_token::{
+    c_keyword::KeywordKind,
+    c_symbol::{CookedSymbolKind, SymbolKind},
+    CTokenKind,
+};
+
+use crate::{
+    ast::{
+        Block, BlockItem, Declaration, DeclarationSpecifiers, Declarator, DeclaratorKind,
+        Expression, ExpressionKind, ExternalDeclaration, FunctionDefinition, Identifier,
+        InitDeclarator, ParameterDeclaration, ParameterList, StaticAssert, StorageClass, TypeName,
+        TypeSpecifier,
+    },
+    constant::{Entity, EvalError, Evaluator},
+    expression::{ASSIGNMENT_PRECEDENCE, CONDITIONAL_PRECEDENCE},
+    scope::Binding,
+    types::{FunctionType, QualifiedType, Qualifiers, TagKind, Type},
+    ParseError, Parser,
+};
+
+pub(crate) fn type_specifier(kind: CTokenKind) -> Option<TypeSpecifier> {
+    Some(match kind {
+        CTokenKind::Keyword(KeywordKind::Void) => TypeSpecifier::Void,
+        CTokenKind::Keyword(KeywordKind::Char) => TypeSpecifier::Char,
+        CTokenKind::Keyword(KeywordKind::Short) => TypeSpecifier::Short,
+        CTokenKind::Keyword(KeywordKind::Int) => TypeSpecifier::Int,
+        CTokenKind::Keyword(KeywordKind::Long) => TypeSpecifier::Long,
+        CTokenKind::Keyword(KeywordKind::Float) => TypeSpecifier::Float,
+        CTokenKind::Keyword(KeywordKind::Double) => TypeSpecifier::Double,
+        CTokenKind::Keyword(KeywordKind::Signed) => TypeSpecifier::Signed,
+        CTokenKind::Keyword(KeywordKind::Unsigned) => TypeSpecifier::Unsigned,
+        _ => return None,
+    })
+}
+
+fn type_qualifier(kind: CTokenKind) -> Option<Qualifiers> {
+    let mut qualifiers = Qualifiers::NONE;
+    match kind {
+        CTokenKind::Keyword(KeywordKind::Const) => qualifiers.is_const = true,
+        CTokenKind::Keyword(KeywordKind::Volatile) => qualifiers.is_volatile = true,
+        CTokenKind::Keyword(KeywordKind::Restrict) => qualifiers.is_restrict = true,
+        _ => return None,
+    }
+    Some(qualifiers)
+}
+
+fn storage_class(kind: CTokenKind) -> Option<StorageClass> {
+    match kind {
+        CTokenKind::Keyword(KeywordKind::Typedef) => Some(StorageClass::Typedef),
+        CTokenKind::Keyword(KeywordKind::Static) => Some(StorageClass::Static),
+        CTokenKind::Keyword(KeywordKind::Extern) => Some(StorageClass::Extern),
+        _ => None,
+    }
+}
+
+/// Whether a keyword can begin the declaration specifiers of a declaration or type name.
+fn is_declaration_specifier(kind: CTokenKind) -> bool {
+    type_specifier(kind).is_some()
+        || type_qualifier(kind).is_some()
+        || storage_class(kind).is_some()
+        || matches!(
+            kind,
+            CTokenKind::Keyword(
+                KeywordKind::Struct | KeywordKind::Union | KeywordKind::Enum | KeywordKind::Inline
+            )
+        )
+}
+
+/// Which declarators are accepted where: declarations need a name, type names
+/// must not have one, and parameters can go either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DeclaratorMode {
+    Named,
+    Abstract,
+    Either,
+}
+
+/// The parameter list of the function a declarator declares, if it declares one.
+pub(crate) fn function_parameters(declarator: &Declarator) -> Option<&ParameterList> {
+    match &declarator.kind {
+        DeclaratorKind::Function(inner, parameters)
+            if matches!(inner.kind, DeclaratorKind::Identifier(_)) =>
+        {
+            Some(parameters)
+        }
+        DeclaratorKind::Pointer(_, inner)
+        | DeclaratorKind::Array(inner, _)
+        | DeclaratorKind::Function(inner, _) => function_parameters(inner),
+        DeclaratorKind::Identifier(_) => None,
+    }
+}
+
+impl Parser<'_> {
+    /// The base type spelled by a combination of type specifiers, in any order.
+    pub(crate) fn base_type(
+        &self,
+        specifiers: &DeclarationSpecifiers,
+    ) -> Result<QualifiedType, ParseError> {
+        use TypeSpecifier::*;
+        let count = |specifier| {
+            specifiers
+                .type_specifiers
+                .iter()
+                .filter(|s| **s == specifier)
+                .count()
+        };
+        let error = |context: &str| Err(ParseError::new(specifiers.span, context));
+        // Typedef names and tagged types can't be combined with other type specifiers.
+        let standalone = specifiers.type_specifiers.iter().find_map(|specifier| {
+            Some(match specifier {
+                TypedefName(name) => self
+                    .scopes
+                    .typedef(&name.name)
+                    .expect("typedef names are only parsed in scope")
+                    .clone(),
+                Record(record) if record.kind == TagKind::Struct => {
+                    Type::Struct(record.tag.clone()).into()
+                }
+                Record(record) => Type::Union(record.tag.clone()).into(),
+                Enum(specifier) => Type::Enum(specifier.tag.clone()).into(),
+                _ => return None,
+            })
+        });
+        if let Some(ty) = standalone {
+            if specifiers.type_specifiers.len() > 1 {
+                return error("invalid combination of type specifiers");
+            }
+            return Ok(ty.qualified(specifiers.qualifiers));
+        }
+        let (signed, unsigned) = (count(Signed), count(Unsigned));
+        if signed + unsigned > 1 {
+            return error("cannot combine `signed` and `unsigned` more than once");
+        }
+        let ty = match (
+            (count(Void), count(Char), count(Short), count(Int)),
+            (count(Long), count(Float), count(Double)),
+        ) {
+            ((1, 0, 0, 0), (0, 0, 0)) if signed + unsigned == 0 => Type::Void,
+            ((0, 1, 0, 0), (0, 0, 0)) if signed == 1 => Type::SignedChar,
+            ((0, 1, 0, 0), (0, 0, 0)) if unsigned == 1 => Type::UnsignedChar,
+            ((0, 1, 0, 0), (0, 0, 0)) => Type::Char,
+            ((0, 0, 1, 0 | 1), (0, 0, 0)) if unsigned == 1 => Type::UnsignedShort,
+            ((0, 0, 1, 0 | 1), (0, 0, 0)) => Type::Short,
+            ((0, 0, 0, 0), (0, 0, 0)) if signed + unsigned == 0 => {
+                return error("expected a type specifier");
+            }
+            ((0, 0, 0, 0 | 1), (0, 0, 0)) if unsigned == 1 => Type::UnsignedInt,
+            ((0, 0, 0, 0 | 1), (0, 0, 0)) => Type::Int,
+            ((0, 0, 0, 0 | 1), (1, 0, 0)) if unsigned == 1 => Type::UnsignedLong,
+            ((0, 0, 0, 0 | 1), (1, 0, 0)) => Type::Long,
+            ((0, 0, 0, 0 | 1), (2, 0, 0)) if unsigned == 1 => Type::UnsignedLongLong,
+            ((0, 0, 0, 0 | 1), (2, 0, 0)) => Type::LongLong,
+            ((0, 0, 0, 0), (0, 1, 0)) if signed + unsigned == 0 => Type::Float,
+            ((0, 0, 0, 0), (0, 0, 1)) if signed + unsigned == 0 => Type::Double,
+            ((0, 0, 0, 0), (1, 0, 1)) if signed + unsigned == 0 => {
+                return error("`long double` is not supported");
+            }
+            _ => return error("invalid combination of type specifiers"),
+        };
+
+        Ok(QualifiedType::new(ty, specifiers.qualifiers))
+    }
+
+    /// The typedef name at the token `n` tokens ahead of the next one, if it is one.
+    fn typedef_name_at(&self, n: usize) -> Option<&QualifiedType> {
+        match self.peek_nth(n) {
+            Some(CTokenKind::Identifier) => self.scopes.typedef(self.peek_nth_span(n).span()),
+            _ => None,
+        }
+    }
+
+    /// Whether the token `n` tokens ahead of the next one can begin a declaration or type name.
+    pub(crate) fn is_declaration_specifier_at(&self, n: usize) -> bool {
+        self.peek_nth(n).is_some_and(is_declaration_specifier) || self.typedef_name_at(n).is_some()
+    }
+
+    /// Run `parse` in a new innermost scope, so the names it declares go out of scope after.
+    pub(crate) fn in_scope<T>(&mut self, parse: impl FnOnce(&mut Self) -> T) -> T {
+        self.scopes.push();
+        let result = parse(self);
+        self.scopes.pop();
+        result
+    }
+
+    /// `<external-declaration> ::= <function-definition> | <declaration>`
+    pub(crate) fn parse_external_declaration(&mut self) -> Result<ExternalDeclaration, ParseError> {
+        if self.check(CTokenKind::Keyword(KeywordKind::StaticAssert)) {
+            return self
+                .parse_static_assert()
+                .map(ExternalDeclaration::StaticAssert);
+        }
+        let specifiers = self.parse_declaration_specifiers()?;
+        let base = self.base_type(&specifiers)?;
+        if self.check(CTokenKind::Punctuator(SymbolKind::Semicolon)) {
+            return self
+                .parse_declaration_rest(specifiers, base, Vec::new())
+                .map(ExternalDeclaration::Declaration);
+        }
+        let declarator = self.parse_declarator(DeclaratorMode::Named)?;
+        let (name, ty) = self.derive_type(&declarator, base.clone())?;
+        let name = name.expect("named declarators always have a name");
+        if !ty.ty.is_function() || !self.check(CTokenKind::Punctuator(SymbolKind::OpenCurlyBrace)) {
+            let first = self.parse_init_declarator_rest(declarator, name, ty, &specifiers)?;
+            return self
+                .parse_declaration_rest(specifiers, base, vec![first])
+                .map(ExternalDeclaration::Declaration);
+        }
+
+        if specifiers.storage_class == Some(StorageClass::Typedef) {
+            return Err(ParseError::new(
+                specifiers.span,
+                "function definition declared `typedef`",
+            ));
+        }
+        let parameters = function_parameters(&declarator)
+            .expect("function types are derived from function declarators")
+            .parameters
+            .clone();
+        if let Some(unnamed) = parameters.iter().find(|parameter| parameter.name.is_none()) {
+            return Err(ParseError::new(
+                unnamed.span,
+                "parameter name omitted in function definition",
+            ));
+        }
+        self.scopes.declare(&name.name, Binding::Ordinary);
+        let body = self.in_scope(|parser| {
+            for parameter in &parameters {
+                let name = parameter.name.as_ref().expect("checked above");
+                parser.scopes.declare(&name.name, Binding::Ordinary);
+            }
+            parser.parse_block()
+        })?;
+        self.check_control_flow(&body);
+        let span = specifiers.span.to(&body.span);
+
+        Ok(ExternalDeclaration::Function(Box::new(
+            FunctionDefinition {
+                specifiers,
+                declarator,
+                name,
+                ty,
+                parameters,
+                body,
+                span,
+            },
+        )))
+    }
+
+    /// `<block> ::= "{" { <block-item> } "}"`
+    pub(crate) fn parse_block(&mut self) -> Result<Block, ParseError> {
+        let start = self.expect(CTokenKind::Punctuator(SymbolKind::OpenCurlyBrace))?;
+        let items = self.in_scope(|parser| {
+            let mut items = Vec::new();
+            while parser.peek().is_some()
+                && !parser.check(CTokenKind::Punctuator(SymbolKind::CloseCurlyBrace))
+            {
+                items.push(parser.parse_block_item_or_recover());
+            }
+            items
+        });
+        let end = self.expect(CTokenKind::Punctuator(SymbolKind::CloseCurlyBrace))?;
+
+        Ok(Block {
+            items,
+            span: start.to(&end),
+        })
+    }
+
+    /// Parse a declaration or statement, replacing it with [`StatementKind::Error`] if it is malformed.
+    fn parse_block_item_or_recover(&mut self) -> BlockItem {
+        let start = self.pos;
+        let item = if self.check(CTokenKind::Keyword(KeywordKind::StaticAssert)) {
+            self.parse_static_assert().map(BlockItem::StaticAssert)
+        } else if self.is_declaration_specifier_at(0) && !self.is_label_at(0) {
+            self.parse_declaration().map(BlockItem::Declaration)
+        } else {
+            self.parse_statement().map(BlockItem::Statement)
+        };
+        item.unwrap_or_else(|err| BlockItem::Statement(self.recover_statement(start, err)))
+    }
+
+    /// `<declaration> ::= <declaration-specifiers> [ <init-declarator> { "," <init-declarator> } ] ";"`
+    pub(crate) fn parse_declaration(&mut self) -> Result<Declaration, ParseError> {
+        let specifiers = self.parse_declaration_specifiers()?;
+        let base = self.base_type(&specifiers)?;
+        let mut declarators = Vec::new();
+        if !self.check(CTokenKind::Punctuator(SymbolKind::Semicolon)) {
+            declarators.push(self.parse_init_declarator(&specifiers, &base)?);
+        }
+        self.parse_declaration_rest(specifiers, base, declarators)
+    }
+
+    /// `<static-assert> ::= "_Static_assert" "(" <constant-exp> "," <string-literal> ")" ";"`
+    ///
+    /// A well-formed assertion that fails, or whose condition isn't constant, is
+    /// reported without making the declaration an error node.
+    fn parse_static_assert(&mut self) -> Result<StaticAssert, ParseError> {
+        let start = self.expect(CTokenKind::Keyword(KeywordKind::StaticAssert))?;
+        self.expect(CTokenKind::Punctuator(SymbolKind::OpenParenthesis))?;
+        let condition = self.parse_expression_with_precedence(CONDITIONAL_PRECEDENCE)?;
+        self.expect(CTokenKind::Punctuator(SymbolKind::Comma))?;
+        if !self.check(CTokenKind::StringLiteral) {
+            return Err(self.error("expected a string literal"));
+        }
+        let message = self.parse_expression_with_precedence(ASSIGNMENT_PRECEDENCE)?;
+        let ExpressionKind::StringLiteral(message) = message.kind else {
+            return Err(ParseError::new(message.span, "expected a string literal"));
+        };
+        self.expect(CTokenKind::Punctuator(SymbolKind::CloseParenthesis))?;
+        let end = self.expect(CTokenKind::Punctuator(SymbolKind::Semicolon))?;
+
+        let not_constant = "static assertion expression is not an integer constant expression";
+        match self.integer_constant(&condition) {
+            Ok(0) => self.report(ParseError::new(
+                condition.span,
+                &format!("static assertion failed: {}", message.escape_ascii()),
+            )),
+            Ok(_) => {}
+            Err(err) => self.report(err.into_error(not_constant)),
+        }
+        Ok(StaticAssert {
+            condition,
+            message,
+            span: start.to(&end),
+        })
+    }
+
+    /// Parse the remaining init-declarators of a declaration, after those already parsed.
+    fn parse_declaration_rest(
+        &mut self,
+        specifiers: DeclarationSpecifiers,
+        base: QualifiedType,
+        mut declarators: Vec<InitDeclarator>,
+    ) -> Result<Declaration, ParseError> {
+        if !declarators.is_empty() {
+            while self
+                .eat(CTokenKind::Punctuator(SymbolKind::Comma))
+                .is_some()
+            {
+                declarators.push(self.parse_init_declarator(&specifiers, &base)?);
+            }
+        }
+        let end = self.expect(CTokenKind::Punctuator(SymbolKind::Semicolon))?;
+
+        Ok(Declaration {
+            span: specifiers.span.to(&end),
+            specifiers,
+            declarators,
+        })
+    }
+
+    /// `<init-declarator> ::= <declarator> [ "=" <initializer> ]`
+    fn parse_init_declarator(
+        &mut self,
+        specifiers: &DeclarationSpecifiers,
+        base: &QualifiedType,
+    ) -> Result<InitDeclarator, ParseError> {
+        let declarator = self.parse_declarator(DeclaratorMode::Named)?;
+        let (name, ty) = self.derive_type(&declarator, base.clone())?;
+        let name = name.expect("named declarators always have a name");
+        self.parse_init_declarator_rest(declarator, name, ty, specifiers)
+    }
+
+    fn parse_init_declarator_rest(
+        &mut self,
+        declarator: Declarator,
+        name: Identifier,
+        ty: QualifiedType,
+        specifiers: &DeclarationSpecifiers,
+    ) -> Result<InitDeclarator, ParseError> {
+        let storage_class = specifiers.storage_class;
+        if specifiers.is_inline
+            && (storage_class == Some(StorageClass::Typedef) || !ty.ty.is_function())
+        {
+            return Err(ParseError::new(
+                declarator.span,
+                "`inline` specified for something other than a function",
+            ));
+        }
+        // The name is in scope from the end of its declarator, before any initializer.
+        let is_typedef = storage_class == Some(StorageClass::Typedef);
+        let binding = match is_typedef {
+            true => Binding::Typedef(ty.clone()),
+            false => Binding::Ordinary,
+        };
+        self.scopes.declare(&name.name, binding);
+        if is_typedef && self.check(CTokenKind::Punctuator(SymbolKind::Equals)) {
+            return Err(ParseError::new(
+                self.peek_span(),
+                "a typedef cannot be initialized",
+            ));
+        }
+        let (initializer, ty, entries) = match self.eat(CTokenKind::Punctuator(SymbolKind::Equals))
+        {
+            Some(_) => {
+                let initializer = self.parse_initializer()?;
+                let is_static =
+                    self.scopes.is_file_scope() || storage_class == Some(StorageClass::Static);
+                let (ty, entries) = self.resolve_initializer(&ty, &initializer, is_static)?;
+                (Some(initializer), ty, entries)
+            }
+            None => (None, ty, Vec::new()),
+        };
+        let span = declarator.span.to(&self.prev_span());
+
+        Ok(InitDeclarator {
+            declarator,
+            name,
+            ty,
+            initializer,
+            entries,
+            span,
+        })
+    }
+
+    /// `<declaration-specifiers> ::= { <storage-class> | <type-specifier> | <type-qualifier> | "inline" }`
+    ///
+    /// An identifier is taken as a typedef name only before any other type
+    /// specifier, so `T T;` can redeclare a typedef name `T` as a variable.
+    pub(crate) fn parse_declaration_specifiers(
+        &mut self,
+    ) -> Result<DeclarationSpecifiers, ParseError> {
+        let start = self.peek_span();
+        let first = self.pos;
+        let mut storage_class = None;
+        let mut is_inline = false;
+        let mut type_specifiers = Vec::new();
+        let mut qualifiers = Qualifiers::NONE;
+        loop {
+            match self.peek() {
+                Some(kind) if self::storage_class(kind).is_some() => {
+                    if storage_class.is_some() {
+                        return Err(self.error("expected at most one storage class"));
+                    }
+                    storage_class = self::storage_class(kind);
+                }
+                // A function specifier may appear more than once (C17 6.7.4p7).
+                Some(CTokenKind::Keyword(KeywordKind::Inline)) => is_inline = true,
+                Some(CTokenKind::Identifier)
+                    if type_specifiers.is_empty() && self.typedef_name_at(0).is_some() =>
+                {
+                    type_specifiers.push(TypeSpecifier::TypedefName(self.parse_identifier()?));
+                    continue;
+                }
+                Some(CTokenKind::Keyword(KeywordKind::Struct | KeywordKind::Union)) => {
+                    type_specifiers.push(TypeSpecifier::Record(self.parse_record_specifier()?));
+                    continue;
+                }
+                Some(CTokenKind::Keyword(KeywordKind::Enum)) => {
+                    type_specifiers.push(TypeSpecifier::Enum(self.parse_enum_specifier()?));
+                    continue;
+                }
+                Some(kind) if type_specifier(kind).is_some() => {
+                    type_specifiers.push(type_specifier(kind).unwrap());
+                }
+                Some(kind) if type_qualifier(kind).is_some() => {
+                    qualifiers = qualifiers.union(type_qualifier(kind).unwrap());
+                }
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        if self.pos == first {
+            return Err(self.error("expected declaration specifiers"));
+        }
+
+        Ok(DeclarationSpecifiers {
+            storage_class,
+            is_inline,
+            type_specifiers,
+            qualifiers,
+            span: start.to(&self.prev_span()),
+        })
+    }
+
+    /// `<declarator> ::= "*" { <type-qualifier> } <declarator> | <direct-declarator>`
+    pub(crate) fn parse_declarator(
+        &mut self,
+        mode: DeclaratorMode,
+    ) -> Result<Declarator, ParseError> {
+        let Some(start) = self.eat(CTokenKind::Punctuator(SymbolKind::Asterisk)) else {
+            return self.parse_direct_declarator(mode);
+        };
+        let mut qualifiers = Qualifiers::NONE;
+        while let Some(qualifier) = self.peek().and_then(type_qualifier) {
+            self.pos += 1;
+            qualifiers = qualifiers.union(qualifier);
+        }
+        let inner = self.parse_declarator(mode)?;
+
+        Ok(Declarator {
+            span: start.to(&self.prev_span()),
+            kind: DeclaratorKind::Pointer(qualifiers, Box::new(inner)),
+        })
+    }
+
+    /// `<direct-declarator> ::= ( <identifier> | "(" <declarator> ")" ) { <declarator-suffix> }`
+    /// `<declarator-suffix> ::= "[" [ <exp> ] "]" | "(" <parameter-list> ")"`
+    ///
+    /// The name is missing from abstract declarators, so the suffixes may come first.
+    fn parse_direct_declarator(&mut self, mode: DeclaratorMode) -> Result<Declarator, ParseError> {
+        let start = self.peek_span();
+        let mut declarator = match self.peek() {
+            Some(CTokenKind::Identifier) if mode != DeclaratorMode::Abstract => {
+                let identifier = self.parse_identifier()?;
+                Declarator {
+                    span: identifier.span,
+                    kind: DeclaratorKind::Identifier(Some(identifier)),
+                }
+            }
+            Some(CTokenKind::Punctuator(SymbolKind::OpenParenthesis))
+                if self.is_grouping_parenthesis(mode) =>
+            {
+                self.pos += 1;
+                let inner = self.parse_declarator(mode)?;
+                self.expect(CTokenKind::Punctuator(SymbolKind::CloseParenthesis))?;
+                inner
+            }
+            _ if mode != DeclaratorMode::Named => Declarator {
+                span: SourceSpan::new(self.src, start.start(), start.start()),
+                kind: DeclaratorKind::Identifier(None),
+            },
+            _ => return Err(self.error("expected an identifier or `(`")),
+        };
+        loop {
+            let kind = if self
+                .eat(CTokenKind::Punctuator(SymbolKind::OpenSquareBracket))
+                .is_some()
+            {
+                let length =
+                    match self.check(CTokenKind::Punctuator(SymbolKind::CloseSquareBracket)) {
+                        true => None,
+                        false => Some(Box::new(
+                            self.parse_expression_with_precedence(ASSIGNMENT_PRECEDENCE)?,
+                        )),
+                    };
+                self.expect(CTokenKind::Punctuator(SymbolKind::CloseSquareBracket))?;
+                DeclaratorKind::Array(Box::new(declarator), length)
+            } else if self
+                .eat(CTokenKind::Punctuator(SymbolKind::OpenParenthesis))
+                .is_some()
+            {
+                let parameters = self.parse_parameter_list()?;
+                DeclaratorKind::Function(Box::new(declarator), parameters)
+            } else {
+                return Ok(declarator);
+            };
+            declarator = Declarator {
+                kind,
+                span: start.to(&self.prev_span()),
+            };
+        }
+    }
+
+    /// Whether the `(` at the next token groups a nested declarator, rather than
+    /// starting the parameter list of an abstract function declarator.
+    fn is_grouping_parenthesis(&self, mode: DeclaratorMode) -> bool {
+        match self.peek_nth(1) {
+            _ if mode == DeclaratorMode::Named => true,
+            Some(CTokenKind::Identifier) => {
+                mode == DeclaratorMode::Either && self.typedef_name_at(1).is_none()
+            }
+            Some(CTokenKind::Punctuator(
+                SymbolKind::Asterisk | SymbolKind::OpenParenthesis | SymbolKind::OpenSquareBracket,
+            )) => true,
+            _ => false,
+        }
+    }
+
+    /// `<parameter-list> ::= [ "void" | <parameter> { "," <parameter> } [ "," "..." ] ] ")"`,
+    /// after the opening `(` has been consumed.
+    fn parse_parameter_list(&mut self) -> Result<ParameterList, ParseError> {
+        self.in_scope(Self::parse_parameters)
+    }
+
+    fn parse_parameters(&mut self) -> Result<ParameterList, ParseError> {
+        let start = self.prev_span();
+        let close = CTokenKind::Punctuator(SymbolKind::CloseParenthesis);
+        let mut parameters = Vec::new();
+        let mut is_variadic = false;
+        let is_prototype = !self.check(close);
+        let is_void =
+            self.check(CTokenKind::Keyword(KeywordKind::Void)) && self.peek_nth(1) == Some(close);
+        if is_void {
+            self.pos += 1;
+        } else if is_prototype {
+            loop {
+                if let Some(ellipsis) = self.eat(CTokenKind::Operator(CookedSymbolKind::Ellipsis)) {
+                    if parameters.is_empty() {
+                        return Err(ParseError::new(
+                            ellipsis,
+                            "expected a parameter before `...`",
+                        ));
+                    }
+                    is_variadic = true;
+                    break;
+                }
+                parameters.push(self.parse_parameter_declaration()?);
+                if self
+                    .eat(CTokenKind::Punctuator(SymbolKind::Comma))
+                    .is_none()
+                {
+                    break;
+                }
+            }
+        }
+        let end = self.expect(close)?;
+
+        Ok(ParameterList {
+            parameters,
+            is_variadic,
+            is_prototype,
+            span: start.to(&end),
+        })
+    }
+
+    /// `<parameter> ::= <declaration-specifiers> ( <declarator> | <abstract-declarator> )`
+    fn parse_parameter_declaration(&mut self) -> Result<ParameterDeclaration, ParseError> {
+        let specifiers = self.parse_declaration_specifiers()?;
+        if specifiers.storage_class.is_some() {
+            return Err(ParseError::new(
+                specifiers.span,
+                "storage class specified for a parameter",
+            ));
+        }
+        if specifiers.is_inline {
+            return Err(ParseError::new(
+                specifiers.span,
+                "`inline` specified for a parameter",
+            ));
+        }
+        let declarator = self.parse_declarator(DeclaratorMode::Either)?;
+        let (name, ty) = self.derive_type(&declarator, self.base_type(&specifiers)?)?;
+        let span = specifiers.span.to(&self.prev_span());
+        if let Some(name) = &name {
+            self.scopes.declare(&name.name, Binding::Ordinary);
+        }
+        // Parameters of array and function type are really pointers (C17 6.7.6.3p7-8).
+        let ty = match ty.ty {
+            Type::Array(element, _) => QualifiedType::new(Type::Pointer(element), ty.qualifiers),
+            Type::Function(function) => Type::pointer_to(Type::Function(function)).into(),
+            Type::Void => {
+                return Err(ParseError::new(
+                    span,
+                    "parameter has incomplete type `void`",
+                ))
+            }
+            _ => ty,
+        };
+
+        Ok(ParameterDeclaration {
+            specifiers,
+            declarator,
+            name,
+            ty,
+            span,
+        })
+    }
+
+    /// `<type-name> ::= <declaration-specifiers> <abstract-declarator>`
+    pub(crate) fn parse_type_name(&mut self) -> Result<TypeName, ParseError> {
+        let specifiers = self.parse_declaration_specifiers()?;
+        if specifiers.storage_class.is_some() {
+            return Err(ParseError::new(
+                specifiers.span,
+                "storage class specified for a type name",
+            ));
+        }
+        if specifiers.is_inline {
+            return Err(ParseError::new(
+                specifiers.span,
+                "`inline` specified for a type name",
+            ));
+        }
+        let declarator = self.parse_declarator(DeclaratorMode::Abstract)?;
+        let (_, ty) = self.derive_type(&declarator, self.base_type(&specifiers)?)?;
+
+        Ok(TypeName {
+            span: specifiers.span.to(&self.prev_span()),
+            specifiers,
+            declarator,
+            ty,
+        })
+    }
+}
+
+impl Parser<'_> {
+    /// Derive the name and type declared by `declarator` for the given base type,
+    /// working from the outside of the declarator inwards.
+    pub(crate) fn derive_type(
+        &self,
+        declarator: &Declarator,
+        base: QualifiedType,
+    ) -> Result<(Option<Identifier>, QualifiedType), ParseError> {
+        let mut ty = base;
+        let mut current = declarator;
+        loop {
+            let error = |context: &str| Err(ParseError::new(current.span, context));
+            match &current.kind {
+                DeclaratorKind::Identifier(name) => return Ok((name.clone(), ty)),
+                DeclaratorKind::Pointer(qualifiers, inner) => {
+                    ty = QualifiedType::new(Type::pointer_to(ty), *qualifiers);
+                    current = inner;
+                }
+                DeclaratorKind::Array(inner, length) => {
+                    match &ty.ty {
+                        Type::Function(_) => return error("declared as an array of functions"),
+                        Type::Void => return error("declared as an array of `void`"),
+                        _ if self.tags.layout(&ty.ty).is_none() => {
+                            return error("array has incomplete element type")
+                        }
+                        _ => {}
+                    }
+                    let length = match length {
+                        Some(length) => Some(self.array_length(length)?),
+                        None => None,
+                    };
+                    ty = Type::Array(Box::new(ty), length).into();
+                    current = inner;
+                }
+                DeclaratorKind::Function(inner, parameters) => {
+                    match &ty.ty {
+                        Type::Function(_) => return error("function cannot return a function"),
+                        Type::Array(..) => return error("function cannot return an array"),
+                        _ => {}
+                    }
+                    ty = Type::Function(FunctionType {
+                        return_type: Box::new(ty),
+                        parameters: parameters.parameters.iter().map(|p| p.ty.clone()).collect(),
+                        is_variadic: parameters.is_variadic,
+                        is_prototype: parameters.is_prototype,
+                    })
+                    .into();
+                    current = inner;
+                }
+            }
+        }
+    }
+
+    fn array_length(&self, length: &Expression) -> Result<u64, ParseError> {
+        let value = self
+            .integer_constant(length)
+            .map_err(|err| err.into_error("array size is not an integer constant expression"))?;
+        match value {
+            value if value < 0 => Err(ParseError::new(length.span, "array has negative size")),
+            value => {
+                u64::try_from(value).map_err(|_| ParseError::new(length.span, "array is too large"))
+            }
+        }
+    }
+
+    /// Evaluate an integer constant expression, as needed for array sizes,
+    /// bit-field widths and enumerators. Only enumeration constants can be
+    /// named in one, so they are all the evaluator needs to look up.
+    pub(crate) fn integer_constant(&self, expression: &Expression) -> Result<i128, EvalError> {
+        let lookup = |identifier: &Identifier| {
+            self.scopes
+                .enum_constant(&identifier.name)
+                .map(Entity::EnumConstant)
+        };
+        Evaluator::new(&self.tags, &lookup).integer(expression)
+    }
+}
+
+#[cfg(test)]
+mod declaration_tests {
+    use expect_test::{expect, Expect};
+    use tokengen::span::Span;
+    use wacc_lexer::Lexer;
+
+    use crate::{
+        ast::{BlockItem, ExternalDeclaration},
+        Parser,
+    };
+
+    /// List every declared name with its derived type spelled out as a C declaration.
+    fn check_declarations(src: &str, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let program = parser.parse_program();
+        let mut output = Vec::new();
+        for item in &program.items {
+            match item {
+                ExternalDeclaration::Function(function) => {
+                    output.push(format!("{} {{", function.ty.declare(&function.name.name)));
+                    for item in &function.body.items {
+                        match item {
+                            BlockItem::Declaration(declaration) => {
+                                for declarator in &declaration.declarators {
+                                    output.push(format!(
+                                        "    {}",
+                                        declarator.ty.declare(&declarator.name.name)
+                                    ));
+                                }
+                            }
+                            BlockItem::Statement(statement) => {
+                                output.push(format!("    `{}`", statement.span.span()));
+                            }
+                            BlockItem::StaticAssert(static_assert) => {
+                                output.push(format!("    `{}`", static_assert.span.span()));
+                            }
+                        }
+                    }
+                    output.push("}".to_string());
+                }
+                ExternalDeclaration::Declaration(declaration) => {
+                    let storage_class = match declaration.specifiers.storage_class {
+                        Some(storage_class) => format!("{storage_class} "),
+                        None => String::new(),
+                    };
+                    for declarator in &declaration.declarators {
+                        let declaration = declarator.ty.declare(&declarator.name.name);
+                        output.push(format!("{storage_class}{declaration}"));
+                    }
+                }
+                ExternalDeclaration::StaticAssert(static_assert) => {
+                    output.push(format!("`{}`", static_assert.span.span()));
+                }
+                ExternalDeclaration::Error(_) => {}
+            }
+        }
+        output.extend(parser.errors().iter().map(|err| err.context().to_string()));
+        expect.assert_eq(&output.join("\n"));
+    }
+
+    #[test]
+    fn test_derived_types() {
+        check_declarations(
+            r#"
+            int (*fp[4])(long, char *);
+            const char *const names[], **argv;
+            unsigned long long int x, *y, z[2][3 * 4];
+            long unsigned lu;
+            signed s;
+            void (*signal(int, void (*)(int)))(int);
+            int printf(const char *restrict, ...);
+            int old();
+            "#,
+            expect![[r#"
+                int (*fp[4])(long, char *)
+                const char *const names[]
+                const char **argv
+                unsigned long long x
+                unsigned long long *y
+                unsigned long long z[2][12]
+                unsigned long lu
+                int s
+                void (*signal(int, void (*)(int)))(int)
+                int printf(const char *restrict, ...)
+                int old()"#]],
+        );
+    }
+
+    #[test]
+    fn test_parameter_adjustment() {
+        check_declarations(
+            r#"
+            int main(int argc, char *argv[]) {
+                int sum = 0, *p = &sum;
+                double (*m)[3];
+                return sum;
+            }
+            void apply(int f(int), int values[4], const int m[][3]);
+            int vla(int n, int values[n]);
+            "#,
+            expect![[r#"
+                int main(int, char **) {
+                    int sum
+                    int *p
+                    double (*m)[3]
+                    `return sum;`
+                }
+                void apply(int (*)(int), int *, const int (*)[3])
+                array size is not an integer constant expression"#]],
+        );
+    }
+
+    #[test]
+    fn test_declaration_errors() {
+        check_declarations(
+            r#"
+            long double a;
+            signed unsigned b;
+            int float c;
+            int f(void)[3];
+            int g(void)(void);
+            int h[-1];
+            void v[2];
+            int (*fns[2])(void)[1];
+            int m[][2], n[2][];
+            int k(void, int);
+            int main(int) { return 0; }
+            const d;
+            "#,
+            expect![[r#"
+                `long double` is not supported
+                cannot combine `signed` and `unsigned` more than once
+                invalid combination of type specifiers
+                function cannot return an array
+                function cannot return a function
+                array has negative size
+                declared as an array of `void`
+                function cannot return an array
+                array has incomplete element type
+                parameter has incomplete type `void`
+                parameter name omitted in function definition
+                expected a type specifier"#]],
+        );
+    }
+
+    #[test]
+    fn test_typedef_names() {
+        check_declarations(
+            r#"
+            typedef int T;
+            typedef T *TP, TA[3];
+            typedef int (*handler)(T);
+            const TA constants;
+            handler handlers[2];
+            void g(T T, double (T));
+            int main(void) {
+                T * x;
+                TP y = (T *)x;
+                return sizeof(T) + sizeof(handler);
+            }
+            int shadow(int T) {
+                T * x;
+                return T;
+            }
+            T restored;
+            int local(void) {
+                T T;
+                T * 2;
+            }
+            "#,
+            expect![[r#"
+                typedef int T
+                typedef int *TP
+                typedef int TA[3]
+                typedef int (*handler)(int)
+                const int constants[3]
+                int (*handlers[2])(int)
+                void g(int, double)
+                int main(void) {
+                    int *x
+                    int *y
+                    `return sizeof(T) + sizeof(handler);`
+                }
+                int shadow(int) {
+                    `T * x;`
+                    `return T;`
+                }
+                int restored
+                int local(void) {
+                    int T
+                    `T * 2;`
+                }"#]],
+        );
+    }
+
+    #[test]
+    fn test_storage_classes() {
+        check_declarations(
+            r#"
+            static int counter;
+            extern const long limit, table[];
+            static int helper(void);
+            extern static int both;
+            int f(static int x);
+            int main(void) { return sizeof(extern int); }
+            static inline int twice(int x) { return 2 * x; }
+            inline inline int square(int x);
+            inline int y;
+            typedef inline int fn(void);
+            int g(inline int x);
+            "#,
+            expect![[r#"
+                static int counter
+                extern const long limit
+                extern const long table[]
+                static int helper(void)
+                static int both
+                int main(void) {
+                    `return sizeof(extern int);`
+                }
+                int twice(int) {
+                    `return 2 * x;`
+                }
+                int square(int)
+                expected at most one storage class, found `static`
+                storage class specified for a parameter
+                storage class specified for a type name
+                `inline` specified for something other than a function
+                `inline` specified for something other than a function
+                `inline` specified for a parameter"#]],
+        );
+    }
+
+    #[test]
+    fn test_static_assertions() {
+        check_declarations(
+            r#"
+            enum { SIZE = 4 };
+            _Static_assert(sizeof(int) == SIZE, "int" " is 32 bits");
+            _Static_assert(SIZE > 4, "too small");
+            _Static_assert(SIZE, 1);
+            int x;
+            _Static_assert(x, "x");
+            int main(void) { _Static_assert(-1 > (unsigned)0, "usual arithmetic conversions"); return 0; }
+            "#,
+            expect![[r#"
+                `_Static_assert(sizeof(int) == SIZE, "int" " is 32 bits");`
+                `_Static_assert(SIZE > 4, "too small");`
+                int x
+                `_Static_assert(x, "x");`
+                int main(void) {
+                    `_Static_assert(-1 > (unsigned)0, "usual arithmetic conversions");`
+                    `return 0;`
+                }
+                static assertion failed: too small
+                expected a string literal, found constant
+                static assertion expression is not an integer constant expression"#]],
+        );
+    }
+
+    #[test]
+    fn test_typedef_errors() {
+        check_declarations(
+            r#"
+            typedef int T = 1;
+            typedef typedef int U;
+            typedef int f(void) { return 0; }
+            int g(typedef int x);
+            T long l;
+            int main(void) { return (typedef int)1; }
+            "#,
+            expect![[r#"
+                typedef int U
+                int main(void) {
+                    `return (typedef int)1;`
+                }
+                a typedef cannot be initialized
+                expected at most one storage class, found `typedef`
+                function definition declared `typedef`
+                storage class specified for a parameter
+                invalid combination of type specifiers
+                storage class specified for a type name"#]],
+        );
+    }
+
+    #[test]
+    fn test_tagged_types() {
+        check_declarations(
+            r#"
+            struct list;
+            struct list { int value; struct list *next; } *head;
+            typedef struct { int x, y; } point;
+            enum color { RED, GREEN = 5, BLUE, };
+            int colors[BLUE], sizes[sizeof(struct list)];
+            const enum color c;
+            union number { int i; double d; } numbers[2];
+            int main(void) {
+                struct list { char c; } shadow;
+                int inner[sizeof(struct list)];
+                enum { RED = 10 } e;
+                int reds[RED];
+            }
+            int outer[sizeof(struct list) + RED];
+            "#,
+            expect![[r#"
+                struct list *head
+                typedef struct (anonymous) point
+                int colors[6]
+                int sizes[16]
+                const enum color c
+                union number numbers[2]
+                int main(void) {
+                    struct list shadow
+                    int inner[1]
+                    enum (anonymous) e
+                    int reds[10]
+                }
+                int outer[16]"#]],
+        );
+    }
+
+    #[test]
+    fn test_tag_errors() {
+        check_declarations(
+            r#"
+            struct s { int x; int x; };
+            struct t { int a[]; int b; };
+            union v { int n; char d[]; };
+            struct w { void v; };
+            struct f { int f(void); };
+            struct b { float x : 3; };
+            struct c { int x : 33; };
+            struct d { int x : 0; };
+            struct e { struct e self; };
+            struct g { int x; };
+            struct g { int y; };
+            union g *gp;
+            enum big { HUGE = 2147483648 };
+            struct {};
+            struct h { int; };
+            struct fwd arr[2];
+            "#,
+            expect![[r#"
+                duplicate member `x`
+                flexible array member must end a struct with other members
+                flexible array member must end a struct with other members
+                member has incomplete type
+                member declared as a function
+                bit-field has non-integer type
+                bit-field is wider than its type
+                named bit-field has zero width
+                member has incomplete type
+                redefinition of `struct g`
+                `g` was declared as a struct
+                enumerator value is out of range of `int`
+                struct has no members
+                declaration does not declare a member
+                array has incomplete element type"#]],
+        );
+    }
+}
@@ -0,0 +1,870 @@
+//! Dumps of the AST for debugging and for external tools.
+//!
+//! Every node is first turned into a generic [`Node`] of named fields, which each
+//! format then renders: an indented tree with spans, S-expressions without them,
+//! or JSON. The C format instead prints normalized source (see [`crate::print`]).
+
+use std::fmt::Write;
+
+use tokengen::span::{SourceSpan, Span};
+
+use crate::{
+    ast::{
+        BlockItem, Declaration, DeclarationSpecifiers, Declarator, DeclaratorKind, Designator,
+        DesignatorKind, EnumSpecifier, Expression, ExpressionKind, ExternalDeclaration, ForInit,
+        FunctionDefinition, Identifier, InitDeclarator, Initializer, InitializerEntry,
+        InitializerEntryKind, InitializerKind, Literal, MemberDeclaration, ParameterDeclaration,
+        ParameterList, Program, RecordSpecifier, Statement, StatementKind, StaticAssert, TypeName,
+        TypeSpecifier,
+    },
+    print::print_program,
+    types::{QualifiedType, Qualifiers},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// An indented tree with the span of every node.
+    Tree,
+    /// S-expressions, without spans.
+    Sexpr,
+    /// JSON, with spans as `[start, end]` byte offsets.
+    Json,
+    /// Normalized C source that parses back to the same AST.
+    C,
+}
+
+/// Render `program` in the given format.
+pub fn dump_program(program: &Program, format: DumpFormat) -> String {
+    let node = program.dump();
+    let mut output = String::new();
+    match format {
+        DumpFormat::Tree => write_tree(&node, 0, &mut output),
+        DumpFormat::Sexpr => {
+            write_sexpr(&Value::Node(node), 0, &mut output);
+            output.push('\n');
+        }
+        DumpFormat::Json => {
+            write_json(&Value::Node(node), 0, &mut output);
+            output.push('\n');
+        }
+        DumpFormat::C => output = print_program(program),
+    }
+    output
+}
+
+/// A node of the AST as a kind, a span and its fields in order.
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+    kind: &'static str,
+    span: Option<SourceSpan>,
+    fields: Vec<(&'static str, Value)>,
+}
+impl Node {
+    fn new(kind: &'static str, span: SourceSpan) -> Self {
+        Self {
+            kind,
+            span: Some(span),
+            fields: Vec::new(),
+        }
+    }
+    /// A node that doesn't come from any one place in the source.
+    fn unspanned(kind: &'static str) -> Self {
+        Self {
+            kind,
+            span: None,
+            fields: Vec::new(),
+        }
+    }
+    fn field(mut self, name: &'static str, value: impl Into<Value>) -> Self {
+        self.fields.push((name, value.into()));
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Node(Node),
+    List(Vec<Value>),
+    Str(String),
+    /// A name from a fixed set, like an operator or a type specifier, rendered unquoted.
+    Symbol(String),
+    Int(i128),
+    Bool(bool),
+    None,
+}
+impl Value {
+    fn symbol(name: impl ToString) -> Self {
+        Self::Symbol(name.to_string())
+    }
+    fn list<T: Dump>(items: &[T]) -> Self {
+        Self::List(items.iter().map(|item| Value::Node(item.dump())).collect())
+    }
+    fn is_scalar(&self) -> bool {
+        match self {
+            Self::Node(_) => false,
+            Self::List(items) => items.iter().all(Value::is_scalar),
+            _ => true,
+        }
+    }
+}
+impl From<Node> for Value {
+    fn from(node: Node) -> Self {
+        Self::Node(node)
+    }
+}
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::None, Into::into)
+    }
+}
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Self::Int(value.into())
+    }
+}
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Self::Int(value.into())
+    }
+}
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Self::Int(value.into())
+    }
+}
+impl From<&Identifier> for Value {
+    fn from(identifier: &Identifier) -> Self {
+        Self::Str(identifier.name.clone())
+    }
+}
+impl From<&QualifiedType> for Value {
+    fn from(ty: &QualifiedType) -> Self {
+        Self::Str(ty.to_string())
+    }
+}
+impl From<Qualifiers> for Value {
+    fn from(qualifiers: Qualifiers) -> Self {
+        match qualifiers.is_empty() {
+            true => Self::None,
+            false => Self::List(
+                qualifiers
+                    .to_string()
+                    .split(' ')
+                    .map(Value::symbol)
+                    .collect(),
+            ),
+        }
+    }
+}
+impl<T: Dump> From<&T> for Value {
+    fn from(node: &T) -> Self {
+        Self::Node(node.dump())
+    }
+}
+
+trait Dump {
+    fn dump(&self) -> Node;
+}
+impl<T: Dump> Dump for Box<T> {
+    fn dump(&self) -> Node {
+        (**self).dump()
+    }
+}
+
+impl Dump for Program {
+    fn dump(&self) -> Node {
+        Node::new("Program", self.span).field("items", Value::list(&self.items))
+    }
+}
+
+impl Dump for ExternalDeclaration {
+    fn dump(&self) -> Node {
+        match self {
+            Self::Function(function) => function.dump(),
+            Self::Declaration(declaration) => declaration.dump(),
+            Self::StaticAssert(static_assert) => static_assert.dump(),
+            Self::Error(span) => Node::new("Error", *span),
+        }
+    }
+}
+
+impl Dump for FunctionDefinition {
+    fn dump(&self) -> Node {
+        Node::new("FunctionDefinition", self.span)
+            .field("name", &self.name)
+            .field("ty", &self.ty)
+            .field("specifiers", &self.specifiers)
+            .field("declarator", &self.declarator)
+            .field("body", Value::list(&self.body.items))
+    }
+}
+
+impl Dump for BlockItem {
+    fn dump(&self) -> Node {
+        match self {
+            Self::Declaration(declaration) => declaration.dump(),
+            Self::StaticAssert(static_assert) => static_assert.dump(),
+            Self::Statement(statement) => statement.dump(),
+        }
+    }
+}
+
+impl Dump for StaticAssert {
+    fn dump(&self) -> Node {
+        Node::new("StaticAssert", self.span)
+            .field("condition", &self.condition)
+            .field(
+                "message",
+                Value::Str(self.message.escape_ascii().to_string()),
+            )
+    }
+}
+
+impl Dump for Declaration {
+    fn dump(&self) -> Node {
+        Node::new("Declaration", self.span)
+            .field("specifiers", &self.specifiers)
+            .field("declarators", Value::list(&self.declarators))
+    }
+}
+
+impl Dump for DeclarationSpecifiers {
+    fn dump(&self) -> Node {
+        let types = self
+            .type_specifiers
+            .iter()
+            .map(|specifier| match specifier {
+                TypeSpecifier::Void => Value::symbol("void"),
+                TypeSpecifier::Char => Value::symbol("char"),
+                TypeSpecifier::Short => Value::symbol("short"),
+                TypeSpecifier::Int => Value::symbol("int"),
+                TypeSpecifier::Long => Value::symbol("long"),
+                TypeSpecifier::Float => Value::symbol("float"),
+                TypeSpecifier::Double => Value::symbol("double"),
+                TypeSpecifier::Signed => Value::symbol("signed"),
+                TypeSpecifier::Unsigned => Value::symbol("unsigned"),
+                TypeSpecifier::TypedefName(name) => Node::new("TypedefName", name.span)
+                    .field("name", name)
+                    .into(),
+                TypeSpecifier::Record(record) => record.into(),
+                TypeSpecifier::Enum(enumeration) => enumeration.into(),
+            })
+            .collect();
+        let storage_class = self.storage_class.map(Value::symbol);
+        Node::new("Specifiers", self.span)
+            .field("storage", storage_class)
+            .field("inline", self.is_inline.then_some(true))
+            .field("qualifiers", self.qualifiers)
+            .field("types", Value::List(types))
+    }
+}
+
+impl Dump for RecordSpecifier {
+    fn dump(&self) -> Node {
+        let members = self.members.as_deref().map(Value::list);
+        Node::new("RecordSpecifier", self.span)
+            .field("kind", Value::symbol(self.kind))
+            .field("name", self.name.as_ref())
+            .field("members", members)
+    }
+}
+
+impl Dump for MemberDeclaration {
+    fn dump(&self) -> Node {
+        let declarators = self
+            .declarators
+            .iter()
+            .map(|declarator| {
+                Node::new("MemberDeclarator", declarator.span)
+                    .field("ty", &declarator.ty)
+                    .field("declarator", &declarator.declarator)
+                    .field("width", declarator.bit_width.as_ref())
+                    .into()
+            })
+            .collect();
+        Node::new("MemberDeclaration", self.span)
+            .field("specifiers", &self.specifiers)
+            .field("declarators", Value::List(declarators))
+    }
+}
+
+impl Dump for EnumSpecifier {
+    fn dump(&self) -> Node {
+        let enumerators = self.enumerators.as_ref().map(|enumerators| {
+            let enumerators = enumerators.iter().map(|enumerator| {
+                Node::new("Enumerator", enumerator.span)
+                    .field("name", &enumerator.name)
+                    .field("constant", enumerator.constant)
+                    .field("value", enumerator.value.as_ref())
+                    .into()
+            });
+            Value::List(enumerators.collect())
+        });
+        Node::new("EnumSpecifier", self.span)
+            .field("name", self.name.as_ref())
+            .field("enumerators", enumerators)
+    }
+}
+
+impl Dump for InitDeclarator {
+    fn dump(&self) -> Node {
+        Node::new("InitDeclarator", self.span)
+            .field("name", &self.name)
+            .field("ty", &self.ty)
+            .field("declarator", &self.declarator)
+            .field("initializer", self.initializer.as_ref())
+            .field("entries", Value::list(&self.entries))
+    }
+}
+
+impl Dump for Initializer {
+    fn dump(&self) -> Node {
+        match &self.kind {
+            InitializerKind::Expression(expression) => expression.dump(),
+            InitializerKind::List(items) => {
+                let items = items.iter().map(|item| {
+                    Node::new("InitializerItem", item.span)
+                        .field("designators", Value::list(&item.designators))
+                        .field("initializer", &item.initializer)
+                        .into()
+                });
+                Node::new("InitializerList", self.span).field("items", Value::List(items.collect()))
+            }
+        }
+    }
+}
+
+impl Dump for Designator {
+    fn dump(&self) -> Node {
+        match &self.kind {
+            DesignatorKind::Index(expression, index) => Node::new("IndexDesignator", self.span)
+                .field("index", *index)
+                .field("expression", expression),
+            DesignatorKind::Member(name) => {
+                Node::new("MemberDesignator", self.span).field("name", name)
+            }
+        }
+    }
+}
+
+impl Dump for InitializerEntry {
+    fn dump(&self) -> Node {
+        let entry = |kind| Node::unspanned(kind).field("offset", self.offset);
+        match &self.kind {
+            InitializerEntryKind::Zero(size) => entry("Zero").field("size", *size),
+            InitializerEntryKind::Value(ty, expression) => {
+                entry("Value").field("ty", ty).field("value", expression)
+            }
+            InitializerEntryKind::BitField(ty, bit_offset, width, expression) => entry("BitField")
+                .field("ty", ty)
+                .field("bit_offset", *bit_offset)
+                .field("width", *width)
+                .field("value", expression),
+            InitializerEntryKind::Bytes(bytes) => {
+                entry("Bytes").field("bytes", Value::Str(bytes.escape_ascii().to_string()))
+            }
+        }
+    }
+}
+
+impl Dump for Declarator {
+    fn dump(&self) -> Node {
+        match &self.kind {
+            DeclaratorKind::Identifier(name) => {
+                Node::new("NameDeclarator", self.span).field("name", name.as_ref())
+            }
+            DeclaratorKind::Pointer(qualifiers, declarator) => {
+                Node::new("PointerDeclarator", self.span)
+                    .field("qualifiers", *qualifiers)
+                    .field("declarator", declarator)
+            }
+            DeclaratorKind::Array(declarator, length) => Node::new("ArrayDeclarator", self.span)
+                .field("declarator", declarator)
+                .field("length", length.as_ref()),
+            DeclaratorKind::Function(declarator, parameters) => {
+                Node::new("FunctionDeclarator", self.span)
+                    .field("declarator", declarator)
+                    .field("parameters", parameters)
+            }
+        }
+    }
+}
+
+impl Dump for ParameterList {
+    fn dump(&self) -> Node {
+        Node::new("ParameterList", self.span)
+            .field("prototype", self.is_prototype)
+            .field("variadic", self.is_variadic)
+            .field("parameters", Value::list(&self.parameters))
+    }
+}
+
+impl Dump for ParameterDeclaration {
+    fn dump(&self) -> Node {
+        Node::new("Parameter", self.span)
+            .field("name", self.name.as_ref())
+            .field("ty", &self.ty)
+            .field("specifiers", &self.specifiers)
+            .field("declarator", &self.declarator)
+    }
+}
+
+impl Dump for TypeName {
+    fn dump(&self) -> Node {
+        Node::new("TypeName", self.span)
+            .field("ty", &self.ty)
+            .field("specifiers", &self.specifiers)
+            .field("declarator", &self.declarator)
+    }
+}
+
+impl Dump for Statement {
+    fn dump(&self) -> Node {
+        let node = |kind| Node::new(kind, self.span);
+        match &self.kind {
+            StatementKind::Return(value) => node("Return").field("value", value.as_ref()),
+            StatementKind::Expression(expression) => {
+                node("ExpressionStatement").field("expression", expression)
+            }
+            StatementKind::Null => node("Null"),
+            StatementKind::Compound(block) => {
+                node("Compound").field("items", Value::list(&block.items))
+            }
+            StatementKind::If(condition, then, els) => node("If")
+                .field("condition", condition)
+                .field("then", then)
+                .field("else", els.as_ref()),
+            StatementKind::While(condition, body) => node("While")
+                .field("condition", condition)
+                .field("body", body),
+            StatementKind::DoWhile(body, condition) => node("DoWhile")
+                .field("body", body)
+                .field("condition", condition),
+            StatementKind::For(init, condition, step, body) => {
+                let init: Value = match &**init {
+                    ForInit::Declaration(declaration) => declaration.into(),
+                    ForInit::Expression(expression) => expression.as_ref().into(),
+                };
+                node("For")
+                    .field("init", init)
+                    .field("condition", condition.as_ref())
+                    .field("step", step.as_ref())
+                    .field("body", body)
+            }
+            StatementKind::Break => node("Break"),
+            StatementKind::Continue => node("Continue"),
+            StatementKind::Switch(condition, body) => node("Switch")
+                .field("condition", condition)
+                .field("body", body),
+            StatementKind::Case(expression, value, body) => node("Case")
+                .field("value", Value::Int(*value))
+                .field("expression", expression)
+                .field("body", body),
+            StatementKind::Default(body) => node("Default").field("body", body),
+            StatementKind::Goto(label) => node("Goto").field("label", label),
+            StatementKind::Labeled(label, body) => {
+                node("Labeled").field("label", label).field("body", body)
+            }
+            StatementKind::Error => node("Error"),
+        }
+    }
+}
+
+impl Dump for Expression {
+    fn dump(&self) -> Node {
+        let node = |kind| Node::new(kind, self.span);
+        let node = match &self.kind {
+            ExpressionKind::Constant(Literal::Integer(value, ty)) => node("Constant")
+                .field("value", Value::Int(*value))
+                .field("ty", Value::Str(ty.to_string())),
+            ExpressionKind::Constant(Literal::Floating(value, ty)) => node("Constant")
+                .field("value", Value::symbol(format!("{value:?}")))
+                .field("ty", Value::Str(ty.to_string())),
+            ExpressionKind::Identifier(name) => node("Identifier").field("name", name),
+            ExpressionKind::Unary(operator, operand) => node("Unary")
+                .field("operator", Value::symbol(operator))
+                .field("operand", operand),
+            ExpressionKind::Postfix(operator, operand) => node("Postfix")
+                .field("operator", Value::symbol(operator))
+                .field("operand", operand),
+            ExpressionKind::Binary(operator, lhs, rhs) => node("Binary")
+                .field("operator", Value::symbol(operator))
+                .field("lhs", lhs)
+                .field("rhs", rhs),
+            ExpressionKind::Assignment(operator, lhs, rhs) => node("Assignment")
+                .field("operator", Value::symbol(operator))
+                .field("lhs", lhs)
+                .field("rhs", rhs),
+            ExpressionKind::Conditional(condition, then, els) => node("Conditional")
+                .field("condition", condition)
+                .field("then", then)
+                .field("else", els),
+            ExpressionKind::StringLiteral(bytes) => {
+                node("StringLiteral").field("bytes", Value::Str(bytes.escape_ascii().to_string()))
+            }
+            ExpressionKind::Cast(type_name, operand) => node("Cast")
+                .field("type", type_name)
+                .field("operand", operand),
+            ExpressionKind::CompoundLiteral(literal) => node("CompoundLiteral")
+                .field("ty", &literal.ty)
+                .field("type", &literal.type_name)
+                .field("initializer", &literal.initializer)
+                .field("entries", Value::list(&literal.entries)),
+            ExpressionKind::SizeofExpression(operand) => {
+                node("SizeofExpression").field("operand", operand)
+            }
+            ExpressionKind::SizeofType(type_name) => node("SizeofType").field("type", type_name),
+            ExpressionKind::Offsetof(type_name, designators, offset) => node("Offsetof")
+                .field("type", type_name)
+                .field("designators", Value::list(designators))
+                .field("offset", *offset),
+            ExpressionKind::Call(callee, arguments) => node("Call")
+                .field("callee", callee)
+                .field("arguments", Value::list(arguments)),
+            ExpressionKind::Subscript(base, index) => {
+                node("Subscript").field("base", base).field("index", index)
+            }
+            ExpressionKind::Member(base, member) => {
+                node("Member").field("base", base).field("member", member)
+            }
+            ExpressionKind::PointerMember(base, member) => node("PointerMember")
+                .field("base", base)
+                .field("member", member),
+            ExpressionKind::ImplicitConversion(conversion, operand) => node("ImplicitConversion")
+                .field("conversion", Value::symbol(format!("{conversion:?}")))
+                .field("operand", operand),
+            ExpressionKind::Error => node("Error"),
+        };
+        // Only type checked expressions have a type to show.
+        match &self.ty {
+            Some(ty) => node.field("ty", ty),
+            None => node,
+        }
+    }
+}
+
+/// `Kind@start..end field=value ...`, then a line for each field holding nodes,
+/// with the nodes indented below it. Empty fields are left out.
+fn write_tree(node: &Node, depth: usize, output: &mut String) {
+    output.push_str(node.kind);
+    if let Some(span) = node.span {
+        write!(output, "@{}..{}", span.start(), span.end()).unwrap();
+    }
+    for (name, value) in &node.fields {
+        let is_empty = match value {
+            Value::None => true,
+            Value::List(items) => items.is_empty(),
+            _ => false,
+        };
+        if value.is_scalar() && !is_empty {
+            output.push(' ');
+            output.push_str(name);
+            output.push('=');
+            write_sexpr_flat(value, output);
+        }
+    }
+    output.push('\n');
+    let indent = "  ".repeat(depth + 1);
+    for (name, value) in &node.fields {
+        match value {
+            Value::Node(child) => {
+                write!(output, "{indent}{name}: ").unwrap();
+                write_tree(child, depth + 1, output);
+            }
+            Value::List(items) if !value.is_scalar() => {
+                writeln!(output, "{indent}{name}:").unwrap();
+                for item in items {
+                    output.push_str(&"  ".repeat(depth + 2));
+                    match item {
+                        Value::Node(child) => write_tree(child, depth + 2, output),
+                        scalar => {
+                            write_sexpr_flat(scalar, output);
+                            output.push('\n');
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// S-expressions wider than this are broken over several lines.
+const SEXPR_WIDTH: usize = 80;
+
+/// `(Kind field ...)`, with fields in order and lists as `(item ...)`. An expression
+/// that fits in what is left of the line is kept on it; others put each field on a
+/// line of its own.
+fn write_sexpr(value: &Value, depth: usize, output: &mut String) {
+    let mut flat = String::new();
+    write_sexpr_flat(value, &mut flat);
+    let (head, children) = match value {
+        Value::Node(node) => (
+            Some(node.kind),
+            node.fields
+                .iter()
+                .map(|(_, value)| value)
+                .collect::<Vec<_>>(),
+        ),
+        Value::List(items) => (None, items.iter().collect()),
+        _ => (None, Vec::new()),
+    };
+    if depth * 2 + flat.len() <= SEXPR_WIDTH || children.is_empty() {
+        output.push_str(&flat);
+        return;
+    }
+    output.push('(');
+    let mut first = true;
+    if let Some(head) = head {
+        output.push_str(head);
+        first = false;
+    }
+    for child in children {
+        if !first {
+            output.push('\n');
+            output.push_str(&"  ".repeat(depth + 1));
+        }
+        first = false;
+        write_sexpr(child, depth + 1, output);
+    }
+    output.push(')');
+}
+
+fn write_sexpr_flat(value: &Value, output: &mut String) {
+    match value {
+        Value::Node(node) => {
+            output.push('(');
+            output.push_str(node.kind);
+            for (_, value) in &node.fields {
+                output.push(' ');
+                write_sexpr_flat(value, output);
+            }
+            output.push(')');
+        }
+        Value::List(items) => {
+            output.push('(');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    output.push(' ');
+                }
+                write_sexpr_flat(item, output);
+            }
+            output.push(')');
+        }
+        Value::Str(string) => write!(output, "{string:?}").unwrap(),
+        Value::Symbol(symbol) => output.push_str(symbol),
+        Value::Int(int) => write!(output, "{int}").unwrap(),
+        Value::Bool(bool) => write!(output, "{bool}").unwrap(),
+        Value::None => output.push_str("nil"),
+    }
+}
+
+/// Pretty printed JSON, with each node an object of its `kind`, `span` and fields.
+fn write_json(value: &Value, depth: usize, output: &mut String) {
+    let indent = "  ".repeat(depth + 1);
+    match value {
+        Value::Node(node) => {
+            write!(output, "{{\n{indent}\"kind\": \"{}\"", node.kind).unwrap();
+            if let Some(span) = node.span {
+                write!(
+                    output,
+                    ",\n{indent}\"span\": [{}, {}]",
+                    span.start(),
+                    span.end()
+                )
+                .unwrap();
+            }
+            for (name, value) in &node.fields {
+                write!(output, ",\n{indent}\"{name}\": ").unwrap();
+                write_json(value, depth + 1, output);
+            }
+            write!(output, "\n{}}}", "  ".repeat(depth)).unwrap();
+        }
+        Value::List(items) if items.is_empty() => output.push_str("[]"),
+        Value::List(items) => {
+            output.push('[');
+            for (i, item) in items.iter().enumerate() {
+                output.push_str(if i == 0 { "\n" } else { ",\n" });
+                output.push_str(&indent);
+                write_json(item, depth + 1, output);
+            }
+            write!(output, "\n{}]", "  ".repeat(depth)).unwrap();
+        }
+        Value::Str(string) | Value::Symbol(string) => write_json_string(string, output),
+        Value::Int(int) => write!(output, "{int}").unwrap(),
+        Value::Bool(bool) => write!(output, "{bool}").unwrap(),
+        Value::None => output.push_str("null"),
+    }
+}
+
+fn write_json_string(string: &str, output: &mut String) {
+    output.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
+            '\r' => output.push_str("\\r"),
+            c if c.is_control() => write!(output, "\\u{:04x}", c as u32).unwrap(),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+#[cfg(test)]
+mod dump_tests {
+    use expect_test::{expect, Expect};
+    use wacc_lexer::Lexer;
+
+    use super::{dump_program, DumpFormat};
+    use crate::Parser;
+
+    fn check_dump(src: &str, format: DumpFormat, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let program = parser.parse_program();
+        assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+        expect.assert_eq(&dump_program(&program, format));
+    }
+
+    const SAMPLE: &str = "int *next(int *p, unsigned n) { return p + -n; }";
+
+    #[test]
+    fn test_tree() {
+        check_dump(
+            SAMPLE,
+            DumpFormat::Tree,
+            expect![[r#"
+            Program@0..48
+              items:
+                FunctionDefinition@0..48 name="next" ty="int *(int *, unsigned int)"
+                  specifiers: Specifiers@0..3 types=(int)
+                  declarator: PointerDeclarator@4..29
+                    declarator: FunctionDeclarator@5..29
+                      declarator: NameDeclarator@5..9 name="next"
+                      parameters: ParameterList@9..29 prototype=true variadic=false
+                        parameters:
+                          Parameter@10..16 name="p" ty="int *"
+                            specifiers: Specifiers@10..13 types=(int)
+                            declarator: PointerDeclarator@14..16
+                              declarator: NameDeclarator@15..16 name="p"
+                          Parameter@18..28 name="n" ty="unsigned int"
+                            specifiers: Specifiers@18..26 types=(unsigned)
+                            declarator: NameDeclarator@27..28 name="n"
+                  body:
+                    Return@32..46
+                      value: Binary@39..45 operator=+
+                        lhs: Identifier@39..40 name="p"
+                        rhs: Unary@43..45 operator=-
+                          operand: Identifier@44..45 name="n"
+        "#]],
+        );
+    }
+
+    #[test]
+    fn test_sexpr() {
+        check_dump(
+            SAMPLE,
+            DumpFormat::Sexpr,
+            expect![[r#"
+            (Program
+              ((FunctionDefinition
+                  "next"
+                  "int *(int *, unsigned int)"
+                  (Specifiers nil nil nil (int))
+                  (PointerDeclarator
+                    nil
+                    (FunctionDeclarator
+                      (NameDeclarator "next")
+                      (ParameterList
+                        true
+                        false
+                        ((Parameter
+                            "p"
+                            "int *"
+                            (Specifiers nil nil nil (int))
+                            (PointerDeclarator nil (NameDeclarator "p")))
+                          (Parameter
+                            "n"
+                            "unsigned int"
+                            (Specifiers nil nil nil (unsigned))
+                            (NameDeclarator "n"))))))
+                  ((Return (Binary + (Identifier "p") (Unary - (Identifier "n"))))))))
+        "#]],
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        check_dump(
+            "const char *s = \"a\\n\";",
+            DumpFormat::Json,
+            expect![[r#"
+            {
+              "kind": "Program",
+              "span": [0, 22],
+              "items": [
+                {
+                  "kind": "Declaration",
+                  "span": [0, 22],
+                  "specifiers": {
+                    "kind": "Specifiers",
+                    "span": [0, 10],
+                    "storage": null,
+                    "inline": null,
+                    "qualifiers": [
+                      "const"
+                    ],
+                    "types": [
+                      "char"
+                    ]
+                  },
+                  "declarators": [
+                    {
+                      "kind": "InitDeclarator",
+                      "span": [11, 21],
+                      "name": "s",
+                      "ty": "const char *",
+                      "declarator": {
+                        "kind": "PointerDeclarator",
+                        "span": [11, 13],
+                        "qualifiers": null,
+                        "declarator": {
+                          "kind": "NameDeclarator",
+                          "span": [12, 13],
+                          "name": "s"
+                        }
+                      },
+                      "initializer": {
+                        "kind": "StringLiteral",
+                        "span": [16, 21],
+                        "bytes": "a\\n"
+                      },
+                      "entries": [
+                        {
+                          "kind": "Value",
+                          "offset": 0,
+                          "ty": "const char *",
+                          "value": {
+                            "kind": "StringLiteral",
+                            "span": [16, 21],
+                            "bytes": "a\\n"
+                          }
+                        }
+                      ]
+                    }
+                  ]
+                }
+              ]
+            }
+        "#]],
+        );
+    }
+}
@@ -0,0 +1,756 @@
+//! Expression parsing by precedence climbing.
+//!
+//! Binary, assignment and conditional operators are driven by [`INFIX_OPERATORS`];
+//! casts, unary and postfix operators bind tighter than all of them and are
+//! parsed by recursive descent.
+
+use tokengen::span::{SourceSpan, Span};
+use wacc_lexer::c_token::{
+    c_keyword::KeywordKind,
+    c_symbol::{CookedSymbolKind, SymbolKind},
+    CTokenKind,
+};
+
+use crate::{
+    ast::{
+        AssignmentOperator, BinaryOperator, CompoundLiteral, Designator, DesignatorKind,
+        Expression, ExpressionKind, PostfixOperator, TypeName, UnaryOperator,
+    },
+    literal::parse_constant,
+    types::Type,
+    ParseError, Parser,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Associativity {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Infix {
+    Binary(BinaryOperator),
+    Assignment(AssignmentOperator),
+    Conditional,
+}
+
+/// The precedence of the comma operator, the loosest binding operator in C.
+pub(crate) const COMMA_PRECEDENCE: u8 = 1;
+/// The precedence of assignment, used wherever C's grammar asks for an
+/// assignment-expression (call arguments, initializers) so commas aren't consumed.
+pub(crate) const ASSIGNMENT_PRECEDENCE: u8 = 2;
+/// The precedence of `?:`, used wherever C's grammar asks for a constant-expression.
+pub(crate) const CONDITIONAL_PRECEDENCE: u8 = 3;
+
+/// Every infix operator with its C17 precedence (higher binds tighter) and associativity.
+///
+/// `?:` is listed by its `?`; the middle operand is parsed as a full expression up to the `:`.
+#[rustfmt::skip]
+const INFIX_OPERATORS: &[(CTokenKind, Infix, u8, Associativity)] = {
+    use Associativity::*;
+    use BinaryOperator::*;
+    use CTokenKind::{Operator as Op, Punctuator as P};
+    use CookedSymbolKind as C;
+    use SymbolKind as S;
+    const fn assign(op: BinaryOperator) -> Infix {
+        Infix::Assignment(AssignmentOperator::Compound(op))
+    }
+    &[
+        (P(S::Comma),                Infix::Binary(Comma),                        1,  Left),
+        (P(S::Equals),               Infix::Assignment(AssignmentOperator::Assign), 2, Right),
+        (Op(C::AddAssign),           assign(Add),                                 2,  Right),
+        (Op(C::SubtractAssign),      assign(Subtract),                            2,  Right),
+        (Op(C::MultiplyAssign),      assign(Multiply),                            2,  Right),
+        (Op(C::DivideAssign),        assign(Divide),                              2,  Right),
+        (Op(C::RemainderAssign),     assign(Remainder),                           2,  Right),
+        (Op(C::ShiftLeftAssign),     assign(ShiftLeft),                           2,  Right),
+        (Op(C::ShiftRightAssign),    assign(ShiftRight),                          2,  Right),
+        (Op(C::AndAssign),           assign(BitwiseAnd),                          2,  Right),
+        (Op(C::XorAssign),           assign(BitwiseXor),                          2,  Right),
+        (Op(C::OrAssign),            assign(BitwiseOr),                           2,  Right),
+        (P(S::QuestionMark),         Infix::Conditional,                          3,  Right),
+        (Op(C::LogicalOr),           Infix::Binary(LogicalOr),                    4,  Left),
+        (Op(C::LogicalAnd),          Infix::Binary(LogicalAnd),                   5,  Left),
+        (P(S::VerticalBar),          Infix::Binary(BitwiseOr),                    6,  Left),
+        (P(S::Caret),                Infix::Binary(BitwiseXor),                   7,  Left),
+        (P(S::Ampersand),            Infix::Binary(BitwiseAnd),                   8,  Left),
+        (Op(C::DoubleEquals),        Infix::Binary(Equal),                        9,  Left),
+        (Op(C::NotEquals),           Infix::Binary(NotEqual),                     9,  Left),
+        (P(S::LessThan),             Infix::Binary(LessThan),                     10, Left),
+        (P(S::GreaterThan),          Infix::Binary(GreaterThan),                  10, Left),
+        (Op(C::LessThanOrEqual),     Infix::Binary(LessOrEqual),                  10, Left),
+        (Op(C::GreaterThanOrEqual),  Infix::Binary(GreaterOrEqual),               10, Left),
+        (Op(C::ShiftLeft),           Infix::Binary(ShiftLeft),                    11, Left),
+        (Op(C::ShiftRight),          Infix::Binary(ShiftRight),                   11, Left),
+        (P(S::Plus),                 Infix::Binary(Add),                          12, Left),
+        (P(S::Minus),                Infix::Binary(Subtract),                     12, Left),
+        (P(S::Asterisk),             Infix::Binary(Multiply),                     13, Left),
+        (P(S::ForwardSlash),         Infix::Binary(Divide),                       13, Left),
+        (P(S::Percent),              Infix::Binary(Remainder),                    13, Left),
+    ]
+};
+
+fn infix_operator(kind: CTokenKind) -> Option<(Infix, u8, Associativity)> {
+    INFIX_OPERATORS
+        .iter()
+        .find(|(token, ..)| *token == kind)
+        .map(|(_, infix, precedence, associativity)| (*infix, *precedence, *associativity))
+}
+
+fn prefix_operator(kind: CTokenKind) -> Option<UnaryOperator> {
+    Some(match kind {
+        CTokenKind::Punctuator(SymbolKind::Minus) => UnaryOperator::Negate,
+        CTokenKind::Punctuator(SymbolKind::Plus) => UnaryOperator::Plus,
+        CTokenKind::Punctuator(SymbolKind::Tilde) => UnaryOperator::Complement,
+        CTokenKind::Punctuator(SymbolKind::ExclamationMark) => UnaryOperator::Not,
+        CTokenKind::Punctuator(SymbolKind::Ampersand) => UnaryOperator::AddressOf,
+        CTokenKind::Punctuator(SymbolKind::Asterisk) => UnaryOperator::Dereference,
+        CTokenKind::Operator(CookedSymbolKind::Increment) => UnaryOperator::PreIncrement,
+        CTokenKind::Operator(CookedSymbolKind::Decrement) => UnaryOperator::PreDecrement,
+        _ => return None,
+    })
+}
+
+/// The bytes a string literal or character constant stands for, with its quotes removed and escape
+/// sequences replaced, or what is wrong with an escape sequence.
+pub(crate) fn unescape(literal: &str) -> Result<Vec<u8>, String> {
+    let inner = &literal.as_bytes()[1..literal.len() - 1];
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut rest = inner.iter().copied().peekable();
+    while let Some(byte) = rest.next() {
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        let escape = rest
+            .next()
+            .expect("the lexer never ends a literal on a backslash");
+        bytes.push(match escape {
+            b'n' => b'\n',
+            b't' => b'\t',
+            b'r' => b'\r',
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b'f' => 0x0c,
+            b'v' => 0x0b,
+            b'\\' | b'\'' | b'"' | b'?' => escape,
+            b'0'..=b'7' => {
+                let mut value = u32::from(escape - b'0');
+                for _ in 0..2 {
+                    match rest.peek() {
+                        Some(digit @ b'0'..=b'7') => value = value * 8 + u32::from(digit - b'0'),
+                        _ => break,
+                    }
+                    rest.next();
+                }
+                u8::try_from(value).map_err(|_| "octal escape sequence out of range")?
+            }
+            b'x' => {
+                let mut value = None::<u32>;
+                while let Some(digit) = rest.peek().and_then(|digit| (*digit as char).to_digit(16))
+                {
+                    value = Some(value.unwrap_or(0).saturating_mul(16).saturating_add(digit));
+                    rest.next();
+                }
+                let value = value.ok_or("`\\x` used with no following hex digits")?;
+                u8::try_from(value).map_err(|_| "hex escape sequence out of range")?
+            }
+            other => {
+                return Err(format!(
+                    "unknown escape sequence `\\{}`",
+                    String::from_utf8_lossy(&[other])
+                ))
+            }
+        });
+    }
+    Ok(bytes)
+}
+
+impl Parser<'_> {
+    /// `<exp> ::= <assignment-exp> { "," <assignment-exp> }`
+    pub(crate) fn parse_expression(&mut self) -> Result<Expression, ParseError> {
+        self.parse_expression_with_precedence(COMMA_PRECEDENCE)
+    }
+
+    /// Parse an expression made of infix operators binding at least as tightly as `min_precedence`.
+    pub(crate) fn parse_expression_with_precedence(
+        &mut self,
+        min_precedence: u8,
+    ) -> Result<Expression, ParseError> {
+        let mut left = self.parse_cast_expression()?;
+        while let Some((infix, precedence, associativity)) = self.peek().and_then(infix_operator) {
+            if precedence < min_precedence {
+                break;
+            }
+            self.pos += 1;
+            let next_precedence = match associativity {
+                Associativity::Left => precedence + 1,
+                Associativity::Right => precedence,
+            };
+            let kind = match infix {
+                Infix::Binary(op) => {
+                    let right = self.parse_expression_with_precedence(next_precedence)?;
+                    ExpressionKind::Binary(op, Box::new(left), Box::new(right))
+                }
+                Infix::Assignment(op) => {
+                    let right = self.parse_expression_with_precedence(next_precedence)?;
+                    ExpressionKind::Assignment(op, Box::new(left), Box::new(right))
+                }
+                Infix::Conditional => {
+                    let then = self.parse_expression()?;
+                    self.expect(CTokenKind::Punctuator(SymbolKind::Colon))?;
+                    let els = self.parse_expression_with_precedence(next_precedence)?;
+                    ExpressionKind::Conditional(Box::new(left), Box::new(then), Box::new(els))
+                }
+            };
+            let span = match &kind {
+                ExpressionKind::Binary(_, l, r) | ExpressionKind::Assignment(_, l, r) => {
+                    l.span.to(&r.span)
+                }
+                ExpressionKind::Conditional(c, _, e) => c.span.to(&e.span),
+                _ => unreachable!(
+                    "infix operators only build binary, assignment or conditional expressions"
+                ),
+            };
+            left = Expression {
+                kind,
+                span,
+                ty: None,
+            };
+        }
+
+        Ok(left)
+    }
+
+    /// `<cast-exp> ::= "(" <type-name> ")" <cast-exp> | <unary-exp>`
+    fn parse_cast_expression(&mut self) -> Result<Expression, ParseError> {
+        if self.check(CTokenKind::Punctuator(SymbolKind::OpenParenthesis))
+            && self.is_declaration_specifier_at(1)
+        {
+            let start = self.expect(CTokenKind::Punctuator(SymbolKind::OpenParenthesis))?;
+            let type_name = self.parse_type_name()?;
+            self.expect(CTokenKind::Punctuator(SymbolKind::CloseParenthesis))?;
+            if self.check(CTokenKind::Punctuator(SymbolKind::OpenCurlyBrace)) {
+                let literal = self.parse_compound_literal(start, type_name)?;
+                return self.parse_postfix_operators(literal);
+            }
+            let operand = self.parse_cast_expression()?;
+            let span = start.to(&operand.span);
+            return Ok(Expression {
+                kind: ExpressionKind::Cast(Box::new(type_name), Box::new(operand)),
+                span,
+                ty: None,
+            });
+        }
+        self.parse_unary_expression()
+    }
+
+    /// `<compound-literal> ::= "(" <type-name> ")" "{" <initializer-list> "}"`, after the `)`.
+    fn parse_compound_literal(
+        &mut self,
+        start: SourceSpan,
+        type_name: TypeName,
+    ) -> Result<Expression, ParseError> {
+        if type_name.ty.ty.is_function() {
+            return Err(ParseError::new(
+                type_name.span,
+                "compound literal has function type",
+            ));
+        }
+        let initializer = self.parse_initializer()?;
+        let (ty, entries) =
+            self.resolve_initializer(&type_name.ty, &initializer, self.scopes.is_file_scope())?;
+        let span = start.to(&initializer.span);
+        Ok(Expression {
+            kind: ExpressionKind::CompoundLiteral(Box::new(CompoundLiteral {
+                type_name,
+                ty,
+                initializer,
+                entries,
+            })),
+            span,
+            ty: None,
+        })
+    }
+
+    /// `<unary-exp> ::= <unop> <cast-exp> | ("++" | "--") <unary-exp>
+    ///                | "sizeof" <unary-exp> | "sizeof" "(" <type-name> ")" | <postfix-exp>`
+    fn parse_unary_expression(&mut self) -> Result<Expression, ParseError> {
+        let start = self.peek_span();
+        if let Some(op) = self.peek().and_then(prefix_operator) {
+            self.pos += 1;
+            let operand = match op {
+                UnaryOperator::PreIncrement | UnaryOperator::PreDecrement => {
+                    self.parse_unary_expression()?
+                }
+                _ => self.parse_cast_expression()?,
+            };
+            let span = start.to(&operand.span);
+            return Ok(Expression {
+                kind: ExpressionKind::Unary(op, Box::new(operand)),
+                span,
+                ty: None,
+            });
+        }
+        if self.eat(CTokenKind::Keyword(KeywordKind::Sizeof)).is_some() {
+            if self.check(CTokenKind::Punctuator(SymbolKind::OpenParenthesis))
+                && self.is_declaration_specifier_at(1)
+            {
+                let open = self.expect(CTokenKind::Punctuator(SymbolKind::OpenParenthesis))?;
+                let type_name = self.parse_type_name()?;
+                let end = self.expect(CTokenKind::Punctuator(SymbolKind::CloseParenthesis))?;
+                if self.check(CTokenKind::Punctuator(SymbolKind::OpenCurlyBrace)) {
+                    let literal = self.parse_compound_literal(open, type_name)?;
+                    let operand = self.parse_postfix_operators(literal)?;
+                    let span = start.to(&operand.span);
+                    return Ok(Expression {
+                        kind: ExpressionKind::SizeofExpression(Box::new(operand)),
+                        span,
+                        ty: None,
+                    });
+                }
+                return Ok(Expression {
+                    kind: ExpressionKind::SizeofType(Box::new(type_name)),
+                    span: start.to(&end),
+                    ty: None,
+                });
+            }
+            let operand = self.parse_unary_expression()?;
+            let span = start.to(&operand.span);
+            return Ok(Expression {
+                kind: ExpressionKind::SizeofExpression(Box::new(operand)),
+                span,
+                ty: None,
+            });
+        }
+        self.parse_postfix_expression()
+    }
+
+    /// `"__builtin_offsetof" "(" <type-name> "," <identifier> { <designator> } ")"`
+    ///
+    /// The offset comes from the layout of the type, so it is worked out here.
+    fn parse_offsetof(&mut self) -> Result<Expression, ParseError> {
+        let start = self.expect(CTokenKind::Identifier)?;
+        self.expect(CTokenKind::Punctuator(SymbolKind::OpenParenthesis))?;
+        let type_name = self.parse_type_name()?;
+        self.expect(CTokenKind::Punctuator(SymbolKind::Comma))?;
+        let member = self.parse_identifier()?;
+        let mut designators = vec![Designator {
+            span: member.span,
+            kind: DesignatorKind::Member(member),
+        }];
+        while let Some(designator) = self.parse_designator("offsetof")? {
+            designators.push(designator);
+        }
+        let end = self.expect(CTokenKind::Punctuator(SymbolKind::CloseParenthesis))?;
+        let offset = self.offset_of(&type_name.ty.ty, &designators)?;
+        Ok(Expression {
+            kind: ExpressionKind::Offsetof(Box::new(type_name), designators, offset),
+            span: start.to(&end),
+            ty: None,
+        })
+    }
+
+    /// The byte offset of the subobject `designators` lead to in an object of type `ty`.
+    fn offset_of<'a>(
+        &'a self,
+        mut ty: &'a Type,
+        designators: &[Designator],
+    ) -> Result<u64, ParseError> {
+        let mut offset: u64 = 0;
+        for designator in designators {
+            let error = |context: &str| Err(ParseError::new(designator.span, context));
+            let (inner, step) = match (ty, &designator.kind) {
+                (_, _) if self.tags.layout(ty).is_none() && !ty.is_array() => {
+                    return error(&format!("offsetof of incomplete type `{ty}`"));
+                }
+                (Type::Struct(tag) | Type::Union(tag), DesignatorKind::Member(name)) => {
+                    match self.tags.member(tag, &name.name) {
+                        Some((_, member)) if member.bit_width.is_some() => {
+                            let context =
+                                format!("cannot compute offset of bit-field `{}`", name.name);
+                            return error(&context);
+                        }
+                        Some((field, member)) => (&member.ty.ty, Some(field.offset)),
+                        None => {
+                            return error(&format!("no member named `{}` in `{ty}`", name.name));
+                        }
+                    }
+                }
+                (Type::Array(element, _), DesignatorKind::Index(_, index)) => {
+                    let Some(layout) = self.tags.layout(&element.ty) else {
+                        return error(&format!("offsetof of incomplete type `{}`", element.ty));
+                    };
+                    (&element.ty, layout.size.checked_mul(*index))
+                }
+                (_, DesignatorKind::Member(_)) => {
+                    return error(&format!("offsetof requires a struct or union, not `{ty}`"));
+                }
+                (_, DesignatorKind::Index(..)) => {
+                    return error(&format!("offsetof requires an array, not `{ty}`"));
+                }
+            };
+            offset = match step.and_then(|step| offset.checked_add(step)) {
+                Some(offset) => offset,
+                None => return error("offsetof is too large"),
+            };
+            ty = inner;
+        }
+        Ok(offset)
+    }
+
+    /// `<postfix-exp> ::= <primary-exp> { "[" <exp> "]" | "(" [ <argument-list> ] ")"
+    ///                  | "." <identifier> | "->" <identifier> | "++" | "--" }`
+    fn parse_postfix_expression(&mut self) -> Result<Expression, ParseError> {
+        let primary = self.parse_primary_expression()?;
+        self.parse_postfix_operators(primary)
+    }
+
+    /// Apply any postfix operators that follow an already parsed operand.
+    fn parse_postfix_operators(
+        &mut self,
+        mut expression: Expression,
+    ) -> Result<Expression, ParseError> {
+        loop {
+            let start = expression.span;
+            let (kind, end) = match self.peek() {
+                Some(CTokenKind::Punctuator(SymbolKind::OpenSquareBracket)) => {
+                    self.pos += 1;
+                    let index = self.parse_expression()?;
+                    let end =
+                        self.expect(CTokenKind::Punctuator(SymbolKind::CloseSquareBracket))?;
+                    (
+                        ExpressionKind::Subscript(Box::new(expression), Box::new(index)),
+                        end,
+                    )
+                }
+                Some(CTokenKind::Punctuator(SymbolKind::OpenParenthesis)) => {
+                    self.pos += 1;
+                    let mut arguments = Vec::new();
+                    if !self.check(CTokenKind::Punctuator(SymbolKind::CloseParenthesis)) {
+                        loop {
+                            arguments.push(
+                                self.parse_expression_with_precedence(ASSIGNMENT_PRECEDENCE)?,
+                            );
+                            if self
+                                .eat(CTokenKind::Punctuator(SymbolKind::Comma))
+                                .is_none()
+                            {
+                                break;
+                            }
+                        }
+                    }
+                    let end = self.expect(CTokenKind::Punctuator(SymbolKind::CloseParenthesis))?;
+                    (ExpressionKind::Call(Box::new(expression), arguments), end)
+                }
+                Some(CTokenKind::Punctuator(SymbolKind::Dot)) => {
+                    self.pos += 1;
+                    let member = self.parse_identifier()?;
+                    let end = member.span;
+                    (ExpressionKind::Member(Box::new(expression), member), end)
+                }
+                Some(CTokenKind::Operator(CookedSymbolKind::Arrow)) => {
+                    self.pos += 1;
+                    let member = self.parse_identifier()?;
+                    let end = member.span;
+                    (
+                        ExpressionKind::PointerMember(Box::new(expression), member),
+                        end,
+                    )
+                }
+                Some(CTokenKind::Operator(CookedSymbolKind::Increment)) => {
+                    let end = self.peek_span();
+                    self.pos += 1;
+                    (
+                        ExpressionKind::Postfix(PostfixOperator::Increment, Box::new(expression)),
+                        end,
+                    )
+                }
+                Some(CTokenKind::Operator(CookedSymbolKind::Decrement)) => {
+                    let end = self.peek_span();
+                    self.pos += 1;
+                    (
+                        ExpressionKind::Postfix(PostfixOperator::Decrement, Box::new(expression)),
+                        end,
+                    )
+                }
+                _ => return Ok(expression),
+            };
+            expression = Expression {
+                kind,
+                span: start.to(&end),
+                ty: None,
+            };
+        }
+    }
+
+    /// `<primary-exp> ::= <int> | <identifier> | <string-literal> { <string-literal> } | "(" <exp> ")"`
+    fn parse_primary_expression(&mut self) -> Result<Expression, ParseError> {
+        match self.peek() {
+            Some(CTokenKind::Constant) => {
+                let span = self.expect(CTokenKind::Constant)?;
+                let literal =
+                    parse_constant(span.span()).map_err(|err| ParseError::new(span, &err))?;
+                Ok(Expression {
+                    kind: ExpressionKind::Constant(literal),
+                    span,
+                    ty: None,
+                })
+            }
+            Some(CTokenKind::StringLiteral) => {
+                // Adjacent string literals are concatenated into one.
+                let start = self.peek_span();
+                let mut bytes = Vec::new();
+                while let Some(span) = self.eat(CTokenKind::StringLiteral) {
+                    let literal =
+                        unescape(span.span()).map_err(|err| ParseError::new(span, &err))?;
+                    bytes.extend(literal);
+                }
+                Ok(Expression {
+                    kind: ExpressionKind::StringLiteral(bytes),
+                    span: start.to(&self.prev_span()),
+                    ty: None,
+                })
+            }
+            Some(CTokenKind::Identifier) if self.peek_span().span() == "__builtin_offsetof" => {
+                self.parse_offsetof()
+            }
+            Some(CTokenKind::Identifier) => {
+                let identifier = self.parse_identifier()?;
+                let span = identifier.span;
+                Ok(Expression {
+                    kind: ExpressionKind::Identifier(identifier),
+                    span,
+                    ty: None,
+                })
+            }
+            Some(CTokenKind::Punctuator(SymbolKind::OpenParenthesis)) => {
+                let start = self.expect(CTokenKind::Punctuator(SymbolKind::OpenParenthesis))?;
+                let expression = self.parse_expression()?;
+                let end = self.expect(CTokenKind::Punctuator(SymbolKind::CloseParenthesis))?;
+                // Parentheses only group, but the span covers them so diagnostics do too.
+                Ok(Expression {
+                    span: start.to(&end),
+                    ..expression
+                })
+            }
+            // An operand is missing right before something that can end an expression,
+            // so stand in an error node and let the caller carry on from here.
+            None
+            | Some(CTokenKind::Punctuator(
+                SymbolKind::Semicolon
+                | SymbolKind::CloseParenthesis
+                | SymbolKind::CloseSquareBracket
+                | SymbolKind::CloseCurlyBrace
+                | SymbolKind::Comma
+                | SymbolKind::Colon,
+            )) => {
+                let err = self.error("expected an expression");
+                self.report(err);
+                let start = self.peek_span().start();
+                Ok(Expression {
+                    kind: ExpressionKind::Error,
+                    span: SourceSpan::new(self.src, start, start),
+                    ty: None,
+                })
+            }
+            _ => Err(self.error("expected an expression")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod expression_tests {
+    use expect_test::{expect, Expect};
+    use wacc_lexer::Lexer;
+
+    use crate::{
+        ast::{AssignmentOperator, Expression, ExpressionKind, PostfixOperator},
+        Parser,
+    };
+
+    /// Render an expression fully parenthesized, so precedence and associativity are visible.
+    fn render(expression: &Expression) -> String {
+        match &expression.kind {
+            ExpressionKind::Constant(value) => value.to_string(),
+            ExpressionKind::Identifier(identifier) => identifier.name.clone(),
+            ExpressionKind::Unary(op, operand) => format!("({op:?} {})", render(operand)),
+            ExpressionKind::Postfix(PostfixOperator::Increment, operand) => {
+                format!("({}++)", render(operand))
+            }
+            ExpressionKind::Postfix(PostfixOperator::Decrement, operand) => {
+                format!("({}--)", render(operand))
+            }
+            ExpressionKind::Binary(op, left, right) => {
+                format!("({} {op:?} {})", render(left), render(right))
+            }
+            ExpressionKind::Assignment(AssignmentOperator::Assign, left, right) => {
+                format!("({} = {})", render(left), render(right))
+            }
+            ExpressionKind::Assignment(AssignmentOperator::Compound(op), left, right) => {
+                format!("({} {op:?}= {})", render(left), render(right))
+            }
+            ExpressionKind::Conditional(condition, then, els) => {
+                format!(
+                    "({} ? {} : {})",
+                    render(condition),
+                    render(then),
+                    render(els)
+                )
+            }
+            ExpressionKind::Cast(type_name, operand) => {
+                format!("(({}) {})", type_name.ty, render(operand))
+            }
+            ExpressionKind::StringLiteral(bytes) => format!("\"{}\"", bytes.escape_ascii()),
+            ExpressionKind::CompoundLiteral(literal) => {
+                format!("(({}) {{...}})", literal.ty)
+            }
+            ExpressionKind::SizeofExpression(operand) => format!("(sizeof {})", render(operand)),
+            ExpressionKind::SizeofType(type_name) => format!("(sizeof({}))", type_name.ty),
+            ExpressionKind::Offsetof(type_name, _, offset) => {
+                format!("(offsetof({}) = {offset})", type_name.ty)
+            }
+            ExpressionKind::Call(function, arguments) => format!(
+                "{}({})",
+                render(function),
+                arguments.iter().map(render).collect::<Vec<_>>().join(", ")
+            ),
+            ExpressionKind::Subscript(base, index) => {
+                format!("{}[{}]", render(base), render(index))
+            }
+            ExpressionKind::Member(base, member) => format!("{}.{}", render(base), member.name),
+            ExpressionKind::PointerMember(base, member) => {
+                format!("{}->{}", render(base), member.name)
+            }
+            ExpressionKind::ImplicitConversion(_, operand) => render(operand),
+            ExpressionKind::Error => "<error>".to_string(),
+        }
+    }
+
+    fn check_expression(src: &str, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let output = match parser.parse_expression() {
+            Ok(expression) if parser.errors().is_empty() => render(&expression),
+            Ok(expression) => format!("{} {}", parser.errors()[0].context(), render(&expression)),
+            Err(err) => err.context().to_string(),
+        };
+        expect.assert_eq(&output);
+    }
+
+    #[test]
+    fn test_binary_precedence() {
+        check_expression(
+            "1 + 2 * 3 - 4 / 5 % 6",
+            expect!["((1 Add (2 Multiply 3)) Subtract ((4 Divide 5) Remainder 6))"],
+        );
+        check_expression(
+            "a << 1 < b & c == d ^ e | f && g || h",
+            expect!["(((((((a ShiftLeft 1) LessThan b) BitwiseAnd (c Equal d)) BitwiseXor e) BitwiseOr f) LogicalAnd g) LogicalOr h)"],
+        );
+    }
+
+    #[test]
+    fn test_right_associative() {
+        check_expression(
+            "a = b += c <<= 2",
+            expect!["(a = (b Add= (c ShiftLeft= 2)))"],
+        );
+        check_expression("a ? b : c ? d : e", expect!["(a ? b : (c ? d : e))"]);
+        check_expression(
+            "x = a || b ? c , d : e",
+            expect!["(x = ((a LogicalOr b) ? (c Comma d) : e))"],
+        );
+        check_expression("a = 1, b = 2", expect!["((a = 1) Comma (b = 2))"]);
+    }
+
+    #[test]
+    fn test_unary_and_postfix() {
+        check_expression(
+            "-~!x++ + --*p",
+            expect!["((Negate (Complement (Not (x++)))) Add (PreDecrement (Dereference p)))"],
+        );
+        check_expression(
+            "&a[1].b->c(2, 3 = 4)--",
+            expect!["(AddressOf (a[1].b->c(2, (3 = 4))--))"],
+        );
+        check_expression(
+            "(unsigned long *)p + sizeof x * sizeof(int)",
+            expect!["(((unsigned long *) p) Add ((sizeof x) Multiply (sizeof(int))))"],
+        );
+        check_expression("(a + b) * c", expect!["((a Add b) Multiply c)"]);
+        check_expression(
+            "(unsigned long (*)[3])p + sizeof(int (*)(void)) + (const char *const *)q",
+            expect!["((((unsigned long (*)[3]) p) Add (sizeof(int (*)(void)))) Add ((const char *const *) q))"],
+        );
+    }
+
+    #[test]
+    fn test_literals() {
+        check_expression(
+            r#"f("a\tb" "\101\x42\0c\"\\", "")"#,
+            expect![[r#"f("a\tbAB\x00c\"\\", "")"#]],
+        );
+        check_expression(
+            "(int[]){1, 2, 3}[1] + sizeof (struct { int x; }){.x = 1}.x",
+            expect!["(((int [3]) {...})[1] Add (sizeof ((struct (anonymous)) {...}).x))"],
+        );
+        check_expression(r#""\q""#, expect![[r#"unknown escape sequence `\q`"#]]);
+        check_expression(r#""\400""#, expect!["octal escape sequence out of range"]);
+        check_expression(
+            r#""\x""#,
+            expect![[r#"`\x` used with no following hex digits"#]],
+        );
+    }
+
+    #[test]
+    fn test_constants() {
+        check_expression(
+            "0x1fUL + 017 * 1.5e3f - '\\''",
+            expect!["((31ul Add (15 Multiply 1500.0f)) Subtract 39)"],
+        );
+        check_expression("09", expect!["invalid digit `9` in octal constant"]);
+        check_expression("0x", expect!["invalid suffix `x` on integer constant"]);
+        check_expression("12lul", expect!["invalid suffix `lul` on integer constant"]);
+        check_expression("1lL", expect!["invalid suffix `lL` on integer constant"]);
+        check_expression(
+            "18446744073709551616",
+            expect!["integer constant is too large"],
+        );
+        check_expression(
+            "9223372036854775808",
+            expect!["integer constant is too large for any signed type"],
+        );
+        check_expression("1.2.3", expect!["too many decimal points in number"]);
+        check_expression("1e+", expect!["exponent has no digits"]);
+        check_expression("1.5q", expect!["invalid suffix `q` on floating constant"]);
+        check_expression("1.5L", expect!["`long double` is not supported"]);
+        check_expression(
+            "1e309",
+            expect!["floating constant is too large for `double`"],
+        );
+        check_expression(
+            "1e39f",
+            expect!["floating constant is too large for `float`"],
+        );
+        check_expression(
+            "0x1.8",
+            expect!["hexadecimal floating constant requires an exponent"],
+        );
+        check_expression("''", expect!["empty character constant"]);
+        check_expression(
+            "'ab'",
+            expect!["multi-character character constants are not supported"],
+        );
+    }
+
+    #[test]
+    fn test_expression_errors() {
+        check_expression(
+            "1 +",
+            expect!["expected an expression, found end of input (1 Add <error>)"],
+        );
+        check_expression("a ? b", expect!["expected `:`, found end of input"]);
+        check_expression("f(1, 2", expect!["expected `)`, found end of input"]);
+    }
+}
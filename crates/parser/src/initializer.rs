@@ -0,0 +1,849 @@
+//! Initializers, and how they map onto the subobjects of the object they initialize.
+//!
+//! A braced initializer list walks the subobjects of its object in order, each
+//! item initializing the next one. A designator moves the walk to the subobject
+//! it names, and the walk carries on from there. When an item for an aggregate
+//! subobject isn't braced itself, its braces were elided: the item initializes
+//! the aggregate's first scalar, and the items after it the rest, for as long as
+//! they last. A subobject initialized twice keeps the last initializer, and
+//! everything left uninitialized is zero.
+//!
+//! The result is a list of [`InitializerEntry`]s covering the whole object.
+
+use std::{collections::HashMap, ops::Range};
+
+use tokengen::span::SourceSpan;
+use wacc_lexer::c_token::{c_symbol::SymbolKind, CTokenKind};
+
+use crate::{
+    ast::{
+        BinaryOperator, Designator, DesignatorKind, Expression, ExpressionKind, Initializer,
+        InitializerEntry, InitializerEntryKind, InitializerKind, InitializerListItem,
+        UnaryOperator,
+    },
+    expression::{ASSIGNMENT_PRECEDENCE, CONDITIONAL_PRECEDENCE},
+    types::{QualifiedType, TagBody, TagTable, Type},
+    ParseError, Parser,
+};
+
+fn is_character(ty: &Type) -> bool {
+    matches!(ty, Type::Char | Type::SignedChar | Type::UnsignedChar)
+}
+
+fn string_literal(expression: &Expression) -> Option<&[u8]> {
+    match &expression.kind {
+        ExpressionKind::StringLiteral(bytes) => Some(bytes),
+        _ => None,
+    }
+}
+
+/// Whether an expression could be a constant expression, as the initializers of
+/// objects with static storage must be. Only operators that can never appear in
+/// one are ruled out; telling address constants from other values needs types.
+fn may_be_constant(expression: &Expression) -> bool {
+    match &expression.kind {
+        ExpressionKind::Assignment(..)
+        | ExpressionKind::Postfix(..)
+        | ExpressionKind::Unary(UnaryOperator::PreIncrement | UnaryOperator::PreDecrement, _)
+        | ExpressionKind::Binary(BinaryOperator::Comma, ..)
+        | ExpressionKind::Call(..) => false,
+        ExpressionKind::Unary(_, operand)
+        | ExpressionKind::Cast(_, operand)
+        | ExpressionKind::ImplicitConversion(_, operand)
+        | ExpressionKind::Member(operand, _)
+        | ExpressionKind::PointerMember(operand, _) => may_be_constant(operand),
+        ExpressionKind::Binary(_, left, right) | ExpressionKind::Subscript(left, right) => {
+            may_be_constant(left) && may_be_constant(right)
+        }
+        ExpressionKind::Conditional(condition, then, els) => {
+            may_be_constant(condition) && may_be_constant(then) && may_be_constant(els)
+        }
+        // `sizeof` doesn't evaluate its operand, and a compound literal at file
+        // scope checks its own initializer.
+        ExpressionKind::Constant(_)
+        | ExpressionKind::Identifier(_)
+        | ExpressionKind::StringLiteral(_)
+        | ExpressionKind::SizeofExpression(_)
+        | ExpressionKind::SizeofType(_)
+        | ExpressionKind::Offsetof(..)
+        | ExpressionKind::CompoundLiteral(_)
+        | ExpressionKind::Error => true,
+    }
+}
+
+impl Parser<'_> {
+    /// `<initializer> ::= <assignment-exp> | "{" [ <initializer-list-item> { "," <initializer-list-item> } [ "," ] ] "}"`
+    pub(crate) fn parse_initializer(&mut self) -> Result<Initializer, ParseError> {
+        let Some(start) = self.eat(CTokenKind::Punctuator(SymbolKind::OpenCurlyBrace)) else {
+            let expression = self.parse_expression_with_precedence(ASSIGNMENT_PRECEDENCE)?;
+            return Ok(Initializer {
+                span: expression.span,
+                kind: InitializerKind::Expression(expression),
+            });
+        };
+        let mut items = Vec::new();
+        while !self.check(CTokenKind::Punctuator(SymbolKind::CloseCurlyBrace)) {
+            items.push(self.parse_initializer_list_item()?);
+            if self
+                .eat(CTokenKind::Punctuator(SymbolKind::Comma))
+                .is_none()
+            {
+                break;
+            }
+        }
+        let end = self.expect(CTokenKind::Punctuator(SymbolKind::CloseCurlyBrace))?;
+
+        Ok(Initializer {
+            kind: InitializerKind::List(items),
+            span: start.to(&end),
+        })
+    }
+
+    /// `<initializer-list-item> ::= [ { <designator> } "=" ] <initializer>`
+    fn parse_initializer_list_item(&mut self) -> Result<InitializerListItem, ParseError> {
+        let start = self.peek_span();
+        let mut designators = Vec::new();
+        while let Some(designator) = self.parse_designator("initializer")? {
+            designators.push(designator);
+        }
+        if !designators.is_empty() {
+            self.expect(CTokenKind::Punctuator(SymbolKind::Equals))?;
+        }
+        let initializer = self.parse_initializer()?;
+
+        Ok(InitializerListItem {
+            designators,
+            span: start.to(&initializer.span),
+            initializer,
+        })
+    }
+
+    /// `<designator> ::= "[" <constant-exp> "]" | "." <identifier>`, in an
+    /// initializer or `__builtin_offsetof`, which `construct` names for errors.
+    pub(crate) fn parse_designator(
+        &mut self,
+        construct: &str,
+    ) -> Result<Option<Designator>, ParseError> {
+        let start = self.peek_span();
+        let kind = if self
+            .eat(CTokenKind::Punctuator(SymbolKind::OpenSquareBracket))
+            .is_some()
+        {
+            let index = self.parse_expression_with_precedence(CONDITIONAL_PRECEDENCE)?;
+            let not_constant = format!("array index in {construct} is not an integer constant");
+            let value = self
+                .integer_constant(&index)
+                .map_err(|err| err.into_error(&not_constant))?;
+            if value < 0 {
+                return Err(ParseError::new(
+                    index.span,
+                    &format!("array index in {construct} is negative"),
+                ));
+            }
+            let value =
+                u64::try_from(value).map_err(|_| ParseError::new(index.span, &not_constant))?;
+            self.expect(CTokenKind::Punctuator(SymbolKind::CloseSquareBracket))?;
+            DesignatorKind::Index(index, value)
+        } else if self.eat(CTokenKind::Punctuator(SymbolKind::Dot)).is_some() {
+            DesignatorKind::Member(self.parse_identifier()?)
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(Designator {
+            kind,
+            span: start.to(&self.prev_span()),
+        }))
+    }
+
+    /// Resolve an initializer for an object of type `ty`, giving the object's type,
+    /// with an array of unknown length completed, and its initial contents. The
+    /// initializer of an object with static storage duration must be constant.
+    pub(crate) fn resolve_initializer(
+        &self,
+        ty: &QualifiedType,
+        initializer: &Initializer,
+        is_static: bool,
+    ) -> Result<(QualifiedType, Vec<InitializerEntry>), ParseError> {
+        if ty.ty.is_function() {
+            return Err(ParseError::new(
+                initializer.span,
+                "a function cannot be initialized",
+            ));
+        }
+        if !matches!(ty.ty, Type::Array(_, None)) && self.tags.layout(&ty.ty).is_none() {
+            return Err(ParseError::new(
+                initializer.span,
+                "cannot initialize an object of incomplete type",
+            ));
+        }
+
+        let mut resolver = Resolver {
+            tags: &self.tags,
+            entries: Vec::new(),
+            union_members: HashMap::new(),
+        };
+        let object = Subobject {
+            ty: ty.clone(),
+            offset: 0,
+            bit_field: None,
+        };
+        let length = match &initializer.kind {
+            InitializerKind::List(items) => resolver.list(&object, items)?,
+            InitializerKind::Expression(expression) => match (&ty.ty, string_literal(expression)) {
+                (Type::Array(element, length), Some(bytes)) if is_character(&element.ty) => {
+                    resolver.string(&object, bytes, *length, expression.span)?
+                }
+                (Type::Array(..), _) => {
+                    return Err(ParseError::new(
+                        initializer.span,
+                        "array initializer must be an initializer list or string literal",
+                    ))
+                }
+                _ => {
+                    resolver.value(&object, expression);
+                    1
+                }
+            },
+        };
+        let ty = match &ty.ty {
+            Type::Array(element, None) => {
+                QualifiedType::new(Type::Array(element.clone(), Some(length)), ty.qualifiers)
+            }
+            _ => ty.clone(),
+        };
+        let size = self
+            .tags
+            .layout(&ty.ty)
+            .expect("initialized types are complete")
+            .size;
+        let entries = resolver.finish(size);
+
+        if is_static {
+            for entry in &entries {
+                if let InitializerEntryKind::Value(_, value)
+                | InitializerEntryKind::BitField(.., value) = &entry.kind
+                {
+                    if !may_be_constant(value) {
+                        return Err(ParseError::new(
+                            value.span,
+                            "initializer element is not constant",
+                        ));
+                    }
+                }
+            }
+        }
+        Ok((ty, entries))
+    }
+}
+
+/// A subobject being initialized: its type and where it is in the whole object.
+struct Subobject {
+    ty: QualifiedType,
+    offset: u64,
+    /// The bit offset and width of a bit-field, within the storage unit at `offset`.
+    bit_field: Option<(u32, u32)>,
+}
+impl Subobject {
+    fn bits(&self, size: u64) -> Range<u64> {
+        match self.bit_field {
+            Some((bit_offset, width)) => {
+                let start = self.offset * 8 + u64::from(bit_offset);
+                start..start + u64::from(width)
+            }
+            None => self.offset * 8..(self.offset + size) * 8,
+        }
+    }
+}
+
+struct Resolver<'a> {
+    tags: &'a TagTable,
+    /// The entries so far in no particular order, with the bits of the object each covers.
+    entries: Vec<(Range<u64>, InitializerEntry)>,
+    /// The member last initialized in each union, by the union's offset and tag.
+    union_members: HashMap<(u64, usize), u64>,
+}
+impl Resolver<'_> {
+    fn size(&self, ty: &Type) -> u64 {
+        self.tags
+            .layout(ty)
+            .expect("initialized subobjects are complete")
+            .size
+    }
+
+    /// The members of a struct or union that take part in initialization,
+    /// which leaves out unnamed bit-fields.
+    fn members(&self, ty: &Type) -> Vec<usize> {
+        let (Type::Struct(tag) | Type::Union(tag)) = ty else {
+            return Vec::new();
+        };
+        let Some(TagBody::Record(members, _)) = &self.tags.get(tag).body else {
+            return Vec::new();
+        };
+        (0..members.len())
+            .filter(|&i| members[i].name.is_some() || members[i].bit_width.is_none())
+            .collect()
+    }
+
+    /// Whether an aggregate initialized in order has a subobject at `position`.
+    /// Only the first member of a union is initialized without a designator.
+    fn has_position(&self, ty: &Type, position: u64) -> bool {
+        match ty {
+            Type::Array(_, Some(length)) => position < *length,
+            Type::Array(_, None) => true,
+            Type::Struct(_) => position < self.members(ty).len() as u64,
+            Type::Union(_) => position == 0 && !self.members(ty).is_empty(),
+            _ => false,
+        }
+    }
+
+    /// The subobject of `object` at `position`. Moving to another member of a union
+    /// discards whatever the union was initialized with so far.
+    fn subobject(&mut self, object: &Subobject, position: u64) -> Subobject {
+        match &object.ty.ty {
+            Type::Array(element, _) => Subobject {
+                offset: object.offset + position * self.size(&element.ty),
+                ty: (**element).clone(),
+                bit_field: None,
+            },
+            Type::Struct(tag) | Type::Union(tag) => {
+                let index = self.members(&object.ty.ty)[position as usize];
+                let Some(TagBody::Record(members, layout)) = &self.tags.get(tag).body else {
+                    unreachable!("records with members are complete");
+                };
+                let (member, field) = (&members[index], layout.fields[index]);
+                if matches!(object.ty.ty, Type::Union(_)) {
+                    let previous = self.union_members.insert((object.offset, tag.id), position);
+                    if previous.is_some_and(|previous| previous != position) {
+                        let size = self.size(&object.ty.ty);
+                        self.clear(&object.bits(size));
+                    }
+                }
+                Subobject {
+                    ty: member.ty.clone(),
+                    offset: object.offset + field.offset,
+                    bit_field: field.bit_offset.zip(member.bit_width),
+                }
+            }
+            _ => unreachable!("only aggregates have subobjects"),
+        }
+    }
+
+    /// Initialize `object` from the items of a braced list, returning how many
+    /// elements were initialized if it is an array.
+    fn list(
+        &mut self,
+        object: &Subobject,
+        items: &[InitializerListItem],
+    ) -> Result<u64, ParseError> {
+        if let (Type::Array(element, length), [item]) = (&object.ty.ty, items) {
+            if let InitializerKind::Expression(expression) = &item.initializer.kind {
+                let bytes = string_literal(expression)
+                    .filter(|_| item.designators.is_empty() && is_character(&element.ty));
+                if let Some(bytes) = bytes {
+                    // A string literal for a character array may be braced.
+                    return self.string(object, bytes, *length, expression.span);
+                }
+            }
+        }
+        if !matches!(
+            object.ty.ty,
+            Type::Array(..) | Type::Struct(_) | Type::Union(_)
+        ) {
+            return match items {
+                [] => Ok(0),
+                [item, ..] if !item.designators.is_empty() => Err(ParseError::new(
+                    item.designators[0].span,
+                    "designator in initializer for a scalar",
+                )),
+                [_] => {
+                    self.initialize(object, items, &mut 0)?;
+                    Ok(1)
+                }
+                [_, excess, ..] => Err(ParseError::new(
+                    excess.span,
+                    "excess elements in scalar initializer",
+                )),
+            };
+        }
+
+        let mut next = 0;
+        let mut position = 0;
+        let mut count = 0;
+        while let Some(item) = items.get(next) {
+            position = match item.designators.as_slice() {
+                [] if !self.has_position(&object.ty.ty, position) => {
+                    let aggregate = match object.ty.ty {
+                        Type::Array(..) => "array",
+                        Type::Struct(_) => "struct",
+                        _ => "union",
+                    };
+                    return Err(ParseError::new(
+                        item.span,
+                        &format!("excess elements in {aggregate} initializer"),
+                    ));
+                }
+                [] => {
+                    let subobject = self.subobject(object, position);
+                    self.initialize(&subobject, items, &mut next)?;
+                    position
+                }
+                designators => self.designate(object, designators, items, &mut next)?,
+            } + 1;
+            count = count.max(position);
+        }
+        Ok(count)
+    }
+
+    /// Initialize `subobject` from the item at `next`, and from the items after it
+    /// if it is an aggregate whose braces were elided.
+    fn initialize(
+        &mut self,
+        subobject: &Subobject,
+        items: &[InitializerListItem],
+        next: &mut usize,
+    ) -> Result<(), ParseError> {
+        let item = &items[*next];
+        if matches!(subobject.ty.ty, Type::Array(_, None)) {
+            return Err(ParseError::new(
+                item.span,
+                "flexible array member cannot be initialized",
+            ));
+        }
+        let expression = match &item.initializer.kind {
+            InitializerKind::List(list) => {
+                *next += 1;
+                return self.list(subobject, list).map(|_| ());
+            }
+            InitializerKind::Expression(expression) => expression,
+        };
+        match (&subobject.ty.ty, string_literal(expression)) {
+            (Type::Array(element, length), Some(bytes)) if is_character(&element.ty) => {
+                *next += 1;
+                self.string(subobject, bytes, *length, expression.span)
+                    .map(|_| ())
+            }
+            // Until expressions are typed, an expression for an aggregate is taken
+            // to start it with its braces elided, never to be a whole struct value.
+            (Type::Array(..) | Type::Struct(_) | Type::Union(_), _) => {
+                if !self.has_position(&subobject.ty.ty, 0) {
+                    return Err(ParseError::new(
+                        item.span,
+                        "initializer for an aggregate with no members",
+                    ));
+                }
+                let first = self.subobject(subobject, 0);
+                self.initialize(&first, items, next)?;
+                self.elide(subobject, 1, items, next)
+            }
+            _ => {
+                *next += 1;
+                self.value(subobject, expression);
+                Ok(())
+            }
+        }
+    }
+
+    /// Carry on initializing `object`, whose braces were elided, from `position` on,
+    /// until it is full or the next item has a designator of its own.
+    fn elide(
+        &mut self,
+        object: &Subobject,
+        mut position: u64,
+        items: &[InitializerListItem],
+        next: &mut usize,
+    ) -> Result<(), ParseError> {
+        while items
+            .get(*next)
+            .is_some_and(|item| item.designators.is_empty())
+            && self.has_position(&object.ty.ty, position)
+        {
+            let subobject = self.subobject(object, position);
+            self.initialize(&subobject, items, next)?;
+            position += 1;
+        }
+        Ok(())
+    }
+
+    /// Initialize the subobject of `object` named by `designators` from the item
+    /// at `next`, carrying on through each enclosing subobject the designators name,
+    /// and return the position in `object` of the first of them.
+    fn designate(
+        &mut self,
+        object: &Subobject,
+        designators: &[Designator],
+        items: &[InitializerListItem],
+        next: &mut usize,
+    ) -> Result<u64, ParseError> {
+        let (designator, rest) = designators
+            .split_first()
+            .expect("designate is only called with designators");
+        let (position, is_anonymous) = self.designated_position(object, designator)?;
+        let subobject = self.subobject(object, position);
+        // A member of an anonymous struct or union is designated through the anonymous member.
+        let rest = match is_anonymous {
+            true => designators,
+            false => rest,
+        };
+        if rest.is_empty() {
+            self.initialize(&subobject, items, next)?;
+        } else {
+            let inner = self.designate(&subobject, rest, items, next)?;
+            self.elide(&subobject, inner + 1, items, next)?;
+        }
+        Ok(position)
+    }
+
+    /// The position in `object` of the subobject `designator` names, and whether
+    /// it is an anonymous member holding the named member.
+    fn designated_position(
+        &self,
+        object: &Subobject,
+        designator: &Designator,
+    ) -> Result<(u64, bool), ParseError> {
+        let error = |context: &str| Err(ParseError::new(designator.span, context));
+        match (&object.ty.ty, &designator.kind) {
+            (Type::Array(_, Some(length)), DesignatorKind::Index(_, index)) if index >= length => {
+                error("array index in initializer exceeds array bounds")
+            }
+            (Type::Array(..), DesignatorKind::Index(_, index)) => Ok((*index, false)),
+            (Type::Struct(tag) | Type::Union(tag), DesignatorKind::Member(name)) => {
+                let Some(TagBody::Record(members, _)) = &self.tags.get(tag).body else {
+                    unreachable!("records with members are complete");
+                };
+                let is_named = |&index: &usize| members[index].name.as_ref() == Some(&name.name);
+                let holds_named =
+                    |&index: &usize| match (&members[index].name, &members[index].ty.ty) {
+                        (None, Type::Struct(inner) | Type::Union(inner)) => {
+                            self.tags.member(inner, &name.name).is_some()
+                        }
+                        _ => false,
+                    };
+                let positions = self.members(&object.ty.ty);
+                if let Some(position) = positions.iter().position(is_named) {
+                    return Ok((position as u64, false));
+                }
+                match positions.iter().position(holds_named) {
+                    Some(position) => Ok((position as u64, true)),
+                    None => error(&format!("no member named `{}` in {}", name.name, object.ty)),
+                }
+            }
+            (_, DesignatorKind::Index(..)) => {
+                error("array index in initializer for a non-array type")
+            }
+            (_, DesignatorKind::Member(_)) => {
+                error("member designator in initializer for a non-struct, non-union type")
+            }
+        }
+    }
+
+    /// Initialize a character array from a string literal, returning the array's length.
+    fn string(
+        &mut self,
+        object: &Subobject,
+        bytes: &[u8],
+        length: Option<u64>,
+        span: SourceSpan,
+    ) -> Result<u64, ParseError> {
+        let length = length.unwrap_or(bytes.len() as u64 + 1);
+        if bytes.len() as u64 > length {
+            return Err(ParseError::new(
+                span,
+                "initializer string is too long for the array",
+            ));
+        }
+        let mut bytes = bytes.to_vec();
+        bytes.push(0);
+        bytes.truncate(length as usize);
+        let bits = object.offset * 8..(object.offset + bytes.len() as u64) * 8;
+        self.store(bits, object.offset, InitializerEntryKind::Bytes(bytes));
+        Ok(length)
+    }
+
+    fn value(&mut self, object: &Subobject, expression: &Expression) {
+        let bits = object.bits(self.size(&object.ty.ty));
+        let kind = match object.bit_field {
+            Some((bit_offset, width)) => InitializerEntryKind::BitField(
+                object.ty.clone(),
+                bit_offset,
+                width,
+                expression.clone(),
+            ),
+            None => InitializerEntryKind::Value(object.ty.clone(), expression.clone()),
+        };
+        self.store(bits, object.offset, kind);
+    }
+
+    /// Record an entry covering `bits` of the object, overriding whatever was there.
+    fn store(&mut self, bits: Range<u64>, offset: u64, kind: InitializerEntryKind) {
+        self.clear(&bits);
+        self.entries.push((bits, InitializerEntry { offset, kind }));
+    }
+
+    /// Remove every entry overlapping `bits`, keeping the rest of any string literal's bytes.
+    fn clear(&mut self, bits: &Range<u64>) {
+        let mut kept = Vec::new();
+        for (range, entry) in std::mem::take(&mut self.entries) {
+            if range.end <= bits.start || bits.end <= range.start {
+                kept.push((range, entry));
+                continue;
+            }
+            let InitializerEntryKind::Bytes(bytes) = entry.kind else {
+                continue;
+            };
+            let (start, end) = (bits.start / 8, bits.end.div_ceil(8));
+            if entry.offset < start {
+                let before = bytes[..(start - entry.offset) as usize].to_vec();
+                kept.push((
+                    range.start..start * 8,
+                    InitializerEntry {
+                        offset: entry.offset,
+                        kind: InitializerEntryKind::Bytes(before),
+                    },
+                ));
+            }
+            if range.end > end * 8 {
+                let after = bytes[(end - entry.offset) as usize..].to_vec();
+                kept.push((
+                    end * 8..range.end,
+                    InitializerEntry {
+                        offset: end,
+                        kind: InitializerEntryKind::Bytes(after),
+                    },
+                ));
+            }
+        }
+        self.entries = kept;
+    }
+
+    /// The entries in order of offset, with the bytes between them zeroed up to `size`.
+    fn finish(mut self, size: u64) -> Vec<InitializerEntry> {
+        self.entries.sort_by_key(|(bits, _)| bits.start);
+        let mut entries = Vec::with_capacity(self.entries.len());
+        let mut end = 0;
+        for (bits, entry) in self.entries {
+            let start = bits.start / 8;
+            if start > end {
+                entries.push(InitializerEntry {
+                    offset: end,
+                    kind: InitializerEntryKind::Zero(start - end),
+                });
+            }
+            end = end.max(bits.end.div_ceil(8));
+            entries.push(entry);
+        }
+        if size > end {
+            entries.push(InitializerEntry {
+                offset: end,
+                kind: InitializerEntryKind::Zero(size - end),
+            });
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod initializer_tests {
+    use expect_test::{expect, Expect};
+    use tokengen::span::Span;
+    use wacc_lexer::Lexer;
+
+    use crate::{
+        ast::{ExternalDeclaration, InitializerEntryKind},
+        Parser,
+    };
+
+    /// List the initial contents of every object declared at file scope, entry by entry.
+    fn check_initializers(src: &str, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let program = parser.parse_program();
+        let mut output = Vec::new();
+        for item in &program.items {
+            let ExternalDeclaration::Declaration(declaration) = item else {
+                continue;
+            };
+            for declarator in &declaration.declarators {
+                output.push(declarator.ty.declare(&declarator.name.name));
+                for entry in &declarator.entries {
+                    let contents = match &entry.kind {
+                        InitializerEntryKind::Zero(size) => format!("{size} zero bytes"),
+                        InitializerEntryKind::Value(ty, value) => {
+                            format!("{ty} `{}`", value.span.span())
+                        }
+                        InitializerEntryKind::BitField(ty, bit_offset, width, value) => format!(
+                            "{ty} bits {bit_offset}..{} `{}`",
+                            bit_offset + width,
+                            value.span.span()
+                        ),
+                        InitializerEntryKind::Bytes(bytes) => {
+                            format!("\"{}\"", bytes.escape_ascii())
+                        }
+                    };
+                    output.push(format!("    {}: {contents}", entry.offset));
+                }
+            }
+        }
+        output.extend(
+            parser
+                .errors()
+                .iter()
+                .map(|err| format!("{} at `{}`", err.context(), err.span().span())),
+        );
+        expect.assert_eq(&output.join("\n"));
+    }
+
+    #[test]
+    fn test_designated_initializers() {
+        check_initializers(
+            r#"
+            struct point { int x, y; };
+            struct point p = { .y = 2 };
+            int a[] = { 1, [4] = 5, 6, [1] = 2 };
+            struct point ps[3] = { [1].y = 7, 8, [0] = { 9 } };
+            struct line { struct point from, to; } l = { .to.x = 1, 2, .from = { .y = 3 } };
+            union number { char c; long l; } n = { .l = 1, .c = 2 };
+            struct anonymous { int a; struct { int b, c; }; } s = { .c = 3, .a = 1 };
+            "#,
+            expect![[r#"
+                struct point p
+                    0: 4 zero bytes
+                    4: int `2`
+                int a[6]
+                    0: int `1`
+                    4: int `2`
+                    8: 8 zero bytes
+                    16: int `5`
+                    20: int `6`
+                struct point ps[3]
+                    0: int `9`
+                    4: 8 zero bytes
+                    12: int `7`
+                    16: int `8`
+                    20: 4 zero bytes
+                struct line l
+                    0: 4 zero bytes
+                    4: int `3`
+                    8: int `1`
+                    12: int `2`
+                union number n
+                    0: char `2`
+                    1: 7 zero bytes
+                struct anonymous s
+                    0: int `1`
+                    4: 4 zero bytes
+                    8: int `3`"#]],
+        );
+    }
+
+    #[test]
+    fn test_brace_elision() {
+        check_initializers(
+            r#"
+            int matrix[2][3] = { 1, 2, 3, 4 };
+            struct point { int x, y; } ps[] = { 1, 2, 3 };
+            struct flags { unsigned a : 3, : 2, b : 5; int n; } f = { 1, 2, 3 };
+            int scalar = { 4 };
+            double empty[2] = {};
+            "#,
+            expect![[r#"
+                int matrix[2][3]
+                    0: int `1`
+                    4: int `2`
+                    8: int `3`
+                    12: int `4`
+                    16: 8 zero bytes
+                struct point ps[2]
+                    0: int `1`
+                    4: int `2`
+                    8: int `3`
+                    12: 4 zero bytes
+                struct flags f
+                    0: unsigned int bits 0..3 `1`
+                    0: unsigned int bits 5..10 `2`
+                    2: 2 zero bytes
+                    4: int `3`
+                int scalar
+                    0: int `4`
+                double empty[2]
+                    0: 16 zero bytes"#]],
+        );
+    }
+
+    #[test]
+    fn test_string_initializers() {
+        check_initializers(
+            r#"
+            char greeting[] = "hi";
+            char exact[2] = "hi";
+            char braced[4] = { "ab" };
+            char lines[][4] = { "one", "two" };
+            struct message { int id; char text[6]; } m = { 1, "hello" };
+            struct message patched = { .text = "abc", .text[1] = 66, 2 };
+            char *pointer = "text";
+            "#,
+            expect![[r#"
+                char greeting[3]
+                    0: "hi\x00"
+                char exact[2]
+                    0: "hi"
+                char braced[4]
+                    0: "ab\x00"
+                    3: 1 zero bytes
+                char lines[2][4]
+                    0: "one\x00"
+                    4: "two\x00"
+                struct message m
+                    0: int `1`
+                    4: "hello\x00"
+                    10: 2 zero bytes
+                struct message patched
+                    0: 4 zero bytes
+                    4: "a"
+                    5: char `66`
+                    6: char `2`
+                    7: "\x00"
+                    8: 4 zero bytes
+                char *pointer
+                    0: char * `"text"`"#]],
+        );
+    }
+
+    #[test]
+    fn test_initializer_errors() {
+        check_initializers(
+            r#"
+            int a[2] = { 1, 2, 3 };
+            int b[2] = { [2] = 1 };
+            struct point { int x, y; } p = { .z = 1 };
+            int c = { 1, 2 };
+            int d = { .x = 1 };
+            char e[2] = "abc";
+            int g[2] = 3;
+            struct incomplete h = { 0 };
+            int i = f();
+            int j[2] = { [-1] = 0 };
+            int k[] = { [n] = 0 };
+            struct point q = { [0] = 1 };
+            int r[1] = { .x = 1 };
+            struct flexible { int n; int data[]; } s = { 1, { 2 } };
+            int t(void) = 0;
+            "#,
+            expect![[r#"
+                excess elements in array initializer at `3`
+                array index in initializer exceeds array bounds at `[2]`
+                no member named `z` in struct point at `.z`
+                excess elements in scalar initializer at `2`
+                designator in initializer for a scalar at `.x`
+                initializer string is too long for the array at `"abc"`
+                array initializer must be an initializer list or string literal at `3`
+                cannot initialize an object of incomplete type at `{ 0 }`
+                initializer element is not constant at `f()`
+                array index in initializer is negative at `-1`
+                array index in initializer is not an integer constant at `n`
+                array index in initializer for a non-array type at `[0]`
+                member designator in initializer for a non-struct, non-union type at `.x`
+                flexible array member cannot be initialized at `{ 2 }`
+                a function cannot be initialized at `0`"#]],
+        );
+    }
+}
@@ -0,0 +1,363 @@
+//! Sizes, alignments and member offsets of C types, following the System V
+//! x86-64 psABI. This is the one place data layout is decided; `sizeof`,
+//! `offsetof` and code generation all ask the [`TagTable`] for it.
+
+use crate::types::{Member, Tag, TagBody, TagKind, TagTable, Type};
+
+/// The size and alignment of a complete object type, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub size: u64,
+    pub align: u64,
+}
+
+/// Where a member of a struct or union is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldLayout {
+    /// The byte offset of the member, or of the storage unit holding a bit-field.
+    pub offset: u64,
+    /// For a bit-field, the offset of its lowest bit within the storage unit at `offset`.
+    pub bit_offset: Option<u32>,
+}
+
+/// The layout of a struct or union, with a [`FieldLayout`] for each of its members in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordLayout {
+    pub size: u64,
+    pub align: u64,
+    pub fields: Vec<FieldLayout>,
+}
+
+fn align_up(n: u64, align: u64) -> u64 {
+    n.div_ceil(align) * align
+}
+
+impl TagTable {
+    /// The size and alignment of a type, or `None` if it is incomplete or a function type.
+    pub fn layout(&self, ty: &Type) -> Option<Layout> {
+        let scalar = |size| Some(Layout { size, align: size });
+        match ty {
+            Type::Char | Type::SignedChar | Type::UnsignedChar => scalar(1),
+            Type::Short | Type::UnsignedShort => scalar(2),
+            Type::Int | Type::UnsignedInt | Type::Float => scalar(4),
+            Type::Long
+            | Type::UnsignedLong
+            | Type::LongLong
+            | Type::UnsignedLongLong
+            | Type::Double
+            | Type::Pointer(_) => scalar(8),
+            Type::Array(element, Some(length)) => {
+                let element = self.layout(&element.ty)?;
+                Some(Layout {
+                    size: element.size.checked_mul(*length)?,
+                    align: element.align,
+                })
+            }
+            Type::Struct(tag) | Type::Union(tag) => match &self.get(tag).body {
+                Some(TagBody::Record(_, layout)) => Some(Layout {
+                    size: layout.size,
+                    align: layout.align,
+                }),
+                _ => None,
+            },
+            Type::Enum(tag) => self.get(tag).body.as_ref().and(scalar(4)),
+            Type::Void | Type::Array(_, None) | Type::Function(_) => None,
+        }
+    }
+
+    /// Lay out the members of a struct or union, which must all be complete apart
+    /// from a flexible array member at the end of a struct.
+    ///
+    /// Members are placed in order at the next offset aligned for their type. A
+    /// bit-field is packed right after the previous one unless it would straddle a
+    /// storage unit of its declared type, in which case it starts the next unit; a
+    /// zero-width bit-field just skips to the next unit. Unnamed bit-fields don't
+    /// affect the alignment of the whole record.
+    pub(crate) fn layout_record(&self, kind: TagKind, members: &[Member]) -> RecordLayout {
+        let mut fields = Vec::with_capacity(members.len());
+        let mut align = 1;
+        // The end of the last member in a struct, or the largest member in a union, in bits.
+        let mut bits = 0_u64;
+        for member in members {
+            let layout = match &member.ty.ty {
+                Type::Array(element, None) => Layout {
+                    size: 0,
+                    align: self.member_layout(&element.ty).align,
+                },
+                ty => self.member_layout(ty),
+            };
+            let Some(width) = member.bit_width.map(u64::from) else {
+                let offset = match kind {
+                    TagKind::Union => 0,
+                    _ => align_up(bits.div_ceil(8), layout.align),
+                };
+                fields.push(FieldLayout {
+                    offset,
+                    bit_offset: None,
+                });
+                bits = bits.max((offset + layout.size) * 8);
+                align = align.max(layout.align);
+                continue;
+            };
+            let unit = layout.size * 8;
+            let start = match kind {
+                TagKind::Union => 0,
+                _ if width == 0 => align_up(bits, unit),
+                _ if bits / unit != (bits + width - 1) / unit => align_up(bits, unit),
+                _ => bits,
+            };
+            let unit_start = start / unit * unit;
+            fields.push(FieldLayout {
+                offset: unit_start / 8,
+                bit_offset: Some((start - unit_start) as u32),
+            });
+            bits = bits.max(start + width);
+            if member.name.is_some() {
+                align = align.max(layout.align);
+            }
+        }
+
+        RecordLayout {
+            size: align_up(bits.div_ceil(8), align),
+            align,
+            fields,
+        }
+    }
+
+    fn member_layout(&self, ty: &Type) -> Layout {
+        self.layout(ty)
+            .expect("members are checked to be complete before layout")
+    }
+
+    /// Find the member `name` of a struct or union, looking inside anonymous
+    /// struct and union members too, along with its layout from the start of the record.
+    pub fn member(&self, tag: &Tag, name: &str) -> Option<(FieldLayout, &Member)> {
+        let Some(TagBody::Record(members, layout)) = &self.get(tag).body else {
+            return None;
+        };
+        for (member, field) in members.iter().zip(&layout.fields) {
+            match (&member.name, &member.ty.ty) {
+                (Some(member_name), _) if member_name == name => return Some((*field, member)),
+                (None, Type::Struct(inner) | Type::Union(inner)) => {
+                    if let Some((inner_field, member)) = self.member(inner, name) {
+                        let field = FieldLayout {
+                            offset: field.offset + inner_field.offset,
+                            ..inner_field
+                        };
+                        return Some((field, member));
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod layout_tests {
+    use expect_test::{expect, Expect};
+    use wacc_lexer::Lexer;
+
+    use tokengen::span::Span;
+
+    use crate::{
+        ast::{ExpressionKind, ExternalDeclaration, Initializer, InitializerKind},
+        types::{Tag, TagBody},
+        Parser,
+    };
+
+    /// Describe the layout of every struct and union defined in `src`, member by member.
+    fn check_layouts(src: &str, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let program = parser.parse_program();
+        assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+        let mut output = Vec::new();
+        for definition in program.tags.iter() {
+            let Some(TagBody::Record(members, layout)) = &definition.body else {
+                continue;
+            };
+            let name = definition.name.as_deref().unwrap_or("(anonymous)");
+            output.push(format!(
+                "{} {name}: size {}, align {}",
+                definition.kind, layout.size, layout.align
+            ));
+            for (member, field) in members.iter().zip(&layout.fields) {
+                let name = member.name.as_deref().unwrap_or("");
+                let position = match (field.bit_offset, member.bit_width) {
+                    (Some(bit), Some(width)) => format!("{}:{bit}..{}", field.offset, bit + width),
+                    _ => field.offset.to_string(),
+                };
+                output.push(format!("    {position} {}", member.ty.declare(name)));
+            }
+        }
+        expect.assert_eq(&output.join("\n"));
+    }
+
+    #[test]
+    fn test_struct_layout() {
+        check_layouts(
+            r#"
+            struct s { char c; double d; short h; };
+            struct nested { char c; struct s inner; int a[3]; char *p; };
+            union u { char c[5]; int i; };
+            struct flexible { int length; char data[]; };
+            struct empty_tail { long l; char c; };
+            "#,
+            expect![[r#"
+                struct s: size 24, align 8
+                    0 char c
+                    8 double d
+                    16 short h
+                struct nested: size 56, align 8
+                    0 char c
+                    8 struct s inner
+                    32 int a[3]
+                    48 char *p
+                union u: size 8, align 4
+                    0 char c[5]
+                    0 int i
+                struct flexible: size 4, align 4
+                    0 int length
+                    4 char data[]
+                struct empty_tail: size 16, align 8
+                    0 long l
+                    8 char c"#]],
+        );
+    }
+
+    #[test]
+    fn test_bit_field_layout() {
+        check_layouts(
+            r#"
+            struct flags { unsigned a : 3; unsigned b : 30; unsigned c : 1; };
+            struct packed { char c; int x : 4; int y : 4; };
+            struct zero { char a : 4; int : 0; char b : 4; };
+            struct unnamed { char c; long : 4; };
+            union bits { int x : 3; char c; };
+            "#,
+            expect![[r#"
+                struct flags: size 8, align 4
+                    0:0..3 unsigned int a
+                    4:0..30 unsigned int b
+                    4:30..31 unsigned int c
+                struct packed: size 4, align 4
+                    0 char c
+                    0:8..12 int x
+                    0:12..16 int y
+                struct zero: size 5, align 1
+                    0:0..4 char a
+                    4:0..0 int
+                    4:0..4 char b
+                struct unnamed: size 2, align 1
+                    0 char c
+                    0:8..12 long
+                union bits: size 4, align 4
+                    0:0..3 int x
+                    0 char c"#]],
+        );
+    }
+
+    #[test]
+    fn test_member_lookup() {
+        let src = "struct outer { int a; union { char c; struct { short x, y; }; }; long b; };";
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let program = parser.parse_program();
+        let id = program
+            .tags
+            .iter()
+            .position(|definition| definition.name.as_deref() == Some("outer"))
+            .unwrap();
+        let outer = Tag {
+            id,
+            name: Some("outer".to_string()),
+        };
+        let offsets = ["a", "c", "x", "y", "b", "z"]
+            .iter()
+            .map(|name| match program.tags.member(&outer, name) {
+                Some((field, member)) => format!("{} @ {}", member.ty.declare(name), field.offset),
+                None => format!("no member {name}"),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        expect![[r#"
+            int a @ 0
+            char c @ 4
+            short x @ 4
+            short y @ 6
+            long b @ 8
+            no member z"#]]
+        .assert_eq(&offsets);
+    }
+
+    /// The offset each `__builtin_offsetof` in the initializers of `src` gives, or
+    /// the errors it reports.
+    fn check_offsetof(src: &str, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let program = parser.parse_program();
+        let mut output = Vec::new();
+        for item in &program.items {
+            let ExternalDeclaration::Declaration(declaration) = item else {
+                continue;
+            };
+            for declarator in &declaration.declarators {
+                let Some(Initializer {
+                    kind: InitializerKind::Expression(expression),
+                    ..
+                }) = &declarator.initializer
+                else {
+                    continue;
+                };
+                if let ExpressionKind::Offsetof(_, _, offset) = &expression.kind {
+                    output.push(format!("{} = {offset}", declarator.name.name));
+                }
+            }
+        }
+        for error in parser.errors() {
+            output.push(format!("{} at {:?}", error.context(), error.span().span()));
+        }
+        expect.assert_eq(&output.join("\n"));
+    }
+
+    #[test]
+    fn test_offsetof() {
+        check_offsetof(
+            r#"
+            struct s { char c; double d; short h; };
+            struct nested { char c; struct s inner[2]; union { int i; struct { char x, y; }; }; };
+            struct bits { int a; unsigned b : 3; };
+            struct flexible { int length; char data[]; };
+            struct incomplete;
+            unsigned long d = __builtin_offsetof(struct s, d);
+            unsigned long h = __builtin_offsetof(struct s, h);
+            unsigned long inner_h = __builtin_offsetof(struct nested, inner[1].h);
+            unsigned long y = __builtin_offsetof(struct nested, y);
+            unsigned long data = __builtin_offsetof(struct flexible, data[3]);
+            unsigned long a = __builtin_offsetof(struct bits, a);
+            char array[__builtin_offsetof(struct s, h)];
+            unsigned long b = __builtin_offsetof(struct bits, b);
+            unsigned long z = __builtin_offsetof(struct s, z);
+            unsigned long i = __builtin_offsetof(struct incomplete, i);
+            unsigned long c = __builtin_offsetof(struct s, c[1]);
+            unsigned long n = __builtin_offsetof(struct nested, inner.d);
+            unsigned long m = __builtin_offsetof(struct nested, inner[-1]);
+            "#,
+            expect![[r#"
+                d = 8
+                h = 16
+                inner_h = 48
+                y = 57
+                data = 7
+                a = 0
+                cannot compute offset of bit-field `b` at "b"
+                no member named `z` in `struct s` at "z"
+                offsetof of incomplete type `struct incomplete` at "i"
+                offsetof requires an array, not `char` at "[1]"
+                offsetof requires a struct or union, not `struct s [2]` at ".d"
+                array index in offsetof is negative at "-1""#]],
+        );
+    }
+}
@@ -0,0 +1,472 @@
+use tokengen::{
+    span::{SourceSpan, Span},
+    token::TokenStream,
+};
+use wacc_lexer::c_token::{CToken, CTokenKind};
+
+use crate::{
+    ast::{ExternalDeclaration, Identifier, Program},
+    scope::Scopes,
+    types::TagTable,
+};
+
+pub mod ast;
+pub mod constant;
+mod control_flow;
+mod declaration;
+pub mod dump;
+mod expression;
+mod initializer;
+pub mod layout;
+mod literal;
+mod print;
+mod recovery;
+mod scope;
+mod statement;
+mod tag;
+pub mod types;
+
+/// Shown as `line:col: error: context`, then the source it covers. The driver
+/// puts the name of the file in front.
+#[derive(Debug, thiserror::Error)]
+#[error("{location}: error: {context}:\n{source}", location = span.location(), source = span.span())]
+pub struct ParseError {
+    span: SourceSpan,
+    context: String,
+}
+impl ParseError {
+    pub fn new(span: SourceSpan, context: &str) -> Self {
+        Self {
+            span,
+            context: context.into(),
+        }
+    }
+    pub fn span(&self) -> SourceSpan {
+        self.span
+    }
+    pub fn context(&self) -> &str {
+        &self.context
+    }
+}
+
+/// A recursive descent parser over the [`CToken`]s produced by `wacc-lexer`.
+///
+/// Syntax errors don't stop the parser: each one is recorded, an error node is
+/// put in its place in the AST, and parsing resumes from the next synchronization
+/// point (see [`recovery`]), so a single run reports every independent error.
+pub struct Parser<'a> {
+    src: &'a str,
+    tokens: &'a [CToken],
+    pos: usize,
+    errors: Vec<ParseError>,
+    /// The token position of the last reported error, used to suppress cascades.
+    last_error: Option<usize>,
+    /// The names declared so far, to tell typedef names from other identifiers.
+    scopes: Scopes,
+    tags: TagTable,
+}
+impl<'a> Parser<'a> {
+    pub fn new(src: &'a str, tokens: &'a TokenStream<CToken>) -> Self {
+        Self {
+            src,
+            tokens: tokens.as_slice(),
+            pos: 0,
+            errors: Vec::new(),
+            last_error: None,
+            scopes: Scopes::new(),
+            tags: TagTable::default(),
+        }
+    }
+
+    /// Every syntax error reported so far, in source order.
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    /// `<program> ::= { <external-declaration> }`
+    pub fn parse_program(&mut self) -> Program {
+        let start = self.peek_span();
+        let mut items = Vec::new();
+        while self.peek().is_some() {
+            let item_start = self.pos;
+            match self.parse_external_declaration() {
+                Ok(item) => items.push(item),
+                Err(err) => {
+                    self.report(err);
+                    self.synchronize_external_declaration(item_start);
+                    items.push(ExternalDeclaration::Error(self.span_from(item_start)));
+                }
+            }
+        }
+        if items.is_empty() {
+            let err = self.error("expected a declaration");
+            self.report(err);
+        }
+
+        Program {
+            items,
+            tags: std::mem::take(&mut self.tags),
+            span: start.to(&self.prev_span()),
+        }
+    }
+
+    fn parse_identifier(&mut self) -> Result<Identifier, ParseError> {
+        let span = self.expect(CTokenKind::Identifier)?;
+        Ok(Identifier {
+            name: span.span().to_string(),
+            span,
+        })
+    }
+
+    fn peek(&self) -> Option<CTokenKind> {
+        self.peek_nth(0)
+    }
+    /// The kind of the token `n` tokens ahead of the next one.
+    fn peek_nth(&self, n: usize) -> Option<CTokenKind> {
+        self.tokens.get(self.pos + n).and_then(CToken::kind)
+    }
+    /// The span of the next token, or an empty span at the end of the source.
+    fn peek_span(&self) -> SourceSpan {
+        self.peek_nth_span(0)
+    }
+    /// The span of the token `n` tokens ahead of the next one.
+    fn peek_nth_span(&self, n: usize) -> SourceSpan {
+        self.tokens
+            .get(self.pos + n)
+            .and_then(CToken::source_span)
+            .unwrap_or_else(|| SourceSpan::new(self.src, self.src.len(), self.src.len()))
+    }
+    /// The span from the token at `start` up to the last consumed token.
+    fn span_from(&self, start: usize) -> SourceSpan {
+        let start_span = self
+            .tokens
+            .get(start)
+            .and_then(CToken::source_span)
+            .unwrap_or_else(|| self.peek_span());
+        if self.pos > start {
+            start_span.to(&self.prev_span())
+        } else {
+            SourceSpan::new(self.src, start_span.start(), start_span.start())
+        }
+    }
+    fn prev_span(&self) -> SourceSpan {
+        self.pos
+            .checked_sub(1)
+            .and_then(|prev| self.tokens[prev].source_span())
+            .unwrap_or_else(|| SourceSpan::new(self.src, 0, 0))
+    }
+    fn check(&self, kind: CTokenKind) -> bool {
+        self.peek() == Some(kind)
+    }
+    /// Consume the next token if it is of the given kind, returning its span.
+    fn eat(&mut self, kind: CTokenKind) -> Option<SourceSpan> {
+        let span = self.check(kind).then(|| self.peek_span())?;
+        self.pos += 1;
+        Some(span)
+    }
+    /// Consume the next token if it is of the expected kind, returning its span.
+    fn expect(&mut self, kind: CTokenKind) -> Result<SourceSpan, ParseError> {
+        if !self.check(kind) {
+            return Err(self.error(&format!("expected {kind}")));
+        }
+        let span = self.peek_span();
+        self.pos += 1;
+        Ok(span)
+    }
+    /// An error at the next token, noting what was found instead.
+    fn error(&self, context: &str) -> ParseError {
+        match self.peek() {
+            Some(found) => ParseError::new(self.peek_span(), &format!("{context}, found {found}")),
+            None => ParseError::new(self.peek_span(), &format!("{context}, found end of input")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use expect_test::{expect, Expect};
+    use tokengen::span::Span;
+    use wacc_lexer::Lexer;
+
+    use super::Parser;
+    use crate::ast::{BlockItem, ExternalDeclaration, StatementKind};
+
+    fn check_program(src: &str, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let program = parser.parse_program();
+        let output = if parser.errors().is_empty() {
+            format!("{program:#?}")
+        } else {
+            parser
+                .errors()
+                .iter()
+                .map(|err| format!("{} at `{}`", err.context(), err.span().span()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        expect.assert_eq(&output);
+    }
+
+    #[test]
+    fn test_parse_return_2() {
+        check_program(
+            "int main(void) {\n  return 2;\n}\n",
+            expect![[r#"
+                Program {
+                    items: [
+                        Function(
+                            FunctionDefinition {
+                                specifiers: DeclarationSpecifiers {
+                                    storage_class: None,
+                                    is_inline: false,
+                                    type_specifiers: [
+                                        Int,
+                                    ],
+                                    qualifiers: Qualifiers {
+                                        is_const: false,
+                                        is_volatile: false,
+                                        is_restrict: false,
+                                    },
+                                    span: SourceSpan {
+                                        src: "int",
+                                        start: 0,
+                                        end: 3,
+                                    },
+                                },
+                                declarator: Declarator {
+                                    kind: Function(
+                                        Declarator {
+                                            kind: Identifier(
+                                                Some(
+                                                    Identifier {
+                                                        name: "main",
+                                                        span: SourceSpan {
+                                                            src: "main",
+                                                            start: 4,
+                                                            end: 8,
+                                                        },
+                                                    },
+                                                ),
+                                            ),
+                                            span: SourceSpan {
+                                                src: "main",
+                                                start: 4,
+                                                end: 8,
+                                            },
+                                        },
+                                        ParameterList {
+                                            parameters: [],
+                                            is_variadic: false,
+                                            is_prototype: true,
+                                            span: SourceSpan {
+                                                src: "(void)",
+                                                start: 8,
+                                                end: 14,
+                                            },
+                                        },
+                                    ),
+                                    span: SourceSpan {
+                                        src: "main(void)",
+                                        start: 4,
+                                        end: 14,
+                                    },
+                                },
+                                name: Identifier {
+                                    name: "main",
+                                    span: SourceSpan {
+                                        src: "main",
+                                        start: 4,
+                                        end: 8,
+                                    },
+                                },
+                                ty: QualifiedType {
+                                    ty: Function(
+                                        FunctionType {
+                                            return_type: QualifiedType {
+                                                ty: Int,
+                                                qualifiers: Qualifiers {
+                                                    is_const: false,
+                                                    is_volatile: false,
+                                                    is_restrict: false,
+                                                },
+                                            },
+                                            parameters: [],
+                                            is_variadic: false,
+                                            is_prototype: true,
+                                        },
+                                    ),
+                                    qualifiers: Qualifiers {
+                                        is_const: false,
+                                        is_volatile: false,
+                                        is_restrict: false,
+                                    },
+                                },
+                                parameters: [],
+                                body: Block {
+                                    items: [
+                                        Statement(
+                                            Statement {
+                                                kind: Return(
+                                                    Some(
+                                                        Expression {
+                                                            kind: Constant(
+                                                                Integer(
+                                                                    2,
+                                                                    Int,
+                                                                ),
+                                                            ),
+                                                            span: SourceSpan {
+                                                                src: "2",
+                                                                start: 26,
+                                                                end: 27,
+                                                            },
+                                                            ty: None,
+                                                        },
+                                                    ),
+                                                ),
+                                                span: SourceSpan {
+                                                    src: "return 2;",
+                                                    start: 19,
+                                                    end: 28,
+                                                },
+                                            },
+                                        ),
+                                    ],
+                                    span: SourceSpan {
+                                        src: "{\n  return 2;\n}",
+                                        start: 15,
+                                        end: 30,
+                                    },
+                                },
+                                span: SourceSpan {
+                                    src: "int main(void) {\n  return 2;\n}",
+                                    start: 0,
+                                    end: 30,
+                                },
+                            },
+                        ),
+                    ],
+                    tags: TagTable {
+                        definitions: [],
+                    },
+                    span: SourceSpan {
+                        src: "int main(void) {\n  return 2;\n}",
+                        start: 0,
+                        end: 30,
+                    },
+                }"#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        check_program(
+            "int main(void) { return 2 }",
+            expect!["expected `;`, found `}` at `}`"],
+        );
+        check_program(
+            "int main(void) { return 2;",
+            expect!["expected `}`, found end of input at ``"],
+        );
+        check_program(
+            "int main(void) { return 2; } foo",
+            expect!["expected declaration specifiers, found identifier at `foo`"],
+        );
+        check_program(
+            "int 3(void) { return 2; }",
+            expect!["expected an identifier or `(`, found constant at `3`"],
+        );
+    }
+
+    #[test]
+    fn test_error_location() {
+        let src = "int main(void) {\n  return 2\n}\n";
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        parser.parse_program();
+        let errors: Vec<String> = parser.errors().iter().map(|err| err.to_string()).collect();
+        expect![[r#"
+            [
+                "3:1: error: expected `;`, found `}`:\n}",
+            ]
+        "#]]
+        .assert_debug_eq(&errors);
+    }
+
+    #[test]
+    fn test_recover_from_independent_errors() {
+        check_program(
+            r#"
+            int main(void) {
+                return 1 +;
+                x = (2 * ;
+                foo(1 2);
+                return 3;
+            }
+            int 4(void) { return 5; }
+            int g(void) { return (6; }
+            "#,
+            expect![[r#"
+                expected an expression, found `;` at `;`
+                expected an expression, found `;` at `;`
+                expected `)`, found constant at `2`
+                expected an identifier or `(`, found constant at `4`
+                expected `)`, found `;` at `;`"#]],
+        );
+    }
+
+    #[test]
+    fn test_error_nodes() {
+        let src = "int main(void) { return ; foo(1 2); int x = 3; return 3; } int 4(void) {} int f(void) {} long y;";
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let program = parser.parse_program();
+        let outline = program
+            .items
+            .iter()
+            .map(|item| match item {
+                ExternalDeclaration::Function(function) => format!(
+                    "{}: {:?}",
+                    function.name.name,
+                    function
+                        .body
+                        .items
+                        .iter()
+                        .map(|item| match item {
+                            BlockItem::Statement(statement) => match &statement.kind {
+                                StatementKind::Return(Some(expression)) => format!(
+                                    "Return({:?}) `{}`",
+                                    expression.kind,
+                                    statement.span.span()
+                                ),
+                                kind => format!("{kind:?} `{}`", statement.span.span()),
+                            },
+                            BlockItem::Declaration(declaration) => {
+                                format!("Declaration `{}`", declaration.span.span())
+                            }
+                            BlockItem::StaticAssert(static_assert) => {
+                                format!("StaticAssert `{}`", static_assert.span.span())
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                ),
+                ExternalDeclaration::Declaration(declaration) => {
+                    format!("Declaration `{}`", declaration.span.span())
+                }
+                ExternalDeclaration::StaticAssert(static_assert) => {
+                    format!("StaticAssert `{}`", static_assert.span.span())
+                }
+                ExternalDeclaration::Error(span) => format!("Error `{}`", span.span()),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        expect![[r#"
+            main: ["Return(None) `return ;`", "Error `foo(1 2);`", "Declaration `int x = 3;`", "Return(Constant(Integer(3, Int))) `return 3;`"]
+            Error `int 4(void) {}`
+            f: []
+            Declaration `long y;`"#]]
+        .assert_eq(&outline);
+        assert_eq!(parser.errors().len(), 2);
+    }
+}
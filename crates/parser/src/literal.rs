@@ -0,0 +1,197 @@
+//! The values and types of integer, floating and character constants, worked out
+//! from their spelling (C17 6.4.4).
+//!
+//! The lexer hands over any preprocessing number (C17 6.4.8), like `1.2.3` or
+//! `0x`, so this is also where one that isn't a valid constant is reported.
+
+use crate::{ast::Literal, expression::unescape, types::Type};
+
+/// The constant `spelling` stands for, or what is wrong with it.
+pub(crate) fn parse_constant(spelling: &str) -> Result<Literal, String> {
+    if spelling.starts_with('\'') {
+        return character_constant(spelling);
+    }
+    let hex_digits = spelling
+        .strip_prefix("0x")
+        .or_else(|| spelling.strip_prefix("0X"));
+    match hex_digits {
+        Some(digits) if digits.contains(['.', 'p', 'P']) => hex_floating_constant(digits),
+        Some(digits) => integer_constant(digits, 16),
+        None if spelling.contains(['.', 'e', 'E']) => floating_constant(spelling),
+        None if spelling.starts_with('0') => integer_constant(spelling, 8),
+        None => integer_constant(spelling, 10),
+    }
+}
+
+/// An integer constant, without the `0x` of a hexadecimal one. Its type is the
+/// first that can represent it of those its suffix and base allow.
+fn integer_constant(spelling: &str, radix: u32) -> Result<Literal, String> {
+    use Type::*;
+
+    let end = spelling
+        .find(|c: char| !c.is_ascii_hexdigit() || (radix != 16 && !c.is_ascii_digit()))
+        .unwrap_or(spelling.len());
+    let (digits, suffix) = spelling.split_at(end);
+    if digits.is_empty() {
+        return Err(format!("invalid suffix `x{suffix}` on integer constant"));
+    }
+    if let Some(digit) = digits.chars().find(|c| c.to_digit(radix).is_none()) {
+        return Err(format!("invalid digit `{digit}` in octal constant"));
+    }
+    let value = u64::from_str_radix(digits, radix)
+        .map_err(|_| "integer constant is too large".to_string())?;
+    let is_decimal = radix == 10;
+    let candidates: &[Type] = match suffix {
+        "" if is_decimal => &[Int, Long, LongLong],
+        "" => &[
+            Int,
+            UnsignedInt,
+            Long,
+            UnsignedLong,
+            LongLong,
+            UnsignedLongLong,
+        ],
+        "u" | "U" => &[UnsignedInt, UnsignedLong, UnsignedLongLong],
+        "l" | "L" if is_decimal => &[Long, LongLong],
+        "l" | "L" => &[Long, UnsignedLong, LongLong, UnsignedLongLong],
+        "ul" | "uL" | "Ul" | "UL" | "lu" | "lU" | "Lu" | "LU" => &[UnsignedLong, UnsignedLongLong],
+        "ll" | "LL" if is_decimal => &[LongLong],
+        "ll" | "LL" => &[LongLong, UnsignedLongLong],
+        "ull" | "uLL" | "Ull" | "ULL" | "llu" | "llU" | "LLu" | "LLU" => &[UnsignedLongLong],
+        _ => return Err(format!("invalid suffix `{suffix}` on integer constant")),
+    };
+    let fits = |ty: &Type| match ty {
+        Int => i32::try_from(value).is_ok(),
+        UnsignedInt => u32::try_from(value).is_ok(),
+        Long | LongLong => i64::try_from(value).is_ok(),
+        _ => true,
+    };
+    match candidates.iter().find(|ty| fits(ty)) {
+        Some(ty) => Ok(Literal::Integer(value.into(), ty.clone())),
+        None => Err("integer constant is too large for any signed type".to_string()),
+    }
+}
+
+/// A decimal floating constant, which Rust's own parsing rounds correctly.
+fn floating_constant(spelling: &str) -> Result<Literal, String> {
+    let significand = spelling
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(spelling.len());
+    if spelling[..significand].matches('.').count() > 1 {
+        return Err("too many decimal points in number".to_string());
+    }
+    let end = match spelling[significand..].strip_prefix(['e', 'E']) {
+        Some(rest) => spelling.len() - split_exponent(rest)?.1.len(),
+        None => significand,
+    };
+    let (number, suffix) = spelling.split_at(end);
+    let ty = floating_suffix(suffix)?;
+    let value = match ty {
+        Type::Float => number.parse::<f32>().map(f64::from),
+        _ => number.parse::<f64>(),
+    };
+    finite(value.expect("digits with a point or an exponent parse"), ty)
+}
+
+/// A hexadecimal floating constant without its `0x`, whose binary exponent
+/// isn't optional.
+fn hex_floating_constant(spelling: &str) -> Result<Literal, String> {
+    let Some((significand, rest)) = spelling.split_once(['p', 'P']) else {
+        return Err("hexadecimal floating constant requires an exponent".to_string());
+    };
+    if significand.is_empty() || significand == "." {
+        return Err("hexadecimal floating constant has no digits".to_string());
+    }
+    let mut mantissa = 0_u128;
+    let mut exponent = 0_i64;
+    let mut is_fraction = false;
+    let mut is_inexact = false;
+    for c in significand.chars() {
+        if c == '.' && !is_fraction {
+            is_fraction = true;
+            continue;
+        }
+        let Some(digit) = c.to_digit(16) else {
+            return Err(format!(
+                "invalid digit `{c}` in hexadecimal floating constant"
+            ));
+        };
+        // Past 120 bits the digits only matter to rounding, so they are kept
+        // as one sticky bit at the bottom.
+        if mantissa >> 120 == 0 {
+            mantissa = mantissa << 4 | u128::from(digit);
+            exponent -= i64::from(is_fraction) * 4;
+        } else {
+            is_inexact |= digit != 0;
+            exponent += i64::from(!is_fraction) * 4;
+        }
+    }
+    let (power, suffix) = split_exponent(rest)?;
+    // An exponent with too many digits makes the value infinite or zero.
+    let power = power
+        .parse::<i64>()
+        .unwrap_or(match power.starts_with('-') {
+            true => i64::MIN,
+            false => i64::MAX,
+        });
+    let exponent = exponent.saturating_add(power).clamp(-10_000, 10_000) as i32;
+    let mantissa = mantissa | u128::from(is_inexact);
+    let ty = floating_suffix(suffix)?;
+    // Converting the mantissa rounds it to the precision of the type, and
+    // scaling it by a power of two only rounds again if the result is subnormal.
+    let value = match ty {
+        Type::Float => f64::from(scale(f64::from(mantissa as f32), exponent) as f32),
+        _ => scale(mantissa as f64, exponent),
+    };
+    finite(value, ty)
+}
+
+/// Split what follows the `e` or `p` of a floating constant into the exponent,
+/// with its sign, and the suffix.
+fn split_exponent(rest: &str) -> Result<(&str, &str), String> {
+    let sign = usize::from(rest.starts_with(['+', '-']));
+    let end = rest[sign..]
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(rest.len(), |end| sign + end);
+    match end == sign {
+        true => Err("exponent has no digits".to_string()),
+        false => Ok(rest.split_at(end)),
+    }
+}
+
+/// `value` times two to the power `exponent`.
+fn scale(value: f64, exponent: i32) -> f64 {
+    // Only powers of two from 2^-1022 to 2^1023 are normal, so a large exponent
+    // is applied in steps. The mantissa has at most 128 bits, so past 2^±1200
+    // the result is infinite or zero anyway.
+    let exponent = exponent.clamp(-1200, 1200);
+    let step = exponent / 2;
+    value * 2_f64.powi(step) * 2_f64.powi(exponent - step)
+}
+
+/// The type a floating constant's suffix gives it.
+fn floating_suffix(suffix: &str) -> Result<Type, String> {
+    match suffix {
+        "" => Ok(Type::Double),
+        "f" | "F" => Ok(Type::Float),
+        "l" | "L" => Err("`long double` is not supported".to_string()),
+        _ => Err(format!("invalid suffix `{suffix}` on floating constant")),
+    }
+}
+
+fn finite(value: f64, ty: Type) -> Result<Literal, String> {
+    match value.is_finite() {
+        true => Ok(Literal::Floating(value, ty)),
+        false => Err(format!("floating constant is too large for `{ty}`")),
+    }
+}
+
+/// A character constant, which has type `int` and the value of its one `char`.
+fn character_constant(spelling: &str) -> Result<Literal, String> {
+    match unescape(spelling)?[..] {
+        // `char` is signed.
+        [byte] => Ok(Literal::Integer((byte as i8).into(), Type::Int)),
+        [] => Err("empty character constant".to_string()),
+        _ => Err("multi-character character constants are not supported".to_string()),
+    }
+}
@@ -0,0 +1,843 @@
+//! Printing the AST back out as normalized C.
+//!
+//! The output is laid out in one fixed style, K&R braces and four space indents,
+//! and only has the parentheses that precedence requires, so parsing it again gives
+//! the same AST up to spans. Error nodes print as `<error>`, which doesn't parse.
+
+use std::fmt::Write;
+
+use crate::{
+    ast::{
+        BinaryOperator, Block, BlockItem, Declaration, DeclarationSpecifiers, Declarator,
+        DeclaratorKind, Designator, DesignatorKind, EnumSpecifier, Expression, ExpressionKind,
+        ExternalDeclaration, ForInit, Initializer, InitializerKind, ParameterList, Program,
+        RecordSpecifier, Statement, StatementKind, StaticAssert, TypeName, TypeSpecifier,
+    },
+    expression::{ASSIGNMENT_PRECEDENCE, COMMA_PRECEDENCE, CONDITIONAL_PRECEDENCE},
+};
+
+/// The precedence of a cast, which binds tighter than every infix operator.
+const CAST_PRECEDENCE: u8 = 14;
+/// The precedence of prefix operators and `sizeof`.
+const UNARY_PRECEDENCE: u8 = 15;
+/// The precedence of postfix operators: calls, subscripts, member accesses, `++` and `--`.
+const POSTFIX_PRECEDENCE: u8 = 16;
+/// The precedence of names, constants and string literals, which never need parentheses.
+const PRIMARY_PRECEDENCE: u8 = 17;
+
+/// Print `program` as C source, one external declaration after another.
+pub fn print_program(program: &Program) -> String {
+    let mut printer = Printer::default();
+    let is_function = |item: &ExternalDeclaration| matches!(item, ExternalDeclaration::Function(_));
+    for (i, item) in program.items.iter().enumerate() {
+        // Function definitions are set off by blank lines.
+        if i > 0 && (is_function(item) || is_function(&program.items[i - 1])) {
+            printer.output.push('\n');
+        }
+        match item {
+            ExternalDeclaration::Function(function) => {
+                printer.specifiers(&function.specifiers);
+                printer.output.push(' ');
+                printer.declarator(&function.declarator);
+                printer.output.push(' ');
+                printer.block(&function.body);
+                printer.output.push('\n');
+            }
+            ExternalDeclaration::Declaration(declaration) => {
+                printer.declaration(declaration);
+                printer.output.push('\n');
+            }
+            ExternalDeclaration::StaticAssert(static_assert) => {
+                printer.static_assert(static_assert);
+                printer.output.push('\n');
+            }
+            ExternalDeclaration::Error(_) => printer.output.push_str("<error>\n"),
+        }
+    }
+    printer.output
+}
+
+#[derive(Default)]
+struct Printer {
+    output: String,
+    indent: usize,
+}
+impl Printer {
+    fn newline(&mut self) {
+        self.output.push('\n');
+        self.output.push_str(&"    ".repeat(self.indent));
+    }
+
+    /// `<specifiers> [ <init-declarator> { "," <init-declarator> } ] ";"`
+    fn declaration(&mut self, declaration: &Declaration) {
+        self.specifiers(&declaration.specifiers);
+        for (i, declarator) in declaration.declarators.iter().enumerate() {
+            self.output.push_str(if i == 0 { " " } else { ", " });
+            self.declarator(&declarator.declarator);
+            if let Some(initializer) = &declarator.initializer {
+                self.output.push_str(" = ");
+                self.initializer(initializer);
+            }
+        }
+        self.output.push(';');
+    }
+
+    /// The storage class, then `inline`, then the qualifiers, then the type specifiers in order.
+    fn specifiers(&mut self, specifiers: &DeclarationSpecifiers) {
+        let mut separator = "";
+        if let Some(storage_class) = specifiers.storage_class {
+            write!(self.output, "{storage_class}").unwrap();
+            separator = " ";
+        }
+        if specifiers.is_inline {
+            write!(self.output, "{separator}inline").unwrap();
+            separator = " ";
+        }
+        if !specifiers.qualifiers.is_empty() {
+            write!(self.output, "{separator}{}", specifiers.qualifiers).unwrap();
+            separator = " ";
+        }
+        for specifier in &specifiers.type_specifiers {
+            self.output.push_str(separator);
+            separator = " ";
+            let keyword = match specifier {
+                TypeSpecifier::Void => "void",
+                TypeSpecifier::Char => "char",
+                TypeSpecifier::Short => "short",
+                TypeSpecifier::Int => "int",
+                TypeSpecifier::Long => "long",
+                TypeSpecifier::Float => "float",
+                TypeSpecifier::Double => "double",
+                TypeSpecifier::Signed => "signed",
+                TypeSpecifier::Unsigned => "unsigned",
+                TypeSpecifier::TypedefName(name) => &name.name,
+                TypeSpecifier::Record(record) => {
+                    self.record(record);
+                    continue;
+                }
+                TypeSpecifier::Enum(enumeration) => {
+                    self.enumeration(enumeration);
+                    continue;
+                }
+            };
+            self.output.push_str(keyword);
+        }
+    }
+
+    /// `("struct" | "union") [ <identifier> ] [ "{" { <member-declaration> } "}" ]`,
+    /// with a member on each line.
+    fn record(&mut self, record: &RecordSpecifier) {
+        write!(self.output, "{}", record.kind).unwrap();
+        if let Some(name) = &record.name {
+            write!(self.output, " {}", name.name).unwrap();
+        }
+        let Some(members) = &record.members else {
+            return;
+        };
+        self.output.push_str(" {");
+        self.indent += 1;
+        for member in members {
+            self.newline();
+            self.specifiers(&member.specifiers);
+            for (i, declarator) in member.declarators.iter().enumerate() {
+                self.output.push_str(if i == 0 { " " } else { ", " });
+                self.declarator(&declarator.declarator);
+                if let Some(width) = &declarator.bit_width {
+                    if !self.output.ends_with(' ') {
+                        self.output.push(' ');
+                    }
+                    self.output.push_str(": ");
+                    self.expression(width, CONDITIONAL_PRECEDENCE);
+                }
+            }
+            self.output.push(';');
+        }
+        self.indent -= 1;
+        self.newline();
+        self.output.push('}');
+    }
+
+    /// `"enum" [ <identifier> ] [ "{" <enumerator> { "," <enumerator> } "}" ]`, on one line.
+    fn enumeration(&mut self, enumeration: &EnumSpecifier) {
+        self.output.push_str("enum");
+        if let Some(name) = &enumeration.name {
+            write!(self.output, " {}", name.name).unwrap();
+        }
+        let Some(enumerators) = &enumeration.enumerators else {
+            return;
+        };
+        self.output.push_str(" { ");
+        for (i, enumerator) in enumerators.iter().enumerate() {
+            if i > 0 {
+                self.output.push_str(", ");
+            }
+            self.output.push_str(&enumerator.name.name);
+            if let Some(value) = &enumerator.value {
+                self.output.push_str(" = ");
+                self.expression(value, CONDITIONAL_PRECEDENCE);
+            }
+        }
+        self.output.push_str(" }");
+    }
+
+    fn designator(&mut self, designator: &Designator) {
+        match &designator.kind {
+            DesignatorKind::Index(index, _) => {
+                self.output.push('[');
+                self.expression(index, CONDITIONAL_PRECEDENCE);
+                self.output.push(']');
+            }
+            DesignatorKind::Member(name) => write!(self.output, ".{}", name.name).unwrap(),
+        }
+    }
+
+    fn declarator(&mut self, declarator: &Declarator) {
+        let declarator = self.declarator_string(declarator);
+        // Take back the space before an abstract declarator that turned out empty.
+        if declarator.is_empty() && self.output.ends_with(' ') {
+            self.output.pop();
+        }
+        self.output.push_str(&declarator);
+    }
+
+    /// Array and function suffixes bind tighter than `*`, so a pointer they apply to
+    /// is parenthesized.
+    fn declarator_string(&mut self, declarator: &Declarator) -> String {
+        let suffixed = |printer: &mut Self, inner: &Declarator| {
+            let inner_string = printer.declarator_string(inner);
+            match inner.kind {
+                DeclaratorKind::Pointer(..) => format!("({inner_string})"),
+                _ => inner_string,
+            }
+        };
+        match &declarator.kind {
+            DeclaratorKind::Identifier(name) => name
+                .as_ref()
+                .map_or_else(String::new, |name| name.name.clone()),
+            DeclaratorKind::Pointer(qualifiers, inner) => {
+                let inner = self.declarator_string(inner);
+                match (qualifiers.is_empty(), inner.is_empty()) {
+                    (true, _) => format!("*{inner}"),
+                    (false, true) => format!("*{qualifiers}"),
+                    (false, false) => format!("*{qualifiers} {inner}"),
+                }
+            }
+            DeclaratorKind::Array(inner, length) => {
+                let inner = suffixed(self, inner);
+                let length = length.as_ref().map_or_else(String::new, |length| {
+                    self.nested(|printer| printer.expression(length, ASSIGNMENT_PRECEDENCE))
+                });
+                format!("{inner}[{length}]")
+            }
+            DeclaratorKind::Function(inner, parameters) => {
+                let inner = suffixed(self, inner);
+                let parameters = self.nested(|printer| printer.parameters(parameters));
+                format!("{inner}({parameters})")
+            }
+        }
+    }
+
+    /// `"void"` for an empty prototype, and nothing for an empty list without one.
+    fn parameters(&mut self, parameters: &ParameterList) {
+        if parameters.parameters.is_empty() {
+            if parameters.is_prototype && !parameters.is_variadic {
+                self.output.push_str("void");
+            }
+            return;
+        }
+        for (i, parameter) in parameters.parameters.iter().enumerate() {
+            if i > 0 {
+                self.output.push_str(", ");
+            }
+            self.specifiers(&parameter.specifiers);
+            self.output.push(' ');
+            self.declarator(&parameter.declarator);
+        }
+        if parameters.is_variadic {
+            self.output.push_str(", ...");
+        }
+    }
+
+    /// Abstract array declarators go right after the specifiers, as in `int[]`.
+    fn type_name(&mut self, type_name: &TypeName) {
+        self.specifiers(&type_name.specifiers);
+        let declarator = self.declarator_string(&type_name.declarator);
+        if !declarator.is_empty() && !declarator.starts_with('[') {
+            self.output.push(' ');
+        }
+        self.output.push_str(&declarator);
+    }
+
+    /// Run `print` against an empty output and return what it printed.
+    fn nested(&mut self, print: impl FnOnce(&mut Self)) -> String {
+        let outer = std::mem::take(&mut self.output);
+        print(self);
+        std::mem::replace(&mut self.output, outer)
+    }
+
+    /// `"{" { <initializer-list-item> "," } "}"`, on one line.
+    fn initializer(&mut self, initializer: &Initializer) {
+        let items = match &initializer.kind {
+            InitializerKind::Expression(expression) => {
+                return self.expression(expression, ASSIGNMENT_PRECEDENCE);
+            }
+            InitializerKind::List(items) if items.is_empty() => {
+                return self.output.push_str("{}");
+            }
+            InitializerKind::List(items) => items,
+        };
+        self.output.push_str("{ ");
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.output.push_str(", ");
+            }
+            for designator in &item.designators {
+                self.designator(designator);
+            }
+            if !item.designators.is_empty() {
+                self.output.push_str(" = ");
+            }
+            self.initializer(&item.initializer);
+        }
+        self.output.push_str(" }");
+    }
+
+    fn string_literal(&mut self, bytes: &[u8]) {
+        self.output.push('"');
+        for &byte in bytes {
+            match byte {
+                b'"' => self.output.push_str("\\\""),
+                b'\\' => self.output.push_str("\\\\"),
+                b'\n' => self.output.push_str("\\n"),
+                b'\t' => self.output.push_str("\\t"),
+                b' '..=b'~' => self.output.push(byte as char),
+                // Always three digits, so a digit after the escape can't extend it.
+                byte => write!(self.output, "\\{byte:03o}").unwrap(),
+            }
+        }
+        self.output.push('"');
+    }
+
+    /// `_Static_assert ( <exp> , <string-literal> ) ;`
+    fn static_assert(&mut self, static_assert: &StaticAssert) {
+        self.output.push_str("_Static_assert(");
+        self.expression(&static_assert.condition, CONDITIONAL_PRECEDENCE);
+        self.output.push_str(", ");
+        self.string_literal(&static_assert.message);
+        self.output.push_str(");");
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.output.push('{');
+        self.indent += 1;
+        for item in &block.items {
+            self.newline();
+            match item {
+                BlockItem::Declaration(declaration) => self.declaration(declaration),
+                BlockItem::StaticAssert(static_assert) => self.static_assert(static_assert),
+                BlockItem::Statement(statement) => self.statement(statement),
+            }
+        }
+        self.indent -= 1;
+        self.newline();
+        self.output.push('}');
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match &statement.kind {
+            StatementKind::Return(None) => self.output.push_str("return;"),
+            StatementKind::Return(Some(value)) => {
+                self.output.push_str("return ");
+                self.expression(value, COMMA_PRECEDENCE);
+                self.output.push(';');
+            }
+            StatementKind::Expression(expression) => {
+                self.expression(expression, COMMA_PRECEDENCE);
+                self.output.push(';');
+            }
+            StatementKind::Null => self.output.push(';'),
+            StatementKind::Compound(block) => self.block(block),
+            StatementKind::If(condition, then, els) => {
+                self.output.push_str("if (");
+                self.expression(condition, COMMA_PRECEDENCE);
+                self.output.push(')');
+                self.body(then);
+                if let Some(els) = els {
+                    match then.kind {
+                        StatementKind::Compound(_) => self.output.push(' '),
+                        _ => self.newline(),
+                    }
+                    self.output.push_str("else");
+                    match els.kind {
+                        // Keep `else if` chains flat.
+                        StatementKind::If(..) => {
+                            self.output.push(' ');
+                            self.statement(els);
+                        }
+                        _ => self.body(els),
+                    }
+                }
+            }
+            StatementKind::While(condition, body) => {
+                self.output.push_str("while (");
+                self.expression(condition, COMMA_PRECEDENCE);
+                self.output.push(')');
+                self.body(body);
+            }
+            StatementKind::DoWhile(body, condition) => {
+                self.output.push_str("do");
+                self.body(body);
+                match body.kind {
+                    StatementKind::Compound(_) => self.output.push(' '),
+                    _ => self.newline(),
+                }
+                self.output.push_str("while (");
+                self.expression(condition, COMMA_PRECEDENCE);
+                self.output.push_str(");");
+            }
+            StatementKind::For(init, condition, step, body) => {
+                self.output.push_str("for (");
+                match &**init {
+                    ForInit::Declaration(declaration) => self.declaration(declaration),
+                    ForInit::Expression(expression) => {
+                        if let Some(expression) = expression {
+                            self.expression(expression, COMMA_PRECEDENCE);
+                        }
+                        self.output.push(';');
+                    }
+                }
+                if let Some(condition) = condition {
+                    self.output.push(' ');
+                    self.expression(condition, COMMA_PRECEDENCE);
+                }
+                self.output.push(';');
+                if let Some(step) = step {
+                    self.output.push(' ');
+                    self.expression(step, COMMA_PRECEDENCE);
+                }
+                self.output.push(')');
+                self.body(body);
+            }
+            StatementKind::Break => self.output.push_str("break;"),
+            StatementKind::Continue => self.output.push_str("continue;"),
+            StatementKind::Switch(condition, body) => {
+                self.output.push_str("switch (");
+                self.expression(condition, COMMA_PRECEDENCE);
+                self.output.push(')');
+                self.body(body);
+            }
+            StatementKind::Case(expression, _, body) => {
+                let expression =
+                    self.nested(|printer| printer.expression(expression, CONDITIONAL_PRECEDENCE));
+                self.label(format_args!("case {expression}:"), body);
+            }
+            StatementKind::Default(body) => {
+                self.label(format_args!("default:"), body);
+            }
+            StatementKind::Goto(label) => write!(self.output, "goto {};", label.name).unwrap(),
+            StatementKind::Labeled(label, body) => {
+                self.label(format_args!("{}:", label.name), body);
+            }
+            StatementKind::Error => self.output.push_str("<error>;"),
+        }
+    }
+
+    /// The body of a control statement: a block on the same line, anything else indented
+    /// on the next.
+    fn body(&mut self, body: &Statement) {
+        if let StatementKind::Compound(block) = &body.kind {
+            self.output.push(' ');
+            return self.block(block);
+        }
+        self.indent += 1;
+        self.newline();
+        self.statement(body);
+        self.indent -= 1;
+    }
+
+    /// Labels sit a level out from the statements around them, with the statement they
+    /// label on the next line.
+    fn label(&mut self, label: std::fmt::Arguments, body: &Statement) {
+        if self.indent > 0 && self.output.ends_with("    ") {
+            self.output.truncate(self.output.len() - 4);
+        }
+        self.output.write_fmt(label).unwrap();
+        self.newline();
+        self.statement(body);
+    }
+
+    /// Print `expression`, parenthesized if it binds looser than `min_precedence`.
+    fn expression(&mut self, expression: &Expression, min_precedence: u8) {
+        let parenthesize = precedence(expression) < min_precedence;
+        if parenthesize {
+            self.output.push('(');
+        }
+        match &expression.kind {
+            ExpressionKind::Constant(value) => write!(self.output, "{value}").unwrap(),
+            ExpressionKind::Identifier(name) => self.output.push_str(&name.name),
+            ExpressionKind::Unary(operator, operand) => {
+                let operator = operator.to_string();
+                self.output.push_str(&operator);
+                let operand = self.nested(|printer| {
+                    let min_precedence = match operator.as_str() {
+                        "++" | "--" => UNARY_PRECEDENCE,
+                        _ => CAST_PRECEDENCE,
+                    };
+                    printer.expression(operand, min_precedence);
+                });
+                // `- -x`, `+ ++x` and `& &x` mustn't run together into other tokens.
+                let last = operator.chars().last();
+                if matches!(last, Some('+' | '-' | '&')) && operand.starts_with(last.unwrap()) {
+                    self.output.push(' ');
+                }
+                self.output.push_str(&operand);
+            }
+            ExpressionKind::Postfix(operator, operand) => {
+                self.expression(operand, POSTFIX_PRECEDENCE);
+                write!(self.output, "{operator}").unwrap();
+            }
+            ExpressionKind::Binary(operator, lhs, rhs) => {
+                let precedence = binary_precedence(*operator);
+                self.expression(lhs, precedence);
+                match operator {
+                    BinaryOperator::Comma => self.output.push_str(", "),
+                    operator => write!(self.output, " {operator} ").unwrap(),
+                }
+                self.expression(rhs, precedence + 1);
+            }
+            ExpressionKind::Assignment(operator, lhs, rhs) => {
+                self.expression(lhs, UNARY_PRECEDENCE);
+                write!(self.output, " {operator} ").unwrap();
+                self.expression(rhs, ASSIGNMENT_PRECEDENCE);
+            }
+            ExpressionKind::Conditional(condition, then, els) => {
+                self.expression(condition, CONDITIONAL_PRECEDENCE + 1);
+                self.output.push_str(" ? ");
+                self.expression(then, COMMA_PRECEDENCE);
+                self.output.push_str(" : ");
+                self.expression(els, CONDITIONAL_PRECEDENCE);
+            }
+            ExpressionKind::StringLiteral(bytes) => self.string_literal(bytes),
+            ExpressionKind::Cast(type_name, operand) => {
+                self.output.push('(');
+                self.type_name(type_name);
+                self.output.push(')');
+                self.expression(operand, CAST_PRECEDENCE);
+            }
+            ExpressionKind::CompoundLiteral(literal) => {
+                self.output.push('(');
+                self.type_name(&literal.type_name);
+                self.output.push(')');
+                self.initializer(&literal.initializer);
+            }
+            ExpressionKind::SizeofExpression(operand) => {
+                self.output.push_str("sizeof ");
+                self.expression(operand, UNARY_PRECEDENCE);
+            }
+            ExpressionKind::SizeofType(type_name) => {
+                self.output.push_str("sizeof(");
+                self.type_name(type_name);
+                self.output.push(')');
+            }
+            ExpressionKind::Offsetof(type_name, designators, _) => {
+                self.output.push_str("__builtin_offsetof(");
+                self.type_name(type_name);
+                self.output.push_str(", ");
+                // The first designator is the member's bare name.
+                for (i, designator) in designators.iter().enumerate() {
+                    match &designator.kind {
+                        DesignatorKind::Member(name) if i == 0 => self.output.push_str(&name.name),
+                        _ => self.designator(designator),
+                    }
+                }
+                self.output.push(')');
+            }
+            ExpressionKind::Call(callee, arguments) => {
+                self.expression(callee, POSTFIX_PRECEDENCE);
+                self.output.push('(');
+                for (i, argument) in arguments.iter().enumerate() {
+                    if i > 0 {
+                        self.output.push_str(", ");
+                    }
+                    self.expression(argument, ASSIGNMENT_PRECEDENCE);
+                }
+                self.output.push(')');
+            }
+            ExpressionKind::Subscript(base, index) => {
+                self.expression(base, POSTFIX_PRECEDENCE);
+                self.output.push('[');
+                self.expression(index, COMMA_PRECEDENCE);
+                self.output.push(']');
+            }
+            ExpressionKind::Member(base, member) => {
+                self.expression(base, POSTFIX_PRECEDENCE);
+                write!(self.output, ".{}", member.name).unwrap();
+            }
+            ExpressionKind::PointerMember(base, member) => {
+                self.expression(base, POSTFIX_PRECEDENCE);
+                write!(self.output, "->{}", member.name).unwrap();
+            }
+            // Implicit conversions stay implicit, and take the precedence of their
+            // operand, so whatever parentheses it needs are already in place.
+            ExpressionKind::ImplicitConversion(_, operand) => self.expression(operand, 0),
+            ExpressionKind::Error => self.output.push_str("<error>"),
+        }
+        if parenthesize {
+            self.output.push(')');
+        }
+    }
+}
+
+fn precedence(expression: &Expression) -> u8 {
+    match &expression.kind {
+        ExpressionKind::Binary(operator, ..) => binary_precedence(*operator),
+        ExpressionKind::Assignment(..) => ASSIGNMENT_PRECEDENCE,
+        ExpressionKind::Conditional(..) => CONDITIONAL_PRECEDENCE,
+        ExpressionKind::Cast(..) => CAST_PRECEDENCE,
+        ExpressionKind::ImplicitConversion(_, operand) => precedence(operand),
+        ExpressionKind::Unary(..)
+        | ExpressionKind::SizeofExpression(_)
+        | ExpressionKind::SizeofType(_) => UNARY_PRECEDENCE,
+        ExpressionKind::Postfix(..)
+        | ExpressionKind::Call(..)
+        | ExpressionKind::Subscript(..)
+        | ExpressionKind::Member(..)
+        | ExpressionKind::PointerMember(..)
+        | ExpressionKind::CompoundLiteral(_) => POSTFIX_PRECEDENCE,
+        ExpressionKind::Constant(_)
+        | ExpressionKind::Identifier(_)
+        | ExpressionKind::StringLiteral(_)
+        | ExpressionKind::Offsetof(..)
+        | ExpressionKind::Error => PRIMARY_PRECEDENCE,
+    }
+}
+
+fn binary_precedence(operator: BinaryOperator) -> u8 {
+    use BinaryOperator::*;
+    match operator {
+        Comma => COMMA_PRECEDENCE,
+        LogicalOr => 4,
+        LogicalAnd => 5,
+        BitwiseOr => 6,
+        BitwiseXor => 7,
+        BitwiseAnd => 8,
+        Equal | NotEqual => 9,
+        LessThan | GreaterThan | LessOrEqual | GreaterOrEqual => 10,
+        ShiftLeft | ShiftRight => 11,
+        Add | Subtract => 12,
+        Multiply | Divide | Remainder => 13,
+    }
+}
+
+#[cfg(test)]
+mod print_tests {
+    use expect_test::{expect, Expect};
+    use wacc_lexer::Lexer;
+
+    use super::print_program;
+    use crate::{
+        dump::{dump_program, DumpFormat},
+        Parser,
+    };
+
+    /// Parse `src`, print it as C, and check that the printed source parses to the
+    /// same AST and prints the same again.
+    fn round_trip(src: &str) -> String {
+        let parse = |src: &str| {
+            let tokens = String::lex_c(src);
+            let mut parser = Parser::new(src, &tokens);
+            let program = parser.parse_program();
+            assert!(parser.errors().is_empty(), "{src}\n{:?}", parser.errors());
+            program
+        };
+        let original = parse(src);
+        let printed = print_program(&original);
+        let reparsed = parse(&printed);
+        assert_eq!(
+            dump_program(&original, DumpFormat::Sexpr),
+            dump_program(&reparsed, DumpFormat::Sexpr),
+            "{src}\nprinted as\n{printed}"
+        );
+        assert_eq!(print_program(&reparsed), printed);
+        printed
+    }
+
+    fn check_print(src: &str, expect: Expect) {
+        expect.assert_eq(&round_trip(src));
+    }
+
+    #[test]
+    fn test_declarations() {
+        check_print(
+            r#"
+            typedef struct node { int value; struct node *next; unsigned flags : 3, : 0; } node;
+            typedef int (*callback)(node *, void *);
+            enum colour { RED, GREEN = 2 + 1, BLUE, };
+            const volatile int *const table[2][3] = { [0] = { 1, 2 }, { 3 } }, count;
+            int (*pick(int which))(void);
+            int printf(const char *format, ...), old();
+            char greeting[] = "hi\n\"there\"\1" "7";
+            "#,
+            expect![[r#"
+                typedef struct node {
+                    int value;
+                    struct node *next;
+                    unsigned flags : 3, : 0;
+                } node;
+                typedef int (*callback)(node *, void *);
+                enum colour { RED, GREEN = 2 + 1, BLUE };
+                const volatile int *const table[2][3] = { [0] = { 1, 2 }, { 3 } }, count;
+                int (*pick(int which))(void);
+                int printf(const char *format, ...), old();
+                char greeting[] = "hi\n\"there\"\0017";
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_statements() {
+        check_print(
+            r#"
+            int walk(int *list, int n) {
+                int count = 0, *p = &count;
+                for (int i = 0; i < n; i++) if (list[i]) continue; else { count++; }
+                for (;;) break;
+                while (n) n--;
+                do --count; while (count > 10);
+                if (n) if (count) n = 1; else n = 2;
+                else if (p) n = 3;
+                switch (count) { case 1: case 2: n++; break; default: ; }
+                goto done;
+            done:
+                return count;
+            }
+            "#,
+            expect![[r#"
+                int walk(int *list, int n) {
+                    int count = 0, *p = &count;
+                    for (int i = 0; i < n; i++)
+                        if (list[i])
+                            continue;
+                        else {
+                            count++;
+                        }
+                    for (;;)
+                        break;
+                    while (n)
+                        n--;
+                    do
+                        --count;
+                    while (count > 10);
+                    if (n)
+                        if (count)
+                            n = 1;
+                        else
+                            n = 2;
+                    else if (p)
+                        n = 3;
+                    switch (count) {
+                    case 1:
+                    case 2:
+                        n++;
+                        break;
+                    default:
+                        ;
+                    }
+                    goto done;
+                done:
+                    return count;
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_expressions() {
+        check_print(
+            r#"
+            struct point { int x, y; };
+            int f(int a, int b, struct point *p) {
+                a = b = (a, b);
+                a = (a + b) * (a - b) - a - (b - a) / -(-a) - - --b + +(+a);
+                a = !a && b || a && !(b || a) ? a ? 1 : 2 : (a = 3);
+                a = (long) (unsigned) -a + sizeof a + sizeof (a + 1) + sizeof (int *[3]);
+                a = (&a)[0] + *&a + & *p + p->x + (*p).y + (int[]){ 1, 2 }[1];
+                return f((a, b), b += 1, (struct point){ .y = 1 }.y ? p : 0);
+            }
+            "#,
+            expect![[r#"
+                struct point {
+                    int x, y;
+                };
+
+                int f(int a, int b, struct point *p) {
+                    a = b = (a, b);
+                    a = (a + b) * (a - b) - a - (b - a) / - -a - - --b + + +a;
+                    a = !a && b || a && !(b || a) ? a ? 1 : 2 : (a = 3);
+                    a = (long)(unsigned)-a + sizeof a + sizeof (a + 1) + sizeof(int *[3]);
+                    a = (&a)[0] + *&a + &*p + p->x + (*p).y + (int[]){ 1, 2 }[1];
+                    return f((a, b), b += 1, (struct point){ .y = 1 }.y ? p : 0);
+                }
+            "#]],
+        );
+    }
+
+    /// A random expression over `a`, `b` and `p`, fully parenthesized so that
+    /// whatever the printer drops is decided by precedence alone.
+    fn random_expression(seed: &mut u64, depth: u32) -> String {
+        let mut next = |n: u64| {
+            *seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (*seed >> 33) % n
+        };
+        if depth == 0 {
+            return ["a", "b", "1", "2", "p[0]", "*p"][next(6) as usize].to_string();
+        }
+        const BINARY: &[&str] = &[
+            "*", "/", "%", "+", "-", "<<", ">>", "<", ">", "<=", ">=", "==", "!=", "&", "^", "|",
+            "&&", "||", ",",
+        ];
+        const ASSIGNMENT: &[&str] = &["=", "+=", "-=", "*=", "<<=", "|="];
+        const PREFIX: &[&str] = &["-", "+", "~", "!", "++", "--", "&", "sizeof ", "(long)"];
+        let choice = next(5);
+        let operator = next(19) as usize;
+        match choice {
+            0 | 1 => {
+                let lhs = random_expression(seed, depth - 1);
+                let rhs = random_expression(seed, depth - 1);
+                format!("({lhs} {} {rhs})", BINARY[operator])
+            }
+            2 => {
+                let condition = random_expression(seed, depth - 1);
+                let then = random_expression(seed, depth - 1);
+                let els = random_expression(seed, depth - 1);
+                format!("({condition} ? {then} : {els})")
+            }
+            3 => {
+                let lhs = ["a", "b", "p[1]", "*p"][operator % 4];
+                let rhs = random_expression(seed, depth - 1);
+                format!("({lhs} {} {rhs})", ASSIGNMENT[operator % ASSIGNMENT.len()])
+            }
+            _ => match PREFIX[operator % PREFIX.len()] {
+                "++" | "--" | "&" => format!(
+                    "({}{})",
+                    PREFIX[operator % PREFIX.len()],
+                    ["a", "b"][operator % 2]
+                ),
+                prefix => format!("({prefix}{})", random_expression(seed, depth - 1)),
+            },
+        }
+    }
+
+    #[test]
+    fn test_random_expressions() {
+        let mut seed = 0x5eed;
+        for _ in 0..500 {
+            let expression = random_expression(&mut seed, 4);
+            round_trip(&format!(
+                "long f(long a, long b, long *p) {{ return {expression}; }}"
+            ));
+        }
+    }
+}
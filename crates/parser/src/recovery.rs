@@ -0,0 +1,165 @@
+//! Error recovery by synchronization.
+//!
+//! When a statement or top-level declaration fails to parse, the parser skips ahead
+//! to the next synchronization point: just past a `;`, before the `}` closing the
+//! enclosing block, or before the start of the next declaration. Groups opened
+//! while skipping (`(...)`, `[...]`, `{...}`) are skipped whole, so the tokens
+//! inside them are never mistaken for synchronization points. A malformed `for`
+//! loop header is skipped up to its `)` instead, since it has `;`s of its own.
+
+use wacc_lexer::c_token::{c_symbol::SymbolKind, CTokenKind};
+
+use crate::{
+    ast::{Statement, StatementKind},
+    ParseError, Parser,
+};
+
+fn opens_group(kind: CTokenKind) -> bool {
+    matches!(
+        kind,
+        CTokenKind::Punctuator(
+            SymbolKind::OpenParenthesis
+                | SymbolKind::OpenSquareBracket
+                | SymbolKind::OpenCurlyBrace
+        )
+    )
+}
+
+fn closes_group(kind: CTokenKind) -> bool {
+    matches!(
+        kind,
+        CTokenKind::Punctuator(
+            SymbolKind::CloseParenthesis
+                | SymbolKind::CloseSquareBracket
+                | SymbolKind::CloseCurlyBrace
+        )
+    )
+}
+
+impl Parser<'_> {
+    /// Record a syntax error, unless it is a follow-on error at the same token as
+    /// the last one, which almost always stems from the same mistake.
+    pub(crate) fn report(&mut self, err: ParseError) {
+        if self.last_error == Some(self.pos) {
+            return;
+        }
+        self.last_error = Some(self.pos);
+        self.errors.push(err);
+    }
+
+    /// Report a malformed statement that began at token `start`, skip to its end,
+    /// and return an error node to stand in for it.
+    pub(crate) fn recover_statement(&mut self, start: usize, err: ParseError) -> Statement {
+        self.report(err);
+        self.synchronize_statement(start);
+        Statement {
+            kind: StatementKind::Error,
+            span: self.span_from(start),
+        }
+    }
+
+    /// Skip to the end of a malformed statement that began at token `start`.
+    pub(crate) fn synchronize_statement(&mut self, start: usize) {
+        let mut depth = 0_usize;
+        while let Some(kind) = self.peek() {
+            match kind {
+                CTokenKind::Punctuator(SymbolKind::Semicolon) if depth == 0 => {
+                    self.pos += 1;
+                    return;
+                }
+                CTokenKind::Punctuator(SymbolKind::CloseCurlyBrace) if depth == 0 => return,
+                _ if depth == 0 && self.pos > start && self.is_declaration_specifier_at(0) => {
+                    return
+                }
+                kind if opens_group(kind) => depth += 1,
+                kind if closes_group(kind) && depth > 0 => {
+                    depth -= 1;
+                    // A skipped block is as good as a `;` for ending the statement.
+                    if depth == 0 && kind == CTokenKind::Punctuator(SymbolKind::CloseCurlyBrace) {
+                        self.pos += 1;
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Skip to just past the `)` closing a malformed `for` loop header that
+    /// began at token `start`, just after its `(`. Without a `)`, the header
+    /// ends before the `{` of the body or the `}` closing the enclosing block.
+    ///
+    /// Only the header itself has `;`s, so any group still open at one was
+    /// never closed.
+    pub(crate) fn synchronize_for_header(&mut self, start: usize) {
+        let mut depth = self.tokens[start..self.pos]
+            .iter()
+            .filter_map(|token| token.kind())
+            .fold(1_usize, |depth, kind| match kind {
+                CTokenKind::Punctuator(SymbolKind::Semicolon) => 1,
+                kind if opens_group(kind) => depth + 1,
+                kind if closes_group(kind) => depth.saturating_sub(1),
+                _ => depth,
+            });
+        if depth == 0 {
+            return;
+        }
+        while let Some(kind) = self.peek() {
+            match kind {
+                CTokenKind::Punctuator(
+                    SymbolKind::OpenCurlyBrace | SymbolKind::CloseCurlyBrace,
+                ) if depth == 1 => return,
+                CTokenKind::Punctuator(SymbolKind::Semicolon) => depth = 1,
+                kind if opens_group(kind) => depth += 1,
+                kind if closes_group(kind) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos += 1;
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Skip to the end of a malformed top-level declaration that began at token `start`.
+    ///
+    /// Unlike statements, the error may be deep inside a declarator's parameter
+    /// list, so groups already opened since `start` are skipped too.
+    pub(crate) fn synchronize_external_declaration(&mut self, start: usize) {
+        let mut depth = self.tokens[start..self.pos]
+            .iter()
+            .filter_map(|token| token.kind())
+            .fold(0_usize, |depth, kind| match kind {
+                kind if opens_group(kind) => depth + 1,
+                kind if closes_group(kind) => depth.saturating_sub(1),
+                _ => depth,
+            });
+        while let Some(kind) = self.peek() {
+            match kind {
+                CTokenKind::Punctuator(SymbolKind::Semicolon) if depth == 0 => {
+                    self.pos += 1;
+                    return;
+                }
+                _ if depth == 0 && self.pos > start && self.is_declaration_specifier_at(0) => {
+                    return
+                }
+                kind if opens_group(kind) => depth += 1,
+                kind if closes_group(kind) => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 && kind == CTokenKind::Punctuator(SymbolKind::CloseCurlyBrace) {
+                        // A function body ends the declaration, as does a `;` after a struct body.
+                        self.pos += 1;
+                        self.eat(CTokenKind::Punctuator(SymbolKind::Semicolon));
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! The scoped tables of ordinary identifiers and tags.
+//!
+//! Whether `T * x;` declares a pointer or multiplies two values depends on whether
+//! `T` names a type at that point, which the lexer can't know. So the parser
+//! reclassifies identifiers itself: every declared name is recorded in the
+//! innermost scope, either as a typedef, an enumeration constant, or as an
+//! ordinary identifier that shadows any typedef of the same name from an
+//! enclosing scope.
+//!
+//! Struct, union and enum tags live in a namespace of their own, scoped the same way.
+
+use std::collections::HashMap;
+
+use crate::types::{QualifiedType, Tag};
+
+/// What an ordinary identifier was declared as.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Binding {
+    Typedef(QualifiedType),
+    EnumConstant(i64),
+    /// A variable, function or parameter.
+    Ordinary,
+}
+
+#[derive(Default)]
+struct Scope {
+    names: HashMap<String, Binding>,
+    tags: HashMap<String, Tag>,
+}
+
+pub(crate) struct Scopes {
+    /// The file scope first, then each nested block or prototype scope.
+    scopes: Vec<Scope>,
+}
+impl Scopes {
+    pub(crate) fn new() -> Self {
+        Self {
+            scopes: vec![Scope::default()],
+        }
+    }
+    pub(crate) fn push(&mut self) {
+        self.scopes.push(Scope::default());
+    }
+    pub(crate) fn pop(&mut self) {
+        assert!(self.scopes.len() > 1, "the file scope is never popped");
+        self.scopes.pop();
+    }
+    pub(crate) fn is_file_scope(&self) -> bool {
+        self.scopes.len() == 1
+    }
+    fn innermost(&mut self) -> &mut Scope {
+        self.scopes
+            .last_mut()
+            .expect("the file scope is never popped")
+    }
+    /// Declare `name` in the innermost scope.
+    pub(crate) fn declare(&mut self, name: &str, binding: Binding) {
+        self.innermost().names.insert(name.to_string(), binding);
+    }
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.names.get(name))
+    }
+    /// The type named by `name`, if its innermost declaration is a typedef.
+    pub(crate) fn typedef(&self, name: &str) -> Option<&QualifiedType> {
+        match self.lookup(name) {
+            Some(Binding::Typedef(ty)) => Some(ty),
+            _ => None,
+        }
+    }
+    /// The value of `name`, if its innermost declaration is an enumeration constant.
+    pub(crate) fn enum_constant(&self, name: &str) -> Option<i64> {
+        match self.lookup(name) {
+            Some(Binding::EnumConstant(value)) => Some(*value),
+            _ => None,
+        }
+    }
+    /// Declare the tag `name` in the innermost scope.
+    pub(crate) fn declare_tag(&mut self, name: &str, tag: Tag) {
+        self.innermost().tags.insert(name.to_string(), tag);
+    }
+    /// The tag `name` declared in the innermost scope, ignoring enclosing scopes.
+    pub(crate) fn innermost_tag(&self, name: &str) -> Option<&Tag> {
+        self.scopes.last()?.tags.get(name)
+    }
+    /// The visible declaration of the tag `name`.
+    pub(crate) fn tag(&self, name: &str) -> Option<&Tag> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.tags.get(name))
+    }
+}
@@ -0,0 +1,459 @@
+//! Statement parsing.
+//!
+//! Selection and iteration statements, and each statement they contain, are
+//! blocks of their own in C17, so anything they declare (say, in a `for`
+//! initializer or a `sizeof(enum { A })` condition) goes out of scope with them.
+
+use wacc_lexer::c_token::{c_keyword::KeywordKind, c_symbol::SymbolKind, CTokenKind};
+
+use crate::{
+    ast::{Expression, ForInit, Statement, StatementKind, StorageClass},
+    expression::CONDITIONAL_PRECEDENCE,
+    ParseError, Parser,
+};
+
+impl Parser<'_> {
+    /// ```text
+    /// <statement> ::= "return" [ <exp> ] ";"
+    ///               | <exp> ";"
+    ///               | ";"
+    ///               | <block>
+    ///               | "if" "(" <exp> ")" <statement> [ "else" <statement> ]
+    ///               | "while" "(" <exp> ")" <statement>
+    ///               | "do" <statement> "while" "(" <exp> ")" ";"
+    ///               | "for" "(" <for-init> [ <exp> ] ";" [ <exp> ] ")" <statement>
+    ///               | "break" ";" | "continue" ";"
+    ///               | "switch" "(" <exp> ")" <statement>
+    ///               | "case" <constant-exp> ":" <statement>
+    ///               | "default" ":" <statement>
+    ///               | "goto" <identifier> ";"
+    ///               | <identifier> ":" <statement>
+    /// ```
+    pub(crate) fn parse_statement(&mut self) -> Result<Statement, ParseError> {
+        let start = self.peek_span();
+        let kind = match self.peek() {
+            Some(CTokenKind::Keyword(KeywordKind::Return)) => {
+                self.pos += 1;
+                let value = match self.check(CTokenKind::Punctuator(SymbolKind::Semicolon)) {
+                    true => None,
+                    false => Some(self.parse_expression()?),
+                };
+                self.expect(CTokenKind::Punctuator(SymbolKind::Semicolon))?;
+                StatementKind::Return(value)
+            }
+            Some(CTokenKind::Punctuator(SymbolKind::Semicolon)) => {
+                self.pos += 1;
+                StatementKind::Null
+            }
+            Some(CTokenKind::Punctuator(SymbolKind::OpenCurlyBrace)) => {
+                StatementKind::Compound(self.parse_block()?)
+            }
+            Some(CTokenKind::Keyword(
+                KeywordKind::If
+                | KeywordKind::While
+                | KeywordKind::Do
+                | KeywordKind::For
+                | KeywordKind::Switch,
+            )) => self.in_scope(Self::parse_selection_or_iteration)?,
+            Some(CTokenKind::Keyword(KeywordKind::Break)) => {
+                self.pos += 1;
+                self.expect(CTokenKind::Punctuator(SymbolKind::Semicolon))?;
+                StatementKind::Break
+            }
+            Some(CTokenKind::Keyword(KeywordKind::Continue)) => {
+                self.pos += 1;
+                self.expect(CTokenKind::Punctuator(SymbolKind::Semicolon))?;
+                StatementKind::Continue
+            }
+            Some(CTokenKind::Keyword(KeywordKind::Case)) => {
+                self.pos += 1;
+                let label = self.parse_expression_with_precedence(CONDITIONAL_PRECEDENCE)?;
+                let value = self
+                    .integer_constant(&label)
+                    .map_err(|err| err.into_error("case label is not an integer constant"))?;
+                self.expect(CTokenKind::Punctuator(SymbolKind::Colon))?;
+                StatementKind::Case(label, value, Box::new(self.parse_statement_or_recover()))
+            }
+            Some(CTokenKind::Keyword(KeywordKind::Default)) => {
+                self.pos += 1;
+                self.expect(CTokenKind::Punctuator(SymbolKind::Colon))?;
+                StatementKind::Default(Box::new(self.parse_statement_or_recover()))
+            }
+            Some(CTokenKind::Keyword(KeywordKind::Goto)) => {
+                self.pos += 1;
+                let label = self.parse_identifier()?;
+                self.expect(CTokenKind::Punctuator(SymbolKind::Semicolon))?;
+                StatementKind::Goto(label)
+            }
+            Some(CTokenKind::Identifier) if self.is_label_at(0) => {
+                let label = self.parse_identifier()?;
+                self.expect(CTokenKind::Punctuator(SymbolKind::Colon))?;
+                StatementKind::Labeled(label, Box::new(self.parse_statement_or_recover()))
+            }
+            _ => {
+                let expression = self.parse_expression()?;
+                self.expect(CTokenKind::Punctuator(SymbolKind::Semicolon))?;
+                StatementKind::Expression(expression)
+            }
+        };
+
+        Ok(Statement {
+            kind,
+            span: start.to(&self.prev_span()),
+        })
+    }
+
+    /// Whether the token `n` ahead starts a label, `<identifier> ":"`.
+    ///
+    /// Labels have a namespace of their own, so even a typedef name can be one.
+    pub(crate) fn is_label_at(&self, n: usize) -> bool {
+        self.peek_nth(n) == Some(CTokenKind::Identifier)
+            && self.peek_nth(n + 1) == Some(CTokenKind::Punctuator(SymbolKind::Colon))
+    }
+
+    /// Parse a statement nested in another, replacing it with [`StatementKind::Error`]
+    /// if it is malformed so that the enclosing statement is kept.
+    fn parse_statement_or_recover(&mut self) -> Statement {
+        let start = self.pos;
+        self.parse_statement()
+            .unwrap_or_else(|err| self.recover_statement(start, err))
+    }
+
+    /// The body of a selection or iteration statement, a block in its own right.
+    fn parse_secondary_block(&mut self) -> Box<Statement> {
+        Box::new(self.in_scope(Self::parse_statement_or_recover))
+    }
+
+    /// Parse a selection or iteration statement, which has already been put in a scope of its own.
+    fn parse_selection_or_iteration(&mut self) -> Result<StatementKind, ParseError> {
+        let keyword = self.peek();
+        self.pos += 1;
+        Ok(match keyword {
+            Some(CTokenKind::Keyword(KeywordKind::If)) => {
+                let condition = self.parse_condition()?;
+                let then = self.parse_secondary_block();
+                let els = self
+                    .eat(CTokenKind::Keyword(KeywordKind::Else))
+                    .map(|_| self.parse_secondary_block());
+                StatementKind::If(condition, then, els)
+            }
+            Some(CTokenKind::Keyword(KeywordKind::While)) => {
+                let condition = self.parse_condition()?;
+                StatementKind::While(condition, self.parse_secondary_block())
+            }
+            Some(CTokenKind::Keyword(KeywordKind::Do)) => {
+                let body = self.parse_secondary_block();
+                self.expect(CTokenKind::Keyword(KeywordKind::While))?;
+                let condition = self.parse_condition()?;
+                self.expect(CTokenKind::Punctuator(SymbolKind::Semicolon))?;
+                StatementKind::DoWhile(body, condition)
+            }
+            Some(CTokenKind::Keyword(KeywordKind::For)) => {
+                self.expect(CTokenKind::Punctuator(SymbolKind::OpenParenthesis))?;
+                let start = self.pos;
+                self.parse_for_loop().unwrap_or_else(|err| {
+                    // Skipping to the next `;` would land inside the header, so skip
+                    // the whole header instead, and still check the body.
+                    self.report(err);
+                    self.synchronize_for_header(start);
+                    self.parse_secondary_block();
+                    StatementKind::Error
+                })
+            }
+            Some(CTokenKind::Keyword(KeywordKind::Switch)) => {
+                let condition = self.parse_condition()?;
+                StatementKind::Switch(condition, self.parse_secondary_block())
+            }
+            _ => unreachable!("only called at a selection or iteration statement"),
+        })
+    }
+
+    /// `"(" <exp> ")"`
+    fn parse_condition(&mut self) -> Result<Expression, ParseError> {
+        self.expect(CTokenKind::Punctuator(SymbolKind::OpenParenthesis))?;
+        let condition = self.parse_expression()?;
+        self.expect(CTokenKind::Punctuator(SymbolKind::CloseParenthesis))?;
+        Ok(condition)
+    }
+
+    /// The rest of a `for` loop after the `(`:
+    /// `<for-init> [ <exp> ] ";" [ <exp> ] ")" <statement>`
+    fn parse_for_loop(&mut self) -> Result<StatementKind, ParseError> {
+        let init = self.parse_for_init()?;
+        let condition = match self.check(CTokenKind::Punctuator(SymbolKind::Semicolon)) {
+            true => None,
+            false => Some(Box::new(self.parse_expression()?)),
+        };
+        self.expect(CTokenKind::Punctuator(SymbolKind::Semicolon))?;
+        let post = match self.check(CTokenKind::Punctuator(SymbolKind::CloseParenthesis)) {
+            true => None,
+            false => Some(Box::new(self.parse_expression()?)),
+        };
+        self.expect(CTokenKind::Punctuator(SymbolKind::CloseParenthesis))?;
+        Ok(StatementKind::For(
+            Box::new(init),
+            condition,
+            post,
+            self.parse_secondary_block(),
+        ))
+    }
+
+    /// `<for-init> ::= <declaration> | [ <exp> ] ";"`
+    fn parse_for_init(&mut self) -> Result<ForInit, ParseError> {
+        if self.is_declaration_specifier_at(0) {
+            let declaration = self.parse_declaration()?;
+            // The rest of the loop is fine, so carry on rather than skip it.
+            let context = match declaration.specifiers.storage_class {
+                Some(StorageClass::Typedef) => Some("`for` loop initializer declares a typedef"),
+                Some(StorageClass::Static | StorageClass::Extern) => {
+                    Some("declaration of non-local variable in `for` loop")
+                }
+                None => None,
+            };
+            if let Some(context) = context {
+                self.report(ParseError::new(declaration.specifiers.span, context));
+            }
+            return Ok(ForInit::Declaration(declaration));
+        }
+        let expression = match self.check(CTokenKind::Punctuator(SymbolKind::Semicolon)) {
+            true => None,
+            false => Some(self.parse_expression()?),
+        };
+        self.expect(CTokenKind::Punctuator(SymbolKind::Semicolon))?;
+        Ok(ForInit::Expression(expression))
+    }
+}
+
+#[cfg(test)]
+mod statement_tests {
+    use expect_test::{expect, Expect};
+    use tokengen::span::Span;
+    use wacc_lexer::Lexer;
+
+    use crate::{
+        ast::{BlockItem, Expression, ExternalDeclaration, ForInit, Statement, StatementKind},
+        Parser,
+    };
+
+    /// Outline a statement and those nested in it, one per line, quoting expressions as written.
+    fn outline(statement: &Statement, depth: usize, output: &mut Vec<String>) {
+        let indent = "    ".repeat(depth);
+        let mut line = |text: String| output.push(format!("{indent}{text}"));
+        let mut nested = Vec::new();
+        match &statement.kind {
+            StatementKind::Return(value) => line(match value {
+                Some(value) => format!("return `{}`", value.span.span()),
+                None => "return".to_string(),
+            }),
+            StatementKind::Expression(expression) => line(format!("`{}`", expression.span.span())),
+            StatementKind::Null => line("null".to_string()),
+            StatementKind::Compound(block) => {
+                line("block".to_string());
+                for item in &block.items {
+                    match item {
+                        BlockItem::Declaration(declaration) => output
+                            .push(format!("{indent}    declare `{}`", declaration.span.span())),
+                        BlockItem::Statement(statement) => outline(statement, depth + 1, output),
+                        BlockItem::StaticAssert(static_assert) => output.push(format!(
+                            "{indent}    static assert `{}`",
+                            static_assert.span.span()
+                        )),
+                    }
+                }
+            }
+            StatementKind::If(condition, then, els) => {
+                line(format!("if `{}`", condition.span.span()));
+                nested.push(then);
+                if let Some(els) = els {
+                    nested.push(els);
+                }
+            }
+            StatementKind::While(condition, body) => {
+                line(format!("while `{}`", condition.span.span()));
+                nested.push(body);
+            }
+            StatementKind::DoWhile(body, condition) => {
+                line(format!("do while `{}`", condition.span.span()));
+                nested.push(body);
+            }
+            StatementKind::For(init, condition, post, body) => {
+                let init = match &**init {
+                    ForInit::Declaration(declaration) => declaration.span.span(),
+                    ForInit::Expression(Some(expression)) => expression.span.span(),
+                    ForInit::Expression(None) => "",
+                };
+                fn clause(clause: &Option<Box<Expression>>) -> &str {
+                    clause
+                        .as_ref()
+                        .map_or("", |expression| expression.span.span())
+                }
+                line(format!(
+                    "for `{init}` `{}` `{}`",
+                    clause(condition),
+                    clause(post)
+                ));
+                nested.push(body);
+            }
+            StatementKind::Break => line("break".to_string()),
+            StatementKind::Continue => line("continue".to_string()),
+            StatementKind::Switch(condition, body) => {
+                line(format!("switch `{}`", condition.span.span()));
+                nested.push(body);
+            }
+            StatementKind::Case(_, value, body) => {
+                line(format!("case {value}"));
+                nested.push(body);
+            }
+            StatementKind::Default(body) => {
+                line("default".to_string());
+                nested.push(body);
+            }
+            StatementKind::Goto(label) => line(format!("goto {}", label.name)),
+            StatementKind::Labeled(label, body) => {
+                line(format!("{}:", label.name));
+                nested.push(body);
+            }
+            StatementKind::Error => line(format!("error `{}`", statement.span.span())),
+        }
+        for statement in nested {
+            outline(statement, depth + 1, output);
+        }
+    }
+
+    fn check_statements(body: &str, expect: Expect) {
+        let src = format!("int f(int x) {{\n{body}\n}}");
+        let tokens = String::lex_c(&src);
+        let mut parser = Parser::new(&src, &tokens);
+        let program = parser.parse_program();
+        let mut output = Vec::new();
+        let Some(ExternalDeclaration::Function(function)) = program.items.first() else {
+            panic!("expected a function definition, got {:?}", program.items);
+        };
+        for item in &function.body.items {
+            match item {
+                BlockItem::Declaration(declaration) => {
+                    output.push(format!("declare `{}`", declaration.span.span()))
+                }
+                BlockItem::Statement(statement) => outline(statement, 0, &mut output),
+                BlockItem::StaticAssert(static_assert) => {
+                    output.push(format!("static assert `{}`", static_assert.span.span()))
+                }
+            }
+        }
+        output.extend(
+            parser
+                .errors()
+                .iter()
+                .map(|err| format!("{} at `{}`", err.context(), err.span().span())),
+        );
+        expect.assert_eq(&output.join("\n"));
+    }
+
+    #[test]
+    fn test_statements() {
+        check_statements(
+            r#"
+            if (x) if (x - 1) return 1; else return 2;
+            while (x > 0) x = x - 1;
+            do { x++; ; } while (x < 10);
+            for (int i = 0, j = 1; i < x; i++) continue;
+            for (;;) break;
+            for (x = 0; ; ) {}
+            switch (x) { case 1: case 2 + 1: x = 3; break; default: return; }
+            goto end;
+            end: return x;
+            "#,
+            expect![[r#"
+                if `x`
+                    if `x - 1`
+                        return `1`
+                        return `2`
+                while `x > 0`
+                    `x = x - 1`
+                do while `x < 10`
+                    block
+                        `x++`
+                        null
+                for `int i = 0, j = 1;` `i < x` `i++`
+                    continue
+                for `` `` ``
+                    break
+                for `x = 0` `` ``
+                    block
+                switch `x`
+                    block
+                        case 1
+                            case 3
+                                `x = 3`
+                        break
+                        default
+                            return
+                goto end
+                end:
+                    return `x`"#]],
+        );
+    }
+
+    #[test]
+    fn test_statement_scopes() {
+        // A label doesn't shadow a typedef name, and a `for` declaration only does so within the loop.
+        check_statements(
+            r#"
+            typedef int T;
+            T: ;
+            for (int T = 0; T < 3; T++) T * 2;
+            T * y;
+            "#,
+            expect![[r#"
+                declare `typedef int T;`
+                T:
+                    null
+                for `int T = 0;` `T < 3` `T++`
+                    `T * 2`
+                declare `T * y;`"#]],
+        );
+    }
+
+    #[test]
+    fn test_statement_recovery() {
+        check_statements(
+            r#"
+            if (x) x = 1 +; else x = (2;
+            while (x) { x = ; y }
+            for (typedef int T;;) ;
+            for (static int i = 0;;) ;
+            for (int i = 0 i < 3; i++) { x = ; }
+            for (i = f(1 2; i < 3; i++) x;
+            case x: ;
+            return x
+            "#,
+            expect![[r#"
+                if `x`
+                    `x = 1 +`
+                    error `x = (2;`
+                while `x`
+                    block
+                        `x = `
+                        error `y`
+                for `typedef int T;` `` ``
+                    null
+                for `static int i = 0;` `` ``
+                    null
+                error `for (int i = 0 i < 3; i++) { x = ; }`
+                error `for (i = f(1 2; i < 3; i++) x;`
+                error `case x: ;`
+                error `return x`
+                expected an expression, found `;` at `;`
+                expected `)`, found `;` at `;`
+                expected an expression, found `;` at `;`
+                expected `;`, found `}` at `}`
+                `for` loop initializer declares a typedef at `typedef int`
+                declaration of non-local variable in `for` loop at `static int`
+                expected `;`, found identifier at `i`
+                expected an expression, found `;` at `;`
+                expected `)`, found constant at `2`
+                case label is not an integer constant at `x`
+                expected `;`, found `}` at `}`"#]],
+        );
+    }
+}
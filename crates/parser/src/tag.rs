@@ -0,0 +1,368 @@
+//! Struct, union and enum specifiers, and the tags they declare.
+//!
+//! A specifier with a body, or a bare `struct s;`, declares its tag in the
+//! innermost scope, completing a forward declaration made there. Any other
+//! mention refers to the visible tag of that name, and only declares a new,
+//! incomplete one if there is none.
+
+use std::collections::HashSet;
+
+use tokengen::span::{SourceSpan, Span};
+use wacc_lexer::c_token::{c_keyword::KeywordKind, c_symbol::SymbolKind, CTokenKind};
+
+use crate::{
+    ast::{
+        Declarator, DeclaratorKind, EnumSpecifier, Enumerator, Expression, Identifier,
+        MemberDeclaration, MemberDeclarator, RecordSpecifier, TypeSpecifier,
+    },
+    declaration::DeclaratorMode,
+    expression::CONDITIONAL_PRECEDENCE,
+    scope::Binding,
+    types::{Member, QualifiedType, Tag, TagBody, TagKind, Type},
+    ParseError, Parser,
+};
+
+impl Parser<'_> {
+    /// `<struct-or-union-specifier> ::= ( "struct" | "union" ) [ <identifier> ] "{" { <member-declaration> } "}"
+    ///                                | ( "struct" | "union" ) <identifier>`
+    pub(crate) fn parse_record_specifier(&mut self) -> Result<RecordSpecifier, ParseError> {
+        let start = self.peek_span();
+        let kind = match self.eat(CTokenKind::Keyword(KeywordKind::Struct)) {
+            Some(_) => TagKind::Struct,
+            None => {
+                self.expect(CTokenKind::Keyword(KeywordKind::Union))?;
+                TagKind::Union
+            }
+        };
+        let (name, tag) = self.parse_tag(kind)?;
+        if !self.check(CTokenKind::Punctuator(SymbolKind::OpenCurlyBrace)) {
+            return Ok(RecordSpecifier {
+                kind,
+                name,
+                members: None,
+                tag,
+                span: start.to(&self.prev_span()),
+            });
+        }
+
+        self.expect(CTokenKind::Punctuator(SymbolKind::OpenCurlyBrace))?;
+        let mut declarations = Vec::new();
+        while self.peek().is_some()
+            && !self.check(CTokenKind::Punctuator(SymbolKind::CloseCurlyBrace))
+        {
+            declarations.push(self.parse_member_declaration()?);
+        }
+        let end = self.expect(CTokenKind::Punctuator(SymbolKind::CloseCurlyBrace))?;
+        let span = start.to(&end);
+        let members = self.check_members(kind, &declarations, span)?;
+        let layout = self.tags.layout_record(kind, &members);
+        self.tags.complete(&tag, TagBody::Record(members, layout));
+
+        Ok(RecordSpecifier {
+            kind,
+            name,
+            members: Some(declarations),
+            tag,
+            span,
+        })
+    }
+
+    /// `<member-declaration> ::= <specifier-qualifier-list> [ <member-declarator> { "," <member-declarator> } ] ";"`
+    fn parse_member_declaration(&mut self) -> Result<MemberDeclaration, ParseError> {
+        let specifiers = self.parse_declaration_specifiers()?;
+        if specifiers.storage_class.is_some() {
+            return Err(ParseError::new(
+                specifiers.span,
+                "storage class specified for a member",
+            ));
+        }
+        if specifiers.is_inline {
+            return Err(ParseError::new(
+                specifiers.span,
+                "`inline` specified for a member",
+            ));
+        }
+        let base = self.base_type(&specifiers)?;
+        let mut declarators = Vec::new();
+        if !self.check(CTokenKind::Punctuator(SymbolKind::Semicolon)) {
+            loop {
+                declarators.push(self.parse_member_declarator(&base)?);
+                if self
+                    .eat(CTokenKind::Punctuator(SymbolKind::Comma))
+                    .is_none()
+                {
+                    break;
+                }
+            }
+        }
+        let end = self.expect(CTokenKind::Punctuator(SymbolKind::Semicolon))?;
+
+        Ok(MemberDeclaration {
+            span: specifiers.span.to(&end),
+            specifiers,
+            declarators,
+        })
+    }
+
+    /// `<member-declarator> ::= <declarator> [ ":" <constant-exp> ] | ":" <constant-exp>`
+    fn parse_member_declarator(
+        &mut self,
+        base: &QualifiedType,
+    ) -> Result<MemberDeclarator, ParseError> {
+        let start = self.peek_span();
+        let declarator = match self.check(CTokenKind::Punctuator(SymbolKind::Colon)) {
+            true => Declarator {
+                kind: DeclaratorKind::Identifier(None),
+                span: SourceSpan::new(self.src, start.start(), start.start()),
+            },
+            false => self.parse_declarator(DeclaratorMode::Named)?,
+        };
+        let (name, ty) = self.derive_type(&declarator, base.clone())?;
+        let bit_width = match self.eat(CTokenKind::Punctuator(SymbolKind::Colon)) {
+            Some(_) => Some(self.parse_expression_with_precedence(CONDITIONAL_PRECEDENCE)?),
+            None => None,
+        };
+
+        Ok(MemberDeclarator {
+            declarator,
+            name,
+            ty,
+            bit_width,
+            span: start.to(&self.prev_span()),
+        })
+    }
+
+    /// Check the members of a struct or union definition, and resolve them for layout.
+    fn check_members(
+        &self,
+        kind: TagKind,
+        declarations: &[MemberDeclaration],
+        span: SourceSpan,
+    ) -> Result<Vec<Member>, ParseError> {
+        let mut members = Vec::new();
+        let mut names = HashSet::new();
+        let declarator_count = declarations
+            .iter()
+            .map(|declaration| declaration.declarators.len())
+            .sum::<usize>();
+        for declaration in declarations {
+            if declaration.declarators.is_empty() {
+                // C11 anonymous structs and unions, whose members belong to the enclosing record.
+                let ty = self.base_type(&declaration.specifiers)?;
+                let is_anonymous = declaration
+                    .specifiers
+                    .type_specifiers
+                    .iter()
+                    .any(|specifier| {
+                        matches!(specifier, TypeSpecifier::Record(record) if record.name.is_none())
+                    });
+                if !is_anonymous {
+                    return Err(ParseError::new(
+                        declaration.span,
+                        "declaration does not declare a member",
+                    ));
+                }
+                members.push(Member {
+                    name: None,
+                    ty,
+                    bit_width: None,
+                });
+                continue;
+            }
+            for declarator in &declaration.declarators {
+                let error = |context: &str| Err(ParseError::new(declarator.span, context));
+                let is_last = members.len() + 1 == declarator_count;
+                if let Some(name) = &declarator.name {
+                    if !names.insert(name.name.as_str()) {
+                        return error(&format!("duplicate member `{}`", name.name));
+                    }
+                }
+                let bit_width = match &declarator.bit_width {
+                    Some(width) => Some(self.bit_width(declarator, width)?),
+                    None if declarator.ty.ty.is_function() => {
+                        return error("member declared as a function");
+                    }
+                    None if matches!(declarator.ty.ty, Type::Array(_, None)) => {
+                        if kind != TagKind::Struct || !is_last || members.is_empty() {
+                            return error(
+                                "flexible array member must end a struct with other members",
+                            );
+                        }
+                        None
+                    }
+                    None if self.tags.layout(&declarator.ty.ty).is_none() => {
+                        return error("member has incomplete type");
+                    }
+                    None => None,
+                };
+                members.push(Member {
+                    name: declarator.name.as_ref().map(|name| name.name.clone()),
+                    ty: declarator.ty.clone(),
+                    bit_width,
+                });
+            }
+        }
+        if members.is_empty() {
+            return Err(ParseError::new(span, &format!("{kind} has no members")));
+        }
+
+        Ok(members)
+    }
+
+    fn bit_width(
+        &self,
+        declarator: &MemberDeclarator,
+        width: &Expression,
+    ) -> Result<u32, ParseError> {
+        let error = |context: &str| Err(ParseError::new(width.span, context));
+        if !declarator.ty.ty.is_integer() {
+            return Err(ParseError::new(
+                declarator.span,
+                "bit-field has non-integer type",
+            ));
+        }
+        let bits = self
+            .tags
+            .layout(&declarator.ty.ty)
+            .expect("integer types are complete")
+            .size
+            * 8;
+        let width = self.integer_constant(width).map_err(|err| {
+            err.into_error("bit-field width is not an integer constant expression")
+        })?;
+        match width {
+            width if width < 0 => error("bit-field has negative width"),
+            0 if declarator.name.is_some() => error("named bit-field has zero width"),
+            width if width > i128::from(bits) => error("bit-field is wider than its type"),
+            width => Ok(width as u32),
+        }
+    }
+
+    /// `<enum-specifier> ::= "enum" [ <identifier> ] "{" <enumerator> { "," <enumerator> } [ "," ] "}"
+    ///                     | "enum" <identifier>`
+    pub(crate) fn parse_enum_specifier(&mut self) -> Result<EnumSpecifier, ParseError> {
+        let start = self.expect(CTokenKind::Keyword(KeywordKind::Enum))?;
+        let (name, tag) = self.parse_tag(TagKind::Enum)?;
+        if !self.check(CTokenKind::Punctuator(SymbolKind::OpenCurlyBrace)) {
+            return Ok(EnumSpecifier {
+                name,
+                enumerators: None,
+                tag,
+                span: start.to(&self.prev_span()),
+            });
+        }
+
+        self.expect(CTokenKind::Punctuator(SymbolKind::OpenCurlyBrace))?;
+        let mut enumerators = Vec::new();
+        let mut next = 0_i128;
+        loop {
+            let enumerator = self.parse_enumerator(next)?;
+            next = i128::from(enumerator.constant) + 1;
+            enumerators.push(enumerator);
+            if self
+                .eat(CTokenKind::Punctuator(SymbolKind::Comma))
+                .is_none()
+                || self.check(CTokenKind::Punctuator(SymbolKind::CloseCurlyBrace))
+            {
+                break;
+            }
+        }
+        let end = self.expect(CTokenKind::Punctuator(SymbolKind::CloseCurlyBrace))?;
+        let constants = enumerators
+            .iter()
+            .map(|enumerator| (enumerator.name.name.clone(), enumerator.constant))
+            .collect::<Vec<_>>();
+        // Like GCC, an enum without negative constants is compatible with `unsigned int`.
+        let compatible = match constants.iter().all(|(_, value)| *value >= 0) {
+            true => Type::UnsignedInt,
+            false => Type::Int,
+        };
+        self.tags
+            .complete(&tag, TagBody::Enum(constants, compatible));
+
+        Ok(EnumSpecifier {
+            name,
+            enumerators: Some(enumerators),
+            tag,
+            span: start.to(&end),
+        })
+    }
+
+    /// `<enumerator> ::= <identifier> [ "=" <constant-exp> ]`, whose value is `next` if not given.
+    fn parse_enumerator(&mut self, next: i128) -> Result<Enumerator, ParseError> {
+        let name = self.parse_identifier()?;
+        let value = match self.eat(CTokenKind::Punctuator(SymbolKind::Equals)) {
+            Some(_) => Some(self.parse_expression_with_precedence(CONDITIONAL_PRECEDENCE)?),
+            None => None,
+        };
+        let span = name.span.to(&self.prev_span());
+        let constant = match &value {
+            Some(value) => self.integer_constant(value).map_err(|err| {
+                err.into_error("enumerator value is not an integer constant expression")
+            })?,
+            None => next,
+        };
+        let constant = i32::try_from(constant)
+            .map_err(|_| ParseError::new(span, "enumerator value is out of range of `int`"))?;
+        // Enumeration constants are in scope right after their own enumerator.
+        self.scopes
+            .declare(&name.name, Binding::EnumConstant(constant.into()));
+
+        Ok(Enumerator {
+            name,
+            value,
+            constant: constant.into(),
+            span,
+        })
+    }
+
+    /// Parse the optional tag name after `struct`, `union` or `enum`, and find or
+    /// declare the tag it refers to.
+    fn parse_tag(&mut self, kind: TagKind) -> Result<(Option<Identifier>, Tag), ParseError> {
+        let name = match self.check(CTokenKind::Identifier) {
+            true => Some(self.parse_identifier()?),
+            false => None,
+        };
+        let defines = self.check(CTokenKind::Punctuator(SymbolKind::OpenCurlyBrace));
+        let Some(identifier) = &name else {
+            if !defines {
+                return Err(self.error("expected an identifier or `{`"));
+            }
+            return Ok((None, self.tags.declare(kind, None)));
+        };
+
+        let declares = defines || self.check(CTokenKind::Punctuator(SymbolKind::Semicolon));
+        let visible = match declares {
+            true => self.scopes.innermost_tag(&identifier.name),
+            false => self.scopes.tag(&identifier.name),
+        };
+        let tag = match visible.cloned() {
+            Some(tag) => {
+                let definition = self.tags.get(&tag);
+                if definition.kind != kind {
+                    return Err(ParseError::new(
+                        identifier.span,
+                        &format!(
+                            "`{}` was declared as a {}",
+                            identifier.name, definition.kind
+                        ),
+                    ));
+                }
+                if defines && definition.body.is_some() {
+                    return Err(ParseError::new(
+                        identifier.span,
+                        &format!("redefinition of `{kind} {}`", identifier.name),
+                    ));
+                }
+                tag
+            }
+            None => {
+                let tag = self.tags.declare(kind, Some(&identifier.name));
+                self.scopes.declare_tag(&identifier.name, tag.clone());
+                tag
+            }
+        };
+
+        Ok((name, tag))
+    }
+}
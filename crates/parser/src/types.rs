@@ -0,0 +1,435 @@
+//! C types, as derived from declaration specifiers and declarators.
+
+use std::fmt;
+
+use crate::layout::RecordLayout;
+
+/// `const`, `volatile` and `restrict`, which can be applied at every level of a type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Qualifiers {
+    pub is_const: bool,
+    pub is_volatile: bool,
+    pub is_restrict: bool,
+}
+impl Qualifiers {
+    pub const NONE: Self = Self {
+        is_const: false,
+        is_volatile: false,
+        is_restrict: false,
+    };
+    pub fn is_empty(&self) -> bool {
+        *self == Self::NONE
+    }
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            is_const: self.is_const || other.is_const,
+            is_volatile: self.is_volatile || other.is_volatile,
+            is_restrict: self.is_restrict || other.is_restrict,
+        }
+    }
+}
+impl fmt::Display for Qualifiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = [
+            (self.is_const, "const"),
+            (self.is_volatile, "volatile"),
+            (self.is_restrict, "restrict"),
+        ];
+        let names = names
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>();
+        write!(f, "{}", names.join(" "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QualifiedType {
+    pub ty: Type,
+    pub qualifiers: Qualifiers,
+}
+impl QualifiedType {
+    pub fn new(ty: Type, qualifiers: Qualifiers) -> Self {
+        Self { ty, qualifiers }
+    }
+    pub fn unqualified(ty: Type) -> Self {
+        Self::new(ty, Qualifiers::NONE)
+    }
+    /// Add qualifiers to this type, as in `const T` for a typedef name `T`.
+    /// Qualifying an array type qualifies its elements instead (C17 6.7.3p10).
+    pub fn qualified(self, qualifiers: Qualifiers) -> Self {
+        match self.ty {
+            Type::Array(element, length) => {
+                Type::Array(Box::new(element.qualified(qualifiers)), length).into()
+            }
+            ty => Self::new(ty, self.qualifiers.union(qualifiers)),
+        }
+    }
+}
+impl From<Type> for QualifiedType {
+    fn from(ty: Type) -> Self {
+        Self::unqualified(ty)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    Void,
+    Char,
+    SignedChar,
+    UnsignedChar,
+    Short,
+    UnsignedShort,
+    Int,
+    UnsignedInt,
+    Long,
+    UnsignedLong,
+    LongLong,
+    UnsignedLongLong,
+    Float,
+    Double,
+    Pointer(Box<QualifiedType>),
+    /// An array of a known length, or of unknown length (an incomplete type) if `None`.
+    Array(Box<QualifiedType>, Option<u64>),
+    Function(FunctionType),
+    Struct(Tag),
+    Union(Tag),
+    Enum(Tag),
+}
+impl Type {
+    pub fn pointer_to(pointee: impl Into<QualifiedType>) -> Self {
+        Self::Pointer(Box::new(pointee.into()))
+    }
+    pub fn is_function(&self) -> bool {
+        matches!(self, Self::Function(_))
+    }
+    pub fn is_array(&self) -> bool {
+        matches!(self, Self::Array(..))
+    }
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            Self::Char
+                | Self::SignedChar
+                | Self::UnsignedChar
+                | Self::Short
+                | Self::UnsignedShort
+                | Self::Int
+                | Self::UnsignedInt
+                | Self::Long
+                | Self::UnsignedLong
+                | Self::LongLong
+                | Self::UnsignedLongLong
+                | Self::Enum(_)
+        )
+    }
+    pub fn is_arithmetic(&self) -> bool {
+        self.is_integer() || matches!(self, Self::Float | Self::Double)
+    }
+    pub fn is_scalar(&self) -> bool {
+        self.is_arithmetic() || matches!(self, Self::Pointer(_))
+    }
+    /// Whether a promoted integer type is signed.
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            Self::Char | Self::SignedChar | Self::Short | Self::Int | Self::Long | Self::LongLong
+        )
+    }
+
+    /// The integer conversion rank of a promoted integer type.
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Long | Self::UnsignedLong => 2,
+            Self::LongLong | Self::UnsignedLongLong => 3,
+            _ => 1,
+        }
+    }
+
+    fn to_unsigned(&self) -> Self {
+        match self {
+            Self::Int => Self::UnsignedInt,
+            Self::Long => Self::UnsignedLong,
+            Self::LongLong => Self::UnsignedLongLong,
+            ty => ty.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FunctionType {
+    pub return_type: Box<QualifiedType>,
+    pub parameters: Vec<QualifiedType>,
+    pub is_variadic: bool,
+    /// `false` for old-style declarators like `int f()`, whose parameters are unspecified.
+    pub is_prototype: bool,
+}
+
+/// A struct, union or enum type, identified by its index in the [`TagTable`].
+/// Every tag declaration that doesn't refer back to a visible one makes a new tag,
+/// so two `struct s` in different scopes are different types.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Tag {
+    pub id: usize,
+    /// `None` for an anonymous `struct { ... }`.
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagKind {
+    Struct,
+    Union,
+    Enum,
+}
+impl fmt::Display for TagKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Struct => write!(f, "struct"),
+            Self::Union => write!(f, "union"),
+            Self::Enum => write!(f, "enum"),
+        }
+    }
+}
+
+/// Every struct, union and enum declared in a translation unit, indexed by [`Tag::id`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagTable {
+    definitions: Vec<TagDefinition>,
+}
+impl TagTable {
+    /// Declare a new, incomplete tag.
+    pub(crate) fn declare(&mut self, kind: TagKind, name: Option<&str>) -> Tag {
+        self.definitions.push(TagDefinition {
+            kind,
+            name: name.map(str::to_string),
+            body: None,
+        });
+        Tag {
+            id: self.definitions.len() - 1,
+            name: name.map(str::to_string),
+        }
+    }
+    /// Complete a tag with the body of its definition.
+    pub(crate) fn complete(&mut self, tag: &Tag, body: TagBody) {
+        self.definitions[tag.id].body = Some(body);
+    }
+    pub fn get(&self, tag: &Tag) -> &TagDefinition {
+        &self.definitions[tag.id]
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &TagDefinition> {
+        self.definitions.iter()
+    }
+
+    /// The integer type an enum is compatible with, or any other type as it is.
+    pub fn underlying_type(&self, ty: &Type) -> Type {
+        match ty {
+            Type::Enum(tag) => match &self.get(tag).body {
+                Some(TagBody::Enum(_, ty)) => ty.clone(),
+                _ => Type::Int,
+            },
+            ty => ty.clone(),
+        }
+    }
+
+    /// The integer promotions: every integer type of lower rank than `int` becomes `int`,
+    /// which can represent all of their values.
+    pub fn promote(&self, ty: &Type) -> Type {
+        match self.underlying_type(ty) {
+            Type::Char
+            | Type::SignedChar
+            | Type::UnsignedChar
+            | Type::Short
+            | Type::UnsignedShort => Type::Int,
+            ty => ty,
+        }
+    }
+
+    /// The usual arithmetic conversions, which find the type two arithmetic operands
+    /// are brought to before an operation.
+    pub fn common_type(&self, left: &Type, right: &Type) -> Type {
+        for floating in [Type::Double, Type::Float] {
+            if *left == floating || *right == floating {
+                return floating;
+            }
+        }
+        let (left, right) = (self.promote(left), self.promote(right));
+        if left == right {
+            return left;
+        }
+        if left.is_signed() == right.is_signed() {
+            return match left.rank() > right.rank() {
+                true => left,
+                false => right,
+            };
+        }
+        let (signed, unsigned) = match left.is_signed() {
+            true => (left, right),
+            false => (right, left),
+        };
+        // The unsigned type wins unless the signed one can hold all of its values.
+        let size = |ty: &Type| self.layout(ty).map(|layout| layout.size);
+        if unsigned.rank() >= signed.rank() {
+            unsigned
+        } else if size(&signed) > size(&unsigned) {
+            signed
+        } else {
+            signed.to_unsigned()
+        }
+    }
+
+    /// Whether two types are compatible (C17 6.2.7), meaning they could be the
+    /// same type declared in two different ways.
+    pub fn compatible(&self, left: &Type, right: &Type) -> bool {
+        let qualified = |left: &QualifiedType, right: &QualifiedType| {
+            left.qualifiers == right.qualifiers && self.compatible(&left.ty, &right.ty)
+        };
+        match (left, right) {
+            _ if left == right => true,
+            (Type::Pointer(left), Type::Pointer(right)) => qualified(left, right),
+            (Type::Array(left, left_length), Type::Array(right, right_length)) => {
+                qualified(left, right)
+                    && (left_length.is_none()
+                        || right_length.is_none()
+                        || left_length == right_length)
+            }
+            (Type::Function(left), Type::Function(right)) => {
+                self.compatible(&left.return_type.ty, &right.return_type.ty)
+                    && (!left.is_prototype
+                        || !right.is_prototype
+                        || left.is_variadic == right.is_variadic
+                            && left.parameters.len() == right.parameters.len()
+                            && left
+                                .parameters
+                                .iter()
+                                .zip(&right.parameters)
+                                .all(|(left, right)| self.compatible(&left.ty, &right.ty)))
+            }
+            (Type::Enum(_), ty) | (ty, Type::Enum(_)) if !matches!(ty, Type::Enum(_)) => {
+                self.underlying_type(left) == self.underlying_type(right)
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagDefinition {
+    pub kind: TagKind,
+    pub name: Option<String>,
+    /// `None` while the tag is incomplete, i.e. only forward declared.
+    pub body: Option<TagBody>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagBody {
+    /// The members of a struct or union, with their [`RecordLayout`].
+    Record(Vec<Member>, RecordLayout),
+    /// The constants of an enum with their values, and the integer type the enum is compatible with.
+    Enum(Vec<(String, i64)>, Type),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    /// `None` for unnamed bit-fields and anonymous struct or union members.
+    pub name: Option<String>,
+    pub ty: QualifiedType,
+    pub bit_width: Option<u32>,
+}
+
+impl fmt::Display for QualifiedType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.declare(""))
+    }
+}
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", QualifiedType::unqualified(self.clone()))
+    }
+}
+
+impl QualifiedType {
+    /// Spell out a declaration of `name` with this type in C syntax, e.g.
+    /// `int (*fp[4])(long, char *)`. An empty name gives the type's name as
+    /// written in a cast.
+    pub fn declare(&self, name: &str) -> String {
+        let mut declarator = name.to_string();
+        let mut current = self;
+        loop {
+            match &current.ty {
+                Type::Pointer(pointee) => {
+                    declarator = match current.qualifiers.is_empty() {
+                        true => format!("*{declarator}"),
+                        false if declarator.is_empty() => format!("*{}", current.qualifiers),
+                        false => format!("*{} {declarator}", current.qualifiers),
+                    };
+                    if pointee.ty.is_array() || pointee.ty.is_function() {
+                        declarator = format!("({declarator})");
+                    }
+                    current = pointee;
+                }
+                Type::Array(element, length) => {
+                    match length {
+                        Some(length) => declarator.push_str(&format!("[{length}]")),
+                        None => declarator.push_str("[]"),
+                    }
+                    current = element;
+                }
+                Type::Function(function) => {
+                    let mut parameters = function
+                        .parameters
+                        .iter()
+                        .map(|parameter| parameter.declare(""))
+                        .collect::<Vec<_>>();
+                    if function.is_variadic {
+                        parameters.push("...".to_string());
+                    } else if parameters.is_empty() && function.is_prototype {
+                        parameters.push("void".to_string());
+                    }
+                    declarator.push_str(&format!("({})", parameters.join(", ")));
+                    current = &function.return_type;
+                }
+                base => {
+                    let base = match current.qualifiers.is_empty() {
+                        true => base_name(base),
+                        false => format!("{} {}", current.qualifiers, base_name(base)),
+                    };
+                    return match declarator.is_empty() {
+                        true => base,
+                        false => format!("{base} {declarator}"),
+                    };
+                }
+            }
+        }
+    }
+}
+
+fn base_name(ty: &Type) -> String {
+    let tag_name = |kind: TagKind, tag: &Tag| match &tag.name {
+        Some(name) => format!("{kind} {name}"),
+        None => format!("{kind} (anonymous)"),
+    };
+    let name = match ty {
+        Type::Void => "void",
+        Type::Char => "char",
+        Type::SignedChar => "signed char",
+        Type::UnsignedChar => "unsigned char",
+        Type::Short => "short",
+        Type::UnsignedShort => "unsigned short",
+        Type::Int => "int",
+        Type::UnsignedInt => "unsigned int",
+        Type::Long => "long",
+        Type::UnsignedLong => "unsigned long",
+        Type::LongLong => "long long",
+        Type::UnsignedLongLong => "unsigned long long",
+        Type::Float => "float",
+        Type::Double => "double",
+        Type::Struct(tag) => return tag_name(TagKind::Struct, tag),
+        Type::Union(tag) => return tag_name(TagKind::Union, tag),
+        Type::Enum(tag) => return tag_name(TagKind::Enum, tag),
+        Type::Pointer(_) | Type::Array(..) | Type::Function(_) => {
+            unreachable!("derived types are spelled by their declarator")
+        }
+    };
+    name.to_string()
+}
@@ -0,0 +1,434 @@
+//! Reachability of the statements in a function body.
+//!
+//! This walks the structured control flow of each function, keeping track of
+//! whether the current point can be reached from the start of the body. A
+//! `return`, `goto`, `break` or `continue`, or a loop whose condition is a nonzero
+//! constant and that is never broken out of, makes what follows unreachable until
+//! a label something jumps to, or a `case` or `default` label of a reachable
+//! `switch`.
+//!
+//! It warns about the first statement of each unreachable stretch of code, and
+//! about non-void functions whose body can end without returning a value. It
+//! doesn't know which functions never return, so calls are assumed to return.
+
+use std::collections::HashSet;
+
+use tokengen::span::{SourceSpan, Span};
+use wacc_parser::{
+    ast::{
+        Block, BlockItem, Expression, ExternalDeclaration, FunctionDefinition, Identifier, Program,
+        Statement, StatementKind,
+    },
+    constant::{Entity, Evaluator},
+    types::{FunctionType, TagTable, Type},
+};
+
+use crate::{
+    resolve::{Resolution, SymbolKind},
+    warning::{Warning, WarningKind},
+};
+
+/// A loop or switch, and how control left it other than by falling out of its body.
+struct Breakable {
+    kind: BreakableKind,
+    /// Whether a reachable `break` leaves it.
+    broken: bool,
+    /// Whether a reachable `continue` goes back to its condition.
+    continued: bool,
+}
+
+enum BreakableKind {
+    Loop,
+    Switch {
+        /// Whether the `switch` itself is reachable, and so are its labels.
+        reachable: bool,
+        has_default: bool,
+    },
+}
+
+/// Checks the reachability of the statements of a resolved, type checked program.
+pub struct FlowChecker<'a> {
+    resolution: &'a Resolution,
+    tags: TagTable,
+    /// The labels of the current function that some `goto` jumps to.
+    targets: HashSet<String>,
+    /// The loops and switches enclosing the current statement, innermost last.
+    enclosing: Vec<Breakable>,
+    /// Set once the current stretch of unreachable code has been reported.
+    reported: bool,
+    warnings: Vec<Warning>,
+}
+impl<'a> FlowChecker<'a> {
+    pub fn new(resolution: &'a Resolution) -> Self {
+        Self {
+            resolution,
+            tags: TagTable::default(),
+            targets: HashSet::new(),
+            enclosing: Vec::new(),
+            reported: false,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Every warning reported so far, in source order.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    pub fn check_program(&mut self, program: &Program) {
+        self.tags.clone_from(&program.tags);
+        for item in &program.items {
+            if let ExternalDeclaration::Function(function) = item {
+                self.function_definition(function);
+            }
+        }
+        self.warnings.sort_by_key(|warning| warning.span().start());
+    }
+
+    fn function_definition(&mut self, function: &FunctionDefinition) {
+        self.targets.clear();
+        gotos(&function.body, &mut self.targets);
+        self.reported = false;
+        let falls_off = self.block(&function.body, true);
+        let Type::Function(FunctionType { return_type, .. }) = &function.ty.ty else {
+            return;
+        };
+        // Reaching the end of `main` returns 0 (C17 5.1.2.2.3).
+        let name = &function.name.name;
+        if falls_off && return_type.ty != Type::Void && name != "main" {
+            let end = function.body.span.end();
+            let span = SourceSpan::new(function.body.span.src(), end - 1, end);
+            self.warnings.push(Warning::new(
+                WarningKind::ReturnType,
+                span,
+                &format!("control reaches end of non-void function `{name}`"),
+            ));
+        }
+    }
+
+    /// Whether control can reach the end of a block entered as `reachable` says.
+    fn block(&mut self, block: &Block, mut reachable: bool) -> bool {
+        for item in &block.items {
+            if let BlockItem::Statement(statement) = item {
+                reachable = self.statement(statement, reachable);
+            }
+        }
+        reachable
+    }
+
+    /// Whether control can fall out of a statement entered as `reachable` says.
+    fn statement(&mut self, statement: &Statement, reachable: bool) -> bool {
+        match reachable {
+            true => self.reported = false,
+            false => self.warn_unreachable(statement),
+        }
+        match &statement.kind {
+            StatementKind::Expression(_) | StatementKind::Null | StatementKind::Error => reachable,
+            StatementKind::Return(_) | StatementKind::Goto(_) => false,
+            StatementKind::Break => {
+                if let Some(breakable) = self.enclosing.last_mut() {
+                    breakable.broken |= reachable;
+                }
+                false
+            }
+            StatementKind::Continue => {
+                let innermost_loop = self
+                    .enclosing
+                    .iter_mut()
+                    .rev()
+                    .find(|breakable| matches!(breakable.kind, BreakableKind::Loop));
+                if let Some(breakable) = innermost_loop {
+                    breakable.continued |= reachable;
+                }
+                false
+            }
+            StatementKind::Compound(block) => self.block(block, reachable),
+            StatementKind::If(_, then, els) => {
+                let then = self.statement(then, reachable);
+                let els = match els {
+                    Some(els) => self.statement(els, reachable),
+                    None => reachable,
+                };
+                then || els
+            }
+            StatementKind::While(condition, body) => {
+                let is_infinite = self.is_nonzero(condition);
+                let (_, breakable) = self.nested(BreakableKind::Loop, body, reachable);
+                breakable.broken || (reachable && !is_infinite)
+            }
+            StatementKind::For(_, condition, _, body) => {
+                let is_infinite = condition
+                    .as_ref()
+                    .is_none_or(|condition| self.is_nonzero(condition));
+                let (_, breakable) = self.nested(BreakableKind::Loop, body, reachable);
+                breakable.broken || (reachable && !is_infinite)
+            }
+            StatementKind::DoWhile(body, condition) => {
+                let is_infinite = self.is_nonzero(condition);
+                let (end, breakable) = self.nested(BreakableKind::Loop, body, reachable);
+                breakable.broken || ((end || breakable.continued) && !is_infinite)
+            }
+            StatementKind::Switch(_, body) => {
+                let kind = BreakableKind::Switch {
+                    reachable,
+                    has_default: false,
+                };
+                // The body is only entered at its labels.
+                let (end, breakable) = self.nested(kind, body, false);
+                let has_default = matches!(
+                    breakable.kind,
+                    BreakableKind::Switch {
+                        has_default: true,
+                        ..
+                    }
+                );
+                breakable.broken || end || (reachable && !has_default)
+            }
+            StatementKind::Case(_, _, body) | StatementKind::Default(body) => {
+                let is_default = matches!(statement.kind, StatementKind::Default(_));
+                let innermost_switch = self.enclosing.iter_mut().rev().find_map(|breakable| {
+                    match &mut breakable.kind {
+                        BreakableKind::Switch {
+                            reachable,
+                            has_default,
+                        } => Some((*reachable, has_default)),
+                        BreakableKind::Loop => None,
+                    }
+                });
+                let switch_reachable = match innermost_switch {
+                    Some((switch_reachable, has_default)) => {
+                        *has_default |= is_default;
+                        switch_reachable
+                    }
+                    None => false,
+                };
+                self.statement(body, reachable || switch_reachable)
+            }
+            StatementKind::Labeled(label, body) => {
+                let is_target = self.targets.contains(&label.name);
+                self.statement(body, reachable || is_target)
+            }
+        }
+    }
+
+    /// Check the body of a loop or switch, returning whether control can fall out
+    /// of it and how else control left it.
+    fn nested(
+        &mut self,
+        kind: BreakableKind,
+        body: &Statement,
+        reachable: bool,
+    ) -> (bool, Breakable) {
+        self.enclosing.push(Breakable {
+            kind,
+            broken: false,
+            continued: false,
+        });
+        let end = self.statement(body, reachable);
+        let breakable = self
+            .enclosing
+            .pop()
+            .expect("breakables are pushed and popped in pairs");
+        (end, breakable)
+    }
+
+    /// Report the first statement of a stretch of unreachable code. Labels are
+    /// checked for themselves, and a `break` or an empty statement after a
+    /// `return` is harmless.
+    fn warn_unreachable(&mut self, statement: &Statement) {
+        let is_harmless = matches!(
+            statement.kind,
+            StatementKind::Null
+                | StatementKind::Break
+                | StatementKind::Compound(_)
+                | StatementKind::Case(..)
+                | StatementKind::Default(_)
+                | StatementKind::Labeled(..)
+                | StatementKind::Error
+        );
+        if is_harmless || self.reported {
+            return;
+        }
+        self.reported = true;
+        self.warnings.push(Warning::new(
+            WarningKind::UnreachableCode,
+            statement.span,
+            "code will never be executed",
+        ));
+    }
+
+    /// Whether a condition is an integer constant expression other than zero.
+    fn is_nonzero(&self, condition: &Expression) -> bool {
+        let resolution = self.resolution;
+        let lookup =
+            |identifier: &Identifier| match resolution.symbol(resolution.binding(identifier)?).kind
+            {
+                SymbolKind::EnumConstant(value) => Some(Entity::EnumConstant(value)),
+                _ => None,
+            };
+        Evaluator::new(&self.tags, &lookup)
+            .integer(condition)
+            .is_ok_and(|value| value != 0)
+    }
+}
+
+/// The labels every `goto` in a block jumps to.
+fn gotos(block: &Block, targets: &mut HashSet<String>) {
+    for item in &block.items {
+        if let BlockItem::Statement(statement) = item {
+            statement_gotos(statement, targets);
+        }
+    }
+}
+
+fn statement_gotos(statement: &Statement, targets: &mut HashSet<String>) {
+    match &statement.kind {
+        StatementKind::Goto(label) => {
+            targets.insert(label.name.clone());
+        }
+        StatementKind::Compound(block) => gotos(block, targets),
+        StatementKind::If(_, then, els) => {
+            statement_gotos(then, targets);
+            if let Some(els) = els {
+                statement_gotos(els, targets);
+            }
+        }
+        StatementKind::While(_, body)
+        | StatementKind::DoWhile(body, _)
+        | StatementKind::For(_, _, _, body)
+        | StatementKind::Switch(_, body)
+        | StatementKind::Case(_, _, body)
+        | StatementKind::Default(body)
+        | StatementKind::Labeled(_, body) => statement_gotos(body, targets),
+        StatementKind::Return(_)
+        | StatementKind::Expression(_)
+        | StatementKind::Null
+        | StatementKind::Break
+        | StatementKind::Continue
+        | StatementKind::Error => {}
+    }
+}
+
+#[cfg(test)]
+mod flow_tests {
+    use expect_test::{expect, Expect};
+    use tokengen::span::Span;
+    use wacc_lexer::Lexer;
+    use wacc_parser::Parser;
+
+    use super::FlowChecker;
+    use crate::{resolve::Resolver, typecheck::TypeChecker};
+
+    /// The warnings about a program, with the source they point at.
+    fn check_flow(src: &str, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let mut program = parser.parse_program();
+        assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+        let mut resolver = Resolver::new();
+        let resolution = resolver.resolve_program(&mut program);
+        assert!(resolver.errors().is_empty(), "{:?}", resolver.errors());
+        let mut checker = TypeChecker::new(&resolution);
+        checker.check_program(&mut program);
+        assert!(checker.errors().is_empty(), "{:?}", checker.errors());
+        let mut flow = FlowChecker::new(&resolution);
+        flow.check_program(&program);
+        let output = flow
+            .warnings()
+            .iter()
+            .map(|warning| {
+                let kind = warning.kind();
+                format!(
+                    "{kind}: {} at {:?}",
+                    warning.context(),
+                    warning.span().span()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        expect.assert_eq(&output);
+    }
+
+    #[test]
+    fn test_returns() {
+        check_flow(
+            r#"
+            int all_paths(int x) {
+                if (x) return 1; else return 2;
+            }
+            int infinite(void) {
+                for (;;) {}
+            }
+            int switched(int x) {
+                switch (x) {
+                case 1: return 1;
+                default: return 0;
+                }
+            }
+            int jumped(int x) {
+            again:
+                if (x--) goto again;
+                return x;
+            }
+            void nothing(void) {}
+            int main(void) {}
+            int missing(int x) {
+                if (x) return 1;
+            }
+            int broken(int x) {
+                while (1) { if (x) break; return 0; }
+            }
+            int no_default(int x) {
+                switch (x) { case 1: return 1; }
+            }
+            int repeated(int x) {
+                do { if (x) continue; return 0; } while (x);
+            }
+            "#,
+            expect![[r#"
+                return-type: control reaches end of non-void function `missing` at "}"
+                return-type: control reaches end of non-void function `broken` at "}"
+                return-type: control reaches end of non-void function `no_default` at "}"
+                return-type: control reaches end of non-void function `repeated` at "}""#]],
+        );
+    }
+
+    #[test]
+    fn test_unreachable_code() {
+        check_flow(
+            r#"
+            int f(int x) {
+                return x;
+                x = 1;
+                x = 2;
+            }
+            int g(int x) {
+                while (x) {
+                    continue;
+                    x++;
+                }
+                switch (x) {
+                    x = 3;
+                case 1:
+                    return 1;
+                    break;
+                default:
+                    goto done;
+                }
+                if (x) x = 4;
+            skipped:
+                x = 5;
+            done:
+                for (;;) {}
+                return x;
+            }
+            "#,
+            expect![[r#"
+                unreachable-code: code will never be executed at "x = 1;"
+                unreachable-code: code will never be executed at "x++;"
+                unreachable-code: code will never be executed at "x = 3;"
+                unreachable-code: code will never be executed at "if (x) x = 4;"
+                unreachable-code: code will never be executed at "return x;""#]],
+        );
+    }
+}
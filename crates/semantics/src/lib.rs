@@ -0,0 +1,48 @@
+//! Semantic analysis of the AST built by `wacc-parser`.
+//!
+//! The parser only checks what it needs to build a well-formed tree. The passes
+//! here check the rest of what makes a program valid C, and rewrite the AST into
+//! the form the later stages assume:
+//!
+//! - [`resolve`] binds every identifier to its declaration and renames locals so
+//!   that every one has a unique name.
+//! - [`typecheck`] gives every expression its type and makes the conversions
+//!   between types explicit.
+//! - [`symbols`] collects every function and static object with its linkage,
+//!   whether it is defined, and its initial contents.
+//! - [`flow`] follows the control flow of every function body to find code that
+//!   is never reached and functions that can end without returning a value.
+//!
+//! Besides errors, the passes report [`warning`]s, which the driver filters by
+//! the `-W` flags it was given.
+
+use tokengen::span::{SourceSpan, Span};
+
+pub mod flow;
+pub mod resolve;
+pub mod symbols;
+pub mod typecheck;
+pub mod warning;
+
+/// An error found by one of the passes here, shown the same way as a
+/// [`wacc_parser::ParseError`].
+#[derive(Debug, thiserror::Error)]
+#[error("{location}: error: {context}:\n{source}", location = span.location(), source = span.span())]
+pub struct SemanticError {
+    span: SourceSpan,
+    context: String,
+}
+impl SemanticError {
+    pub fn new(span: SourceSpan, context: &str) -> Self {
+        Self {
+            span,
+            context: context.into(),
+        }
+    }
+    pub fn span(&self) -> SourceSpan {
+        self.span
+    }
+    pub fn context(&self) -> &str {
+        &self.context
+    }
+}
@@ -0,0 +1,1384 @@
+//! Identifier resolution.
+//!
+//! Every ordinary identifier is bound to the declaration it refers to, following
+//! C's block scoping: a name is in scope from the end of its declarator to the end
+//! of the block declaring it, and an inner declaration hides an outer one. The
+//! scopes and the symbols declared in them are kept as a tree in [`Resolution`].
+//!
+//! Functions and variables declared at file scope or `extern` have linkage
+//! (C17 6.2.2): every declaration of one of them refers to the same entity, even
+//! from different scopes. Variables without linkage (locals, including `static`
+//! ones, and parameters) are renamed as they are resolved, so `x` becomes `x.3`,
+//! and later stages can tell every one of them apart by name alone. Everything
+//! else keeps its name.
+//!
+//! Along the way this reports uses of undeclared identifiers, conflicting
+//! declarations, and operands that must be lvalues but aren't. It also warns
+//! about locals and parameters that are never used, and locals that shadow
+//! another declaration.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+use tokengen::span::{SourceSpan, Span};
+use wacc_parser::{
+    ast::{
+        Block, BlockItem, Declaration, DeclarationSpecifiers, Declarator, DeclaratorKind,
+        DesignatorKind, Expression, ExpressionKind, ExternalDeclaration, ForInit,
+        FunctionDefinition, Identifier, InitDeclarator, Initializer, InitializerEntry,
+        InitializerEntryKind, InitializerKind, ParameterList, Program, Statement, StatementKind,
+        StorageClass, TypeName, TypeSpecifier, UnaryOperator,
+    },
+    types::{QualifiedType, TagTable, Type},
+};
+
+use crate::{
+    warning::{Warning, WarningKind},
+    SemanticError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScopeId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SymbolId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeKind {
+    File,
+    /// A function's parameters and the outermost block of its body, which share a scope.
+    Function,
+    Block,
+    /// The parameters of a function declarator that isn't part of a definition.
+    Prototype,
+}
+
+#[derive(Debug, Clone)]
+pub struct Scope {
+    pub kind: ScopeKind,
+    pub parent: Option<ScopeId>,
+    pub children: Vec<ScopeId>,
+    /// The symbols declared in this scope, in order of declaration.
+    pub symbols: Vec<SymbolId>,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Variable,
+    Parameter,
+    Function,
+    Typedef,
+    EnumConstant(i64),
+}
+
+/// Whether declarations of a name in different scopes refer to the same entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Linkage {
+    /// Every declaration is of a different entity, as with locals and parameters.
+    None,
+    /// Declarations in this translation unit refer to the same entity, which was
+    /// declared `static` at file scope.
+    Internal,
+    /// Declarations in every translation unit of the program refer to the same entity.
+    External,
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    /// The name as written in the source.
+    pub name: String,
+    /// The name every reference to this symbol is renamed to.
+    pub unique_name: String,
+    pub kind: SymbolKind,
+    /// The type, completed by later declarations as in `extern int a[]; int a[3];`.
+    pub ty: QualifiedType,
+    pub linkage: Linkage,
+    /// Whether this is a variable that lives as long as the program does, because
+    /// it has linkage or was declared `static`.
+    pub is_static: bool,
+    pub scope: ScopeId,
+    /// The identifier of the first declaration.
+    pub span: SourceSpan,
+}
+
+/// The scope tree of a translation unit and what every identifier in it refers to.
+#[derive(Debug, Clone, Default)]
+pub struct Resolution {
+    scopes: Vec<Scope>,
+    symbols: Vec<Symbol>,
+    /// The symbol bound to each identifier, by the offset of the identifier in the source.
+    bindings: HashMap<usize, SymbolId>,
+}
+impl Resolution {
+    /// The file scope, the root of the scope tree.
+    pub fn file_scope(&self) -> ScopeId {
+        ScopeId(0)
+    }
+    pub fn scope(&self, id: ScopeId) -> &Scope {
+        &self.scopes[id.0]
+    }
+    pub fn symbol(&self, id: SymbolId) -> &Symbol {
+        &self.symbols[id.0]
+    }
+    pub fn symbols(&self) -> impl Iterator<Item = (SymbolId, &Symbol)> {
+        self.symbols
+            .iter()
+            .enumerate()
+            .map(|(i, symbol)| (SymbolId(i), symbol))
+    }
+    /// The symbol an identifier in the resolved program declares or refers to.
+    pub fn binding(&self, identifier: &Identifier) -> Option<SymbolId> {
+        self.bindings.get(&identifier.span.start()).copied()
+    }
+    /// An indented outline of the scope tree, leaving out scopes that declare
+    /// nothing at all, for debugging and tests.
+    pub fn debug_tree(&self) -> String {
+        let mut output = String::new();
+        if !self.scopes.is_empty() {
+            self.write_scope(self.file_scope(), 0, &mut output);
+        }
+        output
+    }
+    fn write_scope(&self, id: ScopeId, depth: usize, output: &mut String) {
+        let scope = self.scope(id);
+        let indent = "  ".repeat(depth);
+        writeln!(
+            output,
+            "{indent}{:?}@{}..{}",
+            scope.kind,
+            scope.span.start(),
+            scope.span.end()
+        )
+        .unwrap();
+        for &symbol in &scope.symbols {
+            let symbol = self.symbol(symbol);
+            let kind = match symbol.kind {
+                SymbolKind::Variable => "variable".to_string(),
+                SymbolKind::Parameter => "parameter".to_string(),
+                SymbolKind::Function => "function".to_string(),
+                SymbolKind::Typedef => "typedef".to_string(),
+                SymbolKind::EnumConstant(value) => format!("enum constant {value}"),
+            };
+            let storage = match (symbol.linkage, symbol.is_static) {
+                (Linkage::External, _) => " (external)",
+                (Linkage::Internal, _) => " (internal)",
+                (Linkage::None, true) => " (static)",
+                (Linkage::None, false) => "",
+            };
+            writeln!(
+                output,
+                "{indent}  {kind} {}: {}{storage}",
+                symbol.unique_name, symbol.ty
+            )
+            .unwrap();
+        }
+        for &child in &scope.children {
+            if !self.is_empty_subtree(child) {
+                self.write_scope(child, depth + 1, output);
+            }
+        }
+    }
+    fn is_empty_subtree(&self, id: ScopeId) -> bool {
+        let scope = self.scope(id);
+        scope.symbols.is_empty()
+            && scope
+                .children
+                .iter()
+                .all(|&child| self.is_empty_subtree(child))
+    }
+}
+
+/// Resolves the identifiers of a program, renaming them in place.
+#[derive(Default)]
+pub struct Resolver {
+    resolution: Resolution,
+    /// The scopes enclosing the current point, innermost last, with the names declared in each.
+    stack: Vec<(ScopeId, HashMap<String, SymbolId>)>,
+    /// How many locals have been renamed so far, to number the next one.
+    counter: usize,
+    /// Every symbol with linkage, by name, whether or not its declaration is in scope.
+    linked: HashMap<String, SymbolId>,
+    /// The symbols with linkage that have been defined, to catch a second definition.
+    defined: HashSet<SymbolId>,
+    /// The symbols some identifier has referred to, besides declaring them.
+    used: HashSet<SymbolId>,
+    tags: TagTable,
+    errors: Vec<SemanticError>,
+    warnings: Vec<Warning>,
+    /// Set while walking copies of expressions that have already been resolved,
+    /// whose errors have already been reported and declarations already made.
+    quiet: bool,
+}
+impl Resolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every error reported so far, in source order.
+    pub fn errors(&self) -> &[SemanticError] {
+        &self.errors
+    }
+
+    /// Every warning reported so far, in source order.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    pub fn resolve_program(&mut self, program: &mut Program) -> Resolution {
+        self.tags.clone_from(&program.tags);
+        self.push_scope(ScopeKind::File, program.span);
+        for item in &mut program.items {
+            match item {
+                ExternalDeclaration::Function(function) => self.function_definition(function),
+                ExternalDeclaration::Declaration(declaration) => self.declaration(declaration),
+                ExternalDeclaration::StaticAssert(static_assert) => {
+                    self.expression(&mut static_assert.condition)
+                }
+                ExternalDeclaration::Error(_) => {}
+            }
+        }
+        self.pop_scope();
+        self.errors.sort_by_key(|err| err.span().start());
+        self.warnings.sort_by_key(|warning| warning.span().start());
+        std::mem::take(&mut self.resolution)
+    }
+
+    fn error(&mut self, span: SourceSpan, context: &str) {
+        if !self.quiet {
+            self.errors.push(SemanticError::new(span, context));
+        }
+    }
+
+    fn warn(&mut self, kind: WarningKind, span: SourceSpan, context: &str) {
+        if !self.quiet {
+            self.warnings.push(Warning::new(kind, span, context));
+        }
+    }
+
+    fn push_scope(&mut self, kind: ScopeKind, span: SourceSpan) {
+        let id = ScopeId(self.resolution.scopes.len());
+        let parent = self.stack.last().map(|(parent, _)| *parent);
+        if let Some(parent) = parent {
+            self.resolution.scopes[parent.0].children.push(id);
+        }
+        self.resolution.scopes.push(Scope {
+            kind,
+            parent,
+            children: Vec::new(),
+            symbols: Vec::new(),
+            span,
+        });
+        self.stack.push((id, HashMap::new()));
+    }
+    fn pop_scope(&mut self) {
+        let (id, _) = self
+            .stack
+            .pop()
+            .expect("scopes are pushed and popped in pairs");
+        self.warn_unused(id);
+    }
+
+    /// Warn about the locals and parameters declared in a scope that nothing referred to.
+    fn warn_unused(&mut self, id: ScopeId) {
+        let scope = self.resolution.scope(id);
+        if !matches!(scope.kind, ScopeKind::Function | ScopeKind::Block) {
+            return;
+        }
+        let unused = scope
+            .symbols
+            .iter()
+            .map(|&symbol| (symbol, self.resolution.symbol(symbol)))
+            .filter(|(symbol, declared)| {
+                declared.scope == id
+                    && declared.linkage == Linkage::None
+                    && !self.used.contains(symbol)
+            })
+            .filter_map(|(_, symbol)| match symbol.kind {
+                SymbolKind::Variable => Some((WarningKind::UnusedVariable, "variable", symbol)),
+                SymbolKind::Parameter => Some((WarningKind::UnusedParameter, "parameter", symbol)),
+                _ => None,
+            })
+            .map(|(kind, what, symbol)| {
+                Warning::new(
+                    kind,
+                    symbol.span,
+                    &format!("unused {what} `{}`", symbol.name),
+                )
+            })
+            .collect::<Vec<_>>();
+        self.warnings.extend(unused);
+    }
+
+    /// Warn if a new local or parameter hides a declaration in an enclosing scope.
+    fn warn_shadowing(&mut self, identifier: &Identifier) {
+        let Some((scope, _)) = self.stack.last() else {
+            return;
+        };
+        if !matches!(
+            self.resolution.scope(*scope).kind,
+            ScopeKind::Function | ScopeKind::Block
+        ) {
+            return;
+        }
+        let shadowed = self.stack[..self.stack.len() - 1]
+            .iter()
+            .rev()
+            .find_map(|(_, names)| names.get(&identifier.name).copied());
+        let Some(shadowed) = shadowed else {
+            return;
+        };
+        let shadowed = self.resolution.symbol(shadowed);
+        let what = match shadowed.kind {
+            SymbolKind::Parameter => "a parameter",
+            _ if shadowed.scope == self.resolution.file_scope() => "a global declaration",
+            _ => "a previous local",
+        };
+        let context = format!("declaration of `{}` shadows {what}", identifier.name);
+        self.warn(WarningKind::Shadow, identifier.span, &context);
+    }
+    fn in_scope(&mut self, kind: ScopeKind, span: SourceSpan, resolve: impl FnOnce(&mut Self)) {
+        self.push_scope(kind, span);
+        resolve(self);
+        self.pop_scope();
+    }
+    fn is_file_scope(&self) -> bool {
+        self.stack.len() == 1
+    }
+
+    fn bind(&mut self, identifier: &mut Identifier, id: SymbolId) {
+        self.resolution.bindings.insert(identifier.span.start(), id);
+        identifier
+            .name
+            .clone_from(&self.resolution.symbol(id).unique_name);
+    }
+
+    /// Declare `identifier` in the innermost scope and rename it.
+    ///
+    /// A declaration with linkage binds to the entity any earlier declaration with
+    /// linkage declared, even one no longer in scope.
+    fn declare(
+        &mut self,
+        identifier: &mut Identifier,
+        kind: SymbolKind,
+        ty: &QualifiedType,
+        storage_class: Option<StorageClass>,
+        is_definition: bool,
+    ) {
+        let linkage = self.linkage(&identifier.name, kind, storage_class);
+        let linked = match linkage {
+            Linkage::None => None,
+            Linkage::Internal | Linkage::External => self.linked.get(&identifier.name).copied(),
+        };
+        let (scope, names) = self.stack.last().expect("the file scope is always open");
+        let (scope, previous) = (*scope, names.get(&identifier.name).copied());
+        if let Some(previous) = previous {
+            if self.quiet {
+                return self.bind(identifier, previous);
+            }
+            let symbol = self.resolution.symbol(previous);
+            let is_redeclaration = Some(previous) == linked
+                || (kind == SymbolKind::Typedef
+                    && symbol.kind == SymbolKind::Typedef
+                    && symbol.ty == *ty);
+            if !is_redeclaration {
+                let is_object = |kind| matches!(kind, SymbolKind::Variable | SymbolKind::Parameter);
+                let name = &identifier.name;
+                let context = if !is_object(symbol.kind) || !is_object(kind) {
+                    match symbol.kind == kind {
+                        true => format!("redefinition of `{name}`"),
+                        false => format!("`{name}` redeclared as a different kind of symbol"),
+                    }
+                } else if symbol.linkage == Linkage::None && linkage != Linkage::None {
+                    format!("`extern` declaration of `{name}` follows non-`extern` declaration")
+                } else if symbol.linkage != Linkage::None && linkage == Linkage::None {
+                    format!("non-`extern` declaration of `{name}` follows `extern` declaration")
+                } else {
+                    format!("redefinition of `{name}`")
+                };
+                self.error(identifier.span, &context);
+            } else if kind == SymbolKind::Typedef {
+                return self.bind(identifier, previous);
+            }
+        }
+        if let Some(id) = linked {
+            self.redeclare(identifier, id, kind, ty, linkage, is_definition);
+            if previous != Some(id) {
+                self.resolution.scopes[scope.0].symbols.push(id);
+                let (_, names) = self
+                    .stack
+                    .last_mut()
+                    .expect("the file scope is always open");
+                names.insert(identifier.name.clone(), id);
+            }
+            return self.bind(identifier, id);
+        }
+
+        if matches!(kind, SymbolKind::Variable | SymbolKind::Parameter) && linkage == Linkage::None
+        {
+            self.warn_shadowing(identifier);
+        }
+        let unique_name = match kind {
+            SymbolKind::Variable | SymbolKind::Parameter if linkage == Linkage::None => {
+                self.counter += 1;
+                format!("{}.{}", identifier.name, self.counter - 1)
+            }
+            _ => identifier.name.clone(),
+        };
+        let id = SymbolId(self.resolution.symbols.len());
+        self.resolution.symbols.push(Symbol {
+            name: identifier.name.clone(),
+            unique_name,
+            kind,
+            ty: ty.clone(),
+            linkage,
+            is_static: kind == SymbolKind::Variable
+                && (linkage != Linkage::None || storage_class == Some(StorageClass::Static)),
+            scope,
+            span: identifier.span,
+        });
+        self.resolution.scopes[scope.0].symbols.push(id);
+        let (_, names) = self
+            .stack
+            .last_mut()
+            .expect("the file scope is always open");
+        names.insert(identifier.name.clone(), id);
+        if linkage != Linkage::None {
+            self.linked.insert(identifier.name.clone(), id);
+        }
+        if is_definition {
+            self.defined.insert(id);
+        }
+        self.bind(identifier, id);
+    }
+
+    /// The linkage a declaration gives `name` (C17 6.2.2).
+    fn linkage(
+        &self,
+        name: &str,
+        kind: SymbolKind,
+        storage_class: Option<StorageClass>,
+    ) -> Linkage {
+        let is_file_scope = self.is_file_scope();
+        match (kind, storage_class) {
+            (SymbolKind::Parameter | SymbolKind::Typedef | SymbolKind::EnumConstant(_), _) => {
+                Linkage::None
+            }
+            (_, Some(StorageClass::Static)) if is_file_scope => Linkage::Internal,
+            (SymbolKind::Variable, None | Some(StorageClass::Static)) if !is_file_scope => {
+                Linkage::None
+            }
+            (SymbolKind::Variable, None) => Linkage::External,
+            // `extern`, and functions without a storage class, take the linkage of
+            // the declaration in scope, if it has any.
+            _ => {
+                let visible = self
+                    .stack
+                    .iter()
+                    .rev()
+                    .find_map(|(_, names)| names.get(name).copied())
+                    .map(|id| self.resolution.symbol(id).linkage);
+                match visible {
+                    Some(linkage @ (Linkage::Internal | Linkage::External)) => linkage,
+                    Some(Linkage::None) | None => Linkage::External,
+                }
+            }
+        }
+    }
+
+    /// Check a later declaration of the entity `id` against the earlier ones,
+    /// completing its type if this one says more.
+    fn redeclare(
+        &mut self,
+        identifier: &Identifier,
+        id: SymbolId,
+        kind: SymbolKind,
+        ty: &QualifiedType,
+        linkage: Linkage,
+        is_definition: bool,
+    ) {
+        let name = &identifier.name;
+        let symbol = self.resolution.symbol(id);
+        let context = if symbol.kind != kind {
+            Some(format!("`{name}` redeclared as a different kind of symbol"))
+        } else if symbol.linkage == Linkage::External && linkage == Linkage::Internal {
+            Some(format!(
+                "static declaration of `{name}` follows non-static declaration"
+            ))
+        } else if symbol.linkage == Linkage::Internal && linkage == Linkage::External {
+            Some(format!(
+                "non-static declaration of `{name}` follows static declaration"
+            ))
+        } else if symbol.ty.qualifiers != ty.qualifiers
+            || !self.tags.compatible(&symbol.ty.ty, &ty.ty)
+        {
+            Some(format!("conflicting types for `{name}`"))
+        } else if is_definition && self.defined.contains(&id) {
+            Some(format!("redefinition of `{name}`"))
+        } else {
+            None
+        };
+        if let Some(context) = context {
+            return self.error(identifier.span, &context);
+        }
+        if is_definition {
+            self.defined.insert(id);
+        }
+        // The composite type (C17 6.2.7) is whichever of the two says more.
+        let more_complete = match (&symbol.ty.ty, &ty.ty) {
+            (Type::Array(_, None), Type::Array(_, Some(_))) => true,
+            (Type::Function(old), Type::Function(new)) => !old.is_prototype && new.is_prototype,
+            _ => false,
+        };
+        if more_complete {
+            self.resolution.symbols[id.0].ty = ty.clone();
+        }
+    }
+
+    /// Bind a use of `identifier` to the innermost declaration of its name.
+    fn lookup(&mut self, identifier: &mut Identifier) -> Option<SymbolId> {
+        let id = self
+            .stack
+            .iter()
+            .rev()
+            .find_map(|(_, names)| names.get(&identifier.name).copied());
+        match id {
+            Some(id) => {
+                self.used.insert(id);
+                self.bind(identifier, id);
+            }
+            None => self.error(
+                identifier.span,
+                &format!("use of undeclared identifier `{}`", identifier.name),
+            ),
+        }
+        id
+    }
+
+    fn function_definition(&mut self, function: &mut FunctionDefinition) {
+        self.specifiers(&mut function.specifiers);
+        if let Some(name) = declarator_name(&mut function.declarator) {
+            let storage_class = function.specifiers.storage_class;
+            self.declare(
+                name,
+                SymbolKind::Function,
+                &function.ty,
+                storage_class,
+                true,
+            );
+            function.name.clone_from(name);
+        }
+        let FunctionDefinition {
+            declarator,
+            body,
+            span,
+            ..
+        } = function;
+        self.in_scope(ScopeKind::Function, *span, |resolver| {
+            resolver.declarator_parts(declarator, true);
+            resolver.block_items(&mut body.items);
+        });
+        if let Some(parameters) = function_parameters(&function.declarator) {
+            function.parameters.clone_from(&parameters.parameters);
+        }
+    }
+
+    fn declaration(&mut self, declaration: &mut Declaration) {
+        self.specifiers(&mut declaration.specifiers);
+        let storage_class = declaration.specifiers.storage_class;
+        for declarator in &mut declaration.declarators {
+            self.init_declarator(declarator, storage_class);
+        }
+    }
+
+    fn init_declarator(
+        &mut self,
+        declarator: &mut InitDeclarator,
+        storage_class: Option<StorageClass>,
+    ) {
+        self.declarator_parts(&mut declarator.declarator, false);
+        let kind = match (storage_class, declarator.ty.ty.is_function()) {
+            (Some(StorageClass::Typedef), _) => SymbolKind::Typedef,
+            (_, true) => SymbolKind::Function,
+            (_, false) => SymbolKind::Variable,
+        };
+        let is_block_scope = !self.is_file_scope();
+        if let Some(name) = declarator_name(&mut declarator.declarator) {
+            if is_block_scope {
+                match (kind, storage_class) {
+                    (SymbolKind::Function, Some(StorageClass::Static)) => self.error(
+                        name.span,
+                        "function declared in block scope cannot have `static` storage class",
+                    ),
+                    (SymbolKind::Variable, Some(StorageClass::Extern))
+                        if declarator.initializer.is_some() =>
+                    {
+                        self.error(
+                            name.span,
+                            "declaration of block scope identifier with linkage cannot have an initializer",
+                        )
+                    }
+                    _ => {}
+                }
+            }
+            let is_definition = declarator.initializer.is_some();
+            self.declare(name, kind, &declarator.ty, storage_class, is_definition);
+            declarator.name.clone_from(name);
+        }
+        if let Some(initializer) = &mut declarator.initializer {
+            self.initializer(initializer);
+            self.entries(&mut declarator.entries);
+        }
+    }
+
+    /// Struct and enum definitions in declaration specifiers, and typedef names.
+    fn specifiers(&mut self, specifiers: &mut DeclarationSpecifiers) {
+        for specifier in &mut specifiers.type_specifiers {
+            match specifier {
+                TypeSpecifier::TypedefName(name) => {
+                    self.lookup(name);
+                }
+                TypeSpecifier::Record(record) => {
+                    for member in record.members.iter_mut().flatten() {
+                        self.specifiers(&mut member.specifiers);
+                        for declarator in &mut member.declarators {
+                            self.declarator_parts(&mut declarator.declarator, false);
+                            if let Some(width) = &mut declarator.bit_width {
+                                self.expression(width);
+                            }
+                        }
+                    }
+                }
+                TypeSpecifier::Enum(enumeration) => {
+                    let ty = QualifiedType::unqualified(Type::Int);
+                    for enumerator in enumeration.enumerators.iter_mut().flatten() {
+                        if let Some(value) = &mut enumerator.value {
+                            self.expression(value);
+                        }
+                        let kind = SymbolKind::EnumConstant(enumerator.constant);
+                        self.declare(&mut enumerator.name, kind, &ty, None, false);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The array lengths and parameter lists of a declarator, but not the name it declares.
+    ///
+    /// Parameters get a prototype scope of their own, except for those of the
+    /// function a definition defines, which go in the scope already open for its body.
+    fn declarator_parts(&mut self, declarator: &mut Declarator, is_definition: bool) {
+        match &mut declarator.kind {
+            DeclaratorKind::Identifier(_) => {}
+            DeclaratorKind::Pointer(_, inner) => self.declarator_parts(inner, is_definition),
+            DeclaratorKind::Array(inner, length) => {
+                self.declarator_parts(inner, is_definition);
+                if let Some(length) = length {
+                    self.expression(length);
+                }
+            }
+            DeclaratorKind::Function(inner, parameters) => {
+                let is_defined =
+                    is_definition && matches!(inner.kind, DeclaratorKind::Identifier(_));
+                self.declarator_parts(inner, is_definition);
+                match is_defined || parameters.parameters.is_empty() {
+                    true => self.parameters(parameters),
+                    false => self.in_scope(ScopeKind::Prototype, parameters.span, |resolver| {
+                        resolver.parameters(parameters)
+                    }),
+                }
+            }
+        }
+    }
+
+    fn parameters(&mut self, parameters: &mut ParameterList) {
+        for parameter in &mut parameters.parameters {
+            self.specifiers(&mut parameter.specifiers);
+            self.declarator_parts(&mut parameter.declarator, false);
+            if let Some(name) = declarator_name(&mut parameter.declarator) {
+                self.declare(name, SymbolKind::Parameter, &parameter.ty, None, false);
+                parameter.name = Some(name.clone());
+            }
+        }
+    }
+
+    fn type_name(&mut self, type_name: &mut TypeName) {
+        self.specifiers(&mut type_name.specifiers);
+        self.declarator_parts(&mut type_name.declarator, false);
+    }
+
+    fn initializer(&mut self, initializer: &mut Initializer) {
+        match &mut initializer.kind {
+            InitializerKind::Expression(expression) => self.expression(expression),
+            InitializerKind::List(items) => {
+                for item in items {
+                    for designator in &mut item.designators {
+                        if let DesignatorKind::Index(index, _) = &mut designator.kind {
+                            self.expression(index);
+                        }
+                    }
+                    self.initializer(&mut item.initializer);
+                }
+            }
+        }
+    }
+
+    /// The flattened initializer entries, whose values are copies of expressions in
+    /// an initializer that has already been resolved, and only need renaming.
+    fn entries(&mut self, entries: &mut [InitializerEntry]) {
+        let quiet = std::mem::replace(&mut self.quiet, true);
+        for entry in entries {
+            match &mut entry.kind {
+                InitializerEntryKind::Value(_, value)
+                | InitializerEntryKind::BitField(_, _, _, value) => self.expression(value),
+                InitializerEntryKind::Zero(_) | InitializerEntryKind::Bytes(_) => {}
+            }
+        }
+        self.quiet = quiet;
+    }
+
+    fn block_items(&mut self, items: &mut [BlockItem]) {
+        for item in items {
+            match item {
+                BlockItem::Declaration(declaration) => self.declaration(declaration),
+                BlockItem::StaticAssert(static_assert) => {
+                    self.expression(&mut static_assert.condition)
+                }
+                BlockItem::Statement(statement) => self.statement(statement),
+            }
+        }
+    }
+
+    fn block(&mut self, block: &mut Block) {
+        let Block { items, span } = block;
+        self.in_scope(ScopeKind::Block, *span, |resolver| {
+            resolver.block_items(items)
+        });
+    }
+
+    fn statement(&mut self, statement: &mut Statement) {
+        let span = statement.span;
+        match &mut statement.kind {
+            StatementKind::Return(value) => {
+                if let Some(value) = value {
+                    self.expression(value);
+                }
+            }
+            StatementKind::Expression(expression) => self.expression(expression),
+            StatementKind::Compound(block) => self.block(block),
+            // Selection and iteration statements are blocks of their own.
+            StatementKind::If(condition, then, els) => {
+                self.in_scope(ScopeKind::Block, span, |resolver| {
+                    resolver.expression(condition);
+                    resolver.statement(then);
+                    if let Some(els) = els {
+                        resolver.statement(els);
+                    }
+                })
+            }
+            StatementKind::While(condition, body) | StatementKind::Switch(condition, body) => self
+                .in_scope(ScopeKind::Block, span, |resolver| {
+                    resolver.expression(condition);
+                    resolver.statement(body);
+                }),
+            StatementKind::DoWhile(body, condition) => {
+                self.in_scope(ScopeKind::Block, span, |resolver| {
+                    resolver.statement(body);
+                    resolver.expression(condition);
+                })
+            }
+            StatementKind::For(init, condition, step, body) => {
+                self.in_scope(ScopeKind::Block, span, |resolver| {
+                    match &mut **init {
+                        ForInit::Declaration(declaration) => resolver.declaration(declaration),
+                        ForInit::Expression(expression) => {
+                            if let Some(expression) = expression {
+                                resolver.expression(expression);
+                            }
+                        }
+                    }
+                    if let Some(condition) = condition {
+                        resolver.expression(condition);
+                    }
+                    if let Some(step) = step {
+                        resolver.expression(step);
+                    }
+                    resolver.statement(body);
+                })
+            }
+            StatementKind::Case(expression, _, body) => {
+                self.expression(expression);
+                self.statement(body);
+            }
+            StatementKind::Default(body) | StatementKind::Labeled(_, body) => self.statement(body),
+            StatementKind::Null
+            | StatementKind::Break
+            | StatementKind::Continue
+            | StatementKind::Goto(_)
+            | StatementKind::Error => {}
+        }
+    }
+
+    fn expression(&mut self, expression: &mut Expression) {
+        match &mut expression.kind {
+            ExpressionKind::Identifier(name) => {
+                self.lookup(name);
+            }
+            ExpressionKind::Unary(operator, operand) => {
+                self.expression(operand);
+                match operator {
+                    UnaryOperator::PreIncrement | UnaryOperator::PreDecrement => {
+                        self.check_assignable(operand)
+                    }
+                    UnaryOperator::AddressOf if !self.is_addressable(operand) => {
+                        self.error(operand.span, "cannot take the address of an rvalue")
+                    }
+                    _ => {}
+                }
+            }
+            ExpressionKind::Postfix(_, operand) => {
+                self.expression(operand);
+                self.check_assignable(operand);
+            }
+            ExpressionKind::Assignment(_, lhs, rhs) => {
+                self.expression(lhs);
+                self.expression(rhs);
+                self.check_assignable(lhs);
+            }
+            ExpressionKind::Binary(_, lhs, rhs) | ExpressionKind::Subscript(lhs, rhs) => {
+                self.expression(lhs);
+                self.expression(rhs);
+            }
+            ExpressionKind::Conditional(condition, then, els) => {
+                self.expression(condition);
+                self.expression(then);
+                self.expression(els);
+            }
+            ExpressionKind::Cast(type_name, operand) => {
+                self.type_name(type_name);
+                self.expression(operand);
+            }
+            ExpressionKind::CompoundLiteral(literal) => {
+                self.type_name(&mut literal.type_name);
+                self.initializer(&mut literal.initializer);
+                self.entries(&mut literal.entries);
+            }
+            ExpressionKind::SizeofExpression(operand)
+            | ExpressionKind::ImplicitConversion(_, operand)
+            | ExpressionKind::Member(operand, _)
+            | ExpressionKind::PointerMember(operand, _) => self.expression(operand),
+            ExpressionKind::SizeofType(type_name) => self.type_name(type_name),
+            ExpressionKind::Offsetof(type_name, designators, _) => {
+                self.type_name(type_name);
+                for designator in designators {
+                    if let DesignatorKind::Index(index, _) = &mut designator.kind {
+                        self.expression(index);
+                    }
+                }
+            }
+            ExpressionKind::Call(callee, arguments) => {
+                self.expression(callee);
+                for argument in arguments {
+                    self.expression(argument);
+                }
+            }
+            ExpressionKind::Constant(_)
+            | ExpressionKind::StringLiteral(_)
+            | ExpressionKind::Error => {}
+        }
+    }
+
+    /// The kind of symbol a resolved identifier expression refers to, if it was declared.
+    fn symbol_kind(&self, expression: &Expression) -> Option<SymbolKind> {
+        match &expression.kind {
+            ExpressionKind::Identifier(name) => self
+                .resolution
+                .binding(name)
+                .map(|id| self.resolution.symbol(id).kind),
+            _ => None,
+        }
+    }
+
+    /// Whether `expression` designates an object, which is what C calls an lvalue.
+    fn is_lvalue(&self, expression: &Expression) -> bool {
+        match &expression.kind {
+            ExpressionKind::Identifier(_) => matches!(
+                self.symbol_kind(expression),
+                Some(SymbolKind::Variable | SymbolKind::Parameter) | None
+            ),
+            ExpressionKind::Unary(UnaryOperator::Dereference, _)
+            | ExpressionKind::Subscript(..)
+            | ExpressionKind::PointerMember(..)
+            | ExpressionKind::StringLiteral(_)
+            | ExpressionKind::CompoundLiteral(_)
+            | ExpressionKind::Error => true,
+            ExpressionKind::Member(base, _) => self.is_lvalue(base),
+            _ => false,
+        }
+    }
+
+    /// The operand of `&` must be an lvalue or a function designator.
+    fn is_addressable(&self, expression: &Expression) -> bool {
+        self.is_lvalue(expression) || self.symbol_kind(expression) == Some(SymbolKind::Function)
+    }
+
+    /// The operand of an assignment, `++` or `--` must be an lvalue, and one that
+    /// isn't an array like a string literal.
+    fn check_assignable(&mut self, expression: &Expression) {
+        let is_string = matches!(expression.kind, ExpressionKind::StringLiteral(_));
+        if !self.is_lvalue(expression) || is_string {
+            self.error(expression.span, "expression is not assignable");
+        }
+    }
+}
+
+/// The identifier a declarator declares, if it isn't abstract.
+fn declarator_name(declarator: &mut Declarator) -> Option<&mut Identifier> {
+    match &mut declarator.kind {
+        DeclaratorKind::Identifier(name) => name.as_mut(),
+        DeclaratorKind::Pointer(_, inner)
+        | DeclaratorKind::Array(inner, _)
+        | DeclaratorKind::Function(inner, _) => declarator_name(inner),
+    }
+}
+
+/// The parameter list of the function a declarator declares, if it declares one.
+fn function_parameters(declarator: &Declarator) -> Option<&ParameterList> {
+    match &declarator.kind {
+        DeclaratorKind::Function(inner, parameters)
+            if matches!(inner.kind, DeclaratorKind::Identifier(_)) =>
+        {
+            Some(parameters)
+        }
+        DeclaratorKind::Pointer(_, inner)
+        | DeclaratorKind::Array(inner, _)
+        | DeclaratorKind::Function(inner, _) => function_parameters(inner),
+        DeclaratorKind::Identifier(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod resolve_tests {
+    use expect_test::{expect, Expect};
+    use tokengen::span::Span;
+    use wacc_lexer::Lexer;
+    use wacc_parser::{
+        dump::{dump_program, DumpFormat},
+        Parser,
+    };
+
+    use super::Resolver;
+
+    /// The scope tree, then the program with every identifier renamed, then any errors.
+    fn check_resolve(src: &str, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let mut program = parser.parse_program();
+        assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+        let mut resolver = Resolver::new();
+        let resolution = resolver.resolve_program(&mut program);
+        let mut output = resolution.debug_tree();
+        output.push('\n');
+        output.push_str(&dump_program(&program, DumpFormat::C));
+        for err in resolver.errors() {
+            output.push_str(&format!("\n{} at {:?}", err.context(), err.span().span()));
+        }
+        expect.assert_eq(&output);
+    }
+
+    #[test]
+    fn test_shadowing() {
+        check_resolve(
+            r#"
+            int x;
+            int f(int x, int y) {
+                int z = x;
+                {
+                    int x = x + y;
+                    z = x;
+                }
+                for (int x = 0; x < 3; x++) {
+                    int y = x;
+                    z += y;
+                }
+                return x + z;
+            }
+            "#,
+            expect![[r#"
+                File@13..345
+                  variable x: int (external)
+                  function f: int (int, int) (external)
+                  Function@32..345
+                    parameter x.0: int
+                    parameter y.1: int
+                    variable z.2: int
+                    Block@97..178
+                      variable x.3: int
+                    Block@195..301
+                      variable x.4: int
+                      Block@223..301
+                        variable y.5: int
+
+                int x;
+
+                int f(int x.0, int y.1) {
+                    int z.2 = x.0;
+                    {
+                        int x.3 = x.3 + y.1;
+                        z.2 = x.3;
+                    }
+                    for (int x.4 = 0; x.4 < 3; x.4++) {
+                        int y.5 = x.4;
+                        z.2 += y.5;
+                    }
+                    return x.0 + z.2;
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_other_symbols() {
+        check_resolve(
+            r#"
+            typedef int count;
+            enum colour { RED, GREEN = RED + 2 };
+            int apply(int (*callback)(int value), count n);
+            int main(void) {
+                count total = GREEN;
+                int apply(int (*)(int), count);
+                typedef long count;
+                count big = sizeof(enum { BLUE = 4 }) + BLUE;
+                int *p = &total, a[2] = { [RED] = total };
+                return apply(0, total) + big + *p + a[0];
+            }
+            "#,
+            expect![[r#"
+                File@13..484
+                  typedef count: int
+                  enum constant 0 RED: int
+                  enum constant 2 GREEN: int
+                  function apply: int (int (*)(int), int) (external)
+                  function main: int (void) (external)
+                  Prototype@103..140
+                    parameter callback.1: int (*)(int)
+                    parameter n.2: int
+                    Prototype@119..130
+                      parameter value.0: int
+                  Function@154..484
+                    variable total.3: int
+                    function apply: int (int (*)(int), int) (external)
+                    typedef count: long
+                    variable big.4: long
+                    enum constant 4 BLUE: int
+                    variable p.5: int *
+                    variable a.6: int [2]
+
+                typedef int count;
+                enum colour { RED, GREEN = RED + 2 };
+                int apply(int (*callback.1)(int value.0), count n.2);
+
+                int main(void) {
+                    count total.3 = GREEN;
+                    int apply(int (*)(int), count);
+                    typedef long count;
+                    count big.4 = sizeof(enum { BLUE = 4 }) + BLUE;
+                    int *p.5 = &total.3, a.6[2] = { [RED] = total.3 };
+                    return apply(0, total.3) + big.4 + *p.5 + a.6[0];
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        check_resolve(
+            r#"
+            int f(int a, int a);
+            int g;
+            typedef int g;
+            enum { A };
+            int main(int argc) {
+                int argc = 1;
+                int x;
+                long x;
+                y = 3;
+                2 = x;
+                x + 1 = 4;
+                A++;
+                main = 0;
+                --"s";
+                &(x + 1);
+                &main;
+                &"s";
+                *&x = 1;
+                return undeclared(x);
+            }
+            "#,
+            expect![[r#"
+                File@13..504
+                  function f: int (int, int) (external)
+                  variable g: int (external)
+                  typedef g: int
+                  enum constant 0 A: int
+                  function main: int (int) (external)
+                  Prototype@18..32
+                    parameter a.0: int
+                    parameter a.1: int
+                  Function@116..504
+                    parameter argc.2: int
+                    variable argc.3: int
+                    variable x.4: int
+                    variable x.5: long
+
+                int f(int a.0, int a.1);
+                int g;
+                typedef int g;
+                enum { A };
+
+                int main(int argc.2) {
+                    int argc.3 = 1;
+                    int x.4;
+                    long x.5;
+                    y = 3;
+                    2 = x.5;
+                    (x.5 + 1) = 4;
+                    A++;
+                    main = 0;
+                    --"s";
+                    &(x.5 + 1);
+                    &main;
+                    &"s";
+                    *&x.5 = 1;
+                    return undeclared(x.5);
+                }
+
+                redefinition of `a` at "a"
+                `g` redeclared as a different kind of symbol at "g"
+                redefinition of `argc` at "argc"
+                redefinition of `x` at "x"
+                use of undeclared identifier `y` at "y"
+                expression is not assignable at "2"
+                expression is not assignable at "x + 1"
+                expression is not assignable at "A"
+                expression is not assignable at "main"
+                expression is not assignable at "\"s\""
+                cannot take the address of an rvalue at "(x + 1)"
+                use of undeclared identifier `undeclared` at "undeclared""#]],
+        );
+    }
+
+    #[test]
+    fn test_linkage() {
+        check_resolve(
+            r#"
+            static int counter;
+            extern int counter;
+            int total;
+            extern int shared[];
+            int shared[3];
+            static int next(void);
+            int main(void) {
+                static int calls;
+                extern int shared[3];
+                int total = counter;
+                {
+                    extern int total;
+                    int next(void);
+                    return total + next() + shared[calls++];
+                }
+            }
+            int next(void) { return 1; }
+            "#,
+            expect![[r#"
+                File@13..546
+                  variable counter: int (internal)
+                  variable total: int (external)
+                  variable shared: int [3] (external)
+                  function next: int (void) (internal)
+                  function main: int (void) (external)
+                  Function@195..505
+                    variable calls.0: int (static)
+                    variable shared: int [3] (external)
+                    variable total.1: int
+                    Block@337..491
+                      variable total: int (external)
+                      function next: int (void) (internal)
+
+                static int counter;
+                extern int counter;
+                int total;
+                extern int shared[];
+                int shared[3];
+                static int next(void);
+
+                int main(void) {
+                    static int calls.0;
+                    extern int shared[3];
+                    int total.1 = counter;
+                    {
+                        extern int total;
+                        int next(void);
+                        return total + next() + shared[calls.0++];
+                    }
+                }
+
+                int next(void) {
+                    return 1;
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_linkage_errors() {
+        check_resolve(
+            r#"
+            int a;
+            static int a;
+            static int b;
+            extern long b;
+            int c = 1;
+            int c = 2;
+            int f(void) { return 0; }
+            int f(void) { return 1; }
+            int main(void) {
+                int d;
+                extern int d;
+                extern int e;
+                int e;
+                extern int g = 3;
+                static int h(void);
+                return 0;
+            }
+            "#,
+            expect![[r#"
+                File@13..465
+                  variable a: int (external)
+                  variable b: int (internal)
+                  variable c: int (external)
+                  function f: int (void) (external)
+                  function main: int (void) (external)
+                  Function@233..465
+                    variable d.0: int
+                    variable d: int (external)
+                    variable e: int (external)
+                    variable e.1: int
+                    variable g: int (external)
+                    function h: int (void) (external)
+
+                int a;
+                static int a;
+                static int b;
+                extern long b;
+                int c = 1;
+                int c = 2;
+
+                int f(void) {
+                    return 0;
+                }
+
+                int f(void) {
+                    return 1;
+                }
+
+                int main(void) {
+                    int d.0;
+                    extern int d;
+                    extern int e;
+                    int e.1;
+                    extern int g = 3;
+                    static int h(void);
+                    return 0;
+                }
+
+                static declaration of `a` follows non-static declaration at "a"
+                conflicting types for `b` at "b"
+                redefinition of `c` at "c"
+                redefinition of `f` at "f"
+                `extern` declaration of `d` follows non-`extern` declaration at "d"
+                non-`extern` declaration of `e` follows `extern` declaration at "e"
+                declaration of block scope identifier with linkage cannot have an initializer at "g"
+                function declared in block scope cannot have `static` storage class at "h""#]],
+        );
+    }
+
+    /// The warnings about a program, with the source they point at.
+    fn check_warnings(src: &str, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let mut program = parser.parse_program();
+        assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+        let mut resolver = Resolver::new();
+        resolver.resolve_program(&mut program);
+        assert!(resolver.errors().is_empty(), "{:?}", resolver.errors());
+        let output = resolver
+            .warnings()
+            .iter()
+            .map(|warning| {
+                let kind = warning.kind();
+                format!(
+                    "{kind}: {} at {:?}",
+                    warning.context(),
+                    warning.span().span()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        expect.assert_eq(&output);
+    }
+
+    #[test]
+    fn test_warnings() {
+        check_warnings(
+            r#"
+            int x;
+            int prototype(int x);
+            int f(int used, int unused, int (*callback)(int ignored)) {
+                int x = used;
+                static int calls;
+                extern int elsewhere;
+                for (int i = 0; i < x; i++) {
+                    int used = i;
+                    { int i; }
+                }
+                return callback(x);
+            }
+            "#,
+            expect![[r#"
+                unused-parameter: unused parameter `unused` at "unused"
+                shadow: declaration of `x` shadows a global declaration at "x"
+                unused-variable: unused variable `calls` at "calls"
+                shadow: declaration of `used` shadows a parameter at "used"
+                unused-variable: unused variable `used` at "used"
+                shadow: declaration of `i` shadows a previous local at "i"
+                unused-variable: unused variable `i` at "i""#]],
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_location() {
+        let src = "int main(void) {\n    int x;\n    return y;\n}\n";
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let mut program = parser.parse_program();
+        let mut resolver = Resolver::new();
+        resolver.resolve_program(&mut program);
+        let diagnostics: Vec<String> = resolver
+            .errors()
+            .iter()
+            .map(ToString::to_string)
+            .chain(resolver.warnings().iter().map(ToString::to_string))
+            .collect();
+        expect![[r#"
+            [
+                "3:12: error: use of undeclared identifier `y`:\ny",
+                "2:9: warning: unused variable `x` [-Wunused-variable]:\nx",
+            ]
+        "#]]
+        .assert_debug_eq(&diagnostics);
+    }
+}
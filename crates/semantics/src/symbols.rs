@@ -0,0 +1,621 @@
+//! The symbol table of everything with static storage duration.
+//!
+//! Functions, variables with linkage and `static` locals exist once for the whole
+//! program, however many times they are declared. This collects each of them
+//! from the resolved, type checked AST with its linkage, whether this
+//! translation unit defines it, and the bytes it starts out holding.
+//!
+//! A file-scope variable declared without an initializer or `extern` is a
+//! tentative definition (C17 6.9.2): if no declaration gives it an initializer
+//! it is defined as if initialized to zero, and an array whose length was never
+//! given gets a length of one.
+
+use std::{collections::HashMap, fmt::Write};
+
+use tokengen::span::{SourceSpan, Span};
+use wacc_parser::{
+    ast::{
+        Block, BlockItem, Declaration, Expression, ExternalDeclaration, Identifier,
+        InitializerEntry, InitializerEntryKind, Program, Statement, StatementKind, StorageClass,
+    },
+    constant::{Base, Entity, EvalError, Evaluator, Value},
+    types::{QualifiedType, TagTable, Type},
+};
+
+use crate::{
+    resolve::{Linkage, Resolution, SymbolId, SymbolKind},
+    SemanticError,
+};
+
+/// How far the declarations of a symbol went towards defining it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Definition {
+    /// Only declared here, so defined in another translation unit, if anywhere.
+    Declared,
+    /// Declared at file scope without an initializer or `extern`.
+    Tentative,
+    /// Given an initializer or a function body.
+    Defined,
+}
+
+/// Part of the initial contents of an object, in the order they are laid out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaticInit {
+    /// This many bytes of zeros.
+    Zero(u64),
+    /// An integer, or the bits of a floating value, stored in this many bytes.
+    Integer(u64, u64),
+    Bytes(Vec<u8>),
+    /// The address of a symbol plus a byte offset, stored in 8 bytes.
+    Address(String, i64),
+}
+
+#[derive(Debug, Clone)]
+pub struct StaticSymbol {
+    /// The unique name the resolver gave the symbol, which is its label.
+    pub name: String,
+    /// The composite type of every declaration, completed by a tentative definition.
+    pub ty: QualifiedType,
+    pub is_function: bool,
+    pub linkage: Linkage,
+    pub definition: Definition,
+    /// The initial contents of a defined object, which cover all of it; empty otherwise.
+    pub init: Vec<StaticInit>,
+    /// The identifier of the first declaration.
+    pub span: SourceSpan,
+}
+
+/// Every symbol with static storage duration in a translation unit, in order of
+/// first declaration.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    symbols: Vec<StaticSymbol>,
+}
+impl SymbolTable {
+    pub fn iter(&self) -> impl Iterator<Item = &StaticSymbol> {
+        self.symbols.iter()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&StaticSymbol> {
+        self.symbols.iter().find(|symbol| symbol.name == name)
+    }
+
+    /// One line per symbol, followed by the initial contents of defined objects.
+    pub fn debug_table(&self) -> String {
+        let mut output = String::new();
+        for symbol in &self.symbols {
+            let kind = match symbol.is_function {
+                true => "function",
+                false => "variable",
+            };
+            let linkage = match symbol.linkage {
+                Linkage::None => "no linkage",
+                Linkage::Internal => "internal",
+                Linkage::External => "external",
+            };
+            let definition = match symbol.definition {
+                Definition::Declared => "declared",
+                Definition::Tentative => "tentative",
+                Definition::Defined => "defined",
+            };
+            writeln!(
+                output,
+                "{kind} {}: {} ({linkage}, {definition})",
+                symbol.name, symbol.ty
+            )
+            .unwrap();
+            for init in &symbol.init {
+                match init {
+                    StaticInit::Zero(size) => writeln!(output, "  zero {size}"),
+                    StaticInit::Integer(size, value) => {
+                        writeln!(output, "  integer {size} {value:#x}")
+                    }
+                    StaticInit::Bytes(bytes) => {
+                        writeln!(output, "  bytes \"{}\"", bytes.escape_ascii())
+                    }
+                    StaticInit::Address(name, offset) => {
+                        writeln!(output, "  address {name} {offset:+}")
+                    }
+                }
+                .unwrap();
+            }
+        }
+        output
+    }
+}
+
+/// Collects the symbol table of a program after it has been type checked.
+pub struct SymbolCollector<'a> {
+    resolution: &'a Resolution,
+    tags: TagTable,
+    symbols: Vec<StaticSymbol>,
+    /// The index in `symbols` of every symbol seen so far.
+    index: HashMap<SymbolId, usize>,
+    /// The label of the array made for each string literal whose address is taken.
+    strings: HashMap<Vec<u8>, String>,
+    errors: Vec<SemanticError>,
+}
+impl<'a> SymbolCollector<'a> {
+    pub fn new(resolution: &'a Resolution) -> Self {
+        Self {
+            resolution,
+            tags: TagTable::default(),
+            symbols: Vec::new(),
+            index: HashMap::new(),
+            strings: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Every error reported so far, in source order.
+    pub fn errors(&self) -> &[SemanticError] {
+        &self.errors
+    }
+
+    pub fn collect_program(&mut self, program: &Program) -> SymbolTable {
+        self.tags.clone_from(&program.tags);
+        for item in &program.items {
+            match item {
+                ExternalDeclaration::Function(function) => {
+                    self.record(&function.name, Definition::Defined, &[]);
+                    self.block(&function.body);
+                }
+                ExternalDeclaration::Declaration(declaration) => {
+                    self.declaration(declaration, true)
+                }
+                ExternalDeclaration::StaticAssert(_) | ExternalDeclaration::Error(_) => {}
+            }
+        }
+        for i in 0..self.symbols.len() {
+            self.complete(i);
+        }
+        self.errors.sort_by_key(|err| err.span().start());
+        SymbolTable {
+            symbols: std::mem::take(&mut self.symbols),
+        }
+    }
+
+    /// Record a declaration of the symbol `name` is bound to, if it has static
+    /// storage duration, with the initializer it was given if any.
+    fn record(&mut self, name: &Identifier, definition: Definition, entries: &[InitializerEntry]) {
+        let Some(id) = self.resolution.binding(name) else {
+            return;
+        };
+        let symbol = self.resolution.symbol(id);
+        let is_function = symbol.kind == SymbolKind::Function;
+        if !is_function && !symbol.is_static {
+            return;
+        }
+        let i = match self.index.get(&id) {
+            Some(&i) => i,
+            None => {
+                self.index.insert(id, self.symbols.len());
+                self.symbols.push(StaticSymbol {
+                    name: symbol.unique_name.clone(),
+                    ty: symbol.ty.clone(),
+                    is_function,
+                    linkage: symbol.linkage,
+                    definition: Definition::Declared,
+                    init: Vec::new(),
+                    span: symbol.span,
+                });
+                self.symbols.len() - 1
+            }
+        };
+        if definition == Definition::Defined && !entries.is_empty() {
+            self.symbols[i].init = self.static_init(entries);
+        }
+        let symbol = &mut self.symbols[i];
+        symbol.definition = symbol.definition.max(definition);
+    }
+
+    fn declaration(&mut self, declaration: &Declaration, is_file_scope: bool) {
+        let storage_class = declaration.specifiers.storage_class;
+        if storage_class == Some(StorageClass::Typedef) {
+            return;
+        }
+        for declarator in &declaration.declarators {
+            let definition = match (&declarator.initializer, storage_class) {
+                (Some(_), _) => Definition::Defined,
+                (None, Some(StorageClass::Extern)) => Definition::Declared,
+                _ if declarator.ty.ty.is_function() => Definition::Declared,
+                (None, _) if is_file_scope => Definition::Tentative,
+                // A `static` local is defined whether or not it has an initializer.
+                (None, _) => Definition::Defined,
+            };
+            self.record(&declarator.name, definition, &declarator.entries);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        for item in &block.items {
+            match item {
+                BlockItem::Declaration(declaration) => self.declaration(declaration, false),
+                BlockItem::StaticAssert(_) => {}
+                BlockItem::Statement(statement) => self.statement(statement),
+            }
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match &statement.kind {
+            StatementKind::Compound(block) => self.block(block),
+            StatementKind::If(_, then, els) => {
+                self.statement(then);
+                if let Some(els) = els {
+                    self.statement(els);
+                }
+            }
+            StatementKind::While(_, body)
+            | StatementKind::DoWhile(body, _)
+            | StatementKind::For(_, _, _, body)
+            | StatementKind::Switch(_, body)
+            | StatementKind::Case(_, _, body)
+            | StatementKind::Default(body)
+            | StatementKind::Labeled(_, body) => self.statement(body),
+            StatementKind::Return(_)
+            | StatementKind::Expression(_)
+            | StatementKind::Null
+            | StatementKind::Break
+            | StatementKind::Continue
+            | StatementKind::Goto(_)
+            | StatementKind::Error => {}
+        }
+    }
+
+    /// Zero-fill an object that has no initializer but must be defined here.
+    fn complete(&mut self, i: usize) {
+        let symbol = &mut self.symbols[i];
+        let is_uninitialized = symbol.definition == Definition::Tentative
+            || (symbol.definition == Definition::Defined
+                && !symbol.is_function
+                && symbol.init.is_empty());
+        if !is_uninitialized {
+            return;
+        }
+        if let Type::Array(element, None) = &symbol.ty.ty {
+            symbol.ty.ty = Type::Array(element.clone(), Some(1));
+        }
+        match self.tags.layout(&symbol.ty.ty) {
+            Some(layout) => {
+                symbol.definition = Definition::Defined;
+                symbol.init = vec![StaticInit::Zero(layout.size)];
+            }
+            None => {
+                let context = format!(
+                    "tentative definition has type `{}` that is never completed",
+                    symbol.ty
+                );
+                self.errors.push(SemanticError::new(symbol.span, &context));
+            }
+        }
+    }
+
+    /// The initial contents of an object from its resolved initializer.
+    ///
+    /// Bit-fields become the bytes they cover, with the bit-fields that share a
+    /// byte merged, since the rest of their storage unit may hold other members.
+    fn static_init(&mut self, entries: &[InitializerEntry]) -> Vec<StaticInit> {
+        let mut init = Vec::new();
+        // The bytes covered by the bit-fields seen since the last other entry,
+        // and the offset of the first of them.
+        let mut pending: Option<(u64, Vec<u8>)> = None;
+        for entry in entries {
+            let value = match &entry.kind {
+                InitializerEntryKind::Zero(size) => StaticInit::Zero(*size),
+                InitializerEntryKind::Bytes(bytes) => StaticInit::Bytes(bytes.clone()),
+                InitializerEntryKind::Value(ty, value) => {
+                    let size = self.size_of(&ty.ty);
+                    match self.constant(value) {
+                        Some(Value::Integer(value)) => {
+                            StaticInit::Integer(size, value as u64 & mask(size * 8))
+                        }
+                        Some(Value::Floating(value)) => match size {
+                            4 => StaticInit::Integer(size, u64::from((value as f32).to_bits())),
+                            _ => StaticInit::Integer(size, value.to_bits()),
+                        },
+                        Some(Value::Address(base, offset)) => {
+                            StaticInit::Address(self.base(base, value.span), offset as i64)
+                        }
+                        None => StaticInit::Zero(size),
+                    }
+                }
+                InitializerEntryKind::BitField(_, bit_offset, width, value) => {
+                    let bits = match self.constant(value) {
+                        Some(Value::Integer(value)) => value as u64 & mask(u64::from(*width)),
+                        Some(_) => {
+                            self.errors.push(SemanticError::new(
+                                value.span,
+                                "initializer element is not a compile-time constant",
+                            ));
+                            0
+                        }
+                        None => 0,
+                    };
+                    let start = entry.offset * 8 + u64::from(*bit_offset);
+                    let end = start + u64::from(*width);
+                    let shares_byte = matches!(
+                        &pending,
+                        Some((first, bytes)) if start / 8 < first + bytes.len() as u64
+                    );
+                    if !shares_byte {
+                        if let Some((_, bytes)) = pending.take() {
+                            init.push(StaticInit::Bytes(bytes));
+                        }
+                    }
+                    let (first, bytes) = pending.get_or_insert_with(|| (start / 8, Vec::new()));
+                    let first = *first;
+                    bytes.resize((end.div_ceil(8) - first) as usize, 0);
+                    for bit in 0..u64::from(*width) {
+                        if bits >> bit & 1 == 1 {
+                            let position = start + bit - first * 8;
+                            bytes[(position / 8) as usize] |= 1 << (position % 8);
+                        }
+                    }
+                    continue;
+                }
+            };
+            if let Some((_, bytes)) = pending.take() {
+                init.push(StaticInit::Bytes(bytes));
+            }
+            init.push(value);
+        }
+        if let Some((_, bytes)) = pending {
+            init.push(StaticInit::Bytes(bytes));
+        }
+        init
+    }
+
+    fn size_of(&self, ty: &Type) -> u64 {
+        self.tags
+            .layout(ty)
+            .expect("initialized objects are complete")
+            .size
+    }
+
+    /// The value of the constant expression `expression`, or `None` after
+    /// reporting why it isn't one.
+    fn constant(&mut self, expression: &Expression) -> Option<Value> {
+        let resolution = self.resolution;
+        let lookup = |identifier: &Identifier| {
+            let symbol = resolution.symbol(resolution.binding(identifier)?);
+            match symbol.kind {
+                SymbolKind::EnumConstant(value) => Some(Entity::EnumConstant(value)),
+                SymbolKind::Function => Some(Entity::Function(
+                    symbol.unique_name.clone(),
+                    symbol.ty.ty.clone(),
+                )),
+                SymbolKind::Variable if symbol.is_static => Some(Entity::Object(
+                    symbol.unique_name.clone(),
+                    symbol.ty.clone(),
+                )),
+                _ => None,
+            }
+        };
+        let result = Evaluator::new(&self.tags, &lookup).evaluate(expression);
+        match result {
+            Ok(constant) => Some(constant.value),
+            Err(EvalError::NotConstant(span)) => {
+                self.errors.push(SemanticError::new(
+                    span,
+                    "initializer element is not a compile-time constant",
+                ));
+                None
+            }
+            Err(EvalError::Undefined(span, context)) => {
+                self.errors.push(SemanticError::new(span, &context));
+                None
+            }
+        }
+    }
+
+    /// The label of what an address constant points to, defining an array for a
+    /// string literal the first time its contents are seen.
+    fn base(&mut self, base: Base, span: SourceSpan) -> String {
+        let bytes = match base {
+            Base::Symbol(name, _) => return name,
+            Base::String(bytes) => bytes,
+        };
+        if let Some(name) = self.strings.get(&bytes) {
+            return name.clone();
+        }
+        let name = format!(".Lstring.{}", self.strings.len());
+        self.strings.insert(bytes.clone(), name.clone());
+        self.symbols.push(StaticSymbol {
+            name: name.clone(),
+            ty: Type::Array(Box::new(Type::Char.into()), Some(bytes.len() as u64)).into(),
+            is_function: false,
+            linkage: Linkage::None,
+            definition: Definition::Defined,
+            init: vec![StaticInit::Bytes(bytes)],
+            span,
+        });
+        name
+    }
+}
+
+/// The low `bits` bits set.
+fn mask(bits: u64) -> u64 {
+    match bits {
+        64 => u64::MAX,
+        _ => (1 << bits) - 1,
+    }
+}
+
+#[cfg(test)]
+mod symbols_tests {
+    use expect_test::{expect, Expect};
+    use tokengen::span::Span;
+    use wacc_lexer::Lexer;
+    use wacc_parser::Parser;
+
+    use super::SymbolCollector;
+    use crate::{resolve::Resolver, typecheck::TypeChecker};
+
+    /// The symbol table of a program, then any errors.
+    fn check_symbols(src: &str, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let mut program = parser.parse_program();
+        assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+        let mut resolver = Resolver::new();
+        let resolution = resolver.resolve_program(&mut program);
+        assert!(resolver.errors().is_empty(), "{:?}", resolver.errors());
+        let mut checker = TypeChecker::new(&resolution);
+        checker.check_program(&mut program);
+        assert!(checker.errors().is_empty(), "{:?}", checker.errors());
+        let mut collector = SymbolCollector::new(&resolution);
+        let mut output = collector.collect_program(&program).debug_table();
+        for err in collector.errors() {
+            output.push_str(&format!("{} at {:?}\n", err.context(), err.span().span()));
+        }
+        expect.assert_eq(&output);
+    }
+
+    #[test]
+    fn test_definitions() {
+        check_symbols(
+            r#"
+            int tentative;
+            int tentative;
+            extern int declared;
+            extern int defined;
+            int defined = 3;
+            static long internal = -1;
+            int array[];
+            extern int later[];
+            int later[2] = { 1 };
+            int helper(void);
+            static int counter(void) {
+                static int calls;
+                static char name[] = "count";
+                {
+                    extern int declared;
+                }
+                return ++calls;
+            }
+            "#,
+            expect![[r#"
+                variable tentative: int (external, defined)
+                  zero 4
+                variable declared: int (external, declared)
+                variable defined: int (external, defined)
+                  integer 4 0x3
+                variable internal: long (internal, defined)
+                  integer 8 0xffffffffffffffff
+                variable array: int [1] (external, defined)
+                  zero 4
+                variable later: int [2] (external, defined)
+                  integer 4 0x1
+                  zero 4
+                function helper: int (void) (external, declared)
+                function counter: int (void) (internal, defined)
+                variable calls.0: int (no linkage, defined)
+                  zero 4
+                variable name.1: char [6] (no linkage, defined)
+                  bytes "count\x00"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_initial_values() {
+        check_symbols(
+            r#"
+            enum { SIZE = 4 };
+            unsigned char wrapped = 257;
+            signed char negative = -1;
+            short folded = SIZE * 2 + sizeof(int);
+            double fraction = 3;
+            float half = -(float)2;
+            struct flags { unsigned a : 3, b : 5; int c; } f = { 5, 2, 7 };
+            char letters[6] = "abc";
+            long big = 1 ? 2147483647 : 0;
+            "#,
+            expect![[r#"
+                variable wrapped: unsigned char (external, defined)
+                  integer 1 0x1
+                variable negative: signed char (external, defined)
+                  integer 1 0xff
+                variable folded: short (external, defined)
+                  integer 2 0xc
+                variable fraction: double (external, defined)
+                  integer 8 0x4008000000000000
+                variable half: float (external, defined)
+                  integer 4 0xc0000000
+                variable f: struct flags (external, defined)
+                  bytes "\x15"
+                  zero 3
+                  integer 4 0x7
+                variable letters: char [6] (external, defined)
+                  bytes "abc\x00"
+                  zero 2
+                variable big: long (external, defined)
+                  integer 8 0x7fffffff
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_address_constants() {
+        check_symbols(
+            r#"
+            int table[4];
+            static struct { int a, b; } pair;
+            int *third = &table[2];
+            int *second = &pair.b;
+            char *greeting = "hi", *again = "hi" + 1;
+            int main(void) {
+                static int (*entry)(void) = main;
+                static int *end = table + 4;
+                return 0;
+            }
+            "#,
+            expect![[r#"
+                variable table: int [4] (external, defined)
+                  zero 16
+                variable pair: struct (anonymous) (internal, defined)
+                  zero 8
+                variable third: int * (external, defined)
+                  address table +8
+                variable second: int * (external, defined)
+                  address pair +4
+                variable greeting: char * (external, defined)
+                  address .Lstring.0 +0
+                variable .Lstring.0: char [3] (no linkage, defined)
+                  bytes "hi\x00"
+                variable again: char * (external, defined)
+                  address .Lstring.0 +1
+                function main: int (void) (external, defined)
+                variable entry.0: int (*)(void) (no linkage, defined)
+                  address main +0
+                variable end.1: int * (no linkage, defined)
+                  address table +16
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        check_symbols(
+            r#"
+            struct never s;
+            int x = 1;
+            int y = x;
+            int z = 1 / 0;
+            "#,
+            expect![[r#"
+                variable s: struct never (external, tentative)
+                variable x: int (external, defined)
+                  integer 4 0x1
+                variable y: int (external, defined)
+                  zero 4
+                variable z: int (external, defined)
+                  zero 4
+                tentative definition has type `struct never` that is never completed at "s"
+                initializer element is not a compile-time constant at "x"
+                division by zero at "1 / 0"
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,1937 @@
+//! Type checking.
+//!
+//! Every expression is annotated with its C type, and the conversions C leaves
+//! implicit are made explicit as [`ExpressionKind::ImplicitConversion`] nodes:
+//! arrays and functions decaying to pointers, the integer promotions, the usual
+//! arithmetic conversions that bring the operands of a binary operator to a common
+//! type, and the conversions "as if by assignment" of assigned values, arguments,
+//! return values and initializers. An lvalue keeps the qualifiers of the object it
+//! designates; every other expression has an unqualified type.
+//!
+//! Once a program checks, later stages never have to work out a conversion for
+//! themselves:
+//!
+//! - the operands of arithmetic, bitwise and comparison operators have the same
+//!   arithmetic type, or are both pointers;
+//! - a shift count has the type of the value shifted;
+//! - an integer added to or subtracted from a pointer, or used as a subscript, is a `long`;
+//! - in a compound assignment the right operand has the type the operation is done
+//!   in, and the result is converted back to the type of the left one;
+//! - the operand of a cast has been converted to the type of the cast, so the cast
+//!   only marks where the conversion was written;
+//! - the values in initializer entries have the types of the objects they initialize.
+//!
+//! The value of each `case` label is converted to the type of its controlling
+//! expression, which is what it is compared with, so two labels that only differ
+//! before the conversion are reported as duplicates.
+//!
+//! Along the way this reports operands of the wrong type, incompatible
+//! assignments, calls with the wrong number of arguments, and the like. It
+//! warns about implicit conversions that may change a value, and comparisons
+//! that convert a signed operand to unsigned.
+
+use std::{collections::HashSet, mem};
+
+use tokengen::span::{SourceSpan, Span};
+use wacc_parser::{
+    ast::{
+        AssignmentOperator, BinaryOperator, BlockItem, Conversion, Declaration,
+        DeclarationSpecifiers, Declarator, DeclaratorKind, DesignatorKind, Expression,
+        ExpressionKind, ExternalDeclaration, ForInit, FunctionDefinition, Identifier, Initializer,
+        InitializerEntry, InitializerEntryKind, InitializerKind, Literal, PostfixOperator, Program,
+        Statement, StatementKind, StorageClass, TypeName, TypeSpecifier, UnaryOperator,
+    },
+    constant::{Entity, Evaluator},
+    types::{FunctionType, QualifiedType, Qualifiers, TagTable, Type},
+};
+
+use crate::{
+    resolve::{Resolution, SymbolKind},
+    warning::{Warning, WarningKind},
+    SemanticError,
+};
+
+/// Where a value is converted as if by assignment, which only changes the wording of errors.
+#[derive(Debug, Clone, Copy)]
+enum AssignmentContext {
+    Assigning,
+    Initializing,
+    Passing,
+    Returning,
+}
+impl AssignmentContext {
+    /// Describe converting a value of type `from` to `to`, as in "assigning to `int *` from `long`".
+    fn describe(self, to: &Type, from: &Type, is_incompatible: bool) -> String {
+        let incompatible = match is_incompatible {
+            true => "incompatible ",
+            false => "",
+        };
+        match self {
+            Self::Assigning => match is_incompatible {
+                true => format!("assigning to `{to}` from incompatible type `{from}`"),
+                false => format!("assigning to `{to}` from `{from}`"),
+            },
+            Self::Initializing => {
+                format!("initializing `{to}` with an expression of {incompatible}type `{from}`")
+            }
+            Self::Passing => format!("passing `{from}` to parameter of {incompatible}type `{to}`"),
+            Self::Returning => {
+                format!("returning `{from}` from a function with {incompatible}result type `{to}`")
+            }
+        }
+    }
+}
+
+/// Checks the types of a resolved program, annotating and converting its expressions in place.
+pub struct TypeChecker<'a> {
+    resolution: &'a Resolution,
+    tags: TagTable,
+    /// The name and return type of the function whose body is being checked.
+    function: Option<(String, Type)>,
+    /// The promoted type of the controlling expression of each enclosing `switch`,
+    /// innermost last, with the values of its `case` labels so far.
+    switches: Vec<Option<(Type, HashSet<i128>)>>,
+    errors: Vec<SemanticError>,
+    warnings: Vec<Warning>,
+    /// Set while checking copies of expressions whose errors have already been reported.
+    quiet: bool,
+}
+impl<'a> TypeChecker<'a> {
+    pub fn new(resolution: &'a Resolution) -> Self {
+        Self {
+            resolution,
+            tags: TagTable::default(),
+            function: None,
+            switches: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            quiet: false,
+        }
+    }
+
+    /// Every error reported so far, in source order.
+    pub fn errors(&self) -> &[SemanticError] {
+        &self.errors
+    }
+
+    /// Every warning reported so far, in source order.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    pub fn check_program(&mut self, program: &mut Program) {
+        self.tags.clone_from(&program.tags);
+        for item in &mut program.items {
+            match item {
+                ExternalDeclaration::Function(function) => self.function_definition(function),
+                ExternalDeclaration::Declaration(declaration) => self.declaration(declaration),
+                ExternalDeclaration::StaticAssert(static_assert) => {
+                    self.value(&mut static_assert.condition);
+                }
+                ExternalDeclaration::Error(_) => {}
+            }
+        }
+        self.errors.sort_by_key(|err| err.span().start());
+        self.warnings.sort_by_key(|warning| warning.span().start());
+    }
+
+    fn error(&mut self, span: SourceSpan, context: &str) {
+        if !self.quiet {
+            self.errors.push(SemanticError::new(span, context));
+        }
+    }
+
+    fn warn(&mut self, kind: WarningKind, span: SourceSpan, context: &str) {
+        if !self.quiet {
+            self.warnings.push(Warning::new(kind, span, context));
+        }
+    }
+
+    fn function_definition(&mut self, function: &mut FunctionDefinition) {
+        self.specifiers(&mut function.specifiers);
+        self.declarator(&mut function.declarator);
+        for parameter in &function.parameters {
+            if self.tags.layout(&parameter.ty.ty).is_none() {
+                let context = format!("parameter has incomplete type `{}`", parameter.ty);
+                self.error(parameter.span, &context);
+            }
+        }
+        if let Type::Function(FunctionType { return_type, .. }) = &function.ty.ty {
+            self.function = Some((function.name.name.clone(), return_type.ty.clone()));
+        }
+        self.block_items(&mut function.body.items);
+        self.function = None;
+    }
+
+    fn declaration(&mut self, declaration: &mut Declaration) {
+        self.specifiers(&mut declaration.specifiers);
+        // An object defined in a block must be complete where it is declared;
+        // one at file scope can still be completed later, which the symbol
+        // table checks.
+        let is_definition = self.function.is_some()
+            && !matches!(
+                declaration.specifiers.storage_class,
+                Some(StorageClass::Extern | StorageClass::Typedef)
+            );
+        for declarator in &mut declaration.declarators {
+            self.declarator(&mut declarator.declarator);
+            if is_definition
+                && !declarator.ty.ty.is_function()
+                && self.tags.layout(&declarator.ty.ty).is_none()
+            {
+                let context = format!("variable has incomplete type `{}`", declarator.ty);
+                self.error(declarator.name.span, &context);
+            }
+            if let Some(initializer) = &mut declarator.initializer {
+                self.initializer(initializer);
+                self.entries(&mut declarator.entries);
+            }
+        }
+    }
+
+    /// The bit-field widths and enumerator values in struct and enum definitions.
+    fn specifiers(&mut self, specifiers: &mut DeclarationSpecifiers) {
+        for specifier in &mut specifiers.type_specifiers {
+            match specifier {
+                TypeSpecifier::Record(record) => {
+                    for member in record.members.iter_mut().flatten() {
+                        self.specifiers(&mut member.specifiers);
+                        for declarator in &mut member.declarators {
+                            self.declarator(&mut declarator.declarator);
+                            if let Some(width) = &mut declarator.bit_width {
+                                self.value(width);
+                            }
+                        }
+                    }
+                }
+                TypeSpecifier::Enum(enumeration) => {
+                    for enumerator in enumeration.enumerators.iter_mut().flatten() {
+                        if let Some(value) = &mut enumerator.value {
+                            self.value(value);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The array lengths and parameter declarations of a declarator.
+    fn declarator(&mut self, declarator: &mut Declarator) {
+        match &mut declarator.kind {
+            DeclaratorKind::Identifier(_) => {}
+            DeclaratorKind::Pointer(_, inner) => self.declarator(inner),
+            DeclaratorKind::Array(inner, length) => {
+                self.declarator(inner);
+                if let Some(length) = length {
+                    self.value(length);
+                }
+            }
+            DeclaratorKind::Function(inner, parameters) => {
+                self.declarator(inner);
+                for parameter in &mut parameters.parameters {
+                    self.specifiers(&mut parameter.specifiers);
+                    self.declarator(&mut parameter.declarator);
+                }
+            }
+        }
+    }
+
+    fn type_name(&mut self, type_name: &mut TypeName) {
+        self.specifiers(&mut type_name.specifiers);
+        self.declarator(&mut type_name.declarator);
+    }
+
+    /// The expressions of an initializer as written. They are only annotated here;
+    /// the conversions to the types of the objects they initialize are made on the
+    /// resolved entries.
+    fn initializer(&mut self, initializer: &mut Initializer) {
+        match &mut initializer.kind {
+            InitializerKind::Expression(expression) => self.expression(expression),
+            InitializerKind::List(items) => {
+                for item in items {
+                    for designator in &mut item.designators {
+                        if let DesignatorKind::Index(index, _) = &mut designator.kind {
+                            self.value(index);
+                        }
+                    }
+                    self.initializer(&mut item.initializer);
+                }
+            }
+        }
+    }
+
+    /// The flattened initializer entries, whose values are copies of expressions in an
+    /// initializer that has already been checked. Only converting them to the types
+    /// of the objects they initialize can report anything new.
+    fn entries(&mut self, entries: &mut [InitializerEntry]) {
+        for entry in entries {
+            match &mut entry.kind {
+                InitializerEntryKind::Value(ty, value)
+                | InitializerEntryKind::BitField(ty, _, _, value) => {
+                    let quiet = mem::replace(&mut self.quiet, true);
+                    self.value(value);
+                    self.quiet = quiet;
+                    self.assign(value, &ty.ty, AssignmentContext::Initializing);
+                }
+                InitializerEntryKind::Zero(_) | InitializerEntryKind::Bytes(_) => {}
+            }
+        }
+    }
+
+    fn block_items(&mut self, items: &mut [BlockItem]) {
+        for item in items {
+            match item {
+                BlockItem::Declaration(declaration) => self.declaration(declaration),
+                BlockItem::StaticAssert(static_assert) => {
+                    self.value(&mut static_assert.condition);
+                }
+                BlockItem::Statement(statement) => self.statement(statement),
+            }
+        }
+    }
+
+    fn statement(&mut self, statement: &mut Statement) {
+        match &mut statement.kind {
+            StatementKind::Return(value) => self.return_statement(value.as_mut(), statement.span),
+            StatementKind::Expression(expression) => {
+                self.value(expression);
+            }
+            StatementKind::Compound(block) => self.block_items(&mut block.items),
+            StatementKind::If(condition, then, els) => {
+                self.condition(condition);
+                self.statement(then);
+                if let Some(els) = els {
+                    self.statement(els);
+                }
+            }
+            StatementKind::While(condition, body) => {
+                self.condition(condition);
+                self.statement(body);
+            }
+            StatementKind::DoWhile(body, condition) => {
+                self.statement(body);
+                self.condition(condition);
+            }
+            StatementKind::For(init, condition, step, body) => {
+                match &mut **init {
+                    ForInit::Declaration(declaration) => self.declaration(declaration),
+                    ForInit::Expression(expression) => {
+                        if let Some(expression) = expression {
+                            self.value(expression);
+                        }
+                    }
+                }
+                if let Some(condition) = condition {
+                    self.condition(condition);
+                }
+                if let Some(step) = step {
+                    self.value(step);
+                }
+                self.statement(body);
+            }
+            StatementKind::Switch(condition, body) => {
+                let ty = self.value(condition).and_then(|ty| match ty.is_integer() {
+                    true => Some(self.tags.promote(&ty)),
+                    false => {
+                        self.error(
+                            condition.span,
+                            &format!(
+                                "statement requires expression of integer type (`{ty}` invalid)"
+                            ),
+                        );
+                        None
+                    }
+                });
+                if let Some(ty) = &ty {
+                    convert(condition, ty);
+                }
+                self.switches.push(ty.map(|ty| (ty, HashSet::new())));
+                self.statement(body);
+                self.switches.pop();
+            }
+            StatementKind::Case(expression, value, body) => {
+                self.value(expression);
+                if let Some(Some((ty, _))) = self.switches.last() {
+                    convert(expression, &ty.clone());
+                    if let Some(converted) = self.constant(expression) {
+                        *value = converted;
+                    }
+                    if let Some(Some((_, cases))) = self.switches.last_mut() {
+                        if !cases.insert(*value) {
+                            let context = format!("duplicate case value {value}");
+                            self.error(expression.span, &context);
+                        }
+                    }
+                }
+                self.statement(body);
+            }
+            StatementKind::Default(body) | StatementKind::Labeled(_, body) => self.statement(body),
+            StatementKind::Null
+            | StatementKind::Break
+            | StatementKind::Continue
+            | StatementKind::Goto(_)
+            | StatementKind::Error => {}
+        }
+    }
+
+    fn return_statement(&mut self, value: Option<&mut Expression>, span: SourceSpan) {
+        let Some((name, return_type)) = self.function.clone() else {
+            return;
+        };
+        match value {
+            Some(value) => {
+                let ty = self.value(value);
+                match return_type {
+                    // Returning a void expression from a void function is fine.
+                    Type::Void if ty.is_some_and(|ty| ty != Type::Void) => self.error(
+                        value.span,
+                        &format!("void function `{name}` should not return a value"),
+                    ),
+                    Type::Void => {}
+                    _ => self.assign(value, &return_type, AssignmentContext::Returning),
+                }
+            }
+            None if return_type != Type::Void => self.error(
+                span,
+                &format!("non-void function `{name}` should return a value"),
+            ),
+            None => {}
+        }
+    }
+
+    /// The controlling expression of an `if` or a loop, which is compared against zero.
+    fn condition(&mut self, condition: &mut Expression) {
+        if let Some(ty) = self.value(condition) {
+            if !ty.is_scalar() {
+                self.error(
+                    condition.span,
+                    &format!("statement requires expression of scalar type (`{ty}` invalid)"),
+                );
+            }
+        }
+    }
+
+    /// Check an expression and annotate it with its type, which is `None` if it has
+    /// errors. Arrays and functions are left as they are, for `sizeof` and `&`.
+    fn expression(&mut self, expression: &mut Expression) {
+        let ty = self.expression_type(expression);
+        expression.ty = ty;
+    }
+
+    /// Check an expression whose value is used, decaying an array or function to a
+    /// pointer. Returns the unqualified type of the value.
+    fn value(&mut self, expression: &mut Expression) -> Option<Type> {
+        self.expression(expression);
+        let ty = expression.ty.as_ref()?.ty.clone();
+        let (conversion, decayed) = match &ty {
+            Type::Array(element, _) => (Conversion::ArrayToPointer, Type::Pointer(element.clone())),
+            Type::Function(_) => (Conversion::FunctionToPointer, Type::pointer_to(ty)),
+            _ => return Some(ty),
+        };
+        wrap(expression, conversion, decayed.clone());
+        Some(decayed)
+    }
+
+    fn expression_type(&mut self, expression: &mut Expression) -> Option<QualifiedType> {
+        let span = expression.span;
+        match &mut expression.kind {
+            ExpressionKind::Constant(Literal::Integer(_, ty) | Literal::Floating(_, ty)) => {
+                Some(ty.clone().into())
+            }
+            ExpressionKind::Identifier(name) => self.identifier_type(name),
+            ExpressionKind::StringLiteral(bytes) => {
+                let length = bytes.len() as u64 + 1;
+                Some(Type::Array(Box::new(Type::Char.into()), Some(length)).into())
+            }
+            ExpressionKind::Unary(operator, operand) => self.unary(*operator, operand, span),
+            ExpressionKind::Postfix(operator, operand) => {
+                let operator = match operator {
+                    PostfixOperator::Increment => UnaryOperator::PreIncrement,
+                    PostfixOperator::Decrement => UnaryOperator::PreDecrement,
+                };
+                self.increment(operator, operand)
+            }
+            ExpressionKind::Binary(operator, lhs, rhs) => {
+                self.binary(*operator, lhs, rhs, span).map(Into::into)
+            }
+            ExpressionKind::Assignment(operator, lhs, rhs) => {
+                self.assignment(*operator, lhs, rhs, span).map(Into::into)
+            }
+            ExpressionKind::Conditional(condition, then, els) => {
+                if let Some(ty) = self.value(condition) {
+                    if !ty.is_scalar() {
+                        self.error(
+                            condition.span,
+                            &format!(
+                                "used type `{ty}` where arithmetic or pointer type is required"
+                            ),
+                        );
+                    }
+                }
+                self.conditional(then, els, span).map(Into::into)
+            }
+            ExpressionKind::Cast(type_name, operand) => {
+                self.type_name(type_name);
+                self.cast(&type_name.ty.ty, type_name.span, operand)
+                    .map(Into::into)
+            }
+            ExpressionKind::CompoundLiteral(literal) => {
+                self.type_name(&mut literal.type_name);
+                self.initializer(&mut literal.initializer);
+                self.entries(&mut literal.entries);
+                Some(literal.ty.clone())
+            }
+            ExpressionKind::SizeofExpression(operand) => {
+                self.expression(operand);
+                let ty = operand.ty.clone()?;
+                if self.is_bit_field(operand) {
+                    self.error(span, "invalid application of `sizeof` to bit-field");
+                    return None;
+                }
+                self.sizeof(&ty.ty, span)
+            }
+            ExpressionKind::SizeofType(type_name) => {
+                self.type_name(type_name);
+                self.sizeof(&type_name.ty.ty, span)
+            }
+            ExpressionKind::Offsetof(type_name, designators, _) => {
+                self.type_name(type_name);
+                for designator in designators {
+                    if let DesignatorKind::Index(index, _) = &mut designator.kind {
+                        self.value(index);
+                    }
+                }
+                Some(Type::UnsignedLong.into())
+            }
+            ExpressionKind::Call(callee, arguments) => {
+                self.call(callee, arguments, span).map(Into::into)
+            }
+            ExpressionKind::Subscript(base, index) => self.subscript(base, index, span),
+            ExpressionKind::Member(base, member) => {
+                self.expression(base);
+                let record = base.ty.clone()?;
+                self.member(&record, member, base.span)
+            }
+            ExpressionKind::PointerMember(base, member) => {
+                let ty = self.value(base)?;
+                let Type::Pointer(record) = &ty else {
+                    self.error(
+                        base.span,
+                        &format!("member reference type `{ty}` is not a pointer"),
+                    );
+                    return None;
+                };
+                self.member(record, member, base.span)
+            }
+            // Already checked, as this is only ever made by the checker itself.
+            ExpressionKind::ImplicitConversion(..) => expression.ty.clone(),
+            ExpressionKind::Error => None,
+        }
+    }
+
+    fn identifier_type(&self, name: &Identifier) -> Option<QualifiedType> {
+        let symbol = self.resolution.symbol(self.resolution.binding(name)?);
+        match symbol.kind {
+            SymbolKind::EnumConstant(_) => Some(Type::Int.into()),
+            SymbolKind::Variable | SymbolKind::Parameter | SymbolKind::Function => {
+                Some(symbol.ty.clone())
+            }
+            SymbolKind::Typedef => None,
+        }
+    }
+
+    fn unary(
+        &mut self,
+        operator: UnaryOperator,
+        operand: &mut Expression,
+        span: SourceSpan,
+    ) -> Option<QualifiedType> {
+        let invalid = |ty: &Type| format!("invalid argument type `{ty}` to unary expression");
+        match operator {
+            UnaryOperator::Negate | UnaryOperator::Plus | UnaryOperator::Complement => {
+                let ty = self.value(operand)?;
+                let is_valid = match operator {
+                    UnaryOperator::Complement => ty.is_integer(),
+                    _ => ty.is_arithmetic(),
+                };
+                if !is_valid {
+                    self.error(span, &invalid(&ty));
+                    return None;
+                }
+                let promoted = self.tags.promote(&ty);
+                convert(operand, &promoted);
+                Some(promoted.into())
+            }
+            UnaryOperator::Not => {
+                let ty = self.value(operand)?;
+                if !ty.is_scalar() {
+                    self.error(span, &invalid(&ty));
+                    return None;
+                }
+                Some(Type::Int.into())
+            }
+            UnaryOperator::AddressOf => {
+                self.expression(operand);
+                let ty = operand.ty.clone()?;
+                if self.is_bit_field(operand) {
+                    self.error(span, "address of bit-field requested");
+                    return None;
+                }
+                Some(Type::pointer_to(ty).into())
+            }
+            UnaryOperator::Dereference => {
+                let ty = self.value(operand)?;
+                match ty {
+                    Type::Pointer(pointee) => Some(*pointee),
+                    ty => {
+                        self.error(
+                            span,
+                            &format!("indirection requires pointer operand (`{ty}` invalid)"),
+                        );
+                        None
+                    }
+                }
+            }
+            UnaryOperator::PreIncrement | UnaryOperator::PreDecrement => {
+                self.increment(operator, operand)
+            }
+        }
+    }
+
+    /// `++` or `--`, before or after the operand, which gives the type of the result.
+    fn increment(
+        &mut self,
+        operator: UnaryOperator,
+        operand: &mut Expression,
+    ) -> Option<QualifiedType> {
+        self.expression(operand);
+        let ty = operand.ty.clone()?.ty;
+        self.check_modifiable(operand)?;
+        let is_valid = match &ty {
+            Type::Pointer(pointee) => self.check_pointer_arithmetic(pointee, operand.span),
+            ty => ty.is_arithmetic(),
+        };
+        if !is_valid {
+            let verb = match operator {
+                UnaryOperator::PreIncrement => "increment",
+                _ => "decrement",
+            };
+            self.error(operand.span, &format!("cannot {verb} value of type `{ty}`"));
+            return None;
+        }
+        Some(ty.into())
+    }
+
+    fn binary(
+        &mut self,
+        operator: BinaryOperator,
+        lhs: &mut Expression,
+        rhs: &mut Expression,
+        span: SourceSpan,
+    ) -> Option<Type> {
+        let left = self.value(lhs);
+        let right = self.value(rhs);
+        if operator == BinaryOperator::Comma {
+            return right;
+        }
+        let (left, right) = (left?, right?);
+        let both = |is: fn(&Type) -> bool| is(&left) && is(&right);
+        let is_integer: fn(&Type) -> bool = Type::is_integer;
+        let ty = match operator {
+            BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr if both(Type::is_scalar) => {
+                Type::Int
+            }
+            BinaryOperator::Multiply | BinaryOperator::Divide if both(Type::is_arithmetic) => {
+                self.arithmetic(lhs, rhs, &left, &right)
+            }
+            BinaryOperator::Remainder
+            | BinaryOperator::BitwiseAnd
+            | BinaryOperator::BitwiseXor
+            | BinaryOperator::BitwiseOr
+                if both(is_integer) =>
+            {
+                self.arithmetic(lhs, rhs, &left, &right)
+            }
+            BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight if both(is_integer) => {
+                let ty = self.tags.promote(&left);
+                convert(lhs, &ty);
+                convert(rhs, &ty);
+                ty
+            }
+            BinaryOperator::Add | BinaryOperator::Subtract => {
+                self.additive(operator, lhs, rhs, &left, &right, span)?
+            }
+            BinaryOperator::LessThan
+            | BinaryOperator::GreaterThan
+            | BinaryOperator::LessOrEqual
+            | BinaryOperator::GreaterOrEqual
+            | BinaryOperator::Equal
+            | BinaryOperator::NotEqual => {
+                self.comparison(operator, lhs, rhs, &left, &right, span)?;
+                Type::Int
+            }
+            _ => {
+                self.error(
+                    span,
+                    &format!("invalid operands to binary expression (`{left}` and `{right}`)"),
+                );
+                return None;
+            }
+        };
+        Some(ty)
+    }
+
+    /// Bring both operands to their common type by the usual arithmetic conversions.
+    fn arithmetic(
+        &self,
+        lhs: &mut Expression,
+        rhs: &mut Expression,
+        left: &Type,
+        right: &Type,
+    ) -> Type {
+        let ty = self.tags.common_type(left, right);
+        convert(lhs, &ty);
+        convert(rhs, &ty);
+        ty
+    }
+
+    /// `+` and `-`, on numbers, a pointer and an integer, or two pointers.
+    fn additive(
+        &mut self,
+        operator: BinaryOperator,
+        lhs: &mut Expression,
+        rhs: &mut Expression,
+        left: &Type,
+        right: &Type,
+        span: SourceSpan,
+    ) -> Option<Type> {
+        match (left, right) {
+            (left, right) if left.is_arithmetic() && right.is_arithmetic() => {
+                Some(self.arithmetic(lhs, rhs, left, right))
+            }
+            (Type::Pointer(pointee), right) if right.is_integer() => {
+                if !self.check_pointer_arithmetic(pointee, span) {
+                    return None;
+                }
+                convert(rhs, &Type::Long);
+                Some(left.clone())
+            }
+            (left, Type::Pointer(pointee))
+                if left.is_integer() && operator == BinaryOperator::Add =>
+            {
+                if !self.check_pointer_arithmetic(pointee, span) {
+                    return None;
+                }
+                convert(lhs, &Type::Long);
+                Some(right.clone())
+            }
+            (Type::Pointer(left_pointee), Type::Pointer(right_pointee))
+                if operator == BinaryOperator::Subtract =>
+            {
+                if !self.tags.compatible(&left_pointee.ty, &right_pointee.ty) {
+                    self.error(
+                        span,
+                        &format!("`{left}` and `{right}` are not pointers to compatible types"),
+                    );
+                    return None;
+                }
+                self.check_pointer_arithmetic(left_pointee, span)
+                    .then_some(Type::Long)
+            }
+            _ => {
+                self.error(
+                    span,
+                    &format!("invalid operands to binary expression (`{left}` and `{right}`)"),
+                );
+                None
+            }
+        }
+    }
+
+    /// The relational and equality operators, whose result is always an `int`.
+    fn comparison(
+        &mut self,
+        operator: BinaryOperator,
+        lhs: &mut Expression,
+        rhs: &mut Expression,
+        left: &Type,
+        right: &Type,
+        span: SourceSpan,
+    ) -> Option<()> {
+        let is_equality = matches!(operator, BinaryOperator::Equal | BinaryOperator::NotEqual);
+        let context = match (left, right) {
+            (left, right) if left.is_arithmetic() && right.is_arithmetic() => {
+                self.warn_sign_compare(lhs, rhs, left, right, span);
+                self.arithmetic(lhs, rhs, left, right);
+                return Some(());
+            }
+            (Type::Pointer(_), right) if is_equality && self.is_null_pointer_constant(rhs) => {
+                debug_assert!(right.is_integer());
+                convert(rhs, left);
+                return Some(());
+            }
+            (left, Type::Pointer(_)) if is_equality && self.is_null_pointer_constant(lhs) => {
+                debug_assert!(left.is_integer());
+                convert(lhs, right);
+                return Some(());
+            }
+            (Type::Pointer(left_pointee), Type::Pointer(right_pointee)) => {
+                if self.tags.compatible(&left_pointee.ty, &right_pointee.ty) {
+                    return Some(());
+                }
+                // A pointer compared for equality with a `void *` is converted to it.
+                match (&left_pointee.ty, &right_pointee.ty) {
+                    (Type::Void, _) if is_equality => {
+                        convert(rhs, left);
+                        return Some(());
+                    }
+                    (_, Type::Void) if is_equality => {
+                        convert(lhs, right);
+                        return Some(());
+                    }
+                    _ => "comparison of distinct pointer types",
+                }
+            }
+            (Type::Pointer(_), right) | (right, Type::Pointer(_)) if right.is_integer() => {
+                "comparison between pointer and integer"
+            }
+            _ => "invalid operands to binary expression",
+        };
+        self.error(span, &format!("{context} (`{left}` and `{right}`)"));
+        None
+    }
+
+    /// Warn if the usual arithmetic conversions turn a signed operand of a comparison
+    /// unsigned, where a negative value would compare greater than any positive
+    /// one. A constant that isn't negative is left alone.
+    fn warn_sign_compare(
+        &mut self,
+        lhs: &Expression,
+        rhs: &Expression,
+        left: &Type,
+        right: &Type,
+        span: SourceSpan,
+    ) {
+        if !left.is_integer() || !right.is_integer() {
+            return;
+        }
+        let ty = self.tags.common_type(left, right);
+        if ty.is_signed() {
+            return;
+        }
+        let is_signed = |operand: &Expression, ty: &Type| {
+            self.tags.promote(ty).is_signed()
+                && self.constant(operand).is_none_or(|value| value < 0)
+        };
+        if is_signed(lhs, left) || is_signed(rhs, right) {
+            self.warn(
+                WarningKind::SignCompare,
+                span,
+                &format!("comparison of integers of different signs: `{left}` and `{right}`"),
+            );
+        }
+    }
+
+    fn assignment(
+        &mut self,
+        operator: AssignmentOperator,
+        lhs: &mut Expression,
+        rhs: &mut Expression,
+        span: SourceSpan,
+    ) -> Option<Type> {
+        self.expression(lhs);
+        let right = self.value(rhs);
+        let left = lhs.ty.clone()?.ty;
+        self.check_modifiable(lhs)?;
+        let right = right?;
+        let operator = match operator {
+            AssignmentOperator::Assign => {
+                self.assign(rhs, &left, AssignmentContext::Assigning);
+                return Some(left);
+            }
+            AssignmentOperator::Compound(operator) => operator,
+        };
+        // The type the operation is done in, which the right operand is converted to.
+        let computation = match (&left, &right) {
+            (Type::Pointer(pointee), right)
+                if right.is_integer()
+                    && matches!(operator, BinaryOperator::Add | BinaryOperator::Subtract) =>
+            {
+                self.check_pointer_arithmetic(pointee, span)
+                    .then_some(Type::Long)?
+            }
+            (left, right)
+                if left.is_arithmetic()
+                    && right.is_arithmetic()
+                    && matches!(
+                        operator,
+                        BinaryOperator::Add
+                            | BinaryOperator::Subtract
+                            | BinaryOperator::Multiply
+                            | BinaryOperator::Divide
+                    ) =>
+            {
+                self.tags.common_type(left, right)
+            }
+            (left, right)
+                if left.is_integer()
+                    && right.is_integer()
+                    && matches!(
+                        operator,
+                        BinaryOperator::Remainder
+                            | BinaryOperator::BitwiseAnd
+                            | BinaryOperator::BitwiseXor
+                            | BinaryOperator::BitwiseOr
+                    ) =>
+            {
+                self.tags.common_type(left, right)
+            }
+            (left, right)
+                if left.is_integer()
+                    && right.is_integer()
+                    && matches!(
+                        operator,
+                        BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight
+                    ) =>
+            {
+                self.tags.promote(left)
+            }
+            _ => {
+                self.error(
+                    span,
+                    &format!("invalid operands to binary expression (`{left}` and `{right}`)"),
+                );
+                return None;
+            }
+        };
+        convert(rhs, &computation);
+        Some(left)
+    }
+
+    /// The second and third operands of `?:`, which give the type of the result.
+    fn conditional(
+        &mut self,
+        then: &mut Expression,
+        els: &mut Expression,
+        span: SourceSpan,
+    ) -> Option<Type> {
+        let then_ty = self.value(then);
+        let else_ty = self.value(els);
+        let (then_ty, else_ty) = (then_ty?, else_ty?);
+        let ty = match (&then_ty, &else_ty) {
+            (left, right) if left.is_arithmetic() && right.is_arithmetic() => {
+                return Some(self.arithmetic(then, els, left, right));
+            }
+            (Type::Pointer(_), right)
+                if right.is_integer() && self.is_null_pointer_constant(els) =>
+            {
+                then_ty.clone()
+            }
+            (left, Type::Pointer(_))
+                if left.is_integer() && self.is_null_pointer_constant(then) =>
+            {
+                else_ty.clone()
+            }
+            (Type::Pointer(left), Type::Pointer(right)) => {
+                // Pointing to the qualifiers of both, and to `void` if either does.
+                let qualifiers = left.qualifiers.union(right.qualifiers);
+                let pointee = match (&left.ty, &right.ty) {
+                    (Type::Void, _) | (_, Type::Void) => Type::Void,
+                    (left, right) if self.tags.compatible(left, right) => left.clone(),
+                    _ => {
+                        self.error(
+                            span,
+                            &format!("pointer type mismatch (`{then_ty}` and `{else_ty}`)"),
+                        );
+                        return None;
+                    }
+                };
+                Type::pointer_to(QualifiedType::new(pointee, qualifiers))
+            }
+            (Type::Void, Type::Void) => Type::Void,
+            (left, right) if matches!(left, Type::Struct(_) | Type::Union(_)) && left == right => {
+                left.clone()
+            }
+            _ => {
+                self.error(
+                    span,
+                    &format!("incompatible operand types (`{then_ty}` and `{else_ty}`)"),
+                );
+                return None;
+            }
+        };
+        convert(then, &ty);
+        convert(els, &ty);
+        Some(ty)
+    }
+
+    fn cast(&mut self, ty: &Type, span: SourceSpan, operand: &mut Expression) -> Option<Type> {
+        let from = self.value(operand)?;
+        // Anything can be cast to `void` to discard it.
+        if *ty == Type::Void {
+            return Some(Type::Void);
+        }
+        let error = match (ty, &from) {
+            (to, _) if !to.is_scalar() => (
+                span,
+                format!("used type `{to}` where arithmetic or pointer type is required"),
+            ),
+            (_, from) if !from.is_scalar() => (
+                operand.span,
+                format!("operand of type `{from}` where arithmetic or pointer type is required"),
+            ),
+            (Type::Pointer(_), Type::Float | Type::Double) => (
+                operand.span,
+                format!("operand of type `{from}` cannot be cast to a pointer type"),
+            ),
+            (Type::Float | Type::Double, Type::Pointer(_)) => (
+                operand.span,
+                format!("pointer cannot be cast to type `{ty}`"),
+            ),
+            _ => {
+                convert(operand, ty);
+                return Some(ty.clone());
+            }
+        };
+        self.error(error.0, &error.1);
+        None
+    }
+
+    fn sizeof(&mut self, ty: &Type, span: SourceSpan) -> Option<QualifiedType> {
+        let context = match ty {
+            Type::Function(_) => "invalid application of `sizeof` to a function type".to_string(),
+            ty if self.tags.layout(ty).is_none() => {
+                format!("invalid application of `sizeof` to an incomplete type `{ty}`")
+            }
+            _ => return Some(Type::UnsignedLong.into()),
+        };
+        self.error(span, &context);
+        None
+    }
+
+    fn call(
+        &mut self,
+        callee: &mut Expression,
+        arguments: &mut [Expression],
+        span: SourceSpan,
+    ) -> Option<Type> {
+        let ty = self.value(callee);
+        let argument_types = arguments
+            .iter_mut()
+            .map(|argument| self.value(argument))
+            .collect::<Vec<_>>();
+        let ty = ty?;
+        let function = match &ty {
+            Type::Pointer(pointee) => match &pointee.ty {
+                Type::Function(function) => Some(function.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some(function) = function else {
+            self.error(
+                callee.span,
+                &format!("called object type `{ty}` is not a function or function pointer"),
+            );
+            return None;
+        };
+        let FunctionType {
+            return_type,
+            parameters,
+            is_variadic,
+            is_prototype,
+        } = function;
+        let return_type = Some(return_type.ty);
+
+        // Without a prototype, the arguments are only promoted.
+        let parameters = match is_prototype {
+            true => parameters,
+            false => Vec::new(),
+        };
+        let (expected, have) = (parameters.len(), arguments.len());
+        if is_prototype && (have < expected || have > expected && !is_variadic) {
+            let (few, at_least) = match (have < expected, is_variadic) {
+                (true, true) => ("few", "at least "),
+                (true, false) => ("few", ""),
+                (false, _) => ("many", ""),
+            };
+            self.error(
+                span,
+                &format!(
+                    "too {few} arguments to function call, expected {at_least}{expected}, have {have}"
+                ),
+            );
+            return return_type;
+        }
+        for (i, (argument, ty)) in arguments.iter_mut().zip(argument_types).enumerate() {
+            let Some(ty) = ty else { continue };
+            match parameters.get(i) {
+                Some(parameter) => self.assign(argument, &parameter.ty, AssignmentContext::Passing),
+                // The default argument promotions.
+                None => {
+                    let promoted = match ty {
+                        Type::Float => Type::Double,
+                        ty => self.tags.promote(&ty),
+                    };
+                    convert(argument, &promoted);
+                }
+            }
+        }
+        return_type
+    }
+
+    /// `base[index]`, which is `*(base + index)`, so either can be the pointer.
+    fn subscript(
+        &mut self,
+        base: &mut Expression,
+        index: &mut Expression,
+        span: SourceSpan,
+    ) -> Option<QualifiedType> {
+        let base_ty = self.value(base);
+        let index_ty = self.value(index);
+        let (base_ty, index_ty) = (base_ty?, index_ty?);
+        let (pointee, integer) = match (&base_ty, &index_ty) {
+            (Type::Pointer(pointee), index_ty) if index_ty.is_integer() => (pointee, index),
+            (base_ty, Type::Pointer(pointee)) if base_ty.is_integer() => (pointee, base),
+            (Type::Pointer(_), _) | (_, Type::Pointer(_)) => {
+                self.error(index.span, "array subscript is not an integer");
+                return None;
+            }
+            _ => {
+                self.error(span, "subscripted value is not an array or pointer");
+                return None;
+            }
+        };
+        if !self.check_pointer_arithmetic(pointee, span) {
+            return None;
+        }
+        convert(integer, &Type::Long);
+        Some((**pointee).clone())
+    }
+
+    /// The member `name` of a struct or union, with the qualifiers of the record it's in.
+    fn member(
+        &mut self,
+        record: &QualifiedType,
+        name: &Identifier,
+        span: SourceSpan,
+    ) -> Option<QualifiedType> {
+        let (Type::Struct(tag) | Type::Union(tag)) = &record.ty else {
+            self.error(
+                span,
+                &format!("member reference base type `{record}` is not a structure or union"),
+            );
+            return None;
+        };
+        if self.tags.get(tag).body.is_none() {
+            self.error(span, &format!("incomplete definition of type `{record}`"));
+            return None;
+        }
+        let Some((_, member)) = self.tags.member(tag, &name.name) else {
+            let record = QualifiedType::unqualified(record.ty.clone());
+            self.error(
+                name.span,
+                &format!("no member named `{}` in `{record}`", name.name),
+            );
+            return None;
+        };
+        Some(member.ty.clone().qualified(record.qualifiers))
+    }
+
+    /// Whether an expression is a null pointer constant: an integer constant
+    /// expression with the value 0, or one cast to `void *` (C17 6.3.2.3).
+    fn is_null_pointer_constant(&self, expression: &Expression) -> bool {
+        if let ExpressionKind::Cast(type_name, operand) = &expression.kind {
+            if type_name.ty.ty == Type::pointer_to(Type::Void) {
+                return self.is_null_pointer_constant(operand);
+            }
+        }
+        self.constant(expression) == Some(0)
+    }
+
+    /// The value of an integer constant expression, or `None` if it isn't one.
+    fn constant(&self, expression: &Expression) -> Option<i128> {
+        let resolution = self.resolution;
+        let lookup =
+            |identifier: &Identifier| match resolution.symbol(resolution.binding(identifier)?).kind
+            {
+                SymbolKind::EnumConstant(value) => Some(Entity::EnumConstant(value)),
+                _ => None,
+            };
+        Evaluator::new(&self.tags, &lookup).integer(expression).ok()
+    }
+
+    /// Warn about converting a value as if by assignment to an arithmetic type that
+    /// can't represent every value of its own type. An integer constant is only
+    /// reported if its value doesn't fit.
+    fn warn_conversion(&mut self, value: &Expression, to: &Type, from: &Type) {
+        let (to, from) = (
+            self.tags.underlying_type(to),
+            self.tags.underlying_type(from),
+        );
+        let size = |ty: &Type| self.tags.layout(ty).map_or(0, |layout| layout.size);
+        // The bits of precision of a floating type, or of an integer type's magnitude.
+        let precision = |ty: &Type| match ty {
+            Type::Float => 24,
+            Type::Double => 53,
+            ty => size(ty) * 8 - u64::from(ty.is_signed()),
+        };
+        let is_floating = |ty: &Type| matches!(ty, Type::Float | Type::Double);
+        let narrows = match (is_floating(&from), is_floating(&to)) {
+            (false, false) => size(&to) < size(&from),
+            (false, true) | (true, true) => precision(&to) < precision(&from),
+            (true, false) => true,
+        };
+        if !narrows {
+            return;
+        }
+        let context = match self.constant(value) {
+            Some(_) if is_floating(&to) => return,
+            Some(constant) => {
+                let bits = size(&to) * 8;
+                let modulus = 1i128 << bits;
+                let mut converted = constant.rem_euclid(modulus);
+                if to.is_signed() && converted >= modulus / 2 {
+                    converted -= modulus;
+                }
+                if converted == constant {
+                    return;
+                }
+                format!(
+                    "implicit conversion from `{from}` to `{to}` changes value from {constant} to {converted}"
+                )
+            }
+            None => format!("implicit conversion from `{from}` to `{to}` may change value"),
+        };
+        self.warn(WarningKind::Conversion, value.span, &context);
+    }
+
+    /// Convert a value as if by assignment to an object of type `ty`, reporting it
+    /// if C doesn't allow that.
+    fn assign(&mut self, value: &mut Expression, ty: &Type, context: AssignmentContext) {
+        let Some(from) = value.ty.as_ref().map(|from| from.ty.clone()) else {
+            return;
+        };
+        let error = match (ty, &from) {
+            (to, from) if to.is_arithmetic() && from.is_arithmetic() => {
+                self.warn_conversion(value, to, from);
+                None
+            }
+            (Type::Pointer(_), from)
+                if from.is_integer() && self.is_null_pointer_constant(value) =>
+            {
+                None
+            }
+            (Type::Pointer(to), Type::Pointer(pointee)) => {
+                let is_void_pointer = |pointee: &QualifiedType, other: &QualifiedType| {
+                    pointee.ty == Type::Void && !other.ty.is_function()
+                };
+                let is_compatible = is_void_pointer(to, pointee)
+                    || is_void_pointer(pointee, to)
+                    || self.tags.compatible(&to.ty, &pointee.ty);
+                if !is_compatible {
+                    Some(format!(
+                        "incompatible pointer types {}",
+                        context.describe(ty, &from, false)
+                    ))
+                } else if !includes(to.qualifiers, pointee.qualifiers) {
+                    Some(format!(
+                        "{} discards qualifiers",
+                        context.describe(ty, &from, false)
+                    ))
+                } else {
+                    None
+                }
+            }
+            (Type::Pointer(_), from) if from.is_integer() => Some(format!(
+                "incompatible integer to pointer conversion {}",
+                context.describe(ty, from, false)
+            )),
+            (to, Type::Pointer(_)) if to.is_integer() => Some(format!(
+                "incompatible pointer to integer conversion {}",
+                context.describe(ty, &from, false)
+            )),
+            (Type::Struct(_) | Type::Union(_), from) if ty == from => None,
+            _ => Some(context.describe(ty, &from, true)),
+        };
+        match error {
+            Some(context) => self.error(value.span, &context),
+            None => convert(value, ty),
+        }
+    }
+
+    /// The operand of an assignment, `++` or `--` must not be an array or const.
+    /// Whether it is an lvalue at all was checked when it was resolved.
+    fn check_modifiable(&mut self, expression: &Expression) -> Option<()> {
+        let ty = expression.ty.as_ref()?;
+        let context = match &ty.ty {
+            // A string literal has already been reported as not assignable.
+            _ if matches!(expression.kind, ExpressionKind::StringLiteral(_)) => return None,
+            Type::Array(..) => format!("array type `{ty}` is not assignable"),
+            _ if ty.qualifiers.is_const => {
+                format!("cannot assign to an expression with const-qualified type `{ty}`")
+            }
+            _ => return Some(()),
+        };
+        self.error(expression.span, &context);
+        None
+    }
+
+    /// Pointer arithmetic needs to know the size of what is pointed to.
+    fn check_pointer_arithmetic(&mut self, pointee: &QualifiedType, span: SourceSpan) -> bool {
+        let context = match &pointee.ty {
+            Type::Function(_) => {
+                format!("arithmetic on a pointer to the function type `{pointee}`")
+            }
+            ty if self.tags.layout(ty).is_none() => {
+                format!("arithmetic on a pointer to an incomplete type `{pointee}`")
+            }
+            _ => return true,
+        };
+        self.error(span, &context);
+        false
+    }
+
+    /// Whether an expression designates a bit-field, which has no address or size.
+    fn is_bit_field(&self, expression: &Expression) -> bool {
+        let (record, name) = match &expression.kind {
+            ExpressionKind::Member(base, name) => (base.ty.as_ref().map(|ty| &ty.ty), name),
+            ExpressionKind::PointerMember(base, name) => match base.ty.as_ref().map(|ty| &ty.ty) {
+                Some(Type::Pointer(record)) => (Some(&record.ty), name),
+                _ => return false,
+            },
+            _ => return false,
+        };
+        match record {
+            Some(Type::Struct(tag) | Type::Union(tag)) => self
+                .tags
+                .member(tag, &name.name)
+                .is_some_and(|(_, member)| member.bit_width.is_some()),
+            _ => false,
+        }
+    }
+}
+
+/// Whether `qualifiers` has every qualifier in `other`.
+fn includes(qualifiers: Qualifiers, other: Qualifiers) -> bool {
+    qualifiers.union(other) == qualifiers
+}
+
+/// Convert the value of an expression to `ty`, which it must already have been checked
+/// to be convertible to. Nothing changes if it has that type already.
+fn convert(expression: &mut Expression, ty: &Type) {
+    let Some(from) = &expression.ty else {
+        return;
+    };
+    let from = &from.ty;
+    if from == ty {
+        return;
+    }
+    let is_floating = |ty: &Type| matches!(ty, Type::Float | Type::Double);
+    let conversion = match (from, ty) {
+        // Only null pointer constants are converted from integers to pointers
+        // without a cast.
+        (from, Type::Pointer(_)) if from.is_integer() => Conversion::NullPointer,
+        (from, to) if from.is_integer() && to.is_integer() => Conversion::Integer,
+        (from, to) if from.is_integer() && is_floating(to) => Conversion::IntegerToFloating,
+        (from, to) if is_floating(from) && to.is_integer() => Conversion::FloatingToInteger,
+        (from, to) if is_floating(from) && is_floating(to) => Conversion::Floating,
+        _ => Conversion::Pointer,
+    };
+    wrap(expression, conversion, ty.clone());
+}
+
+/// Replace an expression with a conversion of it to `ty`.
+fn wrap(expression: &mut Expression, conversion: Conversion, ty: Type) {
+    let span = expression.span;
+    let placeholder = Expression {
+        kind: ExpressionKind::Error,
+        span,
+        ty: None,
+    };
+    let operand = mem::replace(expression, placeholder);
+    *expression = Expression {
+        kind: ExpressionKind::ImplicitConversion(conversion, Box::new(operand)),
+        span,
+        ty: Some(ty.into()),
+    };
+}
+
+#[cfg(test)]
+mod typecheck_tests {
+    use expect_test::{expect, Expect};
+    use tokengen::span::Span;
+    use wacc_lexer::Lexer;
+    use wacc_parser::{
+        ast::{
+            BlockItem, Expression, ExpressionKind, ExternalDeclaration, InitializerEntryKind,
+            PostfixOperator, Statement, StatementKind,
+        },
+        dump::{dump_program, DumpFormat},
+        Parser,
+    };
+
+    use super::TypeChecker;
+    use crate::resolve::Resolver;
+
+    /// Render an expression fully parenthesized, with every implicit conversion
+    /// written as a cast in angle brackets, as in `(<long>i.0 + l.1)`.
+    fn render(expression: &Expression) -> String {
+        let ty = |expression: &Expression| match &expression.ty {
+            Some(ty) => ty.to_string(),
+            None => "?".to_string(),
+        };
+        match &expression.kind {
+            ExpressionKind::Constant(value) => value.to_string(),
+            ExpressionKind::Identifier(identifier) => identifier.name.clone(),
+            ExpressionKind::StringLiteral(bytes) => format!("\"{}\"", bytes.escape_ascii()),
+            ExpressionKind::Unary(op, operand) => format!("({op}{})", render(operand)),
+            ExpressionKind::Postfix(op, operand) => {
+                let op = match op {
+                    PostfixOperator::Increment => "++",
+                    PostfixOperator::Decrement => "--",
+                };
+                format!("({}{op})", render(operand))
+            }
+            ExpressionKind::Binary(op, left, right) => {
+                format!("({} {op} {})", render(left), render(right))
+            }
+            ExpressionKind::Assignment(op, left, right) => {
+                format!("({} {op} {})", render(left), render(right))
+            }
+            ExpressionKind::Conditional(condition, then, els) => format!(
+                "({} ? {} : {})",
+                render(condition),
+                render(then),
+                render(els)
+            ),
+            ExpressionKind::Cast(type_name, operand) => {
+                format!("(({}) {})", type_name.ty, render(operand))
+            }
+            ExpressionKind::CompoundLiteral(literal) => format!("(({}) {{...}})", literal.ty),
+            ExpressionKind::SizeofExpression(operand) => format!("(sizeof {})", render(operand)),
+            ExpressionKind::SizeofType(type_name) => format!("(sizeof({}))", type_name.ty),
+            ExpressionKind::Offsetof(type_name, _, offset) => {
+                format!("(offsetof({}) = {offset})", type_name.ty)
+            }
+            ExpressionKind::Call(function, arguments) => format!(
+                "{}({})",
+                render(function),
+                arguments.iter().map(render).collect::<Vec<_>>().join(", ")
+            ),
+            ExpressionKind::Subscript(base, index) => {
+                format!("{}[{}]", render(base), render(index))
+            }
+            ExpressionKind::Member(base, member) => format!("{}.{}", render(base), member.name),
+            ExpressionKind::PointerMember(base, member) => {
+                format!("{}->{}", render(base), member.name)
+            }
+            ExpressionKind::ImplicitConversion(_, operand) => {
+                format!("<{}>{}", ty(expression), render(operand))
+            }
+            ExpressionKind::Error => "<error>".to_string(),
+        }
+    }
+
+    /// Check a program, then list the expression statements, return values and
+    /// initializer entries directly in each function body with their types, then
+    /// any errors.
+    fn check_types(src: &str, expect: Expect) {
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let mut program = parser.parse_program();
+        assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+        let mut resolver = Resolver::new();
+        let resolution = resolver.resolve_program(&mut program);
+        assert!(resolver.errors().is_empty(), "{:?}", resolver.errors());
+        let mut checker = TypeChecker::new(&resolution);
+        checker.check_program(&mut program);
+
+        let mut output = String::new();
+        let mut line = |expression: &Expression| {
+            let ty = match &expression.ty {
+                Some(ty) => ty.to_string(),
+                None => "?".to_string(),
+            };
+            output.push_str(&format!("{} : {ty}\n", render(expression)));
+        };
+        for item in &program.items {
+            let ExternalDeclaration::Function(function) = item else {
+                continue;
+            };
+            for item in &function.body.items {
+                match item {
+                    BlockItem::Declaration(declaration) => {
+                        for entry in declaration
+                            .declarators
+                            .iter()
+                            .flat_map(|declarator| &declarator.entries)
+                        {
+                            if let InitializerEntryKind::Value(_, value)
+                            | InitializerEntryKind::BitField(_, _, _, value) = &entry.kind
+                            {
+                                line(value);
+                            }
+                        }
+                    }
+                    BlockItem::Statement(statement) => match &statement.kind {
+                        StatementKind::Expression(expression)
+                        | StatementKind::Return(Some(expression)) => line(expression),
+                        _ => {}
+                    },
+                    BlockItem::StaticAssert(static_assert) => line(&static_assert.condition),
+                }
+            }
+        }
+        for err in checker.errors() {
+            output.push_str(&format!("{} at {:?}\n", err.context(), err.span().span()));
+        }
+        expect.assert_eq(&output);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        check_types(
+            r#"
+            enum e { A, B };
+            int f(char c, short s, unsigned u, long l, unsigned long ul, double d, enum e e) {
+                c + c;
+                s * u;
+                u + l;
+                ul - l;
+                -1 + u;
+                d / c;
+                (float)c + 1;
+                c << l;
+                ~s;
+                !d;
+                c < u;
+                e == A;
+                c ? s : l;
+                c += d;
+                s <<= ul;
+                u = d;
+                c++;
+                2147483648;
+                int i = c;
+                return l;
+            }
+            "#,
+            expect![[r#"
+                (<int>c.0 + <int>c.0) : int
+                (<unsigned int>s.1 * u.2) : unsigned int
+                (<long>u.2 + l.3) : long
+                (ul.4 - <unsigned long>l.3) : unsigned long
+                (<unsigned int>(-1) + u.2) : unsigned int
+                (d.5 / <double>c.0) : double
+                (((float) <float>c.0) + <float>1) : float
+                (<int>c.0 << <int>l.3) : int
+                (~<int>s.1) : int
+                (!d.5) : int
+                (<unsigned int>c.0 < u.2) : int
+                (<unsigned int>e.6 == <unsigned int>A) : int
+                (c.0 ? <long>s.1 : l.3) : long
+                (c.0 += d.5) : char
+                (s.1 <<= <int>ul.4) : short
+                (u.2 = <unsigned int>d.5) : unsigned int
+                (c.0++) : char
+                2147483648l : long
+                <int>c.0 : int
+                <int>l.3 : int
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_constants() {
+        check_types(
+            r#"
+            int f(void) {
+                2147483647;
+                2147483648;
+                0x7fffffff;
+                0x80000000;
+                017777777777u;
+                4294967295U;
+                10L;
+                0xffffffffffffffffl;
+                10lu;
+                10LL;
+                0x8000000000000000ll;
+                10ull;
+                3.5;
+                .5e1;
+                1.f;
+                1e-3F;
+                0x1.8p1;
+                0X.1P-4f;
+                'a';
+                '\n';
+                '\377';
+                1 + 'a';
+                1.5f + 1;
+                return 0;
+            }
+            "#,
+            expect![[r#"
+                2147483647 : int
+                2147483648l : long
+                2147483647 : int
+                2147483648u : unsigned int
+                2147483647u : unsigned int
+                4294967295u : unsigned int
+                10l : long
+                18446744073709551615ul : unsigned long
+                10ul : unsigned long
+                10ll : long long
+                9223372036854775808ull : unsigned long long
+                10ull : unsigned long long
+                3.5 : double
+                5.0 : double
+                1.0f : float
+                0.001f : float
+                3.0 : double
+                0.00390625f : float
+                97 : int
+                10 : int
+                '\xff' : int
+                (1 + 97) : int
+                (1.5f + <float>1) : float
+                0 : int
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_pointers() {
+        check_types(
+            r#"
+            struct point { int x, y; };
+            int printf(const char *format, ...);
+            long sum(int *values, long n);
+            int old();
+            int f(int a[3], struct point *p, void *v, char c, float x) {
+                int matrix[2][3];
+                struct point points[2];
+                int *q = a + c;
+                matrix[1][c];
+                1[a];
+                points[0].x;
+                p->y;
+                &matrix[1];
+                q - a;
+                q == 0;
+                q != v;
+                c ? q : 0;
+                c ? v : q;
+                sum(a, c);
+                printf("%d %f", c, x);
+                old(c, x);
+                (long)q;
+                (int *)0;
+                v = q;
+                sizeof matrix;
+                return *q;
+            }
+            "#,
+            expect![[r#"
+                (a.3 + <long>c.6) : int *
+                <int *><int (*)[3]>matrix.8[<long>1][<long>c.6] : int
+                <long>1[a.3] : int
+                <struct point *>points.9[<long>0].x : int
+                p.4->y : int
+                (&<int (*)[3]>matrix.8[<long>1]) : int (*)[3]
+                (q.10 - a.3) : long
+                (q.10 == <int *>0) : int
+                (<void *>q.10 != v.5) : int
+                (c.6 ? q.10 : <int *>0) : int *
+                (c.6 ? v.5 : <void *>q.10) : void *
+                <long (*)(int *, long)>sum(a.3, <long>c.6) : long
+                <int (*)(const char *, ...)>printf(<const char *><char *>"%d %f", <int>c.6, <double>x.7) : int
+                <int (*)()>old(<int>c.6, <double>x.7) : int
+                ((long) <long>q.10) : long
+                ((int *) <int *>0) : int *
+                (v.5 = <void *>q.10) : void *
+                (sizeof matrix.8) : unsigned long
+                (*q.10) : int
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        check_types(
+            r#"
+            struct s { int x; unsigned bits : 3; } s;
+            struct incomplete;
+            int g(int, int);
+            void h(void);
+            int f(int *p, long *q, const int *c, struct incomplete *i, double d) {
+                p = q;
+                p = c;
+                p = 1;
+                d = p;
+                s = 1;
+                s + 1;
+                *d;
+                d % 2;
+                s.y;
+                d.x;
+                d->x;
+                p[d];
+                d[1];
+                g(1);
+                g(1, 2, 3);
+                d(1);
+                (struct s)1;
+                (int *)d;
+                &s.bits;
+                sizeof h;
+                sizeof(struct incomplete);
+                i + 1;
+                *c = 1;
+                p - q;
+                p < 1;
+                d ? p : q;
+                if (s) return 1;
+                switch (d) {}
+                h();
+                return;
+            }
+            void v(void) { return 1; }
+            void w(struct incomplete by_value) {
+                extern struct incomplete declared;
+                extern int unknown_length[];
+                typedef void nothing;
+                struct incomplete object;
+                void no_value;
+                int array[];
+                static int static_array[];
+                int sized[] = { 1, 2 };
+            }
+            "#,
+            expect![[r#"
+                (p.0 = q.1) : int *
+                (p.0 = c.2) : int *
+                (p.0 = 1) : int *
+                (d.4 = p.0) : double
+                (s = 1) : struct s
+                (s + 1) : ?
+                (*d.4) : ?
+                (d.4 % 2) : ?
+                s.y : ?
+                d.4.x : ?
+                d.4->x : ?
+                p.0[d.4] : ?
+                d.4[1] : ?
+                <int (*)(int, int)>g(1) : int
+                <int (*)(int, int)>g(1, 2, 3) : int
+                d.4(1) : ?
+                ((struct s) 1) : ?
+                ((int *) d.4) : ?
+                (&s.bits) : ?
+                (sizeof h) : ?
+                (sizeof(struct incomplete)) : ?
+                (i.3 + 1) : ?
+                ((*c.2) = 1) : ?
+                (p.0 - q.1) : ?
+                (p.0 < 1) : ?
+                (d.4 ? p.0 : q.1) : ?
+                <void (*)(void)>h() : void
+                1 : int
+                1 : int
+                2 : int
+                incompatible pointer types assigning to `int *` from `long *` at "q"
+                assigning to `int *` from `const int *` discards qualifiers at "c"
+                incompatible integer to pointer conversion assigning to `int *` from `int` at "1"
+                assigning to `double` from incompatible type `int *` at "p"
+                assigning to `struct s` from incompatible type `int` at "1"
+                invalid operands to binary expression (`struct s` and `int`) at "s + 1"
+                indirection requires pointer operand (`double` invalid) at "*d"
+                invalid operands to binary expression (`double` and `int`) at "d % 2"
+                no member named `y` in `struct s` at "y"
+                member reference base type `double` is not a structure or union at "d"
+                member reference type `double` is not a pointer at "d"
+                array subscript is not an integer at "d"
+                subscripted value is not an array or pointer at "d[1]"
+                too few arguments to function call, expected 2, have 1 at "g(1)"
+                too many arguments to function call, expected 2, have 3 at "g(1, 2, 3)"
+                called object type `double` is not a function or function pointer at "d"
+                used type `struct s` where arithmetic or pointer type is required at "struct s"
+                operand of type `double` cannot be cast to a pointer type at "d"
+                address of bit-field requested at "&s.bits"
+                invalid application of `sizeof` to a function type at "sizeof h"
+                invalid application of `sizeof` to an incomplete type `struct incomplete` at "sizeof(struct incomplete)"
+                arithmetic on a pointer to an incomplete type `struct incomplete` at "i + 1"
+                cannot assign to an expression with const-qualified type `const int` at "*c"
+                `int *` and `long *` are not pointers to compatible types at "p - q"
+                comparison between pointer and integer (`int *` and `int`) at "p < 1"
+                pointer type mismatch (`int *` and `long *`) at "d ? p : q"
+                statement requires expression of scalar type (`struct s` invalid) at "s"
+                statement requires expression of integer type (`double` invalid) at "d"
+                non-void function `f` should return a value at "return;"
+                void function `v` should not return a value at "1"
+                parameter has incomplete type `struct incomplete` at "struct incomplete by_value"
+                variable has incomplete type `struct incomplete` at "object"
+                variable has incomplete type `void` at "no_value"
+                variable has incomplete type `int []` at "array"
+                variable has incomplete type `int []` at "static_array"
+            "#]],
+        );
+    }
+
+    /// The value of every `case` label, once converted, then any errors.
+    #[test]
+    fn test_switch_cases() {
+        let src = r#"
+            int f(unsigned u, int i) {
+                switch (u) { case -1: case 4294967295u: case 7l: ; }
+                switch (i) { case 4294967300l: case 4: case 'a': ; }
+                switch ((unsigned long)i) { case 0xFFFFFFFFFFFFFFFFul: case -2: ; }
+                switch ((char)i) { case 300: case 44: ; }
+                return 0;
+            }
+        "#;
+        let tokens = String::lex_c(src);
+        let mut parser = Parser::new(src, &tokens);
+        let mut program = parser.parse_program();
+        assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+        let mut resolver = Resolver::new();
+        let resolution = resolver.resolve_program(&mut program);
+        let mut checker = TypeChecker::new(&resolution);
+        checker.check_program(&mut program);
+
+        fn cases(statement: &Statement, values: &mut Vec<String>) {
+            match &statement.kind {
+                StatementKind::Case(label, value, body) => {
+                    values.push(format!("{} -> {value}", label.span.span()));
+                    cases(body, values);
+                }
+                StatementKind::Switch(_, body) => cases(body, values),
+                StatementKind::Compound(block) => {
+                    for item in &block.items {
+                        if let BlockItem::Statement(statement) = item {
+                            cases(statement, values);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        let mut output = Vec::new();
+        for item in &program.items {
+            if let ExternalDeclaration::Function(function) = item {
+                for item in &function.body.items {
+                    if let BlockItem::Statement(statement) = item {
+                        cases(statement, &mut output);
+                    }
+                }
+            }
+        }
+        for err in checker.errors() {
+            output.push(format!("{} at {:?}", err.context(), err.span().span()));
+        }
+        expect![[r#"
+            -1 -> 4294967295
+            4294967295u -> 4294967295
+            7l -> 7
+            4294967300l -> 4
+            4 -> 4
+            'a' -> 97
+            0xFFFFFFFFFFFFFFFFul -> 18446744073709551615
+            -2 -> 18446744073709551614
+            300 -> 300
+            44 -> 44
+            duplicate case value 4294967295 at "4294967295u"
+            duplicate case value 4 at "4""#]]
+        .assert_eq(&output.join("\n"));
+    }
+
+    #[test]
+    fn test_dump() {
+        let src = "int f(int *p, char c) { return p[c] + c; }";
+        let tokens = String::lex_c(src);
+        let mut program = Parser::new(src, &tokens).parse_program();
+        let resolution = Resolver::new().resolve_program(&mut program);
+        let mut checker = TypeChecker::new(&resolution);
+        checker.check_program(&mut program);
+        assert!(checker.errors().is_empty());
+        expect![[r#"
+            Program@0..42
+              items:
+                FunctionDefinition@0..42 name="f" ty="int (int *, char)"
+                  specifiers: Specifiers@0..3 types=(int)
+                  declarator: FunctionDeclarator@4..21
+                    declarator: NameDeclarator@4..5 name="f"
+                    parameters: ParameterList@5..21 prototype=true variadic=false
+                      parameters:
+                        Parameter@6..12 name="p.0" ty="int *"
+                          specifiers: Specifiers@6..9 types=(int)
+                          declarator: PointerDeclarator@10..12
+                            declarator: NameDeclarator@11..12 name="p.0"
+                        Parameter@14..20 name="c.1" ty="char"
+                          specifiers: Specifiers@14..18 types=(char)
+                          declarator: NameDeclarator@19..20 name="c.1"
+                  body:
+                    Return@24..40
+                      value: Binary@31..39 operator=+ ty="int"
+                        lhs: Subscript@31..35 ty="int"
+                          base: Identifier@31..32 name="p.0" ty="int *"
+                          index: ImplicitConversion@33..34 conversion=Integer ty="long"
+                            operand: Identifier@33..34 name="c.1" ty="char"
+                        rhs: ImplicitConversion@38..39 conversion=Integer ty="int"
+                          operand: Identifier@38..39 name="c.1" ty="char"
+        "#]]
+        .assert_eq(&dump_program(&program, DumpFormat::Tree));
+    }
+
+    #[test]
+    fn test_warnings() {
+        let src = r#"
+            enum small { A = 1 };
+            long l;
+            void take(short s);
+            float f(int i, unsigned u, unsigned long ul, double d) {
+                char c = 300;
+                char ok = 97 + 1;
+                int narrowed = l;
+                enum small e = 2;
+                long widened = i;
+                take(i);
+                c = d;
+                if (i < u || u == -1 || i != ul || u > A || l < u) return i;
+                return d;
+            }
+        "#;
+        let tokens = String::lex_c(src);
+        let mut program = Parser::new(src, &tokens).parse_program();
+        let resolution = Resolver::new().resolve_program(&mut program);
+        let mut checker = TypeChecker::new(&resolution);
+        checker.check_program(&mut program);
+        assert!(checker.errors().is_empty(), "{:?}", checker.errors());
+        let output = checker
+            .warnings()
+            .iter()
+            .map(|warning| {
+                let kind = warning.kind();
+                format!(
+                    "{kind}: {} at {:?}",
+                    warning.context(),
+                    warning.span().span()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        expect![[r#"
+            conversion: implicit conversion from `int` to `char` changes value from 300 to 44 at "300"
+            conversion: implicit conversion from `long` to `int` may change value at "l"
+            conversion: implicit conversion from `int` to `short` may change value at "i"
+            conversion: implicit conversion from `double` to `char` may change value at "d"
+            sign-compare: comparison of integers of different signs: `int` and `unsigned int` at "i < u"
+            sign-compare: comparison of integers of different signs: `unsigned int` and `int` at "u == -1"
+            sign-compare: comparison of integers of different signs: `int` and `unsigned long` at "i != ul"
+            conversion: implicit conversion from `int` to `float` may change value at "i"
+            conversion: implicit conversion from `double` to `float` may change value at "d""#]].assert_eq(&output);
+    }
+}
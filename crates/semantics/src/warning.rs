@@ -0,0 +1,307 @@
+//! Warnings: diagnostics about valid C that is probably not what was meant.
+//!
+//! The passes report every warning they find, whatever the command line asked
+//! for, and [`WarningOptions`] decides which of them are shown and which are
+//! errors, following the `-W` flags GCC takes:
+//!
+//! - `-W<name>` and `-Wno-<name>` turn one warning on or off;
+//! - `-Wall` and `-Wextra` turn on a group of them, except those turned off by name;
+//! - `-Werror` makes every warning that is on an error, and `-Werror=<name>` turns
+//!   one on as an error. `-Wno-error=<name>` keeps one a warning under `-Werror`.
+//!
+//! Only `return-type` is on by default.
+
+use std::{collections::HashSet, fmt};
+
+use tokengen::span::{SourceSpan, Span};
+
+use crate::SemanticError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+    /// A local variable that is never referred to.
+    UnusedVariable,
+    /// A parameter of a function definition that is never referred to.
+    UnusedParameter,
+    /// An implicit conversion to a type that can't represent every value of the original.
+    Conversion,
+    /// A comparison that converts a signed operand to unsigned.
+    SignCompare,
+    /// A non-void function whose body can end without returning a value.
+    ReturnType,
+    /// A statement no path through the function reaches.
+    UnreachableCode,
+    /// A local declaration that hides another declaration of the same name.
+    Shadow,
+}
+impl WarningKind {
+    pub const ALL: [Self; 7] = [
+        Self::UnusedVariable,
+        Self::UnusedParameter,
+        Self::Conversion,
+        Self::SignCompare,
+        Self::ReturnType,
+        Self::UnreachableCode,
+        Self::Shadow,
+    ];
+    /// The warnings on without any flags.
+    const DEFAULT: [Self; 1] = [Self::ReturnType];
+    /// The warnings `-Wall` turns on.
+    const ALL_GROUP: [Self; 3] = [
+        Self::UnusedVariable,
+        Self::ReturnType,
+        Self::UnreachableCode,
+    ];
+    /// The warnings `-Wextra` turns on.
+    const EXTRA_GROUP: [Self; 2] = [Self::UnusedParameter, Self::SignCompare];
+
+    /// The name of the warning in `-W<name>` flags.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::UnusedVariable => "unused-variable",
+            Self::UnusedParameter => "unused-parameter",
+            Self::Conversion => "conversion",
+            Self::SignCompare => "sign-compare",
+            Self::ReturnType => "return-type",
+            Self::UnreachableCode => "unreachable-code",
+            Self::Shadow => "shadow",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("{location}: warning: {context} [-W{kind}]:\n{source}", location = span.location(), source = span.span())]
+pub struct Warning {
+    kind: WarningKind,
+    span: SourceSpan,
+    context: String,
+}
+impl Warning {
+    pub fn new(kind: WarningKind, span: SourceSpan, context: &str) -> Self {
+        Self {
+            kind,
+            span,
+            context: context.into(),
+        }
+    }
+    pub fn kind(&self) -> WarningKind {
+        self.kind
+    }
+    pub fn span(&self) -> SourceSpan {
+        self.span
+    }
+    pub fn context(&self) -> &str {
+        &self.context
+    }
+    /// The warning reported as an error, for `-Werror`.
+    pub fn to_error(&self) -> SemanticError {
+        let context = format!("{} [-Werror={}]", self.context, self.kind);
+        SemanticError::new(self.span, &context)
+    }
+}
+
+/// Whether a warning that is on is shown as a warning or as an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// The warnings turned on by `-W` flags, and which of them are errors.
+#[derive(Debug, Clone)]
+pub struct WarningOptions {
+    enabled: HashSet<WarningKind>,
+    /// The warnings turned on or off by name, which groups don't change.
+    explicit: HashSet<WarningKind>,
+    /// The warnings made errors by `-Werror=<name>`.
+    errors: HashSet<WarningKind>,
+    /// The warnings kept as warnings by `-Wno-error=<name>`.
+    not_errors: HashSet<WarningKind>,
+    /// Whether `-Werror` was given.
+    all_errors: bool,
+}
+impl Default for WarningOptions {
+    fn default() -> Self {
+        Self {
+            enabled: WarningKind::DEFAULT.into_iter().collect(),
+            explicit: HashSet::new(),
+            errors: HashSet::new(),
+            not_errors: HashSet::new(),
+            all_errors: false,
+        }
+    }
+}
+impl WarningOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply `-W` flags in the order they were given, without the leading `-W`,
+    /// so `-Wno-shadow` is `no-shadow`.
+    pub fn from_flags<'a>(flags: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        let mut options = Self::new();
+        for flag in flags {
+            options.apply(flag)?;
+        }
+        Ok(options)
+    }
+
+    /// Apply a single `-W` flag, without the leading `-W`.
+    pub fn apply(&mut self, flag: &str) -> Result<(), String> {
+        let kind = |name: &str| {
+            WarningKind::from_name(name).ok_or_else(|| format!("unknown warning option `-W{flag}`"))
+        };
+        match flag {
+            "all" => self.enable_group(&WarningKind::ALL_GROUP),
+            "extra" => self.enable_group(&WarningKind::EXTRA_GROUP),
+            "error" => self.all_errors = true,
+            "no-error" => self.all_errors = false,
+            _ => {
+                if let Some(name) = flag.strip_prefix("error=") {
+                    let kind = kind(name)?;
+                    self.set(kind, true);
+                    self.errors.insert(kind);
+                    self.not_errors.remove(&kind);
+                } else if let Some(name) = flag.strip_prefix("no-error=") {
+                    let kind = kind(name)?;
+                    self.errors.remove(&kind);
+                    self.not_errors.insert(kind);
+                } else if let Some(name) = flag.strip_prefix("no-") {
+                    self.set(kind(name)?, false);
+                } else {
+                    self.set(kind(flag)?, true);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// How a warning of this kind is reported, or `None` if it is off.
+    pub fn severity(&self, kind: WarningKind) -> Option<Severity> {
+        if !self.enabled.contains(&kind) {
+            return None;
+        }
+        let is_error =
+            self.errors.contains(&kind) || (self.all_errors && !self.not_errors.contains(&kind));
+        match is_error {
+            true => Some(Severity::Error),
+            false => Some(Severity::Warning),
+        }
+    }
+
+    fn set(&mut self, kind: WarningKind, enabled: bool) {
+        self.explicit.insert(kind);
+        match enabled {
+            true => self.enabled.insert(kind),
+            false => self.enabled.remove(&kind),
+        };
+    }
+
+    fn enable_group(&mut self, group: &[WarningKind]) {
+        let implicit = group.iter().filter(|kind| !self.explicit.contains(kind));
+        self.enabled.extend(implicit);
+    }
+}
+
+#[cfg(test)]
+mod warning_tests {
+    use expect_test::{expect, Expect};
+
+    use super::{WarningKind, WarningOptions};
+
+    /// The severity of every warning after applying the flags, or the error.
+    fn check_flags(flags: &[&str], expect: Expect) {
+        let output = match WarningOptions::from_flags(flags.iter().copied()) {
+            Ok(options) => WarningKind::ALL
+                .into_iter()
+                .map(|kind| format!("{kind}: {:?}", options.severity(kind)))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Err(err) => err,
+        };
+        expect.assert_eq(&output);
+    }
+
+    #[test]
+    fn test_default() {
+        check_flags(
+            &[],
+            expect![[r#"
+                unused-variable: None
+                unused-parameter: None
+                conversion: None
+                sign-compare: None
+                return-type: Some(Warning)
+                unreachable-code: None
+                shadow: None"#]],
+        );
+    }
+
+    #[test]
+    fn test_groups() {
+        check_flags(
+            &[
+                "no-unused-variable",
+                "all",
+                "extra",
+                "shadow",
+                "no-return-type",
+            ],
+            expect![[r#"
+                unused-variable: None
+                unused-parameter: Some(Warning)
+                conversion: None
+                sign-compare: Some(Warning)
+                return-type: None
+                unreachable-code: Some(Warning)
+                shadow: Some(Warning)"#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        check_flags(
+            &[
+                "all",
+                "error",
+                "no-error=unreachable-code",
+                "error=conversion",
+            ],
+            expect![[r#"
+                unused-variable: Some(Error)
+                unused-parameter: None
+                conversion: Some(Error)
+                sign-compare: None
+                return-type: Some(Error)
+                unreachable-code: Some(Warning)
+                shadow: None"#]],
+        );
+        check_flags(
+            &["error=shadow", "no-shadow"],
+            expect![[r#"
+                unused-variable: None
+                unused-parameter: None
+                conversion: None
+                sign-compare: None
+                return-type: Some(Warning)
+                unreachable-code: None
+                shadow: None"#]],
+        );
+        check_flags(
+            &["no-such-warning"],
+            expect!["unknown warning option `-Wno-such-warning`"],
+        );
+        check_flags(
+            &["error=everything"],
+            expect!["unknown warning option `-Werror=everything`"],
+        );
+    }
+}
@@ -0,0 +1,882 @@
+//! Typed views over the concrete syntax tree.
+//!
+//! Each view wraps a [`SyntaxNode`] of one kind and finds its parts among the
+//! node's children when asked, so a view is as cheap as the node it wraps and
+//! always agrees with the tree. Parts are optional because a tree with syntax
+//! errors may be missing any of them.
+
+use wacc_lexer::c_token::{
+    c_keyword::KeywordKind,
+    c_symbol::{CookedSymbolKind, SymbolKind},
+    CTokenKind,
+};
+
+use crate::{
+    red::{SyntaxElement, SyntaxNode, SyntaxToken},
+    SyntaxKind,
+};
+
+pub trait AstNode: Sized {
+    fn can_cast(kind: SyntaxKind) -> bool;
+    fn cast(node: SyntaxNode) -> Option<Self>;
+    fn syntax(&self) -> &SyntaxNode;
+}
+
+/// Views over a single node kind, named after the [`SyntaxKind`] they wrap.
+macro_rules! ast_nodes {
+    ($($name:ident),* $(,)?) => {
+        $(
+            #[derive(Debug, Clone, PartialEq, Eq)]
+            pub struct $name(SyntaxNode);
+            impl AstNode for $name {
+                fn can_cast(kind: SyntaxKind) -> bool {
+                    kind == SyntaxKind::$name
+                }
+                fn cast(node: SyntaxNode) -> Option<Self> {
+                    Self::can_cast(node.kind()).then(|| Self(node))
+                }
+                fn syntax(&self) -> &SyntaxNode {
+                    &self.0
+                }
+            }
+        )*
+    };
+}
+
+/// Views over any one of several kinds of node.
+macro_rules! ast_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident($ty:ty)),* $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub enum $name {
+            $($variant($ty),)*
+        }
+        impl AstNode for $name {
+            fn can_cast(kind: SyntaxKind) -> bool {
+                $(<$ty>::can_cast(kind))||*
+            }
+            fn cast(node: SyntaxNode) -> Option<Self> {
+                $(
+                    if <$ty>::can_cast(node.kind()) {
+                        return <$ty>::cast(node).map(Self::$variant);
+                    }
+                )*
+                None
+            }
+            fn syntax(&self) -> &SyntaxNode {
+                match self {
+                    $(Self::$variant(node) => node.syntax(),)*
+                }
+            }
+        }
+    };
+}
+
+ast_nodes!(
+    SourceFile,
+    FunctionDefinition,
+    Declaration,
+    StaticAssert,
+    Specifiers,
+    RecordSpecifier,
+    MemberList,
+    MemberDeclaration,
+    MemberDeclarator,
+    EnumSpecifier,
+    Enumerator,
+    InitDeclarator,
+    NameDeclarator,
+    PointerDeclarator,
+    ParenDeclarator,
+    ArrayDeclarator,
+    FunctionDeclarator,
+    ParameterList,
+    Parameter,
+    TypeName,
+    InitializerList,
+    InitializerItem,
+    IndexDesignator,
+    MemberDesignator,
+    CompoundStatement,
+    ExpressionStatement,
+    ReturnStatement,
+    IfStatement,
+    WhileStatement,
+    DoStatement,
+    ForStatement,
+    SwitchStatement,
+    CaseStatement,
+    DefaultStatement,
+    LabeledStatement,
+    GotoStatement,
+    BreakStatement,
+    ContinueStatement,
+    NameExpr,
+    LiteralExpr,
+    ParenExpr,
+    CastExpr,
+    PrefixExpr,
+    PostfixExpr,
+    BinaryExpr,
+    ConditionalExpr,
+    CallExpr,
+    ArgumentList,
+    IndexExpr,
+    MemberExpr,
+    SizeofExpr,
+    CompoundLiteralExpr,
+);
+
+ast_enum!(Item {
+    FunctionDefinition(FunctionDefinition),
+    Declaration(Declaration),
+    StaticAssert(StaticAssert),
+});
+ast_enum!(BlockItem {
+    Declaration(Declaration),
+    StaticAssert(StaticAssert),
+    Statement(Statement),
+});
+ast_enum!(Statement {
+    Compound(CompoundStatement),
+    Expression(ExpressionStatement),
+    Return(ReturnStatement),
+    If(IfStatement),
+    While(WhileStatement),
+    Do(DoStatement),
+    For(ForStatement),
+    Switch(SwitchStatement),
+    Case(CaseStatement),
+    Default(DefaultStatement),
+    Labeled(LabeledStatement),
+    Goto(GotoStatement),
+    Break(BreakStatement),
+    Continue(ContinueStatement),
+});
+ast_enum!(Expression {
+    Name(NameExpr),
+    Literal(LiteralExpr),
+    Paren(ParenExpr),
+    Cast(CastExpr),
+    Prefix(PrefixExpr),
+    Postfix(PostfixExpr),
+    Binary(BinaryExpr),
+    Conditional(ConditionalExpr),
+    Call(CallExpr),
+    Index(IndexExpr),
+    Member(MemberExpr),
+    Sizeof(SizeofExpr),
+    CompoundLiteral(CompoundLiteralExpr),
+});
+ast_enum!(Declarator {
+    Name(NameDeclarator),
+    Pointer(PointerDeclarator),
+    Paren(ParenDeclarator),
+    Array(ArrayDeclarator),
+    Function(FunctionDeclarator),
+});
+ast_enum!(Initializer {
+    Expression(Expression),
+    List(InitializerList),
+});
+ast_enum!(Designator {
+    Index(IndexDesignator),
+    Member(MemberDesignator),
+});
+
+fn child<N: AstNode>(parent: &SyntaxNode) -> Option<N> {
+    parent.children().find_map(N::cast)
+}
+fn nth_child<N: AstNode>(parent: &SyntaxNode, n: usize) -> Option<N> {
+    parent.children().filter_map(N::cast).nth(n)
+}
+fn children<'a, N: AstNode + 'a>(parent: &'a SyntaxNode) -> impl Iterator<Item = N> + 'a {
+    parent.children().filter_map(N::cast)
+}
+/// The first token of the given kind directly below `parent`.
+fn token(parent: &SyntaxNode, kind: CTokenKind) -> Option<SyntaxToken> {
+    parent
+        .children_with_tokens()
+        .filter_map(SyntaxElement::into_token)
+        .find(|token| token.kind() == SyntaxKind::Token(kind))
+}
+/// The first token directly below `parent` that isn't trivia.
+fn first_token(parent: &SyntaxNode) -> Option<SyntaxToken> {
+    parent
+        .children_with_tokens()
+        .filter_map(SyntaxElement::into_token)
+        .find(|token| !token.kind().is_trivia())
+}
+
+impl SourceFile {
+    /// The view of the root of a tree, which is always a source file.
+    pub(crate) fn from_root(root: SyntaxNode) -> Self {
+        Self::cast(root).expect("the root of a syntax tree is a source file")
+    }
+    pub fn items(&self) -> impl Iterator<Item = Item> + '_ {
+        children(&self.0)
+    }
+}
+
+impl FunctionDefinition {
+    pub fn specifiers(&self) -> Option<Specifiers> {
+        child(&self.0)
+    }
+    pub fn declarator(&self) -> Option<Declarator> {
+        child(&self.0)
+    }
+    pub fn body(&self) -> Option<CompoundStatement> {
+        child(&self.0)
+    }
+}
+
+impl Declaration {
+    pub fn specifiers(&self) -> Option<Specifiers> {
+        child(&self.0)
+    }
+    pub fn declarators(&self) -> impl Iterator<Item = InitDeclarator> + '_ {
+        children(&self.0)
+    }
+}
+
+impl StaticAssert {
+    pub fn condition(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+    /// The message: a literal expression of adjacent string literals.
+    pub fn message(&self) -> Option<Expression> {
+        nth_child(&self.0, 1)
+    }
+}
+
+impl Specifiers {
+    /// Whether these specifiers declare typedef names.
+    pub fn is_typedef(&self) -> bool {
+        token(&self.0, CTokenKind::Keyword(KeywordKind::Typedef)).is_some()
+    }
+    /// The typedef name naming the type, if that is how it is specified.
+    pub fn typedef_name(&self) -> Option<SyntaxToken> {
+        token(&self.0, CTokenKind::Identifier)
+    }
+    pub fn record_specifier(&self) -> Option<RecordSpecifier> {
+        child(&self.0)
+    }
+    pub fn enum_specifier(&self) -> Option<EnumSpecifier> {
+        child(&self.0)
+    }
+}
+
+impl RecordSpecifier {
+    pub fn tag(&self) -> Option<SyntaxToken> {
+        token(&self.0, CTokenKind::Identifier)
+    }
+    pub fn members(&self) -> impl Iterator<Item = MemberDeclaration> {
+        child::<MemberList>(&self.0)
+            .into_iter()
+            .flat_map(|list| children(&list.0).collect::<Vec<_>>())
+    }
+}
+
+impl MemberDeclaration {
+    pub fn specifiers(&self) -> Option<Specifiers> {
+        child(&self.0)
+    }
+    pub fn declarators(&self) -> impl Iterator<Item = MemberDeclarator> + '_ {
+        children(&self.0)
+    }
+}
+
+impl MemberDeclarator {
+    pub fn declarator(&self) -> Option<Declarator> {
+        child(&self.0)
+    }
+    /// The width of a bit-field.
+    pub fn width(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+impl EnumSpecifier {
+    pub fn tag(&self) -> Option<SyntaxToken> {
+        token(&self.0, CTokenKind::Identifier)
+    }
+    pub fn enumerators(&self) -> impl Iterator<Item = Enumerator> {
+        self.0
+            .children()
+            .find(|node| node.kind() == SyntaxKind::EnumeratorList)
+            .into_iter()
+            .flat_map(|list| children(&list).collect::<Vec<_>>())
+    }
+}
+
+impl Enumerator {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, CTokenKind::Identifier)
+    }
+    pub fn value(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+impl InitDeclarator {
+    pub fn declarator(&self) -> Option<Declarator> {
+        child(&self.0)
+    }
+    pub fn initializer(&self) -> Option<Initializer> {
+        child(&self.0)
+    }
+}
+
+impl Declarator {
+    /// The identifier declared, found by following nested declarators inwards.
+    pub fn name(&self) -> Option<SyntaxToken> {
+        match self {
+            Self::Name(name) => name.name(),
+            Self::Pointer(pointer) => pointer.declarator()?.name(),
+            Self::Paren(paren) => paren.declarator()?.name(),
+            Self::Array(array) => array.declarator()?.name(),
+            Self::Function(function) => function.declarator()?.name(),
+        }
+    }
+}
+
+impl NameDeclarator {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, CTokenKind::Identifier)
+    }
+}
+
+impl PointerDeclarator {
+    pub fn declarator(&self) -> Option<Declarator> {
+        child(&self.0)
+    }
+}
+
+impl ParenDeclarator {
+    pub fn declarator(&self) -> Option<Declarator> {
+        child(&self.0)
+    }
+}
+
+impl ArrayDeclarator {
+    /// The declarator the array suffix applies to, absent in an abstract declarator.
+    pub fn declarator(&self) -> Option<Declarator> {
+        child(&self.0)
+    }
+    pub fn length(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+impl FunctionDeclarator {
+    /// The declarator the parameter list applies to, absent in an abstract declarator.
+    pub fn declarator(&self) -> Option<Declarator> {
+        child(&self.0)
+    }
+    pub fn parameter_list(&self) -> Option<ParameterList> {
+        child(&self.0)
+    }
+}
+
+impl ParameterList {
+    pub fn parameters(&self) -> impl Iterator<Item = Parameter> + '_ {
+        children(&self.0)
+    }
+    pub fn is_variadic(&self) -> bool {
+        token(&self.0, CTokenKind::Operator(CookedSymbolKind::Ellipsis)).is_some()
+    }
+}
+
+impl Parameter {
+    pub fn specifiers(&self) -> Option<Specifiers> {
+        child(&self.0)
+    }
+    pub fn declarator(&self) -> Option<Declarator> {
+        child(&self.0)
+    }
+}
+
+impl TypeName {
+    pub fn specifiers(&self) -> Option<Specifiers> {
+        child(&self.0)
+    }
+    pub fn declarator(&self) -> Option<Declarator> {
+        child(&self.0)
+    }
+}
+
+impl InitializerList {
+    pub fn items(&self) -> impl Iterator<Item = InitializerItem> + '_ {
+        children(&self.0)
+    }
+}
+
+impl InitializerItem {
+    pub fn designators(&self) -> impl Iterator<Item = Designator> + '_ {
+        children(&self.0)
+    }
+    pub fn initializer(&self) -> Option<Initializer> {
+        child(&self.0)
+    }
+}
+
+impl IndexDesignator {
+    pub fn index(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+impl MemberDesignator {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, CTokenKind::Identifier)
+    }
+}
+
+impl CompoundStatement {
+    pub fn items(&self) -> impl Iterator<Item = BlockItem> + '_ {
+        children(&self.0)
+    }
+}
+
+impl ExpressionStatement {
+    /// The expression evaluated, absent in a null statement.
+    pub fn expression(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+impl ReturnStatement {
+    pub fn expression(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+impl IfStatement {
+    pub fn condition(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+    pub fn then_branch(&self) -> Option<Statement> {
+        nth_child(&self.0, 0)
+    }
+    pub fn else_branch(&self) -> Option<Statement> {
+        nth_child(&self.0, 1)
+    }
+}
+
+impl WhileStatement {
+    pub fn condition(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+    pub fn body(&self) -> Option<Statement> {
+        child(&self.0)
+    }
+}
+
+impl DoStatement {
+    pub fn body(&self) -> Option<Statement> {
+        child(&self.0)
+    }
+    pub fn condition(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+/// A clause in the header of a `for` statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ForClause {
+    Initializer,
+    Condition,
+    Step,
+}
+
+impl ForStatement {
+    /// The declaration opening the loop, if it opens with one rather than an expression.
+    pub fn declaration(&self) -> Option<Declaration> {
+        child(&self.0)
+    }
+    /// The expression opening the loop, if it opens with one rather than a declaration.
+    pub fn initializer(&self) -> Option<Expression> {
+        self.clause(ForClause::Initializer)
+    }
+    pub fn condition(&self) -> Option<Expression> {
+        self.clause(ForClause::Condition)
+    }
+    pub fn step(&self) -> Option<Expression> {
+        self.clause(ForClause::Step)
+    }
+    pub fn body(&self) -> Option<Statement> {
+        child(&self.0)
+    }
+    /// The expression in a clause, told apart from the others by the `;`s before it.
+    fn clause(&self, clause: ForClause) -> Option<Expression> {
+        let mut current = ForClause::Initializer;
+        for element in self.0.children_with_tokens() {
+            match element {
+                SyntaxElement::Node(node) if node.kind() == SyntaxKind::Declaration => {
+                    current = ForClause::Condition;
+                }
+                SyntaxElement::Node(node) => match Expression::cast(node) {
+                    Some(expression) if current == clause => return Some(expression),
+                    _ => {}
+                },
+                SyntaxElement::Token(token)
+                    if token.kind()
+                        == SyntaxKind::Token(CTokenKind::Punctuator(SymbolKind::Semicolon)) =>
+                {
+                    current = match current {
+                        ForClause::Initializer => ForClause::Condition,
+                        _ => ForClause::Step,
+                    };
+                }
+                SyntaxElement::Token(_) => {}
+            }
+        }
+        None
+    }
+}
+
+impl SwitchStatement {
+    pub fn condition(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+    pub fn body(&self) -> Option<Statement> {
+        child(&self.0)
+    }
+}
+
+impl CaseStatement {
+    pub fn value(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+    pub fn body(&self) -> Option<Statement> {
+        child(&self.0)
+    }
+}
+
+impl DefaultStatement {
+    pub fn body(&self) -> Option<Statement> {
+        child(&self.0)
+    }
+}
+
+impl LabeledStatement {
+    pub fn label(&self) -> Option<SyntaxToken> {
+        token(&self.0, CTokenKind::Identifier)
+    }
+    pub fn body(&self) -> Option<Statement> {
+        child(&self.0)
+    }
+}
+
+impl GotoStatement {
+    pub fn label(&self) -> Option<SyntaxToken> {
+        token(&self.0, CTokenKind::Identifier)
+    }
+}
+
+impl NameExpr {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, CTokenKind::Identifier)
+    }
+}
+
+impl LiteralExpr {
+    /// The constant, or each of the adjacent string literals making up one literal.
+    pub fn tokens(&self) -> impl Iterator<Item = SyntaxToken> + '_ {
+        self.0
+            .children_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .filter(|token| !token.kind().is_trivia())
+    }
+}
+
+impl ParenExpr {
+    pub fn expression(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+impl CastExpr {
+    pub fn type_name(&self) -> Option<TypeName> {
+        child(&self.0)
+    }
+    pub fn operand(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+impl PrefixExpr {
+    pub fn operator(&self) -> Option<SyntaxToken> {
+        first_token(&self.0)
+    }
+    pub fn operand(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+impl PostfixExpr {
+    pub fn operand(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+    pub fn operator(&self) -> Option<SyntaxToken> {
+        first_token(&self.0)
+    }
+}
+
+impl BinaryExpr {
+    pub fn lhs(&self) -> Option<Expression> {
+        nth_child(&self.0, 0)
+    }
+    pub fn operator(&self) -> Option<SyntaxToken> {
+        first_token(&self.0)
+    }
+    pub fn rhs(&self) -> Option<Expression> {
+        nth_child(&self.0, 1)
+    }
+}
+
+impl ConditionalExpr {
+    pub fn condition(&self) -> Option<Expression> {
+        nth_child(&self.0, 0)
+    }
+    pub fn then_value(&self) -> Option<Expression> {
+        nth_child(&self.0, 1)
+    }
+    pub fn else_value(&self) -> Option<Expression> {
+        nth_child(&self.0, 2)
+    }
+}
+
+impl CallExpr {
+    pub fn callee(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+    pub fn arguments(&self) -> impl Iterator<Item = Expression> {
+        child::<ArgumentList>(&self.0)
+            .into_iter()
+            .flat_map(|list| children(&list.0).collect::<Vec<_>>())
+    }
+}
+
+impl IndexExpr {
+    pub fn base(&self) -> Option<Expression> {
+        nth_child(&self.0, 0)
+    }
+    pub fn index(&self) -> Option<Expression> {
+        nth_child(&self.0, 1)
+    }
+}
+
+impl MemberExpr {
+    pub fn base(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+    /// The `.` or `->` selecting the member.
+    pub fn operator(&self) -> Option<SyntaxToken> {
+        first_token(&self.0)
+    }
+    pub fn member(&self) -> Option<SyntaxToken> {
+        token(&self.0, CTokenKind::Identifier)
+    }
+}
+
+impl SizeofExpr {
+    /// The type measured, in `sizeof (type)`.
+    pub fn type_name(&self) -> Option<TypeName> {
+        child(&self.0)
+    }
+    /// The expression measured, in `sizeof expression`.
+    pub fn operand(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+impl CompoundLiteralExpr {
+    pub fn type_name(&self) -> Option<TypeName> {
+        child(&self.0)
+    }
+    pub fn initializer_list(&self) -> Option<InitializerList> {
+        child(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod ast_tests {
+    use expect_test::expect;
+
+    use super::*;
+    use crate::parse;
+
+    fn text(node: Option<impl AstNode>) -> String {
+        node.map_or_else(
+            || "-".into(),
+            |node| node.syntax().text().trim().to_string(),
+        )
+    }
+
+    #[test]
+    fn test_accessors() {
+        let src = r#"
+            typedef struct pair { int first, second : 4; } pair;
+            enum { ZERO, TWO = 2 };
+            _Static_assert(TWO == 2, "two");
+            int sum(pair *p, int (*scale)(int), ...) {
+                for (int i = 0; i < 2; i++) if (i) return scale(p->first) + 1; else ;
+                for (;;) break;
+                return (pair){ .first = 1, [0] = 2 }.second ? sizeof (int) : -1;
+            }
+        "#;
+        let tree = parse(src).tree();
+        let mut output = Vec::new();
+        for item in tree.items() {
+            match item {
+                Item::Declaration(declaration) => {
+                    let specifiers = declaration.specifiers().unwrap();
+                    if let Some(record) = specifiers.record_specifier() {
+                        for member in record.members() {
+                            for declarator in member.declarators() {
+                                output.push(format!(
+                                    "member {} width {}",
+                                    text(declarator.declarator()),
+                                    text(declarator.width())
+                                ));
+                            }
+                        }
+                    }
+                    if let Some(enumeration) = specifiers.enum_specifier() {
+                        for enumerator in enumeration.enumerators() {
+                            output.push(format!(
+                                "enumerator {} = {}",
+                                enumerator.name().unwrap(),
+                                text(enumerator.value())
+                            ));
+                        }
+                    }
+                    for declarator in declaration.declarators() {
+                        let name = declarator.declarator().and_then(|d| d.name()).unwrap();
+                        output.push(format!("typedef {}: {name}", specifiers.is_typedef()));
+                    }
+                }
+                Item::StaticAssert(static_assert) => {
+                    output.push(format!(
+                        "static assert {} {}",
+                        text(static_assert.condition()),
+                        text(static_assert.message())
+                    ));
+                }
+                Item::FunctionDefinition(function) => {
+                    let Some(Declarator::Function(declarator)) = function.declarator() else {
+                        panic!("expected a function declarator");
+                    };
+                    let parameters = declarator.parameter_list().unwrap();
+                    output.push(format!(
+                        "function {} variadic {}",
+                        declarator.declarator().unwrap().name().unwrap(),
+                        parameters.is_variadic()
+                    ));
+                    for parameter in parameters.parameters() {
+                        let name = parameter.declarator().and_then(|d| d.name()).unwrap();
+                        output.push(format!(
+                            "  parameter {name}: {}",
+                            text(parameter.specifiers())
+                        ));
+                    }
+                    for item in function.body().unwrap().items() {
+                        match item {
+                            BlockItem::Statement(Statement::For(for_statement)) => {
+                                output.push(format!(
+                                    "  for {} | {} | {} | {} -> {}",
+                                    text(for_statement.declaration()),
+                                    text(for_statement.initializer()),
+                                    text(for_statement.condition()),
+                                    text(for_statement.step()),
+                                    text(for_statement.body())
+                                ));
+                            }
+                            BlockItem::Statement(Statement::Return(statement)) => {
+                                let Some(Expression::Conditional(conditional)) =
+                                    statement.expression()
+                                else {
+                                    panic!("expected a conditional expression");
+                                };
+                                let Some(Expression::Member(member)) = conditional.condition()
+                                else {
+                                    panic!("expected a member expression");
+                                };
+                                let Some(Expression::CompoundLiteral(literal)) = member.base()
+                                else {
+                                    panic!("expected a compound literal");
+                                };
+                                output.push(format!(
+                                    "  ({}) {} {}",
+                                    text(literal.type_name()),
+                                    member.operator().unwrap(),
+                                    member.member().unwrap()
+                                ));
+                                for item in literal.initializer_list().unwrap().items() {
+                                    let designators = item
+                                        .designators()
+                                        .map(|d| d.syntax().text())
+                                        .collect::<String>();
+                                    output.push(format!(
+                                        "    {designators} = {}",
+                                        text(item.initializer())
+                                    ));
+                                }
+                                output.push(format!(
+                                    "  then {} else {}",
+                                    text(conditional.then_value()),
+                                    text(conditional.else_value())
+                                ));
+                            }
+                            item => output.push(format!("  {}", text(Some(item)))),
+                        }
+                    }
+                }
+            }
+        }
+        expect![[r#"
+            member first width -
+            member second width 4
+            typedef true: pair
+            enumerator ZERO = -
+            enumerator TWO = 2
+            static assert TWO == 2 "two"
+            function sum variadic true
+              parameter p: pair
+              parameter scale: int
+              for int i = 0; | - | i < 2 | i++ -> if (i) return scale(p->first) + 1; else ;
+              for - | - | - | - -> break;
+              (pair) . second
+                .first = 1
+                [0] = 2
+              then sizeof (int) else -1"#]]
+        .assert_eq(&output.join("\n"));
+    }
+
+    #[test]
+    fn test_binary_operands() {
+        let src = "int x = a = b + c * d - (e);";
+        let tree = parse(src).tree();
+        let outline = tree
+            .syntax()
+            .descendants()
+            .filter_map(BinaryExpr::cast)
+            .map(|binary| {
+                format!(
+                    "{} {} {}",
+                    text(binary.lhs()),
+                    binary.operator().unwrap(),
+                    text(binary.rhs())
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        expect![[r#"
+            a = b + c * d - (e)
+            b + c * d - (e)
+            b + c * d
+            c * d"#]]
+        .assert_eq(&outline);
+    }
+}
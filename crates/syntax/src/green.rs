@@ -0,0 +1,215 @@
+//! The immutable, position-independent half of the syntax tree.
+//!
+//! Green nodes only know their kind, their children and the length of the text
+//! they cover, so identical subtrees can be shared and an edit only rebuilds the
+//! nodes on the path from the edit up to the root.
+
+use std::{fmt, rc::Rc};
+
+use crate::SyntaxKind;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct GreenToken(Rc<GreenTokenData>);
+
+#[derive(PartialEq, Eq, Hash)]
+struct GreenTokenData {
+    kind: SyntaxKind,
+    text: Box<str>,
+}
+impl GreenToken {
+    pub fn new(kind: SyntaxKind, text: &str) -> Self {
+        debug_assert!(kind.is_token(), "{kind} is not a token kind");
+        Self(Rc::new(GreenTokenData {
+            kind,
+            text: text.into(),
+        }))
+    }
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.kind
+    }
+    pub fn text(&self) -> &str {
+        &self.0.text
+    }
+    pub fn len(&self) -> usize {
+        self.0.text.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.text.is_empty()
+    }
+}
+impl fmt::Debug for GreenToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:?}", self.kind(), self.text())
+    }
+}
+impl fmt::Display for GreenToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.text())
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct GreenNode(Rc<GreenNodeData>);
+
+#[derive(PartialEq, Eq, Hash)]
+struct GreenNodeData {
+    kind: SyntaxKind,
+    len: usize,
+    children: Box<[GreenElement]>,
+}
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+        debug_assert!(!kind.is_token(), "{kind} is not a node kind");
+        let len = children.iter().map(GreenElement::len).sum();
+        Self(Rc::new(GreenNodeData {
+            kind,
+            len,
+            children: children.into(),
+        }))
+    }
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.kind
+    }
+    /// The length in bytes of the text covered by this node.
+    pub fn len(&self) -> usize {
+        self.0.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.len == 0
+    }
+    pub fn children(&self) -> &[GreenElement] {
+        &self.0.children
+    }
+    /// A copy of this node with the child at `index` replaced.
+    pub fn replace_child(&self, index: usize, child: GreenElement) -> Self {
+        let mut children = self.children().to_vec();
+        children[index] = child;
+        Self::new(self.kind(), children)
+    }
+    /// A copy of this node with `children` in place of the children in `range`.
+    pub fn splice_children(
+        &self,
+        range: std::ops::Range<usize>,
+        children: impl IntoIterator<Item = GreenElement>,
+    ) -> Self {
+        let mut new_children = self.children().to_vec();
+        new_children.splice(range, children);
+        Self::new(self.kind(), new_children)
+    }
+    /// Whether this node and `other` are the same allocation, rather than merely equal.
+    pub(crate) fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+impl fmt::Debug for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.kind(), self.len())
+    }
+}
+impl fmt::Display for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.children()
+            .iter()
+            .try_for_each(|child| write!(f, "{child}"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+    Node(GreenNode),
+    Token(GreenToken),
+}
+impl GreenElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            Self::Node(node) => node.kind(),
+            Self::Token(token) => token.kind(),
+        }
+    }
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Node(node) => node.len(),
+            Self::Token(token) => token.len(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+impl From<GreenNode> for GreenElement {
+    fn from(node: GreenNode) -> Self {
+        Self::Node(node)
+    }
+}
+impl From<GreenToken> for GreenElement {
+    fn from(token: GreenToken) -> Self {
+        Self::Token(token)
+    }
+}
+impl fmt::Display for GreenElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Node(node) => write!(f, "{node}"),
+            Self::Token(token) => write!(f, "{token}"),
+        }
+    }
+}
+
+/// A position in a [`GreenNodeBuilder`] that a node can later be started at,
+/// for when the parser only learns what it has been building after the fact
+/// (the left operand of a binary expression, say).
+#[derive(Debug, Clone, Copy)]
+pub struct Checkpoint(usize);
+
+/// Builds a green tree bottom up from a preorder stream of nodes and tokens.
+#[derive(Debug, Default)]
+pub struct GreenNodeBuilder {
+    /// The kind of each open node and the index of its first child in `children`.
+    parents: Vec<(SyntaxKind, usize)>,
+    children: Vec<GreenElement>,
+}
+impl GreenNodeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn start_node(&mut self, kind: SyntaxKind) {
+        self.parents.push((kind, self.children.len()));
+    }
+    pub fn token(&mut self, kind: SyntaxKind, text: &str) {
+        self.children.push(GreenToken::new(kind, text).into());
+    }
+    pub fn finish_node(&mut self) {
+        let (kind, first_child) = self.parents.pop().expect("no node to finish");
+        let children = self.children.split_off(first_child);
+        self.children.push(GreenNode::new(kind, children).into());
+    }
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.children.len())
+    }
+    /// Start a node whose first child is whatever was added after `checkpoint`.
+    pub fn start_node_at(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        let Checkpoint(first_child) = checkpoint;
+        assert!(
+            first_child <= self.children.len(),
+            "checkpoint is past the children built so far"
+        );
+        if let Some(&(_, parent_first_child)) = self.parents.last() {
+            assert!(
+                first_child >= parent_first_child,
+                "checkpoint is before the start of the open node"
+            );
+        }
+        self.parents.push((kind, first_child));
+    }
+    /// The finished tree, once every node started has been finished.
+    pub fn finish(mut self) -> GreenNode {
+        assert!(
+            self.parents.is_empty(),
+            "unfinished nodes left in the builder"
+        );
+        match (self.children.pop(), self.children.is_empty()) {
+            (Some(GreenElement::Node(root)), true) => root,
+            _ => panic!("a syntax tree must have exactly one root node"),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt;
+
+use wacc_lexer::c_token::CTokenKind;
+
+/// The kind of a node or token in the concrete syntax tree.
+///
+/// Tokens keep the [`CTokenKind`] they were lexed with; the whitespace, comments
+/// and directives between them, which the lexer never sees, become trivia tokens
+/// so that the tree covers every byte of the source.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    Token(CTokenKind),
+    Whitespace,
+    Comment,
+    /// A preprocessing directive, from its `#` to the end of its last line.
+    Directive,
+    /// Source the lexer couldn't make a token of, always in an [`SyntaxKind::Error`] node.
+    Unknown,
+
+    SourceFile,
+    FunctionDefinition,
+    Declaration,
+    StaticAssert,
+    /// Declaration specifiers: type specifiers and qualifiers, storage classes and
+    /// typedef names, in the order they were written.
+    Specifiers,
+    RecordSpecifier,
+    MemberList,
+    MemberDeclaration,
+    MemberDeclarator,
+    EnumSpecifier,
+    EnumeratorList,
+    Enumerator,
+    InitDeclarator,
+    NameDeclarator,
+    PointerDeclarator,
+    ParenDeclarator,
+    ArrayDeclarator,
+    FunctionDeclarator,
+    ParameterList,
+    Parameter,
+    TypeName,
+    InitializerList,
+    InitializerItem,
+    IndexDesignator,
+    MemberDesignator,
+
+    CompoundStatement,
+    ExpressionStatement,
+    ReturnStatement,
+    IfStatement,
+    WhileStatement,
+    DoStatement,
+    ForStatement,
+    SwitchStatement,
+    CaseStatement,
+    DefaultStatement,
+    LabeledStatement,
+    GotoStatement,
+    BreakStatement,
+    ContinueStatement,
+
+    NameExpr,
+    LiteralExpr,
+    ParenExpr,
+    CastExpr,
+    PrefixExpr,
+    PostfixExpr,
+    BinaryExpr,
+    ConditionalExpr,
+    CallExpr,
+    ArgumentList,
+    IndexExpr,
+    MemberExpr,
+    SizeofExpr,
+    CompoundLiteralExpr,
+
+    /// Tokens the parser could not fit into the grammar, kept so nothing is lost.
+    Error,
+}
+impl SyntaxKind {
+    pub fn is_token(self) -> bool {
+        matches!(
+            self,
+            Self::Token(_) | Self::Whitespace | Self::Comment | Self::Directive | Self::Unknown
+        )
+    }
+    pub fn is_trivia(self) -> bool {
+        matches!(self, Self::Whitespace | Self::Comment | Self::Directive)
+    }
+}
+impl From<CTokenKind> for SyntaxKind {
+    fn from(kind: CTokenKind) -> Self {
+        Self::Token(kind)
+    }
+}
+impl fmt::Display for SyntaxKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Token(kind) => write!(f, "{kind}"),
+            Self::Whitespace => write!(f, "whitespace"),
+            Self::Comment => write!(f, "comment"),
+            Self::Directive => write!(f, "directive"),
+            Self::Unknown => write!(f, "unknown"),
+            node => write!(f, "{node:?}"),
+        }
+    }
+}
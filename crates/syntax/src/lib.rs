@@ -0,0 +1,382 @@
+//! A lossless concrete syntax tree for C, for formatters and refactoring tools.
+//!
+//! Where `wacc-parser` builds a typed AST and drops everything the compiler has no
+//! use for, this crate keeps every token, parenthesis and run of whitespace, so
+//! printing a tree reproduces its source byte for byte. Trees are split the usual
+//! red-green way:
+//!
+//! - [`green`] nodes are immutable and position independent, and can be shared
+//!   between trees, so an edit is a rewrite that only rebuilds the path to the root;
+//! - [`red`] nodes are created on demand over a green tree, and add parents and
+//!   offsets for navigation;
+//! - [`ast`] wraps red nodes in typed views with accessors for their parts.
+//!
+//! Source is taken as written, before preprocessing: comments and directives
+//! are kept as trivia, and whatever can't be lexed ends up in an error node.
+
+use std::ops::Range;
+
+use tokengen::span::Span;
+use wacc_lexer::Lexer;
+
+use crate::{green::GreenNode, parser::CstParser, red::SyntaxNode, trivia::Trivia};
+
+pub mod ast;
+pub mod green;
+mod kind;
+mod parser;
+pub mod red;
+mod trivia;
+
+pub use kind::SyntaxKind;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{message}")]
+pub struct SyntaxError {
+    range: Range<usize>,
+    message: String,
+}
+impl SyntaxError {
+    pub fn new(range: Range<usize>, message: String) -> Self {
+        Self { range, message }
+    }
+    /// The byte range of the source the error was found at.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// The result of parsing a source file: a tree covering all of it, and every
+/// syntax error found along the way.
+#[derive(Debug, Clone)]
+pub struct Parse {
+    green: GreenNode,
+    errors: Vec<SyntaxError>,
+}
+impl Parse {
+    pub fn green(&self) -> &GreenNode {
+        &self.green
+    }
+    pub fn syntax(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.green.clone())
+    }
+    pub fn tree(&self) -> ast::SourceFile {
+        ast::SourceFile::from_root(self.syntax())
+    }
+    pub fn errors(&self) -> &[SyntaxError] {
+        &self.errors
+    }
+}
+
+/// Lex and parse `src` into a concrete syntax tree.
+///
+/// The lexer keeps the span of every token, so the whitespace it drops is
+/// recovered from the gaps between them.
+pub fn parse(src: &str) -> Parse {
+    let Trivia {
+        blanked,
+        pieces: mut trivia,
+        mut errors,
+    } = trivia::split_trivia(src);
+    let (tokens, lex_errors) = String::lex_c_with_errors(&blanked);
+    for err in lex_errors {
+        let range = err.span().start()..err.span().end();
+        errors.push(SyntaxError::new(
+            range.clone(),
+            err.context().to_lowercase(),
+        ));
+        trivia.push((range, SyntaxKind::Unknown));
+    }
+    trivia.sort_by_key(|(range, _)| range.start);
+    let (green, parse_errors) = CstParser::new(src, tokens.as_slice(), trivia).parse();
+    errors.extend(parse_errors);
+    errors.sort_by_key(|err| err.range().start);
+    Parse { green, errors }
+}
+
+#[cfg(test)]
+mod syntax_tests {
+    use expect_test::{expect, Expect};
+
+    use super::parse;
+
+    fn check_tree(src: &str, expect: Expect) {
+        let parse = parse(src);
+        assert_eq!(parse.syntax().to_string(), src);
+        let mut output = parse.syntax().debug_tree();
+        for err in parse.errors() {
+            output.push_str(&format!("{} at {:?}\n", err.message(), err.range()));
+        }
+        expect.assert_eq(&output);
+    }
+
+    /// Printing the tree of every sample must give back its source exactly.
+    #[test]
+    fn test_round_trip() {
+        let samples = [
+            "int main(void) { return 2; }",
+            "\n\n  int   main ( void )\n{\n\treturn   (1 + 2) * -3 ;\n}\n\n",
+            r#"
+            typedef struct node { int value; struct node *next; } node;
+            typedef int (*callback)(node *, void *);
+            enum colour { RED, GREEN = 2, BLUE, };
+            union word { unsigned char bytes[4]; unsigned int value : 32; };
+            int walk(node *list, callback visit, void *context, ...) {
+                int count = 0, *p = &count;
+                for (node *it = list; it; it = it->next) {
+                    if (visit(it, context)) continue; else { count++; }
+                }
+                do --count; while (count > 10);
+                switch (count) { case RED: default: break; }
+                goto done;
+            done:
+                return sizeof (node) + sizeof *list + (int) (long) count ? 1 : 0;
+            }
+            int table[2][3] = { [1] = { 1, 2 }, { .x = "a" "b" } };
+            int *ones = (int[]){ 1, 1, };
+            _Static_assert(sizeof (int) == 4, "int" " is 32 bits");
+            void check(void) { _Static_assert(RED < BLUE, ""); }
+            "#,
+            "int f(void) { x = (1; } } int g",
+            "} garbage ;; int",
+            "#if X\n// a\nint /* b */ y = '/';\n  # endif\n$ int z;",
+        ];
+        for src in samples {
+            assert_eq!(parse(src).syntax().to_string(), src);
+        }
+    }
+
+    #[test]
+    fn test_tree() {
+        check_tree(
+            " int main(void) {\n  return (2);\n}\n",
+            expect![[r#"
+            SourceFile@0..34
+              whitespace@0..1 " "
+              FunctionDefinition@1..33
+                Specifiers@1..4
+                  `int`@1..4 "int"
+                whitespace@4..5 " "
+                FunctionDeclarator@5..15
+                  NameDeclarator@5..9
+                    identifier@5..9 "main"
+                  ParameterList@9..15
+                    `(`@9..10 "("
+                    Parameter@10..14
+                      Specifiers@10..14
+                        `void`@10..14 "void"
+                    `)`@14..15 ")"
+                whitespace@15..16 " "
+                CompoundStatement@16..33
+                  `{`@16..17 "{"
+                  whitespace@17..20 "\n  "
+                  ReturnStatement@20..31
+                    `return`@20..26 "return"
+                    whitespace@26..27 " "
+                    ParenExpr@27..30
+                      `(`@27..28 "("
+                      LiteralExpr@28..29
+                        constant@28..29 "2"
+                      `)`@29..30 ")"
+                    `;`@30..31 ";"
+                  whitespace@31..32 "\n"
+                  `}`@32..33 "}"
+              whitespace@33..34 "\n"
+        "#]],
+        );
+    }
+
+    #[test]
+    fn test_declarators_and_casts() {
+        check_tree(
+            "typedef int T; int (*f[2])(T), x = (T) - 1, y = (x) - 1;",
+            expect![[r#"
+                SourceFile@0..56
+                  Declaration@0..14
+                    Specifiers@0..11
+                      `typedef`@0..7 "typedef"
+                      whitespace@7..8 " "
+                      `int`@8..11 "int"
+                    whitespace@11..12 " "
+                    InitDeclarator@12..13
+                      NameDeclarator@12..13
+                        identifier@12..13 "T"
+                    `;`@13..14 ";"
+                  whitespace@14..15 " "
+                  Declaration@15..56
+                    Specifiers@15..18
+                      `int`@15..18 "int"
+                    whitespace@18..19 " "
+                    InitDeclarator@19..29
+                      FunctionDeclarator@19..29
+                        ParenDeclarator@19..26
+                          `(`@19..20 "("
+                          PointerDeclarator@20..25
+                            `*`@20..21 "*"
+                            ArrayDeclarator@21..25
+                              NameDeclarator@21..22
+                                identifier@21..22 "f"
+                              `[`@22..23 "["
+                              LiteralExpr@23..24
+                                constant@23..24 "2"
+                              `]`@24..25 "]"
+                          `)`@25..26 ")"
+                        ParameterList@26..29
+                          `(`@26..27 "("
+                          Parameter@27..28
+                            Specifiers@27..28
+                              identifier@27..28 "T"
+                          `)`@28..29 ")"
+                    `,`@29..30 ","
+                    whitespace@30..31 " "
+                    InitDeclarator@31..42
+                      NameDeclarator@31..32
+                        identifier@31..32 "x"
+                      whitespace@32..33 " "
+                      `=`@33..34 "="
+                      whitespace@34..35 " "
+                      CastExpr@35..42
+                        `(`@35..36 "("
+                        TypeName@36..37
+                          Specifiers@36..37
+                            identifier@36..37 "T"
+                        `)`@37..38 ")"
+                        whitespace@38..39 " "
+                        PrefixExpr@39..42
+                          `-`@39..40 "-"
+                          whitespace@40..41 " "
+                          LiteralExpr@41..42
+                            constant@41..42 "1"
+                    `,`@42..43 ","
+                    whitespace@43..44 " "
+                    InitDeclarator@44..55
+                      NameDeclarator@44..45
+                        identifier@44..45 "y"
+                      whitespace@45..46 " "
+                      `=`@46..47 "="
+                      whitespace@47..48 " "
+                      BinaryExpr@48..55
+                        ParenExpr@48..51
+                          `(`@48..49 "("
+                          NameExpr@49..50
+                            identifier@49..50 "x"
+                          `)`@50..51 ")"
+                        whitespace@51..52 " "
+                        `-`@52..53 "-"
+                        whitespace@53..54 " "
+                        LiteralExpr@54..55
+                          constant@54..55 "1"
+                    `;`@55..56 ";"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_error_nodes() {
+        check_tree(
+            "int f(void) { x = (1; ) } ) int",
+            expect![[r#"
+            SourceFile@0..31
+              FunctionDefinition@0..25
+                Specifiers@0..3
+                  `int`@0..3 "int"
+                whitespace@3..4 " "
+                FunctionDeclarator@4..11
+                  NameDeclarator@4..5
+                    identifier@4..5 "f"
+                  ParameterList@5..11
+                    `(`@5..6 "("
+                    Parameter@6..10
+                      Specifiers@6..10
+                        `void`@6..10 "void"
+                    `)`@10..11 ")"
+                whitespace@11..12 " "
+                CompoundStatement@12..25
+                  `{`@12..13 "{"
+                  whitespace@13..14 " "
+                  ExpressionStatement@14..21
+                    BinaryExpr@14..20
+                      NameExpr@14..15
+                        identifier@14..15 "x"
+                      whitespace@15..16 " "
+                      `=`@16..17 "="
+                      whitespace@17..18 " "
+                      ParenExpr@18..20
+                        `(`@18..19 "("
+                        LiteralExpr@19..20
+                          constant@19..20 "1"
+                    `;`@20..21 ";"
+                  whitespace@21..22 " "
+                  Error@22..23
+                    `)`@22..23 ")"
+                  whitespace@23..24 " "
+                  `}`@24..25 "}"
+              whitespace@25..26 " "
+              Error@26..31
+                `)`@26..27 ")"
+                whitespace@27..28 " "
+                `int`@28..31 "int"
+            expected `)`, found `;` at 20..21
+            expected a statement, found `)` at 22..23
+            expected a declaration, found `)` at 26..27
+        "#]],
+        );
+    }
+
+    #[test]
+    fn test_trivia() {
+        check_tree(
+            "#include <stdio.h>\n#define TWO \\\n  2\nint x = 1 /* one */; // \"x\"\nchar *s = \"/* no */\" @;\n/* open",
+            expect![[r##"
+                SourceFile@0..96
+                  directive@0..18 "#include <stdio.h>"
+                  whitespace@18..19 "\n"
+                  directive@19..36 "#define TWO \\\n  2"
+                  whitespace@36..37 "\n"
+                  Declaration@37..57
+                    Specifiers@37..40
+                      `int`@37..40 "int"
+                    whitespace@40..41 " "
+                    InitDeclarator@41..46
+                      NameDeclarator@41..42
+                        identifier@41..42 "x"
+                      whitespace@42..43 " "
+                      `=`@43..44 "="
+                      whitespace@44..45 " "
+                      LiteralExpr@45..46
+                        constant@45..46 "1"
+                    whitespace@46..47 " "
+                    comment@47..56 "/* one */"
+                    `;`@56..57 ";"
+                  whitespace@57..58 " "
+                  comment@58..64 "// \"x\""
+                  whitespace@64..65 "\n"
+                  Declaration@65..88
+                    Specifiers@65..69
+                      `char`@65..69 "char"
+                    whitespace@69..70 " "
+                    InitDeclarator@70..85
+                      PointerDeclarator@70..72
+                        `*`@70..71 "*"
+                        NameDeclarator@71..72
+                          identifier@71..72 "s"
+                      whitespace@72..73 " "
+                      `=`@73..74 "="
+                      whitespace@74..75 " "
+                      LiteralExpr@75..85
+                        string literal@75..85 "\"/* no */\""
+                    whitespace@85..86 " "
+                    Error@86..87
+                      unknown@86..87 "@"
+                    `;`@87..88 ";"
+                  whitespace@88..89 "\n"
+                  comment@89..96 "/* open"
+                unrecognized token at 86..87
+                unterminated comment at 89..96
+            "##]],
+        );
+    }
+}
@@ -0,0 +1,1105 @@
+//! A recursive descent parser that builds the concrete syntax tree.
+//!
+//! It follows the same grammar as `wacc-parser` but only decides structure: no
+//! types are derived and nothing is checked beyond what is needed to tell a
+//! declaration from an expression. Every token ends up in the tree, in order, and
+//! anything that doesn't fit the grammar is wrapped in an [`SyntaxKind::Error`]
+//! node, so the tree always reproduces its input.
+
+use std::{collections::HashMap, ops::Range};
+
+use tokengen::span::Span;
+use wacc_lexer::c_token::{
+    c_keyword::KeywordKind,
+    c_symbol::{CookedSymbolKind, SymbolKind},
+    CToken, CTokenKind,
+};
+
+use crate::{
+    green::{Checkpoint, GreenNode, GreenNodeBuilder},
+    SyntaxError, SyntaxKind,
+};
+
+const OPEN_PAREN: CTokenKind = CTokenKind::Punctuator(SymbolKind::OpenParenthesis);
+const CLOSE_PAREN: CTokenKind = CTokenKind::Punctuator(SymbolKind::CloseParenthesis);
+const OPEN_BRACE: CTokenKind = CTokenKind::Punctuator(SymbolKind::OpenCurlyBrace);
+const CLOSE_BRACE: CTokenKind = CTokenKind::Punctuator(SymbolKind::CloseCurlyBrace);
+const OPEN_BRACKET: CTokenKind = CTokenKind::Punctuator(SymbolKind::OpenSquareBracket);
+const CLOSE_BRACKET: CTokenKind = CTokenKind::Punctuator(SymbolKind::CloseSquareBracket);
+const SEMICOLON: CTokenKind = CTokenKind::Punctuator(SymbolKind::Semicolon);
+const COMMA: CTokenKind = CTokenKind::Punctuator(SymbolKind::Comma);
+const COLON: CTokenKind = CTokenKind::Punctuator(SymbolKind::Colon);
+const EQUALS: CTokenKind = CTokenKind::Punctuator(SymbolKind::Equals);
+const ASTERISK: CTokenKind = CTokenKind::Punctuator(SymbolKind::Asterisk);
+const DOT: CTokenKind = CTokenKind::Punctuator(SymbolKind::Dot);
+const QUESTION_MARK: CTokenKind = CTokenKind::Punctuator(SymbolKind::QuestionMark);
+const ARROW: CTokenKind = CTokenKind::Operator(CookedSymbolKind::Arrow);
+const ELLIPSIS: CTokenKind = CTokenKind::Operator(CookedSymbolKind::Ellipsis);
+const INCREMENT: CTokenKind = CTokenKind::Operator(CookedSymbolKind::Increment);
+const DECREMENT: CTokenKind = CTokenKind::Operator(CookedSymbolKind::Decrement);
+
+const fn keyword(keyword: KeywordKind) -> CTokenKind {
+    CTokenKind::Keyword(keyword)
+}
+
+/// The precedence of the comma operator, the loosest binding operator in C.
+const COMMA_PRECEDENCE: u8 = 1;
+/// The precedence of assignment, for the operands of calls and initializers.
+const ASSIGNMENT_PRECEDENCE: u8 = 2;
+/// The precedence of `?:`, for constant expressions.
+const CONDITIONAL_PRECEDENCE: u8 = 3;
+
+/// The precedence of an infix operator (higher binds tighter) and whether it is
+/// right associative. `?:` is listed by its `?`.
+fn infix_operator(kind: CTokenKind) -> Option<(u8, bool)> {
+    use CTokenKind::{Operator as Op, Punctuator as P};
+    use CookedSymbolKind as C;
+    use SymbolKind as S;
+    Some(match kind {
+        P(S::Comma) => (1, false),
+        P(S::Equals)
+        | Op(C::AddAssign | C::SubtractAssign | C::MultiplyAssign | C::DivideAssign)
+        | Op(C::RemainderAssign | C::ShiftLeftAssign | C::ShiftRightAssign)
+        | Op(C::AndAssign | C::XorAssign | C::OrAssign) => (2, true),
+        P(S::QuestionMark) => (3, true),
+        Op(C::LogicalOr) => (4, false),
+        Op(C::LogicalAnd) => (5, false),
+        P(S::VerticalBar) => (6, false),
+        P(S::Caret) => (7, false),
+        P(S::Ampersand) => (8, false),
+        Op(C::DoubleEquals | C::NotEquals) => (9, false),
+        P(S::LessThan | S::GreaterThan) | Op(C::LessThanOrEqual | C::GreaterThanOrEqual) => {
+            (10, false)
+        }
+        Op(C::ShiftLeft | C::ShiftRight) => (11, false),
+        P(S::Plus | S::Minus) => (12, false),
+        P(S::Asterisk | S::ForwardSlash | S::Percent) => (13, false),
+        _ => return None,
+    })
+}
+
+fn is_prefix_operator(kind: CTokenKind) -> bool {
+    matches!(
+        kind,
+        CTokenKind::Punctuator(
+            SymbolKind::Minus
+                | SymbolKind::Plus
+                | SymbolKind::Tilde
+                | SymbolKind::ExclamationMark
+                | SymbolKind::Ampersand
+                | SymbolKind::Asterisk
+        ) | CTokenKind::Operator(CookedSymbolKind::Increment | CookedSymbolKind::Decrement)
+    )
+}
+
+/// Whether `kind` is a keyword that can only start declaration specifiers.
+fn is_specifier_keyword(kind: CTokenKind) -> bool {
+    use KeywordKind::*;
+    matches!(
+        kind,
+        CTokenKind::Keyword(
+            Int | Void
+                | Char
+                | Short
+                | Long
+                | Signed
+                | Unsigned
+                | Float
+                | Double
+                | Const
+                | Volatile
+                | Restrict
+                | Typedef
+                | Static
+                | Extern
+                | Inline
+                | Struct
+                | Union
+                | Enum
+        )
+    )
+}
+
+/// Whether a declarator must, may or must not have a name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeclaratorMode {
+    Named,
+    Abstract,
+    /// A parameter declarator, which may be named or abstract.
+    Either,
+}
+
+/// What the rest of the parser needs to know about a declarator.
+#[derive(Debug, Default)]
+struct DeclaratorInfo {
+    name: Option<String>,
+    /// The parameter names of the function declarator applied to the name first, if any.
+    parameters: Option<Vec<String>>,
+}
+
+pub(crate) struct CstParser<'a> {
+    src: &'a str,
+    tokens: Vec<CToken>,
+    pos: usize,
+    /// The end of the last token added to the tree, where any trivia before the next token starts.
+    cursor: usize,
+    /// The comments, directives and unlexable source between tokens, in order.
+    trivia: Vec<(Range<usize>, SyntaxKind)>,
+    next_trivia: usize,
+    builder: GreenNodeBuilder,
+    errors: Vec<SyntaxError>,
+    /// The token position of the last reported error, used to suppress cascades.
+    last_error: Option<usize>,
+    /// Every name in scope, innermost scope last, and whether it names a typedef.
+    scopes: Vec<HashMap<String, bool>>,
+}
+impl<'a> CstParser<'a> {
+    pub(crate) fn new(
+        src: &'a str,
+        tokens: &[CToken],
+        trivia: Vec<(Range<usize>, SyntaxKind)>,
+    ) -> Self {
+        Self {
+            src,
+            tokens: tokens
+                .iter()
+                .copied()
+                .filter(|token| !token.is_whitespace())
+                .collect(),
+            pos: 0,
+            cursor: 0,
+            trivia,
+            next_trivia: 0,
+            builder: GreenNodeBuilder::new(),
+            errors: Vec::new(),
+            last_error: None,
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    /// `<source-file> ::= { <external-declaration> }`
+    pub(crate) fn parse(mut self) -> (GreenNode, Vec<SyntaxError>) {
+        // The root starts before any leading trivia, so that it covers the whole source.
+        self.builder.start_node(SyntaxKind::SourceFile);
+        while self.peek().is_some() {
+            let start = self.pos;
+            self.external_declaration();
+            if self.pos == start {
+                self.error("expected a declaration");
+                self.recover_declaration();
+            }
+        }
+        self.trivia();
+        self.builder.finish_node();
+        let green = self.builder.finish();
+        debug_assert_eq!(
+            green.len(),
+            self.src.len(),
+            "the tree must cover the whole source"
+        );
+        (green, self.errors)
+    }
+
+    fn peek(&self) -> Option<CTokenKind> {
+        self.peek_nth(0)
+    }
+    fn peek_nth(&self, n: usize) -> Option<CTokenKind> {
+        self.tokens.get(self.pos + n).and_then(CToken::kind)
+    }
+    fn peek_text(&self, n: usize) -> &'a str {
+        match self.tokens.get(self.pos + n).and_then(CToken::source_span) {
+            Some(span) => &self.src[span.start()..span.end()],
+            None => "",
+        }
+    }
+    fn at(&self, kind: CTokenKind) -> bool {
+        self.peek() == Some(kind)
+    }
+    /// The byte range of the next token, or an empty range at the end of the source.
+    fn next_range(&self) -> Range<usize> {
+        match self.tokens.get(self.pos).and_then(CToken::source_span) {
+            Some(span) => span.start()..span.end(),
+            None => self.src.len()..self.src.len(),
+        }
+    }
+
+    /// Add the trivia between the last token and the next one to the open node.
+    fn trivia(&mut self) {
+        let end = self.next_range().start;
+        while self.cursor < end {
+            let (start, next) = match self.trivia.get(self.next_trivia) {
+                Some((range, kind)) if range.start < end => {
+                    (range.start, Some((range.clone(), *kind)))
+                }
+                _ => (end, None),
+            };
+            if start > self.cursor {
+                self.builder
+                    .token(SyntaxKind::Whitespace, &self.src[self.cursor..start]);
+                self.cursor = start;
+            }
+            let Some((range, kind)) = next else {
+                continue;
+            };
+            if kind == SyntaxKind::Unknown {
+                self.builder.start_node(SyntaxKind::Error);
+                self.builder.token(kind, &self.src[range.clone()]);
+                self.builder.finish_node();
+            } else {
+                self.builder.token(kind, &self.src[range.clone()]);
+            }
+            self.cursor = range.end;
+            self.next_trivia += 1;
+        }
+    }
+    fn bump(&mut self) {
+        let (Some(kind), range) = (self.peek(), self.next_range()) else {
+            return;
+        };
+        self.trivia();
+        self.builder.token(kind.into(), &self.src[range.clone()]);
+        self.cursor = range.end;
+        self.pos += 1;
+    }
+    fn eat(&mut self, kind: CTokenKind) -> bool {
+        let found = self.at(kind);
+        if found {
+            self.bump();
+        }
+        found
+    }
+    fn expect(&mut self, kind: CTokenKind) {
+        if !self.eat(kind) {
+            self.error(&format!("expected {kind}"));
+        }
+    }
+    /// Start a node at the next token, leaving the trivia before it to the enclosing node.
+    fn start_node(&mut self, kind: SyntaxKind) {
+        self.trivia();
+        self.builder.start_node(kind);
+    }
+    fn finish_node(&mut self) {
+        self.builder.finish_node();
+    }
+    fn checkpoint(&mut self) -> Checkpoint {
+        self.trivia();
+        self.builder.checkpoint()
+    }
+    /// Record an error at the next token, noting what was found instead.
+    fn error(&mut self, context: &str) {
+        if self.last_error == Some(self.pos) {
+            return;
+        }
+        self.last_error = Some(self.pos);
+        let message = match self.peek() {
+            Some(found) => format!("{context}, found {found}"),
+            None => format!("{context}, found end of input"),
+        };
+        self.errors
+            .push(SyntaxError::new(self.next_range(), message));
+    }
+    /// Wrap the next token in an error node.
+    fn bump_error(&mut self) {
+        self.start_node(SyntaxKind::Error);
+        self.bump();
+        self.finish_node();
+    }
+    /// Skip to the end of the current declaration, wrapping what was skipped in an
+    /// error node: past the next `;` or the `}` closing a block, whichever comes first.
+    fn recover_declaration(&mut self) {
+        self.start_node(SyntaxKind::Error);
+        let mut depth = 0_usize;
+        while let Some(kind) = self.peek() {
+            self.bump();
+            match kind {
+                OPEN_BRACE => depth += 1,
+                CLOSE_BRACE if depth <= 1 => break,
+                CLOSE_BRACE => depth -= 1,
+                SEMICOLON if depth == 0 => break,
+                _ => {}
+            }
+        }
+        self.finish_node();
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+    fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+    fn declare(&mut self, name: String, is_typedef: bool) {
+        let scope = self
+            .scopes
+            .last_mut()
+            .expect("the file scope is never popped");
+        scope.insert(name, is_typedef);
+    }
+    fn is_typedef_name(&self, name: &str) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .is_some_and(|is_typedef| *is_typedef)
+    }
+    fn is_typedef_name_at(&self, n: usize) -> bool {
+        self.peek_nth(n) == Some(CTokenKind::Identifier) && self.is_typedef_name(self.peek_text(n))
+    }
+    fn is_specifier_at(&self, n: usize) -> bool {
+        self.peek_nth(n).is_some_and(is_specifier_keyword) || self.is_typedef_name_at(n)
+    }
+    /// Whether a block item starts here with a declaration rather than a statement.
+    fn is_declaration_start(&self) -> bool {
+        self.is_specifier_at(0) && !self.is_label_start()
+    }
+    fn is_label_start(&self) -> bool {
+        self.at(CTokenKind::Identifier) && self.peek_nth(1) == Some(COLON)
+    }
+
+    /// `<external-declaration> ::= <function-definition> | <declaration>`
+    fn external_declaration(&mut self) {
+        if self.at(keyword(KeywordKind::StaticAssert)) {
+            self.static_assert();
+            return;
+        }
+        if !self.is_specifier_at(0) {
+            return;
+        }
+        let start = self.checkpoint();
+        let is_typedef = self.specifiers();
+        if self.at(SEMICOLON) {
+            self.builder.start_node_at(start, SyntaxKind::Declaration);
+            self.bump();
+            self.finish_node();
+            return;
+        }
+        let declarator_start = self.checkpoint();
+        let declarator = self.declarator(DeclaratorMode::Named);
+        if let (Some(parameters), true) = (&declarator.parameters, self.at(OPEN_BRACE)) {
+            let parameters = parameters.clone();
+            self.builder
+                .start_node_at(start, SyntaxKind::FunctionDefinition);
+            if let Some(name) = declarator.name {
+                self.declare(name, false);
+            }
+            self.compound_statement(parameters);
+            self.finish_node();
+            return;
+        }
+        self.builder.start_node_at(start, SyntaxKind::Declaration);
+        self.builder
+            .start_node_at(declarator_start, SyntaxKind::InitDeclarator);
+        if let Some(name) = declarator.name {
+            self.declare(name, is_typedef);
+        }
+        self.initializer_rest();
+        self.finish_node();
+        self.declaration_rest(is_typedef);
+        self.finish_node();
+    }
+
+    /// `<declaration> ::= <specifiers> [ <init-declarator> { "," <init-declarator> } ] ";"`
+    fn declaration(&mut self) {
+        self.start_node(SyntaxKind::Declaration);
+        let is_typedef = self.specifiers();
+        if !self.at(SEMICOLON) {
+            self.init_declarator(is_typedef);
+            self.declaration_rest(is_typedef);
+        } else {
+            self.bump();
+        }
+        self.finish_node();
+    }
+
+    /// `<static-assert> ::= "_Static_assert" "(" <constant-exp> "," <string-literal> ")" ";"`
+    fn static_assert(&mut self) {
+        self.start_node(SyntaxKind::StaticAssert);
+        self.bump();
+        self.expect(OPEN_PAREN);
+        self.expression_bp(CONDITIONAL_PRECEDENCE);
+        self.expect(COMMA);
+        if self.at(CTokenKind::StringLiteral) {
+            self.expression_bp(CONDITIONAL_PRECEDENCE);
+        } else {
+            self.error("expected a string literal");
+        }
+        self.expect(CLOSE_PAREN);
+        self.expect(SEMICOLON);
+        self.finish_node();
+    }
+
+    /// The declarators after the first in a declaration, and its closing `;`.
+    fn declaration_rest(&mut self, is_typedef: bool) {
+        while self.eat(COMMA) {
+            self.init_declarator(is_typedef);
+        }
+        self.expect(SEMICOLON);
+    }
+
+    /// `<init-declarator> ::= <declarator> [ "=" <initializer> ]`
+    fn init_declarator(&mut self, is_typedef: bool) {
+        self.start_node(SyntaxKind::InitDeclarator);
+        let declarator = self.declarator(DeclaratorMode::Named);
+        // The name is in scope in its own initializer.
+        if let Some(name) = declarator.name {
+            self.declare(name, is_typedef);
+        }
+        self.initializer_rest();
+        self.finish_node();
+    }
+
+    fn initializer_rest(&mut self) {
+        if self.eat(EQUALS) {
+            self.initializer();
+        }
+    }
+
+    /// `<specifiers> ::= { <type-specifier> | <type-qualifier> | "typedef" }`
+    ///
+    /// Returns whether the specifiers declare typedef names.
+    fn specifiers(&mut self) -> bool {
+        use KeywordKind::*;
+        self.start_node(SyntaxKind::Specifiers);
+        let (mut is_typedef, mut has_type) = (false, false);
+        loop {
+            match self.peek() {
+                Some(CTokenKind::Keyword(Typedef)) => {
+                    is_typedef = true;
+                    self.bump();
+                }
+                Some(CTokenKind::Keyword(
+                    Static | Extern | Inline | Const | Volatile | Restrict,
+                )) => self.bump(),
+                Some(CTokenKind::Keyword(
+                    Int | Void | Char | Short | Long | Signed | Unsigned | Float | Double,
+                )) => {
+                    has_type = true;
+                    self.bump();
+                }
+                Some(CTokenKind::Keyword(Struct | Union)) => {
+                    has_type = true;
+                    self.record_specifier();
+                }
+                Some(CTokenKind::Keyword(Enum)) => {
+                    has_type = true;
+                    self.enum_specifier();
+                }
+                // Once there is a type, an identifier is being declared even if it names a typedef.
+                Some(CTokenKind::Identifier) if !has_type && self.is_typedef_name_at(0) => {
+                    has_type = true;
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+        self.finish_node();
+        is_typedef
+    }
+
+    /// `<record-specifier> ::= ( "struct" | "union" ) [ <identifier> ] [ <member-list> ]`
+    fn record_specifier(&mut self) {
+        self.start_node(SyntaxKind::RecordSpecifier);
+        self.bump();
+        let has_tag = self.eat(CTokenKind::Identifier);
+        if self.at(OPEN_BRACE) {
+            self.member_list();
+        } else if !has_tag {
+            self.error("expected a tag or `{`");
+        }
+        self.finish_node();
+    }
+
+    /// `<member-list> ::= "{" { <member-declaration> } "}"`
+    fn member_list(&mut self) {
+        self.start_node(SyntaxKind::MemberList);
+        self.bump();
+        while !self.at(CLOSE_BRACE) && self.peek().is_some() {
+            if self.is_specifier_at(0) {
+                self.member_declaration();
+            } else {
+                self.error("expected a member declaration");
+                self.bump_error();
+            }
+        }
+        self.expect(CLOSE_BRACE);
+        self.finish_node();
+    }
+
+    /// `<member-declaration> ::= <specifiers> [ <member-declarator> { "," <member-declarator> } ] ";"`
+    fn member_declaration(&mut self) {
+        self.start_node(SyntaxKind::MemberDeclaration);
+        self.specifiers();
+        if !self.at(SEMICOLON) {
+            self.member_declarator();
+            while self.eat(COMMA) {
+                self.member_declarator();
+            }
+        }
+        self.expect(SEMICOLON);
+        self.finish_node();
+    }
+
+    /// `<member-declarator> ::= <declarator> | [ <declarator> ] ":" <constant-expression>`
+    fn member_declarator(&mut self) {
+        self.start_node(SyntaxKind::MemberDeclarator);
+        if !self.at(COLON) {
+            self.declarator(DeclaratorMode::Named);
+        }
+        if self.eat(COLON) {
+            self.expression_bp(CONDITIONAL_PRECEDENCE);
+        }
+        self.finish_node();
+    }
+
+    /// `<enum-specifier> ::= "enum" [ <identifier> ] [ "{" <enumerator> { "," <enumerator> } [ "," ] "}" ]`
+    fn enum_specifier(&mut self) {
+        self.start_node(SyntaxKind::EnumSpecifier);
+        self.bump();
+        let has_tag = self.eat(CTokenKind::Identifier);
+        if self.at(OPEN_BRACE) {
+            self.start_node(SyntaxKind::EnumeratorList);
+            self.bump();
+            while self.at(CTokenKind::Identifier) {
+                self.start_node(SyntaxKind::Enumerator);
+                let name = self.peek_text(0).to_string();
+                self.bump();
+                if self.eat(EQUALS) {
+                    self.expression_bp(CONDITIONAL_PRECEDENCE);
+                }
+                self.finish_node();
+                self.declare(name, false);
+                if !self.eat(COMMA) {
+                    break;
+                }
+            }
+            self.expect(CLOSE_BRACE);
+            self.finish_node();
+        } else if !has_tag {
+            self.error("expected a tag or `{`");
+        }
+        self.finish_node();
+    }
+
+    /// `<type-name> ::= <specifiers> <abstract-declarator>`
+    fn type_name(&mut self) {
+        self.start_node(SyntaxKind::TypeName);
+        self.specifiers();
+        self.declarator(DeclaratorMode::Abstract);
+        self.finish_node();
+    }
+
+    /// `<declarator> ::= "*" { <type-qualifier> } <declarator> | <direct-declarator>`
+    fn declarator(&mut self, mode: DeclaratorMode) -> DeclaratorInfo {
+        if !self.at(ASTERISK) {
+            return self.direct_declarator(mode);
+        }
+        self.start_node(SyntaxKind::PointerDeclarator);
+        self.bump();
+        while matches!(
+            self.peek(),
+            Some(CTokenKind::Keyword(
+                KeywordKind::Const | KeywordKind::Volatile | KeywordKind::Restrict
+            ))
+        ) {
+            self.bump();
+        }
+        let info = self.declarator(mode);
+        self.finish_node();
+        info
+    }
+
+    /// Whether a `(` starting a direct declarator groups a nested declarator,
+    /// rather than starting the parameter list of an abstract function declarator.
+    fn is_paren_declarator(&self, mode: DeclaratorMode) -> bool {
+        match self.peek_nth(1) {
+            Some(ASTERISK | OPEN_PAREN | OPEN_BRACKET) => true,
+            Some(CTokenKind::Identifier) => {
+                mode != DeclaratorMode::Abstract && !self.is_typedef_name_at(1)
+            }
+            _ => false,
+        }
+    }
+
+    /// ```text
+    /// <direct-declarator> ::= ( <identifier> | "(" <declarator> ")" ) { <declarator-suffix> }
+    /// <declarator-suffix> ::= "[" [ <assignment-expression> ] "]" | <parameter-list>
+    /// ```
+    ///
+    /// In an abstract declarator the name, and so the part before the suffixes, is left out.
+    fn direct_declarator(&mut self, mode: DeclaratorMode) -> DeclaratorInfo {
+        let start = self.checkpoint();
+        let mut info = DeclaratorInfo::default();
+        if self.at(CTokenKind::Identifier) && mode != DeclaratorMode::Abstract {
+            self.start_node(SyntaxKind::NameDeclarator);
+            info.name = Some(self.peek_text(0).to_string());
+            self.bump();
+            self.finish_node();
+        } else if self.at(OPEN_PAREN) && self.is_paren_declarator(mode) {
+            self.start_node(SyntaxKind::ParenDeclarator);
+            self.bump();
+            info = self.declarator(mode);
+            self.expect(CLOSE_PAREN);
+            self.finish_node();
+        } else if mode == DeclaratorMode::Named {
+            self.error("expected a declarator");
+            return info;
+        }
+        loop {
+            if self.at(OPEN_BRACKET) {
+                self.builder
+                    .start_node_at(start, SyntaxKind::ArrayDeclarator);
+                self.bump();
+                if !self.at(CLOSE_BRACKET) {
+                    self.expression_bp(ASSIGNMENT_PRECEDENCE);
+                }
+                self.expect(CLOSE_BRACKET);
+                self.finish_node();
+            } else if self.at(OPEN_PAREN) {
+                self.builder
+                    .start_node_at(start, SyntaxKind::FunctionDeclarator);
+                let parameters = self.parameter_list();
+                info.parameters.get_or_insert(parameters);
+                self.finish_node();
+            } else {
+                break info;
+            }
+        }
+    }
+
+    /// ```text
+    /// <parameter-list> ::= "(" [ <parameter> { "," <parameter> } [ "," "..." ] ] ")"
+    /// <parameter> ::= <specifiers> <declarator>
+    /// ```
+    ///
+    /// Returns the names of the parameters, to be declared in a function body.
+    fn parameter_list(&mut self) -> Vec<String> {
+        self.start_node(SyntaxKind::ParameterList);
+        self.bump();
+        // Parameter names are only in scope in the rest of the parameter list.
+        self.push_scope();
+        let mut names = Vec::new();
+        while !self.at(CLOSE_PAREN) {
+            if self.eat(ELLIPSIS) {
+                break;
+            }
+            if !self.is_specifier_at(0) {
+                self.error("expected a parameter declaration");
+                if matches!(
+                    self.peek(),
+                    None | Some(SEMICOLON | OPEN_BRACE | CLOSE_BRACE)
+                ) {
+                    break;
+                }
+                self.bump_error();
+            } else {
+                self.start_node(SyntaxKind::Parameter);
+                self.specifiers();
+                if let Some(name) = self.declarator(DeclaratorMode::Either).name {
+                    self.declare(name.clone(), false);
+                    names.push(name);
+                }
+                self.finish_node();
+            }
+            if !self.eat(COMMA) {
+                break;
+            }
+        }
+        self.pop_scope();
+        self.expect(CLOSE_PAREN);
+        self.finish_node();
+        names
+    }
+
+    /// `<initializer> ::= <assignment-expression> | <initializer-list>`
+    fn initializer(&mut self) {
+        if self.at(OPEN_BRACE) {
+            self.initializer_list();
+        } else {
+            self.expression_bp(ASSIGNMENT_PRECEDENCE);
+        }
+    }
+
+    /// ```text
+    /// <initializer-list> ::= "{" [ <initializer-item> { "," <initializer-item> } [ "," ] ] "}"
+    /// <initializer-item> ::= [ <designator> { <designator> } "=" ] <initializer>
+    /// <designator> ::= "[" <constant-expression> "]" | "." <identifier>
+    /// ```
+    fn initializer_list(&mut self) {
+        self.start_node(SyntaxKind::InitializerList);
+        self.bump();
+        while !self.at(CLOSE_BRACE) && self.peek().is_some() {
+            self.start_node(SyntaxKind::InitializerItem);
+            let mut designated = false;
+            loop {
+                if self.at(OPEN_BRACKET) {
+                    self.start_node(SyntaxKind::IndexDesignator);
+                    self.bump();
+                    self.expression_bp(CONDITIONAL_PRECEDENCE);
+                    self.expect(CLOSE_BRACKET);
+                } else if self.at(DOT) {
+                    self.start_node(SyntaxKind::MemberDesignator);
+                    self.bump();
+                    self.expect(CTokenKind::Identifier);
+                } else {
+                    break;
+                }
+                self.finish_node();
+                designated = true;
+            }
+            if designated {
+                self.expect(EQUALS);
+            }
+            self.initializer();
+            self.finish_node();
+            if !self.eat(COMMA) {
+                break;
+            }
+        }
+        self.expect(CLOSE_BRACE);
+        self.finish_node();
+    }
+
+    /// `<compound-statement> ::= "{" { <declaration> | <statement> } "}"`
+    ///
+    /// A function's parameters are declared in the scope of its body.
+    fn compound_statement(&mut self, parameters: Vec<String>) {
+        self.start_node(SyntaxKind::CompoundStatement);
+        self.bump();
+        self.push_scope();
+        for parameter in parameters {
+            self.declare(parameter, false);
+        }
+        while !self.at(CLOSE_BRACE) && self.peek().is_some() {
+            let start = self.pos;
+            if self.at(keyword(KeywordKind::StaticAssert)) {
+                self.static_assert();
+            } else if self.is_declaration_start() {
+                self.declaration();
+            } else {
+                self.statement();
+            }
+            if self.pos == start {
+                self.bump_error();
+            }
+        }
+        self.pop_scope();
+        self.expect(CLOSE_BRACE);
+        self.finish_node();
+    }
+
+    fn statement(&mut self) {
+        use KeywordKind::*;
+        let Some(kind) = self.peek() else {
+            self.error("expected a statement");
+            return;
+        };
+        match kind {
+            OPEN_BRACE => self.compound_statement(Vec::new()),
+            CTokenKind::Keyword(Return) => {
+                self.start_node(SyntaxKind::ReturnStatement);
+                self.bump();
+                if !self.at(SEMICOLON) {
+                    self.expression();
+                }
+                self.expect(SEMICOLON);
+                self.finish_node();
+            }
+            CTokenKind::Keyword(If) => {
+                self.start_node(SyntaxKind::IfStatement);
+                self.bump();
+                self.condition();
+                self.statement();
+                if self.eat(keyword(Else)) {
+                    self.statement();
+                }
+                self.finish_node();
+            }
+            CTokenKind::Keyword(While) => {
+                self.start_node(SyntaxKind::WhileStatement);
+                self.bump();
+                self.condition();
+                self.statement();
+                self.finish_node();
+            }
+            CTokenKind::Keyword(Do) => {
+                self.start_node(SyntaxKind::DoStatement);
+                self.bump();
+                self.statement();
+                self.expect(keyword(While));
+                self.condition();
+                self.expect(SEMICOLON);
+                self.finish_node();
+            }
+            CTokenKind::Keyword(For) => self.for_statement(),
+            CTokenKind::Keyword(Switch) => {
+                self.start_node(SyntaxKind::SwitchStatement);
+                self.bump();
+                self.condition();
+                self.statement();
+                self.finish_node();
+            }
+            CTokenKind::Keyword(Case) => {
+                self.start_node(SyntaxKind::CaseStatement);
+                self.bump();
+                self.expression_bp(CONDITIONAL_PRECEDENCE);
+                self.expect(COLON);
+                self.statement();
+                self.finish_node();
+            }
+            CTokenKind::Keyword(Default) => {
+                self.start_node(SyntaxKind::DefaultStatement);
+                self.bump();
+                self.expect(COLON);
+                self.statement();
+                self.finish_node();
+            }
+            CTokenKind::Keyword(Goto) => {
+                self.start_node(SyntaxKind::GotoStatement);
+                self.bump();
+                self.expect(CTokenKind::Identifier);
+                self.expect(SEMICOLON);
+                self.finish_node();
+            }
+            CTokenKind::Keyword(Break) | CTokenKind::Keyword(Continue) => {
+                self.start_node(match kind {
+                    CTokenKind::Keyword(Break) => SyntaxKind::BreakStatement,
+                    _ => SyntaxKind::ContinueStatement,
+                });
+                self.bump();
+                self.expect(SEMICOLON);
+                self.finish_node();
+            }
+            CTokenKind::Identifier if self.is_label_start() => {
+                self.start_node(SyntaxKind::LabeledStatement);
+                self.bump();
+                self.bump();
+                self.statement();
+                self.finish_node();
+            }
+            SEMICOLON => {
+                self.start_node(SyntaxKind::ExpressionStatement);
+                self.bump();
+                self.finish_node();
+            }
+            _ if self.is_expression_start() => {
+                self.start_node(SyntaxKind::ExpressionStatement);
+                self.expression();
+                self.expect(SEMICOLON);
+                self.finish_node();
+            }
+            _ => self.error("expected a statement"),
+        }
+    }
+
+    /// `"(" <expression> ")"`, the condition of a selection or iteration statement.
+    fn condition(&mut self) {
+        self.expect(OPEN_PAREN);
+        self.expression();
+        self.expect(CLOSE_PAREN);
+    }
+
+    /// `"for" "(" ( <declaration> | [ <expression> ] ";" ) [ <expression> ] ";" [ <expression> ] ")" <statement>`
+    fn for_statement(&mut self) {
+        self.start_node(SyntaxKind::ForStatement);
+        self.bump();
+        self.push_scope();
+        self.expect(OPEN_PAREN);
+        if self.is_declaration_start() {
+            self.declaration();
+        } else {
+            if !self.at(SEMICOLON) {
+                self.expression();
+            }
+            self.expect(SEMICOLON);
+        }
+        if !self.at(SEMICOLON) {
+            self.expression();
+        }
+        self.expect(SEMICOLON);
+        if !self.at(CLOSE_PAREN) {
+            self.expression();
+        }
+        self.expect(CLOSE_PAREN);
+        self.statement();
+        self.pop_scope();
+        self.finish_node();
+    }
+
+    fn is_expression_start(&self) -> bool {
+        match self.peek() {
+            Some(
+                CTokenKind::Identifier
+                | CTokenKind::Constant
+                | CTokenKind::StringLiteral
+                | CTokenKind::Keyword(KeywordKind::Sizeof)
+                | OPEN_PAREN,
+            ) => true,
+            Some(kind) => is_prefix_operator(kind),
+            None => false,
+        }
+    }
+
+    fn expression(&mut self) {
+        self.expression_bp(COMMA_PRECEDENCE);
+    }
+
+    /// Parse an expression whose infix operators all bind at least as tightly as `min_precedence`.
+    fn expression_bp(&mut self, min_precedence: u8) {
+        let start = self.checkpoint();
+        self.unary_expression();
+        while let Some((precedence, right_associative)) = self.peek().and_then(infix_operator) {
+            if precedence < min_precedence {
+                break;
+            }
+            let next_precedence = match right_associative {
+                true => precedence,
+                false => precedence + 1,
+            };
+            if self.at(QUESTION_MARK) {
+                self.builder
+                    .start_node_at(start, SyntaxKind::ConditionalExpr);
+                self.bump();
+                self.expression();
+                self.expect(COLON);
+            } else {
+                self.builder.start_node_at(start, SyntaxKind::BinaryExpr);
+                self.bump();
+            }
+            self.expression_bp(next_precedence);
+            self.finish_node();
+        }
+    }
+
+    /// ```text
+    /// <unary-expression> ::= <unary-operator> <unary-expression>
+    ///                      | "sizeof" <unary-expression> | "sizeof" "(" <type-name> ")"
+    ///                      | "(" <type-name> ")" <unary-expression>
+    ///                      | <postfix-expression>
+    /// ```
+    fn unary_expression(&mut self) {
+        match self.peek() {
+            Some(kind) if is_prefix_operator(kind) => {
+                self.start_node(SyntaxKind::PrefixExpr);
+                self.bump();
+                self.unary_expression();
+                self.finish_node();
+            }
+            Some(CTokenKind::Keyword(KeywordKind::Sizeof)) => {
+                self.start_node(SyntaxKind::SizeofExpr);
+                self.bump();
+                if self.at(OPEN_PAREN) && self.is_specifier_at(1) {
+                    let start = self.checkpoint();
+                    self.bump();
+                    self.type_name();
+                    self.expect(CLOSE_PAREN);
+                    if self.at(OPEN_BRACE) {
+                        self.compound_literal(start);
+                    }
+                } else {
+                    self.unary_expression();
+                }
+                self.finish_node();
+            }
+            Some(OPEN_PAREN) if self.is_specifier_at(1) => {
+                let start = self.checkpoint();
+                self.bump();
+                self.type_name();
+                self.expect(CLOSE_PAREN);
+                if self.at(OPEN_BRACE) {
+                    self.compound_literal(start);
+                } else {
+                    self.builder.start_node_at(start, SyntaxKind::CastExpr);
+                    self.unary_expression();
+                    self.finish_node();
+                }
+            }
+            _ => {
+                let start = self.checkpoint();
+                self.primary_expression();
+                self.postfix_operators(start);
+            }
+        }
+    }
+
+    /// `"(" <type-name> ")" <initializer-list>`, with the type name already parsed from `start`.
+    fn compound_literal(&mut self, start: Checkpoint) {
+        self.builder
+            .start_node_at(start, SyntaxKind::CompoundLiteralExpr);
+        self.initializer_list();
+        self.finish_node();
+        self.postfix_operators(start);
+    }
+
+    /// `<postfix-operator> ::= "[" <expression> "]" | <argument-list> | ( "." | "->" ) <identifier> | "++" | "--"`
+    fn postfix_operators(&mut self, start: Checkpoint) {
+        loop {
+            match self.peek() {
+                Some(OPEN_BRACKET) => {
+                    self.builder.start_node_at(start, SyntaxKind::IndexExpr);
+                    self.bump();
+                    self.expression();
+                    self.expect(CLOSE_BRACKET);
+                }
+                Some(OPEN_PAREN) => {
+                    self.builder.start_node_at(start, SyntaxKind::CallExpr);
+                    self.argument_list();
+                }
+                Some(DOT | ARROW) => {
+                    self.builder.start_node_at(start, SyntaxKind::MemberExpr);
+                    self.bump();
+                    self.expect(CTokenKind::Identifier);
+                }
+                Some(INCREMENT | DECREMENT) => {
+                    self.builder.start_node_at(start, SyntaxKind::PostfixExpr);
+                    self.bump();
+                }
+                _ => break,
+            }
+            self.finish_node();
+        }
+    }
+
+    /// `<argument-list> ::= "(" [ <assignment-expression> { "," <assignment-expression> } ] ")"`
+    fn argument_list(&mut self) {
+        self.start_node(SyntaxKind::ArgumentList);
+        self.bump();
+        if !self.at(CLOSE_PAREN) {
+            self.expression_bp(ASSIGNMENT_PRECEDENCE);
+            while self.eat(COMMA) {
+                self.expression_bp(ASSIGNMENT_PRECEDENCE);
+            }
+        }
+        self.expect(CLOSE_PAREN);
+        self.finish_node();
+    }
+
+    /// `<primary-expression> ::= <identifier> | <constant> | <string-literal> { <string-literal> } | "(" <expression> ")"`
+    fn primary_expression(&mut self) {
+        match self.peek() {
+            Some(CTokenKind::Identifier) => {
+                self.start_node(SyntaxKind::NameExpr);
+                self.bump();
+                self.finish_node();
+            }
+            Some(CTokenKind::Constant) => {
+                self.start_node(SyntaxKind::LiteralExpr);
+                self.bump();
+                self.finish_node();
+            }
+            Some(CTokenKind::StringLiteral) => {
+                // Adjacent string literals are one literal, trivia between them included.
+                self.start_node(SyntaxKind::LiteralExpr);
+                while self.eat(CTokenKind::StringLiteral) {}
+                self.finish_node();
+            }
+            Some(OPEN_PAREN) => {
+                self.start_node(SyntaxKind::ParenExpr);
+                self.bump();
+                self.expression();
+                self.expect(CLOSE_PAREN);
+                self.finish_node();
+            }
+            _ => self.error("expected an expression"),
+        }
+    }
+}
@@ -0,0 +1,333 @@
+//! The positioned, navigable half of the syntax tree.
+//!
+//! Red nodes are built on demand as the green tree is walked: each one wraps a
+//! green node with its parent and its offset in the source, which is all the green
+//! tree leaves out. They are cheap to create and are never stored in the tree.
+
+use std::{fmt, ops::Range, rc::Rc};
+
+use crate::{
+    green::{GreenElement, GreenNode, GreenToken},
+    SyntaxKind,
+};
+
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+struct NodeData {
+    green: GreenNode,
+    parent: Option<SyntaxNode>,
+    /// The index of this node among its parent's children.
+    index: usize,
+    offset: usize,
+}
+impl SyntaxNode {
+    pub fn new_root(green: GreenNode) -> Self {
+        Self(Rc::new(NodeData {
+            green,
+            parent: None,
+            index: 0,
+            offset: 0,
+        }))
+    }
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind()
+    }
+    pub fn green(&self) -> &GreenNode {
+        &self.0.green
+    }
+    /// The byte range of the source this node covers, trivia included.
+    pub fn text_range(&self) -> Range<usize> {
+        self.0.offset..self.0.offset + self.0.green.len()
+    }
+    /// The source this node covers, exactly as it was written.
+    pub fn text(&self) -> String {
+        self.to_string()
+    }
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+    /// This node and then each of its ancestors up to the root.
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        std::iter::successors(Some(self.clone()), SyntaxNode::parent)
+    }
+    pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> + '_ {
+        let mut offset = self.0.offset;
+        self.green()
+            .children()
+            .iter()
+            .enumerate()
+            .map(move |(index, child)| {
+                let element = match child {
+                    GreenElement::Node(green) => {
+                        SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                            green: green.clone(),
+                            parent: Some(self.clone()),
+                            index,
+                            offset,
+                        })))
+                    }
+                    GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                        green: green.clone(),
+                        parent: self.clone(),
+                        index,
+                        offset,
+                    }),
+                };
+                offset += child.len();
+                element
+            })
+    }
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> + '_ {
+        self.children_with_tokens()
+            .filter_map(SyntaxElement::into_node)
+    }
+    /// This node and every node below it, in preorder.
+    pub fn descendants(&self) -> impl Iterator<Item = SyntaxNode> {
+        let mut stack = vec![self.clone()];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            let children = node.children().collect::<Vec<_>>();
+            stack.extend(children.into_iter().rev());
+            Some(node)
+        })
+    }
+    /// Every token below this node, trivia included, in source order.
+    pub fn tokens(&self) -> impl Iterator<Item = SyntaxToken> {
+        let mut stack = vec![SyntaxElement::Node(self.clone())];
+        std::iter::from_fn(move || loop {
+            match stack.pop()? {
+                SyntaxElement::Token(token) => return Some(token),
+                SyntaxElement::Node(node) => {
+                    let children = node.children_with_tokens().collect::<Vec<_>>();
+                    stack.extend(children.into_iter().rev());
+                }
+            }
+        })
+    }
+    /// The first token below this node that isn't trivia.
+    pub fn first_token(&self) -> Option<SyntaxToken> {
+        self.tokens().find(|token| !token.kind().is_trivia())
+    }
+    /// A new tree in which this node is replaced by `replacement`, returned as its root.
+    ///
+    /// Only the ancestors of this node are rebuilt; every other subtree is shared
+    /// with the original tree, which is left unchanged.
+    pub fn replace_with(&self, replacement: GreenNode) -> GreenNode {
+        match &self.0.parent {
+            None => replacement,
+            Some(parent) => parent.replace_child(self.0.index, replacement.into()),
+        }
+    }
+    fn replace_child(&self, index: usize, child: GreenElement) -> GreenNode {
+        self.replace_with(self.green().replace_child(index, child))
+    }
+    /// An indented outline of this node and everything below it, for debugging and tests.
+    pub fn debug_tree(&self) -> String {
+        let mut output = String::new();
+        self.write_tree(&mut output, 0);
+        output
+    }
+    fn write_tree(&self, output: &mut String, depth: usize) {
+        output.push_str(&format!("{:indent$}{self:?}\n", "", indent = depth * 2));
+        for child in self.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) => node.write_tree(output, depth + 1),
+                SyntaxElement::Token(token) => output.push_str(&format!(
+                    "{:indent$}{token:?}\n",
+                    "",
+                    indent = depth * 2 + 2
+                )),
+            }
+        }
+    }
+}
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.offset == other.0.offset && self.green().ptr_eq(other.green())
+    }
+}
+impl Eq for SyntaxNode {}
+impl fmt::Debug for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Range { start, end } = self.text_range();
+        write!(f, "{}@{start}..{end}", self.kind())
+    }
+}
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.green())
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct SyntaxToken {
+    green: GreenToken,
+    parent: SyntaxNode,
+    index: usize,
+    offset: usize,
+}
+impl SyntaxToken {
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind()
+    }
+    pub fn green(&self) -> &GreenToken {
+        &self.green
+    }
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+    pub fn text_range(&self) -> Range<usize> {
+        self.offset..self.offset + self.green.len()
+    }
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+    /// A new tree in which this token is replaced by `replacement`, returned as its root.
+    pub fn replace_with(&self, replacement: GreenToken) -> GreenNode {
+        self.parent.replace_child(self.index, replacement.into())
+    }
+}
+impl fmt::Debug for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Range { start, end } = self.text_range();
+        write!(f, "{}@{start}..{end} {:?}", self.kind(), self.text())
+    }
+}
+impl fmt::Display for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.text())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+impl SyntaxElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            Self::Node(node) => node.kind(),
+            Self::Token(token) => token.kind(),
+        }
+    }
+    pub fn text_range(&self) -> Range<usize> {
+        match self {
+            Self::Node(node) => node.text_range(),
+            Self::Token(token) => token.text_range(),
+        }
+    }
+    pub fn into_node(self) -> Option<SyntaxNode> {
+        match self {
+            Self::Node(node) => Some(node),
+            Self::Token(_) => None,
+        }
+    }
+    pub fn into_token(self) -> Option<SyntaxToken> {
+        match self {
+            Self::Node(_) => None,
+            Self::Token(token) => Some(token),
+        }
+    }
+}
+
+#[cfg(test)]
+mod red_tests {
+    use expect_test::expect;
+
+    use super::SyntaxNode;
+    use crate::{
+        ast::{AstNode, Expression, ParenExpr},
+        green::GreenToken,
+        parse, SyntaxKind,
+    };
+    use wacc_lexer::c_token::CTokenKind;
+
+    #[test]
+    fn test_navigation() {
+        let src = "int f(int a) { return a + 1; }";
+        let root = parse(src).syntax();
+        let one = root
+            .tokens()
+            .find(|token| token.text() == "1")
+            .expect("the literal is in the tree");
+        let ancestors = one
+            .parent()
+            .ancestors()
+            .map(|node| format!("{node:?}"))
+            .collect::<Vec<_>>()
+            .join(" < ");
+        expect!["LiteralExpr@26..27 < BinaryExpr@22..27 < ReturnStatement@15..28 < CompoundStatement@13..30 < FunctionDefinition@0..30 < SourceFile@0..30"]
+            .assert_eq(&ancestors);
+        let first = root.descendants().nth(4).unwrap();
+        expect!["NameDeclarator@4..5"].assert_eq(&format!("{first:?}"));
+        expect![[r#"`return`@15..21 "return""#]].assert_eq(&format!(
+            "{:?}",
+            one.parent()
+                .ancestors()
+                .nth(2)
+                .unwrap()
+                .first_token()
+                .unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_rewrites() {
+        let src = "int f(int count) {\n  return (count) * ((2) + count);\n}\nint g;\n";
+        let original = parse(src).syntax();
+
+        // Drop the parentheses around names and literals, one rewrite at a time.
+        let mut root = original.clone();
+        while let Some(paren) = root
+            .descendants()
+            .filter_map(ParenExpr::cast)
+            .find(|paren| {
+                matches!(
+                    paren.expression(),
+                    Some(Expression::Name(_) | Expression::Literal(_))
+                )
+            })
+        {
+            let inner = paren.expression().unwrap();
+            root =
+                SyntaxNode::new_root(paren.syntax().replace_with(inner.syntax().green().clone()));
+        }
+        expect![[r#"
+            int f(int count) {
+              return count * (2 + count);
+            }
+            int g;
+        "#]]
+        .assert_eq(&root.text());
+
+        // Rename every use of a name.
+        while let Some(token) = root.tokens().find(|token| {
+            token.kind() == SyntaxKind::Token(CTokenKind::Identifier) && token.text() == "count"
+        }) {
+            let renamed = GreenToken::new(token.kind(), "total");
+            root = SyntaxNode::new_root(token.replace_with(renamed));
+        }
+        expect![[r#"
+            int f(int total) {
+              return total * (2 + total);
+            }
+            int g;
+        "#]]
+        .assert_eq(&root.text());
+
+        // The original is untouched, and what the rewrites didn't reach is shared with it.
+        assert_eq!(original.text(), src);
+        let declaration = |root: &SyntaxNode| root.children().nth(1).unwrap();
+        assert!(declaration(&original)
+            .green()
+            .ptr_eq(declaration(&root).green()));
+        assert!(!original
+            .children()
+            .next()
+            .unwrap()
+            .green()
+            .ptr_eq(root.children().next().unwrap().green()));
+    }
+}
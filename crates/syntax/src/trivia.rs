@@ -0,0 +1,96 @@
+//! Comments and preprocessing directives, which `wacc-lexer` doesn't know about
+//! since the compiler only ever sees preprocessed source.
+//!
+//! They are found before lexing and blanked out with spaces, so the lexer skips
+//! them like any other whitespace while every token keeps its offset.
+
+use std::ops::Range;
+
+use crate::{SyntaxError, SyntaxKind};
+
+/// The comments and directives of a source file.
+pub(crate) struct Trivia {
+    /// The source with each comment and directive replaced by spaces.
+    pub(crate) blanked: String,
+    /// Every comment and directive, in order.
+    pub(crate) pieces: Vec<(Range<usize>, SyntaxKind)>,
+    pub(crate) errors: Vec<SyntaxError>,
+}
+
+pub(crate) fn split_trivia(src: &str) -> Trivia {
+    let bytes = src.as_bytes();
+    let mut trivia = Vec::new();
+    let mut errors = Vec::new();
+    // Whether only whitespace has come since the start of the line, so a `#` starts a directive.
+    let mut at_line_start = true;
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let start = pos;
+        match bytes[pos..] {
+            [b'/', b'*', ..] => {
+                pos = match src[pos + 2..].find("*/") {
+                    Some(end) => pos + 2 + end + 2,
+                    None => {
+                        errors.push(SyntaxError::new(
+                            start..src.len(),
+                            "unterminated comment".to_string(),
+                        ));
+                        src.len()
+                    }
+                };
+                trivia.push((start..pos, SyntaxKind::Comment));
+                continue;
+            }
+            [b'/', b'/', ..] => {
+                pos = line_end(bytes, pos, false);
+                trivia.push((start..pos, SyntaxKind::Comment));
+                continue;
+            }
+            [b'#', ..] if at_line_start => {
+                pos = line_end(bytes, pos, true);
+                trivia.push((start..pos, SyntaxKind::Directive));
+                continue;
+            }
+            // A `/*` or `//` in a literal doesn't start a comment.
+            [quote @ (b'"' | b'\''), ..] => {
+                pos += 1;
+                while pos < bytes.len() && bytes[pos] != quote && bytes[pos] != b'\n' {
+                    pos += if bytes[pos] == b'\\' { 2 } else { 1 };
+                }
+                pos = (pos + 1).min(bytes.len());
+                at_line_start = false;
+                continue;
+            }
+            [b'\n', ..] => at_line_start = true,
+            [byte, ..] if byte.is_ascii_whitespace() => {}
+            _ => at_line_start = false,
+        }
+        pos += 1;
+    }
+
+    let mut blanked = src.as_bytes().to_vec();
+    for (range, _) in &trivia {
+        blanked[range.clone()]
+            .iter_mut()
+            .filter(|byte| **byte != b'\n')
+            .for_each(|byte| *byte = b' ');
+    }
+    let blanked = String::from_utf8(blanked).expect("only whole characters are blanked");
+    Trivia {
+        blanked,
+        pieces: trivia,
+        errors,
+    }
+}
+
+/// The end of the line at `pos`, before its newline. A directive carries on
+/// past a newline escaped with a backslash.
+fn line_end(bytes: &[u8], mut pos: usize, is_directive: bool) -> usize {
+    while pos < bytes.len() && bytes[pos] != b'\n' {
+        pos += 1;
+        if is_directive && bytes[pos - 1] == b'\\' && bytes.get(pos) == Some(&b'\n') {
+            pos += 1;
+        }
+    }
+    pos
+}
@@ -0,0 +1,141 @@
+use std::{fmt::Debug, marker::PhantomData};
+
+use crate::{
+    span::{SourceMap, SourceSpan, Span},
+    token::{Token, TokenSink},
+};
+
+/// The kind of a token without its span, encoded as a single byte.
+pub trait TokenKind: Copy + Debug {
+    fn to_byte(self) -> u8;
+    fn from_byte(byte: u8) -> Self;
+}
+
+/// A [`Token`] that can be stored in a [`CompactTokenStream`].
+pub trait CompactToken: Token {
+    type Kind: TokenKind;
+    /// The kind and span of the token, `None` for tokens that aren't stored (e.g. whitespace).
+    fn compact(&self) -> Option<(Self::Kind, SourceSpan)>;
+}
+
+/// Struct-of-arrays token storage: a kind byte plus a `u32` start and length per token,
+/// with spans resolved through a [`SourceMap`] on demand.
+///
+/// This is 9 bytes per token, where a [`crate::token::TokenStream`] of full tokens pays
+/// for a [`SourceSpan`] (pointer, length, start and end) and an enum tag on every token.
+#[derive(Debug)]
+pub struct CompactTokenStream<K: TokenKind> {
+    kinds: Vec<u8>,
+    starts: Vec<u32>,
+    lens: Vec<u32>,
+    _kind: PhantomData<K>,
+}
+impl<K: TokenKind> CompactTokenStream<K> {
+    /// Create a new token stream from the length of the source to avoid reallocations
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            kinds: Vec::with_capacity(capacity),
+            starts: Vec::with_capacity(capacity),
+            lens: Vec::with_capacity(capacity),
+            _kind: PhantomData,
+        }
+    }
+    pub fn push_kind(&mut self, kind: K, start: u32, len: u32) {
+        self.kinds.push(kind.to_byte());
+        self.starts.push(start);
+        self.lens.push(len);
+    }
+    pub fn len(&self) -> usize {
+        self.kinds.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+    pub fn kind(&self, index: usize) -> K {
+        K::from_byte(self.kinds[index])
+    }
+    pub fn start(&self, index: usize) -> u32 {
+        self.starts[index]
+    }
+    pub fn token_len(&self, index: usize) -> u32 {
+        self.lens[index]
+    }
+    /// The source text of the token at `index`.
+    pub fn span<'a>(&self, index: usize, source_map: &SourceMap<'a>) -> &'a str {
+        source_map.resolve(self.starts[index], self.lens[index])
+    }
+    /// Expand the token at `index` into a full [`SourceSpan`].
+    pub fn source_span(&self, index: usize, source_map: &SourceMap) -> SourceSpan {
+        source_map.source_span(self.starts[index], self.lens[index])
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (K, u32, u32)> + '_ {
+        self.kinds
+            .iter()
+            .zip(&self.starts)
+            .zip(&self.lens)
+            .map(|((kind, start), len)| (K::from_byte(*kind), *start, *len))
+    }
+    /// Release any capacity reserved up front that went unused.
+    pub fn shrink_to_fit(&mut self) {
+        self.kinds.shrink_to_fit();
+        self.starts.shrink_to_fit();
+        self.lens.shrink_to_fit();
+    }
+    /// The number of heap bytes reserved for the tokens in this stream.
+    pub fn heap_size(&self) -> usize {
+        self.kinds.capacity() + (self.starts.capacity() + self.lens.capacity()) * 4
+    }
+}
+impl<T: CompactToken> TokenSink<T> for CompactTokenStream<T::Kind> {
+    fn push(&mut self, token: T) {
+        if let Some((kind, span)) = token.compact() {
+            let start = u32::try_from(span.start()).expect("token start exceeds u32::MAX");
+            let len = u32::try_from(span.len()).expect("token length exceeds u32::MAX");
+            self.push_kind(kind, start, len);
+        }
+    }
+    fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod compact_tests {
+    use super::{CompactTokenStream, TokenKind};
+    use crate::span::{SourceMap, Span};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Kind {
+        Word,
+        Number,
+    }
+    impl TokenKind for Kind {
+        fn to_byte(self) -> u8 {
+            self as u8
+        }
+        fn from_byte(byte: u8) -> Self {
+            [Kind::Word, Kind::Number][byte as usize]
+        }
+    }
+
+    #[test]
+    fn test_compact_spans() {
+        let src = "return\n  42";
+        let source_map = SourceMap::new(src);
+        let mut stream = CompactTokenStream::new(2);
+        stream.push_kind(Kind::Word, 0, 6);
+        stream.push_kind(Kind::Number, 9, 2);
+
+        assert_eq!(stream.len(), 2);
+        assert_eq!(stream.kind(1), Kind::Number);
+        assert_eq!(stream.span(0, &source_map), "return");
+        assert_eq!(stream.span(1, &source_map), "42");
+        assert_eq!(stream.source_span(1, &source_map).span(), "42");
+        assert_eq!(source_map.line_col(stream.start(1)), (2, 3));
+        assert_eq!(
+            stream.iter().collect::<Vec<_>>(),
+            vec![(Kind::Word, 0, 6), (Kind::Number, 9, 2)]
+        );
+        assert_eq!(stream.heap_size(), 2 * 9);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod compact;
 pub mod span;
 pub mod token;
 
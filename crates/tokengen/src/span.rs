@@ -29,12 +29,86 @@ impl SourceSpan {
             end,
         }
     }
+    /// A span covering everything from the start of `self` to the end of `other`.
+    pub fn to(&self, other: &SourceSpan) -> Self {
+        Self {
+            end: other.end,
+            ..*self
+        }
+    }
+    /// Capture the span of anything implementing [`Span`].
+    pub fn from_span<S: Span + ?Sized>(span: &S) -> Self {
+        Self::new(span.src(), span.start(), span.end())
+    }
+    /// Where the span starts, for a diagnostic. Builds a [`SourceMap`] of the
+    /// whole source, so it is only meant for the odd error or warning.
+    pub fn location(&self) -> Location {
+        let (line, column) = SourceMap::new(self.src()).line_col(self.start as u32);
+        Location { line, column }
+    }
+}
+
+/// The 1-based line and column of a position in the source, shown as `line:col`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Resolves compact `u32` offsets back into source text, lines and columns on demand.
+///
+/// Only a single translation unit is mapped; the preprocessor is run with `-P` so
+/// there are no line markers to follow.
+#[derive(Debug, Clone)]
+pub struct SourceMap<'a> {
+    src: &'a str,
+    line_starts: Vec<u32>,
+}
+impl<'a> SourceMap<'a> {
+    pub fn new(src: &'a str) -> Self {
+        assert!(
+            u32::try_from(src.len()).is_ok(),
+            "source files larger than 4GiB are not supported"
+        );
+        let line_starts = std::iter::once(0)
+            .chain(
+                src.bytes()
+                    .enumerate()
+                    .filter(|(_, b)| *b == b'\n')
+                    .map(|(i, _)| i as u32 + 1),
+            )
+            .collect();
+        Self { src, line_starts }
+    }
+    pub fn src(&self) -> &'a str {
+        self.src
+    }
+    /// The source text covered by `len` bytes starting at `start`.
+    pub fn resolve(&self, start: u32, len: u32) -> &'a str {
+        &self.src[start as usize..(start + len) as usize]
+    }
+    /// Expand a compact span into a full [`SourceSpan`].
+    pub fn source_span(&self, start: u32, len: u32) -> SourceSpan {
+        SourceSpan::new(self.src, start as usize, (start + len) as usize)
+    }
+    /// The 1-based line and column of a byte offset.
+    pub fn line_col(&self, offset: u32) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        (line + 1, (offset - self.line_starts[line]) as usize + 1)
+    }
 }
 
 impl Span for SourceSpan {
     fn src(&self) -> &str {
         let slice: &[u8] = unsafe { std::slice::from_raw_parts(self.src_ptr, self.src_len) };
-        std::str::from_utf8(slice).expect("source span failed to convert slice to &str")
+        // SAFETY: the pointer and length were taken from a `&str` in `SourceSpan::new`.
+        // Validating here would make every span lookup linear in the size of the source.
+        unsafe { std::str::from_utf8_unchecked(slice) }
     }
     fn start(&self) -> usize {
         self.start
@@ -25,6 +25,38 @@ impl<T: Token> TokenStream<T> {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.0.iter()
+    }
+    pub fn as_slice(&self) -> &[T] {
+        &self.0
+    }
+    /// The number of heap bytes reserved for the tokens in this stream.
+    pub fn heap_size(&self) -> usize {
+        self.0.capacity() * std::mem::size_of::<T>()
+    }
+}
+impl<T: Token> TokenSink<T> for TokenStream<T> {
+    fn push(&mut self, token: T) {
+        self.0.push(token)
+    }
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+impl<'a, T: Token> IntoIterator for &'a TokenStream<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// Anything a lexer can push tokens into, so the same lexer can fill different token layouts.
+pub trait TokenSink<T: Token> {
+    fn push(&mut self, token: T);
+    fn is_empty(&self) -> bool;
 }
 
 /// Basic single character symbols that can later be used in crafting tokens,
@@ -84,6 +116,67 @@ macro_rules! symbol {
         pub enum Symbol {
             $($name($name),)+
         }
+        /// The kind of a [`Symbol`] without its span, small enough to be stored as a single byte.
+        #[allow(dead_code)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[repr(u8)]
+        pub enum SymbolKind {
+            $($name,)+
+        }
+        #[allow(dead_code)]
+        impl SymbolKind {
+            pub const ALL: &'static [SymbolKind] = &[$(Self::$name,)+];
+        }
+        impl AsRef<char> for SymbolKind {
+            fn as_ref(&self) -> &char {
+                match self {
+                    $(Self::$name => $name::STATIC_REF,)+
+                }
+            }
+        }
+        #[allow(dead_code)]
+        impl Symbol {
+            pub fn kind(&self) -> SymbolKind {
+                match self {
+                    $(Self::$name(_) => SymbolKind::$name,)+
+                }
+            }
+            /// The symbol spelled by `src[start..end]`, if any.
+            pub fn from_src(src: &str, start: usize, end: usize) -> Option<Self> {
+                let mut chars = src[start..end].chars();
+                match (chars.next(), chars.next()) {
+                    $((Some($char), None) => Some(Self::$name($name::new(src, start, end))),)+
+                    _ => None,
+                }
+            }
+        }
+        impl $crate::span::Span for Symbol {
+            fn src(&self) -> &str {
+                match self {
+                    $(Self::$name(s) => s.src(),)+
+                }
+            }
+            fn start(&self) -> usize {
+                match self {
+                    $(Self::$name(s) => s.start(),)+
+                }
+            }
+            fn end(&self) -> usize {
+                match self {
+                    $(Self::$name(s) => s.end(),)+
+                }
+            }
+            fn span(&self) -> &str {
+                match self {
+                    $(Self::$name(s) => s.span(),)+
+                }
+            }
+            fn len(&self) -> usize {
+                match self {
+                    $(Self::$name(s) => s.len(),)+
+                }
+            }
+        }
         impl AsRef<char> for Symbol {
             fn as_ref(&self) -> &char {
                 match self {
@@ -101,6 +194,138 @@ macro_rules! symbol {
     };
 }
 
+/// Symbols made up of more than one character, like `->` or `<<=`, "cooked"
+/// from several basic symbols. Aliases and additional derive traits can be
+/// passed exactly as they are for [`symbol!`].
+#[macro_export]
+macro_rules! cooked_symbol {
+    ( $([$name:ident, $str:literal $(,[$($alias:ident),*]),* $(,{$($trait:ident),*})* ]),+ ) => {
+        $(
+            #[allow(dead_code)] // Ignore warnings if alias is never used
+            $($(pub type $alias = $name;)*)*
+
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Token $(,$($trait,)*)*)]
+            pub struct $name {
+                span: $crate::span::SourceSpan,
+            }
+            impl $name {
+                pub const STATIC_REF: &'static str = $str;
+
+                #[allow(dead_code)] // Ignore warnings if constructor is never used
+                pub fn new(src: &str, start: usize, end: usize) -> Self {
+                    Self { span: $crate::span::SourceSpan::new(src, start, end) }
+                }
+            }
+            impl $crate::span::Span for $name {
+                fn src(&self) -> &str {
+                    self.span.src()
+                }
+                fn start(&self) -> usize {
+                    self.span.start()
+                }
+                fn end(&self) -> usize {
+                    self.span.end()
+                }
+                fn span(&self) -> &str {
+                    self.span.span()
+                }
+                fn len(&self) -> usize {
+                    self.span.len()
+                }
+            }
+            impl AsRef<str> for $name {
+                fn as_ref(&self) -> &str {
+                    $str
+                }
+            }
+            impl std::fmt::Display for $name {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, "{}", self.as_ref())
+                }
+            }
+        )+
+        #[allow(dead_code)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+        pub enum CookedSymbol {
+            $($name($name),)+
+        }
+        /// The kind of a [`CookedSymbol`] without its span, small enough to be stored as a single byte.
+        #[allow(dead_code)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[repr(u8)]
+        pub enum CookedSymbolKind {
+            $($name,)+
+        }
+        #[allow(dead_code)]
+        impl CookedSymbolKind {
+            pub const ALL: &'static [CookedSymbolKind] = &[$(Self::$name,)+];
+        }
+        impl AsRef<str> for CookedSymbolKind {
+            fn as_ref(&self) -> &str {
+                match self {
+                    $(Self::$name => $name::STATIC_REF,)+
+                }
+            }
+        }
+        #[allow(dead_code)]
+        impl CookedSymbol {
+            pub fn kind(&self) -> CookedSymbolKind {
+                match self {
+                    $(Self::$name(_) => CookedSymbolKind::$name,)+
+                }
+            }
+            /// The symbol spelled by `src[start..end]`, if any.
+            pub fn from_src(src: &str, start: usize, end: usize) -> Option<Self> {
+                match &src[start..end] {
+                    $($str => Some(Self::$name($name::new(src, start, end))),)+
+                    _ => None,
+                }
+            }
+        }
+        impl $crate::span::Span for CookedSymbol {
+            fn src(&self) -> &str {
+                match self {
+                    $(Self::$name(s) => s.src(),)+
+                }
+            }
+            fn start(&self) -> usize {
+                match self {
+                    $(Self::$name(s) => s.start(),)+
+                }
+            }
+            fn end(&self) -> usize {
+                match self {
+                    $(Self::$name(s) => s.end(),)+
+                }
+            }
+            fn span(&self) -> &str {
+                match self {
+                    $(Self::$name(s) => s.span(),)+
+                }
+            }
+            fn len(&self) -> usize {
+                match self {
+                    $(Self::$name(s) => s.len(),)+
+                }
+            }
+        }
+        impl AsRef<str> for CookedSymbol {
+            fn as_ref(&self) -> &str {
+                match self {
+                    $(Self::$name(_) => $name::STATIC_REF,)+
+                }
+            }
+        }
+        impl std::fmt::Display for CookedSymbol {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    $(Self::$name(_) => write!(f, "{}", self.as_ref()),)+
+                }
+            }
+        }
+    };
+}
+
 /// A keyword is some string that is reserved for a language
 #[macro_export]
 macro_rules! keyword {
@@ -149,6 +374,66 @@ macro_rules! keyword {
         pub enum Keyword {
             $($name($name),)+
         }
+        /// The kind of a [`Keyword`] without its span, small enough to be stored as a single byte.
+        #[allow(dead_code)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[repr(u8)]
+        pub enum KeywordKind {
+            $($name,)+
+        }
+        #[allow(dead_code)]
+        impl KeywordKind {
+            pub const ALL: &'static [KeywordKind] = &[$(Self::$name,)+];
+        }
+        impl AsRef<str> for KeywordKind {
+            fn as_ref(&self) -> &str {
+                match self {
+                    $(Self::$name => $name::STATIC_REF,)+
+                }
+            }
+        }
+        #[allow(dead_code)]
+        impl Keyword {
+            pub fn kind(&self) -> KeywordKind {
+                match self {
+                    $(Self::$name(_) => KeywordKind::$name,)+
+                }
+            }
+            /// The keyword spelled by `src[start..end]`, if any.
+            pub fn from_src(src: &str, start: usize, end: usize) -> Option<Self> {
+                match &src[start..end] {
+                    $($str => Some(Self::$name($name::new(src, start, end))),)+
+                    _ => None,
+                }
+            }
+        }
+        impl $crate::span::Span for Keyword {
+            fn src(&self) -> &str {
+                match self {
+                    $(Self::$name(k) => k.src(),)+
+                }
+            }
+            fn start(&self) -> usize {
+                match self {
+                    $(Self::$name(k) => k.start(),)+
+                }
+            }
+            fn end(&self) -> usize {
+                match self {
+                    $(Self::$name(k) => k.end(),)+
+                }
+            }
+            fn span(&self) -> &str {
+                match self {
+                    $(Self::$name(k) => k.span(),)+
+                }
+            }
+            fn len(&self) -> usize {
+                match self {
+                    $(Self::$name(k) => k.len(),)+
+                }
+            }
+        }
         impl AsRef<str> for Keyword {
             fn as_ref(&self) -> &str {
                 match self {
@@ -276,6 +561,7 @@ mod token_tests {
         [ClosedParenthesis, ')', { Delimiter }]
     );
     keyword!([If, "if"]);
+    cooked_symbol!([Arrow, "->"], [ShiftLeftAssign, "<<=", [ShlAssign]]);
 
     fn check_spans<S: Span + std::fmt::Debug>(output: S, expect: Expect) {
         expect.assert_eq(&format!("{output:#?}"));
@@ -324,6 +610,41 @@ mod token_tests {
         );
     }
 
+    #[test]
+    fn test_cooked_symbol() {
+        let symbol_str = ShiftLeftAssign::STATIC_REF;
+        let src = r#"x <<= 2;"#;
+        let shl_assign = ShlAssign::new(src, 2, 2 + symbol_str.len());
+
+        assert_eq!(symbol_str.len(), shl_assign.len());
+        assert_eq!(symbol_str, format!("{shl_assign}"));
+        assert_eq!(
+            CookedSymbol::from_src(src, 2, 5).map(|s| s.kind()),
+            Some(CookedSymbolKind::ShiftLeftAssign)
+        );
+        assert_eq!(CookedSymbol::from_src(src, 0, 2), None);
+        assert_eq!(
+            Symbol::from_src(src, 7, 8),
+            None,
+            "`;` isn't one of the test symbols"
+        );
+        assert_eq!(
+            Keyword::from_src("if x", 0, 2).map(|k| k.kind()),
+            Some(KeywordKind::If)
+        );
+        check_spans(
+            shl_assign,
+            expect![[r#"
+                ShiftLeftAssign {
+                    span: SourceSpan {
+                        src: "<<=",
+                        start: 2,
+                        end: 5,
+                    },
+                }"#]],
+        );
+    }
+
     #[test]
     fn test_delimiter() {
         let open_str = OpenParenthesis::STATIC_REF.to_string();
@@ -82,6 +82,9 @@
           ./crates/lexer
           ./crates/derive-token
           ./crates/tokengen
+          ./crates/parser
+          ./crates/semantics
+          ./crates/ir
         ];
       });
       wacc-lexer = craneLib.buildPackage (individualCrateArgs // {